@@ -1,7 +1,7 @@
 // An example of an echo server using fixed buffers for reading and writing TCP streams.
 // A buffer registry size of two is created, to allow a maximum of two simultaneous connections.
 
-use std::{env, iter, net::SocketAddr};
+use std::{env, net::SocketAddr};
 
 use tokio_uring::{
     buf::{
@@ -38,9 +38,7 @@ async fn accept_loop(listen_addr: SocketAddr) {
     );
 
     // Other iterators may be passed to FixedBufRegistry::new also.
-    let buffers = iter::repeat(vec![0; 4096])
-        .take(POOL_SIZE)
-        .map(Buffer::from);
+    let buffers = std::iter::repeat_n(vec![0; 4096], POOL_SIZE).map(Buffer::from);
 
     // Register the buffers with the kernel, asserting the syscall passed.
 
@@ -86,7 +84,7 @@ async fn echo_handler(stream: TcpStream, peer: SocketAddr, registry: FixedBufReg
             if read == 0 {
                 break;
             }
-            assert_eq!(4096, fbuf1.len()); // To prove a point.
+            assert_eq!(4096, fbuf1.capacity()); // To prove a point.
 
             let (_, nslice) = stream.write_fixed_all(fbuf1.slice(..read)).await.unwrap();
             println!("peer {} all {} bytes ping-ponged", peer, read);
@@ -15,7 +15,7 @@ fn main() {
         let listener = UnixListener::bind(&socket_addr).unwrap();
 
         loop {
-            let stream = listener.accept().await.unwrap();
+            let (stream, _) = listener.accept().await.unwrap();
             let socket_addr = socket_addr.clone();
             tokio_uring::spawn(async move {
                 let buf = Buffer::new(vec![1u8; 128]);
@@ -0,0 +1,176 @@
+use crate::buf::BufferImpl;
+use std::alloc::{self, Layout};
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::ptr::NonNull;
+
+/// A heap buffer starting at an address aligned to a chosen power of two, as direct I/O
+/// (`O_DIRECT`) requires.
+///
+/// Like a `Vec<u8>`, it has a capacity and an initialized length, which reads into it
+/// extend. It converts into a [`Buffer`](crate::Buffer) for any operation, fixed buffer
+/// registration included, and back with [`Buffer::try_into`](crate::Buffer::try_into).
+///
+/// # Examples
+///
+/// ```no_run
+/// use tokio_uring::buf::{fixed::registry, AlignedBuf};
+/// use tokio_uring::fs::OpenOptions;
+/// use std::os::unix::fs::OpenOptionsExt;
+///
+/// tokio_uring::start(async {
+///     let registry = registry::register(std::iter::once(AlignedBuf::new(4096, 4096).into()))?;
+///     let file = OpenOptions::new()
+///         .read(true)
+///         .custom_flags(libc::O_DIRECT)
+///         .open("data.bin")
+///         .await?;
+///
+///     let buf = registry.check_out(0).unwrap();
+///     let (n, _buf) = file.read_fixed_at(buf, 0).await.unwrap();
+///     println!("read {} bytes", n);
+///     Ok::<_, std::io::Error>(())
+/// })
+/// .unwrap();
+/// ```
+pub struct AlignedBuf {
+    ptr: NonNull<u8>,
+    len: usize,
+    cap: usize,
+    align: usize,
+}
+
+// Safety: the buffer owns its allocation, like a `Vec<u8>`.
+unsafe impl Send for AlignedBuf {}
+unsafe impl Sync for AlignedBuf {}
+
+impl AlignedBuf {
+    /// Allocates an empty buffer of `capacity` bytes, aligned to `align`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `align` is not a power of two, or if the capacity rounded up to `align`
+    /// overflows `isize`.
+    pub fn new(capacity: usize, align: usize) -> AlignedBuf {
+        let layout = layout(capacity, align);
+        let ptr = if capacity == 0 {
+            // Nothing to allocate; the address only needs to be aligned.
+            NonNull::new(align as *mut u8).unwrap()
+        } else {
+            // Safety: the layout has a non-zero size.
+            let ptr = unsafe { alloc::alloc(layout) };
+            NonNull::new(ptr).unwrap_or_else(|| alloc::handle_alloc_error(layout))
+        };
+        AlignedBuf {
+            ptr,
+            len: 0,
+            cap: capacity,
+            align,
+        }
+    }
+
+    /// Allocates a buffer of `len` zeroed bytes, all initialized, aligned to `align`.
+    ///
+    /// # Panics
+    ///
+    /// Panics as [`new`](AlignedBuf::new) does.
+    pub fn zeroed(len: usize, align: usize) -> AlignedBuf {
+        let mut buf = AlignedBuf::new(len, align);
+        // Safety: the allocation holds `len` bytes.
+        unsafe { buf.ptr.as_ptr().write_bytes(0, len) };
+        buf.len = len;
+        buf
+    }
+
+    /// Returns the number of initialized bytes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if no byte is initialized.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of bytes the buffer can hold.
+    pub fn capacity(&self) -> usize {
+        self.cap
+    }
+
+    /// Returns the alignment of the buffer's address.
+    pub fn align(&self) -> usize {
+        self.align
+    }
+}
+
+fn layout(capacity: usize, align: usize) -> Layout {
+    Layout::from_size_align(capacity, align).expect("invalid alignment or capacity")
+}
+
+impl Drop for AlignedBuf {
+    fn drop(&mut self) {
+        if self.cap != 0 {
+            // Safety: the buffer was allocated with this layout in `new`.
+            unsafe { alloc::dealloc(self.ptr.as_ptr(), layout(self.cap, self.align)) };
+        }
+    }
+}
+
+impl Deref for AlignedBuf {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        // Safety: the first `len` bytes are initialized.
+        unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl DerefMut for AlignedBuf {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        // Safety: the first `len` bytes are initialized.
+        unsafe { std::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl fmt::Debug for AlignedBuf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AlignedBuf")
+            .field("len", &self.len)
+            .field("cap", &self.cap)
+            .field("align", &self.align)
+            .finish()
+    }
+}
+
+// The alignment is carried in the user data, to rebuild the layout the allocation is freed with.
+unsafe impl BufferImpl for AlignedBuf {
+    type UserData = usize;
+
+    fn alignment(&self) -> usize {
+        self.align
+    }
+
+    fn into_raw_parts(self) -> (Vec<*mut u8>, Vec<usize>, Vec<usize>, Self::UserData) {
+        let this = std::mem::ManuallyDrop::new(self);
+        (
+            vec![this.ptr.as_ptr()],
+            vec![this.len],
+            vec![this.cap],
+            this.align,
+        )
+    }
+
+    unsafe fn from_raw_parts(
+        ptr: Vec<*mut u8>,
+        len: Vec<usize>,
+        cap: Vec<usize>,
+        align: Self::UserData,
+    ) -> Self {
+        AlignedBuf {
+            ptr: NonNull::new_unchecked(ptr[0]),
+            len: len[0],
+            cap: cap[0],
+            align,
+        }
+    }
+}
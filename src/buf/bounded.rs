@@ -154,6 +154,11 @@ pub trait BoundedBufMut: BoundedBuf<Buf = Self::BufMut> {
     /// to `pos` are initialized and owned by the buffer.
     unsafe fn set_init(&mut self, pos: usize);
 
+    /// Like [`IoBufMut::is_read_only`], for the underlying buffer.
+    fn is_read_only(&self) -> bool {
+        IoBufMut::is_read_only(self.get_buf())
+    }
+
     /// Copies the given byte slice into the buffer, starting at
     /// this view's offset.
     ///
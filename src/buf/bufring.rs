@@ -0,0 +1,290 @@
+//! Buffers selected by the kernel from a ring shared with it.
+//!
+//! This module provides [`BufRing`], a group of equally sized buffers registered with the
+//! kernel as a provided buffer ring (`IORING_REGISTER_PBUF_RING`). Operations that select a
+//! buffer from the group, such as [`TcpStream::recv_multi`] and [`TcpStream::read_select`],
+//! leave the choice of buffer to the kernel, which only takes one once data has actually
+//! arrived. Many idle connections can then share a small group instead of each holding a
+//! buffer of its own.
+//!
+//! [`TcpStream::recv_multi`]: crate::net::TcpStream::recv_multi
+//! [`TcpStream::read_select`]: crate::net::TcpStream::read_select
+
+use crate::buf::BufferImpl;
+use crate::runtime::CONTEXT;
+use crate::Buffer;
+use std::io;
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::Waker;
+
+/// A group of buffers the kernel selects from, registered under a buffer group id.
+///
+/// Buffers leave the ring when an operation completes into them, and are handed to the
+/// application as [`Buffer`]s. Dropping such a `Buffer` puts it back in the ring for the
+/// kernel to use again.
+///
+/// A `BufRing` value is a lightweight handle; cloning it creates a new reference to the same
+/// group. The ring stays registered, and its memory allocated, until the runtime it was
+/// registered with shuts down and every buffer taken from it has been dropped.
+#[derive(Clone)]
+pub struct BufRing {
+    inner: Arc<Mutex<Ring>>,
+}
+
+impl BufRing {
+    /// Allocates `entry_count` buffers of `buf_size` bytes each and registers them with the
+    /// kernel as the buffer group `bgid`.
+    ///
+    /// This method must be called in the context of a `tokio-uring` runtime, and requires
+    /// Linux 5.19 or later.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tokio_uring::buf::bufring::BufRing;
+    ///
+    /// tokio_uring::start(async {
+    ///     let ring = BufRing::register(64, 4096, 0).unwrap();
+    ///     assert_eq!(ring.bgid(), 0);
+    /// });
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`InvalidInput`](io::ErrorKind::InvalidInput) unless `entry_count` is a
+    /// power of two no larger than 32768 and `buf_size` is non-zero and fits in a `u32`.
+    /// Registering a `bgid` already in use on the runtime fails with `EEXIST`.
+    pub fn register(entry_count: u16, buf_size: usize, bgid: u16) -> io::Result<BufRing> {
+        if !entry_count.is_power_of_two() || entry_count > 1 << 15 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "entry count must be a power of two no larger than 32768",
+            ));
+        }
+        if buf_size == 0 || buf_size > u32::MAX as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "buffer size must be non-zero and fit in a u32",
+            ));
+        }
+
+        let inner = Arc::new(Mutex::new(Ring::new(entry_count, buf_size, bgid)?));
+        CONTEXT.with(|x| {
+            x.handle()
+                .as_ref()
+                .expect("Not in a runtime context")
+                .register_buf_ring(inner.clone())
+        })?;
+        Ok(BufRing { inner })
+    }
+
+    /// Returns the buffer group id the ring is registered under.
+    pub fn bgid(&self) -> u16 {
+        self.inner.lock().unwrap().bgid
+    }
+
+    /// Returns the size of each buffer in the ring.
+    pub fn buf_size(&self) -> usize {
+        self.inner.lock().unwrap().buf_size
+    }
+
+    /// Takes the buffer `bid`, which the kernel selected and filled with `len` bytes.
+    pub(crate) fn take(&self, bid: u16, len: usize) -> Buffer {
+        let mut ring = self.inner.lock().unwrap();
+        ring.outstanding += 1;
+        let buf = RingBuf {
+            ptr: ring.buf_ptr(bid),
+            len,
+            cap: ring.buf_size,
+            info: Some(RingBufInfo {
+                ring: self.inner.clone(),
+                bid,
+            }),
+        };
+        drop(ring);
+        Buffer::new(buf)
+    }
+
+    /// Narrows `buf`, taken from a ring, to its `len` bytes from `offset` on, such as the
+    /// payload after a header the kernel wrote in front of it. The whole buffer still returns
+    /// to the ring once dropped.
+    pub(crate) fn narrow(buf: Buffer, offset: usize, len: usize) -> Buffer {
+        let Ok(mut buf) = buf.try_into::<RingBuf>() else {
+            panic!("not a buffer taken from a ring");
+        };
+        assert!(offset + len <= buf.cap);
+        // Safety: the offset stays within the buffer, as checked above.
+        buf.ptr = unsafe { buf.ptr.add(offset) };
+        buf.len = len;
+        buf.cap -= offset;
+        Buffer::new(buf)
+    }
+
+    /// Registers `waker` to be woken once a buffer is returned to the ring, unless one was
+    /// returned since the kernel last ran out, in which case this returns `false`.
+    pub(crate) fn wait_for_buf(&self, waker: &Waker) -> bool {
+        let mut ring = self.inner.lock().unwrap();
+        if ring.outstanding < ring.entries as usize {
+            return false;
+        }
+        ring.waiters.push(waker.clone());
+        true
+    }
+}
+
+/// The ring shared with the kernel, and the buffers it points into.
+pub(crate) struct Ring {
+    // `entries` io_uring_buf entries, mapped with mmap so they are page aligned.
+    entries_ptr: *mut io_uring::types::BufRingEntry,
+    entries: u16,
+    // Our copy of the tail, which the kernel only reads.
+    tail: u16,
+    // Owns the memory `bufs_ptr` points into.
+    _bufs: Vec<u8>,
+    bufs_ptr: *mut u8,
+    buf_size: usize,
+    bgid: u16,
+    // Buffers taken by the application and not yet returned.
+    outstanding: usize,
+    waiters: Vec<Waker>,
+}
+
+// Safety: the raw pointers refer to memory owned by the ring, and are only accessed with the
+// ring's mutex held.
+unsafe impl Send for Ring {}
+
+impl Ring {
+    fn new(entries: u16, buf_size: usize, bgid: u16) -> io::Result<Ring> {
+        let ring_len = entries as usize * std::mem::size_of::<io_uring::types::BufRingEntry>();
+        // Safety: an anonymous private mapping has no preconditions.
+        let entries_ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                ring_len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_ANONYMOUS | libc::MAP_PRIVATE,
+                -1,
+                0,
+            )
+        };
+        if entries_ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+
+        let total = (entries as usize).checked_mul(buf_size).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "buffer ring is too large")
+        })?;
+        let mut bufs = vec![0; total];
+        let mut ring = Ring {
+            entries_ptr: entries_ptr.cast(),
+            entries,
+            tail: 0,
+            bufs_ptr: bufs.as_mut_ptr(),
+            _bufs: bufs,
+            buf_size,
+            bgid,
+            outstanding: entries as usize,
+            waiters: Vec::new(),
+        };
+        for bid in 0..entries {
+            ring.push(bid);
+        }
+        Ok(ring)
+    }
+
+    pub(crate) fn addr(&self) -> u64 {
+        self.entries_ptr as u64
+    }
+
+    pub(crate) fn entries(&self) -> u16 {
+        self.entries
+    }
+
+    pub(crate) fn bgid(&self) -> u16 {
+        self.bgid
+    }
+
+    fn buf_ptr(&self, bid: u16) -> *mut u8 {
+        // Safety: bids are below `entries`, so the offset stays within `bufs`.
+        unsafe { self.bufs_ptr.add(bid as usize * self.buf_size) }
+    }
+
+    // Hands the buffer `bid` back to the kernel.
+    fn push(&mut self, bid: u16) {
+        let index = self.tail & (self.entries - 1);
+        // Safety: the index is masked to the ring, and the kernel does not read entries past
+        // the tail, which is only published below.
+        unsafe {
+            let entry = &mut *self.entries_ptr.add(index as usize);
+            entry.set_addr(self.buf_ptr(bid) as u64);
+            entry.set_len(self.buf_size as u32);
+            entry.set_bid(bid);
+        }
+        self.tail = self.tail.wrapping_add(1);
+        // Safety: the tail overlays the reserved field of the first entry, which the kernel
+        // reads with acquire ordering; the release store publishes the entry written above.
+        unsafe {
+            let tail = io_uring::types::BufRingEntry::tail(self.entries_ptr) as *const AtomicU16;
+            (*tail).store(self.tail, Ordering::Release);
+        }
+        self.outstanding -= 1;
+    }
+}
+
+impl Drop for Ring {
+    fn drop(&mut self) {
+        let ring_len = self.entries as usize * std::mem::size_of::<io_uring::types::BufRingEntry>();
+        // Safety: the ring is no longer registered with the kernel, or it would not be
+        // dropped. The buffers are freed along with `_bufs`.
+        unsafe { libc::munmap(self.entries_ptr.cast(), ring_len) };
+    }
+}
+
+struct RingBuf {
+    ptr: *mut u8,
+    len: usize,
+    cap: usize,
+    info: Option<RingBufInfo>,
+}
+
+struct RingBufInfo {
+    ring: Arc<Mutex<Ring>>,
+    bid: u16,
+}
+
+unsafe impl BufferImpl for RingBuf {
+    type UserData = RingBufInfo;
+
+    fn into_raw_parts(mut self) -> (Vec<*mut u8>, Vec<usize>, Vec<usize>, Self::UserData) {
+        let info = self.info.take().unwrap();
+        (vec![self.ptr], vec![self.len], vec![self.cap], info)
+    }
+
+    unsafe fn from_raw_parts(
+        ptr: Vec<*mut u8>,
+        len: Vec<usize>,
+        cap: Vec<usize>,
+        user_data: Self::UserData,
+    ) -> Self {
+        RingBuf {
+            ptr: ptr[0],
+            len: len[0],
+            cap: cap[0],
+            info: Some(user_data),
+        }
+    }
+}
+
+impl Drop for RingBuf {
+    fn drop(&mut self) {
+        let Some(info) = self.info.take() else {
+            return;
+        };
+        let mut ring = info.ring.lock().unwrap();
+        ring.push(info.bid);
+        for waker in ring.waiters.drain(..) {
+            waker.wake();
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use crate::buf::fixed::{pool, registry};
+use crate::buf::BufferImpl;
+use crate::Buffer;
+use std::any::TypeId;
+use std::io;
+use std::iter::zip;
+
+impl Buffer {
+    /// Appends the segments of `next` to those of this buffer, making one buffer of both for
+    /// a single vectored operation, such as a static header written along with an owned body.
+    ///
+    /// Each buffer keeps its memory and is dropped with the chain, fixed buffers going back
+    /// to their collection then; the chain is not itself a fixed buffer. Views are chained
+    /// whole, as by [`into_inner`](Buffer::into_inner). The chain is
+    /// [read-only](Buffer::is_read_only) if either buffer is. Chaining a chain appends to it,
+    /// rather than nesting it.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use tokio_uring::net::UnixStream;
+    /// use tokio_uring::{Buffer, Submit};
+    ///
+    /// tokio_uring::start(async {
+    ///     let (a, _b) = UnixStream::pair().unwrap();
+    ///     let body = b"{\"ok\":true}".to_vec();
+    ///     let buf = Buffer::from_static(b"HTTP/1.1 200 OK\r\n\r\n").chain(Buffer::from(body));
+    ///     assert_eq!(buf.segment_count(), 2);
+    ///
+    ///     let (n, _) = a.write(buf).submit().await.unwrap();
+    ///     println!("wrote {} bytes", n);
+    /// });
+    /// ```
+    pub fn chain(self, next: Buffer) -> Buffer {
+        let mut parts = Chain::parts(self);
+        parts.extend(Chain::parts(next));
+        let read_only = parts.iter().any(Buffer::is_read_only);
+        let mut buf = Buffer::new(Chain(parts));
+        buf.read_only = read_only;
+        buf
+    }
+
+    /// Gathers the initialized bytes of the buffer into a single segment, for code that
+    /// cannot handle more, such as a parser after a vectored read.
+    ///
+    /// A buffer of one segment is returned as it is. A buffer created from a `Vec<Vec<u8>>`
+    /// whose first vector has room for the bytes of the others has them appended to it, and
+    /// keeps its allocation; the other vectors are freed. Any other buffer, views included,
+    /// is copied into a single new allocation of its initialized length. Either way, the
+    /// result holds exactly the bytes that were initialized, in order.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`InvalidInput`](io::ErrorKind::InvalidInput), handing the buffer back, for
+    /// a buffer checked out from a fixed buffer collection, which would lose its registration
+    /// by being copied elsewhere.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tokio_uring::Buffer;
+    ///
+    /// let mut first = Vec::with_capacity(16);
+    /// first.extend_from_slice(b"hello");
+    /// let ptr = first.as_ptr();
+    /// let buf = Buffer::from(vec![first, b" world".to_vec()]).coalesce().unwrap();
+    /// assert_eq!(buf.segment_count(), 1);
+    /// assert_eq!(&buf[0], b"hello world");
+    /// assert_eq!(buf[0].as_ptr(), ptr);
+    /// ```
+    pub fn coalesce(self) -> Result<Buffer, crate::Error<Buffer>> {
+        let fixed = self.type_id() == TypeId::of::<registry::FixedBuf>()
+            || self.type_id() == TypeId::of::<pool::FixedBuf>()
+            || self.type_id() == TypeId::of::<registry::SharedFixedBuf>();
+        if fixed {
+            let err = io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "a fixed buffer cannot be coalesced",
+            );
+            return Err(crate::Error(err, self));
+        }
+        if self.segment_count() == 1 {
+            return Ok(self);
+        }
+        let rest = self.len() - self.iovec.first().map_or(0, |iovec| iovec.iov_len);
+        let reusable = self.window.is_none()
+            && self.type_id() == TypeId::of::<Vec<Vec<u8>>>()
+            && zip(&self.iovec, &self.cap)
+                .next()
+                .is_some_and(|(iovec, cap)| cap - iovec.iov_len >= rest);
+        if !reusable {
+            return Ok(Buffer::from(self.to_contiguous_vec()));
+        }
+        let mut vecs = self
+            .try_into::<Vec<Vec<u8>>>()
+            .expect("the buffer was created from a Vec<Vec<u8>>");
+        let mut first = vecs.remove(0);
+        for vec in &vecs {
+            first.extend_from_slice(vec);
+        }
+        Ok(Buffer::from(first))
+    }
+
+    /// Copies the initialized bytes of every segment, in order, into a new vector.
+    pub fn to_contiguous_vec(&self) -> Vec<u8> {
+        let mut vec = Vec::with_capacity(self.len());
+        for seg in self.iter_segments() {
+            vec.extend_from_slice(seg);
+        }
+        vec
+    }
+}
+
+// Buffers laid end to end, each owning its segments.
+struct Chain(Vec<Buffer>);
+
+impl Chain {
+    // The buffers of a chain, or the whole of any other buffer.
+    fn parts(buf: Buffer) -> Vec<Buffer> {
+        match buf.try_into::<Chain>() {
+            Ok(chain) => chain.0,
+            Err(buf) => vec![buf.into_inner()],
+        }
+    }
+}
+
+// The buffers are kept as the user data, and given back the lengths of their segments.
+unsafe impl BufferImpl for Chain {
+    type UserData = Vec<Buffer>;
+
+    fn into_raw_parts(self) -> (Vec<*mut u8>, Vec<usize>, Vec<usize>, Self::UserData) {
+        let (mut ptr, mut len, mut cap) = (Vec::new(), Vec::new(), Vec::new());
+        for part in &self.0 {
+            for (iovec, &seg_cap) in zip(&part.iovec, &part.cap) {
+                ptr.push(iovec.iov_base as *mut u8);
+                len.push(iovec.iov_len);
+                cap.push(seg_cap);
+            }
+        }
+        (ptr, len, cap, self.0)
+    }
+
+    unsafe fn from_raw_parts(
+        _ptr: Vec<*mut u8>,
+        len: Vec<usize>,
+        _cap: Vec<usize>,
+        mut parts: Self::UserData,
+    ) -> Self {
+        let mut len = len.into_iter();
+        for iovec in parts.iter_mut().flat_map(|part| part.iovec.iter_mut()) {
+            iovec.iov_len = len.next().expect("a length per segment");
+        }
+        Chain(parts)
+    }
+}
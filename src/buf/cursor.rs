@@ -0,0 +1,182 @@
+use crate::Buffer;
+use bytes::buf::UninitSlice;
+use bytes::{Buf, BufMut};
+use std::ptr::NonNull;
+
+/// Reads the initialized bytes of a [`Buffer`] through [`bytes::Buf`], created by
+/// [`Buffer::reader`].
+///
+/// The bytes of every segment are read in order, as a write of the buffer would send them.
+/// A [`chunk`](Buf::chunk) ends at the end of its segment, so values straddling two segments
+/// are best read with the `get_*` methods, which piece them together.
+#[derive(Debug)]
+pub struct BufferReader<'a> {
+    iovec: &'a [libc::iovec],
+    // The segment being read, and the offset of the next byte in it. Past the end once all
+    // bytes were read.
+    seg: usize,
+    offset: usize,
+    remaining: usize,
+}
+
+impl BufferReader<'_> {
+    // Moves on to the next segment with bytes left.
+    fn skip_exhausted(&mut self) {
+        while self.seg < self.iovec.len() && self.offset == self.iovec[self.seg].iov_len {
+            self.seg += 1;
+            self.offset = 0;
+        }
+    }
+}
+
+impl Buf for BufferReader<'_> {
+    fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+    fn chunk(&self) -> &[u8] {
+        let Some(iovec) = self.iovec.get(self.seg) else {
+            return &[];
+        };
+        // Safety: the segment's first `iov_len` bytes are initialized.
+        let seg = unsafe { std::slice::from_raw_parts(iovec.iov_base as *const u8, iovec.iov_len) };
+        &seg[self.offset..]
+    }
+
+    fn advance(&mut self, mut cnt: usize) {
+        assert!(
+            cnt <= self.remaining,
+            "cannot advance past the {} bytes remaining",
+            self.remaining
+        );
+        self.remaining -= cnt;
+        while cnt > 0 {
+            let step = cnt.min(self.iovec[self.seg].iov_len - self.offset);
+            self.offset += step;
+            cnt -= step;
+            self.skip_exhausted();
+        }
+    }
+}
+
+/// Appends to a [`Buffer`] through [`bytes::BufMut`], created by [`Buffer::writer`].
+///
+/// Bytes are written after the last initialized byte, filling each segment's capacity before
+/// moving on to the next, and count as initialized as they are written: a write of the buffer
+/// then sends them. A [`chunk_mut`](BufMut::chunk_mut) ends at the end of its segment.
+#[derive(Debug)]
+pub struct BufferWriter<'a> {
+    buf: &'a mut Buffer,
+    // The segment being written.
+    seg: usize,
+}
+
+impl BufferWriter<'_> {
+    // Moves on to the next segment with capacity left.
+    fn skip_full(&mut self) {
+        let buf = &*self.buf;
+        while self.seg < buf.iovec.len() && buf.iovec[self.seg].iov_len == buf.cap[self.seg] {
+            self.seg += 1;
+        }
+    }
+}
+
+// Safety: `advance_mut` only counts bytes of the segments' capacity as initialized, which the
+// caller has written to through `chunk_mut`.
+unsafe impl BufMut for BufferWriter<'_> {
+    fn remaining_mut(&self) -> usize {
+        let buf = &*self.buf;
+        buf.iovec[self.seg..]
+            .iter()
+            .zip(&buf.cap[self.seg..])
+            .map(|(iovec, cap)| cap - iovec.iov_len)
+            .sum()
+    }
+
+    fn chunk_mut(&mut self) -> &mut UninitSlice {
+        self.skip_full();
+        let Some(iovec) = self.buf.iovec.get(self.seg) else {
+            // Safety: an empty slice may start at a dangling pointer.
+            return unsafe { UninitSlice::from_raw_parts_mut(NonNull::dangling().as_ptr(), 0) };
+        };
+        let spare = self.buf.cap[self.seg] - iovec.iov_len;
+        // Safety: the spare capacity of the segment follows its initialized bytes.
+        unsafe {
+            UninitSlice::from_raw_parts_mut(iovec.iov_base.cast::<u8>().add(iovec.iov_len), spare)
+        }
+    }
+
+    unsafe fn advance_mut(&mut self, mut cnt: usize) {
+        assert!(
+            cnt <= self.remaining_mut(),
+            "cannot advance past the end of the buffer"
+        );
+        while cnt > 0 {
+            self.skip_full();
+            let seg = self.seg;
+            let step = cnt.min(self.buf.cap[seg] - self.buf.iovec[seg].iov_len);
+            self.buf.iovec[seg].iov_len += step;
+            cnt -= step;
+        }
+    }
+}
+
+impl Buffer {
+    /// Returns a reader of the initialized bytes of the buffer, across its segments, through
+    /// [`bytes::Buf`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bytes::Buf;
+    /// use tokio_uring::Buffer;
+    ///
+    /// let buf = Buffer::from(vec![vec![0, 0], vec![0, 5, b'h', b'i']]);
+    /// let mut reader = buf.reader();
+    /// assert_eq!(reader.get_u32(), 5);
+    /// assert_eq!(reader.chunk(), b"hi");
+    /// ```
+    pub fn reader(&self) -> BufferReader<'_> {
+        let mut reader = BufferReader {
+            iovec: &self.iovec,
+            seg: 0,
+            offset: 0,
+            remaining: self.iovec.iter().map(|iovec| iovec.iov_len).sum(),
+        };
+        reader.skip_exhausted();
+        reader
+    }
+
+    /// Returns a writer appending to the buffer, across its segments, through
+    /// [`bytes::BufMut`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffer is [read-only](Buffer::is_read_only).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bytes::BufMut;
+    /// use tokio_uring::Buffer;
+    ///
+    /// let mut buf = Buffer::from(vec![Vec::with_capacity(3), Vec::with_capacity(8)]);
+    /// let mut writer = buf.writer();
+    /// writer.put_u32(2);
+    /// writer.put_slice(b"hi");
+    /// assert_eq!(&buf[0], &[0, 0, 0]);
+    /// assert_eq!(&buf[1], &[2, b'h', b'i']);
+    /// ```
+    pub fn writer(&mut self) -> BufferWriter<'_> {
+        self.assert_writable();
+        // Writing goes on from the last segment holding initialized bytes.
+        let seg = self
+            .iovec
+            .iter()
+            .rposition(|iovec| iovec.iov_len > 0)
+            .unwrap_or(0);
+        let mut writer = BufferWriter { buf: self, seg };
+        writer.skip_full();
+        writer
+    }
+}
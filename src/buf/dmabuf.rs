@@ -0,0 +1,149 @@
+use super::mmap::Mapping;
+use crate::buf::BufferImpl;
+use std::ops::{Deref, DerefMut};
+use std::os::unix::io::{AsRawFd, OwnedFd};
+use std::{fmt, io, mem};
+
+// The magic number of the filesystem dma-buf file descriptors belong to.
+const DMA_BUF_MAGIC: libc::c_long = 0x444d_4142;
+
+/// A dma-buf imported from a device driver, such as an accelerator's memory
+/// exported for peer-to-peer transfers, mapped to be used as a buffer.
+///
+/// The dma-buf is mapped shared and writable, and can be registered as a
+/// fixed buffer like any other memory, so that data move between the device
+/// memory and a file or socket without a copy through a bounce buffer. There
+/// is no registration opcode specific to dma-bufs: the kernel pins the
+/// mapped pages like those of ordinary memory, which works for exporters
+/// backed by pages, such as `udmabuf`, and fails at registration for those
+/// whose mappings are not.
+///
+/// Every byte of the mapping counts as initialized, and a read fills it from
+/// its start. The mapping and the file descriptor are kept until the buffer is
+/// dropped, which its registration, or an operation using it, delays. Any
+/// synchronization the exporter requires for CPU access through the mapping,
+/// with `DMA_BUF_IOCTL_SYNC`, is up to the application.
+///
+/// # Examples
+///
+/// ```no_run
+/// use tokio_uring::buf::fixed::registry;
+/// use tokio_uring::buf::DmaBuf;
+/// use tokio_uring::fs::File;
+/// use tokio_uring::Buffer;
+/// use std::os::unix::io::OwnedFd;
+///
+/// # fn exported_fd() -> OwnedFd { unimplemented!() }
+/// tokio_uring::start(async {
+///     // A dma-buf exported by the device driver.
+///     let fd: OwnedFd = exported_fd();
+///     let dmabuf = DmaBuf::import(fd, 1 << 20)?;
+///
+///     let registry = registry::register(std::iter::once(Buffer::from(dmabuf)))?;
+///     let buf = registry.check_out(0).unwrap();
+///     let file = File::open("weights.bin").await?;
+///     let (n, _) = file.read_fixed_at(buf, 0).await.unwrap();
+///     println!("read {} bytes into device memory", n);
+///     Ok::<_, std::io::Error>(())
+/// })
+/// .unwrap();
+/// ```
+pub struct DmaBuf {
+    map: Mapping,
+    fd: OwnedFd,
+}
+
+impl DmaBuf {
+    /// Maps the first `len` bytes of the dma-buf `fd` for reading and
+    /// writing, taking ownership of the descriptor.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`InvalidInput`](io::ErrorKind::InvalidInput) if `fd` is
+    /// not a dma-buf, or is smaller than `len` bytes. Fails with the error
+    /// from `mmap(2)` if the exporter does not allow the dma-buf to be mapped,
+    /// or `len` is zero.
+    pub fn import(fd: OwnedFd, len: usize) -> io::Result<DmaBuf> {
+        // Safety: `statfs` is plain data, for which all zeros is valid.
+        let mut statfs: libc::statfs = unsafe { mem::zeroed() };
+        // Safety: `statfs` is valid for writes.
+        if unsafe { libc::fstatfs(fd.as_raw_fd(), &mut statfs) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        if statfs.f_type as libc::c_long != DMA_BUF_MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the file descriptor is not a dma-buf",
+            ));
+        }
+        // A dma-buf reports its size by seeking to its end.
+        // Safety: seeking only reads the size of the dma-buf.
+        let size = unsafe { libc::lseek(fd.as_raw_fd(), 0, libc::SEEK_END) };
+        if size < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        if (size as u64) < len as u64 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the dma-buf is smaller than the requested length",
+            ));
+        }
+        let map = Mapping::new(&fd, 0, len, libc::PROT_READ | libc::PROT_WRITE)?;
+        Ok(DmaBuf { map, fd })
+    }
+}
+
+impl AsRawFd for DmaBuf {
+    fn as_raw_fd(&self) -> std::os::unix::io::RawFd {
+        self.fd.as_raw_fd()
+    }
+}
+
+impl Deref for DmaBuf {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.map.as_slice()
+    }
+}
+
+impl DerefMut for DmaBuf {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        // Safety: the mapping is writable, and owned by this value alone.
+        unsafe { std::slice::from_raw_parts_mut(self.map.ptr, self.map.len) }
+    }
+}
+
+impl fmt::Debug for DmaBuf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DmaBuf")
+            .field("fd", &self.fd)
+            .field("len", &self.map.len)
+            .finish()
+    }
+}
+
+// The mapping is kept along with the descriptor, which the exporter may rely on to keep the
+// memory around.
+unsafe impl BufferImpl for DmaBuf {
+    type UserData = (Mapping, OwnedFd);
+
+    fn into_raw_parts(self) -> (Vec<*mut u8>, Vec<usize>, Vec<usize>, Self::UserData) {
+        let len = self.map.len;
+        (
+            vec![self.map.ptr],
+            vec![len],
+            vec![len],
+            (self.map, self.fd),
+        )
+    }
+
+    unsafe fn from_raw_parts(
+        _ptr: Vec<*mut u8>,
+        _len: Vec<usize>,
+        _cap: Vec<usize>,
+        (map, fd): Self::UserData,
+    ) -> Self {
+        DmaBuf { map, fd }
+    }
+}
@@ -19,3 +19,51 @@ mod plumbing;
 pub mod pool;
 
 pub mod registry;
+
+use crate::Buffer;
+use std::time::Duration;
+
+/// Counters of the demand on a [`FixedBufRegistry`] or [`FixedBufPool`], as
+/// returned by their `stats` methods, to tell when tasks are starved of
+/// buffers.
+///
+/// The counters are kept up to date as buffers are checked out and in and as
+/// tasks queue up for them, rather than sampled. A task waits from the moment
+/// it is queued for a buffer until one is handed over to it, or it gives up.
+///
+/// [`FixedBufRegistry`]: registry::FixedBufRegistry
+/// [`FixedBufPool`]: pool::FixedBufPool
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FixedBufStats {
+    /// The number of tasks waiting for a buffer.
+    pub waiters: usize,
+    /// The number of times a task has had to wait for a buffer.
+    pub total_waits: u64,
+    /// The time spent waiting by the tasks that are done waiting.
+    pub total_wait_time: Duration,
+    /// The number of buffers in use, including those held by operations in
+    /// flight.
+    pub in_use: usize,
+    /// The most buffers that have been in use at once.
+    pub peak_in_use: usize,
+}
+
+/// Checks a buffer back in to its [`FixedBufRegistry`] or [`FixedBufPool`]
+/// without zeroing it, even though the collection was set to
+/// [`zero_on_check_in`](registry::FixedBufRegistry::zero_on_check_in). Use it
+/// for a buffer known to hold nothing sensitive, to save the cost of the
+/// zeroing.
+///
+/// Any other buffer is simply dropped.
+///
+/// [`FixedBufRegistry`]: registry::FixedBufRegistry
+/// [`FixedBufPool`]: pool::FixedBufPool
+pub fn check_in_without_zero(buf: Buffer) {
+    let buf = match buf.try_into::<registry::FixedBuf>() {
+        Ok(mut fixed) => return fixed.check_in(false),
+        Err(buf) => buf,
+    };
+    if let Ok(mut fixed) = buf.try_into::<pool::FixedBuf>() {
+        fixed.check_in(false);
+    }
+}
@@ -1,8 +1,68 @@
 // Internal data structures shared between thread-local and thread-safe
 // fixed buffer collections.
 
+use std::io;
+
 mod pool;
-pub(super) use pool::Pool;
+pub(super) use pool::{Checkout, Pool};
 
 mod registry;
-pub(super) use registry::Registry;
+pub(super) use registry::{iovec_of, read_only_error, IndexedCheckout, Registry};
+
+mod stats;
+
+// Overwrites the initialized bytes of a buffer being checked in with zeros,
+// with a write the compiler cannot elide as dead.
+pub(super) fn zero(iovec: &libc::iovec, init_len: usize) {
+    debug_assert!(init_len <= iovec.iov_len);
+    // Safety: the first `init_len` bytes of the buffer are initialized, and
+    // owned by the handle being dropped.
+    unsafe { libc::explicit_bzero(iovec.iov_base, init_len) };
+}
+
+// Faults in every page of a free buffer, so that the first operation on it
+// does not take the faults. The contents are left as they are, and nothing
+// is read from the bytes past `init_len`.
+pub(super) fn prefault(iovec: &libc::iovec, init_len: usize) -> io::Result<()> {
+    if iovec.iov_len == 0 {
+        return Ok(());
+    }
+    // Safety: sysconf has no preconditions.
+    let page = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+    let start = iovec.iov_base as usize;
+    let aligned = start - start % page;
+    // Safety: the pages are mapped, as the buffer lies in them, and are
+    // populated without changing what they hold.
+    let res = unsafe {
+        libc::madvise(
+            aligned as *mut libc::c_void,
+            start + iovec.iov_len - aligned,
+            libc::MADV_POPULATE_WRITE,
+        )
+    };
+    if res == 0 {
+        return Ok(());
+    }
+    let err = io::Error::last_os_error();
+    // Kernels before 5.14 do not know the advice: touch a byte of each page
+    // instead, writing back what it holds if initialized, and zero if not.
+    if err.raw_os_error() != Some(libc::EINVAL) {
+        return Err(err);
+    }
+    let base = iovec.iov_base as *mut u8;
+    let mut offset = 0;
+    while offset < iovec.iov_len {
+        // Safety: the byte is within the buffer, which the caller owns while
+        // it is free.
+        unsafe {
+            let byte = base.add(offset);
+            let value = match offset < init_len {
+                true => byte.read_volatile(),
+                false => 0,
+            };
+            byte.write_volatile(value);
+        }
+        offset += page - (start + offset) % page;
+    }
+    Ok(())
+}
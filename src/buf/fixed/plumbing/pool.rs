@@ -1,12 +1,19 @@
-use tokio::sync::Notify;
-
 use std::cmp;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::task::Waker;
+use std::time::{Duration, Instant};
 
+use super::stats::Metrics;
+use crate::buf::fixed::FixedBufStats;
 use crate::buf::IoBuf;
 use crate::Buffer;
 
+// A checked out buffer: its iovec, initialized length and index.
+pub(crate) type Checkout = (libc::iovec, usize, usize);
+
 // Internal state shared by FixedBufPool and FixedBuf handles.
 pub(crate) struct Pool {
     // Pointer to an allocated array of iovec records referencing
@@ -14,10 +21,25 @@ pub(crate) struct Pool {
     // same as the length of the states array.
     iovecs: Vec<libc::iovec>,
     states: Vec<BufState>,
-    // Table of head indices of the free buffer lists in each size bucket.
-    free_buf_head_by_cap: HashMap<usize, u16>,
-    // Used to notify tasks pending on `next`
-    notify_next_by_cap: HashMap<usize, Arc<Notify>>,
+    // Table of head indices of the free buffer lists in each size bucket,
+    // ordered by capacity to find the smallest bucket that fits a request.
+    free_buf_head_by_cap: BTreeMap<usize, u16>,
+    // The capacities of the registered buffers, free or not.
+    caps: BTreeSet<usize>,
+    // Tasks pending on `next`, in the order they started waiting.
+    waiters: VecDeque<Waiter>,
+    // Buffers checked in and handed over to a waiter that has yet to take them.
+    handed_over: HashMap<u64, Checkout>,
+    next_waiter_id: u64,
+    // The number of free buffers, kept up to date as they are checked out
+    // and in.
+    free: usize,
+    // Whether the initialized bytes of a buffer are zeroed as it is checked in.
+    zero_on_check_in: bool,
+    // Set by the runtime while the buffers are registered with the kernel.
+    // Once cleared, their indices may name the buffers of another collection.
+    registered: Arc<AtomicBool>,
+    metrics: Metrics,
     // Original buffers
     _buffers: Vec<Buffer>,
 }
@@ -40,6 +62,14 @@ enum BufState {
     CheckedOut,
 }
 
+struct Waiter {
+    id: u64,
+    // The capacity the task asked for.
+    cap: usize,
+    waker: Option<Waker>,
+    since: Instant,
+}
+
 impl Pool {
     pub(crate) fn new(bufs: impl Iterator<Item = Buffer>) -> Self {
         // Limit the number of buffers to the maximum allowable number.
@@ -51,9 +81,9 @@ impl Pool {
         let buffers = bufs.collect::<Vec<_>>();
         let mut iovecs = Vec::with_capacity(buffers.len());
         let mut states = Vec::with_capacity(buffers.len());
-        let mut free_buf_head_by_cap = HashMap::new();
+        let mut free_buf_head_by_cap = BTreeMap::new();
         for (i, buf) in buffers.iter().enumerate() {
-            debug_assert_eq!(buf.len(), 1);
+            debug_assert_eq!(buf.segment_count(), 1);
             let ptr = buf.stable_ptr();
             let len = buf.bytes_init();
             let cap = buf.bytes_total();
@@ -73,38 +103,81 @@ impl Pool {
             });
         }
         debug_assert_eq!(iovecs.len(), states.len());
+        let caps = iovecs.iter().map(|iovec| iovec.iov_len).collect();
 
         Pool {
             iovecs,
             states,
             free_buf_head_by_cap,
-            notify_next_by_cap: HashMap::new(),
+            caps,
+            waiters: VecDeque::new(),
+            handed_over: HashMap::new(),
+            next_waiter_id: 0,
+            free: buffers.len(),
+            zero_on_check_in: false,
+            registered: Arc::new(AtomicBool::new(false)),
+            metrics: Metrics::default(),
             _buffers: buffers,
         }
     }
 
+    pub(crate) fn set_registered(&mut self, registered: Arc<AtomicBool>) {
+        self.registered = registered;
+    }
+
+    // Returns `true` once the buffers were unregistered.
+    pub(crate) fn is_revoked(&self) -> bool {
+        !self.registered.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn set_zero_on_check_in(&mut self, zero: bool) {
+        self.zero_on_check_in = zero;
+    }
+
+    pub(crate) fn zero_on_check_in(&self) -> bool {
+        self.zero_on_check_in
+    }
+
+    pub(crate) fn set_on_wait_start(&mut self, hook: Box<dyn Fn() + Send + Sync>) {
+        self.metrics.set_on_wait_start(hook);
+    }
+
+    pub(crate) fn set_on_wait_end(&mut self, hook: Box<dyn Fn(Duration) + Send + Sync>) {
+        self.metrics.set_on_wait_end(hook);
+    }
+
+    pub(crate) fn stats(&self) -> FixedBufStats {
+        self.metrics.stats(self.iovecs.len() - self.free)
+    }
+
     pub(crate) fn iovecs(&self) -> &[libc::iovec] {
         &self.iovecs
     }
 
-    // If the free buffer list for this capacity is not empty, checks out the first buffer
-    // from the list and returns its data. Otherwise, returns None.
-    pub(crate) fn try_next(&mut self, cap: usize) -> Option<(libc::iovec, usize, usize)> {
-        let free_head = self.free_buf_head_by_cap.get_mut(&cap)?;
-        let index = *free_head as usize;
+    pub(crate) fn free_count(&self) -> usize {
+        self.free
+    }
+
+    // Checks out the first buffer from the free list of the smallest capacity
+    // of at least `cap` that has any, and returns its data. Otherwise, returns None.
+    pub(crate) fn try_next(&mut self, cap: usize) -> Option<Checkout> {
+        let (&bucket, &index) = self.free_buf_head_by_cap.range(cap..).next()?;
+        let index = index as usize;
         let state = self.states.get_mut(index).expect("invalid buffer index");
         let BufState::Free { init_len, next } = *state else {
             panic!("buffer is checked out")
         };
         *state = BufState::CheckedOut;
+        self.free -= 1;
+        self.metrics.record_in_use(self.iovecs.len() - self.free);
 
         // Update the head of the free list for this capacity.
         match next {
             Some(i) => {
-                *free_head = i;
+                self.free_buf_head_by_cap.insert(bucket, i);
             }
             None => {
-                self.free_buf_head_by_cap.remove(&cap);
+                self.free_buf_head_by_cap.remove(&bucket);
             }
         }
 
@@ -113,32 +186,109 @@ impl Pool {
         Some((iovec, init_len, index))
     }
 
-    // Returns a `Notify` to use for waking up tasks awaiting a buffer of
-    // the specified capacity.
-    pub(crate) fn notify_on_next(&mut self, cap: usize) -> Arc<Notify> {
-        let notify = self.notify_next_by_cap.entry(cap).or_default();
-        Arc::clone(notify)
+    // Checks out a buffer like `try_next`, or failing that, queues a waiter
+    // for one and returns its id.
+    pub(crate) fn next_or_wait(&mut self, cap: usize) -> Result<Checkout, u64> {
+        if let Some(checkout) = self.try_next(cap) {
+            return Ok(checkout);
+        }
+        let id = self.next_waiter_id;
+        self.next_waiter_id += 1;
+        let since = self.metrics.wait_started();
+        self.waiters.push_back(Waiter {
+            id,
+            cap,
+            waker: None,
+            since,
+        });
+        Err(id)
+    }
+
+    // Takes the buffer handed over to a waiter, or else registers the waker
+    // to be woken when one is.
+    pub(crate) fn poll_waiter(&mut self, id: u64, waker: &Waker) -> Option<Checkout> {
+        if let Some(checkout) = self.handed_over.remove(&id) {
+            return Some(checkout);
+        }
+        let waiter = self
+            .waiters
+            .iter_mut()
+            .find(|waiter| waiter.id == id)
+            .expect("waiter is neither queued nor served");
+        match &waiter.waker {
+            Some(w) if w.will_wake(waker) => {}
+            _ => waiter.waker = Some(waker.clone()),
+        }
+        None
+    }
+
+    // Dequeues a waiter that gave up, checking back in a buffer handed over
+    // to it in the meantime, so that it goes to the next waiter in line.
+    pub(crate) fn cancel_waiter(&mut self, id: u64) {
+        match self.handed_over.remove(&id) {
+            Some((_, init_len, index)) => self.check_in(index, init_len),
+            None => {
+                let position = self.waiters.iter().position(|waiter| waiter.id == id);
+                if let Some(waiter) = position.and_then(|i| self.waiters.remove(i)) {
+                    self.metrics.wait_ended(waiter.since);
+                }
+            }
+        }
+    }
+
+    // Faults in the pages of the free buffers, leaving those checked out to
+    // their owners.
+    pub(crate) fn prefault(&self) -> io::Result<()> {
+        for (iovec, state) in self.iovecs.iter().zip(&self.states) {
+            if let BufState::Free { init_len, .. } = *state {
+                super::prefault(iovec, init_len)?;
+            }
+        }
+        Ok(())
+    }
+
+    // The capacity of the buffers a request for `cap` is served from, when
+    // all are free.
+    fn size_class(&self, cap: usize) -> Option<usize> {
+        self.caps.range(cap..).next().copied()
     }
 
     pub(crate) fn check_in(&mut self, index: usize, init_len: usize) {
         let cap = self.iovecs[index].iov_len;
-        let state = &mut self.states[index];
         debug_assert!(
-            matches!(state, BufState::CheckedOut),
-            "the buffer must be checked out"
+            matches!(self.states[index], BufState::CheckedOut),
+            "the buffer must be checked in once"
         );
 
+        // Waiters are queued only when no free buffer fits them, so a buffer
+        // they could use goes straight to one of them, without a trip through
+        // the free lists where `try_next` could take it first. The first
+        // waiter whose own size class this is gets it; only if there is none
+        // does a waiter for a smaller class. Smaller requests would otherwise
+        // take every large buffer returned ahead of the requests that need it.
+        let position = self
+            .waiters
+            .iter()
+            .position(|waiter| self.size_class(waiter.cap) == Some(cap))
+            .or_else(|| self.waiters.iter().position(|waiter| waiter.cap <= cap));
+        if let Some(position) = position {
+            let waiter = self.waiters.remove(position).unwrap();
+            self.metrics.wait_ended(waiter.since);
+            self.handed_over
+                .insert(waiter.id, (self.iovecs[index], init_len, index));
+            if let Some(waker) = waiter.waker {
+                waker.wake();
+            }
+            return;
+        }
+
         // Link the buffer as the new head of the free list for its capacity.
         // Recently checked in buffers will be first to be reused,
         // improving cache locality.
         let next = self.free_buf_head_by_cap.insert(cap, index as u16);
 
-        *state = BufState::Free { init_len, next };
-
-        if let Some(notify) = self.notify_next_by_cap.get(&cap) {
-            // Wake up a single task pending on `next`
-            notify.notify_one();
-        }
+        self.states[index] = BufState::Free { init_len, next };
+        self.free += 1;
     }
 }
 
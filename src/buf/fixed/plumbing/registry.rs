@@ -1,6 +1,19 @@
 use std::cmp;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::io;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::Waker;
+use std::time::{Duration, Instant};
 
-use crate::{buf::IoBuf, Buffer};
+use super::stats::Metrics;
+use crate::buf::fixed::registry::{BufStatus, RegistrationRevoked};
+use crate::buf::fixed::FixedBufStats;
+use crate::buf::{IoBuf, PageBacking};
+use crate::Buffer;
+
+// A checked out buffer: its index, along with its iovec and initialized length.
+pub(crate) type IndexedCheckout = (usize, (libc::iovec, usize));
 
 // Internal state shared by FixedBufRegistry and Buffers.
 pub(crate) struct Registry {
@@ -11,8 +24,36 @@ pub(crate) struct Registry {
     // State information on the buffers. Indices in this array correspond to
     // the indices in the array at iovecs.
     states: Vec<BufState>,
-    // Original buffers
-    _buffers: Vec<Buffer>,
+    // Tasks pending on `check_out_async`, queued per buffer index in the
+    // order they started waiting.
+    waiters: HashMap<usize, VecDeque<Waiter>>,
+    // Tasks pending on `check_out_any_async`, served with a buffer nobody
+    // waits for by index.
+    any_waiters: VecDeque<Waiter>,
+    // Buffers checked in and handed over to a waiter that has yet to take
+    // them, with their index.
+    handed_over: HashMap<u64, IndexedCheckout>,
+    next_waiter_id: u64,
+    // Original buffers, `None` in empty slots.
+    buffers: Vec<Option<Buffer>>,
+    // The indices of the buffers in the `Free` state, and the number of
+    // those in the `CheckedOut` or `Shared` state, kept up to date by
+    // `set_state`.
+    free: BTreeSet<usize>,
+    checked_out: usize,
+    // Set once the registry is being unregistered: no buffer can be checked
+    // out any more, and `drained` is woken when the last one is checked in.
+    draining: bool,
+    drained: Option<Waker>,
+    // Set by the runtime while the buffers are registered with the kernel.
+    // Once cleared, their indices may name the buffers of another collection.
+    registered: Arc<AtomicBool>,
+    // Whether the initialized bytes of a buffer are zeroed as it is checked in.
+    zero_on_check_in: bool,
+    // Mirrors `checked_out` for the runtime, which refuses to unregister
+    // memory the caller owns while buffers of it are checked out.
+    pinned: Option<Arc<AtomicUsize>>,
+    metrics: Metrics,
 }
 
 unsafe impl Send for Registry {}
@@ -20,12 +61,24 @@ unsafe impl Sync for Registry {}
 
 // State information of a buffer in the registry,
 enum BufState {
+    // The slot of a sparse registry has no buffer yet.
+    Empty,
     // The buffer is not in use.
     Free { init_len: usize },
     // The buffer is checked out.
     // Its data are logically owned by the Buffer,
     // which also keeps track of the length of the initialized part.
-    CheckedOut,
+    // `in_flight` is set while an operation holds it.
+    CheckedOut { in_flight: bool },
+    // The buffer is checked out read-only by `count` shared holds, and
+    // cannot be checked out exclusively until the last one is released.
+    Shared { init_len: usize, count: usize },
+}
+
+struct Waiter {
+    id: u64,
+    waker: Option<Waker>,
+    since: Instant,
 }
 
 impl Registry {
@@ -40,63 +93,455 @@ impl Registry {
         let mut iovecs = Vec::with_capacity(buffers.len());
         let mut states = Vec::with_capacity(buffers.len());
         for buf in buffers.iter() {
-            debug_assert_eq!(buf.len(), 1);
+            debug_assert_eq!(buf.segment_count(), 1);
             // Origin buffer will be dropped when Registry is dropped
-            let ptr = buf.stable_ptr();
-            let len = buf.bytes_init();
-            let cap = buf.bytes_total();
-            let iovec = libc::iovec {
-                iov_base: ptr as _,
-                iov_len: cap,
-            };
-            iovecs.push(iovec);
-            states.push(BufState::Free { init_len: len });
+            iovecs.push(iovec_of(buf));
+            states.push(BufState::Free {
+                init_len: buf.bytes_init(),
+            });
         }
         debug_assert_eq!(iovecs.len(), states.len());
 
         Self {
+            free: (0..states.len()).collect(),
+            checked_out: 0,
             iovecs,
             states,
-            _buffers: buffers,
+            waiters: HashMap::new(),
+            any_waiters: VecDeque::new(),
+            handed_over: HashMap::new(),
+            next_waiter_id: 0,
+            buffers: buffers.into_iter().map(Some).collect(),
+            draining: false,
+            drained: None,
+            registered: Arc::new(AtomicBool::new(false)),
+            zero_on_check_in: false,
+            pinned: None,
+            metrics: Metrics::default(),
         }
     }
 
+    // Creates a registry of `count` empty slots.
+    pub(crate) fn sparse(count: usize) -> Self {
+        let empty = libc::iovec {
+            iov_base: std::ptr::null_mut(),
+            iov_len: 0,
+        };
+        Self {
+            iovecs: vec![empty; count],
+            states: (0..count).map(|_| BufState::Empty).collect(),
+            waiters: HashMap::new(),
+            any_waiters: VecDeque::new(),
+            handed_over: HashMap::new(),
+            next_waiter_id: 0,
+            buffers: (0..count).map(|_| None).collect(),
+            free: BTreeSet::new(),
+            checked_out: 0,
+            draining: false,
+            drained: None,
+            registered: Arc::new(AtomicBool::new(false)),
+            zero_on_check_in: false,
+            pinned: None,
+            metrics: Metrics::default(),
+        }
+    }
+
+    // Takes the flag the runtime set on registering the buffers.
+    pub(crate) fn set_registered(&mut self, registered: Arc<AtomicBool>) {
+        self.registered = registered;
+    }
+
+    // Returns `true` once the buffers were unregistered.
+    pub(crate) fn is_revoked(&self) -> bool {
+        !self.registered.load(Ordering::Acquire)
+    }
+
+    // Returns the count of checked out buffers, kept up to date from now on.
+    pub(crate) fn pin(&mut self) -> Arc<AtomicUsize> {
+        let pinned = Arc::new(AtomicUsize::new(self.checked_out));
+        self.pinned = Some(pinned.clone());
+        pinned
+    }
+
+    pub(crate) fn set_zero_on_check_in(&mut self, zero: bool) {
+        self.zero_on_check_in = zero;
+    }
+
+    pub(crate) fn zero_on_check_in(&self) -> bool {
+        self.zero_on_check_in
+    }
+
+    pub(crate) fn set_on_wait_start(&mut self, hook: Box<dyn Fn() + Send + Sync>) {
+        self.metrics.set_on_wait_start(hook);
+    }
+
+    pub(crate) fn set_on_wait_end(&mut self, hook: Box<dyn Fn(Duration) + Send + Sync>) {
+        self.metrics.set_on_wait_end(hook);
+    }
+
+    pub(crate) fn stats(&self) -> FixedBufStats {
+        self.metrics.stats(self.checked_out)
+    }
+
     pub(crate) fn iovecs(&self) -> &[libc::iovec] {
         &self.iovecs
     }
 
+    pub(crate) fn free_count(&self) -> usize {
+        self.free.len()
+    }
+
+    pub(crate) fn checked_out_count(&self) -> usize {
+        self.checked_out
+    }
+
+    pub(crate) fn statuses(&self) -> Vec<BufStatus> {
+        self.states
+            .iter()
+            .map(|state| match state {
+                BufState::Empty => BufStatus::Empty,
+                BufState::Free { .. } => BufStatus::Free,
+                BufState::CheckedOut { in_flight: false } => BufStatus::CheckedOut,
+                BufState::CheckedOut { in_flight: true } => BufStatus::InFlight,
+                BufState::Shared { .. } => BufStatus::Shared,
+            })
+            .collect()
+    }
+
+    pub(crate) fn page_backings(&self) -> Vec<PageBacking> {
+        self.buffers
+            .iter()
+            .map(|buf| {
+                buf.as_ref()
+                    .map_or(PageBacking::Regular, Buffer::page_backing)
+            })
+            .collect()
+    }
+
+    // Moves the indexed buffer to `state`, keeping the counts in step.
+    fn set_state(&mut self, index: usize, state: BufState) {
+        match state {
+            BufState::Free { .. } => self.free.insert(index),
+            _ => self.free.remove(&index),
+        };
+        let checked_out = |state: &BufState| {
+            matches!(state, BufState::CheckedOut { .. } | BufState::Shared { .. }) as usize
+        };
+        self.checked_out =
+            self.checked_out + checked_out(&state) - checked_out(&self.states[index]);
+        self.states[index] = state;
+        self.metrics.record_in_use(self.checked_out);
+        if let Some(pinned) = &self.pinned {
+            pinned.store(self.checked_out, Ordering::Release);
+        }
+    }
+
+    // Records whether an operation holds the indexed buffer, if it is still
+    // checked out.
+    pub(crate) fn set_in_flight(&mut self, index: usize, in_flight: bool) {
+        if let Some(BufState::CheckedOut { in_flight: flag }) = self.states.get_mut(index) {
+            *flag = in_flight;
+        }
+    }
+
     // If the indexed buffer is free, changes its state to checked out
     // and returns its data.
-    // If the buffer is already checked out, returns None.
+    // If the buffer is already checked out, the slot is empty, or the
+    // registry is draining or revoked, returns None.
     pub(crate) fn check_out(&mut self, index: usize) -> Option<(libc::iovec, usize)> {
-        let state = self.states.get_mut(index).expect("invalid buffer index");
+        let state = self.states.get(index).expect("invalid buffer index");
         let BufState::Free { init_len } = *state else {
             return None;
         };
-        *state = BufState::CheckedOut;
+        if self.draining || self.is_revoked() {
+            return None;
+        }
+        self.set_state(index, BufState::CheckedOut { in_flight: false });
 
         let iovec = self.iovecs[index];
 
         Some((iovec, init_len))
     }
 
+    // Takes a shared hold on the indexed buffer, if it is free or already
+    // shared, and returns its data. While tasks wait to check it out
+    // exclusively, no new hold is taken, so that they are not starved.
+    pub(crate) fn check_out_shared(&mut self, index: usize) -> Option<(libc::iovec, usize)> {
+        let state = self.states.get(index).expect("invalid buffer index");
+        if self.draining || self.is_revoked() {
+            return None;
+        }
+        let (init_len, count) = match *state {
+            BufState::Free { init_len } => (init_len, 1),
+            BufState::Shared { init_len, count } if !self.waiters.contains_key(&index) => {
+                (init_len, count + 1)
+            }
+            _ => return None,
+        };
+        self.set_state(index, BufState::Shared { init_len, count });
+        Some((self.iovecs[index], init_len))
+    }
+
+    // Releases a shared hold on the indexed buffer, checking it in with the
+    // last one.
+    pub(crate) fn release_shared(&mut self, index: usize) {
+        let Some(&BufState::Shared { init_len, count }) = self.states.get(index) else {
+            panic!("the buffer must be shared");
+        };
+        if count > 1 {
+            self.set_state(
+                index,
+                BufState::Shared {
+                    init_len,
+                    count: count - 1,
+                },
+            );
+            return;
+        }
+        self.set_state(index, BufState::CheckedOut { in_flight: false });
+        self.check_in(index, init_len);
+    }
+
+    // Checks out the free buffer of the lowest index, like `check_out`, and
+    // returns the index along with its data.
+    pub(crate) fn check_out_any(&mut self) -> Option<IndexedCheckout> {
+        let index = *self.free.first()?;
+        self.check_out(index).map(|checkout| (index, checkout))
+    }
+
+    // Checks out the indexed buffer like `check_out`, or any buffer like
+    // `check_out_any` for no index, or failing that, queues a waiter for it
+    // and returns its id.
+    pub(crate) fn check_out_or_wait(
+        &mut self,
+        index: Option<usize>,
+    ) -> Result<IndexedCheckout, u64> {
+        let checkout = match index {
+            Some(index) => self.check_out(index).map(|checkout| (index, checkout)),
+            None => self.check_out_any(),
+        };
+        if let Some(checkout) = checkout {
+            return Ok(checkout);
+        }
+        let id = self.next_waiter_id;
+        self.next_waiter_id += 1;
+        let waiter = Waiter {
+            id,
+            waker: None,
+            since: self.metrics.wait_started(),
+        };
+        match index {
+            Some(index) => self.waiters.entry(index).or_default().push_back(waiter),
+            None => self.any_waiters.push_back(waiter),
+        }
+        Err(id)
+    }
+
+    // Takes the buffer handed over to a waiter, or else registers the waker
+    // to be woken when it is. A waiter of a draining or revoked registry is
+    // dequeued and fails, as no buffer will be handed over to it.
+    pub(crate) fn poll_waiter(
+        &mut self,
+        index: Option<usize>,
+        id: u64,
+        waker: &Waker,
+    ) -> Result<Option<IndexedCheckout>, RegistrationRevoked> {
+        if let Some(checkout) = self.handed_over.remove(&id) {
+            return Ok(Some(checkout));
+        }
+        if self.draining || self.is_revoked() {
+            self.cancel_waiter(index, id);
+            return Err(RegistrationRevoked);
+        }
+        let queue = match index {
+            Some(index) => self.waiters.get_mut(&index),
+            None => Some(&mut self.any_waiters),
+        };
+        let waiter = queue
+            .and_then(|queue| queue.iter_mut().find(|waiter| waiter.id == id))
+            .expect("waiter is neither queued nor served");
+        match &waiter.waker {
+            Some(w) if w.will_wake(waker) => {}
+            _ => waiter.waker = Some(waker.clone()),
+        }
+        Ok(None)
+    }
+
+    // Dequeues a waiter that gave up, checking back in the buffer if it was
+    // handed over in the meantime, so that it goes to the next waiter in line.
+    pub(crate) fn cancel_waiter(&mut self, index: Option<usize>, id: u64) {
+        if let Some((index, (_, init_len))) = self.handed_over.remove(&id) {
+            self.check_in(index, init_len);
+            return;
+        }
+        let queue = match index {
+            Some(index) => self.waiters.get_mut(&index),
+            None => Some(&mut self.any_waiters),
+        };
+        let Some(queue) = queue else {
+            return;
+        };
+        let position = queue.iter().position(|waiter| waiter.id == id);
+        if let Some(waiter) = position.and_then(|i| queue.remove(i)) {
+            self.metrics.wait_ended(waiter.since);
+        }
+        if let Some(index) = index {
+            if self.waiters.get(&index).is_some_and(VecDeque::is_empty) {
+                self.waiters.remove(&index);
+            }
+        }
+    }
+
+    // Faults in the pages of the free buffers, leaving those checked out to
+    // their owners.
+    pub(crate) fn prefault(&self) -> io::Result<()> {
+        for (iovec, state) in self.iovecs.iter().zip(&self.states) {
+            if let BufState::Free { init_len } = *state {
+                super::prefault(iovec, init_len)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Stops buffers from being checked out, or handed over to waiters, so
+    // that all of them end up checked in. The waiters are woken to fail.
+    pub(crate) fn start_draining(&mut self) {
+        self.draining = true;
+        let waiters = self.waiters.values_mut().flatten();
+        for waiter in waiters.chain(&mut self.any_waiters) {
+            if let Some(waker) = waiter.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+
+    // Returns `true` once draining and no buffer is checked out, or else
+    // registers the waker to be woken when that happens.
+    pub(crate) fn poll_drained(&mut self, waker: &Waker) -> bool {
+        debug_assert!(self.draining);
+        if self.checked_out == 0 {
+            self.drained = None;
+            return true;
+        }
+        match &self.drained {
+            Some(w) if w.will_wake(waker) => {}
+            _ => self.drained = Some(waker.clone()),
+        }
+        false
+    }
+
+    // Fails unless the slot can be given another buffer: it must exist, and
+    // its buffer must not be checked out, whether by the application or by
+    // an operation in flight. No slot is replaceable while draining, or once
+    // revoked.
+    pub(crate) fn check_replaceable(&self, index: usize) -> io::Result<()> {
+        if self.draining {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the registry is being unregistered",
+            ));
+        }
+        if self.is_revoked() {
+            return Err(io::Error::other(RegistrationRevoked));
+        }
+        match self.states.get(index) {
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "buffer index out of range of the registry",
+            )),
+            Some(BufState::CheckedOut { .. } | BufState::Shared { .. }) => Err(io::Error::new(
+                io::ErrorKind::ResourceBusy,
+                "the buffer in this slot is checked out",
+            )),
+            Some(_) => Ok(()),
+        }
+    }
+
+    // Puts `buf`, already registered with the kernel in this slot, in place
+    // of the previous buffer, which is returned. Tasks waiting for the slot
+    // get the new buffer.
+    pub(crate) fn replace(&mut self, index: usize, buf: Buffer) -> Option<Buffer> {
+        debug_assert!(self.check_replaceable(index).is_ok());
+        let init_len = buf.bytes_init();
+        self.iovecs[index] = iovec_of(&buf);
+        let old = self.buffers[index].replace(buf);
+        self.set_state(index, BufState::CheckedOut { in_flight: false });
+        self.check_in(index, init_len);
+        old
+    }
+
     pub(crate) fn check_in(&mut self, index: usize, init_len: usize) {
-        let state = self.states.get_mut(index).expect("invalid buffer index");
+        let state = self.states.get(index).expect("invalid buffer index");
         debug_assert!(
-            matches!(state, BufState::CheckedOut),
+            matches!(state, BufState::CheckedOut { .. }),
             "the buffer must be checked out"
         );
-        *state = BufState::Free { init_len };
+
+        // The buffer stays checked out and goes straight to the first waiter
+        // for its index, or else to the first waiter for any buffer, so that
+        // `check_out` cannot take it ahead of the queue. A draining or revoked
+        // registry keeps its waiters waiting.
+        let waiter = match self.draining || self.is_revoked() {
+            false => self.next_waiter(index),
+            true => None,
+        };
+        if let Some(waiter) = waiter {
+            self.metrics.wait_ended(waiter.since);
+            self.handed_over
+                .insert(waiter.id, (index, (self.iovecs[index], init_len)));
+            self.set_in_flight(index, false);
+            if let Some(waker) = waiter.waker {
+                waker.wake();
+            }
+            return;
+        }
+
+        self.set_state(index, BufState::Free { init_len });
+        if self.draining && self.checked_out == 0 {
+            if let Some(waker) = self.drained.take() {
+                waker.wake();
+            }
+        }
+    }
+
+    // Dequeues the waiter next in line for the indexed buffer.
+    fn next_waiter(&mut self, index: usize) -> Option<Waiter> {
+        if let Some(queue) = self.waiters.get_mut(&index) {
+            let waiter = queue.pop_front().expect("empty queues are removed");
+            if queue.is_empty() {
+                self.waiters.remove(&index);
+            }
+            return Some(waiter);
+        }
+        self.any_waiters.pop_front()
     }
 }
 
 impl Drop for Registry {
     fn drop(&mut self) {
         assert!(
-            self.states
-                .iter()
-                .all(|state| matches!(state, BufState::Free { .. })),
+            self.states.iter().all(|state| !matches!(
+                state,
+                BufState::CheckedOut { .. } | BufState::Shared { .. }
+            )),
             "all buffers must be checked in"
         );
     }
 }
+
+// The iovec registered for a buffer.
+pub(crate) fn iovec_of(buf: &Buffer) -> libc::iovec {
+    libc::iovec {
+        iov_base: buf.stable_ptr() as _,
+        iov_len: buf.bytes_total(),
+    }
+}
+
+// The error for registering a read-only buffer, which reads into the fixed
+// buffer would write through.
+pub(crate) fn read_only_error() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "a read-only buffer cannot be registered",
+    )
+}
@@ -0,0 +1,60 @@
+use std::time::{Duration, Instant};
+
+use crate::buf::fixed::FixedBufStats;
+
+// Backpressure counters of a collection, updated as buffers are checked out
+// and in and as tasks queue up for them, with the hooks waits are reported to.
+#[derive(Default)]
+pub(crate) struct Metrics {
+    waiters: usize,
+    total_waits: u64,
+    total_wait_time: Duration,
+    peak_in_use: usize,
+    on_wait_start: Option<Box<dyn Fn() + Send + Sync>>,
+    on_wait_end: Option<Box<dyn Fn(Duration) + Send + Sync>>,
+}
+
+impl Metrics {
+    pub(crate) fn set_on_wait_start(&mut self, hook: Box<dyn Fn() + Send + Sync>) {
+        self.on_wait_start = Some(hook);
+    }
+
+    pub(crate) fn set_on_wait_end(&mut self, hook: Box<dyn Fn(Duration) + Send + Sync>) {
+        self.on_wait_end = Some(hook);
+    }
+
+    // Counts a task queued for a buffer, and returns when it started waiting.
+    pub(crate) fn wait_started(&mut self) -> Instant {
+        self.waiters += 1;
+        self.total_waits += 1;
+        if let Some(hook) = &self.on_wait_start {
+            hook();
+        }
+        Instant::now()
+    }
+
+    // Counts a task dequeued, whether handed a buffer or giving up, that
+    // started waiting at `since`.
+    pub(crate) fn wait_ended(&mut self, since: Instant) {
+        let waited = since.elapsed();
+        self.waiters -= 1;
+        self.total_wait_time += waited;
+        if let Some(hook) = &self.on_wait_end {
+            hook(waited);
+        }
+    }
+
+    pub(crate) fn record_in_use(&mut self, in_use: usize) {
+        self.peak_in_use = self.peak_in_use.max(in_use);
+    }
+
+    pub(crate) fn stats(&self, in_use: usize) -> FixedBufStats {
+        FixedBufStats {
+            waiters: self.waiters,
+            total_waits: self.total_waits,
+            total_wait_time: self.total_wait_time,
+            in_use,
+            peak_in_use: self.peak_in_use,
+        }
+    }
+}
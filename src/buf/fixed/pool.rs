@@ -8,18 +8,19 @@
 //!
 //! [`FixedBufPool`]: self::FixedBufPool
 
-use super::plumbing;
+use super::{plumbing, FixedBufStats};
 
 use crate::buf::BufferImpl;
 use crate::runtime::CONTEXT;
 use crate::Buffer;
 
-use tokio::pin;
-use tokio::sync::Notify;
-
+use std::future::Future;
 use std::io;
+use std::pin::Pin;
 use std::sync::Arc;
 use std::sync::Mutex;
+use std::task::{Context, Poll};
+use std::time::Duration;
 
 /// A dynamic collection of I/O buffers pre-registered with the kernel.
 ///
@@ -27,12 +28,13 @@ use std::sync::Mutex;
 /// allocated in memory, that can be registered in the current `tokio-uring`
 /// context using the [`register`] method. Unlike [`FixedBufRegistry`],
 /// individual buffers are not retrieved by index; instead, an available
-/// buffer matching a specified capacity can be retrieved with the [`try_next`]
+/// buffer of at least a specified capacity can be retrieved with the [`try_next`]
 /// method. In asynchronous contexts, the [`next`] method can be used to wait
 /// until such a buffer becomes available.
 /// This allows some flexibility in managing sets of buffers with
-/// different capacity tiers. The need to maintain lists of free buffers,
-/// however, imposes additional runtime overhead.
+/// different capacity tiers, or size classes: a request is served from the
+/// smallest class that has a free buffer large enough. The need to maintain
+/// lists of free buffers, however, imposes additional runtime overhead.
 ///
 /// A `FixedBufPool` value is a lightweight handle for a collection of
 /// allocated buffers. Cloning of a `FixedBufPool` creates a new reference to
@@ -101,40 +103,130 @@ impl FixedBufPool {
         }
     }
 
-    /// Returns a buffer of requested capacity from this pool
+    /// Returns a buffer of at least the requested capacity from this pool
     /// that is not currently owned by any other [`FixedBuf`] handle.
     /// If no such free buffer is available, returns `None`.
     ///
+    /// The buffer comes from the smallest capacity that has a free one, so
+    /// larger buffers are only used once the smaller ones run out.
+    ///
     /// The buffer is released to be available again once the
     /// returned `FixedBuf` handle has been dropped. An I/O operation
     /// using the buffer takes ownership of it and returns it once completed,
     /// preventing shared use of the buffer while the operation is in flight.
     ///
     /// An application should not rely on any particular order
-    /// in which available buffers are retrieved.
+    /// in which available buffers of the same capacity are retrieved.
     pub fn try_next(&self, cap: usize) -> Option<Buffer> {
-        let (iovec, init_len, index) = {
+        let checkout = {
             let mut inner = self.inner.lock().unwrap();
             inner.try_next(cap)?
         };
+        Some(self.buffer(checkout))
+    }
 
-        let pool_info = PoolInfo {
-            pool: self.inner.clone(),
-            index: index as u16,
-        };
-        let buf = FixedBuf {
-            iovec,
-            init_len,
-            pool_info: Some(pool_info),
-        };
+    /// Sets whether the initialized bytes of a buffer are overwritten with
+    /// zeros as it is returned to the pool, as for
+    /// [`FixedBufRegistry::zero_on_check_in`](super::registry::FixedBufRegistry::zero_on_check_in).
+    /// Off by default.
+    pub fn zero_on_check_in(self, zero: bool) -> Self {
+        self.inner.lock().unwrap().set_zero_on_check_in(zero);
+        self
+    }
+
+    /// Faults in every page of the free buffers ahead of their first use, as
+    /// [`FixedBufRegistry::prefault`](super::registry::FixedBufRegistry::prefault)
+    /// does, and returns the pool, to be chained on [`register`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use tokio_uring::buf::fixed::pool;
+    /// use tokio_uring::Buffer;
+    ///
+    /// tokio_uring::start(async {
+    ///     let pool = pool::register(
+    ///         (0..64).map(|_| Buffer::from(Vec::<u8>::with_capacity(1 << 20))),
+    ///     )?
+    ///     .prefault()?;
+    ///     // ...
+    ///     Ok::<_, std::io::Error>(())
+    /// })
+    /// .unwrap();
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails with the error from `madvise(2)` if the pages cannot be
+    /// populated.
+    pub fn prefault(self) -> io::Result<Self> {
+        self.inner.lock().unwrap().prefault()?;
+        Ok(self)
+    }
+
+    /// Sets a hook called each time a task starts waiting in
+    /// [`next`](Self::next), for export to a metrics system.
+    ///
+    /// Hooks run with the pool locked, and must not use it.
+    pub fn on_wait_start(self, hook: impl Fn() + Send + Sync + 'static) -> Self {
+        self.inner.lock().unwrap().set_on_wait_start(Box::new(hook));
+        self
+    }
+
+    /// Sets a hook called with the time a task waited in
+    /// [`next`](Self::next), once it is handed a buffer or gives up. It runs
+    /// in the task checking the buffer in, or in the one giving up, with the
+    /// pool locked.
+    pub fn on_wait_end(self, hook: impl Fn(Duration) + Send + Sync + 'static) -> Self {
+        self.inner.lock().unwrap().set_on_wait_end(Box::new(hook));
+        self
+    }
+
+    /// Returns the counters of waits for buffers and of buffers in use.
+    pub fn stats(&self) -> FixedBufStats {
+        self.inner.lock().unwrap().stats()
+    }
+
+    /// Returns the number of buffers in the pool.
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().iovecs().len()
+    }
+
+    /// Returns `true` if the pool has no buffers.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of buffers free to be taken with
+    /// [`try_next`](Self::try_next) or [`next`](Self::next).
+    pub fn free_count(&self) -> usize {
+        self.inner.lock().unwrap().free_count()
+    }
+
+    /// Returns the number of buffers taken from the pool and not yet
+    /// returned, including those held by operations in flight.
+    pub fn in_use_count(&self) -> usize {
+        let inner = self.inner.lock().unwrap();
+        inner.iovecs().len() - inner.free_count()
+    }
 
-        Some(Buffer::new(buf))
+    /// Returns the total capacity of the buffers in the pool, in bytes.
+    pub fn total_bytes(&self) -> usize {
+        let inner = self.inner.lock().unwrap();
+        inner.iovecs().iter().map(|iovec| iovec.iov_len).sum()
     }
 
-    /// Resolves to a buffer of requested capacity
-    /// when it is or becomes available in this pool.
-    /// This may happen when a [`Buffer`] handle owning a buffer
-    /// of the same capacity is dropped.
+    /// Resolves to a buffer of at least the requested capacity
+    /// when one is or becomes available in this pool.
+    /// This happens when a [`Buffer`] handle owning a large enough buffer
+    /// is dropped.
+    ///
+    /// Waiting tasks are served in order, each buffer going to the first task
+    /// whose request it fits, without waking any other. A task asking for the
+    /// capacity of the buffer is served before earlier ones asking for less,
+    /// so that small requests do not hold back large ones. If the future is
+    /// dropped after a buffer was handed over to it, the buffer goes to the
+    /// next task in line.
     ///
     /// If no matching buffers are available and none are being released,
     /// this asynchronous function will never resolve. Applications should take
@@ -142,40 +234,62 @@ impl FixedBufPool {
     /// will complete I/O operations owning the buffers, or back it up with a
     /// timeout using, for example, `tokio::util::timeout`.
     pub async fn next(&self, cap: usize) -> Buffer {
-        // Fast path: get the buffer if it's already available
-        let notify = {
-            if let Some(buffer) = self.try_next(cap) {
-                return buffer;
-            }
-            self.inner.lock().unwrap().notify_on_next(cap)
+        // Checking for a free buffer and queueing up happen under one lock,
+        // so a buffer checked in between cannot be missed.
+        let id = match self.inner.lock().unwrap().next_or_wait(cap) {
+            Ok(checkout) => return self.buffer(checkout),
+            Err(id) => id,
+        };
+        let checkout = Waiting {
+            pool: self,
+            id: Some(id),
+        }
+        .await;
+        self.buffer(checkout)
+    }
+
+    fn buffer(&self, (iovec, init_len, index): plumbing::Checkout) -> Buffer {
+        let pool_info = PoolInfo {
+            pool: self.inner.clone(),
+            index: index as u16,
+        };
+        let buf = FixedBuf {
+            iovec,
+            init_len,
+            pool_info: Some(pool_info),
         };
 
-        // Poll for a buffer, engaging the `Notify` machinery.
-        self.next_when_notified(cap, notify).await
+        Buffer::new(buf)
     }
+}
+
+// A task queued in the pool for a buffer, dequeued if dropped.
+struct Waiting<'a> {
+    pool: &'a FixedBufPool,
+    // `None` once served.
+    id: Option<u64>,
+}
+
+impl Future for Waiting<'_> {
+    type Output = plumbing::Checkout;
 
-    #[cold]
-    async fn next_when_notified(&self, cap: usize, notify: Arc<Notify>) -> Buffer {
-        let notified = notify.notified();
-        pin!(notified);
-        loop {
-            // In the single-threaded case, no buffers could get checked in
-            // between us calling `try_next` and here, so we can't miss a wakeup.
-            notified.as_mut().await;
-
-            if let Some(buffer) = self.try_next(cap) {
-                return buffer;
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let id = self.id.expect("polled after completion");
+        let checkout = self.pool.inner.lock().unwrap().poll_waiter(id, cx.waker());
+        match checkout {
+            Some(checkout) => {
+                self.id = None;
+                Poll::Ready(checkout)
             }
+            None => Poll::Pending,
+        }
+    }
+}
 
-            // It's possible that the task did not get a buffer from `try_next`.
-            // The `Notify` entries are created once for each requested capacity
-            // and never removed, so this `Notify` could have been holding
-            // a permit from a buffer checked in previously when no tasks were
-            // waiting. Then a task would call `next` on this pool and receive
-            // the buffer without consuming the permit. It's also possible that
-            // a task calls `try_next` directly.
-            // Reset the `Notified` future to wait for another wakeup.
-            notified.set(notify.notified());
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            self.pool.inner.lock().unwrap().cancel_waiter(id);
         }
     }
 }
@@ -256,15 +370,21 @@ impl FixedBufPool {
 ///
 /// If a collection of buffers is currently registered in the context
 /// of the `tokio-uring` runtime this call is made in, the function returns
-/// an error.
+/// an error. It fails with [`InvalidInput`](io::ErrorKind::InvalidInput) if
+/// any of the buffers is [read-only](Buffer::is_read_only).
 pub fn register(bufs: impl Iterator<Item = Buffer>) -> io::Result<FixedBufPool> {
-    let pool_inner = plumbing::Pool::new(bufs);
-    CONTEXT.with(|x| {
+    let mut read_only = false;
+    let mut pool_inner = plumbing::Pool::new(bufs.inspect(|buf| read_only |= buf.is_read_only()));
+    if read_only {
+        return Err(plumbing::read_only_error());
+    }
+    let registered = CONTEXT.with(|x| {
         x.handle()
             .as_ref()
             .expect("Not in a runtime context")
             .register_buffers(pool_inner.iovecs())
     })?;
+    pool_inner.set_registered(registered);
     Ok(FixedBufPool::new(pool_inner))
 }
 
@@ -274,8 +394,9 @@ pub fn register(bufs: impl Iterator<Item = Buffer>) -> io::Result<FixedBufPool>
 /// where the buffers should have been previously registered.
 ///
 /// This operation invalidates any `Buffer` handles checked out from
-/// this registry instance. Continued use of such handles in I/O
-/// operations may result in an error.
+/// this pool. Fixed operations on such handles fail with
+/// [`RegistrationRevoked`](super::registry::RegistrationRevoked), as the indices
+/// of the buffers may name those of a collection registered since.
 ///
 /// # Errors
 ///
@@ -304,6 +425,13 @@ pub(crate) struct PoolInfo {
     pub index: u16,
 }
 
+impl PoolInfo {
+    // Returns `true` once the pool the buffer came from was unregistered.
+    pub(crate) fn is_revoked(&self) -> bool {
+        self.pool.lock().unwrap().is_revoked()
+    }
+}
+
 unsafe impl BufferImpl for FixedBuf {
     type UserData = PoolInfo;
 
@@ -335,12 +463,23 @@ unsafe impl BufferImpl for FixedBuf {
     }
 }
 
-impl Drop for FixedBuf {
-    fn drop(&mut self) {
+impl FixedBuf {
+    // Returns the buffer to the pool, zeroing it first if the pool asks for
+    // it and `zero` allows it.
+    pub(super) fn check_in(&mut self, zero: bool) {
         let Some(pool_info) = self.pool_info.take() else {
             return;
         };
         let mut pool = pool_info.pool.lock().unwrap();
+        if zero && pool.zero_on_check_in() {
+            plumbing::zero(&self.iovec, self.init_len);
+        }
         pool.check_in(pool_info.index as usize, self.init_len);
     }
 }
+
+impl Drop for FixedBuf {
+    fn drop(&mut self) {
+        self.check_in(true);
+    }
+}
@@ -6,20 +6,28 @@
 //!
 //! [`FixedBufRegister`]: self::FixedBufRegister
 
-use super::plumbing;
+use super::{plumbing, pool, FixedBufStats};
 
-use crate::buf::BufferImpl;
+use crate::buf::{BufferImpl, PageBacking};
 use crate::runtime::CONTEXT;
 use crate::Buffer;
-use std::io;
+use std::any::TypeId;
+use std::future::Future;
+use std::ops::Deref;
+use std::pin::Pin;
 use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use std::{fmt, io};
 
 /// An indexed collection of I/O buffers pre-registered with the kernel.
 ///
 /// `FixedBufRegistry` allows the application to manage a collection of buffers
 /// allocated in memory, that can be registered in the current `tokio-uring`
 /// context using the [`register`] function. The buffers are accessed by their
-/// indices using the [`check_out`] method.
+/// indices using the [`check_out`] method, or [`check_out_async`] to wait for a
+/// buffer in use by another task. When the buffers are interchangeable,
+/// [`check_out_any`] takes whichever is free.
 ///
 /// A `FixedBufRegistry` value is a lightweight handle for a collection of
 /// allocated buffers. Cloning of a `FixedBufRegistry` creates a new reference to
@@ -33,6 +41,8 @@ use std::sync::{Arc, Mutex};
 ///
 /// [`register`]: register
 /// [`check_out`]: Self::check_out
+/// [`check_out_async`]: Self::check_out_async
+/// [`check_out_any`]: Self::check_out_any
 /// [`Runtime`]: crate::Runtime
 /// ['Buffer']: crate::Buffer
 #[derive(Clone)]
@@ -54,12 +64,462 @@ impl FixedBufRegistry {
     /// returned `Buffer` handle has been dropped. An I/O operation
     /// using the buffer takes ownership of it and returns it once completed,
     /// preventing shared use of the buffer while the operation is in flight.
+    ///
+    /// Once the collection is unregistered, this returns `None` for every
+    /// index; [`try_check_out`](Self::try_check_out) tells that case apart.
     pub fn check_out(&self, index: usize) -> Option<Buffer> {
-        let (iovec, init_len) = {
+        self.try_check_out(index).ok().flatten()
+    }
+
+    /// Like [`check_out`](Self::check_out), failing if the collection was
+    /// unregistered.
+    ///
+    /// After [`unregister`], the kernel may hold another collection of
+    /// buffers at the same indices, so the buffers of this one are not
+    /// handed out any more. Those checked out before stay valid memory, but
+    /// are refused by fixed operations.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`RegistrationRevoked`] once the collection was
+    /// unregistered.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use tokio_uring::buf::fixed::registry;
+    /// use tokio_uring::Buffer;
+    ///
+    /// tokio_uring::start(async {
+    ///     let old = registry::register(
+    ///         (0..2).map(|_| Buffer::from(Vec::<u8>::with_capacity(4096))),
+    ///     )
+    ///     .unwrap();
+    ///     registry::unregister().unwrap();
+    ///     let new = registry::register(
+    ///         (0..4).map(|_| Buffer::from(Vec::<u8>::with_capacity(4096))),
+    ///     )
+    ///     .unwrap();
+    ///
+    ///     assert!(old.try_check_out(0).is_err());
+    ///     assert!(new.try_check_out(0).unwrap().is_some());
+    /// });
+    /// ```
+    pub fn try_check_out(&self, index: usize) -> Result<Option<Buffer>, RegistrationRevoked> {
+        let checkout = {
             let mut inner = self.inner.lock().unwrap();
-            inner.check_out(index)?
+            if inner.is_revoked() {
+                return Err(RegistrationRevoked);
+            }
+            inner.check_out(index)
+        };
+        Ok(checkout.map(|checkout| self.buffer(index, checkout)))
+    }
+
+    /// Returns a shared, read-only handle to the buffer identified by the
+    /// specified index, unless the buffer is checked out exclusively.
+    ///
+    /// Any number of shared handles to a buffer can be out at once, and
+    /// each converts into a [`Buffer`] for writes and sends, so that many
+    /// operations can write the same registered buffer at the same time,
+    /// such as a response template sent to every connection. The buffer
+    /// holds the bytes initialized when it was last checked in.
+    ///
+    /// Shared and exclusive check-outs exclude each other: while any handle,
+    /// or any `Buffer` made from one, is alive, [`check_out`] returns `None`
+    /// for the buffer and [`check_out_async`] waits. Once a task waits so,
+    /// this returns `None` too, so that the task gets the buffer as soon as
+    /// the handles out are dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of range of the registered buffers.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use tokio_uring::buf::fixed::registry;
+    /// use tokio_uring::net::TcpStream;
+    /// use tokio_uring::{Buffer, Submit};
+    ///
+    /// async fn serve(streams: Vec<TcpStream>, page: registry::SharedFixedBuf) {
+    ///     for stream in streams {
+    ///         let page = Buffer::from(page.clone());
+    ///         tokio_uring::spawn(async move { stream.write(page).submit().await });
+    ///     }
+    /// }
+    ///
+    /// tokio_uring::start(async {
+    ///     let registry = registry::register(std::iter::once(Buffer::from(
+    ///         b"HTTP/1.1 200 OK\r\n\r\n".to_vec(),
+    ///     )))
+    ///     .unwrap();
+    ///     let page = registry.check_out_shared(0).unwrap();
+    ///     # let streams = Vec::new();
+    ///     serve(streams, page).await;
+    /// });
+    /// ```
+    ///
+    /// [`check_out`]: Self::check_out
+    /// [`check_out_async`]: Self::check_out_async
+    pub fn check_out_shared(&self, index: usize) -> Option<SharedFixedBuf> {
+        let (iovec, init_len) = self.inner.lock().unwrap().check_out_shared(index)?;
+        Some(SharedFixedBuf {
+            hold: Arc::new(SharedHold {
+                registry: self.inner.clone(),
+                index: index as u16,
+                iovec,
+                init_len,
+            }),
+        })
+    }
+
+    /// Resolves to the buffer identified by the specified index once it is
+    /// free, waiting for it to be checked back in if it is in use.
+    ///
+    /// Tasks waiting for the same buffer get it in the order they started
+    /// waiting: a checked in buffer goes straight to the first of them, and
+    /// [`check_out`] cannot take it first. If the future is dropped after the
+    /// buffer was handed over to it, the buffer goes to the next task in line.
+    ///
+    /// Like [`FixedBufPool::next`], this never resolves if the buffer is never
+    /// checked back in; see [`try_check_out_for`] to give up after a while.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`RegistrationRevoked`] once the collection is draining for
+    /// [`unregister_graceful`], which wakes the tasks waiting here, or was
+    /// unregistered.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of range of the registered buffers.
+    ///
+    /// [`check_out`]: Self::check_out
+    /// [`try_check_out_for`]: Self::try_check_out_for
+    /// [`FixedBufPool::next`]: crate::buf::fixed::pool::FixedBufPool::next
+    pub async fn check_out_async(&self, index: usize) -> Result<Buffer, RegistrationRevoked> {
+        self.check_out_or_wait(Some(index)).await
+    }
+
+    /// Like [`check_out_async`](Self::check_out_async), giving up and
+    /// returning `None` if the buffer is not free within `timeout`, or the
+    /// collection is draining or unregistered.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use tokio_uring::buf::fixed::registry;
+    /// use tokio_uring::Buffer;
+    /// use std::time::Duration;
+    ///
+    /// tokio_uring::start(async {
+    ///     let registry = registry::register(
+    ///         std::iter::once(Vec::<u8>::with_capacity(4096)).map(Buffer::from),
+    ///     )
+    ///     .unwrap();
+    ///
+    ///     match registry.try_check_out_for(0, Duration::from_millis(100)).await {
+    ///         Some(buf) => { /* use the buffer */ }
+    ///         None => eprintln!("buffer 0 is still busy"),
+    ///     }
+    /// });
+    /// ```
+    pub async fn try_check_out_for(&self, index: usize, timeout: Duration) -> Option<Buffer> {
+        tokio::time::timeout(timeout, self.check_out_async(index))
+            .await
+            .ok()?
+            .ok()
+    }
+
+    /// Returns any free buffer for use by the application, or `None` if
+    /// every buffer is in use.
+    ///
+    /// This takes the free buffer of the lowest index, without scanning the
+    /// collection: the registry keeps track of which buffers are free. It
+    /// composes with [`check_out`](Self::check_out), which cannot take a
+    /// buffer checked out here, nor this one a buffer checked out there; the
+    /// index of the buffer is that of its slot in the collection.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use tokio_uring::buf::fixed::registry;
+    /// use tokio_uring::Buffer;
+    ///
+    /// tokio_uring::start(async {
+    ///     let registry = registry::register(
+    ///         (0..4).map(|_| Buffer::from(Vec::<u8>::with_capacity(4096))),
+    ///     )
+    ///     .unwrap();
+    ///
+    ///     let mut bufs = Vec::new();
+    ///     while let Some(buf) = registry.check_out_any() {
+    ///         bufs.push(buf);
+    ///     }
+    ///     assert_eq!(bufs.len(), 4);
+    /// });
+    /// ```
+    pub fn check_out_any(&self) -> Option<Buffer> {
+        let (index, checkout) = self.inner.lock().unwrap().check_out_any()?;
+        Some(self.buffer(index, checkout))
+    }
+
+    /// Resolves to any free buffer, waiting for one to be checked back in if
+    /// every buffer is in use.
+    ///
+    /// A buffer checked back in goes to the tasks waiting for it by index in
+    /// [`check_out_async`](Self::check_out_async) first, and otherwise to the
+    /// tasks waiting here, in the order they started waiting. As for
+    /// `check_out_async`, this never resolves if no buffer is ever checked
+    /// back in, and fails once the collection is draining or unregistered.
+    pub async fn check_out_any_async(&self) -> Result<Buffer, RegistrationRevoked> {
+        self.check_out_or_wait(None).await
+    }
+
+    // Checks out the indexed buffer, or any for no index, waiting in line for
+    // it if there is none free.
+    async fn check_out_or_wait(&self, index: Option<usize>) -> Result<Buffer, RegistrationRevoked> {
+        // Checking the buffer and queueing up happen under one lock, so a
+        // check-in between them cannot be missed.
+        let id = match self.inner.lock().unwrap().check_out_or_wait(index) {
+            Ok((index, checkout)) => return Ok(self.buffer(index, checkout)),
+            Err(id) => id,
         };
+        let (index, checkout) = Waiting {
+            registry: self,
+            index,
+            id: Some(id),
+        }
+        .await?;
+        Ok(self.buffer(index, checkout))
+    }
 
+    /// Puts `buf` in the slot at `index`, registering it with the kernel in
+    /// place of the buffer the slot held, if any, which is dropped.
+    ///
+    /// See [`replace`](Self::replace).
+    pub fn update(&self, index: usize, buf: Buffer) -> Result<(), crate::Error<Buffer>> {
+        self.replace(index, buf).map(drop)
+    }
+
+    /// Puts `buf` in the slot at `index`, registering it with the kernel in
+    /// place of the buffer the slot held, which is returned.
+    ///
+    /// This fills in the slots of a [sparse](register_sparse) registry, or
+    /// swaps out buffers of any registry for ones of another size. Tasks
+    /// waiting in [`check_out_async`](Self::check_out_async) for the slot get
+    /// the new buffer. It requires Linux 5.13 or later.
+    ///
+    /// This method must be called in the context of a `tokio-uring` runtime.
+    ///
+    /// # Errors
+    ///
+    /// Fails, handing `buf` back, with
+    /// [`ResourceBusy`](io::ErrorKind::ResourceBusy) if the buffer in the slot
+    /// is checked out, including by an operation in flight, and with
+    /// [`InvalidInput`](io::ErrorKind::InvalidInput) if `index` is out of
+    /// range, `buf` is not a single contiguous, writable buffer, or the
+    /// registry is [draining](unregister_graceful). Once the collection was
+    /// unregistered, it fails with [`RegistrationRevoked`].
+    pub fn replace(
+        &self,
+        index: usize,
+        buf: Buffer,
+    ) -> Result<Option<Buffer>, crate::Error<Buffer>> {
+        if buf.segment_count() != 1 {
+            let err = io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "a registered buffer must be a single contiguous buffer",
+            );
+            return Err(crate::Error(err, buf));
+        }
+        if buf.is_read_only() {
+            return Err(crate::Error(plumbing::read_only_error(), buf));
+        }
+        let mut inner = self.inner.lock().unwrap();
+        if let Err(e) = inner.check_replaceable(index) {
+            return Err(crate::Error(e, buf));
+        }
+        let res = CONTEXT.with(|x| {
+            x.handle()
+                .as_ref()
+                .expect("Not in a runtime context")
+                .register_buffers_update(index as u32, &[plumbing::iovec_of(&buf)])
+        });
+        if let Err(e) = res {
+            return Err(crate::Error(e, buf));
+        }
+        Ok(inner.replace(index, buf))
+    }
+
+    /// Sets whether the initialized bytes of a buffer are overwritten with
+    /// zeros as it is checked back in, so that data left by one user of the
+    /// buffer cannot show through a short read of the next. Off by default.
+    ///
+    /// The zeroing cannot be optimized away. A buffer known to hold nothing
+    /// sensitive can skip it with [`check_in_without_zero`].
+    ///
+    /// [`check_in_without_zero`]: crate::buf::fixed::check_in_without_zero
+    pub fn zero_on_check_in(self, zero: bool) -> Self {
+        self.inner.lock().unwrap().set_zero_on_check_in(zero);
+        self
+    }
+
+    /// Faults in every page of the free buffers ahead of their first use, so
+    /// that the first operation on each does not stall on page faults.
+    ///
+    /// Pages are populated with `madvise(MADV_POPULATE_WRITE)`, or on kernels
+    /// without it, by touching a byte of each. Neither changes what the
+    /// buffers hold, nor their initialized lengths: a buffer that was empty
+    /// is still empty when checked out. Buffers checked out are skipped.
+    ///
+    /// This holds the collection's lock until all the buffers are done, so
+    /// call it once registered, before the buffers are in demand.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use tokio_uring::buf::fixed::registry;
+    /// use tokio_uring::Buffer;
+    ///
+    /// tokio_uring::start(async {
+    ///     let registry = registry::register(
+    ///         (0..64).map(|_| Buffer::from(Vec::<u8>::with_capacity(1 << 20))),
+    ///     )?;
+    ///     registry.prefault()?;
+    ///     // ...
+    ///     Ok::<_, std::io::Error>(())
+    /// })
+    /// .unwrap();
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails with the error from `madvise(2)` if the pages cannot be
+    /// populated, for instance for lack of memory.
+    pub fn prefault(&self) -> io::Result<()> {
+        self.inner.lock().unwrap().prefault()
+    }
+
+    /// Sets a hook called each time a task starts waiting in
+    /// [`check_out_async`](Self::check_out_async) or
+    /// [`check_out_any_async`](Self::check_out_any_async), for export to a
+    /// metrics system.
+    ///
+    /// Hooks run with the collection locked, and must not use it.
+    pub fn on_wait_start(self, hook: impl Fn() + Send + Sync + 'static) -> Self {
+        self.inner.lock().unwrap().set_on_wait_start(Box::new(hook));
+        self
+    }
+
+    /// Sets a hook called with the time a task waited for a buffer, once it
+    /// is handed one or gives up. It runs in the task checking the buffer in,
+    /// or in the one giving up, with the collection locked.
+    pub fn on_wait_end(self, hook: impl Fn(Duration) + Send + Sync + 'static) -> Self {
+        self.inner.lock().unwrap().set_on_wait_end(Box::new(hook));
+        self
+    }
+
+    /// Returns the counters of waits for buffers and of buffers checked out.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use tokio_uring::buf::fixed::registry;
+    /// use tokio_uring::Buffer;
+    ///
+    /// tokio_uring::start(async {
+    ///     let registry = registry::register(
+    ///         (0..4).map(|_| Buffer::from(Vec::<u8>::with_capacity(4096))),
+    ///     )
+    ///     .unwrap()
+    ///     .on_wait_end(|waited| eprintln!("waited {:?} for a buffer", waited));
+    ///
+    ///     // ...
+    ///     let stats = registry.stats();
+    ///     println!(
+    ///         "{} waiting, {} waits, peak {} of {} in use",
+    ///         stats.waiters,
+    ///         stats.total_waits,
+    ///         stats.peak_in_use,
+    ///         registry.len(),
+    ///     );
+    /// });
+    /// ```
+    pub fn stats(&self) -> FixedBufStats {
+        self.inner.lock().unwrap().stats()
+    }
+
+    /// Returns the number of buffer slots in the collection, filled or not.
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().iovecs().len()
+    }
+
+    /// Returns `true` if the collection has no buffer slots.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of buffers free to be checked out.
+    pub fn free_count(&self) -> usize {
+        self.inner.lock().unwrap().free_count()
+    }
+
+    /// Returns the number of buffers checked out, by the application or by
+    /// operations in flight.
+    pub fn in_use_count(&self) -> usize {
+        self.inner.lock().unwrap().checked_out_count()
+    }
+
+    /// Returns the total capacity of the registered buffers, in bytes.
+    pub fn total_bytes(&self) -> usize {
+        let inner = self.inner.lock().unwrap();
+        inner.iovecs().iter().map(|iovec| iovec.iov_len).sum()
+    }
+
+    /// Returns the status of each buffer slot, by index, along with the kind
+    /// of pages backing its buffer.
+    ///
+    /// The statuses are taken together under the collection's lock, so they
+    /// are consistent with each other, but may change as soon as this
+    /// returns.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use tokio_uring::buf::fixed::registry::{self, BufStatus};
+    /// use tokio_uring::Buffer;
+    ///
+    /// tokio_uring::start(async {
+    ///     let registry = registry::register(
+    ///         (0..4).map(|_| Buffer::from(Vec::<u8>::with_capacity(4096))),
+    ///     )
+    ///     .unwrap();
+    ///     let _buf = registry.check_out(2).unwrap();
+    ///
+    ///     let busy = registry
+    ///         .snapshot()
+    ///         .iter()
+    ///         .filter(|slot| slot.status() != BufStatus::Free)
+    ///         .count();
+    ///     assert_eq!(busy, 1);
+    /// });
+    /// ```
+    pub fn snapshot(&self) -> Vec<BufSnapshot> {
+        let inner = self.inner.lock().unwrap();
+        inner
+            .statuses()
+            .into_iter()
+            .zip(inner.page_backings())
+            .map(|(status, page_backing)| BufSnapshot {
+                status,
+                page_backing,
+            })
+            .collect()
+    }
+
+    fn buffer(&self, index: usize, (iovec, init_len): (libc::iovec, usize)) -> Buffer {
         let registry_info = RegistryInfo {
             registry: self.inner.clone(),
             index: index as u16,
@@ -70,7 +530,108 @@ impl FixedBufRegistry {
             registry_info: Some(registry_info),
         };
 
-        Some(Buffer::new(buf))
+        Buffer::new(buf)
+    }
+}
+
+/// A buffer slot of a [`FixedBufRegistry`], as reported by
+/// [`FixedBufRegistry::snapshot`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BufSnapshot {
+    status: BufStatus,
+    page_backing: PageBacking,
+}
+
+impl BufSnapshot {
+    /// Returns the status of the slot.
+    pub fn status(&self) -> BufStatus {
+        self.status
+    }
+
+    /// Returns the kind of pages backing the buffer in the slot, regular
+    /// ones for an empty slot.
+    pub fn page_backing(&self) -> PageBacking {
+        self.page_backing
+    }
+}
+
+/// The status of a buffer slot in a [`FixedBufRegistry`], as reported by
+/// [`FixedBufRegistry::snapshot`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BufStatus {
+    /// The slot of a [sparse](register_sparse) registry has not been filled.
+    Empty,
+    /// The buffer is free to be checked out.
+    Free,
+    /// The buffer is checked out and held by the application.
+    CheckedOut,
+    /// The buffer is checked out and held by an I/O operation.
+    InFlight,
+    /// The buffer is checked out through one or more
+    /// [shared](FixedBufRegistry::check_out_shared) handles.
+    Shared,
+}
+
+/// The error for using a [`FixedBufRegistry`] after it was unregistered.
+///
+/// Returned by [`FixedBufRegistry::try_check_out`], and by
+/// [`FixedBufRegistry::check_out_async`] once the collection is draining
+/// for [`unregister_graceful`] too. Fixed operations on
+/// buffers checked out before the collection, or a
+/// [`FixedBufPool`](super::pool::FixedBufPool), was unregistered fail with it
+/// too, wrapped in an [`io::Error`]; check for it with
+/// `err.get_ref().map_or(false, |e| e.is::<RegistrationRevoked>())`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RegistrationRevoked;
+
+impl fmt::Display for RegistrationRevoked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("the fixed buffers were unregistered")
+    }
+}
+
+impl std::error::Error for RegistrationRevoked {}
+
+// A task queued in the registry for a buffer, or for any with no index,
+// dequeued if dropped.
+struct Waiting<'a> {
+    registry: &'a FixedBufRegistry,
+    index: Option<usize>,
+    // `None` once served.
+    id: Option<u64>,
+}
+
+impl Future for Waiting<'_> {
+    type Output = Result<plumbing::IndexedCheckout, RegistrationRevoked>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let id = self.id.expect("polled after completion");
+        let checkout = self
+            .registry
+            .inner
+            .lock()
+            .unwrap()
+            .poll_waiter(self.index, id, cx.waker());
+        match checkout {
+            Ok(None) => Poll::Pending,
+            res => {
+                // Served, or dequeued by the failure.
+                self.id = None;
+                Poll::Ready(res.map(Option::unwrap))
+            }
+        }
+    }
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            self.registry
+                .inner
+                .lock()
+                .unwrap()
+                .cancel_waiter(self.index, id);
+        }
     }
 }
 
@@ -149,31 +710,168 @@ impl FixedBufRegistry {
 ///
 /// If a collection of buffers is currently registered in the context
 /// of the `tokio-uring` runtime this call is made in, the function returns
-/// an error.
+/// an error. It fails with [`InvalidInput`](io::ErrorKind::InvalidInput) if
+/// any of the buffers is [read-only](Buffer::is_read_only).
 pub fn register(bufs: impl Iterator<Item = Buffer>) -> io::Result<FixedBufRegistry> {
-    let registry_inner = plumbing::Registry::new(bufs);
-    CONTEXT.with(|x| {
+    let mut read_only = false;
+    let mut registry_inner =
+        plumbing::Registry::new(bufs.inspect(|buf| read_only |= buf.is_read_only()));
+    if read_only {
+        return Err(plumbing::read_only_error());
+    }
+    let registered = CONTEXT.with(|x| {
         x.handle()
             .as_ref()
             .expect("Not in a runtime context")
             .register_buffers(registry_inner.iovecs())
     })?;
+    registry_inner.set_registered(registered);
+    Ok(FixedBufRegistry::new(registry_inner))
+}
+
+/// Registers a table of `count` empty buffer slots with the kernel, and
+/// creates a collection of buffers for it.
+///
+/// The slots are filled in later with [`FixedBufRegistry::update`] or
+/// [`FixedBufRegistry::replace`], so the runtime can start before the sizes of
+/// the buffers are known. [`check_out`](FixedBufRegistry::check_out) returns
+/// `None` for a slot that has not been filled. Registering a sparse table
+/// requires Linux 5.13 or later.
+///
+/// As with [`register`], this must be called in the context of a `tokio-uring`
+/// runtime, and the registration persists until [`unregister`] is called.
+///
+/// # Examples
+///
+/// ```no_run
+/// use tokio_uring::buf::fixed::registry;
+/// use tokio_uring::Buffer;
+///
+/// tokio_uring::start(async {
+///     let registry = registry::register_sparse(8)?;
+///     registry
+///         .update(0, Buffer::from(Vec::<u8>::with_capacity(16 * 1024)))
+///         .map_err(|e| e.0)?;
+///     let buf = registry.check_out(0).unwrap();
+///     // ...
+///     Ok::<_, std::io::Error>(())
+/// })
+/// # .unwrap();
+/// ```
+///
+/// # Errors
+///
+/// Fails with [`InvalidInput`](io::ErrorKind::InvalidInput) if `count`
+/// exceeds the number of buffers a registry can index, and with the error
+/// from the kernel if a collection of buffers is currently registered or the
+/// kernel cannot register a sparse table.
+pub fn register_sparse(count: usize) -> io::Result<FixedBufRegistry> {
+    if count > u16::MAX as usize {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "too many buffer slots for a registry",
+        ));
+    }
+    let registered = CONTEXT.with(|x| {
+        x.handle()
+            .as_ref()
+            .expect("Not in a runtime context")
+            .register_buffers_sparse(count as u32)
+    })?;
+    let mut registry_inner = plumbing::Registry::sparse(count);
+    registry_inner.set_registered(registered);
     Ok(FixedBufRegistry::new(registry_inner))
 }
 
+/// Registers memory regions owned outside the crate with the kernel, and
+/// creates a collection of buffers over them, without copying them into
+/// vectors of its own.
+///
+/// Each `(ptr, len)` pair describes a region, wrapped in a [`RawRegionBuf`]
+/// whose `len` bytes are the initialized contents of the buffer. Otherwise,
+/// the collection behaves as one from [`register`]: buffers are checked out
+/// by index, and the registration persists until [`unregister`] is called,
+/// or the runtime is dropped. Unlike for `register`, `unregister` fails with
+/// [`ResourceBusy`](io::ErrorKind::ResourceBusy) while any buffer of the
+/// regions is checked out, by the application or by an operation in flight,
+/// so the regions can be released as soon as it succeeds.
+///
+/// # Safety
+///
+/// For each region, for as long as it is registered and any buffer over it
+/// is alive:
+///
+/// - `ptr` must be valid for reads and writes of `len` bytes, and those
+///   bytes must be initialized;
+/// - the memory must not be moved, freed, or unmapped;
+/// - no other code may access it while its buffer is checked out, as the
+///   buffer's holder and the kernel may write to it then; while the buffer
+///   is checked in, other code may read it, but must not write to it
+///   concurrently with operations on another buffer of the same region.
+///
+/// Regions must not overlap.
+///
+/// # Examples
+///
+/// ```no_run
+/// use tokio_uring::buf::fixed::registry;
+/// use tokio_uring::fs::File;
+///
+/// tokio_uring::start(async {
+///     // An arena that lives for the rest of the program.
+///     let arena: &'static mut [u8] = Box::leak(vec![0; 64 * 1024].into_boxed_slice());
+///     let regions = arena.chunks_exact_mut(16 * 1024).map(|c| (c.as_mut_ptr(), c.len()));
+///     // Safety: the arena is never freed, and only reached through the registry.
+///     let registry = unsafe { registry::register_raw(regions)? };
+///
+///     let file = File::open("data.bin").await?;
+///     let buf = registry.check_out(0).unwrap();
+///     let (n, buf) = file.read_fixed_at(buf, 0).await.unwrap();
+///     // ...
+///     Ok::<_, std::io::Error>(())
+/// })
+/// .unwrap();
+/// ```
+///
+/// # Errors
+///
+/// As for [`register`].
+pub unsafe fn register_raw(
+    regions: impl Iterator<Item = (*mut u8, usize)>,
+) -> io::Result<FixedBufRegistry> {
+    // Safety: the caller upholds the contract of `RawRegionBuf::new` for each region.
+    let bufs = regions.map(|(ptr, len)| Buffer::new(unsafe { RawRegionBuf::new(ptr, len) }));
+    let registry = register(bufs)?;
+    let pinned = registry.inner.lock().unwrap().pin();
+    CONTEXT.with(|x| {
+        x.handle()
+            .as_ref()
+            .expect("Not in a runtime context")
+            .pin_fixed_buffers(pinned)
+    });
+    Ok(registry)
+}
+
 /// Unregisters this collection of buffers.
 ///
 /// This method must be called in the context of a `tokio-uring` runtime,
 /// where the buffers should have been previously registered.
 ///
-/// This operation invalidates any `Buffer` handles checked out from
-/// this registry instance. Continued use of such handles in I/O
-/// operations may result in an error.
+/// This operation revokes the registered [`FixedBufRegistry`]: it checks
+/// out no more buffers, and fixed operations on the buffers checked out
+/// from it fail with [`RegistrationRevoked`]. Another collection can be
+/// registered right after.
+///
+/// To wait for the buffers to be checked back in first, see
+/// [`unregister_graceful`].
 ///
 /// # Errors
 ///
 /// Calling `unregister` when no `FixedBufRegistry` is currently
-/// registered on this runtime returns an error. Any of 'Buffer' check-outed from 'FixedBufRegistry' is in used, also returns an error
+/// registered on this runtime returns an error. For a collection from
+/// [`register_raw`], it fails with
+/// [`ResourceBusy`](io::ErrorKind::ResourceBusy) while any of its buffers
+/// is checked out.
 pub fn unregister() -> io::Result<()> {
     CONTEXT.with(|x| {
         x.handle()
@@ -183,6 +881,149 @@ pub fn unregister() -> io::Result<()> {
     })
 }
 
+/// Unregisters `registry` once all of its buffers are checked back in.
+///
+/// The registry starts draining right away: [`check_out`] returns `None`,
+/// [`replace`] fails, and tasks waiting in [`check_out_async`] or
+/// [`check_out_any_async`] are woken and fail with [`RegistrationRevoked`],
+/// as the buffers checked in from then on stay in the registry.
+/// Once the last buffer held by the application or by an operation in flight
+/// is checked in, the collection is unregistered as by [`unregister`].
+///
+/// With a `timeout`, the wait is given up on after that long, and the
+/// collection unregistered all the same. The buffers still checked out then
+/// are counted and returned; like those checked out across [`unregister`],
+/// they no longer work for fixed operations.
+///
+/// This function must be called in the context of a `tokio-uring` runtime.
+///
+/// # Examples
+///
+/// ```no_run
+/// use tokio_uring::buf::fixed::registry;
+/// use tokio_uring::Buffer;
+/// use std::time::Duration;
+///
+/// tokio_uring::start(async {
+///     let registry = registry::register(
+///         (0..4).map(|_| Buffer::from(Vec::<u8>::with_capacity(4096))),
+///     )
+///     .unwrap();
+///     // ...
+///
+///     let stragglers = registry::unregister_graceful(&registry, Some(Duration::from_secs(5)))
+///         .await
+///         .unwrap();
+///     if stragglers > 0 {
+///         eprintln!("{} buffers were still in use", stragglers);
+///     }
+/// });
+/// ```
+///
+/// # Errors
+///
+/// Fails as [`unregister`] does. The registry keeps draining.
+///
+/// [`check_out`]: FixedBufRegistry::check_out
+/// [`check_out_async`]: FixedBufRegistry::check_out_async
+/// [`check_out_any_async`]: FixedBufRegistry::check_out_any_async
+/// [`replace`]: FixedBufRegistry::replace
+pub async fn unregister_graceful(
+    registry: &FixedBufRegistry,
+    timeout: Option<Duration>,
+) -> io::Result<usize> {
+    registry.inner.lock().unwrap().start_draining();
+    let drained = Drained { registry };
+    let stragglers = match timeout {
+        Some(timeout) => match tokio::time::timeout(timeout, drained).await {
+            Ok(()) => 0,
+            Err(_) => registry.in_use_count(),
+        },
+        None => {
+            drained.await;
+            0
+        }
+    };
+    unregister()?;
+    Ok(stragglers)
+}
+
+// Resolves once no buffer of a draining registry is checked out.
+struct Drained<'a> {
+    registry: &'a FixedBufRegistry,
+}
+
+impl Future for Drained<'_> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut inner = self.registry.inner.lock().unwrap();
+        match inner.poll_drained(cx.waker()) {
+            true => Poll::Ready(()),
+            false => Poll::Pending,
+        }
+    }
+}
+
+/// Marks a buffer checked out from a registry as held by an operation, for
+/// as long as the marker lives.
+pub(crate) struct InFlight {
+    registry: Arc<Mutex<plumbing::Registry>>,
+    index: usize,
+}
+
+impl InFlight {
+    /// Marks `buf`, unless it is not a buffer checked out from a registry.
+    pub(crate) fn mark(buf: &Buffer) -> Option<InFlight> {
+        if buf.type_id() != TypeId::of::<FixedBuf>() {
+            return None;
+        }
+        // Safety: the buffer comes from a `FixedBuf`, whose user data is a
+        // `RegistryInfo`.
+        let info = unsafe { &*(buf.user_data() as *const RegistryInfo) };
+        let index = info.index as usize;
+        info.registry.lock().unwrap().set_in_flight(index, true);
+        Some(InFlight {
+            registry: info.registry.clone(),
+            index,
+        })
+    }
+}
+
+/// Fails with [`RegistrationRevoked`] if `buf` was checked out, exclusively
+/// or shared, from a registry, or from a pool, since unregistered, whose
+/// index may name another buffer.
+pub(crate) fn check_registered(buf: &Buffer) -> io::Result<()> {
+    let revoked = if buf.type_id() == TypeId::of::<FixedBuf>() {
+        // Safety: as in `InFlight::mark`.
+        let info = unsafe { &*(buf.user_data() as *const RegistryInfo) };
+        info.registry.lock().unwrap().is_revoked()
+    } else if buf.type_id() == TypeId::of::<SharedFixedBuf>() {
+        // Safety: the user data of a `SharedFixedBuf` is the handle itself.
+        let shared = unsafe { &*(buf.user_data() as *const SharedFixedBuf) };
+        shared.hold.registry.lock().unwrap().is_revoked()
+    } else if buf.type_id() == TypeId::of::<pool::FixedBuf>() {
+        // Safety: the user data of a buffer checked out from a pool is its `PoolInfo`.
+        let info = unsafe { &*(buf.user_data() as *const pool::PoolInfo) };
+        info.is_revoked()
+    } else {
+        return Ok(());
+    };
+    match revoked {
+        true => Err(io::Error::other(RegistrationRevoked)),
+        false => Ok(()),
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.registry
+            .lock()
+            .unwrap()
+            .set_in_flight(self.index, false);
+    }
+}
+
 pub(crate) struct FixedBuf {
     iovec: libc::iovec,
     init_len: usize,
@@ -226,12 +1067,173 @@ unsafe impl BufferImpl for FixedBuf {
     }
 }
 
-impl Drop for FixedBuf {
-    fn drop(&mut self) {
+impl FixedBuf {
+    // Checks the buffer in, zeroing it first if the registry asks for it and
+    // `zero` allows it.
+    pub(super) fn check_in(&mut self, zero: bool) {
         let Some(registry_info) = self.registry_info.take() else {
             return;
         };
         let mut registry = registry_info.registry.lock().unwrap();
+        if zero && registry.zero_on_check_in() {
+            plumbing::zero(&self.iovec, self.init_len);
+        }
         registry.check_in(registry_info.index as usize, self.init_len);
     }
 }
+
+impl Drop for FixedBuf {
+    fn drop(&mut self) {
+        self.check_in(true);
+    }
+}
+
+/// A shared, read-only handle to a buffer of a [`FixedBufRegistry`], from
+/// [`FixedBufRegistry::check_out_shared`].
+///
+/// Cloning the handle only bumps a reference count. It converts into a
+/// [read-only](Buffer::is_read_only) [`Buffer`] of the initialized bytes,
+/// which writes and sends use as a fixed buffer, and whose use in a read
+/// panics. The buffer is checked back in to the registry once the last
+/// handle, and the last `Buffer` made from one, is dropped.
+#[derive(Clone)]
+pub struct SharedFixedBuf {
+    hold: Arc<SharedHold>,
+}
+
+impl SharedFixedBuf {
+    /// Returns the index of the buffer in its registry.
+    pub fn buf_index(&self) -> u16 {
+        self.hold.index
+    }
+}
+
+impl Deref for SharedFixedBuf {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        // Safety: nothing writes to a buffer while it is shared.
+        unsafe { std::slice::from_raw_parts(self.hold.iovec.iov_base as _, self.hold.init_len) }
+    }
+}
+
+impl fmt::Debug for SharedFixedBuf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedFixedBuf")
+            .field("buf_index", &self.hold.index)
+            .field("len", &self.hold.init_len)
+            .finish()
+    }
+}
+
+// A shared hold on a buffer of a registry, released when dropped.
+struct SharedHold {
+    registry: Arc<Mutex<plumbing::Registry>>,
+    index: u16,
+    iovec: libc::iovec,
+    init_len: usize,
+}
+
+// Safety: the iovec points into a registered buffer, which is only read while
+// it is shared.
+unsafe impl Send for SharedHold {}
+unsafe impl Sync for SharedHold {}
+
+impl Drop for SharedHold {
+    fn drop(&mut self) {
+        self.registry
+            .lock()
+            .unwrap()
+            .release_shared(self.index as usize);
+    }
+}
+
+// The handle is kept as the user data, so the buffer stays shared while it is in use.
+unsafe impl BufferImpl for SharedFixedBuf {
+    type UserData = SharedFixedBuf;
+
+    const READ_ONLY: bool = true;
+
+    fn into_raw_parts(self) -> (Vec<*mut u8>, Vec<usize>, Vec<usize>, Self::UserData) {
+        let len = self.hold.init_len;
+        (
+            vec![self.hold.iovec.iov_base as _],
+            vec![len],
+            vec![len],
+            self,
+        )
+    }
+
+    unsafe fn from_raw_parts(
+        _ptr: Vec<*mut u8>,
+        _len: Vec<usize>,
+        _cap: Vec<usize>,
+        user_data: Self::UserData,
+    ) -> Self {
+        user_data
+    }
+}
+
+/// Returns the index of `buf` in its registry, if it is made from a
+/// [`SharedFixedBuf`].
+pub(crate) fn shared_index(buf: &Buffer) -> Option<u16> {
+    if buf.type_id() != TypeId::of::<SharedFixedBuf>() {
+        return None;
+    }
+    // Safety: as in `check_registered`.
+    let shared = unsafe { &*(buf.user_data() as *const SharedFixedBuf) };
+    Some(shared.hold.index)
+}
+
+/// A memory region owned outside the crate, as a buffer to register with
+/// [`register_raw`].
+///
+/// The region is only described, not owned: dropping the buffer leaves the
+/// memory alone.
+pub struct RawRegionBuf {
+    ptr: *mut u8,
+    init_len: usize,
+    cap: usize,
+}
+
+// Safety: the creator of the region vouches for access to it from the
+// buffer's holder alone, on whichever thread.
+unsafe impl Send for RawRegionBuf {}
+unsafe impl Sync for RawRegionBuf {}
+
+impl RawRegionBuf {
+    /// Describes the `len` bytes at `ptr`, all initialized, as a buffer.
+    ///
+    /// # Safety
+    ///
+    /// The region must satisfy the contract of [`register_raw`] for as long
+    /// as the buffer, or any [`Buffer`] made from it, is alive.
+    pub unsafe fn new(ptr: *mut u8, len: usize) -> RawRegionBuf {
+        RawRegionBuf {
+            ptr,
+            init_len: len,
+            cap: len,
+        }
+    }
+}
+
+unsafe impl BufferImpl for RawRegionBuf {
+    type UserData = ();
+
+    fn into_raw_parts(self) -> (Vec<*mut u8>, Vec<usize>, Vec<usize>, Self::UserData) {
+        (vec![self.ptr], vec![self.init_len], vec![self.cap], ())
+    }
+
+    unsafe fn from_raw_parts(
+        ptr: Vec<*mut u8>,
+        len: Vec<usize>,
+        cap: Vec<usize>,
+        _: Self::UserData,
+    ) -> Self {
+        RawRegionBuf {
+            ptr: ptr[0],
+            init_len: len[0],
+            cap: cap[0],
+        }
+    }
+}
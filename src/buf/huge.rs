@@ -0,0 +1,205 @@
+use crate::buf::BufferImpl;
+use crate::Buffer;
+use std::any::TypeId;
+use std::mem::ManuallyDrop;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::{fs, io, ptr};
+
+/// The kind of pages backing the memory of a [`Buffer`], as reported by
+/// [`Buffer::page_backing`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PageBacking {
+    /// Regular pages, as for memory from the heap.
+    Regular,
+    /// Transparent huge pages, requested with `madvise(MADV_HUGEPAGE)`. The kernel backs the
+    /// memory with huge pages where it can, and with regular ones elsewhere.
+    Transparent,
+    /// Huge pages reserved in the hugetlb pool, mapped with `MAP_HUGETLB`.
+    HugeTlb,
+}
+
+impl PageBacking {
+    /// Returns `true` if the memory is backed, or meant to be backed, by huge pages.
+    pub fn is_huge(self) -> bool {
+        self != PageBacking::Regular
+    }
+}
+
+impl Buffer {
+    /// Allocates an empty buffer of at least `capacity` bytes on huge pages, to register as a
+    /// fixed buffer: the kernel pins and accounts for a huge page at once, rather than for
+    /// each of the regular pages it spans.
+    ///
+    /// The memory is mapped with `MAP_HUGETLB`, from the huge pages reserved in the hugetlb
+    /// pool (see `/proc/sys/vm/nr_hugepages`). When none are free, it falls back to a regular
+    /// mapping aligned to the huge page size, with transparent huge pages requested through
+    /// `madvise(2)`, and to regular pages should those be disabled;
+    /// [`page_backing`](Buffer::page_backing) tells which. The capacity is rounded up to a
+    /// whole number of huge pages.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use tokio_uring::buf::fixed::registry;
+    /// use tokio_uring::Buffer;
+    ///
+    /// tokio_uring::start(async {
+    ///     let buf = Buffer::with_capacity_hugetlb(2 << 20)?;
+    ///     println!("huge pages: {}", buf.page_backing().is_huge());
+    ///
+    ///     let registry = registry::register(std::iter::once(buf))?;
+    ///     // ...
+    ///     Ok::<_, std::io::Error>(())
+    /// })
+    /// .unwrap();
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails with the error from `mmap(2)` if no memory could be mapped at all.
+    pub fn with_capacity_hugetlb(capacity: usize) -> io::Result<Buffer> {
+        let huge_page = huge_page_size();
+        let cap = capacity
+            .max(1)
+            .checked_next_multiple_of(huge_page)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "capacity overflows"))?;
+        let pages = match map(cap, libc::MAP_HUGETLB) {
+            Ok(ptr) => HugePages {
+                ptr,
+                len: 0,
+                cap,
+                backing: PageBacking::HugeTlb,
+            },
+            Err(_) => map_transparent(cap, huge_page)?,
+        };
+        Ok(Buffer::new(pages))
+    }
+
+    /// Returns the kind of pages backing the buffer's memory: huge pages for a buffer from
+    /// [`with_capacity_hugetlb`](Buffer::with_capacity_hugetlb), and regular pages for any
+    /// other.
+    pub fn page_backing(&self) -> PageBacking {
+        if self.type_id() != TypeId::of::<HugePages>() {
+            return PageBacking::Regular;
+        }
+        // Safety: the user data of a `HugePages` is its `PageBacking`.
+        unsafe { *(self.user_data() as *const PageBacking) }
+    }
+}
+
+// An anonymous mapping of whole huge pages, unmapped when dropped.
+struct HugePages {
+    ptr: *mut u8,
+    len: usize,
+    cap: usize,
+    backing: PageBacking,
+}
+
+// Safety: the mapping is owned, like the allocation of a `Vec<u8>`.
+unsafe impl Send for HugePages {}
+unsafe impl Sync for HugePages {}
+
+impl Drop for HugePages {
+    fn drop(&mut self) {
+        // Safety: the region was mapped for this value alone.
+        unsafe { libc::munmap(self.ptr.cast(), self.cap) };
+    }
+}
+
+unsafe impl BufferImpl for HugePages {
+    type UserData = PageBacking;
+
+    fn into_raw_parts(self) -> (Vec<*mut u8>, Vec<usize>, Vec<usize>, Self::UserData) {
+        let this = ManuallyDrop::new(self);
+        (vec![this.ptr], vec![this.len], vec![this.cap], this.backing)
+    }
+
+    unsafe fn from_raw_parts(
+        ptr: Vec<*mut u8>,
+        len: Vec<usize>,
+        cap: Vec<usize>,
+        backing: Self::UserData,
+    ) -> Self {
+        HugePages {
+            ptr: ptr[0],
+            len: len[0],
+            cap: cap[0],
+            backing,
+        }
+    }
+}
+
+fn map(len: usize, flags: libc::c_int) -> io::Result<*mut u8> {
+    // Safety: a new anonymous mapping overlaps no memory in use.
+    let ptr = unsafe {
+        libc::mmap(
+            ptr::null_mut(),
+            len,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | flags,
+            -1,
+            0,
+        )
+    };
+    if ptr == libc::MAP_FAILED {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(ptr.cast())
+}
+
+// Maps `cap` bytes at an address aligned to `huge_page`, so that the kernel can back them
+// with transparent huge pages, and asks it to.
+fn map_transparent(cap: usize, huge_page: usize) -> io::Result<HugePages> {
+    // Map a huge page more, and trim the unaligned ends.
+    let len = cap
+        .checked_add(huge_page)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "capacity overflows"))?;
+    let base = map(len, 0)?;
+    let head = base.align_offset(huge_page);
+    let tail = len - head - cap;
+    // Safety: both ends lie within the mapping, and nothing uses them.
+    unsafe {
+        if head > 0 {
+            libc::munmap(base.cast(), head);
+        }
+        if tail > 0 {
+            libc::munmap(base.add(head + cap).cast(), tail);
+        }
+    }
+    let ptr = base.wrapping_add(head);
+    // Safety: the advice only applies to the mapping made above.
+    let advised = unsafe { libc::madvise(ptr.cast(), cap, libc::MADV_HUGEPAGE) } == 0;
+    Ok(HugePages {
+        ptr,
+        len: 0,
+        cap,
+        backing: match advised {
+            true => PageBacking::Transparent,
+            false => PageBacking::Regular,
+        },
+    })
+}
+
+// The default huge page size, read once from `/proc/meminfo`; 2 MiB if it cannot be read.
+fn huge_page_size() -> usize {
+    static SIZE: AtomicUsize = AtomicUsize::new(0);
+
+    match SIZE.load(Ordering::Relaxed) {
+        0 => {
+            let size = fs::read_to_string("/proc/meminfo")
+                .ok()
+                .and_then(|meminfo| {
+                    let line = meminfo
+                        .lines()
+                        .find(|line| line.starts_with("Hugepagesize:"))?;
+                    let kib = line.split_whitespace().nth(1)?.parse::<usize>().ok()?;
+                    Some(kib * 1024)
+                })
+                .filter(|size| size.is_power_of_two())
+                .unwrap_or(2 << 20);
+            SIZE.store(size, Ordering::Relaxed);
+            size
+        }
+        size => size,
+    }
+}
@@ -32,6 +32,15 @@ pub unsafe trait IoBufMut: IoBuf {
     /// The caller must ensure that all bytes starting at `stable_mut_ptr()` up
     /// to `pos` are initialized and owned by the buffer.
     unsafe fn set_init(&mut self, pos: usize);
+
+    /// Returns `true` if the buffer's memory must not be written to. Reads into such a
+    /// buffer fail with [`std::io::ErrorKind::InvalidInput`], without calling
+    /// `stable_mut_ptr`.
+    ///
+    /// The default implementation returns `false`.
+    fn is_read_only(&self) -> bool {
+        false
+    }
 }
 
 unsafe impl IoBufMut for Vec<u8> {
@@ -0,0 +1,202 @@
+use crate::buf::BufferImpl;
+use std::ops::{Deref, DerefMut};
+use std::os::unix::io::AsRawFd;
+use std::sync::Arc;
+use std::{fmt, io, ptr};
+
+/// A read-only memory mapping of a file region, for writing its contents without copying
+/// them first.
+///
+/// Cloning shares the mapping, so the same region can be written by many operations at once.
+/// It is unmapped once the last clone is dropped; a [`Buffer`](crate::Buffer) made from a clone
+/// holds it for as long as an operation uses it. Such a buffer is
+/// [read-only](crate::Buffer::is_read_only), and cannot be registered as a fixed buffer.
+///
+/// Write part of the mapping with a [view](crate::Buffer::view) of the buffer.
+///
+/// # Examples
+///
+/// ```no_run
+/// use tokio_uring::buf::MmapBuf;
+/// use tokio_uring::fs::File;
+/// use tokio_uring::{Buffer, Submit};
+///
+/// tokio_uring::start(async {
+///     let src = std::fs::File::open("large.bin").unwrap();
+///     let map = MmapBuf::map(&src, 0, 1 << 20).unwrap();
+///
+///     let dst = File::create("copy.bin").await.unwrap();
+///     let (n, _) = dst.write_at(Buffer::from(map).view(4096..), 0).submit().await.unwrap();
+///     println!("wrote {} bytes", n);
+/// });
+/// ```
+#[derive(Clone)]
+pub struct MmapBuf {
+    map: Arc<Mapping>,
+}
+
+impl MmapBuf {
+    /// Maps `len` bytes of `file`, from `offset` on, for reading. The file must be open for
+    /// reading.
+    ///
+    /// # Errors
+    ///
+    /// Fails with the error from `mmap(2)`, for instance if `len` is zero or `offset` is not a
+    /// multiple of the page size.
+    pub fn map(file: &impl AsRawFd, offset: u64, len: usize) -> io::Result<MmapBuf> {
+        let map = Mapping::new(file, offset, len, libc::PROT_READ)?;
+        Ok(MmapBuf { map: Arc::new(map) })
+    }
+}
+
+impl Deref for MmapBuf {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.map.as_slice()
+    }
+}
+
+impl fmt::Debug for MmapBuf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MmapBuf")
+            .field("len", &self.map.len)
+            .finish()
+    }
+}
+
+unsafe impl BufferImpl for MmapBuf {
+    type UserData = Arc<Mapping>;
+
+    const READ_ONLY: bool = true;
+
+    fn into_raw_parts(self) -> (Vec<*mut u8>, Vec<usize>, Vec<usize>, Self::UserData) {
+        let len = self.map.len;
+        (vec![self.map.ptr], vec![len], vec![len], self.map)
+    }
+
+    unsafe fn from_raw_parts(
+        _ptr: Vec<*mut u8>,
+        _len: Vec<usize>,
+        _cap: Vec<usize>,
+        map: Self::UserData,
+    ) -> Self {
+        MmapBuf { map }
+    }
+}
+
+/// A writable, shared memory mapping of a file region: writes to it, including reads into it
+/// by operations, change the file.
+///
+/// Every byte of the mapping counts as initialized, and a read fills it from its start. It is
+/// unmapped once dropped, which a [`Buffer`](crate::Buffer) made from it delays for as long
+/// as an operation uses it. It can be registered as a fixed buffer, though the kernel only
+/// allows that for mappings of memory-backed files, such as those of a `tmpfs` or a `memfd`.
+pub struct MmapBufMut {
+    map: Mapping,
+}
+
+impl MmapBufMut {
+    /// Maps `len` bytes of `file`, from `offset` on, for reading and writing. The file must
+    /// be open for both.
+    ///
+    /// # Errors
+    ///
+    /// Fails as [`MmapBuf::map`] does.
+    pub fn map(file: &impl AsRawFd, offset: u64, len: usize) -> io::Result<MmapBufMut> {
+        let map = Mapping::new(file, offset, len, libc::PROT_READ | libc::PROT_WRITE)?;
+        Ok(MmapBufMut { map })
+    }
+}
+
+impl Deref for MmapBufMut {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.map.as_slice()
+    }
+}
+
+impl DerefMut for MmapBufMut {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        // Safety: the mapping is writable, and owned by this value alone.
+        unsafe { std::slice::from_raw_parts_mut(self.map.ptr, self.map.len) }
+    }
+}
+
+impl fmt::Debug for MmapBufMut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MmapBufMut")
+            .field("len", &self.map.len)
+            .finish()
+    }
+}
+
+unsafe impl BufferImpl for MmapBufMut {
+    type UserData = Mapping;
+
+    fn into_raw_parts(self) -> (Vec<*mut u8>, Vec<usize>, Vec<usize>, Self::UserData) {
+        let len = self.map.len;
+        (vec![self.map.ptr], vec![len], vec![len], self.map)
+    }
+
+    unsafe fn from_raw_parts(
+        _ptr: Vec<*mut u8>,
+        _len: Vec<usize>,
+        _cap: Vec<usize>,
+        map: Self::UserData,
+    ) -> Self {
+        MmapBufMut { map }
+    }
+}
+
+/// A region mapped with `mmap(2)`, unmapped when dropped.
+#[doc(hidden)]
+pub struct Mapping {
+    pub(super) ptr: *mut u8,
+    pub(super) len: usize,
+}
+
+// Safety: the mapping is plain memory, only written through `MmapBufMut`, which owns it.
+unsafe impl Send for Mapping {}
+unsafe impl Sync for Mapping {}
+
+impl Mapping {
+    pub(super) fn new(
+        file: &impl AsRawFd,
+        offset: u64,
+        len: usize,
+        prot: libc::c_int,
+    ) -> io::Result<Mapping> {
+        // Safety: a new shared mapping overlaps no memory in use.
+        let ptr = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                len,
+                prot,
+                libc::MAP_SHARED,
+                file.as_raw_fd(),
+                offset as libc::off_t,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Mapping {
+            ptr: ptr.cast(),
+            len,
+        })
+    }
+
+    pub(super) fn as_slice(&self) -> &[u8] {
+        // Safety: the mapping is readable for its whole length.
+        unsafe { std::slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        // Safety: the region was mapped in `new`, and nothing refers to it any more.
+        unsafe { libc::munmap(self.ptr.cast(), self.len) };
+    }
+}
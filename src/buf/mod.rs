@@ -3,15 +3,20 @@
 //! `io-uring` APIs require passing ownership of buffers to the runtime. The
 //! crate defines [`IoBuf`] and [`IoBufMut`] traits which are implemented by buffer
 //! types that respect the `io-uring` contract.
+pub mod bufring;
+
 pub mod fixed;
 
+pub mod pool;
+
 mod io_buf;
 use std::any::{Any, TypeId};
 use std::fmt::Debug;
 use std::{
+    io,
     iter::zip,
     mem::ManuallyDrop,
-    ops::{Index, IndexMut},
+    ops::{self, Index, IndexMut},
 };
 
 pub use io_buf::IoBuf;
@@ -25,12 +30,46 @@ pub use slice::Slice;
 mod bounded;
 pub use bounded::{BoundedBuf, BoundedBufMut};
 
+mod aligned;
+pub use aligned::AlignedBuf;
+
+mod huge;
+pub use huge::PageBacking;
+
+#[cfg(feature = "bytes")]
+mod cursor;
+#[cfg(feature = "bytes")]
+pub use cursor::{BufferReader, BufferWriter};
+
+#[cfg(feature = "mmap")]
+mod mmap;
+#[cfg(feature = "mmap")]
+pub use mmap::{MmapBuf, MmapBufMut};
+
+#[cfg(feature = "dmabuf")]
+mod dmabuf;
+#[cfg(feature = "dmabuf")]
+pub use dmabuf::DmaBuf;
+
+mod chain;
+
+mod shared;
+pub use shared::SharedBuf;
+
+mod string;
+
 pub(crate) fn deref(buf: &impl IoBuf) -> &[u8] {
     // Safety: the `IoBuf` trait is marked as unsafe and is expected to be
     // implemented correctly.
     unsafe { std::slice::from_raw_parts(buf.stable_ptr(), buf.bytes_init()) }
 }
 
+// The error for reading into a read-only buffer, checked before the read would write
+// through it.
+pub(crate) fn read_only_error() -> io::Error {
+    io::Error::from_raw_os_error(libc::EINVAL)
+}
+
 pub(crate) fn deref_mut(buf: &mut impl IoBufMut) -> &mut [u8] {
     // Safety: the `IoBufMut` trait is marked as unsafe and is expected to be
     // implemented correct.
@@ -48,6 +87,18 @@ pub(crate) fn deref_mut(buf: &mut impl IoBufMut) -> &mut [u8] {
 pub unsafe trait BufferImpl: Any {
     type UserData: Send + Sync + 'static;
 
+    /// Whether the memory must not be written to, as when other owners share it. Operations
+    /// reading into a [`Buffer`] made from such a type fail with `InvalidInput`.
+    const READ_ONLY: bool = false;
+
+    /// The alignment the type guarantees for the start of each segment of its memory, such
+    /// as the one an [`AlignedBuf`] was allocated with, to be reported by
+    /// [`Buffer::alignment`] whatever the addresses. The default of 1 guarantees nothing, and
+    /// leaves the buffer to tell the alignment from the addresses alone.
+    fn alignment(&self) -> usize {
+        1
+    }
+
     fn into_raw_parts(self) -> (Vec<*mut u8>, Vec<usize>, Vec<usize>, Self::UserData);
 
     /// # Safety
@@ -66,6 +117,11 @@ pub struct Buffer {
     cap: Vec<usize>,
     user_data: *mut (),
     ty: TypeId,
+    read_only: bool,
+    // The alignment the `BufferImpl` declared.
+    align: usize,
+    // The whole buffer, while `iovec` and `cap` describe a view of it.
+    window: Option<Box<Window>>,
     // SAFETY: Buffer cannot be used after execute `dtor`
     #[allow(clippy::type_complexity)]
     dtor: Option<Box<dyn FnOnce(Vec<*mut u8>, Vec<usize>, Vec<usize>, *mut ())>>,
@@ -74,13 +130,27 @@ pub struct Buffer {
 unsafe impl Send for Buffer {}
 unsafe impl Sync for Buffer {}
 
+// Lists the segments as `len/cap`, leaving their contents out.
 impl Debug for Buffer {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        struct Segments<'a>(&'a Buffer);
+
+        impl Debug for Segments<'_> {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                let mut list = f.debug_list();
+                for (iovec, cap) in zip(&self.0.iovec, &self.0.cap) {
+                    list.entry(&format_args!("{}/{}", iovec.iov_len, cap));
+                }
+                list.finish()
+            }
+        }
+
         f.debug_struct("Buffer")
-            .field("iovec", &self.iovec)
-            .field("cap", &self.cap)
-            .field("user", &self.user_data)
-            .field("ty", &self.ty)
+            .field("len", &self.len())
+            .field("capacity", &self.capacity())
+            .field("segments", &Segments(self))
+            .field("read_only", &self.read_only)
+            .field("view", &self.window.is_some())
             .finish()
     }
 }
@@ -95,7 +165,12 @@ impl Buffer {
     #[allow(missing_docs)]
     pub fn new<B: BufferImpl>(buf: B) -> Self {
         let ty = buf.type_id();
+        let align = buf.alignment();
         let (ptr, len, cap, user_data) = buf.into_raw_parts();
+        debug_assert!(
+            ptr.iter().all(|&ptr| (ptr as usize).is_multiple_of(align)),
+            "memory is not aligned as declared"
+        );
         let iovec = ptr
             .into_iter()
             .zip(len)
@@ -110,6 +185,9 @@ impl Buffer {
             cap,
             user_data,
             ty,
+            read_only: B::READ_ONLY,
+            align,
+            window: None,
             dtor: Some(Box::new(|ptr, len, cap, user_data| unsafe {
                 let user_data = Box::from_raw(user_data as *mut B::UserData);
                 drop(B::from_raw_parts(ptr, len, cap, *user_data));
@@ -117,7 +195,32 @@ impl Buffer {
         }
     }
 
-    #[allow(missing_docs)]
+    /// Gives back the value the buffer was created from, such as a `Vec<u8>` whose length is
+    /// now the number of bytes a read initialized, or a `Vec<Vec<u8>>` with one such `Vec` per
+    /// segment. The value's own allocation is handed back, without copying.
+    ///
+    /// A [view](Buffer::view) gives back the whole value it was taken of.
+    ///
+    /// # Errors
+    ///
+    /// Hands the buffer back if it was created from a type other than `B`. Buffers checked out
+    /// from a fixed buffer collection belong to the collection, and are never converted.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use tokio_uring::fs::File;
+    /// use tokio_uring::{Buffer, Submit};
+    ///
+    /// tokio_uring::start(async {
+    ///     let file = File::open("hello.txt").await.unwrap();
+    ///     let buf = Buffer::from(Vec::<u8>::with_capacity(4096));
+    ///
+    ///     let (n, buf) = file.read_at(buf, 0).submit().await.unwrap();
+    ///     let vec = buf.try_into::<Vec<u8>>().unwrap();
+    ///     assert_eq!(vec.len(), n);
+    /// });
+    /// ```
     pub fn try_into<B: BufferImpl>(self) -> Result<B, Self> {
         // Convert only if the type id of source is equal to the type id of target
         if self.ty != TypeId::of::<B>() {
@@ -125,11 +228,13 @@ impl Buffer {
         }
 
         unsafe {
-            let this = ManuallyDrop::new(self);
-            let cap = std::ptr::read(&this.cap);
+            let mut this = ManuallyDrop::new(self.into_inner());
+            // The destructor is not run: the parts are handed over instead.
+            drop(this.dtor.take());
+            let iovec = std::mem::take(&mut this.iovec);
+            let cap = std::mem::take(&mut this.cap);
             let user_data = Box::from_raw(this.user_data as *mut B::UserData);
-            let (ptrs, len) = this
-                .iovec
+            let (ptrs, len) = iovec
                 .iter()
                 .map(|iovec| (iovec.iov_base as *mut u8, iovec.iov_len))
                 .collect();
@@ -141,18 +246,113 @@ impl Buffer {
 }
 
 impl Buffer {
-    #[allow(missing_docs)]
-    pub fn len(&self) -> usize {
+    /// Returns the number of segments of the buffer, which [indexing](Index) picks from.
+    pub fn segment_count(&self) -> usize {
         self.iovec.len()
     }
 
-    #[allow(missing_docs)]
+    /// Returns the number of initialized bytes, across all segments.
+    pub fn len(&self) -> usize {
+        self.iovec.iter().map(|iovec| iovec.iov_len).sum()
+    }
+
+    /// Returns `true` if no segment holds initialized bytes.
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
 
+    /// Returns the capacity of the buffer, across all segments.
+    pub fn capacity(&self) -> usize {
+        self.cap.iter().sum()
+    }
+
+    /// Returns an iterator over the initialized bytes of each segment, in order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tokio_uring::Buffer;
+    ///
+    /// let buf = Buffer::from(vec![b"hello".to_vec(), Vec::new(), b" world".to_vec()]);
+    /// let text: Vec<u8> = buf.iter_segments().flatten().copied().collect();
+    /// assert_eq!(text, b"hello world");
+    /// assert_eq!(buf.iter_segments().filter(|seg| seg.is_empty()).count(), 1);
+    /// ```
+    pub fn iter_segments(&self) -> impl Iterator<Item = &[u8]> + '_ {
+        self.iovec.iter().map(|iovec| {
+            // Safety: the segment holds `iov_len` initialized bytes.
+            unsafe { std::slice::from_raw_parts(iovec.iov_base as *const u8, iovec.iov_len) }
+        })
+    }
+
+    /// Returns an iterator over the initialized bytes of each segment, in order, for
+    /// modifying them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffer is [read-only](Buffer::is_read_only).
+    pub fn iter_segments_mut(&mut self) -> impl Iterator<Item = &mut [u8]> + '_ {
+        self.assert_writable();
+        self.iovec.iter_mut().map(|iovec| {
+            // Safety: the segment holds `iov_len` initialized bytes, and each is borrowed once.
+            unsafe { std::slice::from_raw_parts_mut(iovec.iov_base as *mut u8, iovec.iov_len) }
+        })
+    }
+
+    /// Returns the alignment of the buffer's memory: the largest power of two that the start
+    /// address of every segment is a multiple of, as direct I/O (`O_DIRECT`) requires of
+    /// buffers.
+    ///
+    /// This is the smallest of the segments' alignments, each taken from its address, or
+    /// the alignment the buffer's type [declares](BufferImpl::alignment) if larger. A
+    /// [view](Buffer::view) starting within a segment is only as aligned as its address. A
+    /// buffer of no segments reports the largest power of two.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tokio_uring::buf::AlignedBuf;
+    /// use tokio_uring::Buffer;
+    ///
+    /// let buf = Buffer::from(AlignedBuf::new(8192, 4096));
+    /// assert!(buf.alignment() >= 4096);
+    /// assert_eq!(buf.view(512..).alignment(), 512);
+    /// ```
+    pub fn alignment(&self) -> usize {
+        let declared = match self.window {
+            None => self.align,
+            Some(_) => 1,
+        };
+        self.iovec
+            .iter()
+            .map(|iovec| alignment_of(iovec.iov_base as usize).max(declared))
+            .min()
+            .unwrap_or(MAX_ALIGNMENT)
+    }
+
+    /// Returns `true` if the buffer's memory must not be written to, as for a [`SharedBuf`].
+    /// Reading into it fails with [`io::ErrorKind::InvalidInput`], and indexing it mutably
+    /// panics.
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    fn assert_writable(&self) {
+        assert!(!self.read_only, "cannot write into a read-only buffer");
+    }
+
+    fn assert_segment(&self, index: usize) {
+        assert!(
+            index < self.iovec.len(),
+            "segment index {} out of range for a buffer of {} segments",
+            index,
+            self.iovec.len()
+        );
+    }
+
     #[allow(missing_docs)]
     pub fn fill(&mut self) {
+        self.assert_writable();
         for (iovec, cap) in zip(&mut self.iovec, &self.cap) {
             iovec.iov_len = *cap;
         }
@@ -163,6 +363,325 @@ impl Buffer {
         self.iovec.iter()
     }
 
+    /// Locates the initialized byte at index `n`, counting across segments.
+    ///
+    /// Returns the index of the segment holding it and the byte's offset within that segment,
+    /// or `None` if the buffer has no more than `n` initialized bytes. After a partial vectored
+    /// write of `n` bytes, this is where the unsent data starts.
+    pub fn position(&self, mut n: usize) -> Option<(usize, usize)> {
+        for (i, iovec) in self.iovec.iter().enumerate() {
+            if n < iovec.iov_len {
+                return Some((i, n));
+            }
+            n -= iovec.iov_len;
+        }
+        None
+    }
+
+    /// Copies `src` into the capacity of the buffer from byte `offset` on, counting across
+    /// segments, and extends the initialized bytes of each segment it reaches to cover the
+    /// copy.
+    ///
+    /// The copy may overwrite initialized bytes, but must not leave uninitialized ones before
+    /// it: `offset` lies within the initialized bytes of its segment, or right after them.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`InvalidInput`](io::ErrorKind::InvalidInput), copying nothing, if the copy
+    /// would run past the capacity of the buffer, or start past the initialized bytes of its
+    /// segment. The buffer is never grown, so that a registered buffer stays valid.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffer is [read-only](Buffer::is_read_only).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tokio_uring::Buffer;
+    ///
+    /// let mut buf = Buffer::from(vec![Vec::with_capacity(4), Vec::with_capacity(4)]);
+    /// buf.copy_from_slice(0, b"hello").unwrap();
+    /// buf.copy_from_slice(1, b"ipp").unwrap();
+    /// assert_eq!(&buf[0], b"hipp");
+    /// assert_eq!(&buf[1], b"o");
+    /// assert!(buf.copy_from_slice(6, b"!").is_err());
+    /// ```
+    pub fn copy_from_slice(&mut self, offset: usize, src: &[u8]) -> io::Result<()> {
+        self.assert_writable();
+        let total = self.cap.iter().sum::<usize>();
+        if offset.checked_add(src.len()).is_none_or(|end| end > total) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "not enough capacity in the buffer",
+            ));
+        }
+        if src.is_empty() {
+            return Ok(());
+        }
+        let (mut seg, mut start) = (0, offset);
+        while start >= self.cap[seg] {
+            start -= self.cap[seg];
+            seg += 1;
+        }
+        if start > self.iovec[seg].iov_len {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "copy would leave uninitialized bytes in the buffer",
+            ));
+        }
+        let mut src = src;
+        while !src.is_empty() {
+            let n = src.len().min(self.cap[seg] - start);
+            let iovec = &mut self.iovec[seg];
+            // Safety: the segment has room for `n` bytes from `start` on.
+            unsafe {
+                std::ptr::copy_nonoverlapping(
+                    src.as_ptr(),
+                    iovec.iov_base.cast::<u8>().add(start),
+                    n,
+                )
+            };
+            iovec.iov_len = iovec.iov_len.max(start + n);
+            src = &src[n..];
+            seg += 1;
+            start = 0;
+        }
+        Ok(())
+    }
+
+    /// Appends `src` to the initialized bytes of the buffer, going on from the last segment
+    /// holding any into the segments after it.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`InvalidInput`](io::ErrorKind::InvalidInput), copying nothing, if `src` does
+    /// not fit in the capacity left. The buffer is never grown, so that a registered buffer
+    /// stays valid.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffer is [read-only](Buffer::is_read_only).
+    pub fn extend_from_slice(&mut self, src: &[u8]) -> io::Result<()> {
+        let end = match self.iovec.iter().rposition(|iovec| iovec.iov_len > 0) {
+            Some(seg) => self.cap[..seg].iter().sum::<usize>() + self.iovec[seg].iov_len,
+            None => 0,
+        };
+        self.copy_from_slice(end, src)
+    }
+
+    /// Marks every byte of the buffer as uninitialized, keeping its capacity, so that it can
+    /// be reused for another read.
+    ///
+    /// The memory is left as it is, and so is the registration of a fixed buffer: only the
+    /// length changes, which goes back to the registry with the buffer.
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+
+    /// Shortens the initialized bytes to the first `n`, counting across segments, or does
+    /// nothing if the buffer holds no more than `n`.
+    ///
+    /// The segment holding byte `n` keeps the bytes before it, and the segments after it are
+    /// left with none initialized. The capacity is unchanged. Truncating a
+    /// [view](Buffer::view) leaves the bytes of the whole buffer initialized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tokio_uring::Buffer;
+    ///
+    /// let mut buf = Buffer::from(vec![b"hello".to_vec(), b" world".to_vec()]);
+    /// buf.truncate(3);
+    /// assert_eq!(&buf[0], b"hel");
+    /// assert!(buf[1].is_empty());
+    /// assert_eq!(buf.capacity(), 11);
+    /// ```
+    pub fn truncate(&mut self, mut n: usize) {
+        for iovec in &mut self.iovec {
+            iovec.iov_len = iovec.iov_len.min(n);
+            n -= iovec.iov_len;
+        }
+    }
+
+    /// Sets the number of initialized bytes to `n`, counting across segments: the segments
+    /// are initialized up to their capacity in order until `n` bytes are covered, and those
+    /// after are left with none.
+    ///
+    /// # Safety
+    ///
+    /// The first `n` bytes of the capacity, with the segments laid end to end, must be
+    /// initialized, as after filling the memory outside of the buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` exceeds the capacity of the buffer.
+    pub unsafe fn set_init(&mut self, mut n: usize) {
+        assert!(
+            n <= self.capacity(),
+            "{} initialized bytes out of range for a buffer of {}",
+            n,
+            self.capacity()
+        );
+        for (iovec, cap) in zip(&mut self.iovec, &self.cap) {
+            let size = std::cmp::min(*cap, n);
+            iovec.iov_len = size;
+            n -= size;
+        }
+    }
+
+    /// Returns iovecs covering the initialized bytes from index `n` on, counting across
+    /// segments.
+    pub(crate) fn init_iovecs_from(&self, n: usize) -> Vec<libc::iovec> {
+        iovecs_from(
+            self.iovec
+                .iter()
+                .map(|iovec| (iovec.iov_base, iovec.iov_len)),
+            n,
+        )
+    }
+
+    /// Returns iovecs covering the capacity from byte `n` on, counting across segments.
+    pub(crate) fn spare_iovecs_from(&self, n: usize) -> Vec<libc::iovec> {
+        self.assert_writable();
+        iovecs_from(
+            zip(&self.iovec, &self.cap).map(|(iovec, cap)| (iovec.iov_base, *cap)),
+            n,
+        )
+    }
+
+    /// Returns a view of the bytes in `range` of the buffer, counting across segments, for
+    /// use with any operation taking a `Buffer`.
+    ///
+    /// The range is over the capacity of the segments laid end to end. The view has a segment
+    /// for each one the range overlaps, cut to the range: a write sends the initialized bytes
+    /// within it, and a read fills it from its start. Get the whole buffer back with
+    /// [`into_inner`](Buffer::into_inner). A view of a view is a narrower view of the same
+    /// buffer, with `range` relative to the outer view.
+    ///
+    /// Unlike [`BoundedBuf::slice`], which gives a contiguous [`Slice`] of a single-segment
+    /// buffer for the fixed buffer operations, this works on buffers of any number of
+    /// segments.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range ends past the capacity of the buffer, or starts past its end.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use tokio_uring::fs::File;
+    /// use tokio_uring::{Buffer, Submit};
+    ///
+    /// tokio_uring::start(async {
+    ///     let file = File::create("hello.txt").await.unwrap();
+    ///     let buf = Buffer::from(vec![b"hello".to_vec(), b" wor".to_vec(), b"ld".to_vec()]);
+    ///
+    ///     // Writes "llo wo", from the first two segments.
+    ///     let (n, view) = file.write_at(buf.view(2..8), 0).submit().await.unwrap();
+    ///     assert_eq!(n, 6);
+    ///     let buf = view.into_inner();
+    ///     assert_eq!(buf.segment_count(), 3);
+    /// });
+    /// ```
+    pub fn view(mut self, range: impl ops::RangeBounds<usize>) -> Buffer {
+        let total = self.cap.iter().sum::<usize>();
+        let start = match range.start_bound() {
+            ops::Bound::Included(&n) => n,
+            ops::Bound::Excluded(&n) => n.checked_add(1).expect("out of range"),
+            ops::Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            ops::Bound::Included(&n) => n.checked_add(1).expect("out of range"),
+            ops::Bound::Excluded(&n) => n,
+            ops::Bound::Unbounded => total,
+        };
+        assert!(
+            end <= total,
+            "range end {} out of range for buffer of {}",
+            end,
+            total
+        );
+        assert!(
+            start <= end,
+            "range starts at {} but ends at {}",
+            start,
+            end
+        );
+
+        let mut window = self.window.take().unwrap_or_else(|| {
+            Box::new(Window {
+                iovec: self.iovec.clone(),
+                cap: self.cap.clone(),
+                segments: (0..self.iovec.len()).map(|i| (i, 0)).collect(),
+            })
+        });
+
+        let mut iovec = Vec::new();
+        let mut cap = Vec::new();
+        let mut segments = Vec::new();
+        let mut seg_start = 0;
+        for (i, (seg, &seg_cap)) in zip(&self.iovec, &self.cap).enumerate() {
+            let from = start.max(seg_start);
+            let to = end.min(seg_start + seg_cap);
+            if from < to {
+                let offset = from - seg_start;
+                iovec.push(libc::iovec {
+                    // Safety: `offset` is within the segment's capacity.
+                    iov_base: unsafe { seg.iov_base.cast::<u8>().add(offset) }.cast(),
+                    iov_len: seg.iov_len.saturating_sub(offset).min(to - from),
+                });
+                cap.push(to - from);
+                let (whole, whole_offset) = window.segments[i];
+                segments.push((whole, whole_offset + offset));
+            }
+            seg_start += seg_cap;
+        }
+        window.segments = segments;
+
+        // With its destructor moved out, dropping `self` does nothing.
+        Buffer {
+            iovec,
+            cap,
+            user_data: self.user_data,
+            ty: self.ty,
+            read_only: self.read_only,
+            align: self.align,
+            window: Some(window),
+            dtor: self.dtor.take(),
+        }
+    }
+
+    /// Returns the whole buffer a [view](Buffer::view) was taken of, or the buffer itself if
+    /// it is not a view.
+    ///
+    /// Bytes a read initialized through the view count as initialized in the whole buffer,
+    /// as long as they follow on from the bytes of their segment that already were.
+    pub fn into_inner(mut self) -> Buffer {
+        self.restore();
+        self
+    }
+
+    // Puts back the segments of the whole buffer, if this is a view.
+    fn restore(&mut self) {
+        let Some(window) = self.window.take() else {
+            return;
+        };
+        let Window {
+            mut iovec,
+            cap,
+            segments,
+        } = *window;
+        for (view, &(whole, offset)) in self.iovec.iter().zip(&segments) {
+            let seg = &mut iovec[whole];
+            if offset <= seg.iov_len {
+                seg.iov_len = seg.iov_len.max(offset + view.iov_len);
+            }
+        }
+        self.iovec = iovec;
+        self.cap = cap;
+    }
+
     pub(crate) fn user_data(&self) -> *mut () {
         self.user_data
     }
@@ -172,9 +691,50 @@ impl Buffer {
     }
 }
 
+// The largest power of two a `usize` holds.
+const MAX_ALIGNMENT: usize = 1 << (usize::BITS - 1);
+
+// The largest power of two that `addr` is a multiple of.
+pub(crate) fn alignment_of(addr: usize) -> usize {
+    match addr {
+        0 => MAX_ALIGNMENT,
+        addr => addr & addr.wrapping_neg(),
+    }
+}
+
+// Skips the first `n` bytes of the regions, which are given as base and length.
+fn iovecs_from(
+    regions: impl Iterator<Item = (*mut libc::c_void, usize)>,
+    mut n: usize,
+) -> Vec<libc::iovec> {
+    regions
+        .filter_map(|(base, len)| {
+            let skip = n.min(len);
+            n -= skip;
+            (skip < len).then(|| libc::iovec {
+                // Safety: `skip` is within the region.
+                iov_base: unsafe { base.cast::<u8>().add(skip) }.cast(),
+                iov_len: len - skip,
+            })
+        })
+        .collect()
+}
+
+// The segments of a buffer a view was taken of.
+struct Window {
+    iovec: Vec<libc::iovec>,
+    cap: Vec<usize>,
+    // For each segment of the view, the whole buffer's segment it lies in and its offset there.
+    segments: Vec<(usize, usize)>,
+}
+
 impl Drop for Buffer {
     fn drop(&mut self) {
-        let dtor = self.dtor.take().unwrap();
+        // The destructor takes the segments as the buffer was created.
+        self.restore();
+        let Some(dtor) = self.dtor.take() else {
+            return;
+        };
         let (ptr, len) = self
             .iovec
             .iter()
@@ -190,6 +750,7 @@ impl Index<usize> for Buffer {
     type Output = [u8];
 
     fn index(&self, index: usize) -> &Self::Output {
+        self.assert_segment(index);
         let iovec = &self.iovec[index];
         unsafe { std::slice::from_raw_parts(iovec.iov_base as *const u8, iovec.iov_len) }
     }
@@ -197,6 +758,8 @@ impl Index<usize> for Buffer {
 
 impl IndexMut<usize> for Buffer {
     fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        self.assert_writable();
+        self.assert_segment(index);
         let iovec = &mut self.iovec[index];
         unsafe { std::slice::from_raw_parts_mut(iovec.iov_base as *mut u8, iovec.iov_len) }
     }
@@ -212,16 +775,17 @@ unsafe impl IoBuf for Buffer {
     }
 
     fn bytes_init(&self) -> usize {
-        self.iovec.iter().map(|iovec| iovec.iov_len).sum()
+        self.len()
     }
 
     fn bytes_total(&self) -> usize {
-        self.cap.iter().sum()
+        self.capacity()
     }
 }
 
 unsafe impl IoBufMut for Buffer {
     fn stable_mut_ptr(&mut self) -> *mut u8 {
+        self.assert_writable();
         if self.iovec.len() == 1 {
             self.iovec[0].iov_base as *mut u8
         } else {
@@ -229,12 +793,12 @@ unsafe impl IoBufMut for Buffer {
         }
     }
 
-    unsafe fn set_init(&mut self, mut pos: usize) {
-        for (iovec, cap) in zip(&mut self.iovec, &self.cap) {
-            let size = std::cmp::min(*cap, pos);
-            iovec.iov_len = size;
-            pos -= size;
-        }
+    unsafe fn set_init(&mut self, pos: usize) {
+        Buffer::set_init(self, pos)
+    }
+
+    fn is_read_only(&self) -> bool {
+        self.read_only
     }
 }
 
@@ -288,3 +852,76 @@ unsafe impl BufferImpl for Vec<Vec<u8>> {
         vec
     }
 }
+
+unsafe impl BufferImpl for Box<[u8]> {
+    type UserData = ();
+
+    fn into_raw_parts(self) -> (Vec<*mut u8>, Vec<usize>, Vec<usize>, Self::UserData) {
+        let len = self.len();
+        let ptr = Box::into_raw(self) as *mut u8;
+        (vec![ptr], vec![len], vec![len], ())
+    }
+
+    unsafe fn from_raw_parts(
+        ptr: Vec<*mut u8>,
+        _len: Vec<usize>,
+        cap: Vec<usize>,
+        _user: Self::UserData,
+    ) -> Self {
+        // Every byte of the slice stays initialized, whatever a read left counted.
+        Box::from_raw(std::ptr::slice_from_raw_parts_mut(ptr[0], cap[0]))
+    }
+}
+
+// A `Bytes` is shared and immutable, so it makes a read-only buffer. The handle itself is
+// kept, and given back as it was.
+#[cfg(feature = "bytes")]
+unsafe impl BufferImpl for bytes::Bytes {
+    type UserData = bytes::Bytes;
+
+    const READ_ONLY: bool = true;
+
+    fn into_raw_parts(self) -> (Vec<*mut u8>, Vec<usize>, Vec<usize>, Self::UserData) {
+        (
+            vec![self.as_ptr() as _],
+            vec![self.len()],
+            vec![self.len()],
+            self,
+        )
+    }
+
+    unsafe fn from_raw_parts(
+        _ptr: Vec<*mut u8>,
+        _len: Vec<usize>,
+        _cap: Vec<usize>,
+        user_data: Self::UserData,
+    ) -> Self {
+        user_data
+    }
+}
+
+// The region of a `BytesMut`, up to its capacity, belongs to that handle alone, even when
+// split halves share the allocation, so the kernel may fill its spare capacity. The handle is
+// kept while the buffer is in use, so it cannot be grown or unsplit meanwhile, and given back
+// with its length updated.
+#[cfg(feature = "bytes")]
+unsafe impl BufferImpl for bytes::BytesMut {
+    type UserData = bytes::BytesMut;
+
+    fn into_raw_parts(mut self) -> (Vec<*mut u8>, Vec<usize>, Vec<usize>, Self::UserData) {
+        let ptr = self.as_mut_ptr();
+        let (len, cap) = (self.len(), self.capacity());
+        (vec![ptr], vec![len], vec![cap], self)
+    }
+
+    unsafe fn from_raw_parts(
+        ptr: Vec<*mut u8>,
+        len: Vec<usize>,
+        _cap: Vec<usize>,
+        mut user_data: Self::UserData,
+    ) -> Self {
+        debug_assert_eq!(ptr[0], user_data.as_mut_ptr());
+        user_data.set_len(len[0]);
+        user_data
+    }
+}
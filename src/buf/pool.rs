@@ -0,0 +1,320 @@
+//! A pool of plain I/O buffers, recycled as they are dropped.
+//!
+//! This module provides [`BufPool`], which hands out ordinary [`Buffer`]s
+//! over a bounded set of allocations. Unlike the collections in
+//! [`fixed`](super::fixed), the memory is not registered with the kernel, so
+//! the pool takes no part of the `RLIMIT_MEMLOCK` budget or of the single
+//! registration slot of the runtime; it only saves allocating a new vector
+//! per operation.
+
+use crate::buf::BufferImpl;
+use crate::Buffer;
+
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::mem::{self, ManuallyDrop};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+/// A pool of plain buffers of one size, whose memory goes back to the pool
+/// when they are dropped rather than being freed.
+///
+/// Buffers are allocated as they are first needed, up to `max_buffers` of
+/// them; after that, a buffer is only handed out once another is dropped. A
+/// buffer from the pool is an ordinary [`Buffer`] of `buf_size` bytes of
+/// capacity, empty when handed out, and can be used by any operation. It
+/// returns to the pool when dropped, wherever that happens, including in an
+/// operation the runtime cancels.
+///
+/// A `BufPool` value is a lightweight handle: cloning it creates a new
+/// reference to the same pool. The memory is freed once the pool and every
+/// buffer from it have been dropped.
+///
+/// # Examples
+///
+/// ```no_run
+/// use tokio_uring::buf::pool::BufPool;
+/// use tokio_uring::fs::File;
+/// use tokio_uring::Submit;
+///
+/// tokio_uring::start(async {
+///     let pool = BufPool::new(4096, 16);
+///     let file = File::open("hello.txt").await.unwrap();
+///
+///     let buf = pool.acquire().await;
+///     let (n, buf) = file.read_at(buf, 0).submit().await.unwrap();
+///     println!("{:?}", &buf[0][..n]);
+///     // Dropping `buf` hands its memory to the next `acquire`.
+/// });
+/// ```
+#[derive(Clone)]
+pub struct BufPool {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl BufPool {
+    /// Creates a pool of buffers of `buf_size` bytes, allocating at most
+    /// `max_buffers` of them.
+    ///
+    /// No memory is allocated until buffers are acquired.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_buffers` is 0.
+    pub fn new(buf_size: usize, max_buffers: usize) -> BufPool {
+        assert!(max_buffers > 0, "a pool needs room for at least one buffer");
+        BufPool {
+            inner: Arc::new(Mutex::new(Inner {
+                buf_size,
+                max_buffers,
+                free: Vec::new(),
+                allocations: 0,
+                in_use: 0,
+                high_water_mark: 0,
+                waiters: VecDeque::new(),
+                handed_over: HashMap::new(),
+                next_waiter_id: 0,
+            })),
+        }
+    }
+
+    /// Returns a buffer from the pool, allocating one if none is free and
+    /// fewer than `max_buffers` have been. Returns `None` if every buffer is
+    /// in use.
+    pub fn try_acquire(&self) -> Option<Buffer> {
+        let vec = self.inner.lock().unwrap().try_acquire()?;
+        Some(self.buffer(vec))
+    }
+
+    /// Resolves to a buffer from the pool once one is free, waiting for a
+    /// buffer to be dropped if every one is in use.
+    ///
+    /// Waiting tasks are served in order, each dropped buffer going straight
+    /// to the first of them. If the future is dropped after a buffer was
+    /// handed over to it, the buffer goes to the next task in line.
+    ///
+    /// If no buffer is ever dropped, this never resolves; wait on it
+    /// concurrently with the tasks holding the buffers, or under a timeout.
+    pub async fn acquire(&self) -> Buffer {
+        // Checking for a free buffer and queueing up happen under one lock,
+        // so a buffer dropped in between cannot be missed.
+        let id = match self.inner.lock().unwrap().acquire_or_wait() {
+            Ok(vec) => return self.buffer(vec),
+            Err(id) => id,
+        };
+        let vec = Waiting {
+            pool: self,
+            id: Some(id),
+        }
+        .await;
+        self.buffer(vec)
+    }
+
+    /// Returns the capacity of each buffer, in bytes.
+    pub fn buf_size(&self) -> usize {
+        self.inner.lock().unwrap().buf_size
+    }
+
+    /// Returns the most buffers the pool allocates.
+    pub fn max_buffers(&self) -> usize {
+        self.inner.lock().unwrap().max_buffers
+    }
+
+    /// Returns the number of buffers allocated so far, which never exceeds
+    /// [`max_buffers`](Self::max_buffers): recycled buffers are not counted
+    /// again.
+    pub fn allocations(&self) -> usize {
+        self.inner.lock().unwrap().allocations
+    }
+
+    /// Returns the number of buffers handed out and not yet dropped,
+    /// including those held by operations in flight.
+    pub fn in_use_count(&self) -> usize {
+        self.inner.lock().unwrap().in_use
+    }
+
+    /// Returns the number of allocated buffers waiting in the pool.
+    pub fn free_count(&self) -> usize {
+        self.inner.lock().unwrap().free.len()
+    }
+
+    /// Returns the most buffers that have been in use at once, to size
+    /// `max_buffers` from a representative run.
+    pub fn high_water_mark(&self) -> usize {
+        self.inner.lock().unwrap().high_water_mark
+    }
+
+    fn buffer(&self, vec: Vec<u8>) -> Buffer {
+        Buffer::new(PooledVec {
+            vec,
+            pool: Some(self.inner.clone()),
+        })
+    }
+}
+
+// State shared by a pool's handles and the buffers it handed out.
+struct Inner {
+    buf_size: usize,
+    max_buffers: usize,
+    // Allocated buffers not in use, most recently dropped last, to be
+    // reused first while their memory is likely still in cache.
+    free: Vec<Vec<u8>>,
+    allocations: usize,
+    in_use: usize,
+    high_water_mark: usize,
+    // Tasks pending on `acquire`, in the order they started waiting.
+    waiters: VecDeque<Waiter>,
+    // Buffers dropped and handed over to a waiter that has yet to take them.
+    handed_over: HashMap<u64, Vec<u8>>,
+    next_waiter_id: u64,
+}
+
+struct Waiter {
+    id: u64,
+    waker: Option<Waker>,
+}
+
+impl Inner {
+    fn try_acquire(&mut self) -> Option<Vec<u8>> {
+        let vec = match self.free.pop() {
+            Some(vec) => vec,
+            None if self.allocations < self.max_buffers => {
+                self.allocations += 1;
+                Vec::with_capacity(self.buf_size)
+            }
+            None => return None,
+        };
+        self.in_use += 1;
+        self.high_water_mark = self.high_water_mark.max(self.in_use);
+        Some(vec)
+    }
+
+    // Takes a buffer like `try_acquire`, or failing that, queues a waiter for
+    // one and returns its id.
+    fn acquire_or_wait(&mut self) -> Result<Vec<u8>, u64> {
+        if let Some(vec) = self.try_acquire() {
+            return Ok(vec);
+        }
+        let id = self.next_waiter_id;
+        self.next_waiter_id += 1;
+        self.waiters.push_back(Waiter { id, waker: None });
+        Err(id)
+    }
+
+    // Takes the buffer handed over to a waiter, or else registers the waker
+    // to be woken when one is.
+    fn poll_waiter(&mut self, id: u64, waker: &Waker) -> Option<Vec<u8>> {
+        if let Some(vec) = self.handed_over.remove(&id) {
+            return Some(vec);
+        }
+        let waiter = self
+            .waiters
+            .iter_mut()
+            .find(|waiter| waiter.id == id)
+            .expect("waiter is neither queued nor served");
+        match &waiter.waker {
+            Some(w) if w.will_wake(waker) => {}
+            _ => waiter.waker = Some(waker.clone()),
+        }
+        None
+    }
+
+    // Dequeues a waiter that gave up, recycling a buffer handed over to it in
+    // the meantime, so that it goes to the next waiter in line.
+    fn cancel_waiter(&mut self, id: u64) {
+        match self.handed_over.remove(&id) {
+            Some(vec) => self.recycle(vec),
+            None => self.waiters.retain(|waiter| waiter.id != id),
+        }
+    }
+
+    // Takes back the memory of a dropped buffer. A waiter gets it directly,
+    // still counted as in use, without a trip through the free list where
+    // `try_acquire` could take it first.
+    fn recycle(&mut self, mut vec: Vec<u8>) {
+        vec.clear();
+        if let Some(waiter) = self.waiters.pop_front() {
+            self.handed_over.insert(waiter.id, vec);
+            if let Some(waker) = waiter.waker {
+                waker.wake();
+            }
+            return;
+        }
+        self.in_use -= 1;
+        self.free.push(vec);
+    }
+}
+
+// A task queued in the pool for a buffer, dequeued if dropped.
+struct Waiting<'a> {
+    pool: &'a BufPool,
+    // `None` once served.
+    id: Option<u64>,
+}
+
+impl Future for Waiting<'_> {
+    type Output = Vec<u8>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let id = self.id.expect("polled after completion");
+        let vec = self.pool.inner.lock().unwrap().poll_waiter(id, cx.waker());
+        match vec {
+            Some(vec) => {
+                self.id = None;
+                Poll::Ready(vec)
+            }
+            None => Poll::Pending,
+        }
+    }
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            self.pool.inner.lock().unwrap().cancel_waiter(id);
+        }
+    }
+}
+
+// The memory of a buffer from a pool, handed back to it when dropped.
+struct PooledVec {
+    vec: Vec<u8>,
+    // `None` once the parts were taken by a `Buffer`.
+    pool: Option<Arc<Mutex<Inner>>>,
+}
+
+unsafe impl BufferImpl for PooledVec {
+    type UserData = Arc<Mutex<Inner>>;
+
+    fn into_raw_parts(mut self) -> (Vec<*mut u8>, Vec<usize>, Vec<usize>, Self::UserData) {
+        let pool = self.pool.take().unwrap();
+        let mut vec = ManuallyDrop::new(mem::take(&mut self.vec));
+        (
+            vec![vec.as_mut_ptr()],
+            vec![vec.len()],
+            vec![vec.capacity()],
+            pool,
+        )
+    }
+
+    unsafe fn from_raw_parts(
+        ptr: Vec<*mut u8>,
+        len: Vec<usize>,
+        cap: Vec<usize>,
+        pool: Self::UserData,
+    ) -> Self {
+        PooledVec {
+            vec: Vec::from_raw_parts(ptr[0], len[0], cap[0]),
+            pool: Some(pool),
+        }
+    }
+}
+
+impl Drop for PooledVec {
+    fn drop(&mut self) {
+        if let Some(pool) = self.pool.take() {
+            pool.lock().unwrap().recycle(mem::take(&mut self.vec));
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use crate::buf::BufferImpl;
+use crate::Buffer;
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// Bytes shared through an [`Arc`], for writing the same data from many operations at once,
+/// such as a cached response sent to every connection.
+///
+/// Cloning only bumps the reference count, and each operation holds a clone until it
+/// completes. A [`Buffer`](crate::Buffer) made from a `SharedBuf` is
+/// [read-only](crate::Buffer::is_read_only): it is for writes and sends, and operations
+/// reading into it fail with `InvalidInput`, handing it back.
+///
+/// # Examples
+///
+/// ```no_run
+/// use tokio_uring::buf::SharedBuf;
+/// use tokio_uring::net::TcpStream;
+///
+/// async fn serve(streams: Vec<TcpStream>, page: SharedBuf) {
+///     for stream in streams {
+///         let page = page.clone();
+///         tokio_uring::spawn(async move { stream.write_all(page.into()).await });
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SharedBuf(pub Arc<[u8]>);
+
+impl From<Arc<[u8]>> for SharedBuf {
+    fn from(bytes: Arc<[u8]>) -> SharedBuf {
+        SharedBuf(bytes)
+    }
+}
+
+impl From<Vec<u8>> for SharedBuf {
+    fn from(bytes: Vec<u8>) -> SharedBuf {
+        SharedBuf(bytes.into())
+    }
+}
+
+impl Deref for SharedBuf {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+// The clone is kept as the user data, so the reference count holds while the buffer is in use.
+unsafe impl BufferImpl for SharedBuf {
+    type UserData = Arc<[u8]>;
+
+    const READ_ONLY: bool = true;
+
+    fn into_raw_parts(self) -> (Vec<*mut u8>, Vec<usize>, Vec<usize>, Self::UserData) {
+        let len = self.0.len();
+        (vec![self.0.as_ptr() as _], vec![len], vec![len], self.0)
+    }
+
+    unsafe fn from_raw_parts(
+        _ptr: Vec<*mut u8>,
+        _len: Vec<usize>,
+        _cap: Vec<usize>,
+        user_data: Self::UserData,
+    ) -> Self {
+        SharedBuf(user_data)
+    }
+}
+
+impl Buffer {
+    /// Creates a [read-only](Buffer::is_read_only) buffer of bytes in static memory, such as
+    /// a protocol constant, for writes and sends without copying them first.
+    ///
+    /// Operations reading into the buffer fail with `InvalidInput`. [Chain](Buffer::chain) it
+    /// with owned buffers to write it along with them.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use tokio_uring::net::TcpStream;
+    /// use tokio_uring::Buffer;
+    ///
+    /// async fn not_found(stream: &TcpStream) -> std::io::Result<()> {
+    ///     let page = Buffer::from_static(b"HTTP/1.1 404 Not Found\r\ncontent-length: 0\r\n\r\n");
+    ///     stream.write_all(page).await.map(drop).map_err(|e| e.0)
+    /// }
+    /// ```
+    pub fn from_static(bytes: &'static [u8]) -> Buffer {
+        Buffer::new(StaticBuf(bytes))
+    }
+}
+
+// Bytes that live, unchanged, for the rest of the program, so that the parts of a buffer made
+// of them need no owner.
+struct StaticBuf(&'static [u8]);
+
+unsafe impl BufferImpl for StaticBuf {
+    type UserData = ();
+
+    const READ_ONLY: bool = true;
+
+    fn into_raw_parts(self) -> (Vec<*mut u8>, Vec<usize>, Vec<usize>, Self::UserData) {
+        let len = self.0.len();
+        (vec![self.0.as_ptr() as _], vec![len], vec![len], ())
+    }
+
+    unsafe fn from_raw_parts(
+        ptr: Vec<*mut u8>,
+        _len: Vec<usize>,
+        cap: Vec<usize>,
+        _: Self::UserData,
+    ) -> Self {
+        StaticBuf(std::slice::from_raw_parts(ptr[0], cap[0]))
+    }
+}
@@ -0,0 +1,110 @@
+use crate::buf::BufferImpl;
+use crate::Buffer;
+use std::mem::ManuallyDrop;
+
+impl From<String> for Buffer {
+    /// Creates a [read-only](Buffer::is_read_only) buffer of the string's bytes, for writes
+    /// and sends: a read could leave bytes in it that are not UTF-8, and fails with
+    /// `InvalidInput` instead. Get the string back with [`try_into_string`](Buffer::try_into_string).
+    fn from(s: String) -> Buffer {
+        Buffer::new(StringBuf {
+            utf8_len: s.len(),
+            vec: s.into_bytes(),
+        })
+    }
+}
+
+impl Buffer {
+    /// Converts the initialized bytes of the buffer into a `String`.
+    ///
+    /// A buffer created from a `String` gives back the string's own allocation, without
+    /// checking it is UTF-8 again, as long as its bytes still end on a character boundary.
+    /// Any other buffer has its initialized bytes, across segments, checked and copied into
+    /// a new string.
+    ///
+    /// # Errors
+    ///
+    /// Hands the buffer back, unchanged, if its bytes are not UTF-8.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use tokio_uring::fs::File;
+    /// use tokio_uring::{Buffer, Submit};
+    ///
+    /// tokio_uring::start(async {
+    ///     let file = File::create("log.txt").await.unwrap();
+    ///     let line = format!("{} events\n", 42);
+    ///
+    ///     let (_, buf) = file.write_at(Buffer::from(line), 0).submit().await.unwrap();
+    ///     let line = buf.try_into_string().unwrap();
+    ///     assert_eq!(line, "42 events\n");
+    /// });
+    /// ```
+    pub fn try_into_string(self) -> Result<String, Buffer> {
+        let buf = match self.try_into::<StringBuf>() {
+            Ok(buf) => return buf.into_string().map_err(Buffer::new),
+            Err(buf) => buf,
+        };
+        let bytes = buf.iter_segments().flatten().copied().collect();
+        String::from_utf8(bytes).map_err(|_| buf)
+    }
+}
+
+// The bytes of a `String`, of which the first `utf8_len` are known to be UTF-8. A buffer may
+// only shorten them, but `Buffer::set_init` can claim more.
+struct StringBuf {
+    vec: Vec<u8>,
+    utf8_len: usize,
+}
+
+impl StringBuf {
+    fn into_string(self) -> Result<String, StringBuf> {
+        let StringBuf { vec, utf8_len } = self;
+        if vec.len() <= utf8_len {
+            // Safety: the string's bytes are still in the allocation, as the buffer is
+            // read-only.
+            let string = unsafe { std::slice::from_raw_parts(vec.as_ptr(), utf8_len) };
+            // Safety: as above.
+            let string = unsafe { std::str::from_utf8_unchecked(string) };
+            if string.is_char_boundary(vec.len()) {
+                // Safety: the bytes are a prefix of the string, ending on a character
+                // boundary.
+                return Ok(unsafe { String::from_utf8_unchecked(vec) });
+            }
+        }
+        String::from_utf8(vec).map_err(|e| StringBuf {
+            vec: e.into_bytes(),
+            utf8_len,
+        })
+    }
+}
+
+// The length of the string is kept as the user data.
+unsafe impl BufferImpl for StringBuf {
+    type UserData = usize;
+
+    const READ_ONLY: bool = true;
+
+    fn into_raw_parts(self) -> (Vec<*mut u8>, Vec<usize>, Vec<usize>, Self::UserData) {
+        let mut vec = ManuallyDrop::new(self.vec);
+        (
+            vec![vec.as_mut_ptr()],
+            vec![vec.len()],
+            vec![vec.capacity()],
+            self.utf8_len,
+        )
+    }
+
+    unsafe fn from_raw_parts(
+        ptr: Vec<*mut u8>,
+        len: Vec<usize>,
+        cap: Vec<usize>,
+        utf8_len: Self::UserData,
+    ) -> Self {
+        StringBuf {
+            vec: Vec::from_raw_parts(ptr[0], len[0], cap[0]),
+            utf8_len,
+        }
+    }
+}
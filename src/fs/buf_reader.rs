@@ -0,0 +1,215 @@
+use super::File;
+use crate::buf::{Buffer, IoBuf};
+use crate::{InFlightOneshot, ReadWriteData, ReadWriteTransform, Submit, Unsubmitted};
+use std::future::{poll_fn, Future};
+use std::io;
+use std::pin::Pin;
+
+const DEFAULT_CAPACITY: usize = 64 * 1024;
+
+/// Adds buffering to positional reads of a [`File`].
+///
+/// `BufReader` keeps its own file offset, starting at 0, and refills an internal buffer with
+/// large reads at that offset. It offers the `fill_buf`/`consume` pair of [`std::io::BufRead`]
+/// along with line-oriented helpers.
+///
+/// A read that is interrupted by dropping the future of one of these methods keeps running in
+/// the background and owns the internal buffer. The next call picks up its result, so no data is
+/// lost and the buffer is never reallocated.
+///
+/// Reaching end of file is not sticky: if the file grows, later calls return the new data.
+///
+/// # Examples
+///
+/// ```no_run
+/// use tokio_uring::fs::{BufReader, File};
+///
+/// tokio_uring::start(async {
+///     let file = File::open("access.log").await.unwrap();
+///     let mut lines = BufReader::new(file).lines();
+///
+///     while let Some(line) = lines.next_line().await.unwrap() {
+///         println!("{}", line);
+///     }
+/// })
+/// ```
+pub struct BufReader {
+    file: File,
+    // `None` while a read owns the buffer.
+    buf: Option<Buffer>,
+    pending: Option<InFlightOneshot<ReadWriteData, ReadWriteTransform>>,
+    // Consumed and filled positions within `buf`.
+    pos: usize,
+    filled: usize,
+    // File offset of the next read.
+    offset: u64,
+}
+
+impl BufReader {
+    /// Creates a new `BufReader` with a default capacity of 64 KiB.
+    pub fn new(file: File) -> BufReader {
+        BufReader::with_capacity(DEFAULT_CAPACITY, file)
+    }
+
+    /// Creates a new `BufReader` with the specified buffer capacity.
+    pub fn with_capacity(capacity: usize, file: File) -> BufReader {
+        BufReader {
+            file,
+            buf: Some(Buffer::from(Vec::<u8>::with_capacity(capacity))),
+            pending: None,
+            pos: 0,
+            filled: 0,
+            offset: 0,
+        }
+    }
+
+    /// Gets a reference to the underlying file.
+    pub fn get_ref(&self) -> &File {
+        &self.file
+    }
+
+    /// Unwraps this `BufReader`, returning the underlying file.
+    ///
+    /// Buffered data that has not been consumed is lost.
+    pub fn into_inner(self) -> File {
+        self.file
+    }
+
+    /// Returns the file offset of the next byte that [`fill_buf`] would return.
+    ///
+    /// [`fill_buf`]: BufReader::fill_buf
+    pub fn position(&self) -> u64 {
+        self.offset - (self.filled - self.pos) as u64
+    }
+
+    /// Returns the contents of the internal buffer, filling it with more data from the file if
+    /// it is empty.
+    ///
+    /// An empty slice is returned at end of file.
+    pub async fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.pos >= self.filled {
+            if self.pending.is_none() {
+                let buf = self.buf.take().expect("buffer owned by the reader");
+                let read = Unsubmitted::read_at(&self.file.fd, buf, self.offset);
+                self.pending = Some(read.submit());
+            }
+
+            let pending = self.pending.as_mut().unwrap();
+            let res = poll_fn(|cx| Pin::new(&mut *pending).poll(cx)).await;
+            self.pending = None;
+
+            match res {
+                Ok((n, buf)) => {
+                    self.buf = Some(buf);
+                    self.pos = 0;
+                    self.filled = n;
+                    self.offset += n as u64;
+                }
+                Err(crate::Error(e, buf)) => {
+                    self.buf = Some(buf);
+                    self.pos = 0;
+                    self.filled = 0;
+                    return Err(e);
+                }
+            }
+        }
+
+        let buf = self.buf.as_ref().unwrap();
+        // Safety: the first `filled` bytes were initialized by the last read.
+        let data = unsafe { std::slice::from_raw_parts(buf.stable_ptr(), self.filled) };
+        Ok(&data[self.pos..])
+    }
+
+    /// Marks `amt` bytes of the data returned by [`fill_buf`] as consumed.
+    ///
+    /// [`fill_buf`]: BufReader::fill_buf
+    pub fn consume(&mut self, amt: usize) {
+        self.pos = std::cmp::min(self.pos + amt, self.filled);
+    }
+
+    /// Reads bytes into `buf` until the delimiter `byte` or end of file is reached.
+    ///
+    /// The delimiter, if found, is appended too. Returns the number of bytes read.
+    pub async fn read_until(&mut self, byte: u8, buf: &mut Vec<u8>) -> io::Result<usize> {
+        let mut read = 0;
+        loop {
+            let (done, used) = {
+                let available = self.fill_buf().await?;
+                match available.iter().position(|&b| b == byte) {
+                    Some(i) => {
+                        buf.extend_from_slice(&available[..=i]);
+                        (true, i + 1)
+                    }
+                    None => {
+                        buf.extend_from_slice(available);
+                        (available.is_empty(), available.len())
+                    }
+                }
+            };
+            self.consume(used);
+            read += used;
+            if done {
+                return Ok(read);
+            }
+        }
+    }
+
+    /// Reads bytes until a newline (the `0xA` byte) is reached and appends them to `buf`.
+    ///
+    /// The newline, if any, is kept, as are carriage returns. Returns the number of bytes read;
+    /// `0` means end of file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidData` error if the line is not valid UTF-8, in which case `buf` is
+    /// left unchanged.
+    pub async fn read_line(&mut self, buf: &mut String) -> io::Result<usize> {
+        let mut bytes = Vec::new();
+        let n = self.read_until(b'\n', &mut bytes).await?;
+        match String::from_utf8(bytes) {
+            Ok(line) => {
+                buf.push_str(&line);
+                Ok(n)
+            }
+            Err(_) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "stream did not contain valid UTF-8",
+            )),
+        }
+    }
+
+    /// Returns a [`Lines`] reader over the lines of this reader.
+    pub fn lines(self) -> Lines {
+        Lines { reader: self }
+    }
+}
+
+/// Reads the lines of a [`BufReader`] one at a time.
+///
+/// Created by [`BufReader::lines`]. Lines are returned without their `\n` or `\r\n`
+/// terminator.
+pub struct Lines {
+    reader: BufReader,
+}
+
+impl Lines {
+    /// Returns the next line, or `None` at end of file.
+    pub async fn next_line(&mut self) -> io::Result<Option<String>> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line).await? == 0 {
+            return Ok(None);
+        }
+        if line.ends_with('\n') {
+            line.pop();
+            if line.ends_with('\r') {
+                line.pop();
+            }
+        }
+        Ok(Some(line))
+    }
+
+    /// Unwraps this `Lines`, returning the underlying reader.
+    pub fn into_inner(self) -> BufReader {
+        self.reader
+    }
+}
@@ -0,0 +1,136 @@
+use super::File;
+use crate::buf::pool::BufPool;
+use crate::buf::{Buffer, IoBuf};
+use crate::io::SharedFd;
+use crate::{InFlightOneshot, ReadWriteData, ReadWriteTransform, Submit, Unsubmitted};
+use futures_core::Stream;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+// Buffers kept around for reuse, in addition to the one owned by the in-flight read. As many
+// chunks' allocations are also returned to the stream when they are dropped: enough for the
+// consumer to hold one chunk while the next is read.
+const POOL_SIZE: usize = 2;
+
+impl File {
+    /// Returns a stream reading the file in chunks of `chunk_size` bytes, starting at `offset`.
+    ///
+    /// The stream keeps one read in flight ahead of the consumer, so the next chunk is usually
+    /// ready by the time it is polled for. No further reads are issued while the stream is not
+    /// polled. Buffers handed back with [`Chunks::recycle`] are reused for later reads, and so
+    /// is the memory of the last few chunks dropped by the consumer.
+    ///
+    /// The stream ends after the first chunk shorter than `chunk_size`, which is the last part
+    /// of the file, or when a read returns no data.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use futures_util::StreamExt;
+    /// use tokio_uring::fs::File;
+    ///
+    /// tokio_uring::start(async {
+    ///     let file = File::open("index.html").await.unwrap();
+    ///     let mut chunks = file.chunks(0, 16 * 1024);
+    ///
+    ///     while let Some(chunk) = chunks.next().await {
+    ///         let chunk = chunk.unwrap();
+    ///         println!("{} bytes", chunk[0].len());
+    ///         chunks.recycle(chunk);
+    ///     }
+    /// })
+    /// ```
+    pub fn chunks(&self, offset: u64, chunk_size: usize) -> Chunks {
+        Chunks {
+            fd: self.fd.clone(),
+            offset,
+            chunk_size,
+            in_flight: None,
+            pool: Vec::with_capacity(POOL_SIZE),
+            free: BufPool::new(chunk_size, POOL_SIZE),
+            done: false,
+        }
+    }
+}
+
+/// A stream of chunks read from a [`File`].
+///
+/// Created by [`File::chunks`]. Dropping the stream cancels the outstanding read; its buffer is
+/// released once the kernel is done with it.
+pub struct Chunks {
+    fd: SharedFd,
+    // File offset of the next read to submit.
+    offset: u64,
+    chunk_size: usize,
+    in_flight: Option<InFlightOneshot<ReadWriteData, ReadWriteTransform>>,
+    pool: Vec<Buffer>,
+    // Allocations that go back to the stream as the chunks holding them are dropped.
+    free: BufPool,
+    done: bool,
+}
+
+impl Chunks {
+    /// Hands a chunk back to the stream so its allocation can be reused.
+    ///
+    /// Buffers beyond the small internal pool, or with a different capacity, are dropped.
+    pub fn recycle(&mut self, buf: Buffer) {
+        if self.pool.len() < POOL_SIZE
+            && buf.segment_count() == 1
+            && buf.bytes_total() == self.chunk_size
+        {
+            self.pool.push(buf);
+        }
+    }
+
+    fn submit_next(&mut self) {
+        let buf = self
+            .pool
+            .pop()
+            .or_else(|| self.free.try_acquire())
+            .unwrap_or_else(|| Buffer::from(Vec::<u8>::with_capacity(self.chunk_size)));
+        let read = Unsubmitted::read_at(&self.fd, buf, self.offset);
+        self.offset += self.chunk_size as u64;
+        self.in_flight = Some(read.submit());
+    }
+}
+
+impl Stream for Chunks {
+    type Item = io::Result<Buffer>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        if this.done {
+            return Poll::Ready(None);
+        }
+        if this.in_flight.is_none() {
+            this.submit_next();
+        }
+
+        let res = ready!(Pin::new(this.in_flight.as_mut().unwrap()).poll(cx));
+        this.in_flight = None;
+
+        match res {
+            Ok((0, buf)) => {
+                this.done = true;
+                this.recycle(buf);
+                Poll::Ready(None)
+            }
+            Ok((n, buf)) => {
+                if n < this.chunk_size {
+                    this.done = true;
+                } else {
+                    // Read ahead while the consumer processes this chunk.
+                    this.submit_next();
+                }
+                Poll::Ready(Some(Ok(buf)))
+            }
+            Err(crate::Error(e, buf)) => {
+                this.done = true;
+                this.recycle(buf);
+                Poll::Ready(Some(Err(e)))
+            }
+        }
+    }
+}
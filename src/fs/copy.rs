@@ -0,0 +1,202 @@
+use super::reflink::dup;
+use super::{File, OpenOptions};
+use crate::buf::Buffer;
+use crate::Submit;
+use std::io;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+// Upper bound on the bytes moved by one copy_file_range call. The calls run on the blocking
+// pool, and a copy whose future is dropped stops at the end of the call in progress.
+const COPY_CHUNK: usize = 4 * 1024 * 1024;
+
+// Size of the buffer used when copying through user space.
+const BUF_SIZE: usize = 256 * 1024;
+
+/// Copies the contents of one file to another, returning the number of bytes copied.
+///
+/// `to` is created if it does not exist and truncated if it does. The permission bits of `from`
+/// are applied to `to`.
+///
+/// The data is copied in the kernel with copy_file_range(2), which lets filesystems share
+/// extents or offload the copy. The calls, which io_uring has no opcode for, run on Tokio's
+/// blocking pool so the runtime thread keeps serving other tasks. When the kernel cannot copy between the two files, for example
+/// across filesystems on older kernels, the copy falls back to reads and writes through a single
+/// reused buffer.
+///
+/// The destination is preallocated to the size of the source to limit fragmentation. The copy
+/// continues until the end of the source is reached, so a source truncated or extended while it
+/// is being copied yields the bytes that were readable at the time.
+///
+/// # Errors
+///
+/// Fails with `InvalidInput` if `from` is not a regular file, and with any error from opening,
+/// reading or writing either file.
+///
+/// # Examples
+///
+/// ```no_run
+/// tokio_uring::start(async {
+///     let n = tokio_uring::fs::copy("foo.txt", "bar.txt").await.unwrap();
+///     println!("copied {} bytes", n);
+/// })
+/// ```
+pub async fn copy(from: impl AsRef<Path>, to: impl AsRef<Path>) -> io::Result<u64> {
+    let src = File::open(from).await?;
+    let res = copy_from(&src, to.as_ref()).await;
+    let closed = src.close().await;
+    let n = res?;
+    closed?;
+    Ok(n)
+}
+
+async fn copy_from(src: &File, to: &Path) -> io::Result<u64> {
+    let metadata = src.metadata().await?;
+    if !metadata.is_file() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "the source path is not a regular file",
+        ));
+    }
+    let mode = metadata.mode() & 0o7777;
+
+    let dst = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(mode)
+        .open(to)
+        .await?;
+    let res = copy_contents(src, &dst, mode, metadata.len()).await;
+    let closed = dst.close().await;
+    let n = res?;
+    closed?;
+    Ok(n)
+}
+
+async fn copy_contents(src: &File, dst: &File, mode: u32, len: u64) -> io::Result<u64> {
+    if len > 0 {
+        // KEEP_SIZE leaves the file length alone, so nothing needs trimming if the source
+        // shrinks while it is copied. Preallocation is only a hint.
+        match dst.fallocate(0, len, libc::FALLOC_FL_KEEP_SIZE).await {
+            Ok(()) => {}
+            Err(e) if is_unsupported(&e) => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    // The blocking task may outlive this future if it is dropped, so it gets descriptors of its
+    // own rather than borrowing ones that could be closed and reused meanwhile.
+    let (src_fd, dst_fd) = (dup(src)?, dup(dst)?);
+    let stop = StopOnDrop(Arc::new(AtomicBool::new(false)));
+    let stopped = stop.0.clone();
+    let (copied, done) = tokio::task::spawn_blocking(move || {
+        // The open mode is filtered by the umask and ignored for existing files.
+        syscall!(fchmod(dst_fd.as_raw_fd(), mode as libc::mode_t))?;
+        copy_in_kernel(src_fd.as_raw_fd(), dst_fd.as_raw_fd(), len, &stopped)
+    })
+    .await
+    .map_err(io::Error::other)??;
+    drop(stop);
+
+    if done {
+        return Ok(copied);
+    }
+    copy_buffered(src, dst, copied).await
+}
+
+// Copies with copy_file_range until the end of the source, returning the bytes copied and
+// whether the copy is done, or has to go on through user space.
+fn copy_in_kernel(
+    src: RawFd,
+    dst: RawFd,
+    len: u64,
+    stopped: &AtomicBool,
+) -> io::Result<(u64, bool)> {
+    let mut copied = 0;
+    while !stopped.load(Ordering::Relaxed) {
+        match copy_file_range(src, dst, copied) {
+            Ok(0) => {
+                // Some pseudo filesystems report an empty file through copy_file_range even
+                // though reading it yields data.
+                return Ok((copied, copied > 0 || len == 0));
+            }
+            Ok(n) => copied += n as u64,
+            Err(e) if is_unsupported(&e) || e.raw_os_error() == Some(libc::EXDEV) => {
+                return Ok((copied, false))
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Err(io::ErrorKind::Interrupted.into())
+}
+
+fn copy_file_range(src: RawFd, dst: RawFd, offset: u64) -> io::Result<usize> {
+    let mut off_in = offset as libc::loff_t;
+    let mut off_out = offset as libc::loff_t;
+    let n = syscall!(copy_file_range(
+        src,
+        &mut off_in,
+        dst,
+        &mut off_out,
+        COPY_CHUNK,
+        0
+    ))?;
+    Ok(n as usize)
+}
+
+// Tells the blocking copy to stop if the future driving it is dropped.
+struct StopOnDrop(Arc<AtomicBool>);
+
+impl Drop for StopOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+// Copies from `offset` to the end of the source, reusing one buffer for every read.
+async fn copy_buffered(src: &File, dst: &File, mut offset: u64) -> io::Result<u64> {
+    let mut buf = Buffer::from(Vec::<u8>::with_capacity(BUF_SIZE));
+    loop {
+        let (n, read) = src.read_at(buf, offset).submit().await.map_err(|e| e.0)?;
+        if n == 0 {
+            return Ok(offset);
+        }
+
+        let (written, read) = dst.write_at(read, offset).submit().await.map_err(|e| e.0)?;
+        if written < n {
+            write_all_at(dst, &read[0][written..n], offset + written as u64).await?;
+        }
+
+        offset += n as u64;
+        buf = read;
+    }
+}
+
+// Finishes a short write. Rare enough that the remainder is copied rather than sliced out of
+// the reused buffer.
+async fn write_all_at(dst: &File, mut data: &[u8], mut offset: u64) -> io::Result<()> {
+    while !data.is_empty() {
+        let (n, _) = dst
+            .write_at(Buffer::from(data.to_vec()), offset)
+            .submit()
+            .await
+            .map_err(|e| e.0)?;
+        if n == 0 {
+            return Err(io::ErrorKind::WriteZero.into());
+        }
+        data = &data[n..];
+        offset += n as u64;
+    }
+    Ok(())
+}
+
+fn is_unsupported(e: &io::Error) -> bool {
+    matches!(
+        e.raw_os_error(),
+        Some(libc::EOPNOTSUPP) | Some(libc::ENOSYS) | Some(libc::EINVAL)
+    )
+}
@@ -4,6 +4,10 @@ use std::path::Path;
 
 /// Recursively create a directory and all of its parent components if they are missing.
 ///
+/// Components that already exist as directories are accepted, so calling this
+/// on an existing tree succeeds. If any component exists but is not a
+/// directory, an error is returned.
+///
 /// # Examples
 ///
 /// ```no_run
@@ -133,16 +137,17 @@ impl DirBuilder {
             match self.inner.mkdir(path).await {
                 Ok(()) => return Ok(()),
                 Err(ref e) if e.kind() == io::ErrorKind::NotFound => {}
-                Err(_) if is_dir(path).await => return Ok(()),
+                // Only an existing directory satisfies the request; an existing file or other
+                // non-directory in the way is reported with the original EEXIST.
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                    return if is_dir(path).await { Ok(()) } else { Err(e) };
+                }
                 Err(e) => return Err(e),
             }
             match path.parent() {
                 Some(p) => self.recurse_create_dir_all(p).await?,
                 None => {
-                    return Err(std::io::Error::new(
-                        std::io::ErrorKind::Other,
-                        "failed to create whole tree",
-                    ));
+                    return Err(io::Error::other("failed to create whole tree"));
                     /* TODO build own allocation free error some day like the std library does.
                     return Err(io::const_io_error!(
                         io::ErrorKind::Uncategorized,
@@ -151,9 +156,16 @@ impl DirBuilder {
                     */
                 }
             }
+            // Another process may have created this component since the first attempt.
             match self.inner.mkdir(path).await {
                 Ok(()) => Ok(()),
-                Err(_) if is_dir(path).await => Ok(()),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                    if is_dir(path).await {
+                        Ok(())
+                    } else {
+                        Err(e)
+                    }
+                }
                 Err(e) => Err(e),
             }
         })
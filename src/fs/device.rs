@@ -0,0 +1,236 @@
+use super::{DioAlignment, File, OpenOptions};
+use crate::buf::{self, BoundedBuf, BoundedBufMut, Buffer, IoBuf};
+use crate::{Submit, WithBuffer};
+use std::io;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+
+// _IOR(0x12, 114, size_t). The direction and size bits are laid out differently on a few
+// architectures, and libc does not export this request.
+#[cfg(not(any(
+    target_arch = "mips",
+    target_arch = "mips64",
+    target_arch = "powerpc",
+    target_arch = "powerpc64",
+    target_arch = "sparc64"
+)))]
+const BLKGETSIZE64: u64 = 0x8008_1272;
+#[cfg(any(
+    target_arch = "mips",
+    target_arch = "mips64",
+    target_arch = "powerpc",
+    target_arch = "powerpc64",
+    target_arch = "sparc64"
+))]
+const BLKGETSIZE64: u64 = 0x4008_1272;
+
+/// An open block device, such as `/dev/nvme0n1` or a loop device.
+///
+/// Devices are opened with `O_DIRECT`. Their geometry is queried once when opening, since
+/// statx(2) reports a size of 0 for block devices. Reads and writes are checked against the
+/// [direct I/O alignment](Device::dio_alignment) before submission, so misaligned requests
+/// fail with `InvalidInput`
+/// instead of an opaque `EINVAL` from the kernel, and reads starting at or past the end of the
+/// device fail with `UnexpectedEof`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use tokio_uring::fs::Device;
+/// use tokio_uring::Submit;
+///
+/// tokio_uring::start(async {
+///     let dev = Device::open("/dev/nvme0n1").await.unwrap();
+///     println!("{} bytes, {} byte blocks", dev.size_bytes(), dev.block_size());
+///
+///     let buf = tokio_uring::Buffer::from(vec![0u8; dev.block_size() as usize]);
+///     let (n, _) = dev.read_at(buf, 0).await.unwrap();
+///     assert_eq!(n, dev.block_size() as usize);
+/// })
+/// ```
+pub struct Device {
+    file: File,
+    size: u64,
+    block_size: u32,
+    physical_block_size: u32,
+    dio: DioAlignment,
+}
+
+impl Device {
+    /// Opens a block device for reading.
+    ///
+    /// # Errors
+    ///
+    /// Fails with `ENOTBLK` if `path` is not a block device.
+    pub async fn open(path: impl AsRef<Path>) -> io::Result<Device> {
+        Device::open_with(path, OpenOptions::new().read(true)).await
+    }
+
+    /// Opens a block device for reading and writing.
+    pub async fn open_rw(path: impl AsRef<Path>) -> io::Result<Device> {
+        Device::open_with(path, OpenOptions::new().read(true).write(true)).await
+    }
+
+    async fn open_with(path: impl AsRef<Path>, options: &mut OpenOptions) -> io::Result<Device> {
+        let file = options.custom_flags(libc::O_DIRECT).open(path).await?;
+
+        match Device::from_file(&file).await {
+            Ok((size, block_size, physical_block_size, dio)) => Ok(Device {
+                file,
+                size,
+                block_size,
+                physical_block_size,
+                dio,
+            }),
+            Err(e) => {
+                file.close().await?;
+                Err(e)
+            }
+        }
+    }
+
+    async fn from_file(file: &File) -> io::Result<(u64, u32, u32, DioAlignment)> {
+        if !file.metadata().await?.file_type().is_block_device() {
+            return Err(io::Error::from_raw_os_error(libc::ENOTBLK));
+        }
+
+        // These ioctls only read cached queue limits and never wait on the device.
+        let fd = file.fd.raw_fd();
+        let mut size: u64 = 0;
+        let mut block_size: libc::c_int = 0;
+        let mut physical_block_size: libc::c_uint = 0;
+        syscall!(ioctl(fd, BLKGETSIZE64 as _, &mut size as *mut u64))?;
+        syscall!(ioctl(
+            fd,
+            libc::BLKSSZGET as _,
+            &mut block_size as *mut libc::c_int
+        ))?;
+        syscall!(ioctl(
+            fd,
+            libc::BLKPBSZGET as _,
+            &mut physical_block_size as *mut libc::c_uint
+        ))?;
+
+        let block_size = block_size as u32;
+        // Kernels before 6.1 do not report the alignment, which is then the logical block size.
+        let dio = file.dio_alignment().await?.unwrap_or(DioAlignment {
+            mem_align: block_size,
+            offset_align: block_size,
+        });
+
+        Ok((size, block_size, physical_block_size, dio))
+    }
+
+    /// Returns the size of the device in bytes.
+    pub fn size_bytes(&self) -> u64 {
+        self.size
+    }
+
+    /// Returns the logical block size.
+    pub fn block_size(&self) -> u32 {
+        self.block_size
+    }
+
+    /// Returns the alignment required of offsets, lengths and buffer addresses, as reported
+    /// by [`File::dio_alignment`], or the logical block size for all three where it is not
+    /// reported.
+    pub fn dio_alignment(&self) -> DioAlignment {
+        self.dio
+    }
+
+    /// Returns the physical block size. Writes of this granularity avoid read-modify-write
+    /// cycles in the device.
+    pub fn physical_block_size(&self) -> u32 {
+        self.physical_block_size
+    }
+
+    /// Returns the underlying file.
+    pub fn as_file(&self) -> &File {
+        &self.file
+    }
+
+    /// Reads into `buf` at the byte offset `pos`.
+    ///
+    /// See [`File::read_at`]. The read is truncated at the end of the device.
+    pub async fn read_at(&self, buf: Buffer, pos: u64) -> crate::Result<usize, Buffer> {
+        if let Err(e) = self.check_read(buf.alignment(), IoBuf::bytes_total(&buf), pos) {
+            return Err(e).with_buffer(buf);
+        }
+        self.file.read_at(buf, pos).submit().await
+    }
+
+    /// Writes the initialized part of `buf` at the byte offset `pos`.
+    ///
+    /// See [`File::write_at`].
+    pub async fn write_at(&self, buf: Buffer, pos: u64) -> crate::Result<usize, Buffer> {
+        if let Err(e) = self.check_aligned(buf.alignment(), IoBuf::bytes_init(&buf), pos) {
+            return Err(e).with_buffer(buf);
+        }
+        self.file.write_at(buf, pos).submit().await
+    }
+
+    /// Like [`read_at`](Device::read_at), but using a pre-registered buffer.
+    pub async fn read_fixed_at<T>(&self, buf: T, pos: u64) -> crate::Result<usize, T>
+    where
+        T: BoundedBufMut<BufMut = Buffer>,
+    {
+        let align = buf::alignment_of(buf.stable_ptr() as usize);
+        if let Err(e) = self.check_read(align, buf.bytes_total(), pos) {
+            return Err(e).with_buffer(buf);
+        }
+        self.file.read_fixed_at(buf, pos).await
+    }
+
+    /// Like [`write_at`](Device::write_at), but using a pre-registered buffer.
+    pub async fn write_fixed_at<T>(&self, buf: T, pos: u64) -> crate::Result<usize, T>
+    where
+        T: BoundedBuf<Buf = Buffer>,
+    {
+        let align = buf::alignment_of(buf.stable_ptr() as usize);
+        if let Err(e) = self.check_aligned(align, buf.bytes_init(), pos) {
+            return Err(e).with_buffer(buf);
+        }
+        self.file.write_fixed_at(buf, pos).await
+    }
+
+    /// Closes the device.
+    pub async fn close(self) -> io::Result<()> {
+        self.file.close().await
+    }
+
+    fn check_read(&self, align: usize, len: usize, pos: u64) -> io::Result<()> {
+        self.check_aligned(align, len, pos)?;
+        if pos >= self.size {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "read starts at or past the end of the device",
+            ));
+        }
+        Ok(())
+    }
+
+    // Checks the offset, the length and the alignment of the buffer's memory against the
+    // direct I/O alignment.
+    fn check_aligned(&self, align: usize, len: usize, pos: u64) -> io::Result<()> {
+        let mask = u64::from(self.dio.offset_align) - 1;
+        if pos & mask != 0 || len as u64 & mask != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "offset and length must be aligned to {} bytes",
+                    self.dio.offset_align
+                ),
+            ));
+        }
+        if (align as u64) < u64::from(self.dio.mem_align) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "buffer memory is aligned to {} bytes, short of the required {}",
+                    align, self.dio.mem_align
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
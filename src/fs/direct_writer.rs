@@ -0,0 +1,333 @@
+use super::reflink::dup;
+use super::File;
+use crate::buf::fixed::{pool, registry};
+use crate::buf::{Buffer, IoBuf, IoBufMut};
+use crate::io::WriteFixed;
+use crate::runtime::driver::op::Op;
+use std::any::TypeId;
+use std::os::unix::io::AsRawFd;
+use std::{cmp, io, ptr};
+
+/// A buffered writer for files opened with `O_DIRECT`.
+///
+/// Direct I/O requires every write to start at an aligned offset and cover an aligned length
+/// from an aligned memory address. `DirectWriter` accepts appends of arbitrary size, stages them
+/// in fixed buffers (checked out from a [`FixedBufRegistry`] or [`FixedBufPool`]) and writes
+/// only whole buffers with [`write_fixed_at`], carrying any remainder forward.
+///
+/// When given two buffers, the writer fills one while the other is being flushed, so appends
+/// rarely wait on the device.
+///
+/// The file is written sequentially from offset 0. Call [`close`] to write the unaligned tail:
+/// it is padded with zeros up to the alignment and, unless disabled with
+/// [`truncate_on_close`], the file is then truncated back to the number of bytes appended.
+///
+/// [`FixedBufRegistry`]: crate::buf::fixed::registry::FixedBufRegistry
+/// [`FixedBufPool`]: crate::buf::fixed::pool::FixedBufPool
+/// [`write_fixed_at`]: File::write_fixed_at
+/// [`close`]: DirectWriter::close
+/// [`truncate_on_close`]: DirectWriter::truncate_on_close
+///
+/// # Examples
+///
+/// ```no_run
+/// use tokio_uring::buf::{fixed::registry, AlignedBuf};
+/// use tokio_uring::fs::{DirectWriter, OpenOptions};
+/// use std::os::unix::fs::OpenOptionsExt;
+///
+/// tokio_uring::start(async {
+///     let bufs = (0..2).map(|_| AlignedBuf::new(64 * 1024, 4096).into());
+///     let registry = registry::register(bufs)?;
+///     let bufs = vec![registry.check_out(0).unwrap(), registry.check_out(1).unwrap()];
+///
+///     let file = OpenOptions::new()
+///         .write(true)
+///         .create(true)
+///         .custom_flags(libc::O_DIRECT)
+///         .open("records.log")
+///         .await?;
+///
+///     let mut writer = DirectWriter::new(file, bufs, 4096)?;
+///     writer.write(b"first record\n").await?;
+///     writer.write(b"second record\n").await?;
+///     writer.close().await
+/// })
+/// # .unwrap();
+/// ```
+pub struct DirectWriter {
+    file: File,
+    // Alignment of write offsets and lengths.
+    align: usize,
+    truncate: bool,
+    // Buffer being filled by appends. Its initialized length is the number of staged bytes.
+    current: Option<Buffer>,
+    // Buffers that are neither being filled nor being written.
+    spare: Vec<Buffer>,
+    in_flight: Option<InFlight>,
+    // File offset at which `current` will be written.
+    pos: u64,
+}
+
+struct InFlight {
+    op: Op<WriteFixed<Buffer>>,
+    pos: u64,
+    len: usize,
+}
+
+impl DirectWriter {
+    /// Creates a writer that stages appends in `bufs` and writes them to `file`.
+    ///
+    /// `bufs` must hold one or two fixed buffers, each starting at an address aligned to
+    /// `align` with a capacity that is a non-zero multiple of `align`. `align` must be a power
+    /// of two, usually the device's logical block size.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidInput` error if the buffers or the alignment do not meet these
+    /// requirements.
+    pub fn new(file: File, bufs: Vec<Buffer>, align: usize) -> io::Result<DirectWriter> {
+        DirectWriter::with_alignment(file, bufs, align, align)
+    }
+
+    /// Creates a writer like [`new`](DirectWriter::new), with the alignment required by
+    /// `file` as reported by [`File::dio_alignment`].
+    ///
+    /// The buffers must start at an address aligned to the reported memory alignment, with a
+    /// capacity that is a multiple of the reported offset alignment, which the tail is padded
+    /// to. When the kernel or the filesystem does not report them, both fall back to the
+    /// file's preferred I/O block size, `st_blksize`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidInput` error if the buffers do not meet these requirements, and
+    /// any error of the statx(2) calls.
+    pub async fn with_dio_alignment(file: File, bufs: Vec<Buffer>) -> io::Result<DirectWriter> {
+        let (mem_align, align) = match file.dio_alignment().await? {
+            Some(dio) => (dio.mem_align as usize, dio.offset_align as usize),
+            None => {
+                let blksize = file.statx().await?.stx_blksize as usize;
+                (blksize, blksize)
+            }
+        };
+        DirectWriter::with_alignment(file, bufs, align, mem_align)
+    }
+
+    fn with_alignment(
+        file: File,
+        bufs: Vec<Buffer>,
+        align: usize,
+        mem_align: usize,
+    ) -> io::Result<DirectWriter> {
+        if !align.is_power_of_two() || !mem_align.is_power_of_two() {
+            return Err(invalid_input("alignment must be a power of two"));
+        }
+        if bufs.is_empty() || bufs.len() > 2 {
+            return Err(invalid_input("DirectWriter takes one or two buffers"));
+        }
+        for buf in &bufs {
+            let is_fixed = buf.type_id() == TypeId::of::<registry::FixedBuf>()
+                || buf.type_id() == TypeId::of::<pool::FixedBuf>();
+            if !is_fixed || buf.segment_count() != 1 {
+                return Err(invalid_input("buffers must be single fixed buffers"));
+            }
+            let mask = align - 1;
+            let cap = buf.bytes_total();
+            if buf.alignment() < mem_align || cap == 0 || cap & mask != 0 {
+                return Err(invalid_input("buffers must be aligned"));
+            }
+        }
+
+        let spare = bufs
+            .into_iter()
+            .map(|mut buf| {
+                // Safety: no bytes are considered staged in a fresh buffer.
+                unsafe { buf.set_init(0) };
+                buf
+            })
+            .collect();
+
+        Ok(DirectWriter {
+            file,
+            align,
+            truncate: true,
+            current: None,
+            spare,
+            in_flight: None,
+            pos: 0,
+        })
+    }
+
+    /// Sets whether [`close`] truncates the file to the number of bytes appended, removing the
+    /// zero padding of the final block.
+    ///
+    /// This option defaults to `true`.
+    ///
+    /// [`close`]: DirectWriter::close
+    pub fn truncate_on_close(&mut self, truncate: bool) -> &mut Self {
+        self.truncate = truncate;
+        self
+    }
+
+    /// Returns the number of bytes appended so far.
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> u64 {
+        self.pos
+            + self
+                .current
+                .as_ref()
+                .map_or(0, |buf| buf.bytes_init() as u64)
+    }
+
+    /// Appends `data`, writing out every buffer that becomes full.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error encountered writing to the file. The writer should not be used
+    /// after an error.
+    pub async fn write(&mut self, mut data: &[u8]) -> io::Result<()> {
+        while !data.is_empty() {
+            let buf = match self.current {
+                Some(ref mut buf) => buf,
+                None => {
+                    let buf = self.next_buffer().await?;
+                    self.current.insert(buf)
+                }
+            };
+
+            let init = buf.bytes_init();
+            let n = cmp::min(buf.bytes_total() - init, data.len());
+            // Safety: the destination range lies within the buffer's capacity and the bytes up
+            // to `init + n` are initialized by the copy.
+            unsafe {
+                ptr::copy_nonoverlapping(data.as_ptr(), buf.stable_mut_ptr().add(init), n);
+                buf.set_init(init + n);
+            }
+            data = &data[n..];
+
+            if buf.bytes_init() == buf.bytes_total() {
+                let buf = self.current.take().unwrap();
+                self.flush_buffer(buf).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Appends the initialized contents of every segment of `buf`.
+    pub async fn write_buffer(&mut self, buf: &Buffer) -> io::Result<()> {
+        for i in 0..buf.segment_count() {
+            self.write(&buf[i]).await?;
+        }
+        Ok(())
+    }
+
+    /// Writes the remaining data and closes the file.
+    ///
+    /// The unaligned tail is padded with zeros to the alignment and written. The file is then
+    /// truncated to [`len`] unless [`truncate_on_close`] was disabled.
+    ///
+    /// [`len`]: DirectWriter::len
+    /// [`truncate_on_close`]: DirectWriter::truncate_on_close
+    pub async fn close(mut self) -> io::Result<()> {
+        let len = self.len();
+        self.wait_in_flight().await?;
+
+        if let Some(mut buf) = self.current.take() {
+            let tail = buf.bytes_init();
+            let padded = (tail + self.align - 1) & !(self.align - 1);
+            // Safety: the capacity is a multiple of the alignment, so `padded` fits.
+            unsafe {
+                ptr::write_bytes(buf.stable_mut_ptr().add(tail), 0, padded - tail);
+                buf.set_init(padded);
+            }
+            self.write_rest(buf, self.pos, padded, 0).await?;
+
+            if self.truncate && padded != tail {
+                // On its own descriptor, as the blocking task may outlive this future if it
+                // is dropped.
+                let fd = dup(&self.file)?;
+                tokio::task::spawn_blocking(move || {
+                    syscall!(ftruncate(fd.as_raw_fd(), len as libc::off_t)).map(drop)
+                })
+                .await
+                .map_err(io::Error::other)??;
+            }
+        }
+
+        self.file.close().await
+    }
+
+    // Returns an empty buffer, waiting for the in-flight write if every buffer is busy.
+    async fn next_buffer(&mut self) -> io::Result<Buffer> {
+        if self.spare.is_empty() {
+            self.wait_in_flight().await?;
+        }
+        Ok(self.spare.pop().expect("a buffer is free"))
+    }
+
+    // Submits a full buffer. Only one write is in flight at a time so blocks land in order.
+    async fn flush_buffer(&mut self, buf: Buffer) -> io::Result<()> {
+        self.wait_in_flight().await?;
+
+        registry::check_registered(&buf)?;
+        let len = buf.bytes_init();
+        let op = Op::write_fixed_at(&self.file.fd, buf, self.pos)?;
+        self.in_flight = Some(InFlight {
+            op,
+            pos: self.pos,
+            len,
+        });
+        self.pos += len as u64;
+        Ok(())
+    }
+
+    async fn wait_in_flight(&mut self) -> io::Result<()> {
+        let Some(in_flight) = self.in_flight.take() else {
+            return Ok(());
+        };
+
+        let (n, buf) = in_flight.op.await.map_err(|e| e.0)?;
+        let mut buf = self
+            .write_rest(buf, in_flight.pos, in_flight.len, n)
+            .await?;
+
+        // Safety: the buffer's contents have been written out and are no longer needed.
+        unsafe { buf.set_init(0) };
+        self.spare.push(buf);
+        Ok(())
+    }
+
+    // Writes the first `len` bytes of `buf` at `pos`, of which the first `n` are already
+    // written. Direct I/O can only carry on after a short write from a block boundary, so a
+    // write stopping anywhere else is an error rather than retried unaligned.
+    async fn write_rest(
+        &self,
+        mut buf: Buffer,
+        pos: u64,
+        len: usize,
+        mut n: usize,
+    ) -> io::Result<Buffer> {
+        while n < len {
+            if !n.is_multiple_of(self.align) {
+                return Err(io::Error::other(
+                    "short direct write ended off a block boundary",
+                ));
+            }
+            let slice = crate::buf::BoundedBuf::slice(buf, n..len);
+            let (written, slice) = self
+                .file
+                .write_fixed_at(slice, pos + n as u64)
+                .await
+                .map_err(|e| e.0)?;
+            if written == 0 {
+                return Err(io::ErrorKind::WriteZero.into());
+            }
+            n += written;
+            buf = slice.into_inner();
+        }
+        Ok(buf)
+    }
+}
+
+fn invalid_input(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
+}
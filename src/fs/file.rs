@@ -1,10 +1,12 @@
+use crate::buf::fixed::registry;
 use crate::buf::{BoundedBuf, BoundedBufMut, Buffer, Slice};
 use crate::fs::OpenOptions;
-use crate::io::SharedFd;
+use crate::io::{Pipe, SharedFd, UnsubmittedFsync};
+use crate::net::TcpStream;
 
 use crate::runtime::driver::op::Op;
-use crate::MapResult;
-use crate::Unsubmitted;
+use crate::{MapResult, WithBuffer};
+use crate::{Submit, Unsubmitted};
 use std::fmt;
 use std::io;
 use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd, RawFd};
@@ -144,7 +146,8 @@ impl File {
     /// data from the file. If `n` is `0`, then one of the following happened:
     ///
     /// 1. The specified offset is the end of the file.
-    /// 2. The buffer specified was 0 bytes in length.
+    /// 2. The buffer specified was 0 bytes in length, across all its segments. Such a read
+    ///    completes as soon as it is submitted, without going through the kernel.
     ///
     /// It is not an error if the returned value `n` is smaller than the buffer
     /// size, even when the file contains enough data to fill the buffer.
@@ -180,102 +183,10 @@ impl File {
         Unsubmitted::read_at(&self.fd, buf, pos)
     }
 
-    /// Read some bytes at the specified offset from the file into the specified
-    /// array of buffers, returning how many bytes were read.
-    ///
-    /// # Return
-    ///
-    /// The method returns the operation result and the same array of buffers
-    /// passed as an argument.
-    ///
-    /// If the method returns [`Ok(n)`], then the read was successful. A nonzero
-    /// `n` value indicates that the buffers have been filled with `n` bytes of
-    /// data from the file. If `n` is `0`, then one of the following happened:
-    ///
-    /// 1. The specified offset is the end of the file.
-    /// 2. The buffers specified were 0 bytes in length.
-    ///
-    /// It is not an error if the returned value `n` is smaller than the buffer
-    /// size, even when the file contains enough data to fill the buffer.
-    ///
-    /// # Errors
-    ///
-    /// If this function encounters any form of I/O or other error, an error
-    /// variant will be returned. The buffer is returned on error.
-    ///
-    /// # Examples
-    ///
-    /// ```no_run
-    /// use tokio_uring::fs::File;
-    /// use tokio_uring::Submit;
-    ///
-    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
-    ///     tokio_uring::start(async {
-    ///         let f = File::open("foo.txt").await?;
-    ///         let buffers = vec![Vec::<u8>::with_capacity(10), Vec::<u8>::with_capacity(10)].into();
-    ///
-    ///         // Read up to 20 bytes
-    ///         let (n, _) = f.read_at(buffers, 0).submit().await?;
-    ///
-    ///         println!("Read {} bytes", n);
-    ///
-    ///         // Close the file
-    ///         f.close().await?;
-    ///         Ok(())
-    ///     })
-    /// }
-    /// ```
     // pub fn readv_at<T: BoundedBufMut>(&self, bufs: Vec<T>, pos: u64) -> UnsubmittedReadv<T> {
     // UnsubmittedOneshot::readv_at(&self.fd, bufs, pos)
     // }
 
-    /// Write data from buffers into this file at the specified offset,
-    /// returning how many bytes were written.
-    ///
-    /// This function will attempt to write the entire contents of `bufs`, but
-    /// the entire write may not succeed, or the write may also generate an
-    /// error. The bytes will be written starting at the specified offset.
-    ///
-    /// # Return
-    ///
-    /// The method returns the operation result and the same array of buffers passed
-    /// in as an argument. A return value of `0` typically means that the
-    /// underlying file is no longer able to accept bytes and will likely not be
-    /// able to in the future as well, or that the buffer provided is empty.
-    ///
-    /// # Errors
-    ///
-    /// Each call to `write` may generate an I/O error indicating that the
-    /// operation could not be completed. If an error is returned then no bytes
-    /// in the buffer were written to this writer.
-    ///
-    /// It is **not** considered an error if the entire buffer could not be
-    /// written to this writer.
-    ///
-    /// # Examples
-    ///
-    /// ```no_run
-    /// use tokio_uring::fs::File;
-    /// use tokio_uring::Submit;
-    ///
-    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
-    ///     tokio_uring::start(async {
-    ///         let file = File::create("foo.txt").await?;
-    ///
-    ///         // Writes some prefix of the byte string, not necessarily all of it.
-    ///         let bufs = vec!["some".to_owned().into_bytes(), " bytes".to_owned().into_bytes()].into();
-    ///         let (n, _) = file.write_at(bufs, 0).submit().await?;
-    ///
-    ///         println!("wrote {} bytes", n);
-    ///
-    ///         // Close the file
-    ///         file.close().await?;
-    ///         Ok(())
-    ///     })
-    /// }
-    /// ```
-    ///
-    /// [`Ok(n)`]: Ok
     // pub fn writev_at(&self, bufs: Vec<T>, pos: u64) -> UnsubmittedWritev<T> {
     // UnsubmittedOneshot::writev_at(&self.fd, bufs, pos)
     // }
@@ -323,6 +234,12 @@ impl File {
     where
         T: BoundedBufMut<BufMut = Buffer>,
     {
+        if buf.bytes_total() == 0 {
+            return Ok((0, buf));
+        }
+        if let Err(e) = registry::check_registered(buf.get_buf()) {
+            return Err(e).with_buffer(buf);
+        }
         // Submit the read operation
         let op = Op::read_fixed_at(&self.fd, buf, pos).unwrap();
         op.await
@@ -340,7 +257,8 @@ impl File {
     /// The method returns the operation result and the same buffer value passed
     /// in as an argument. A return value of `0` typically means that the
     /// underlying file is no longer able to accept bytes and will likely not be
-    /// able to in the future as well, or that the buffer provided is empty.
+    /// able to in the future as well, or that the buffer provided is empty. A write of an
+    /// empty buffer completes as soon as it is submitted, without going through the kernel.
     ///
     /// # Errors
     ///
@@ -422,6 +340,12 @@ impl File {
     where
         T: BoundedBuf<Buf = Buffer>,
     {
+        if buf.bytes_init() == 0 {
+            return Ok((0, buf));
+        }
+        if let Err(e) = registry::check_registered(buf.get_buf()) {
+            return Err(e).with_buffer(buf);
+        }
         let op = Op::write_fixed_at(&self.fd, buf, pos).unwrap();
         op.await
     }
@@ -560,6 +484,95 @@ impl File {
         Op::datasync(&self.fd)?.await
     }
 
+    /// Writes a buffer at the specified offset and syncs it to disk, in a single submission.
+    ///
+    /// The write is linked to an fsync (or fdatasync when `datasync` is `true`) with
+    /// `IOSQE_IO_LINK`, so the kernel only starts the sync once the write has completed in
+    /// full. The future resolves after both operations have completed.
+    ///
+    /// # Return
+    ///
+    /// The first element is the result of the write, carrying the byte count and the buffer
+    /// just like [`write_at`]. The second is the result of the sync. When the write fails or is
+    /// short, the kernel cancels the sync and the second element is an `ECANCELED` error; the
+    /// original cause is always reported in the first element.
+    ///
+    /// [`write_at`]: File::write_at
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use tokio_uring::fs::File;
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     tokio_uring::start(async {
+    ///         let f = File::create("foo.txt").await?;
+    ///
+    ///         let (res, sync) = f.write_at_sync(b"Hello, world!".to_vec().into(), 0, false).await;
+    ///         let (n, _) = res?;
+    ///         sync?;
+    ///
+    ///         println!("wrote and synced {} bytes", n);
+    ///         f.close().await?;
+    ///         Ok(())
+    ///     })
+    /// }
+    /// ```
+    pub async fn write_at_sync(
+        &self,
+        buf: Buffer,
+        pos: u64,
+        datasync: bool,
+    ) -> (crate::Result<usize, Buffer>, io::Result<()>) {
+        let write = Unsubmitted::write_at(&self.fd, buf, pos);
+        let sync = UnsubmittedFsync::fsync(&self.fd, datasync);
+
+        let (res, sync) = write.link(sync).submit().await;
+        (res, sync.await)
+    }
+
+    /// Issues an `IORING_OP_URING_CMD` passthrough command to the driver backing this file.
+    ///
+    /// `cmd_op` and the 16 bytes of `cmd` are interpreted by the driver, much like an ioctl
+    /// request and its argument. The CQE result is returned on success.
+    ///
+    /// Commands needing more than 16 bytes, such as NVMe passthrough, go through
+    /// [`uring_cmd80`] instead.
+    ///
+    /// [`uring_cmd80`]: File::uring_cmd80
+    ///
+    /// # Safety
+    ///
+    /// The driver may read or write memory referenced from `cmd`. The caller must make sure any
+    /// such memory is valid for the whole operation, including if the returned future is
+    /// dropped before completion.
+    pub async unsafe fn uring_cmd(&self, cmd_op: u32, cmd: [u8; 16]) -> io::Result<u32> {
+        Op::uring_cmd16(&self.fd, cmd_op, cmd)?.await
+    }
+
+    /// Issues a passthrough command carrying 80 bytes of command data.
+    ///
+    /// The command fills the 80-byte area of a 128-byte SQE, so the runtime must be started
+    /// with [`Builder::uring_builder_big`](crate::Builder::uring_builder_big). On success, the
+    /// CQE result is returned along with the 16 extra bytes of the 32-byte CQE, which some
+    /// drivers, such as NVMe, use for the command's own result.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`Unsupported`](io::ErrorKind::Unsupported) if the runtime was started with
+    /// the default ring, rather than submitting a truncated command.
+    ///
+    /// # Safety
+    ///
+    /// See [`uring_cmd`](File::uring_cmd).
+    pub async unsafe fn uring_cmd80(
+        &self,
+        cmd_op: u32,
+        cmd: [u8; 80],
+    ) -> io::Result<(u32, [u64; 2])> {
+        Op::uring_cmd80(&self.fd, cmd_op, cmd)?.await
+    }
+
     /// Manipulate the allocated disk space of the file.
     ///
     /// The manipulated range starts at the `offset` and continues for `len` bytes.
@@ -589,6 +602,80 @@ impl File {
         Op::fallocate(&self.fd, offset, len, flags)?.await
     }
 
+    /// Sends `len` bytes of the file, starting at `offset`, to the stream without copying them
+    /// through userspace, like `sendfile(2)`.
+    ///
+    /// The data moves through a pipe internal to the call, spliced in from the file and out to
+    /// the socket in turn. Returns the number of bytes sent, which is less than `len` only if
+    /// the file ends first.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use tokio_uring::fs::File;
+    /// use tokio_uring::net::TcpListener;
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     tokio_uring::start(async {
+    ///         let listener = TcpListener::bind("127.0.0.1:8080".parse().unwrap())?;
+    ///         let (stream, _) = listener.accept().await?;
+    ///
+    ///         let f = File::open("index.html").await?;
+    ///         let len = f.statx().await?.stx_size;
+    ///         f.send_to_socket(&stream, 0, len).await?;
+    ///         Ok(())
+    ///     })
+    /// }
+    /// ```
+    pub async fn send_to_socket(
+        &self,
+        stream: &TcpStream,
+        offset: u64,
+        len: u64,
+    ) -> io::Result<u64> {
+        let pipe = Pipe::new()?;
+        let socket = &stream.inner.fd;
+
+        let mut sent = 0;
+        let mut pos = offset;
+        // Bytes moved into the pipe that have yet to reach the socket.
+        let mut buffered = 0;
+        while sent < len {
+            if buffered > 0 {
+                match Op::splice(&pipe.read, None, socket, buffered as u32)?.await {
+                    Ok(n) => {
+                        sent += n as u64;
+                        buffered -= n;
+                    }
+                    Err(e) if is_retryable(&e) => {}
+                    Err(e) => return Err(e),
+                }
+                continue;
+            }
+
+            let chunk = (len - sent).min(pipe.size as u64) as u32;
+            let (fill, drain) = Op::splice_through(&self.fd, pos, &pipe, socket, chunk)?;
+            let (filled, drained) = (fill.await, drain.await);
+            match filled? {
+                0 => break,
+                n => {
+                    pos += n as u64;
+                    buffered = n;
+                }
+            }
+            match drained {
+                Ok(n) => {
+                    sent += n as u64;
+                    buffered -= n;
+                }
+                // Cancelled by a short splice from the file; the rest is sent from the pipe.
+                Err(e) if e.raw_os_error() == Some(libc::ECANCELED) || is_retryable(&e) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(sent)
+    }
+
     /// Closes the file using the uring asynchronous close operation and returns the possible error
     /// as described in the close(2) man page.
     ///
@@ -622,6 +709,13 @@ impl File {
     }
 }
 
+fn is_retryable(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::WouldBlock | io::ErrorKind::Interrupted
+    )
+}
+
 impl FromRawFd for File {
     unsafe fn from_raw_fd(fd: RawFd) -> Self {
         File::from_shared_fd(SharedFd::new(fd))
@@ -0,0 +1,286 @@
+use super::{File, StatxBuilder};
+use std::{
+    fs::Permissions,
+    io,
+    os::unix::fs::PermissionsExt,
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+/// Metadata information about a file, as returned by statx(2).
+///
+/// This mirrors the subset of [`std::fs::Metadata`] that statx(2) provides. The raw
+/// `libc::statx` structure remains available through [`Metadata::statx`].
+#[derive(Clone)]
+pub struct Metadata {
+    statx: libc::statx,
+}
+
+/// A structure representing the type of a file, as reported in [`Metadata`].
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct FileType {
+    mode: u32,
+}
+
+impl File {
+    /// Queries metadata about the underlying file.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use tokio_uring::fs::File;
+    ///
+    /// tokio_uring::start(async {
+    ///     let f = File::open("foo.txt").await.unwrap();
+    ///     let metadata = f.metadata().await.unwrap();
+    ///
+    ///     println!("{} bytes", metadata.len());
+    ///     f.close().await.unwrap();
+    /// })
+    /// ```
+    pub async fn metadata(&self) -> io::Result<Metadata> {
+        self.statx().await.map(Metadata::from_statx)
+    }
+}
+
+/// Given a path, queries the file system to get information about a file, directory, etc.
+///
+/// Nothing is opened: a single statx(2) is submitted relative to the current working directory.
+/// Symbolic links are followed, so a dangling link results in a `NotFound` error.
+///
+/// # Examples
+///
+/// ```no_run
+/// tokio_uring::start(async {
+///     let metadata = tokio_uring::fs::metadata("foo.txt").await.unwrap();
+///     assert!(metadata.is_file());
+/// })
+/// ```
+pub async fn metadata<P: AsRef<Path>>(path: P) -> io::Result<Metadata> {
+    path_metadata(path.as_ref(), 0).await
+}
+
+/// Queries the metadata about a file without following symlinks.
+///
+/// If `path` is a symbolic link, the metadata returned describes the link itself. This is the
+/// case even when the link target does not exist.
+///
+/// # Examples
+///
+/// ```no_run
+/// tokio_uring::start(async {
+///     let metadata = tokio_uring::fs::symlink_metadata("link").await.unwrap();
+///     assert!(metadata.is_symlink());
+/// })
+/// ```
+pub async fn symlink_metadata<P: AsRef<Path>>(path: P) -> io::Result<Metadata> {
+    path_metadata(path.as_ref(), libc::AT_SYMLINK_NOFOLLOW).await
+}
+
+/// Returns `Ok(true)` if the path points at an existing entity.
+///
+/// Unlike a plain `metadata(path).is_ok()`, this only reports `Ok(false)` when the path is
+/// known not to exist. Errors that leave the answer undetermined, such as permission denied on
+/// a parent directory, are returned as-is. Symbolic links are followed, so a dangling link
+/// reports `Ok(false)`.
+///
+/// # Examples
+///
+/// ```no_run
+/// tokio_uring::start(async {
+///     if tokio_uring::fs::try_exists("foo.txt").await.unwrap() {
+///         println!("found it");
+///     }
+/// })
+/// ```
+pub async fn try_exists<P: AsRef<Path>>(path: P) -> io::Result<bool> {
+    exists(path_metadata(path.as_ref(), 0).await)
+}
+
+/// Like [`try_exists`], but does not follow symbolic links.
+///
+/// A dangling symbolic link reports `Ok(true)` since the link itself exists.
+pub async fn symlink_try_exists<P: AsRef<Path>>(path: P) -> io::Result<bool> {
+    exists(path_metadata(path.as_ref(), libc::AT_SYMLINK_NOFOLLOW).await)
+}
+
+fn exists(res: io::Result<Metadata>) -> io::Result<bool> {
+    match res {
+        Ok(_) => Ok(true),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+async fn path_metadata(path: &Path, flags: i32) -> io::Result<Metadata> {
+    StatxBuilder::new()
+        .flags(flags)
+        .pathname(path)?
+        .statx()
+        .await
+        .map(Metadata::from_statx)
+}
+
+impl Metadata {
+    pub(crate) fn from_statx(statx: libc::statx) -> Metadata {
+        Metadata { statx }
+    }
+
+    /// Returns the file type for this metadata.
+    pub fn file_type(&self) -> FileType {
+        FileType {
+            mode: u32::from(self.statx.stx_mode),
+        }
+    }
+
+    /// Returns `true` if this metadata is for a directory.
+    pub fn is_dir(&self) -> bool {
+        self.file_type().is_dir()
+    }
+
+    /// Returns `true` if this metadata is for a regular file.
+    pub fn is_file(&self) -> bool {
+        self.file_type().is_file()
+    }
+
+    /// Returns `true` if this metadata is for a symbolic link.
+    pub fn is_symlink(&self) -> bool {
+        self.file_type().is_symlink()
+    }
+
+    /// Returns the size of the file, in bytes.
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> u64 {
+        self.statx.stx_size
+    }
+
+    /// Returns the permissions of the file.
+    pub fn permissions(&self) -> Permissions {
+        Permissions::from_mode(self.mode())
+    }
+
+    /// Returns the full mode bits, file type included.
+    pub fn mode(&self) -> u32 {
+        u32::from(self.statx.stx_mode)
+    }
+
+    /// Returns the inode number.
+    pub fn ino(&self) -> u64 {
+        self.statx.stx_ino
+    }
+
+    /// Returns the number of hard links pointing to this file.
+    pub fn nlink(&self) -> u64 {
+        u64::from(self.statx.stx_nlink)
+    }
+
+    /// Returns the user ID of the owner.
+    pub fn uid(&self) -> u32 {
+        self.statx.stx_uid
+    }
+
+    /// Returns the group ID of the owner.
+    pub fn gid(&self) -> u32 {
+        self.statx.stx_gid
+    }
+
+    /// Returns the preferred block size for I/O.
+    pub fn blksize(&self) -> u64 {
+        u64::from(self.statx.stx_blksize)
+    }
+
+    /// Returns the number of 512-byte blocks allocated to the file.
+    pub fn blocks(&self) -> u64 {
+        self.statx.stx_blocks
+    }
+
+    /// Returns the last modification time.
+    pub fn modified(&self) -> io::Result<SystemTime> {
+        self.timestamp(libc::STATX_MTIME, self.statx.stx_mtime)
+    }
+
+    /// Returns the last access time.
+    pub fn accessed(&self) -> io::Result<SystemTime> {
+        self.timestamp(libc::STATX_ATIME, self.statx.stx_atime)
+    }
+
+    /// Returns the creation time.
+    ///
+    /// Not every filesystem records a birth time; an `Unsupported` error is returned when the
+    /// kernel did not fill it in.
+    pub fn created(&self) -> io::Result<SystemTime> {
+        self.timestamp(libc::STATX_BTIME, self.statx.stx_btime)
+    }
+
+    /// Returns the raw statx(2) structure this metadata was built from.
+    pub fn statx(&self) -> &libc::statx {
+        &self.statx
+    }
+
+    fn timestamp(&self, mask: u32, ts: libc::statx_timestamp) -> io::Result<SystemTime> {
+        if self.statx.stx_mask & mask == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "timestamp not available on this platform",
+            ));
+        }
+        let nsec = Duration::from_nanos(u64::from(ts.tv_nsec));
+        if ts.tv_sec >= 0 {
+            Ok(SystemTime::UNIX_EPOCH + Duration::from_secs(ts.tv_sec as u64) + nsec)
+        } else {
+            Ok(SystemTime::UNIX_EPOCH - Duration::from_secs(ts.tv_sec.unsigned_abs()) + nsec)
+        }
+    }
+}
+
+impl std::fmt::Debug for Metadata {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Metadata")
+            .field("file_type", &self.file_type())
+            .field("mode", &format_args!("{:#o}", self.mode()))
+            .field("len", &self.len())
+            .field("ino", &self.ino())
+            .finish_non_exhaustive()
+    }
+}
+
+impl FileType {
+    fn is(&self, fmt: u32) -> bool {
+        self.mode & libc::S_IFMT == fmt
+    }
+
+    /// Tests whether this file type represents a directory.
+    pub fn is_dir(&self) -> bool {
+        self.is(libc::S_IFDIR)
+    }
+
+    /// Tests whether this file type represents a regular file.
+    pub fn is_file(&self) -> bool {
+        self.is(libc::S_IFREG)
+    }
+
+    /// Tests whether this file type represents a symbolic link.
+    pub fn is_symlink(&self) -> bool {
+        self.is(libc::S_IFLNK)
+    }
+
+    /// Tests whether this file type represents a block device.
+    pub fn is_block_device(&self) -> bool {
+        self.is(libc::S_IFBLK)
+    }
+
+    /// Tests whether this file type represents a character device.
+    pub fn is_char_device(&self) -> bool {
+        self.is(libc::S_IFCHR)
+    }
+
+    /// Tests whether this file type represents a FIFO.
+    pub fn is_fifo(&self) -> bool {
+        self.is(libc::S_IFIFO)
+    }
+
+    /// Tests whether this file type represents a socket.
+    pub fn is_socket(&self) -> bool {
+        self.is(libc::S_IFSOCK)
+    }
+}
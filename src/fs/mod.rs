@@ -4,19 +4,55 @@ mod directory;
 pub use directory::create_dir;
 pub use directory::remove_dir;
 
+mod buf_reader;
+pub use buf_reader::BufReader;
+pub use buf_reader::Lines;
+
+mod chunks;
+pub use chunks::Chunks;
+
+mod copy;
+pub use copy::copy;
+
 mod create_dir_all;
 pub use create_dir_all::create_dir_all;
 pub use create_dir_all::DirBuilder;
 
+mod device;
+pub use device::Device;
+
+mod direct_writer;
+pub use direct_writer::DirectWriter;
+
 mod file;
 pub use file::remove_file;
 pub use file::rename;
 pub use file::File;
 
+mod metadata;
+pub use metadata::metadata;
+pub use metadata::symlink_metadata;
+pub use metadata::symlink_try_exists;
+pub use metadata::try_exists;
+pub use metadata::FileType;
+pub use metadata::Metadata;
+
 mod open_options;
-pub use open_options::OpenOptions;
+pub use open_options::{OpenOptions, ResolveFlags};
+
+mod read_link;
+pub use read_link::read_link;
+
+mod reflink;
 
 mod statx;
 pub use statx::is_dir_regfile;
 pub use statx::statx;
+pub use statx::DioAlignment;
 pub use statx::StatxBuilder;
+
+mod write_atomic;
+pub use write_atomic::write_atomic;
+
+mod write_queue;
+pub use write_queue::WriteQueue;
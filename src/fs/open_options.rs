@@ -1,7 +1,9 @@
 use crate::fs::File;
+use crate::io::SharedFd;
 
 use crate::runtime::driver::op::Op;
 use std::io;
+use std::ops::{BitOr, BitOrAssign};
 use std::os::unix::fs::OpenOptionsExt;
 use std::path::Path;
 
@@ -64,6 +66,7 @@ pub struct OpenOptions {
     create_new: bool,
     pub(crate) mode: libc::mode_t,
     pub(crate) custom_flags: libc::c_int,
+    pub(crate) resolve: ResolveFlags,
 }
 
 impl OpenOptions {
@@ -97,6 +100,7 @@ impl OpenOptions {
             create_new: false,
             mode: 0o666,
             custom_flags: 0,
+            resolve: ResolveFlags::empty(),
         }
     }
 
@@ -333,7 +337,82 @@ impl OpenOptions {
     /// [`Other`]: io::ErrorKind::Other
     /// [`PermissionDenied`]: io::ErrorKind::PermissionDenied
     pub async fn open(&self, path: impl AsRef<Path>) -> io::Result<File> {
-        Op::open(path.as_ref(), self)?.await
+        self.open_inner(None, path.as_ref()).await
+    }
+
+    /// Sets how the path is resolved, using the `RESOLVE_*` flags of
+    /// openat2(2).
+    ///
+    /// When any flag is set the file is opened with openat2, which requires
+    /// Linux 5.6 or newer. Violations of the restrictions fail the open, e.g.
+    /// with `EXDEV` when [`ResolveFlags::BENEATH`] is set and the path escapes
+    /// the starting directory, or `ELOOP` when [`ResolveFlags::NO_SYMLINKS`]
+    /// is set and the path contains a symbolic link.
+    ///
+    /// Paths are resolved relative to the current working directory by
+    /// [`open`], or to a directory given to [`open_at`].
+    ///
+    /// [`open`]: OpenOptions::open
+    /// [`open_at`]: OpenOptions::open_at
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::os::unix::fs::OpenOptionsExt;
+    /// use tokio_uring::fs::{OpenOptions, ResolveFlags};
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     tokio_uring::start(async {
+    ///         let root = OpenOptions::new()
+    ///             .read(true)
+    ///             .custom_flags(libc::O_DIRECTORY)
+    ///             .open("/srv/www")
+    ///             .await?;
+    ///
+    ///         let file = OpenOptions::new()
+    ///             .read(true)
+    ///             .resolve(ResolveFlags::BENEATH | ResolveFlags::NO_SYMLINKS)
+    ///             .open_at(&root, "css/site.css")
+    ///             .await?;
+    ///         Ok(())
+    ///     })
+    /// }
+    /// ```
+    pub fn resolve(&mut self, flags: ResolveFlags) -> &mut OpenOptions {
+        self.resolve = flags;
+        self
+    }
+
+    /// Opens a file at `path` relative to the directory `dir`, with the
+    /// options specified by `self`.
+    ///
+    /// `dir` must have been opened as a directory, for example with
+    /// `O_DIRECTORY` in [`custom_flags`]. Absolute paths ignore `dir`, unless
+    /// resolve flags restrict them.
+    ///
+    /// # Errors
+    ///
+    /// See [`open`].
+    ///
+    /// [`custom_flags`]: OpenOptionsExt::custom_flags
+    /// [`open`]: OpenOptions::open
+    pub async fn open_at(&self, dir: &File, path: impl AsRef<Path>) -> io::Result<File> {
+        self.open_inner(Some(&dir.fd), path.as_ref()).await
+    }
+
+    async fn open_inner(&self, dir: Option<&SharedFd>, path: &Path) -> io::Result<File> {
+        loop {
+            match Op::open(dir, path, self)?.await {
+                // openat2 fails with EAGAIN when a concurrent rename or mount
+                // may have raced with the restricted lookup. It is not an
+                // error of the caller's making, so the lookup is redone.
+                Err(e)
+                    if e.raw_os_error() == Some(libc::EAGAIN)
+                        && !self.resolve.is_empty()
+                        && !self.resolve.contains(ResolveFlags::CACHED) => {}
+                res => return res,
+            }
+        }
     }
 
     pub(crate) fn access_mode(&self) -> io::Result<libc::c_int> {
@@ -389,3 +468,69 @@ impl OpenOptionsExt for OpenOptions {
         self
     }
 }
+
+/// Flags restricting how [`OpenOptions`] resolves a path, passed to openat2(2)
+/// as the `resolve` field of `struct open_how`.
+///
+/// Flags are combined with `|`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct ResolveFlags(u64);
+
+impl ResolveFlags {
+    /// Do not permit the path to escape the starting directory, whether
+    /// through `..`, an absolute path or a symbolic link.
+    pub const BENEATH: ResolveFlags = ResolveFlags(libc::RESOLVE_BENEATH);
+
+    /// Treat the starting directory as the root of the filesystem, as if the
+    /// process had called chroot(2) on it.
+    pub const IN_ROOT: ResolveFlags = ResolveFlags(libc::RESOLVE_IN_ROOT);
+
+    /// Do not follow magic links, such as the entries of `/proc/[pid]/fd`.
+    pub const NO_MAGICLINKS: ResolveFlags = ResolveFlags(libc::RESOLVE_NO_MAGICLINKS);
+
+    /// Do not follow any symbolic link, including magic links, in any
+    /// component of the path.
+    pub const NO_SYMLINKS: ResolveFlags = ResolveFlags(libc::RESOLVE_NO_SYMLINKS);
+
+    /// Do not cross a mount point.
+    pub const NO_XDEV: ResolveFlags = ResolveFlags(libc::RESOLVE_NO_XDEV);
+
+    /// Only complete the lookup from the dentry cache, failing with `EAGAIN`
+    /// otherwise. Requires Linux 5.12 or newer. Opens using this flag are not
+    /// retried on `EAGAIN`.
+    pub const CACHED: ResolveFlags = ResolveFlags(libc::RESOLVE_CACHED);
+
+    /// Returns a set with no flags.
+    pub const fn empty() -> ResolveFlags {
+        ResolveFlags(0)
+    }
+
+    /// Returns the raw value of the flags.
+    pub const fn bits(&self) -> u64 {
+        self.0
+    }
+
+    /// Returns `true` if no flag is set.
+    pub const fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns `true` if all flags in `other` are set in `self`.
+    pub const fn contains(&self, other: ResolveFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for ResolveFlags {
+    type Output = ResolveFlags;
+
+    fn bitor(self, rhs: ResolveFlags) -> ResolveFlags {
+        ResolveFlags(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for ResolveFlags {
+    fn bitor_assign(&mut self, rhs: ResolveFlags) {
+        self.0 |= rhs.0;
+    }
+}
@@ -0,0 +1,65 @@
+use crate::io::cstr;
+use std::{
+    ffi::{CString, OsString},
+    io,
+    os::unix::ffi::OsStringExt,
+    path::{Path, PathBuf},
+};
+
+// Initial size of the buffer handed to readlinkat(2); grown as needed.
+const INITIAL_LEN: usize = 256;
+
+/// Reads a symbolic link, returning the file that the link points to.
+///
+/// The target is returned as raw bytes wrapped in a [`PathBuf`]; no UTF-8 validation is
+/// performed.
+///
+/// io_uring has no readlink opcode, so this issues readlinkat(2) on the blocking thread pool,
+/// where a slow filesystem cannot stall the ring.
+///
+/// # Errors
+///
+/// * `path` doesn't exist.
+///      * [`io::ErrorKind`] would be set to `NotFound`
+/// * `path` isn't a symbolic link.
+///      * The raw OS error would be `EINVAL`
+///
+/// # Examples
+///
+/// ```no_run
+/// tokio_uring::start(async {
+///     let target = tokio_uring::fs::read_link("current").await.unwrap();
+///     println!("current -> {}", target.display());
+/// })
+/// ```
+pub async fn read_link<P: AsRef<Path>>(path: P) -> io::Result<PathBuf> {
+    let path = cstr(path.as_ref())?;
+    tokio::task::spawn_blocking(move || read_link_blocking(&path))
+        .await
+        .map_err(io::Error::other)?
+}
+
+fn read_link_blocking(path: &CString) -> io::Result<PathBuf> {
+    let mut buf: Vec<u8> = Vec::with_capacity(INITIAL_LEN);
+
+    loop {
+        let n = syscall!(readlinkat(
+            libc::AT_FDCWD,
+            path.as_ptr(),
+            buf.as_mut_ptr() as *mut libc::c_char,
+            buf.capacity()
+        ))? as usize;
+
+        // A result that exactly fills the buffer may have been truncated, so only a strictly
+        // shorter result is known to be complete.
+        if n < buf.capacity() {
+            // Safety: the kernel initialized the first `n` bytes.
+            unsafe { buf.set_len(n) };
+            buf.shrink_to_fit();
+            return Ok(PathBuf::from(OsString::from_vec(buf)));
+        }
+
+        // Nothing in the buffer is kept, so start over with twice the room.
+        buf = Vec::with_capacity(buf.capacity() * 2);
+    }
+}
@@ -0,0 +1,155 @@
+use super::File;
+use std::io;
+use std::os::unix::io::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+
+// _IOW(0x94, 9, int) and _IOW(0x94, 13, struct file_clone_range). The direction bits differ on a
+// few architectures, and older libc releases do not export these requests.
+#[cfg(not(any(
+    target_arch = "mips",
+    target_arch = "mips64",
+    target_arch = "powerpc",
+    target_arch = "powerpc64",
+    target_arch = "sparc64"
+)))]
+mod request {
+    pub(super) const FICLONE: u64 = 0x4004_9409;
+    pub(super) const FICLONERANGE: u64 = 0x4020_940d;
+}
+#[cfg(any(
+    target_arch = "mips",
+    target_arch = "mips64",
+    target_arch = "powerpc",
+    target_arch = "powerpc64",
+    target_arch = "sparc64"
+))]
+mod request {
+    pub(super) const FICLONE: u64 = 0x8004_9409;
+    pub(super) const FICLONERANGE: u64 = 0x8020_940d;
+}
+
+#[repr(C)]
+struct FileCloneRange {
+    src_fd: i64,
+    src_offset: u64,
+    src_length: u64,
+    dest_offset: u64,
+}
+
+impl File {
+    /// Shares `len` bytes of `src`, starting at `src_off`, with this file at `dst_off`, using the
+    /// FICLONERANGE ioctl.
+    ///
+    /// The range is reflinked: both files refer to the same extents on disk until one of them is
+    /// modified, so the clone completes without copying data. Both files must be on the same
+    /// filesystem, and the filesystem must support reflinks, as btrfs and XFS do. A `len` of 0
+    /// clones everything from `src_off` to the end of `src`.
+    ///
+    /// The ioctl runs on tokio's blocking thread pool, so the ring keeps being serviced while the
+    /// filesystem updates its metadata.
+    ///
+    /// # Errors
+    ///
+    /// * `Unsupported` if the filesystem cannot reflink, or the files are on different
+    ///   filesystems. Callers can fall back to copying, for example with [`fs::copy`].
+    /// * `InvalidInput` if `src_off`, `dst_off` or `len` is not a multiple of the filesystem block
+    ///   size. `len` may be unaligned only if the range ends at the end of `src`.
+    ///
+    /// [`fs::copy`]: crate::fs::copy
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use tokio_uring::fs::File;
+    ///
+    /// tokio_uring::start(async {
+    ///     let src = File::open("image.raw").await.unwrap();
+    ///     let dst = File::create("image-snapshot.raw").await.unwrap();
+    ///
+    ///     // Share the first megabyte.
+    ///     dst.clone_range_from(&src, 0, 0, 1024 * 1024).await.unwrap();
+    /// })
+    /// ```
+    pub async fn clone_range_from(
+        &self,
+        src: &File,
+        src_off: u64,
+        dst_off: u64,
+        len: u64,
+    ) -> io::Result<()> {
+        let src_meta = src.metadata().await?;
+        let block = src_meta.blksize();
+        let mask = block.saturating_sub(1);
+        let to_eof = len == 0 || src_off.checked_add(len) == Some(src_meta.len());
+        if block.is_power_of_two()
+            && (src_off & mask != 0 || dst_off & mask != 0 || (!to_eof && len & mask != 0))
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "clone offsets and length must be multiples of the {} byte filesystem block \
+                     size (got src_off {}, dst_off {}, len {}); only a range ending at the end \
+                     of the source may have an unaligned length",
+                    block, src_off, dst_off, len
+                ),
+            ));
+        }
+
+        clone_blocking(src, self, move |src, dst| {
+            let range = FileCloneRange {
+                src_fd: src as i64,
+                src_offset: src_off,
+                src_length: len,
+                dest_offset: dst_off,
+            };
+            syscall!(ioctl(
+                dst,
+                request::FICLONERANGE as _,
+                &range as *const FileCloneRange
+            ))
+        })
+        .await
+    }
+
+    /// Makes this file a reflinked copy of all of `src`, using the FICLONE ioctl.
+    ///
+    /// The previous contents of this file are discarded. See
+    /// [`clone_range_from`](File::clone_range_from) for the requirements and errors.
+    pub async fn clone_from(&self, src: &File) -> io::Result<()> {
+        clone_blocking(src, self, |src, dst| {
+            syscall!(ioctl(dst, request::FICLONE as _, src as libc::c_int))
+        })
+        .await
+    }
+}
+
+async fn clone_blocking<F>(src: &File, dst: &File, f: F) -> io::Result<()>
+where
+    F: FnOnce(RawFd, RawFd) -> io::Result<libc::c_int> + Send + 'static,
+{
+    // The blocking task may outlive this future if it is dropped, so it gets descriptors of its
+    // own rather than borrowing ones that could be closed and reused meanwhile.
+    let src = dup(src)?;
+    let dst = dup(dst)?;
+    let res = tokio::task::spawn_blocking(move || f(src.as_raw_fd(), dst.as_raw_fd()))
+        .await
+        .map_err(io::Error::other)?;
+
+    match res {
+        Ok(_) => Ok(()),
+        Err(e) if is_unsupported(&e) => Err(io::Error::new(io::ErrorKind::Unsupported, e)),
+        Err(e) => Err(e),
+    }
+}
+
+pub(super) fn dup(file: &File) -> io::Result<OwnedFd> {
+    let fd = syscall!(fcntl(file.as_raw_fd(), libc::F_DUPFD_CLOEXEC, 0))?;
+    // Safety: the descriptor was just created and is owned by nothing else.
+    Ok(unsafe { OwnedFd::from_raw_fd(fd) })
+}
+
+fn is_unsupported(e: &io::Error) -> bool {
+    matches!(
+        e.raw_os_error(),
+        Some(libc::EOPNOTSUPP) | Some(libc::ENOTTY) | Some(libc::EXDEV)
+    )
+}
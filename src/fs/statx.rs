@@ -76,6 +76,58 @@ impl File {
             mask: libc::STATX_ALL,
         }
     }
+
+    /// Returns the direct I/O alignment requirements of this file, as reported by statx(2)
+    /// with `STATX_DIOALIGN`.
+    ///
+    /// Returns `None` when the kernel (before 6.1) or the filesystem does not report them, or
+    /// when the file does not support direct I/O.
+    ///
+    /// [`DirectWriter::with_dio_alignment`](super::DirectWriter::with_dio_alignment) and
+    /// [`Device`](super::Device) check their requests against these requirements.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use tokio_uring::fs::File;
+    ///
+    /// tokio_uring::start(async {
+    ///     let f = File::open("foo.txt").await.unwrap();
+    ///
+    ///     let align = f
+    ///         .dio_alignment()
+    ///         .await
+    ///         .unwrap()
+    ///         .map_or(4096, |a| a.mem_align as usize);
+    ///     println!("allocate buffers aligned to {}", align);
+    ///
+    ///     f.close().await.unwrap();
+    /// })
+    /// ```
+    pub async fn dio_alignment(&self) -> io::Result<Option<DioAlignment>> {
+        let statx = Op::statx(Some(self.fd.clone()), None, 0, libc::STATX_DIOALIGN)?.await?;
+        if statx.stx_mask & libc::STATX_DIOALIGN == 0
+            || statx.stx_dio_mem_align == 0
+            || statx.stx_dio_offset_align == 0
+        {
+            return Ok(None);
+        }
+        Ok(Some(DioAlignment {
+            mem_align: statx.stx_dio_mem_align,
+            offset_align: statx.stx_dio_offset_align,
+        }))
+    }
+}
+
+/// Direct I/O alignment requirements of a file.
+///
+/// Returned by [`File::dio_alignment`] and [`Device::dio_alignment`](super::Device::dio_alignment).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DioAlignment {
+    /// Required alignment, in bytes, of the memory buffers used for direct I/O.
+    pub mem_align: u32,
+    /// Required alignment, in bytes, of file offsets and I/O lengths for direct I/O.
+    pub offset_align: u32,
 }
 
 /// Returns statx(2) metadata for a path via a uring call.
@@ -0,0 +1,120 @@
+use super::reflink::dup;
+use super::{metadata, remove_file, rename, File, OpenOptions};
+use crate::buf::Buffer;
+use crate::Submit;
+use std::{
+    ffi::OsString,
+    io,
+    os::unix::{fs::OpenOptionsExt, io::AsRawFd},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+// Distinguishes temp files created concurrently by the same process.
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Atomically replaces the contents of a file.
+///
+/// The contents are written to a temporary file in the same directory as `path`, which is
+/// synced, renamed over `path`, and then the directory itself is synced so the rename is
+/// durable. Readers observe either the previous contents or the new contents, never a partial
+/// file.
+///
+/// If `path` already exists, its permission bits are carried over to the new file. Otherwise the
+/// file is created with mode `0o666` (before the process umask).
+///
+/// On failure the temporary file is removed and `path` is left untouched.
+///
+/// # Examples
+///
+/// ```no_run
+/// tokio_uring::start(async {
+///     tokio_uring::fs::write_atomic("config.toml", "answer = 42\n").await.unwrap();
+/// })
+/// ```
+pub async fn write_atomic<P: AsRef<Path>, C: AsRef<[u8]>>(path: P, contents: C) -> io::Result<()> {
+    let path = path.as_ref();
+    let (dir, file_name) = match (path.parent(), path.file_name()) {
+        (Some(dir), Some(file_name)) => (dir, file_name),
+        _ => return Err(io::Error::from_raw_os_error(libc::EISDIR)),
+    };
+    // `Path::parent` yields an empty path for a bare file name.
+    let dir = if dir == Path::new("") {
+        Path::new(".")
+    } else {
+        dir
+    };
+
+    let mode = match metadata(path).await {
+        Ok(metadata) => Some(metadata.mode() & 0o7777),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e),
+    };
+
+    let tmp_path = temp_path(dir, file_name);
+    let file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(mode.unwrap_or(0o666))
+        .open(&tmp_path)
+        .await?;
+
+    let res = write_and_sync(&file, contents.as_ref(), mode).await;
+    let res = match (res, file.close().await) {
+        (Ok(()), Ok(())) => rename(&tmp_path, path).await,
+        (Err(e), _) | (Ok(()), Err(e)) => Err(e),
+    };
+    if let Err(e) = res {
+        let _ = remove_file(&tmp_path).await;
+        return Err(e);
+    }
+
+    let dir = OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_DIRECTORY)
+        .open(dir)
+        .await?;
+    let res = dir.sync_all().await;
+    dir.close().await?;
+    res
+}
+
+fn temp_path(dir: &Path, file_name: &std::ffi::OsStr) -> PathBuf {
+    let mut name = OsString::from(".");
+    name.push(file_name);
+    name.push(format!(
+        ".{}.{}.tmp",
+        std::process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    dir.join(name)
+}
+
+async fn write_and_sync(file: &File, contents: &[u8], mode: Option<u32>) -> io::Result<()> {
+    // The open mode is filtered by the umask, so apply the original bits explicitly.
+    if let Some(mode) = mode {
+        // On its own descriptor, as the blocking task may outlive this future if it is dropped.
+        let fd = dup(file)?;
+        tokio::task::spawn_blocking(move || {
+            syscall!(fchmod(fd.as_raw_fd(), mode as libc::mode_t)).map(drop)
+        })
+        .await
+        .map_err(io::Error::other)??;
+    }
+
+    let mut written = 0;
+    while written < contents.len() {
+        let buf = Buffer::new(contents[written..].to_vec());
+        let (n, _) = file
+            .write_at(buf, written as u64)
+            .submit()
+            .await
+            .map_err(|e| e.0)?;
+        if n == 0 {
+            return Err(io::ErrorKind::WriteZero.into());
+        }
+        written += n;
+    }
+
+    file.sync_all().await
+}
@@ -0,0 +1,395 @@
+use super::File;
+use crate::buf::{Buffer, IoBuf};
+use crate::io::{FsyncData, FsyncTransform, SharedFd, UnsubmittedFsync};
+use crate::runtime::CONTEXT;
+use crate::{InFlightOneshot, ReadWriteData, ReadWriteTransform, Unsubmitted};
+use io_uring::squeue::Flags;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::future::{poll_fn, Future};
+use std::io;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
+
+// Longest chain of linked operations submitted at once.
+const MAX_CHAIN: usize = 64;
+
+/// Appends buffers to a [`File`] in order, keeping several writes in flight.
+///
+/// Each buffer passed to [`push`] is assigned the file offset following the previous one.
+/// Queued operations are submitted as chains linked with `IOSQE_IO_LINK`, so the kernel executes
+/// them one after another, in the order they were pushed, without a round trip to user space in
+/// between. A chain is submitted once the previous one has completed.
+///
+/// A short write breaks its chain: the kernel cancels the operations linked after it. The queue
+/// then resubmits the unwritten remainder followed by the cancelled operations, so a later write
+/// never completes before an earlier one has been written in full.
+///
+/// The queue is driven by polling any of the futures it returns. Operations that were pushed but
+/// not yet submitted are discarded when the queue is dropped, so call [`flush`] first.
+///
+/// The first failed operation stops the queue: its future returns the error, and every later
+/// operation fails without being submitted.
+///
+/// [`push`]: WriteQueue::push
+/// [`flush`]: WriteQueue::flush
+///
+/// # Examples
+///
+/// ```no_run
+/// use tokio_uring::fs::{File, WriteQueue};
+/// use tokio_uring::Buffer;
+///
+/// tokio_uring::start(async {
+///     let file = File::create("events.log").await.unwrap();
+///     let queue = WriteQueue::new(file, 0);
+///
+///     for i in 0..100 {
+///         // The returned futures need not be awaited; flush waits for everything.
+///         let _ = queue.push(Buffer::from(format!("event {}\n", i).into_bytes()));
+///     }
+///     queue.sync().await.unwrap();
+/// })
+/// ```
+pub struct WriteQueue {
+    file: File,
+    state: Rc<RefCell<State>>,
+}
+
+struct State {
+    fd: SharedFd,
+    // Offset assigned to the next pushed buffer.
+    next_offset: u64,
+    next_seq: u64,
+    // Every operation with a lower sequence number has completed.
+    completed: u64,
+    // Operations waiting for the current chain to finish.
+    staged: VecDeque<Entry>,
+    // The current chain, in submission order.
+    in_flight: VecDeque<InFlight>,
+    // Remainders and cancelled operations of the current chain, to be submitted first.
+    retry: Vec<Entry>,
+    // Sequence number and error of the first failed operation.
+    failed: Option<(u64, io::Error)>,
+    waiters: Vec<Waker>,
+}
+
+struct Entry {
+    seq: u64,
+    op: Pending,
+}
+
+enum Pending {
+    Write { buf: Buffer, offset: u64 },
+    Sync,
+}
+
+struct InFlight {
+    seq: u64,
+    op: InFlightOp,
+}
+
+enum InFlightOp {
+    Write {
+        offset: u64,
+        len: usize,
+        fut: InFlightOneshot<ReadWriteData, ReadWriteTransform>,
+    },
+    Sync(InFlightOneshot<FsyncData, FsyncTransform>),
+}
+
+// An operation of a chain being built, before it is pushed to the submission queue.
+enum ChainOp {
+    Write(Unsubmitted, u64, usize),
+    Sync(UnsubmittedFsync),
+}
+
+impl WriteQueue {
+    /// Creates a queue that appends to `file` starting at `start_offset`.
+    pub fn new(file: File, start_offset: u64) -> WriteQueue {
+        let state = State {
+            fd: file.fd.clone(),
+            next_offset: start_offset,
+            next_seq: 0,
+            completed: 0,
+            staged: VecDeque::new(),
+            in_flight: VecDeque::new(),
+            retry: Vec::new(),
+            failed: None,
+            waiters: Vec::new(),
+        };
+        WriteQueue {
+            file,
+            state: Rc::new(RefCell::new(state)),
+        }
+    }
+
+    /// Queues the initialized contents of `buf` for writing at the end of the queue.
+    ///
+    /// The write is submitted right away if the queue is idle, and otherwise with the next chain.
+    /// The returned future resolves once this write and every earlier operation have completed.
+    /// It does not need to be awaited for the write to happen.
+    pub fn push(&self, buf: Buffer) -> impl Future<Output = io::Result<()>> {
+        let seq = {
+            let mut state = self.state.borrow_mut();
+            let offset = state.next_offset;
+            state.next_offset += buf.bytes_init() as u64;
+            state.push(Pending::Write { buf, offset })
+        };
+        self.wait(seq)
+    }
+
+    /// Queues an fsync of the file, linked after every write pushed so far.
+    ///
+    /// The returned future resolves once the data written by those writes is durable.
+    pub fn sync(&self) -> impl Future<Output = io::Result<()>> {
+        let seq = self.state.borrow_mut().push(Pending::Sync);
+        self.wait(seq)
+    }
+
+    /// Waits for every queued operation to complete.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the first failed operation, if any.
+    pub async fn flush(&self) -> io::Result<()> {
+        poll_fn(|cx| self.state.borrow_mut().poll_flush(cx)).await
+    }
+
+    /// Returns the offset at which the next pushed buffer will be written.
+    pub fn offset(&self) -> u64 {
+        self.state.borrow().next_offset
+    }
+
+    /// Gets a reference to the underlying file.
+    pub fn get_ref(&self) -> &File {
+        &self.file
+    }
+
+    /// Flushes the queue and returns the underlying file.
+    pub async fn into_inner(self) -> io::Result<File> {
+        self.flush().await?;
+        Ok(self.file)
+    }
+
+    fn wait(&self, seq: u64) -> impl Future<Output = io::Result<()>> {
+        let state = self.state.clone();
+        poll_fn(move |cx| state.borrow_mut().poll_seq(seq, cx))
+    }
+}
+
+impl State {
+    fn push(&mut self, op: Pending) -> u64 {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        if self.failed.is_none() {
+            self.staged.push_back(Entry { seq, op });
+            if self.in_flight.is_empty() {
+                self.submit_chain();
+            }
+        }
+        seq
+    }
+
+    fn poll_seq(&mut self, seq: u64, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.register(cx);
+        // Progress is made even if this operation completes early, so later operations keep
+        // moving while the caller is busy.
+        let _ = self.poll_progress(cx);
+
+        match self.failed {
+            Some((failed, ref e)) if failed <= seq => Poll::Ready(Err(copy_error(e))),
+            _ if seq < self.completed => Poll::Ready(Ok(())),
+            _ => Poll::Pending,
+        }
+    }
+
+    fn poll_flush(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.register(cx);
+        ready!(self.poll_progress(cx));
+        match self.failed {
+            Some((_, ref e)) => Poll::Ready(Err(copy_error(e))),
+            None => Poll::Ready(Ok(())),
+        }
+    }
+
+    // An in-flight operation only wakes the task that polled it last, so every task waiting on
+    // the queue is also woken whenever an operation completes, whichever task drives it.
+    fn register(&mut self, cx: &mut Context<'_>) {
+        if !self.waiters.iter().any(|w| w.will_wake(cx.waker())) {
+            self.waiters.push(cx.waker().clone());
+        }
+    }
+
+    // Drives the chains until every queued operation has completed or the queue has failed.
+    fn poll_progress(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        loop {
+            if self.in_flight.is_empty() {
+                if self.failed.is_some() || self.staged.is_empty() {
+                    return Poll::Ready(());
+                }
+                self.submit_chain();
+            }
+
+            let front = self.in_flight.front_mut().unwrap();
+            match front.op {
+                InFlightOp::Write {
+                    offset,
+                    len,
+                    ref mut fut,
+                } => {
+                    let res = ready!(Pin::new(fut).poll(cx));
+                    let seq = front.seq;
+                    self.in_flight.pop_front();
+                    self.complete_write(seq, offset, len, res);
+                }
+                InFlightOp::Sync(ref mut fut) => {
+                    let res = ready!(Pin::new(fut).poll(cx));
+                    let seq = front.seq;
+                    self.in_flight.pop_front();
+                    self.complete_sync(seq, res);
+                }
+            }
+
+            if self.in_flight.is_empty() {
+                // Resubmit whatever the chain left unfinished before anything queued later.
+                for entry in self.retry.drain(..).rev() {
+                    self.staged.push_front(entry);
+                }
+            }
+            for waker in self.waiters.drain(..) {
+                waker.wake();
+            }
+        }
+    }
+
+    fn complete_write(
+        &mut self,
+        seq: u64,
+        offset: u64,
+        len: usize,
+        res: crate::Result<usize, Buffer>,
+    ) {
+        match res {
+            Ok((n, _)) if n == len => self.succeeded(seq),
+            Ok((0, _)) => self.fail(seq, io::ErrorKind::WriteZero.into()),
+            Ok((n, buf)) => {
+                // The rest of the chain was cancelled. Write the remainder first.
+                self.retry.push(Entry {
+                    seq,
+                    op: Pending::Write {
+                        buf: remainder(&buf, n),
+                        offset: offset + n as u64,
+                    },
+                });
+            }
+            Err(crate::Error(e, buf)) if self.is_cancelled_link(&e) => {
+                self.retry.push(Entry {
+                    seq,
+                    op: Pending::Write { buf, offset },
+                });
+            }
+            Err(crate::Error(e, _)) => self.fail(seq, e),
+        }
+    }
+
+    fn complete_sync(&mut self, seq: u64, res: io::Result<()>) {
+        match res {
+            Ok(()) => self.succeeded(seq),
+            Err(e) if self.is_cancelled_link(&e) => self.retry.push(Entry {
+                seq,
+                op: Pending::Sync,
+            }),
+            Err(e) => self.fail(seq, e),
+        }
+    }
+
+    // An operation cancelled because an earlier one in its chain came up short.
+    fn is_cancelled_link(&self, e: &io::Error) -> bool {
+        !self.retry.is_empty() && e.raw_os_error() == Some(libc::ECANCELED)
+    }
+
+    fn succeeded(&mut self, seq: u64) {
+        if self.retry.is_empty() && self.failed.is_none() {
+            self.completed = seq + 1;
+        }
+    }
+
+    fn fail(&mut self, seq: u64, e: io::Error) {
+        if self.failed.is_none() {
+            self.failed = Some((seq, e));
+            self.staged.clear();
+            self.retry.clear();
+        }
+    }
+
+    fn submit_chain(&mut self) {
+        let handle = CONTEXT
+            .with(|x| x.handle())
+            .expect("Not in a runtime context");
+
+        // The whole chain is pushed to the submission queue at once, so no other operation can
+        // end up between two linked entries.
+        let n = self.staged.len().min(MAX_CHAIN).min(handle.sq_capacity());
+        let mut chain = Vec::with_capacity(n);
+        let mut sqes = Vec::with_capacity(n);
+        for (i, entry) in self.staged.drain(..n).enumerate() {
+            let flags = if i + 1 < n {
+                Flags::IO_LINK
+            } else {
+                Flags::empty()
+            };
+            match entry.op {
+                Pending::Write { buf, offset } => {
+                    let len = buf.bytes_init();
+                    let op = Unsubmitted::write_at(&self.fd, buf, offset).set_flags(flags);
+                    sqes.push(op.sqe.clone());
+                    chain.push((entry.seq, ChainOp::Write(op, offset, len)));
+                }
+                Pending::Sync => {
+                    let op = UnsubmittedFsync::fsync(&self.fd, false).set_flags(flags);
+                    sqes.push(op.sqe.clone());
+                    chain.push((entry.seq, ChainOp::Sync(op)));
+                }
+            }
+        }
+
+        let indices = handle.submit_ops(sqes.into_iter());
+        for ((seq, op), index) in chain.into_iter().zip(indices) {
+            let op = match op {
+                ChainOp::Write(op, offset, len) => InFlightOp::Write {
+                    offset,
+                    len,
+                    fut: op.inflight(index),
+                },
+                ChainOp::Sync(op) => InFlightOp::Sync(op.inflight(index)),
+            };
+            self.in_flight.push_back(InFlight { seq, op });
+        }
+    }
+}
+
+// Copies the initialized bytes of `buf` after the first `n`.
+fn remainder(buf: &Buffer, n: usize) -> Buffer {
+    let mut rest = Vec::with_capacity(buf.bytes_init() - n);
+    let mut skip = n;
+    for i in 0..buf.segment_count() {
+        let segment = &buf[i];
+        if skip >= segment.len() {
+            skip -= segment.len();
+            continue;
+        }
+        rest.extend_from_slice(&segment[skip..]);
+        skip = 0;
+    }
+    Buffer::from(rest)
+}
+
+// Every future waiting on a failed operation reports the same error.
+fn copy_error(e: &io::Error) -> io::Error {
+    match e.raw_os_error() {
+        Some(code) => io::Error::from_raw_os_error(code),
+        None => io::Error::new(e.kind(), e.to_string()),
+    }
+}
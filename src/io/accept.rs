@@ -1,9 +1,71 @@
-use crate::io::{SharedFd, Socket};
+use crate::io::{DirectFd, DirectTableFull, SharedFd, Socket};
 use crate::runtime::driver::op;
 use crate::runtime::driver::op::{Completable, Op};
 use crate::runtime::CONTEXT;
-use std::net::SocketAddr;
-use std::{boxed::Box, io};
+use futures_core::Stream;
+use slab::Slab;
+use std::cell::{Cell, RefCell};
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
+use std::{boxed::Box, fmt, io, ops};
+
+/// Flags set on the descriptors of accepted connections, passed to the `accept_with_flags`
+/// method of the listener types.
+///
+/// Plain `accept` uses [`CLOEXEC`](AcceptFlags::CLOEXEC), so connections do not leak into
+/// programs the process executes. Combine flags with `|`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct AcceptFlags(libc::c_int);
+
+impl AcceptFlags {
+    /// No flags: the descriptor is inherited across `exec`.
+    pub const NONE: AcceptFlags = AcceptFlags(0);
+
+    /// `SOCK_CLOEXEC`: the descriptor is closed on `exec`.
+    pub const CLOEXEC: AcceptFlags = AcceptFlags(libc::SOCK_CLOEXEC);
+
+    /// `SOCK_NONBLOCK`: the descriptor is in non-blocking mode.
+    ///
+    /// This is for connections handed to code outside the runtime, through `into_std` or
+    /// `into_raw_fd`. io_uring honors the mode, so operations on the stream fail with
+    /// `EAGAIN` instead of waiting.
+    pub const NONBLOCK: AcceptFlags = AcceptFlags(libc::SOCK_NONBLOCK);
+
+    /// Returns `true` if the flags include [`CLOEXEC`](AcceptFlags::CLOEXEC).
+    pub fn is_cloexec(self) -> bool {
+        self.0 & libc::SOCK_CLOEXEC != 0
+    }
+
+    /// Returns `true` if the flags include [`NONBLOCK`](AcceptFlags::NONBLOCK).
+    pub fn is_nonblock(self) -> bool {
+        self.0 & libc::SOCK_NONBLOCK != 0
+    }
+}
+
+impl Default for AcceptFlags {
+    fn default() -> AcceptFlags {
+        AcceptFlags::CLOEXEC
+    }
+}
+
+impl ops::BitOr for AcceptFlags {
+    type Output = AcceptFlags;
+
+    fn bitor(self, other: AcceptFlags) -> AcceptFlags {
+        AcceptFlags(self.0 | other.0)
+    }
+}
+
+impl fmt::Debug for AcceptFlags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AcceptFlags")
+            .field("cloexec", &self.is_cloexec())
+            .field("nonblock", &self.is_nonblock())
+            .finish()
+    }
+}
 
 pub(crate) struct Accept {
     fd: SharedFd,
@@ -11,7 +73,7 @@ pub(crate) struct Accept {
 }
 
 impl Op<Accept> {
-    pub(crate) fn accept(fd: &SharedFd) -> io::Result<Op<Accept>> {
+    pub(crate) fn accept(fd: &SharedFd, flags: AcceptFlags) -> io::Result<Op<Accept>> {
         use io_uring::{opcode, types};
 
         let socketaddr = Box::new((
@@ -30,7 +92,7 @@ impl Op<Accept> {
                         &mut accept.socketaddr.0 as *mut _ as *mut _,
                         &mut accept.socketaddr.1,
                     )
-                    .flags(libc::O_CLOEXEC)
+                    .flags(flags.0)
                     .build()
                 },
             )
@@ -39,7 +101,7 @@ impl Op<Accept> {
 }
 
 impl Completable for Accept {
-    type Output = io::Result<(Socket, Option<SocketAddr>)>;
+    type Output = io::Result<(Socket, socket2::SockAddr)>;
 
     fn complete(self, cqe: op::CqeResult) -> Self::Output {
         let fd = cqe.result?;
@@ -52,6 +114,273 @@ impl Completable for Accept {
                 Ok(())
             })?
         };
-        Ok((socket, addr.as_socket()))
+        Ok((socket, addr))
+    }
+}
+
+/// Holds back the accepts of a listener while it is paused. Clones share the state.
+///
+/// Accepts in flight when the gate closes are cancelled by their pollers, which register to
+/// be woken for that whether or not they are waiting to be let through. Each poller holds
+/// one slot for its waker, through a [`GateWaiter`], until it is dropped.
+#[derive(Clone, Default)]
+pub(crate) struct AcceptGate {
+    inner: Rc<Gate>,
+}
+
+#[derive(Default)]
+struct Gate {
+    paused: Cell<bool>,
+    wakers: RefCell<Slab<Waker>>,
+}
+
+impl AcceptGate {
+    pub(crate) fn set_paused(&self, paused: bool) {
+        if self.inner.paused.replace(paused) != paused {
+            for (_, waker) in self.inner.wakers.borrow().iter() {
+                waker.wake_by_ref();
+            }
+        }
+    }
+
+    pub(crate) fn is_paused(&self) -> bool {
+        self.inner.paused.get()
+    }
+
+    /// Returns a handle for a stream of accepts to wait on the gate through.
+    pub(crate) fn waiter(&self) -> GateWaiter {
+        GateWaiter {
+            gate: self.clone(),
+            key: None,
+        }
+    }
+}
+
+/// The slot of one stream of accepts in the wakers of an [`AcceptGate`], freed when dropped.
+pub(crate) struct GateWaiter {
+    gate: AcceptGate,
+    key: Option<usize>,
+}
+
+impl GateWaiter {
+    pub(crate) fn is_paused(&self) -> bool {
+        self.gate.is_paused()
+    }
+
+    // Has the task woken the next time the gate is paused or resumed, in place of the task
+    // registered before.
+    pub(crate) fn register(&mut self, waker: &Waker) {
+        let mut wakers = self.gate.inner.wakers.borrow_mut();
+        match self.key {
+            Some(key) => {
+                if !wakers[key].will_wake(waker) {
+                    wakers[key] = waker.clone();
+                }
+            }
+            None => self.key = Some(wakers.insert(waker.clone())),
+        }
+    }
+}
+
+impl Drop for GateWaiter {
+    fn drop(&mut self) {
+        if let Some(key) = self.key {
+            self.gate.inner.wakers.borrow_mut().remove(key);
+        }
+    }
+}
+
+/// A single-shot accept, of a connection of type `Conn`.
+pub(crate) trait AcceptOp:
+    Completable<Output = io::Result<(Self::Conn, socket2::SockAddr)>> + Unpin + Sized + 'static
+{
+    type Conn;
+
+    fn submit(fd: &SharedFd, flags: AcceptFlags) -> io::Result<Op<Self>>;
+}
+
+impl AcceptOp for Accept {
+    type Conn = Socket;
+
+    fn submit(fd: &SharedFd, flags: AcceptFlags) -> io::Result<Op<Self>> {
+        Op::accept(fd, flags)
+    }
+}
+
+/// A stream of connections accepted on a listener, with one single-shot accept in flight at
+/// a time, which every kernel supports.
+///
+/// While the gate is closed, the stream is pending, with the accept in flight cancelled. A
+/// connection it accepted before the cancellation took effect is still yielded.
+pub(crate) struct Incoming<T: AcceptOp = Accept> {
+    fd: SharedFd,
+    gate: GateWaiter,
+    flags: AcceptFlags,
+    accept: Option<Op<T>>,
+    // Whether the accept in flight was cancelled for the gate.
+    cancelled: bool,
+}
+
+impl<T: AcceptOp> Incoming<T> {
+    pub(crate) fn new(fd: &SharedFd, gate: &AcceptGate, flags: AcceptFlags) -> Incoming<T> {
+        Incoming {
+            fd: fd.clone(),
+            gate: gate.waiter(),
+            flags,
+            accept: None,
+            cancelled: false,
+        }
+    }
+}
+
+impl<T: AcceptOp> Stream for Incoming<T> {
+    type Item = io::Result<(T::Conn, socket2::SockAddr)>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        this.gate.register(cx.waker());
+        loop {
+            match this.accept.as_mut() {
+                None if this.gate.is_paused() => return Poll::Pending,
+                None => {
+                    this.accept = Some(T::submit(&this.fd, this.flags)?);
+                    this.cancelled = false;
+                }
+                Some(op) => {
+                    if this.gate.is_paused() && !this.cancelled {
+                        op.cancel();
+                        this.cancelled = true;
+                    }
+                    let res = ready!(Pin::new(op).poll(cx));
+                    this.accept = None;
+                    match res {
+                        Err(e) if this.cancelled && e.raw_os_error() == Some(libc::ECANCELED) => {}
+                        res => return Poll::Ready(Some(res)),
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<T: AcceptOp> Drop for Incoming<T> {
+    fn drop(&mut self) {
+        if let Some(mut op) = self.accept.take() {
+            // The accept may have completed before the cancellation reached it, with the
+            // connection sitting in its completion; close it rather than leak it.
+            op.cancel();
+            let mut cx = Context::from_waker(futures_util::task::noop_waker_ref());
+            if let Poll::Ready(res) = Pin::new(&mut op).poll(&mut cx) {
+                drop(res);
+            }
+        }
+    }
+}
+
+/// Accept a connection into a free slot of the direct descriptor table.
+pub(crate) struct AcceptDirect {
+    fd: SharedFd,
+    socketaddr: Box<(libc::sockaddr_storage, libc::socklen_t)>,
+}
+
+impl Op<AcceptDirect> {
+    pub(crate) fn accept_direct(fd: &SharedFd) -> io::Result<Op<AcceptDirect>> {
+        use io_uring::{opcode, types};
+
+        let socketaddr = Box::new((
+            unsafe { std::mem::zeroed() },
+            std::mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t,
+        ));
+        CONTEXT.with(|x| {
+            x.handle().expect("Not in a runtime context").submit_op(
+                AcceptDirect {
+                    fd: fd.clone(),
+                    socketaddr,
+                },
+                |accept| {
+                    // Direct descriptors are not inherited by children, and the kernel rejects
+                    // SOCK_CLOEXEC for them.
+                    opcode::Accept::new(
+                        types::Fd(accept.fd.raw_fd()),
+                        &mut accept.socketaddr.0 as *mut _ as *mut _,
+                        &mut accept.socketaddr.1,
+                    )
+                    .file_index(Some(types::DestinationSlot::auto_target()))
+                    .build()
+                },
+            )
+        })
+    }
+}
+
+impl AcceptOp for AcceptDirect {
+    type Conn = DirectFd;
+
+    // Direct descriptors take no flags.
+    fn submit(fd: &SharedFd, _flags: AcceptFlags) -> io::Result<Op<Self>> {
+        Op::accept_direct(fd)
+    }
+}
+
+impl Completable for AcceptDirect {
+    type Output = io::Result<(DirectFd, socket2::SockAddr)>;
+
+    fn complete(self, cqe: op::CqeResult) -> Self::Output {
+        let slot = match cqe.result {
+            Ok(slot) => slot,
+            Err(e) if e.raw_os_error() == Some(libc::ENFILE) => {
+                return Err(io::Error::other(DirectTableFull))
+            }
+            Err(e) => return Err(e),
+        };
+        let fd = DirectFd::new(slot);
+        let (_, addr) = unsafe {
+            socket2::SockAddr::init(move |addr_storage, len| {
+                self.socketaddr.0.clone_into(&mut *addr_storage);
+                *len = self.socketaddr.1;
+                Ok(())
+            })?
+        };
+        Ok((fd, addr))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::AcceptGate;
+    use futures_util::task::{waker, ArcWake};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    struct Counter(AtomicUsize);
+
+    impl ArcWake for Counter {
+        fn wake_by_ref(arc_self: &Arc<Self>) {
+            arc_self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn gate_keeps_one_waker_per_waiter() {
+        let gate = AcceptGate::default();
+        let mut waiter = gate.waiter();
+        let counters: Vec<_> = (0..100)
+            .map(|_| Arc::new(Counter(AtomicUsize::new(0))))
+            .collect();
+        for counter in &counters {
+            waiter.register(&waker(counter.clone()));
+            waiter.register(&waker(counter.clone()));
+        }
+        assert_eq!(gate.inner.wakers.borrow().len(), 1);
+
+        // Only the task that polled last is woken, on every change of state.
+        gate.set_paused(true);
+        gate.set_paused(true);
+        gate.set_paused(false);
+        assert_eq!(counters[99].0.load(Ordering::Relaxed), 2);
+        assert_eq!(counters[0].0.load(Ordering::Relaxed), 0);
+
+        drop(waiter);
+        assert_eq!(gate.inner.wakers.borrow().len(), 0);
     }
 }
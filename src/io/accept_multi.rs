@@ -0,0 +1,178 @@
+use crate::io::accept::Accept;
+use crate::io::{AcceptFlags, AcceptGate, GateWaiter, SharedFd, Socket};
+use crate::runtime::driver::op::{Completable, CqeResult, MultiCQEStream, Op};
+use crate::runtime::CONTEXT;
+use futures_core::Stream;
+use io_uring::cqueue;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// A multishot accept, completing once per accepted connection.
+pub(crate) struct AcceptMulti {
+    /// Holds a strong ref to the FD, preventing the file from being closed
+    /// while the operation is in-flight.
+    #[allow(dead_code)]
+    fd: SharedFd,
+}
+
+impl Op<AcceptMulti, MultiCQEStream> {
+    pub(crate) fn accept_multi(fd: &SharedFd) -> io::Result<Self> {
+        use io_uring::{opcode, types};
+
+        CONTEXT.with(|x| {
+            x.handle().expect("Not in a runtime context").submit_op(
+                AcceptMulti { fd: fd.clone() },
+                |accept| {
+                    opcode::AcceptMulti::new(types::Fd(accept.fd.raw_fd()))
+                        .flags(libc::O_CLOEXEC)
+                        .build()
+                },
+            )
+        })
+    }
+}
+
+impl Completable for AcceptMulti {
+    type Output = io::Result<Socket>;
+
+    fn complete(self, cqe: CqeResult) -> Self::Output {
+        accepted(cqe)
+    }
+}
+
+fn accepted(cqe: CqeResult) -> io::Result<Socket> {
+    let fd = cqe.result?;
+    Ok(Socket {
+        fd: SharedFd::new(fd as i32),
+    })
+}
+
+enum State {
+    /// Nothing in flight; the next poll submits.
+    Idle,
+    Multishot(Op<AcceptMulti, MultiCQEStream>),
+    Single(Op<Accept>),
+}
+
+/// A stream of sockets accepted on a listener.
+///
+/// Uses a single multishot accept, re-armed whenever the kernel ends it. Kernels without
+/// multishot accept get one single-shot accept in flight at a time instead.
+///
+/// As with `Incoming`, the stream is pending while the gate is closed, and yields the
+/// connections accepted before the cancellation of the operation in flight took effect.
+pub(crate) struct AcceptStream {
+    fd: SharedFd,
+    gate: GateWaiter,
+    state: State,
+    // Whether the operation in flight was cancelled for the gate.
+    cancelled: bool,
+    multishot: bool,
+    // Whether the multishot accept has completed successfully before, which rules out treating
+    // an EINVAL as coming from a kernel that does not know the flag.
+    confirmed: bool,
+}
+
+impl AcceptStream {
+    pub(crate) fn new(fd: &SharedFd, gate: &AcceptGate) -> AcceptStream {
+        // Multishot accept arrived in 5.19, together with IORING_OP_SOCKET, which unlike the
+        // flag can be probed for.
+        let multishot = CONTEXT.with(|x| {
+            x.handle()
+                .expect("Not in a runtime context")
+                .is_supported(io_uring::opcode::Socket::CODE)
+        });
+        AcceptStream {
+            fd: fd.clone(),
+            gate: gate.waiter(),
+            state: State::Idle,
+            cancelled: false,
+            multishot,
+            confirmed: false,
+        }
+    }
+}
+
+impl Stream for AcceptStream {
+    type Item = io::Result<Socket>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        this.gate.register(cx.waker());
+        loop {
+            let paused = this.gate.is_paused();
+            match &mut this.state {
+                State::Idle if paused => return Poll::Pending,
+                State::Idle => {
+                    this.state = if this.multishot {
+                        State::Multishot(Op::accept_multi(&this.fd)?)
+                    } else {
+                        State::Single(Op::accept(&this.fd, AcceptFlags::default())?)
+                    };
+                    this.cancelled = false;
+                }
+                State::Multishot(op) => {
+                    if paused && !this.cancelled {
+                        op.cancel();
+                        this.cancelled = true;
+                    }
+                    let cqe = match op.poll_next_cqe(cx) {
+                        Poll::Ready(cqe) => cqe,
+                        Poll::Pending => return Poll::Pending,
+                    };
+                    if !cqueue::more(cqe.flags) {
+                        // Terminated, by an error or by the kernel running out of room for
+                        // completions; the next poll re-arms.
+                        this.state = State::Idle;
+                    }
+                    match cqe.result {
+                        Err(ref e) if e.raw_os_error() == Some(libc::EINVAL) && !this.confirmed => {
+                            this.multishot = false;
+                        }
+                        Err(ref e)
+                            if this.cancelled && e.raw_os_error() == Some(libc::ECANCELED) => {}
+                        _ => {
+                            this.confirmed |= cqe.result.is_ok();
+                            return Poll::Ready(Some(accepted(cqe)));
+                        }
+                    }
+                }
+                State::Single(op) => {
+                    if paused && !this.cancelled {
+                        op.cancel();
+                        this.cancelled = true;
+                    }
+                    let res = match Pin::new(op).poll(cx) {
+                        Poll::Ready(res) => res,
+                        Poll::Pending => return Poll::Pending,
+                    };
+                    this.state = State::Idle;
+                    match res {
+                        Err(e) if this.cancelled && e.raw_os_error() == Some(libc::ECANCELED) => {}
+                        res => return Poll::Ready(Some(res.map(|(socket, _)| socket))),
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Drop for AcceptStream {
+    fn drop(&mut self) {
+        if let State::Multishot(op) = &mut self.state {
+            // Connections the kernel already accepted are sitting in the op's completions;
+            // close them rather than leaving them to the peer's timeout.
+            op.cancel();
+            let mut cx = Context::from_waker(futures_util::task::noop_waker_ref());
+            while let Poll::Ready(cqe) = op.poll_next_cqe(&mut cx) {
+                let more = cqueue::more(cqe.flags);
+                drop(accepted(cqe));
+                if !more {
+                    break;
+                }
+            }
+        }
+    }
+}
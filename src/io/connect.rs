@@ -3,6 +3,7 @@ use crate::runtime::driver::op::{Completable, CqeResult, Op};
 use crate::runtime::CONTEXT;
 use socket2::SockAddr;
 use std::io;
+use std::time::Duration;
 
 /// Open a file
 pub(crate) struct Connect {
@@ -10,6 +11,9 @@ pub(crate) struct Connect {
     // this avoids a UAF (UAM?) if the future is moved, but not if the future is
     // dropped. no Op can be dropped before completion in tokio-uring land right now.
     socket_addr: Box<SockAddr>,
+    // Read by the kernel when the linked timeout is submitted, which may be after this op is
+    // created.
+    timeout: Option<Box<io_uring::types::Timespec>>,
 }
 
 impl Op<Connect> {
@@ -22,6 +26,7 @@ impl Op<Connect> {
                 Connect {
                     fd: fd.clone(),
                     socket_addr: Box::new(socket_addr),
+                    timeout: None,
                 },
                 |connect| {
                     opcode::Connect::new(
@@ -34,12 +39,54 @@ impl Op<Connect> {
             )
         })
     }
+
+    /// Submit a request to connect, which the kernel cancels once `timeout` expires.
+    pub(crate) fn connect_timeout(
+        fd: &SharedFd,
+        socket_addr: SockAddr,
+        timeout: Duration,
+    ) -> io::Result<Op<Connect>> {
+        use io_uring::{opcode, types};
+
+        let timespec = types::Timespec::new()
+            .sec(timeout.as_secs())
+            .nsec(timeout.subsec_nanos());
+
+        CONTEXT.with(|x| {
+            x.handle()
+                .expect("Not in a runtime context")
+                .submit_op_linked(
+                    Connect {
+                        fd: fd.clone(),
+                        socket_addr: Box::new(socket_addr),
+                        timeout: Some(Box::new(timespec)),
+                    },
+                    |connect| {
+                        let sqe = opcode::Connect::new(
+                            types::Fd(connect.fd.raw_fd()),
+                            connect.socket_addr.as_ptr(),
+                            connect.socket_addr.len(),
+                        )
+                        .build();
+                        let timeout =
+                            opcode::LinkTimeout::new(&**connect.timeout.as_ref().unwrap()).build();
+                        (sqe, timeout)
+                    },
+                )
+        })
+    }
 }
 
 impl Completable for Connect {
     type Output = io::Result<()>;
 
     fn complete(self, cqe: CqeResult) -> Self::Output {
-        cqe.result.map(|_| ())
+        match cqe.result {
+            // The linked timeout expired and cancelled the connect.
+            Err(e) if self.timeout.is_some() && e.raw_os_error() == Some(libc::ECANCELED) => Err(
+                io::Error::new(io::ErrorKind::TimedOut, "connection timed out"),
+            ),
+            res => res.map(|_| ()),
+        }
     }
 }
@@ -0,0 +1,199 @@
+use crate::buf::Buffer;
+use crate::runtime::driver::op::{Completable, CqeResult, Op};
+use crate::runtime::CONTEXT;
+use crate::WithBuffer;
+use io_uring::{opcode, squeue, types};
+use std::rc::Rc;
+use std::{fmt, io};
+
+/// The error returned when the runtime's table of direct descriptors has no free slot left.
+///
+/// It is wrapped in an [`io::Error`]; check for it with
+/// `err.get_ref().map_or(false, |e| e.is::<DirectTableFull>())`. Slots are freed as the
+/// descriptors in them are closed.
+#[derive(Debug)]
+pub struct DirectTableFull;
+
+impl fmt::Display for DirectTableFull {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("no free slot in the direct descriptor table")
+    }
+}
+
+impl std::error::Error for DirectTableFull {}
+
+/// A descriptor in the runtime's table of direct descriptors, used by SQEs in place of a
+/// file descriptor.
+///
+/// Like `SharedFd`, in-flight operations hold clones, so the slot is only freed, and made
+/// available for reuse, once none of them can still resolve it.
+#[derive(Clone)]
+pub(crate) struct DirectFd {
+    inner: Rc<Slot>,
+}
+
+struct Slot(u32);
+
+impl DirectFd {
+    pub(crate) fn new(slot: u32) -> DirectFd {
+        DirectFd {
+            inner: Rc::new(Slot(slot)),
+        }
+    }
+
+    pub(crate) fn slot(&self) -> u32 {
+        self.inner.0
+    }
+
+    fn fixed(&self) -> types::Fixed {
+        types::Fixed(self.inner.0)
+    }
+
+    /// Frees the slot, unless operations are still in flight on it, in which case the last
+    /// of them frees it as it completes.
+    pub(crate) fn close(self) -> io::Result<()> {
+        match Rc::try_unwrap(self.inner) {
+            Ok(slot) => {
+                let res = free(slot.0);
+                std::mem::forget(slot);
+                res
+            }
+            Err(_) => Ok(()),
+        }
+    }
+}
+
+fn free(slot: u32) -> io::Result<()> {
+    match CONTEXT.with(|x| x.handle()) {
+        Some(handle) => handle.free_direct(slot),
+        // The table went away with the runtime.
+        None => Ok(()),
+    }
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        let _ = free(self.0);
+    }
+}
+
+/// Read into or write from a buffer, through a direct descriptor.
+pub(crate) struct DirectRw {
+    /// Holds a strong ref to the slot, so it is not reused while the operation is in
+    /// flight.
+    #[allow(dead_code)]
+    fd: DirectFd,
+
+    buf: Buffer,
+
+    // The segments read or written by the kernel.
+    iovecs: Vec<libc::iovec>,
+
+    // Describes `iovecs` to a send; `None` for reads.
+    msghdr: Option<Box<libc::msghdr>>,
+
+    read: bool,
+}
+
+impl Op<DirectRw> {
+    /// Reads into the capacity of `buf`.
+    pub(crate) fn direct_read(fd: &DirectFd, buf: Buffer) -> io::Result<Op<DirectRw>> {
+        let iovecs = buf.spare_iovecs_from(0);
+        submit(
+            DirectRw {
+                fd: fd.clone(),
+                buf,
+                iovecs,
+                msghdr: None,
+                read: true,
+            },
+            |rw| {
+                opcode::Readv::new(rw.fd.fixed(), rw.iovecs.as_ptr(), rw.iovecs.len() as _).build()
+            },
+        )
+    }
+
+    /// Sends the initialized bytes of `buf` from index `from` on, without raising `SIGPIPE`
+    /// should the connection be closed.
+    pub(crate) fn direct_write(
+        fd: &DirectFd,
+        buf: Buffer,
+        from: usize,
+    ) -> io::Result<Op<DirectRw>> {
+        let iovecs = buf.init_iovecs_from(from);
+        // Safety: a zeroed msghdr is valid.
+        let msghdr: Box<libc::msghdr> = Box::new(unsafe { std::mem::zeroed() });
+        submit(
+            DirectRw {
+                fd: fd.clone(),
+                buf,
+                iovecs,
+                msghdr: Some(msghdr),
+                read: false,
+            },
+            |rw| {
+                let msghdr = rw.msghdr.as_mut().unwrap();
+                msghdr.msg_iov = rw.iovecs.as_mut_ptr();
+                msghdr.msg_iovlen = rw.iovecs.len() as _;
+                opcode::SendMsg::new(rw.fd.fixed(), msghdr.as_ref() as *const _)
+                    .flags(libc::MSG_NOSIGNAL as u32)
+                    .build()
+            },
+        )
+    }
+}
+
+fn submit<T: Completable>(data: T, f: impl FnOnce(&mut T) -> squeue::Entry) -> io::Result<Op<T>> {
+    CONTEXT.with(|x| {
+        x.handle()
+            .expect("Not in a runtime context")
+            .submit_op(data, f)
+    })
+}
+
+impl Completable for DirectRw {
+    type Output = crate::Result<usize, Buffer>;
+
+    fn complete(self, cqe: CqeResult) -> Self::Output {
+        let mut buf = self.buf;
+        let n = match cqe.result {
+            Ok(n) => n as usize,
+            Err(e) => return Err(e).with_buffer(buf),
+        };
+        if self.read {
+            // Safety: the kernel wrote `n` bytes across the segments, in order.
+            unsafe { buf.set_init(n) };
+        }
+        Ok((n, buf))
+    }
+}
+
+/// Shut down one or both halves of a connection, through a direct descriptor.
+pub(crate) struct DirectShutdown {
+    #[allow(dead_code)]
+    fd: DirectFd,
+}
+
+impl Op<DirectShutdown> {
+    pub(crate) fn direct_shutdown(
+        fd: &DirectFd,
+        how: std::net::Shutdown,
+    ) -> io::Result<Op<DirectShutdown>> {
+        let how = match how {
+            std::net::Shutdown::Read => libc::SHUT_RD,
+            std::net::Shutdown::Write => libc::SHUT_WR,
+            std::net::Shutdown::Both => libc::SHUT_RDWR,
+        };
+        submit(DirectShutdown { fd: fd.clone() }, |shutdown| {
+            opcode::Shutdown::new(shutdown.fd.fixed(), how).build()
+        })
+    }
+}
+
+impl Completable for DirectShutdown {
+    type Output = io::Result<()>;
+
+    fn complete(self, cqe: CqeResult) -> Self::Output {
+        cqe.result.map(|_| ())
+    }
+}
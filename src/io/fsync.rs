@@ -3,7 +3,8 @@ use std::io;
 use crate::io::SharedFd;
 use crate::runtime::driver::op::{Completable, CqeResult, Op};
 use crate::runtime::CONTEXT;
-use io_uring::{opcode, types};
+use crate::{OneshotOutputTransform, UnsubmittedOneshot};
+use io_uring::{cqueue, opcode, types};
 
 pub(crate) struct Fsync {
     fd: SharedFd,
@@ -41,3 +42,44 @@ impl Completable for Fsync {
         cqe.result.map(|_| ())
     }
 }
+
+pub(crate) type UnsubmittedFsync = UnsubmittedOneshot<FsyncData, FsyncTransform>;
+
+pub(crate) struct FsyncData {
+    /// Holds a strong ref to the FD, preventing the file from being closed
+    /// while the operation is in-flight.
+    _fd: SharedFd,
+}
+
+pub(crate) struct FsyncTransform;
+
+impl OneshotOutputTransform for FsyncTransform {
+    type Output = io::Result<()>;
+
+    type StoredData = FsyncData;
+
+    fn transform_oneshot_output(self, _data: FsyncData, cqe: cqueue::Entry) -> io::Result<()> {
+        let res = cqe.result();
+        if res < 0 {
+            Err(io::Error::from_raw_os_error(-res))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl UnsubmittedFsync {
+    /// An fsync, or fdatasync when `datasync` is set, that can be linked after another op.
+    pub(crate) fn fsync(fd: &SharedFd, datasync: bool) -> Self {
+        let mut opcode = opcode::Fsync::new(types::Fd(fd.raw_fd()));
+        if datasync {
+            opcode = opcode.flags(types::FsyncFlags::DATASYNC);
+        }
+
+        Self::new(
+            FsyncData { _fd: fd.clone() },
+            FsyncTransform,
+            opcode.build(),
+        )
+    }
+}
@@ -1,28 +1,59 @@
 mod accept;
+pub use accept::AcceptFlags;
+pub(crate) use accept::{AcceptGate, GateWaiter, Incoming};
+
+mod accept_multi;
+pub(crate) use accept_multi::AcceptStream;
 
 mod close;
 
 mod connect;
 
+mod direct;
+pub(crate) use direct::DirectFd;
+pub use direct::DirectTableFull;
+
 mod fallocate;
 
 mod fsync;
+pub(crate) use fsync::{FsyncData, FsyncTransform, UnsubmittedFsync};
 
 mod mkdir_at;
 
+mod msg_control;
+
 mod noop;
 pub(crate) use noop::NoOp;
 
 mod open;
 
+mod poll;
+pub use poll::{Interest, Ready};
+
 mod read_fixed;
 
+mod read_select;
+pub(crate) use read_select::SelectedRead;
+
+mod recv_batch;
+pub(crate) use recv_batch::RecvBatch;
+
 mod recv_from;
+pub use recv_from::DatagramTruncated;
+
+mod recv_multi;
+pub(crate) use recv_multi::RecvStream;
 
 mod recvmsg;
 
+mod recvmsg_multi;
+pub(crate) use recvmsg_multi::{header_len as recvmsg_header_len, RecvMsgStream};
+
 mod rename_at;
 
+mod resume;
+pub(crate) use resume::Resume;
+
 mod send_to;
 
 mod send_zc;
@@ -31,19 +62,32 @@ mod sendmsg;
 
 mod sendmsg_zc;
 
+mod shutdown;
+pub(crate) use shutdown::Shutdown;
+
 mod shared_fd;
 pub(crate) use shared_fd::SharedFd;
 
 mod socket;
 pub(crate) use socket::Socket;
 
+mod splice;
+pub(crate) use splice::Pipe;
+
 mod statx;
 
 mod unlink_at;
 
+mod uring_cmd;
+
 mod util;
 pub(crate) use util::cstr;
 
 pub(crate) mod read_write;
 
+pub(crate) mod recv;
+
+pub(crate) mod send;
+
 mod write_fixed;
+pub(crate) use write_fixed::WriteFixed;
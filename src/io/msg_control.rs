@@ -0,0 +1,160 @@
+use crate::buf::Buffer;
+use crate::io::SharedFd;
+use crate::net::CMsgs;
+use crate::runtime::driver::op::{Completable, CqeResult, Op};
+use crate::runtime::CONTEXT;
+use crate::WithBuffer;
+use socket2::SockAddr;
+use std::io;
+
+/// Receive a message along with its control messages.
+pub(crate) struct RecvMsgControl {
+    fd: SharedFd,
+    buf: Buffer,
+    // Aligned storage for the control messages.
+    _control: Vec<u64>,
+    socket_addr: Box<SockAddr>,
+    msghdr: Box<libc::msghdr>,
+}
+
+impl Op<RecvMsgControl> {
+    pub(crate) fn recv_msg_control(
+        fd: &SharedFd,
+        mut buf: Buffer,
+        control_len: usize,
+    ) -> io::Result<Op<RecvMsgControl>> {
+        use io_uring::{opcode, types};
+
+        // Expose the whole capacity of every segment to the kernel.
+        buf.fill();
+        let mut control = vec![0u64; control_len.div_ceil(8)];
+        let socket_addr = Box::new(unsafe { SockAddr::init(|_, _| Ok(()))?.1 });
+
+        let mut msghdr: Box<libc::msghdr> = Box::new(unsafe { std::mem::zeroed() });
+        msghdr.msg_iov = buf.iter().as_slice().as_ptr() as *mut _;
+        msghdr.msg_iovlen = buf.segment_count() as _;
+        msghdr.msg_name = socket_addr.as_ptr() as *mut libc::c_void;
+        msghdr.msg_namelen = socket_addr.len();
+        if control_len > 0 {
+            msghdr.msg_control = control.as_mut_ptr().cast();
+            msghdr.msg_controllen = control_len as _;
+        }
+
+        CONTEXT.with(|x| {
+            x.handle().expect("Not in a runtime context").submit_op(
+                RecvMsgControl {
+                    fd: fd.clone(),
+                    buf,
+                    _control: control,
+                    socket_addr,
+                    msghdr,
+                },
+                |recv| {
+                    opcode::RecvMsg::new(
+                        types::Fd(recv.fd.raw_fd()),
+                        recv.msghdr.as_mut() as *mut _,
+                    )
+                    // File descriptors passed with SCM_RIGHTS must not leak into children.
+                    .flags(libc::MSG_CMSG_CLOEXEC as u32)
+                    .build()
+                },
+            )
+        })
+    }
+}
+
+impl Completable for RecvMsgControl {
+    type Output = crate::Result<(usize, SockAddr, CMsgs), Buffer>;
+
+    fn complete(self, cqe: CqeResult) -> Self::Output {
+        let mut buf = self.buf;
+        let n = match cqe.result {
+            Ok(n) => n as usize,
+            Err(e) => {
+                // Safety: nothing was received.
+                unsafe { buf.set_init(0) };
+                return Err(e).with_buffer(buf);
+            }
+        };
+        // Safety: the kernel wrote `n` bytes across the segments, in order.
+        unsafe { buf.set_init(n) };
+
+        if self.msghdr.msg_flags & libc::MSG_TRUNC != 0 {
+            return Err(io::Error::from_raw_os_error(libc::EMSGSIZE)).with_buffer(buf);
+        }
+        // Safety: the kernel updated the control length to the bytes it wrote.
+        let cmsgs = unsafe { CMsgs::decode(&self.msghdr) };
+        // Safety: the storage is a `sockaddr_storage`, and the kernel updated the name length
+        // to the size of the address it wrote, which matters for Unix domain addresses.
+        let socket_addr = unsafe {
+            SockAddr::new(
+                *(self.socket_addr.as_ptr() as *const libc::sockaddr_storage),
+                self.msghdr.msg_namelen,
+            )
+        };
+
+        Ok(((n, socket_addr, cmsgs), buf))
+    }
+}
+
+/// Send a message along with control messages.
+pub(crate) struct SendMsgControl {
+    fd: SharedFd,
+    buf: Buffer,
+    _control: Vec<u64>,
+    _socket_addr: Option<Box<SockAddr>>,
+    msghdr: Box<libc::msghdr>,
+}
+
+impl Op<SendMsgControl> {
+    pub(crate) fn send_msg_control(
+        fd: &SharedFd,
+        buf: Buffer,
+        socket_addr: Option<SockAddr>,
+        cmsgs: &CMsgs,
+    ) -> io::Result<Op<SendMsgControl>> {
+        use io_uring::{opcode, types};
+
+        let (mut control, control_len) = cmsgs.encode();
+
+        let mut msghdr: Box<libc::msghdr> = Box::new(unsafe { std::mem::zeroed() });
+        // The initialized length of each segment is what gets sent.
+        msghdr.msg_iov = buf.iter().as_slice().as_ptr() as *mut _;
+        msghdr.msg_iovlen = buf.segment_count() as _;
+        let socket_addr = socket_addr.map(|socket_addr| {
+            let socket_addr = Box::new(socket_addr);
+            msghdr.msg_name = socket_addr.as_ptr() as *mut libc::c_void;
+            msghdr.msg_namelen = socket_addr.len();
+            socket_addr
+        });
+        if control_len > 0 {
+            msghdr.msg_control = control.as_mut_ptr().cast();
+            msghdr.msg_controllen = control_len as _;
+        }
+
+        CONTEXT.with(|x| {
+            x.handle().expect("Not in a runtime context").submit_op(
+                SendMsgControl {
+                    fd: fd.clone(),
+                    buf,
+                    _control: control,
+                    _socket_addr: socket_addr,
+                    msghdr,
+                },
+                |send| {
+                    opcode::SendMsg::new(types::Fd(send.fd.raw_fd()), &*send.msghdr as *const _)
+                        .flags(libc::MSG_NOSIGNAL as u32)
+                        .build()
+                },
+            )
+        })
+    }
+}
+
+impl Completable for SendMsgControl {
+    type Output = crate::Result<usize, Buffer>;
+
+    fn complete(self, cqe: CqeResult) -> Self::Output {
+        cqe.result.map(|n| n as usize).with_buffer(self.buf)
+    }
+}
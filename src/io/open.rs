@@ -12,11 +12,24 @@ use std::path::Path;
 pub(crate) struct Open {
     pub(crate) path: CString,
     pub(crate) flags: libc::c_int,
+    /// Directory that relative paths are resolved against, kept open until the
+    /// operation completes.
+    dir: Option<SharedFd>,
+    /// Argument of openat2, read by the kernel when the operation is submitted.
+    how: Option<Box<io_uring::types::OpenHow>>,
 }
 
 impl Op<Open> {
     /// Submit a request to open a file.
-    pub(crate) fn open(path: &Path, options: &OpenOptions) -> io::Result<Op<Open>> {
+    ///
+    /// Relative paths are resolved against `dir`, or the current working
+    /// directory if `dir` is `None`. openat2 is used when the options carry
+    /// resolve flags.
+    pub(crate) fn open(
+        dir: Option<&SharedFd>,
+        path: &Path,
+        options: &OpenOptions,
+    ) -> io::Result<Op<Open>> {
         use io_uring::{opcode, types};
         let path = super::util::cstr(path)?;
         let flags = libc::O_CLOEXEC
@@ -24,20 +37,50 @@ impl Op<Open> {
             | options.creation_mode()?
             | (options.custom_flags & !libc::O_ACCMODE);
 
+        let how = if options.resolve.is_empty() {
+            None
+        } else {
+            // Unlike openat, openat2 rejects a mode when no file can be created.
+            let mode = if flags & libc::O_CREAT != 0 || flags & libc::O_TMPFILE == libc::O_TMPFILE {
+                options.mode
+            } else {
+                0
+            };
+            Some(Box::new(
+                types::OpenHow::new()
+                    .flags(flags as u64)
+                    .mode(mode as u64)
+                    .resolve(options.resolve.bits()),
+            ))
+        };
+
         CONTEXT.with(|x| {
-            x.handle()
-                .expect("Not in a runtime context")
-                .submit_op(Open { path, flags }, |open| {
+            x.handle().expect("Not in a runtime context").submit_op(
+                Open {
+                    path,
+                    flags,
+                    dir: dir.cloned(),
+                    how,
+                },
+                |open| {
                     // Get a reference to the memory. The string will be held by the
                     // operation state and will not be accessed again until the operation
                     // completes.
                     let p_ref = open.path.as_c_str().as_ptr();
+                    let dirfd =
+                        types::Fd(open.dir.as_ref().map_or(libc::AT_FDCWD, |fd| fd.raw_fd()));
 
-                    opcode::OpenAt::new(types::Fd(libc::AT_FDCWD), p_ref)
-                        .flags(flags)
-                        .mode(options.mode)
-                        .build()
-                })
+                    match open.how {
+                        Some(ref how) => {
+                            opcode::OpenAt2::new(dirfd, p_ref, &**how as *const _).build()
+                        }
+                        None => opcode::OpenAt::new(dirfd, p_ref)
+                            .flags(flags)
+                            .mode(options.mode)
+                            .build(),
+                    }
+                },
+            )
         })
     }
 }
@@ -0,0 +1,161 @@
+use crate::io::SharedFd;
+use crate::runtime::driver::op::{Completable, CqeResult, Op};
+use crate::runtime::CONTEXT;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::{fmt, io, ops};
+
+/// Readiness events to wait for, passed to the `ready` method of the socket types.
+///
+/// Combine them with `|`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Interest(u32);
+
+impl Interest {
+    /// Interest in the socket becoming readable (`POLLIN`).
+    pub const READABLE: Interest = Interest(libc::POLLIN as u32);
+
+    /// Interest in the socket becoming writable (`POLLOUT`).
+    pub const WRITABLE: Interest = Interest(libc::POLLOUT as u32);
+
+    /// Returns `true` if the interest includes readable events.
+    pub fn is_readable(self) -> bool {
+        self.0 & Interest::READABLE.0 != 0
+    }
+
+    /// Returns `true` if the interest includes writable events.
+    pub fn is_writable(self) -> bool {
+        self.0 & Interest::WRITABLE.0 != 0
+    }
+}
+
+impl ops::BitOr for Interest {
+    type Output = Interest;
+
+    fn bitor(self, other: Interest) -> Interest {
+        Interest(self.0 | other.0)
+    }
+}
+
+impl ops::BitOrAssign for Interest {
+    fn bitor_assign(&mut self, other: Interest) {
+        self.0 |= other.0;
+    }
+}
+
+impl fmt::Debug for Interest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Interest")
+            .field("readable", &self.is_readable())
+            .field("writable", &self.is_writable())
+            .finish()
+    }
+}
+
+/// The readiness events that fired, returned by the `ready` method of the socket types.
+///
+/// Errors and hang-ups are reported whatever the interest, as `poll(2)` does.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Ready(u32);
+
+impl Ready {
+    /// Returns `true` if the socket is readable: data is waiting, or the peer closed its
+    /// write half, in which case a read returns 0.
+    pub fn is_readable(self) -> bool {
+        self.0 & (libc::POLLIN | libc::POLLRDHUP | libc::POLLHUP) as u32 != 0
+    }
+
+    /// Returns `true` if the socket is writable.
+    pub fn is_writable(self) -> bool {
+        self.0 & libc::POLLOUT as u32 != 0
+    }
+
+    /// Returns `true` if the peer closed its write half of the connection (`POLLRDHUP`), or
+    /// the connection was shut down entirely.
+    pub fn is_read_closed(self) -> bool {
+        self.0 & (libc::POLLRDHUP | libc::POLLHUP) as u32 != 0
+    }
+
+    /// Returns `true` if an error is pending on the socket (`POLLERR`).
+    pub fn is_error(self) -> bool {
+        self.0 & libc::POLLERR as u32 != 0
+    }
+}
+
+impl fmt::Debug for Ready {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Ready")
+            .field("readable", &self.is_readable())
+            .field("writable", &self.is_writable())
+            .field("read_closed", &self.is_read_closed())
+            .field("error", &self.is_error())
+            .finish()
+    }
+}
+
+/// Wait for a file descriptor to become ready, without transferring anything.
+pub(crate) struct PollAdd {
+    /// Holds a strong ref to the FD, preventing the file from being closed
+    /// while the operation is in-flight.
+    #[allow(dead_code)]
+    fd: SharedFd,
+}
+
+impl Op<PollAdd> {
+    pub(crate) fn poll_add(fd: &SharedFd, interest: Interest) -> io::Result<Op<PollAdd>> {
+        use io_uring::{opcode, types};
+
+        // POLLRDHUP has the kernel report a peer's shutdown even to writers.
+        let events = interest.0 | libc::POLLRDHUP as u32;
+        CONTEXT.with(|x| {
+            x.handle()
+                .expect("Not in a runtime context")
+                .submit_op(PollAdd { fd: fd.clone() }, |poll| {
+                    opcode::PollAdd::new(types::Fd(poll.fd.raw_fd()), events).build()
+                })
+        })
+    }
+}
+
+impl Completable for PollAdd {
+    type Output = io::Result<Ready>;
+
+    fn complete(self, cqe: CqeResult) -> Self::Output {
+        cqe.result.map(Ready)
+    }
+}
+
+/// Resolves once the descriptor is ready, cancelling the poll if dropped before then.
+pub(crate) struct Readiness {
+    // `None` once the poll completed.
+    op: Option<Op<PollAdd>>,
+}
+
+impl Readiness {
+    pub(crate) fn new(fd: &SharedFd, interest: Interest) -> io::Result<Readiness> {
+        Ok(Readiness {
+            op: Some(Op::poll_add(fd, interest)?),
+        })
+    }
+}
+
+impl Future for Readiness {
+    type Output = io::Result<Ready>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let op = self.op.as_mut().expect("polled after completion");
+        let res = ready!(Pin::new(op).poll(cx));
+        self.op = None;
+        Poll::Ready(res)
+    }
+}
+
+impl Drop for Readiness {
+    fn drop(&mut self) {
+        // Unlike a transfer, a poll may never complete, so it is not left to run on.
+        if let Some(op) = &self.op {
+            op.cancel();
+        }
+    }
+}
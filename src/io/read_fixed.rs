@@ -1,5 +1,5 @@
 use crate::buf::fixed::pool::PoolInfo;
-use crate::buf::fixed::registry::RegistryInfo;
+use crate::buf::fixed::registry::{InFlight, RegistryInfo};
 use crate::buf::fixed::{pool, registry};
 use crate::buf::BoundedBufMut;
 use crate::io::SharedFd;
@@ -19,6 +19,9 @@ pub(crate) struct ReadFixed<T> {
 
     /// The in-flight buffer.
     buf: T,
+
+    // Marks a registry buffer as held by the operation.
+    _in_flight: Option<InFlight>,
 }
 
 impl<T> Op<ReadFixed<T>>
@@ -36,6 +39,7 @@ where
             x.handle().expect("Not in a runtime context").submit_op(
                 ReadFixed {
                     fd: fd.clone(),
+                    _in_flight: InFlight::mark(buf.get_buf()),
                     buf,
                 },
                 |read_fixed| {
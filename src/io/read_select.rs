@@ -0,0 +1,131 @@
+use crate::buf::bufring::BufRing;
+use crate::buf::Buffer;
+use crate::io::SharedFd;
+use crate::runtime::driver::op::{Completable, CqeResult, Op};
+use crate::runtime::CONTEXT;
+use io_uring::{cqueue, squeue};
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// A read into a buffer the kernel selects from a group once data is ready.
+pub(crate) struct ReadSelect {
+    /// Holds a strong ref to the FD, preventing the file from being closed
+    /// while the operation is in-flight.
+    #[allow(dead_code)]
+    fd: SharedFd,
+}
+
+impl Op<ReadSelect> {
+    pub(crate) fn read_select(fd: &SharedFd, ring: &BufRing) -> io::Result<Self> {
+        use io_uring::{opcode, types};
+
+        let (bgid, len) = (ring.bgid(), ring.buf_size() as u32);
+        CONTEXT.with(|x| {
+            x.handle().expect("Not in a runtime context").submit_op(
+                ReadSelect { fd: fd.clone() },
+                |read| {
+                    opcode::Read::new(types::Fd(read.fd.raw_fd()), std::ptr::null_mut(), len)
+                        .buf_group(bgid)
+                        .build()
+                        .flags(squeue::Flags::BUFFER_SELECT)
+                },
+            )
+        })
+    }
+}
+
+impl Completable for ReadSelect {
+    type Output = CqeResult;
+
+    fn complete(self, cqe: CqeResult) -> Self::Output {
+        cqe
+    }
+}
+
+enum State {
+    /// Nothing in flight; the next poll submits.
+    Idle,
+    Armed(Op<ReadSelect>),
+    /// The group ran out of buffers; the next poll resubmits once one is returned.
+    Starved,
+    Done,
+}
+
+/// Reads once into a buffer selected from a [`BufRing`], resolving to `None` at the end of
+/// the stream.
+pub(crate) struct SelectedRead {
+    fd: SharedFd,
+    ring: BufRing,
+    state: State,
+}
+
+impl SelectedRead {
+    pub(crate) fn new(fd: &SharedFd, ring: &BufRing) -> SelectedRead {
+        SelectedRead {
+            fd: fd.clone(),
+            ring: ring.clone(),
+            state: State::Idle,
+        }
+    }
+}
+
+impl Future for SelectedRead {
+    type Output = io::Result<Option<Buffer>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        loop {
+            match &mut this.state {
+                State::Idle => {
+                    this.state = State::Armed(Op::read_select(&this.fd, &this.ring)?);
+                }
+                State::Armed(op) => {
+                    let cqe = ready!(Pin::new(op).poll(cx));
+                    this.state = State::Done;
+                    // Take the buffer first, so that it goes back to the ring whatever the
+                    // outcome.
+                    let buf = cqueue::buffer_select(cqe.flags).map(|bid| {
+                        let n = *cqe.result.as_ref().unwrap_or(&0) as usize;
+                        this.ring.take(bid, n)
+                    });
+                    match cqe.result {
+                        Ok(0) => return Poll::Ready(Ok(None)),
+                        Ok(_) => {
+                            let buf = buf.expect("a read with data selects a buffer");
+                            return Poll::Ready(Ok(Some(buf)));
+                        }
+                        Err(e) if e.raw_os_error() == Some(libc::ENOBUFS) => {
+                            this.state = State::Starved;
+                        }
+                        Err(e) => return Poll::Ready(Err(e)),
+                    }
+                }
+                State::Starved => {
+                    if this.ring.wait_for_buf(cx.waker()) {
+                        return Poll::Pending;
+                    }
+                    this.state = State::Idle;
+                }
+                State::Done => panic!("polled after completion"),
+            }
+        }
+    }
+}
+
+impl Drop for SelectedRead {
+    fn drop(&mut self) {
+        if let State::Armed(op) = &mut self.state {
+            // Cancelling dispatches a completion the kernel already posted, and the buffer it
+            // selected must go back to the ring.
+            op.cancel();
+            let mut cx = Context::from_waker(futures_util::task::noop_waker_ref());
+            if let Poll::Ready(cqe) = Pin::new(op).poll(&mut cx) {
+                if let Some(bid) = cqueue::buffer_select(cqe.flags) {
+                    drop(self.ring.take(bid, 0));
+                }
+            }
+        }
+    }
+}
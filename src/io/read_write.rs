@@ -1,7 +1,7 @@
 use libc::iovec;
 
 use crate::buf::fixed::pool::PoolInfo;
-use crate::buf::fixed::registry::RegistryInfo;
+use crate::buf::fixed::registry::{InFlight, RegistryInfo};
 use crate::buf::fixed::{pool, registry};
 use crate::buf::{BoundedBufMut, Buffer};
 use crate::WithBuffer;
@@ -19,6 +19,12 @@ pub struct ReadWriteData {
     _fd: SharedFd,
 
     buf: Buffer,
+
+    // Read by the kernel for a vectored send.
+    _msghdr: Option<Box<libc::msghdr>>,
+
+    // Marks a registry buffer as held by the operation.
+    _in_flight: Option<InFlight>,
 }
 
 enum Kind {
@@ -53,6 +59,10 @@ impl OneshotOutputTransform for ReadWriteTransform {
     }
 }
 
+// A write of no initialized bytes, or a read into no capacity, completes with 0 as soon as
+// it is submitted, without reaching the ring: the kernel would either do nothing or, for
+// some files, fail with `EINVAL`. Datagram sockets opt out, as an empty datagram is a
+// message of its own.
 impl Unsubmitted {
     pub(crate) fn write_at(fd: &SharedFd, buf: Buffer, offset: u64) -> Self {
         use io_uring::{opcode, types};
@@ -61,11 +71,15 @@ impl Unsubmitted {
         let ptr = buf.stable_ptr();
         let len = buf.bytes_init();
 
-        let sqe = if buf.len() == 1 {
+        let sqe = if buf.segment_count() == 1 {
             // Fixed buffer io not support vectored io
 
-            // Get buf_index from raw pointer
-            if buf.type_id() == TypeId::of::<registry::FixedBuf>() {
+            // Get buf_index from raw pointer. A buffer of an unregistered
+            // registry is still valid memory, but its index may name another
+            // buffer now, so it goes through a plain write.
+            if buf.type_id() == TypeId::of::<registry::FixedBuf>()
+                && registry::check_registered(&buf).is_ok()
+            {
                 // Safety: The condition above indicates that the source of buffer is `registry::FixedBuf`.
                 // According to the `BufferImpl` implementation for `registry::FixedBuf`, the user_data
                 // pointer contains a raw pointer of type `RegistryInfo`, so this raw pointer casting is safe.
@@ -76,7 +90,9 @@ impl Unsubmitted {
                 opcode::WriteFixed::new(types::Fd(fd.raw_fd()), ptr, len as _, buf_index)
                     .offset(offset as _)
                     .build()
-            } else if buf.type_id() == TypeId::of::<pool::FixedBuf>() {
+            } else if buf.type_id() == TypeId::of::<pool::FixedBuf>()
+                && registry::check_registered(&buf).is_ok()
+            {
                 // Safety: This raw pointer casting is also safe as above.
                 let buf_index = unsafe {
                     let pool_info = buf.user_data() as *const PoolInfo;
@@ -85,41 +101,119 @@ impl Unsubmitted {
                 opcode::WriteFixed::new(types::Fd(fd.raw_fd()), ptr, len as _, buf_index)
                     .offset(offset as _)
                     .build()
+            } else if let Some(buf_index) =
+                registry::shared_index(&buf).filter(|_| registry::check_registered(&buf).is_ok())
+            {
+                opcode::WriteFixed::new(types::Fd(fd.raw_fd()), ptr, len as _, buf_index)
+                    .offset(offset as _)
+                    .build()
             } else {
                 opcode::Write::new(types::Fd(fd.raw_fd()), ptr, len as _)
                     .offset(offset as _)
                     .build()
             }
         } else {
-            opcode::Writev::new(types::Fd(fd.raw_fd()), ptr as *const iovec, buf.len() as _)
-                .offset(offset as _)
-                .build()
+            opcode::Writev::new(
+                types::Fd(fd.raw_fd()),
+                ptr as *const iovec,
+                buf.segment_count() as _,
+            )
+            .offset(offset as _)
+            .build()
         };
 
         Self::new(
             ReadWriteData {
                 _fd: fd.clone(),
+                _in_flight: InFlight::mark(&buf),
                 buf,
+                _msghdr: None,
             },
             ReadWriteTransform(Kind::Write),
             sqe,
         )
+        .set_empty(len == 0)
+    }
+
+    /// Writes to a socket with `send` or `sendmsg`, which take `MSG_NOSIGNAL`, rather than
+    /// `write`, so that writing to a closed connection fails with `EPIPE` instead of raising
+    /// `SIGPIPE`. Registered buffers still go through `write_at`, as sends cannot name them.
+    pub(crate) fn send(fd: &SharedFd, buf: Buffer) -> Self {
+        use io_uring::{opcode, types};
+
+        let fixed = buf.type_id() == TypeId::of::<registry::FixedBuf>()
+            || buf.type_id() == TypeId::of::<pool::FixedBuf>()
+            || buf.type_id() == TypeId::of::<registry::SharedFixedBuf>();
+        if fixed {
+            return Self::write_at(fd, buf, 0);
+        }
+
+        let empty = buf.is_empty();
+        let (sqe, msghdr) = if buf.segment_count() == 1 {
+            let sqe = opcode::Send::new(
+                types::Fd(fd.raw_fd()),
+                buf.stable_ptr(),
+                buf.bytes_init() as _,
+            )
+            .flags(libc::MSG_NOSIGNAL)
+            .build();
+            (sqe, None)
+        } else {
+            // The buffer's own iovecs cover the initialized bytes of every segment.
+            let mut msghdr: Box<libc::msghdr> = Box::new(unsafe { std::mem::zeroed() });
+            msghdr.msg_iov = buf.iter().as_slice().as_ptr() as *mut _;
+            msghdr.msg_iovlen = buf.segment_count() as _;
+            let sqe = opcode::SendMsg::new(types::Fd(fd.raw_fd()), msghdr.as_ref() as *const _)
+                .flags(libc::MSG_NOSIGNAL as u32)
+                .build();
+            (sqe, Some(msghdr))
+        };
+
+        Self::new(
+            ReadWriteData {
+                _fd: fd.clone(),
+                _in_flight: InFlight::mark(&buf),
+                buf,
+                _msghdr: msghdr,
+            },
+            ReadWriteTransform(Kind::Write),
+            sqe,
+        )
+        .set_empty(empty)
     }
 
     pub(crate) fn read_at(fd: &SharedFd, mut buf: Buffer, offset: u64) -> Self {
         use io_uring::{opcode, types};
 
+        if buf.is_read_only() {
+            // Stands in for the read in a chain, failing with `EBADF`.
+            let sqe = opcode::Read::new(types::Fd(-1), std::ptr::null_mut(), 0).build();
+            return Self::new(
+                ReadWriteData {
+                    _fd: fd.clone(),
+                    _in_flight: None,
+                    buf,
+                    _msghdr: None,
+                },
+                ReadWriteTransform(Kind::Read),
+                sqe,
+            )
+            .set_error(libc::EINVAL);
+        }
+
         // Get raw buffer info
         let ptr = buf.stable_mut_ptr();
         let len = buf.bytes_total();
 
         buf.fill();
 
-        let sqe = if buf.len() == 1 {
+        let sqe = if buf.segment_count() == 1 {
             // Fixed buffer io not support vectored io
 
-            // Get buf_index from raw pointer
-            if buf.type_id() == TypeId::of::<registry::FixedBuf>() {
+            // Get buf_index from raw pointer, as in `write_at`.
+            if buf.type_id() == TypeId::of::<registry::FixedBuf>()
+                && registry::check_registered(&buf).is_ok()
+            {
                 // Safety: The condition above indicates that the source of buffer is `registry::FixedBuf`.
                 // According to the `BufferImpl` implementation for `registry::FixedBuf`, the user_data
                 // pointer contains a raw pointer of type `RegistryInfo`, so this raw pointer casting is safe.
@@ -130,7 +224,9 @@ impl Unsubmitted {
                 opcode::ReadFixed::new(types::Fd(fd.raw_fd()), ptr, len as _, buf_index)
                     .offset(offset as _)
                     .build()
-            } else if buf.type_id() == TypeId::of::<pool::FixedBuf>() {
+            } else if buf.type_id() == TypeId::of::<pool::FixedBuf>()
+                && registry::check_registered(&buf).is_ok()
+            {
                 // Safety: This raw pointer casting is also safe as above.
                 let buf_index = unsafe {
                     let pool_info = buf.user_data() as *const PoolInfo;
@@ -145,18 +241,252 @@ impl Unsubmitted {
                     .build()
             }
         } else {
-            opcode::Readv::new(types::Fd(fd.raw_fd()), ptr as *mut iovec, buf.len() as _)
-                .offset(offset as _)
-                .build()
+            opcode::Readv::new(
+                types::Fd(fd.raw_fd()),
+                ptr as *mut iovec,
+                buf.segment_count() as _,
+            )
+            .offset(offset as _)
+            .build()
         };
 
         Self::new(
             ReadWriteData {
                 _fd: fd.clone(),
+                _in_flight: InFlight::mark(&buf),
                 buf,
+                _msghdr: None,
             },
             ReadWriteTransform(Kind::Read),
             sqe,
         )
+        .set_empty(len == 0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate as tokio_uring;
+    use crate::buf::fixed::registry;
+    use crate::buf::BoundedBuf;
+    use crate::fs::File;
+    use crate::net::{TcpListener, TcpStream, UdpSocket, UnixStream};
+    use crate::runtime::CONTEXT;
+    use crate::{Buffer, Submit};
+
+    fn sqes_pushed() -> u64 {
+        CONTEXT.with(|cx| cx.handle().unwrap().sqes_pushed())
+    }
+
+    // Buffers with nothing to read into: no capacity at all, several segments of none, and
+    // a view of none.
+    fn no_capacity() -> Vec<Buffer> {
+        vec![
+            Buffer::new(Vec::<u8>::new()),
+            Buffer::from(vec![Vec::new(), Vec::new(), Vec::new()]),
+            Buffer::new(vec![0; 8]).view(8..),
+        ]
+    }
+
+    // Buffers with nothing to write: no initialized bytes, in one segment or several.
+    fn no_bytes() -> Vec<Buffer> {
+        vec![
+            Buffer::new(Vec::<u8>::new()),
+            Buffer::new(Vec::<u8>::with_capacity(8)),
+            Buffer::from(vec![Vec::new(), Vec::with_capacity(4)]),
+        ]
+    }
+
+    #[test]
+    fn empty_file_ops_skip_the_ring() {
+        let tempfile = tempfile::NamedTempFile::new().unwrap();
+
+        tokio_uring::start(async {
+            let file = std::fs::OpenOptions::new()
+                .read(true)
+                .write(true)
+                .open(tempfile.path())
+                .unwrap();
+            let file = File::from_std(file);
+            let before = sqes_pushed();
+
+            for buf in no_capacity() {
+                let segments = buf.segment_count();
+                let (n, buf) = file.read_at(buf, 0).submit().await.unwrap();
+                assert_eq!(n, 0);
+                assert_eq!(buf.segment_count(), segments);
+            }
+            for buf in no_bytes() {
+                let capacity = buf.capacity();
+                let (n, buf) = file.write_at(buf, 0).submit().await.unwrap();
+                assert_eq!(n, 0);
+                assert_eq!(buf.capacity(), capacity);
+            }
+            assert_eq!(sqes_pushed(), before);
+
+            // A buffer with room still reaches the kernel.
+            file.read_at(Buffer::new(vec![0; 8]), 0)
+                .submit()
+                .await
+                .unwrap();
+            assert_eq!(sqes_pushed(), before + 1);
+        });
+    }
+
+    #[test]
+    fn empty_fixed_ops_skip_the_ring() {
+        let tempfile = tempfile::NamedTempFile::new().unwrap();
+
+        tokio_uring::start(async {
+            let file = File::create(tempfile.path()).await.unwrap();
+            let registry = registry::register(std::iter::once(Buffer::new(vec![0; 8]))).unwrap();
+            let before = sqes_pushed();
+
+            let buf = registry.check_out(0).unwrap();
+            let (n, slice) = file.read_fixed_at(buf.slice(4..4), 0).await.unwrap();
+            assert_eq!(n, 0);
+            let (n, slice) = file
+                .write_fixed_at(slice.into_inner().slice(..0), 0)
+                .await
+                .unwrap();
+            assert_eq!(n, 0);
+            assert_eq!(sqes_pushed(), before);
+            drop(slice);
+        });
+    }
+
+    #[test]
+    fn empty_stream_ops_skip_the_ring() {
+        tokio_uring::start(async {
+            let (a, b) = UnixStream::pair().unwrap();
+            let before = sqes_pushed();
+
+            for buf in no_capacity() {
+                let (n, _) = a.read(buf).await.unwrap();
+                assert_eq!(n, 0);
+            }
+            for buf in no_bytes() {
+                let (n, _) = b.write(buf).submit().await.unwrap();
+                assert_eq!(n, 0);
+            }
+            assert_eq!(sqes_pushed(), before);
+
+            // The stream still works, with nothing written by the empty writes.
+            b.write(Buffer::new(b"hello".to_vec()))
+                .submit()
+                .await
+                .unwrap();
+            let (n, buf) = a.read(Buffer::new(vec![0; 16])).await.unwrap();
+            assert_eq!(&buf[0][..n], b"hello");
+        });
+    }
+
+    #[test]
+    fn empty_tcp_sends_and_receives_skip_the_ring() {
+        tokio_uring::start(async {
+            let listener = TcpListener::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+            let a = TcpStream::connect(listener.local_addr().unwrap())
+                .await
+                .unwrap();
+            let (b, _) = listener.accept().await.unwrap();
+            let before = sqes_pushed();
+
+            for buf in no_capacity() {
+                let (n, _) = a.recv(buf).waitall().submit().await.unwrap();
+                assert_eq!(n, 0);
+            }
+            for buf in no_bytes() {
+                let (n, _) = b.send(buf).more().submit().await.unwrap();
+                assert_eq!(n, 0);
+            }
+            assert_eq!(sqes_pushed(), before);
+        });
+    }
+
+    #[test]
+    fn empty_ops_in_a_chain_keep_their_place() {
+        let tempfile = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(tempfile.path(), b"hello").unwrap();
+
+        tokio_uring::start(async {
+            let ro = File::open(tempfile.path()).await.unwrap();
+            let other = File::open(tempfile.path()).await.unwrap();
+            let before = sqes_pushed();
+
+            // The write fails on a read-only file, cancelling the empty write linked to it,
+            // and nothing past the chain.
+            let chain = ro
+                .write_at(Buffer::new(b"x".to_vec()), 0)
+                .link(ro.write_at(Buffer::new(Vec::<u8>::new()), 0))
+                .submit();
+            let read = other.read_at(Buffer::new(vec![0; 8]), 0).submit();
+            let (first, second) = chain.await;
+            let err = first.unwrap_err();
+            assert_eq!(err.0.raw_os_error(), Some(libc::EBADF));
+            let err = second.await.unwrap_err();
+            assert_eq!(err.0.raw_os_error(), Some(libc::ECANCELED));
+            let (n, buf) = read.await.unwrap();
+            assert_eq!(&buf[0][..n], b"hello");
+            assert_eq!(sqes_pushed(), before + 3);
+        });
+    }
+
+    #[test]
+    fn read_only_reads_break_a_chain() {
+        let tempfile = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(tempfile.path(), b"hello").unwrap();
+
+        tokio_uring::start(async {
+            let file = File::open(tempfile.path()).await.unwrap();
+
+            // Alone, the read fails without reaching the ring.
+            let before = sqes_pushed();
+            let err = file
+                .read_at(Buffer::from_static(b"static"), 0)
+                .submit()
+                .await
+                .unwrap_err();
+            assert_eq!(err.0.kind(), std::io::ErrorKind::InvalidInput);
+            assert_eq!(sqes_pushed(), before);
+
+            // In a chain, its stand-in fails, cancelling the read linked to it.
+            let (first, second) = file
+                .read_at(Buffer::from_static(b"static"), 0)
+                .link(file.read_at(Buffer::new(vec![0; 8]), 0))
+                .submit()
+                .await;
+            let err = first.unwrap_err();
+            assert_eq!(err.0.kind(), std::io::ErrorKind::InvalidInput);
+            assert_eq!(&err.1[0][..], b"static");
+            let err = second.await.unwrap_err();
+            assert_eq!(err.0.raw_os_error(), Some(libc::ECANCELED));
+        });
+    }
+
+    #[test]
+    fn empty_datagrams_are_still_sent() {
+        tokio_uring::start(async {
+            let a = UdpSocket::bind("127.0.0.1:0".parse().unwrap())
+                .await
+                .unwrap();
+            let b = UdpSocket::bind("127.0.0.1:0".parse().unwrap())
+                .await
+                .unwrap();
+            a.connect(b.local_addr().unwrap()).await.unwrap();
+            b.connect(a.local_addr().unwrap()).await.unwrap();
+            let before = sqes_pushed();
+
+            // An empty datagram is a message of its own, and reading into no capacity
+            // consumes it.
+            let (n, _) = a
+                .write(Buffer::new(Vec::<u8>::new()))
+                .submit()
+                .await
+                .unwrap();
+            assert_eq!(n, 0);
+            let (n, _) = b.read(Buffer::new(Vec::<u8>::new())).await.unwrap();
+            assert_eq!(n, 0);
+            assert_eq!(sqes_pushed(), before + 2);
+        });
     }
 }
@@ -0,0 +1,185 @@
+use crate::buf::{Buffer, IoBuf};
+use crate::io::SharedFd;
+use crate::{InFlightOneshot, OneshotOutputTransform, Submit, UnsubmittedOneshot, WithBuffer};
+use io_uring::cqueue;
+use std::io;
+
+/// A receive that has not been submitted yet, whose `MSG_*` flags can still be set.
+///
+/// Returned by the `recv` method of the socket types; [`submit`](Submit::submit) it and await
+/// the result, which is the number of bytes received and the original buffer.
+///
+/// # Examples
+///
+/// Looking at the start of a connection without consuming it:
+///
+/// ```no_run
+/// use tokio_uring::net::TcpListener;
+/// use tokio_uring::Submit;
+///
+/// tokio_uring::start(async {
+///     let listener = TcpListener::bind("127.0.0.1:8080".parse().unwrap()).unwrap();
+///     let (stream, _) = listener.accept().await.unwrap();
+///
+///     let (n, buf) = stream.recv(vec![0; 4].into()).peek().submit().await.unwrap();
+///     if &buf[0][..n] == b"GET " {
+///         // The request line is still there for the HTTP parser to read.
+///     }
+/// });
+/// ```
+pub struct UnsubmittedRecv {
+    fd: SharedFd,
+    buf: Buffer,
+    flags: libc::c_int,
+    datagram: bool,
+}
+
+impl UnsubmittedRecv {
+    pub(crate) fn new(fd: &SharedFd, buf: Buffer, datagram: bool) -> UnsubmittedRecv {
+        UnsubmittedRecv {
+            fd: fd.clone(),
+            buf,
+            flags: 0,
+            datagram,
+        }
+    }
+
+    /// Sets `MSG_PEEK`: the data is copied into the buffer but left in the socket, so the
+    /// next receive returns it again.
+    pub fn peek(mut self) -> Self {
+        self.flags |= libc::MSG_PEEK;
+        self
+    }
+
+    /// Sets `MSG_WAITALL`: on a stream socket, the kernel only completes the receive once the
+    /// whole buffer is filled, unless the peer closes the connection, an error occurs, or a
+    /// signal interrupts the wait.
+    pub fn waitall(mut self) -> Self {
+        self.flags |= libc::MSG_WAITALL;
+        self
+    }
+
+    /// Sets `MSG_TRUNC`: on a datagram socket, a datagram larger than the buffer is clipped
+    /// to fit, and the returned count is the full size of the datagram rather than the
+    /// number of bytes in the buffer. Combined with [`peek`](Self::peek), this learns the size
+    /// of the next datagram without consuming it.
+    ///
+    /// Without this flag, a datagram that does not fit fails the receive with `EMSGSIZE`.
+    ///
+    /// On a TCP socket, the kernel discards the received bytes instead of copying them, and
+    /// the buffer is left empty.
+    pub fn trunc(mut self) -> Self {
+        self.flags |= libc::MSG_TRUNC;
+        self
+    }
+}
+
+impl Submit for UnsubmittedRecv {
+    type Output = InFlightOneshot<RecvData, RecvTransform>;
+
+    fn submit(self) -> Self::Output {
+        use io_uring::{opcode, types};
+
+        let mut buf = self.buf;
+        if buf.is_read_only() {
+            // Stands in for the receive in a chain, failing with `EBADF`.
+            let sqe = opcode::RecvMsg::new(types::Fd(-1), std::ptr::null_mut()).build();
+            return UnsubmittedOneshot::new(
+                RecvData {
+                    _fd: self.fd,
+                    buf,
+                    msghdr: Box::new(unsafe { std::mem::zeroed() }),
+                },
+                RecvTransform {
+                    flags: self.flags,
+                    datagram: self.datagram,
+                },
+                sqe,
+            )
+            .set_error(libc::EINVAL)
+            .submit();
+        }
+        // On a stream, there is nothing to receive into no capacity; from a datagram socket,
+        // such a receive still consumes a datagram.
+        let empty = !self.datagram && buf.capacity() == 0;
+        // Expose the whole capacity of every segment to the kernel.
+        buf.fill();
+
+        let mut msghdr: Box<libc::msghdr> = Box::new(unsafe { std::mem::zeroed() });
+        msghdr.msg_iov = buf.iter().as_slice().as_ptr() as *mut _;
+        msghdr.msg_iovlen = buf.segment_count() as _;
+
+        let sqe = opcode::RecvMsg::new(types::Fd(self.fd.raw_fd()), msghdr.as_mut() as *mut _)
+            .flags(self.flags as u32)
+            .build();
+
+        UnsubmittedOneshot::new(
+            RecvData {
+                _fd: self.fd,
+                buf,
+                msghdr,
+            },
+            RecvTransform {
+                flags: self.flags,
+                datagram: self.datagram,
+            },
+            sqe,
+        )
+        .set_empty(empty)
+        .submit()
+    }
+}
+
+#[allow(missing_docs)]
+pub struct RecvData {
+    /// Holds a strong ref to the FD, preventing the file from being closed
+    /// while the operation is in-flight.
+    _fd: SharedFd,
+
+    buf: Buffer,
+
+    // Read by the kernel, which reports whether the datagram was truncated in it.
+    msghdr: Box<libc::msghdr>,
+}
+
+#[allow(missing_docs)]
+pub struct RecvTransform {
+    flags: libc::c_int,
+    datagram: bool,
+}
+
+impl OneshotOutputTransform for RecvTransform {
+    type Output = crate::Result<usize, Buffer>;
+
+    type StoredData = RecvData;
+
+    fn transform_oneshot_output(self, data: RecvData, cqe: cqueue::Entry) -> Self::Output {
+        let mut buf = data.buf;
+        let n = cqe.result();
+        if n < 0 {
+            // Safety: nothing was received. A read-only buffer was never filled, so it is left
+            // as it was.
+            if !buf.is_read_only() {
+                unsafe { buf.set_init(0) };
+            }
+            return Err(io::Error::from_raw_os_error(-n)).with_buffer(buf);
+        }
+        let n = n as usize;
+
+        let copied = if self.flags & libc::MSG_TRUNC == 0 {
+            n
+        } else if self.datagram {
+            // The count is the size of the datagram, of which only the start was copied.
+            n.min(buf.bytes_total())
+        } else {
+            0
+        };
+        // Safety: the kernel wrote `copied` bytes across the segments, in order.
+        unsafe { buf.set_init(copied) };
+
+        if self.flags & libc::MSG_TRUNC == 0 && data.msghdr.msg_flags & libc::MSG_TRUNC != 0 {
+            return Err(io::Error::from_raw_os_error(libc::EMSGSIZE)).with_buffer(buf);
+        }
+        Ok((n, buf))
+    }
+}
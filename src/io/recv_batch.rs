@@ -0,0 +1,107 @@
+use crate::buf::Buffer;
+use crate::io::recv_from::RecvFrom;
+use crate::io::SharedFd;
+use crate::runtime::driver::op::Op;
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+type Datagram = (usize, Buffer, SocketAddr);
+
+/// Receives a datagram into each of a set of buffers, with a recvmsg in flight for each.
+///
+/// Resolves once `min` datagrams arrived, or a receive failed, after cancelling the receives
+/// still in flight and waiting for them to hand their buffers back. Receives that completed
+/// before their cancellation took effect still count, so no datagram is lost.
+pub(crate) struct RecvBatch {
+    // In submission order; `None` once completed.
+    ops: Vec<Option<Op<RecvFrom<Buffer>>>>,
+    // Datagrams received, with the index of the receive that got them.
+    received: Vec<(usize, Datagram)>,
+    unused: Vec<Buffer>,
+    error: Option<io::Error>,
+    min: usize,
+    cancelled: bool,
+}
+
+impl RecvBatch {
+    pub(crate) fn new(fd: &SharedFd, bufs: Vec<Buffer>, min: usize) -> RecvBatch {
+        let min = min.clamp(1, bufs.len().max(1));
+        // The receives are queued back to back, so they reach the kernel together.
+        let ops = bufs
+            .into_iter()
+            .map(|buf| Some(Op::recv_from(fd, buf).unwrap()))
+            .collect::<Vec<_>>();
+        RecvBatch {
+            received: Vec::with_capacity(ops.len()),
+            unused: Vec::new(),
+            ops,
+            error: None,
+            min,
+            cancelled: false,
+        }
+    }
+}
+
+impl Future for RecvBatch {
+    type Output = crate::Result<Vec<Datagram>, Vec<Buffer>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        loop {
+            let mut pending = false;
+            for (i, slot) in this.ops.iter_mut().enumerate() {
+                let Some(op) = slot else {
+                    continue;
+                };
+                let Poll::Ready(res) = Pin::new(op).poll(cx) else {
+                    pending = true;
+                    continue;
+                };
+                *slot = None;
+                match res {
+                    Ok(((n, socket_addr), buf)) => this.received.push((i, (n, buf, socket_addr))),
+                    Err(crate::Error(e, buf)) => {
+                        if e.raw_os_error() != Some(libc::ECANCELED) && this.error.is_none() {
+                            this.error = Some(e);
+                        }
+                        this.unused.push(buf);
+                    }
+                }
+            }
+
+            if !pending {
+                break;
+            }
+            if this.cancelled || (this.received.len() < this.min && this.error.is_none()) {
+                return Poll::Pending;
+            }
+            // Cancelling dispatches the completions already posted, so look at the ops again.
+            this.cancelled = true;
+            for op in this.ops.iter().flatten() {
+                op.cancel();
+            }
+        }
+
+        let unused = std::mem::take(&mut this.unused);
+        if this.received.is_empty() {
+            if let Some(e) = this.error.take() {
+                return Poll::Ready(Err(crate::Error(e, unused)));
+            }
+        }
+        this.received.sort_by_key(|(i, _)| *i);
+        let received = this.received.drain(..).map(|(_, datagram)| datagram);
+        Poll::Ready(Ok((received.collect(), unused)))
+    }
+}
+
+impl Drop for RecvBatch {
+    fn drop(&mut self) {
+        // Left in flight, the receives would swallow datagrams meant for later ones.
+        for op in self.ops.iter().flatten() {
+            op.cancel();
+        }
+    }
+}
@@ -4,10 +4,50 @@ use crate::WithBuffer;
 use crate::{buf::BoundedBufMut, io::SharedFd, Result};
 use socket2::SockAddr;
 use std::{
+    fmt,
     io::IoSliceMut,
     {boxed::Box, io, net::SocketAddr},
 };
 
+/// The error returned when a datagram did not fit in the buffers it was received into.
+///
+/// It is wrapped in an [`io::Error`]; get at it with
+/// `err.get_ref().and_then(|e| e.downcast_ref::<DatagramTruncated>())`. The buffers still
+/// hold the leading part of the datagram, marked as initialized; the rest is discarded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DatagramTruncated {
+    addr: SocketAddr,
+    datagram_len: usize,
+}
+
+impl DatagramTruncated {
+    pub(crate) fn new_error(addr: SocketAddr, datagram_len: usize) -> io::Error {
+        io::Error::other(DatagramTruncated { addr, datagram_len })
+    }
+
+    /// Returns the address the datagram came from.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Returns the full length of the datagram, before truncation.
+    pub fn datagram_len(&self) -> usize {
+        self.datagram_len
+    }
+}
+
+impl fmt::Display for DatagramTruncated {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "datagram of {} bytes from {} truncated",
+            self.datagram_len, self.addr
+        )
+    }
+}
+
+impl std::error::Error for DatagramTruncated {}
+
 #[allow(dead_code)]
 pub(crate) struct RecvFrom<T> {
     fd: SharedFd,
@@ -47,6 +87,8 @@ impl<T: BoundedBufMut> Op<RecvFrom<T>> {
                         types::Fd(recv_from.fd.raw_fd()),
                         recv_from.msghdr.as_mut() as *mut _,
                     )
+                    // Have the kernel return the full length of a truncated datagram.
+                    .flags(libc::MSG_TRUNC as u32)
                     .build()
                 },
             )
@@ -71,14 +113,23 @@ where
         let res = res.map(|n| {
             let socket_addr: SocketAddr = socket_addr.unwrap();
 
-            // Safety: the kernel wrote `n` bytes to the buffer.
+            // Safety: the kernel wrote `n` bytes to the buffer, or filled it if the datagram
+            // was truncated.
             unsafe {
-                buf.set_init(n);
+                buf.set_init(n.min(buf.bytes_total()));
             }
 
             (n, socket_addr)
         });
 
+        // The kernel discarded the part of the datagram that did not fit.
+        let res = match res {
+            Ok((n, addr)) if self.msghdr.msg_flags & libc::MSG_TRUNC != 0 => {
+                Err(DatagramTruncated::new_error(addr, n))
+            }
+            res => res,
+        };
+
         res.with_buffer(buf)
     }
 }
@@ -0,0 +1,143 @@
+use crate::buf::bufring::BufRing;
+use crate::buf::Buffer;
+use crate::io::SharedFd;
+use crate::runtime::driver::op::{Completable, CqeResult, MultiCQEStream, Op};
+use crate::runtime::CONTEXT;
+use futures_core::Stream;
+use io_uring::cqueue;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// A multishot recv, completing into a buffer selected from a group each time data arrives.
+pub(crate) struct RecvMulti {
+    /// Holds a strong ref to the FD, preventing the file from being closed
+    /// while the operation is in-flight.
+    #[allow(dead_code)]
+    fd: SharedFd,
+}
+
+impl Op<RecvMulti, MultiCQEStream> {
+    pub(crate) fn recv_multi(fd: &SharedFd, bgid: u16) -> io::Result<Self> {
+        use io_uring::{opcode, types};
+
+        CONTEXT.with(|x| {
+            x.handle()
+                .expect("Not in a runtime context")
+                .submit_op(RecvMulti { fd: fd.clone() }, |recv| {
+                    opcode::RecvMulti::new(types::Fd(recv.fd.raw_fd()), bgid).build()
+                })
+        })
+    }
+}
+
+impl Completable for RecvMulti {
+    type Output = CqeResult;
+
+    fn complete(self, cqe: CqeResult) -> Self::Output {
+        cqe
+    }
+}
+
+enum State {
+    /// Nothing in flight; the next poll submits.
+    Idle,
+    Armed(Op<RecvMulti, MultiCQEStream>),
+    /// The group ran out of buffers; the next poll re-arms once one is returned.
+    Starved,
+    /// The peer closed the connection.
+    Done,
+}
+
+/// A stream of buffers received on a socket, selected from a [`BufRing`].
+///
+/// Uses a single multishot recv, re-armed whenever the kernel ends it, including when it ends
+/// it for lack of buffers.
+pub(crate) struct RecvStream {
+    fd: SharedFd,
+    ring: BufRing,
+    state: State,
+}
+
+impl RecvStream {
+    pub(crate) fn new(fd: &SharedFd, ring: &BufRing) -> RecvStream {
+        RecvStream {
+            fd: fd.clone(),
+            ring: ring.clone(),
+            state: State::Idle,
+        }
+    }
+}
+
+// Turns a completion into the buffer the kernel filled, if it selected one.
+fn selected(ring: &BufRing, cqe: &CqeResult) -> Option<(Buffer, usize)> {
+    let bid = cqueue::buffer_select(cqe.flags)?;
+    let n = *cqe.result.as_ref().ok()? as usize;
+    Some((ring.take(bid, n), n))
+}
+
+impl Stream for RecvStream {
+    type Item = io::Result<(Buffer, usize)>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            match &mut this.state {
+                State::Idle => {
+                    this.state = State::Armed(Op::recv_multi(&this.fd, this.ring.bgid())?);
+                }
+                State::Armed(op) => {
+                    let cqe = match op.poll_next_cqe(cx) {
+                        Poll::Ready(cqe) => cqe,
+                        Poll::Pending => return Poll::Pending,
+                    };
+                    if !cqueue::more(cqe.flags) {
+                        // Terminated, by an error, the end of the stream, or the kernel running
+                        // out of room for completions; the next poll re-arms.
+                        this.state = State::Idle;
+                    }
+                    if let Some((buf, n)) = selected(&this.ring, &cqe) {
+                        if n > 0 {
+                            return Poll::Ready(Some(Ok((buf, n))));
+                        }
+                    }
+                    match cqe.result {
+                        Ok(0) => {
+                            this.state = State::Done;
+                            return Poll::Ready(None);
+                        }
+                        Ok(_) => {}
+                        Err(e) if e.raw_os_error() == Some(libc::ENOBUFS) => {
+                            this.state = State::Starved;
+                        }
+                        Err(e) => return Poll::Ready(Some(Err(e))),
+                    }
+                }
+                State::Starved => {
+                    if this.ring.wait_for_buf(cx.waker()) {
+                        return Poll::Pending;
+                    }
+                    this.state = State::Idle;
+                }
+                State::Done => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+impl Drop for RecvStream {
+    fn drop(&mut self) {
+        if let State::Armed(op) = &mut self.state {
+            // Buffers the kernel already filled are sitting in the op's completions; taking
+            // them returns them to the ring.
+            op.cancel();
+            let mut cx = Context::from_waker(futures_util::task::noop_waker_ref());
+            while let Poll::Ready(cqe) = op.poll_next_cqe(&mut cx) {
+                drop(selected(&self.ring, &cqe));
+                if !cqueue::more(cqe.flags) {
+                    break;
+                }
+            }
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use crate::runtime::driver::op::{Completable, CqeResult, Op};
 use crate::runtime::CONTEXT;
 use crate::WithBuffer;
-use crate::{buf::BoundedBufMut, io::SharedFd, Result};
+use crate::{buf::BoundedBufMut, io::DatagramTruncated, io::SharedFd, Result};
 use socket2::SockAddr;
 use std::{
     io::IoSliceMut,
@@ -51,6 +51,8 @@ impl<T: BoundedBufMut> Op<RecvMsg<T>> {
                         types::Fd(recv_from.fd.raw_fd()),
                         recv_from.msghdr.as_mut() as *mut _,
                     )
+                    // Have the kernel return the full length of a truncated datagram.
+                    .flags(libc::MSG_TRUNC as u32)
                     .build()
                 },
             )
@@ -77,11 +79,12 @@ where
 
             let mut bytes = n;
             for buf in &mut bufs {
-                // Safety: the kernel wrote `n` bytes to the buffer.
+                let total = buf.bytes_total();
+                // Safety: the kernel wrote `n` bytes to the buffers, or filled them if the
+                // datagram was truncated.
                 unsafe {
-                    buf.set_init(bytes);
+                    buf.set_init(bytes.min(total));
                 }
-                let total = buf.bytes_total();
                 if bytes > total {
                     bytes -= total;
                 } else {
@@ -93,6 +96,14 @@ where
             (n, socket_addr)
         });
 
+        // The kernel discarded the part of the datagram that did not fit.
+        let res = match res {
+            Ok((n, addr)) if self.msghdr.msg_flags & libc::MSG_TRUNC != 0 => {
+                Err(DatagramTruncated::new_error(addr, n))
+            }
+            res => res,
+        };
+
         res.with_buffer(bufs)
     }
 }
@@ -0,0 +1,239 @@
+use crate::buf::bufring::BufRing;
+use crate::buf::Buffer;
+use crate::io::SharedFd;
+use crate::net::CMsgs;
+use crate::runtime::driver::op::{Completable, CqeResult, MultiCQEStream, Op};
+use crate::runtime::CONTEXT;
+use futures_core::Stream;
+use io_uring::{cqueue, types::RecvMsgOut};
+use socket2::SockAddr;
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// A multishot recvmsg, completing into a buffer selected from a group for each datagram.
+///
+/// The kernel writes an `io_uring_recvmsg_out` header into the buffer, followed by fields
+/// for the source address and the control data, sized as in `msghdr`, and the payload.
+pub(crate) struct RecvMsgMulti {
+    /// Holds a strong ref to the FD, preventing the file from being closed
+    /// while the operation is in-flight.
+    #[allow(dead_code)]
+    fd: SharedFd,
+
+    // Only its `msg_namelen` and `msg_controllen` matter, which size the fields in front of
+    // each payload.
+    #[allow(dead_code)]
+    msghdr: Box<libc::msghdr>,
+}
+
+impl Op<RecvMsgMulti, MultiCQEStream> {
+    pub(crate) fn recvmsg_multi(fd: &SharedFd, bgid: u16, control_len: usize) -> io::Result<Self> {
+        use io_uring::{opcode, types};
+
+        let msghdr = Box::new(fields(control_len));
+        CONTEXT.with(|x| {
+            x.handle().expect("Not in a runtime context").submit_op(
+                RecvMsgMulti {
+                    fd: fd.clone(),
+                    msghdr,
+                },
+                |recv| {
+                    opcode::RecvMsgMulti::new(
+                        types::Fd(recv.fd.raw_fd()),
+                        recv.msghdr.as_ref() as *const _,
+                        bgid,
+                    )
+                    .build()
+                },
+            )
+        })
+    }
+}
+
+impl Completable for RecvMsgMulti {
+    type Output = CqeResult;
+
+    fn complete(self, cqe: CqeResult) -> Self::Output {
+        cqe
+    }
+}
+
+// The room left for the source address, enough for any IP address.
+const NAME_LEN: usize = std::mem::size_of::<libc::sockaddr_in6>();
+
+// A msghdr sizing the address and control fields the kernel writes in front of each payload.
+fn fields(control_len: usize) -> libc::msghdr {
+    // Safety: a zeroed msghdr is valid.
+    let mut msghdr: libc::msghdr = unsafe { std::mem::zeroed() };
+    msghdr.msg_namelen = NAME_LEN as _;
+    msghdr.msg_controllen = control_len as _;
+    msghdr
+}
+
+/// Returns the size of the header, address and control fields in front of each payload.
+pub(crate) fn header_len(control_len: usize) -> usize {
+    // struct io_uring_recvmsg_out is four u32s.
+    16 + NAME_LEN + control_len
+}
+
+enum State {
+    /// Nothing in flight; the next poll submits.
+    Idle,
+    Armed(Op<RecvMsgMulti, MultiCQEStream>),
+    /// The group ran out of buffers; the next poll re-arms once one is returned.
+    Starved,
+}
+
+/// A stream of datagrams received on a socket into buffers selected from a [`BufRing`],
+/// with their source addresses and control messages.
+///
+/// Uses a single multishot recvmsg, re-armed whenever the kernel ends it, including when it
+/// ends it for lack of buffers.
+pub(crate) struct RecvMsgStream {
+    fd: SharedFd,
+    ring: BufRing,
+    control_len: usize,
+    state: State,
+}
+
+impl RecvMsgStream {
+    pub(crate) fn new(fd: &SharedFd, ring: &BufRing, control_len: usize) -> RecvMsgStream {
+        RecvMsgStream {
+            fd: fd.clone(),
+            ring: ring.clone(),
+            control_len,
+            state: State::Idle,
+        }
+    }
+}
+
+type Datagram = (Buffer, SocketAddr, CMsgs);
+
+// Splits a buffer the kernel selected and filled into the payload, narrowed to, and the
+// address and control messages that came with it.
+fn parse(ring: &BufRing, cqe: &CqeResult, msghdr: &libc::msghdr) -> Option<io::Result<Datagram>> {
+    let bid = cqueue::buffer_select(cqe.flags)?;
+    let n = *cqe.result.as_ref().ok()? as usize;
+    let buf = ring.take(bid, n);
+
+    let (offset, len, socket_addr, cmsgs) = {
+        let out = match RecvMsgOut::parse(&buf[0], msghdr) {
+            Ok(out) => out,
+            Err(()) => {
+                return Some(Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "received buffer is too short for its header",
+                )))
+            }
+        };
+        if out.is_payload_truncated() {
+            // As with a single receive, a datagram that does not fit is an error.
+            return Some(Err(io::Error::from_raw_os_error(libc::EMSGSIZE)));
+        }
+
+        let name = out.name_data();
+        // Safety: the name is a socket address of `name.len()` bytes, which fits the storage
+        // since the kernel clipped it to `NAME_LEN`.
+        let socket_addr = unsafe {
+            SockAddr::init(|storage, len| {
+                std::ptr::copy_nonoverlapping(name.as_ptr(), storage.cast::<u8>(), name.len());
+                *len = name.len() as _;
+                Ok(())
+            })
+        }
+        .ok()
+        .and_then(|(_, addr)| addr.as_socket());
+        let Some(socket_addr) = socket_addr else {
+            return Some(Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "datagram received from a non-IP address",
+            )));
+        };
+
+        // The control data is parsed through a msghdr describing just it, which carries the
+        // flags for `MSG_CTRUNC`.
+        let control = out.control_data();
+        // Safety: as in `fields`.
+        let mut control_msghdr: libc::msghdr = unsafe { std::mem::zeroed() };
+        if !control.is_empty() {
+            control_msghdr.msg_control = control.as_ptr() as *mut _;
+            control_msghdr.msg_controllen = control.len() as _;
+        }
+        control_msghdr.msg_flags = out.flags() as _;
+        // Safety: the msghdr describes the initialized control data in the buffer.
+        let cmsgs = unsafe { CMsgs::decode(&control_msghdr) };
+
+        let payload = out.payload_data();
+        let offset = payload.as_ptr() as usize - buf[0].as_ptr() as usize;
+        (offset, payload.len(), socket_addr, cmsgs)
+    };
+    Some(Ok((BufRing::narrow(buf, offset, len), socket_addr, cmsgs)))
+}
+
+impl Stream for RecvMsgStream {
+    type Item = io::Result<Datagram>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            match &mut this.state {
+                State::Idle => {
+                    this.state = State::Armed(Op::recvmsg_multi(
+                        &this.fd,
+                        this.ring.bgid(),
+                        this.control_len,
+                    )?);
+                }
+                State::Armed(op) => {
+                    let cqe = match op.poll_next_cqe(cx) {
+                        Poll::Ready(cqe) => cqe,
+                        Poll::Pending => return Poll::Pending,
+                    };
+                    let datagram = parse(&this.ring, &cqe, &fields(this.control_len));
+                    if !cqueue::more(cqe.flags) {
+                        // Terminated, by an error or the kernel running out of room for
+                        // completions; the next poll re-arms.
+                        this.state = State::Idle;
+                    }
+                    if let Some(datagram) = datagram {
+                        return Poll::Ready(Some(datagram));
+                    }
+                    match cqe.result {
+                        Ok(_) => {}
+                        Err(e) if e.raw_os_error() == Some(libc::ENOBUFS) => {
+                            this.state = State::Starved;
+                        }
+                        Err(e) => return Poll::Ready(Some(Err(e))),
+                    }
+                }
+                State::Starved => {
+                    if this.ring.wait_for_buf(cx.waker()) {
+                        return Poll::Pending;
+                    }
+                    this.state = State::Idle;
+                }
+            }
+        }
+    }
+}
+
+impl Drop for RecvMsgStream {
+    fn drop(&mut self) {
+        if let State::Armed(op) = &mut self.state {
+            // Buffers the kernel already filled are sitting in the op's completions; taking
+            // them returns them to the ring.
+            op.cancel();
+            let mut cx = Context::from_waker(futures_util::task::noop_waker_ref());
+            while let Poll::Ready(cqe) = op.poll_next_cqe(&mut cx) {
+                if let Some(bid) = cqueue::buffer_select(cqe.flags) {
+                    drop(self.ring.take(bid, 0));
+                }
+                if !cqueue::more(cqe.flags) {
+                    break;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use crate::buf::Buffer;
+use crate::io::SharedFd;
+use crate::runtime::driver::op::{Completable, CqeResult, Op};
+use crate::runtime::CONTEXT;
+use crate::WithBuffer;
+use std::io;
+
+/// Continue a read or write partway through a buffer, after a short one.
+pub(crate) struct Resume {
+    /// Holds a strong ref to the FD, preventing the file from being closed
+    /// while the operation is in-flight.
+    #[allow(dead_code)]
+    fd: SharedFd,
+
+    buf: Buffer,
+
+    // The part of the buffer still to be transferred, read by the kernel.
+    iovecs: Vec<libc::iovec>,
+
+    // Describes `iovecs` to a send; `None` for reads.
+    msghdr: Option<Box<libc::msghdr>>,
+}
+
+impl Op<Resume> {
+    /// Sends the initialized bytes of `buf` from index `from` on, to a socket.
+    ///
+    /// `MSG_NOSIGNAL` has a closed connection fail the send with `EPIPE`, where a write
+    /// would raise `SIGPIPE`.
+    pub(crate) fn write_from(fd: &SharedFd, buf: Buffer, from: usize) -> io::Result<Op<Resume>> {
+        use io_uring::{opcode, types};
+
+        let iovecs = buf.init_iovecs_from(from);
+        // Safety: a zeroed msghdr is valid.
+        let msghdr: Box<libc::msghdr> = Box::new(unsafe { std::mem::zeroed() });
+        CONTEXT.with(|x| {
+            x.handle().expect("Not in a runtime context").submit_op(
+                Resume {
+                    fd: fd.clone(),
+                    buf,
+                    iovecs,
+                    msghdr: Some(msghdr),
+                },
+                |resume| {
+                    let msghdr = resume.msghdr.as_mut().unwrap();
+                    msghdr.msg_iov = resume.iovecs.as_mut_ptr();
+                    msghdr.msg_iovlen = resume.iovecs.len() as _;
+                    opcode::SendMsg::new(types::Fd(resume.fd.raw_fd()), msghdr.as_ref() as *const _)
+                        .flags(libc::MSG_NOSIGNAL as u32)
+                        .build()
+                },
+            )
+        })
+    }
+
+    /// Reads into the capacity of `buf` from byte `from` on, leaving the bytes before it as
+    /// they are.
+    pub(crate) fn read_from(fd: &SharedFd, buf: Buffer, from: usize) -> io::Result<Op<Resume>> {
+        use io_uring::{opcode, types};
+
+        let iovecs = buf.spare_iovecs_from(from);
+        CONTEXT.with(|x| {
+            x.handle().expect("Not in a runtime context").submit_op(
+                Resume {
+                    fd: fd.clone(),
+                    buf,
+                    iovecs,
+                    msghdr: None,
+                },
+                |resume| {
+                    opcode::Readv::new(
+                        types::Fd(resume.fd.raw_fd()),
+                        resume.iovecs.as_ptr(),
+                        resume.iovecs.len() as _,
+                    )
+                    .build()
+                },
+            )
+        })
+    }
+}
+
+impl Completable for Resume {
+    type Output = crate::Result<usize, Buffer>;
+
+    fn complete(self, cqe: CqeResult) -> Self::Output {
+        cqe.result.map(|n| n as usize).with_buffer(self.buf)
+    }
+}
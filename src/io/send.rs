@@ -0,0 +1,114 @@
+use crate::buf::Buffer;
+use crate::io::SharedFd;
+use crate::{InFlightOneshot, OneshotOutputTransform, Submit, UnsubmittedOneshot, WithBuffer};
+use io_uring::cqueue;
+use std::io;
+
+/// A send that has not been submitted yet, whose `MSG_*` flags can still be set.
+///
+/// Returned by [`TcpStream::send`](crate::net::TcpStream::send); [`submit`](Submit::submit)
+/// it and await the result, which is the number of bytes sent and the original buffer.
+///
+/// # Examples
+///
+/// Sending a header and a body as one segment rather than two:
+///
+/// ```no_run
+/// use tokio_uring::net::TcpStream;
+/// use tokio_uring::Submit;
+///
+/// tokio_uring::start(async {
+///     let stream = TcpStream::connect("127.0.0.1:8080".parse().unwrap()).await.unwrap();
+///
+///     let header = b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\n".to_vec();
+///     stream.send(header.into()).more().submit().await.unwrap();
+///     stream.send(b"hello".to_vec().into()).submit().await.unwrap();
+/// });
+/// ```
+pub struct UnsubmittedSend {
+    fd: SharedFd,
+    buf: Buffer,
+    flags: libc::c_int,
+}
+
+impl UnsubmittedSend {
+    pub(crate) fn new(fd: &SharedFd, buf: Buffer) -> UnsubmittedSend {
+        UnsubmittedSend {
+            fd: fd.clone(),
+            buf,
+            flags: 0,
+        }
+    }
+
+    /// Sets `MSG_MORE`: the kernel holds the data back, expecting more to follow, and sends
+    /// it along with the next send without the flag, or after a short timeout, in as few
+    /// segments as possible.
+    ///
+    /// Unlike [`TcpStream::set_cork`](crate::net::TcpStream::set_cork), this applies to this
+    /// send alone, and is honored whether or not `TCP_NODELAY` is set.
+    pub fn more(mut self) -> Self {
+        self.flags |= libc::MSG_MORE;
+        self
+    }
+}
+
+impl Submit for UnsubmittedSend {
+    type Output = InFlightOneshot<SendData, SendTransform>;
+
+    fn submit(self) -> Self::Output {
+        use io_uring::{opcode, types};
+
+        let buf = self.buf;
+        let empty = buf.is_empty();
+
+        // The buffer's own iovecs cover the initialized bytes of every segment.
+        let mut msghdr: Box<libc::msghdr> = Box::new(unsafe { std::mem::zeroed() });
+        msghdr.msg_iov = buf.iter().as_slice().as_ptr() as *mut _;
+        msghdr.msg_iovlen = buf.segment_count() as _;
+
+        let sqe = opcode::SendMsg::new(types::Fd(self.fd.raw_fd()), msghdr.as_ref() as *const _)
+            .flags((self.flags | libc::MSG_NOSIGNAL) as u32)
+            .build();
+
+        UnsubmittedOneshot::new(
+            SendData {
+                _fd: self.fd,
+                buf,
+                _msghdr: msghdr,
+            },
+            SendTransform,
+            sqe,
+        )
+        .set_empty(empty)
+        .submit()
+    }
+}
+
+#[allow(missing_docs)]
+pub struct SendData {
+    /// Holds a strong ref to the FD, preventing the file from being closed
+    /// while the operation is in-flight.
+    _fd: SharedFd,
+
+    buf: Buffer,
+
+    // Read by the kernel.
+    _msghdr: Box<libc::msghdr>,
+}
+
+#[allow(missing_docs)]
+pub struct SendTransform;
+
+impl OneshotOutputTransform for SendTransform {
+    type Output = crate::Result<usize, Buffer>;
+
+    type StoredData = SendData;
+
+    fn transform_oneshot_output(self, data: SendData, cqe: cqueue::Entry) -> Self::Output {
+        let n = cqe.result();
+        if n < 0 {
+            return Err(io::Error::from_raw_os_error(-n)).with_buffer(data.buf);
+        }
+        Ok((n as usize, data.buf))
+    }
+}
@@ -16,7 +16,8 @@ pub(crate) struct SendTo<T> {
     io_slices: Vec<IoSlice<'static>>,
     #[allow(dead_code)]
     socket_addr: Option<Box<SockAddr>>,
-    pub(crate) msghdr: Box<libc::msghdr>,
+    // `None` for a send on a connected socket, which goes out as a plain `IORING_OP_SEND`.
+    pub(crate) msghdr: Option<Box<libc::msghdr>>,
 }
 
 impl<T: BoundedBuf> Op<SendTo<T>> {
@@ -27,27 +28,37 @@ impl<T: BoundedBuf> Op<SendTo<T>> {
     ) -> io::Result<Op<SendTo<T>>> {
         use io_uring::{opcode, types};
 
+        let Some(socket_addr) = socket_addr else {
+            return CONTEXT.with(|x| {
+                x.handle().expect("Not in a runtime context").submit_op(
+                    SendTo {
+                        fd: fd.clone(),
+                        buf,
+                        io_slices: Vec::new(),
+                        socket_addr: None,
+                        msghdr: None,
+                    },
+                    |send| {
+                        let ptr = send.buf.stable_ptr();
+                        let len = send.buf.bytes_init();
+                        opcode::Send::new(types::Fd(send.fd.raw_fd()), ptr, len as _)
+                            .flags(libc::MSG_NOSIGNAL)
+                            .build()
+                    },
+                )
+            });
+        };
+
         let io_slices = vec![IoSlice::new(unsafe {
             std::slice::from_raw_parts(buf.stable_ptr(), buf.bytes_init())
         })];
 
+        let socket_addr = Box::new(SockAddr::from(socket_addr));
         let mut msghdr: Box<libc::msghdr> = Box::new(unsafe { std::mem::zeroed() });
         msghdr.msg_iov = io_slices.as_ptr() as *mut _;
         msghdr.msg_iovlen = io_slices.len() as _;
-
-        let socket_addr = match socket_addr {
-            Some(_socket_addr) => {
-                let socket_addr = Box::new(SockAddr::from(_socket_addr));
-                msghdr.msg_name = socket_addr.as_ptr() as *mut libc::c_void;
-                msghdr.msg_namelen = socket_addr.len();
-                Some(socket_addr)
-            }
-            None => {
-                msghdr.msg_name = std::ptr::null_mut();
-                msghdr.msg_namelen = 0;
-                None
-            }
-        };
+        msghdr.msg_name = socket_addr.as_ptr() as *mut libc::c_void;
+        msghdr.msg_namelen = socket_addr.len();
 
         CONTEXT.with(|x| {
             x.handle().expect("Not in a runtime context").submit_op(
@@ -55,14 +66,15 @@ impl<T: BoundedBuf> Op<SendTo<T>> {
                     fd: fd.clone(),
                     buf,
                     io_slices,
-                    socket_addr,
-                    msghdr,
+                    socket_addr: Some(socket_addr),
+                    msghdr: Some(msghdr),
                 },
                 |send_to| {
                     opcode::SendMsg::new(
                         types::Fd(send_to.fd.raw_fd()),
-                        send_to.msghdr.as_ref() as *const _,
+                        send_to.msghdr.as_deref().unwrap() as *const _,
                     )
+                    .flags(libc::MSG_NOSIGNAL as u32)
                     .build()
                 },
             )
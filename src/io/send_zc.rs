@@ -14,8 +14,14 @@ pub(crate) struct SendZc<T> {
 
     /// Hold the number of transmitted bytes
     bytes: usize,
+
+    /// An error reported ahead of the notification
+    error: Option<io::Error>,
 }
 
+/// Set on the CQE which tells the buffer is no longer referenced by the kernel.
+pub(crate) const IORING_CQE_F_NOTIF: u32 = 1 << 3;
+
 impl<T: BoundedBuf> Op<SendZc<T>, MultiCQEFuture> {
     pub(crate) fn send_zc(fd: &SharedFd, buf: T) -> io::Result<Self> {
         use io_uring::{opcode, types};
@@ -26,13 +32,16 @@ impl<T: BoundedBuf> Op<SendZc<T>, MultiCQEFuture> {
                     fd: fd.clone(),
                     buf,
                     bytes: 0,
+                    error: None,
                 },
                 |send| {
                     // Get raw buffer info
                     let ptr = send.buf.stable_ptr();
                     let len = send.buf.bytes_init();
 
-                    opcode::SendZc::new(types::Fd(fd.raw_fd()), ptr, len as _).build()
+                    opcode::SendZc::new(types::Fd(fd.raw_fd()), ptr, len as _)
+                        .flags(libc::MSG_NOSIGNAL)
+                        .build()
                 },
             )
         })
@@ -43,6 +52,14 @@ impl<T> Completable for SendZc<T> {
     type Output = Result<usize, T>;
 
     fn complete(self, cqe: CqeResult) -> Self::Output {
+        if let Some(error) = self.error {
+            return Err(error).with_buffer(self.buf);
+        }
+        if cqe.flags & IORING_CQE_F_NOTIF != 0 {
+            // The byte count came with the first completion.
+            return Ok((self.bytes, self.buf));
+        }
+        // No notification follows, such as when nothing could be sent.
         cqe.result
             .map(|v| self.bytes + v as usize)
             .with_buffer(self.buf)
@@ -51,7 +68,11 @@ impl<T> Completable for SendZc<T> {
 
 impl<T> Updateable for SendZc<T> {
     fn update(&mut self, cqe: CqeResult) {
-        // uring send_zc promises there will be no error on CQE's marked more
-        self.bytes += *cqe.result.as_ref().unwrap() as usize;
+        // A failed send may still be followed by a notification, if the kernel had already
+        // pinned the buffer.
+        match cqe.result {
+            Ok(n) => self.bytes += n as usize,
+            Err(e) => self.error = Some(e),
+        }
     }
 }
@@ -78,6 +78,7 @@ impl<T: BoundedBuf, U: BoundedBuf> Op<SendMsg<T, U>> {
                         types::Fd(sendmsg._fd.raw_fd()),
                         &*sendmsg.msghdr as *const _,
                     )
+                    .flags(libc::MSG_NOSIGNAL as u32)
                     .build()
                 },
             )
@@ -1,7 +1,9 @@
-use crate::buf::BoundedBuf;
+use crate::buf::{BoundedBuf, Buffer};
+use crate::io::send_zc::IORING_CQE_F_NOTIF;
 use crate::io::SharedFd;
 use crate::runtime::driver::op::{Completable, CqeResult, MultiCQEFuture, Op, Updateable};
 use crate::runtime::CONTEXT;
+use crate::WithBuffer;
 use socket2::SockAddr;
 use std::io;
 use std::io::IoSlice;
@@ -86,6 +88,7 @@ impl<T: BoundedBuf, U: BoundedBuf> Op<SendMsgZc<T, U>, MultiCQEFuture> {
                         types::Fd(sendmsg_zc.fd.raw_fd()),
                         sendmsg_zc.msghdr.as_mut() as *const _,
                     )
+                    .flags(libc::MSG_NOSIGNAL as u32)
                     .build()
                 },
             )
@@ -116,3 +119,74 @@ impl<T, U> Updateable for SendMsgZc<T, U> {
         self.bytes += *cqe.result.as_ref().unwrap() as usize;
     }
 }
+
+/// Zero-copy vectored send of every segment of a `Buffer`.
+pub(crate) struct WritevZc {
+    #[allow(dead_code)]
+    fd: SharedFd,
+    buf: Buffer,
+    #[allow(dead_code)]
+    msghdr: Box<libc::msghdr>,
+
+    /// Hold the number of transmitted bytes
+    bytes: usize,
+
+    /// An error reported ahead of the notification
+    error: Option<io::Error>,
+}
+
+impl Op<WritevZc, MultiCQEFuture> {
+    pub(crate) fn writev_zc(fd: &SharedFd, buf: Buffer) -> io::Result<Self> {
+        use io_uring::{opcode, types};
+
+        let mut msghdr: Box<libc::msghdr> = Box::new(unsafe { std::mem::zeroed() });
+        // The initialized length of each segment is what gets sent.
+        msghdr.msg_iov = buf.iter().as_slice().as_ptr() as *mut _;
+        msghdr.msg_iovlen = buf.segment_count() as _;
+
+        CONTEXT.with(|x| {
+            x.handle().expect("Not in a runtime context").submit_op(
+                WritevZc {
+                    fd: fd.clone(),
+                    buf,
+                    msghdr,
+                    bytes: 0,
+                    error: None,
+                },
+                |writev_zc| {
+                    opcode::SendMsgZc::new(
+                        types::Fd(writev_zc.fd.raw_fd()),
+                        writev_zc.msghdr.as_ref() as *const _,
+                    )
+                    .flags(libc::MSG_NOSIGNAL as u32)
+                    .build()
+                },
+            )
+        })
+    }
+}
+
+impl Completable for WritevZc {
+    type Output = crate::Result<usize, Buffer>;
+
+    fn complete(self, cqe: CqeResult) -> Self::Output {
+        if let Some(error) = self.error {
+            return Err(error).with_buffer(self.buf);
+        }
+        if cqe.flags & IORING_CQE_F_NOTIF != 0 {
+            return Ok((self.bytes, self.buf));
+        }
+        cqe.result
+            .map(|v| self.bytes + v as usize)
+            .with_buffer(self.buf)
+    }
+}
+
+impl Updateable for WritevZc {
+    fn update(&mut self, cqe: CqeResult) {
+        match cqe.result {
+            Ok(n) => self.bytes += n as usize,
+            Err(e) => self.error = Some(e),
+        }
+    }
+}
@@ -58,6 +58,20 @@ impl SharedFd {
         self.inner.fd
     }
 
+    /// Releases ownership of the file descriptor, leaving it open.
+    ///
+    /// Fails, handing back the `SharedFd`, while operations are still in flight on it.
+    pub(crate) fn try_into_raw_fd(mut self) -> Result<RawFd, SharedFd> {
+        match Rc::get_mut(&mut self.inner) {
+            Some(inner) => {
+                // Marking it closed keeps the drop below from closing it.
+                *inner.state.get_mut() = State::Closed;
+                Ok(inner.fd)
+            }
+            None => Err(self),
+        }
+    }
+
     /// An FD cannot be closed until all in-flight operation have completed.
     /// This prevents bugs where in-flight reads could operate on the incorrect
     /// file descriptor.
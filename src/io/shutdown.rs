@@ -0,0 +1,38 @@
+use crate::io::SharedFd;
+use crate::runtime::driver::op::{Completable, CqeResult, Op};
+use crate::runtime::CONTEXT;
+use std::io;
+use std::net::Shutdown as How;
+
+/// Shut down one or both halves of a connection.
+pub(crate) struct Shutdown {
+    fd: SharedFd,
+}
+
+impl Op<Shutdown> {
+    pub(crate) fn shutdown(fd: &SharedFd, how: How) -> io::Result<Op<Shutdown>> {
+        use io_uring::{opcode, types};
+
+        let how = match how {
+            How::Read => libc::SHUT_RD,
+            How::Write => libc::SHUT_WR,
+            How::Both => libc::SHUT_RDWR,
+        };
+
+        CONTEXT.with(|x| {
+            x.handle()
+                .expect("Not in a runtime context")
+                .submit_op(Shutdown { fd: fd.clone() }, |shutdown| {
+                    opcode::Shutdown::new(types::Fd(shutdown.fd.raw_fd()), how).build()
+                })
+        })
+    }
+}
+
+impl Completable for Shutdown {
+    type Output = io::Result<()>;
+
+    fn complete(self, cqe: CqeResult) -> Self::Output {
+        cqe.result.map(|_| ())
+    }
+}
@@ -1,16 +1,23 @@
-use crate::buf::Buffer;
+use crate::buf::fixed::registry;
+use crate::buf::{read_only_error, Buffer, IoBuf, IoBufMut};
+use crate::io::accept::{Accept, AcceptDirect};
+use crate::io::poll::Readiness;
 use crate::io::read_write::Unsubmitted;
+use crate::net::KeepaliveConfig;
 use crate::runtime::driver::op::{Op, Submit};
+use crate::runtime::CONTEXT;
 use crate::{
     buf::{BoundedBuf, BoundedBufMut, Slice},
-    io::SharedFd,
-    UnsubmittedOneshot,
+    io::{AcceptFlags, AcceptGate, DirectFd, Incoming, Interest, Ready, RecvBatch, SharedFd},
+    UnsubmittedOneshot, WithBuffer,
 };
+use futures_util::StreamExt;
 use std::{
     io,
-    net::SocketAddr,
-    os::unix::io::{AsRawFd, IntoRawFd, RawFd},
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr},
+    os::unix::io::{AsRawFd, FromRawFd, IntoRawFd, RawFd},
     path::Path,
+    sync::atomic::{AtomicU8, Ordering},
 };
 
 #[derive(Clone)]
@@ -28,9 +35,19 @@ pub(crate) fn get_domain(socket_addr: SocketAddr) -> libc::c_int {
 
 impl Socket {
     pub(crate) fn new(socket_addr: SocketAddr, socket_type: libc::c_int) -> io::Result<Socket> {
+        Self::new_with_protocol(socket_addr, socket_type, 0)
+    }
+
+    /// Like `new`, with an explicit protocol, as raw sockets need.
+    pub(crate) fn new_with_protocol(
+        socket_addr: SocketAddr,
+        socket_type: libc::c_int,
+        protocol: libc::c_int,
+    ) -> io::Result<Socket> {
         let socket_type = socket_type | libc::SOCK_CLOEXEC;
         let domain = get_domain(socket_addr);
-        let fd = socket2::Socket::new(domain.into(), socket_type.into(), None)?.into_raw_fd();
+        let protocol = (protocol != 0).then(|| protocol.into());
+        let fd = socket2::Socket::new(domain.into(), socket_type.into(), protocol)?.into_raw_fd();
         let fd = SharedFd::new(fd);
         Ok(Socket { fd })
     }
@@ -43,14 +60,50 @@ impl Socket {
         Ok(Socket { fd })
     }
 
+    pub(crate) fn new_vsock(socket_type: libc::c_int) -> io::Result<Socket> {
+        let socket_type = socket_type | libc::SOCK_CLOEXEC;
+        let fd = socket2::Socket::new(socket2::Domain::VSOCK, socket_type.into(), None)?;
+        Ok(Socket {
+            fd: SharedFd::new(fd.into_raw_fd()),
+        })
+    }
+
+    pub(crate) fn pair(socket_type: libc::c_int) -> io::Result<(Socket, Socket)> {
+        let (a, b) = socket2::Socket::pair(socket2::Domain::UNIX, socket_type.into(), None)?;
+        let a = Socket::from_shared_fd(SharedFd::new(a.into_raw_fd()));
+        let b = Socket::from_shared_fd(SharedFd::new(b.into_raw_fd()));
+        Ok((a, b))
+    }
+
     pub(crate) fn write(&self, buf: Buffer) -> Unsubmitted {
-        Unsubmitted::write_at(&self.fd, buf, 0)
+        Unsubmitted::send(&self.fd, buf)
+    }
+
+    /// Like `write`, but submits the write even with no bytes to send, which sends an empty
+    /// datagram.
+    pub(crate) fn write_datagram(&self, buf: Buffer) -> Unsubmitted {
+        Unsubmitted::send(&self.fd, buf).set_empty(false)
     }
 
     pub(crate) async fn write_fixed<T>(&self, buf: T) -> crate::Result<usize, T>
     where
         T: BoundedBuf<Buf = Buffer>,
     {
+        if buf.bytes_init() == 0 {
+            return Ok((0, buf));
+        }
+        self.write_fixed_datagram(buf).await
+    }
+
+    /// Like `write_fixed`, but submits the write even with no bytes to send, which sends an
+    /// empty datagram.
+    pub(crate) async fn write_fixed_datagram<T>(&self, buf: T) -> crate::Result<usize, T>
+    where
+        T: BoundedBuf<Buf = Buffer>,
+    {
+        if let Err(e) = registry::check_registered(buf.get_buf()) {
+            return Err(e).with_buffer(buf);
+        }
         let op = Op::write_fixed_at(&self.fd, buf, 0).unwrap();
         op.await
     }
@@ -94,6 +147,58 @@ impl Socket {
         Ok(((), buf.into_inner()))
     }
 
+    pub(crate) async fn write_all(&self, buf: Buffer) -> crate::Result<(), Buffer> {
+        let total = IoBuf::bytes_init(&buf);
+        let mut written = 0;
+        let mut buf = buf;
+        while written < total {
+            let (n, b) = Op::write_from(&self.fd, buf, written).unwrap().await?;
+            if n == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::WriteZero,
+                    "failed to write whole buffer",
+                ))
+                .with_buffer(b);
+            }
+            written += n;
+            buf = b;
+        }
+        Ok(((), buf))
+    }
+
+    pub(crate) async fn read_exact(&self, mut buf: Buffer) -> crate::Result<(), Buffer> {
+        if buf.is_read_only() {
+            return Err(read_only_error()).with_buffer(buf);
+        }
+        let total = IoBuf::bytes_total(&buf);
+        let mut read = 0;
+        // Safety: nothing has been read yet.
+        unsafe { IoBufMut::set_init(&mut buf, 0) };
+        while read < total {
+            let (n, mut b) = match Op::read_from(&self.fd, buf, read).unwrap().await {
+                Ok(res) => res,
+                Err(e) => {
+                    let (e, mut b) = (e.0, e.1);
+                    // Safety: the bytes read by the earlier operations are still there.
+                    unsafe { IoBufMut::set_init(&mut b, read) };
+                    return Err(e).with_buffer(b);
+                }
+            };
+            read += n;
+            // Safety: the kernel wrote `n` bytes after the `read - n` already there.
+            unsafe { IoBufMut::set_init(&mut b, read) };
+            if n == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "failed to fill whole buffer",
+                ))
+                .with_buffer(b);
+            }
+            buf = b;
+        }
+        Ok(((), buf))
+    }
+
     pub(crate) async fn send_to<T: BoundedBuf>(
         &self,
         buf: T,
@@ -103,11 +208,55 @@ impl Socket {
         op.await
     }
 
+    pub(crate) async fn send_batch(
+        &self,
+        items: Vec<(Buffer, SocketAddr)>,
+    ) -> Vec<crate::Result<usize, Buffer>> {
+        // The sends are queued back to back, so they reach the kernel together.
+        let ops: Vec<_> = items
+            .into_iter()
+            .map(|(buf, socket_addr)| Op::send_to(&self.fd, buf, Some(socket_addr)).unwrap())
+            .collect();
+        futures_util::future::join_all(ops).await
+    }
+
     pub(crate) async fn send_zc<T: BoundedBuf>(&self, buf: T) -> crate::Result<usize, T> {
         let op = Op::send_zc(&self.fd, buf).unwrap();
         op.await
     }
 
+    pub(crate) async fn write_zc(&self, buf: Buffer) -> crate::Result<usize, Buffer> {
+        if buf.segment_count() != 1 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "zero-copy writes take a single-segment buffer",
+            ))
+            .with_buffer(buf);
+        }
+        let zc = CONTEXT.with(|x| {
+            x.handle()
+                .expect("Not in a runtime context")
+                .is_supported(io_uring::opcode::SendZc::CODE)
+        });
+        if !zc {
+            return self.write(buf).submit().await;
+        }
+        self.send_zc(buf).await
+    }
+
+    pub(crate) async fn writev_zc(&self, buf: Buffer) -> crate::Result<usize, Buffer> {
+        let zc = CONTEXT.with(|x| {
+            x.handle()
+                .expect("Not in a runtime context")
+                .is_supported(io_uring::opcode::SendMsgZc::CODE)
+        });
+        if !zc {
+            return self.write(buf).submit().await;
+        }
+        let op = Op::writev_zc(&self.fd, buf).unwrap();
+        op.await
+    }
+
     pub(crate) async fn sendmsg<T: BoundedBuf, U: BoundedBuf>(
         &self,
         io_slices: Vec<T>,
@@ -132,33 +281,120 @@ impl Socket {
         UnsubmittedOneshot::read_at(&self.fd, buf, 0).submit().await
     }
 
+    /// Like `read`, but submits the read even into no capacity, which consumes a datagram.
+    pub(crate) async fn read_datagram(&self, buf: Buffer) -> crate::Result<usize, Buffer> {
+        UnsubmittedOneshot::read_at(&self.fd, buf, 0)
+            .set_empty(false)
+            .submit()
+            .await
+    }
+
     pub(crate) async fn read_fixed<T>(&self, buf: T) -> crate::Result<usize, T>
     where
         T: BoundedBufMut<BufMut = Buffer>,
     {
+        if buf.bytes_total() == 0 {
+            return Ok((0, buf));
+        }
+        self.read_fixed_datagram(buf).await
+    }
+
+    /// Like `read_fixed`, but submits the read even into no capacity, which consumes a
+    /// datagram.
+    pub(crate) async fn read_fixed_datagram<T>(&self, buf: T) -> crate::Result<usize, T>
+    where
+        T: BoundedBufMut<BufMut = Buffer>,
+    {
+        if let Err(e) = registry::check_registered(buf.get_buf()) {
+            return Err(e).with_buffer(buf);
+        }
         let op = Op::read_fixed_at(&self.fd, buf, 0).unwrap();
         op.await
     }
 
+    pub(crate) async fn recv_batch(
+        &self,
+        bufs: Vec<Buffer>,
+        min: usize,
+    ) -> crate::Result<Vec<(usize, Buffer, SocketAddr)>, Vec<Buffer>> {
+        if bufs.iter().any(Buffer::is_read_only) {
+            return Err(read_only_error()).with_buffer(bufs);
+        }
+        RecvBatch::new(&self.fd, bufs, min).await
+    }
+
     pub(crate) async fn recv_from<T: BoundedBufMut>(
         &self,
         buf: T,
     ) -> crate::Result<(usize, SocketAddr), T> {
+        if buf.is_read_only() {
+            return Err(read_only_error()).with_buffer(buf);
+        }
         let op = Op::recv_from(&self.fd, buf).unwrap();
         op.await
     }
 
+    pub(crate) async fn recv_msg(
+        &self,
+        buf: Buffer,
+        control_len: usize,
+    ) -> crate::Result<(usize, socket2::SockAddr, crate::net::CMsgs), Buffer> {
+        if buf.is_read_only() {
+            return Err(read_only_error()).with_buffer(buf);
+        }
+        let op = Op::recv_msg_control(&self.fd, buf, control_len).unwrap();
+        op.await
+    }
+
+    pub(crate) async fn send_msg(
+        &self,
+        buf: Buffer,
+        socket_addr: Option<socket2::SockAddr>,
+        cmsgs: &crate::net::CMsgs,
+    ) -> crate::Result<usize, Buffer> {
+        let op = Op::send_msg_control(&self.fd, buf, socket_addr, cmsgs).unwrap();
+        op.await
+    }
+
     pub(crate) async fn recvmsg<T: BoundedBufMut>(
         &self,
         buf: Vec<T>,
     ) -> crate::Result<(usize, SocketAddr), Vec<T>> {
+        if buf.iter().any(BoundedBufMut::is_read_only) {
+            return Err(read_only_error()).with_buffer(buf);
+        }
         let op = Op::recvmsg(&self.fd, buf).unwrap();
         op.await
     }
 
-    pub(crate) async fn accept(&self) -> io::Result<(Socket, Option<SocketAddr>)> {
-        let op = Op::accept(&self.fd)?;
-        op.await
+    pub(crate) async fn ready(&self, interest: Interest) -> io::Result<Ready> {
+        Readiness::new(&self.fd, interest)?.await
+    }
+
+    /// Accepts a connection once `gate` lets it through.
+    pub(crate) async fn accept(
+        &self,
+        flags: AcceptFlags,
+        gate: &AcceptGate,
+    ) -> io::Result<(Socket, socket2::SockAddr)> {
+        let mut incoming = Incoming::<Accept>::new(&self.fd, gate, flags);
+        incoming.next().await.expect("accepting never ends")
+    }
+
+    pub(crate) async fn accept_direct(
+        &self,
+        gate: &AcceptGate,
+    ) -> io::Result<(DirectFd, socket2::SockAddr)> {
+        let has_table =
+            CONTEXT.with(|x| x.handle().expect("Not in a runtime context").direct_files() > 0);
+        if !has_table {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "the runtime has no direct descriptor table; size it with Builder::direct_files",
+            ));
+        }
+        let mut incoming = Incoming::<AcceptDirect>::new(&self.fd, gate, AcceptFlags::NONE);
+        incoming.next().await.expect("accepting never ends")
     }
 
     pub(crate) async fn connect(&self, socket_addr: socket2::SockAddr) -> io::Result<()> {
@@ -166,6 +402,135 @@ impl Socket {
         op.await
     }
 
+    pub(crate) async fn connect_timeout(
+        &self,
+        socket_addr: socket2::SockAddr,
+        timeout: std::time::Duration,
+    ) -> io::Result<()> {
+        let op = Op::connect_timeout(&self.fd, socket_addr, timeout)?;
+        op.await
+    }
+
+    /// Connects with TCP Fast Open, sending `buf` in full. Returns the number of bytes the
+    /// server accepted along with the SYN, which is 0 when fast open did not happen.
+    pub(crate) async fn connect_with_data(
+        &self,
+        socket_addr: SocketAddr,
+        buf: Buffer,
+    ) -> crate::Result<usize, Buffer> {
+        let total = IoBuf::bytes_init(&buf);
+        // Without data, there is nothing to gain, and a plain connect reports failures.
+        let fast_open = total > 0 && self.set_fastopen_connect();
+        if let Err(e) = self.connect(socket_addr.into()).await {
+            return Err(e).with_buffer(buf);
+        }
+
+        // With TCP_FASTOPEN_CONNECT, the connect above completed at once, without sending
+        // anything. The first write sends the SYN, carrying as much of the data as fits if the
+        // kernel holds a cookie from an earlier connection to the server, and completes with
+        // that much; later writes wait for the handshake.
+        let mut written = 0;
+        let mut syn_data = None;
+        let mut buf = buf;
+        while written < total {
+            let (n, b) = match Op::write_from(&self.fd, buf, written).unwrap().await {
+                Ok(res) => res,
+                // No cookie yet: the SYN went out on its own, asking for one.
+                Err(e) if fast_open && e.0.raw_os_error() == Some(libc::EINPROGRESS) => {
+                    syn_data.get_or_insert(0);
+                    buf = e.1;
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            if n == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::WriteZero,
+                    "failed to write whole buffer",
+                ))
+                .with_buffer(b);
+            }
+            syn_data.get_or_insert(n);
+            written += n;
+            buf = b;
+        }
+        if !fast_open {
+            return Ok((0, buf));
+        }
+
+        // Even an empty send waits for the handshake, after which the kernel knows whether
+        // the server took the data in the SYN or it had to be sent again. This also reports
+        // a refused connection, which the writes may not have waited long enough to see.
+        let empty = Buffer::from(Vec::<u8>::new());
+        if let Err(e) = self.send_msg(empty, None, &crate::net::CMsgs::new()).await {
+            return Err(e.0).with_buffer(buf);
+        }
+        match self.tcp_info() {
+            Ok(info) if info.tcpi_options & TCPI_OPT_SYN_DATA != 0 => {
+                Ok((syn_data.unwrap_or(0), buf))
+            }
+            Ok(_) => Ok((0, buf)),
+            Err(e) => Err(e).with_buffer(buf),
+        }
+    }
+
+    // Sets TCP_FASTOPEN_CONNECT, returning `false` if the kernel, or its configuration in
+    // `net.ipv4.tcp_fastopen`, does not allow fast open on connect.
+    fn set_fastopen_connect(&self) -> bool {
+        let on: libc::c_int = 1;
+        // Safety: the option value is a valid int for the duration of the call.
+        let res = unsafe {
+            libc::setsockopt(
+                self.as_raw_fd(),
+                libc::IPPROTO_TCP,
+                libc::TCP_FASTOPEN_CONNECT,
+                &on as *const _ as *const libc::c_void,
+                std::mem::size_of_val(&on) as libc::socklen_t,
+            )
+        };
+        res == 0
+    }
+
+    /// Sets TCP_FASTOPEN on a socket about to listen, with the length of the queue of
+    /// connections that sent data in their SYN but have not completed the handshake.
+    pub(crate) fn set_fastopen(&self, queue_len: u32) -> io::Result<()> {
+        let len = queue_len.min(libc::c_int::MAX as u32) as libc::c_int;
+        // Safety: the option value is a valid int for the duration of the call.
+        let res = unsafe {
+            libc::setsockopt(
+                self.as_raw_fd(),
+                libc::IPPROTO_TCP,
+                libc::TCP_FASTOPEN,
+                &len as *const _ as *const libc::c_void,
+                std::mem::size_of_val(&len) as libc::socklen_t,
+            )
+        };
+        if res == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    fn tcp_info(&self) -> io::Result<libc::tcp_info> {
+        // Safety: tcp_info is a plain C struct, for which zeroes are valid.
+        let mut info: libc::tcp_info = unsafe { std::mem::zeroed() };
+        let mut len = std::mem::size_of_val(&info) as libc::socklen_t;
+        // Safety: the kernel writes at most `len` bytes into `info`.
+        let res = unsafe {
+            libc::getsockopt(
+                self.as_raw_fd(),
+                libc::IPPROTO_TCP,
+                libc::TCP_INFO,
+                &mut info as *mut _ as *mut libc::c_void,
+                &mut len,
+            )
+        };
+        if res == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(info)
+    }
+
     pub(crate) fn bind(socket_addr: SocketAddr, socket_type: libc::c_int) -> io::Result<Socket> {
         Self::bind_internal(
             socket_addr.into(),
@@ -179,12 +544,37 @@ impl Socket {
         socket_type: libc::c_int,
     ) -> io::Result<Socket> {
         let addr = socket2::SockAddr::unix(path.as_ref())?;
+        Self::bind_unix_addr(addr, socket_type)
+    }
+
+    pub(crate) fn bind_unix_addr(
+        addr: socket2::SockAddr,
+        socket_type: libc::c_int,
+    ) -> io::Result<Socket> {
         Self::bind_internal(addr, libc::AF_UNIX.into(), socket_type.into())
     }
 
     pub(crate) fn from_std<T: IntoRawFd>(socket: T) -> Socket {
-        let fd = SharedFd::new(socket.into_raw_fd());
-        Self::from_shared_fd(fd)
+        let fd = socket.into_raw_fd();
+        // io_uring waits for readiness itself, so the socket is kept in blocking mode, which
+        // is also what std expects should it be converted back.
+        // Safety: fcntl on a descriptor we own; it only fails for invalid descriptors.
+        unsafe {
+            let flags = libc::fcntl(fd, libc::F_GETFL);
+            if flags >= 0 && flags & libc::O_NONBLOCK != 0 {
+                libc::fcntl(fd, libc::F_SETFL, flags & !libc::O_NONBLOCK);
+            }
+        }
+        Self::from_shared_fd(SharedFd::new(fd))
+    }
+
+    pub(crate) fn into_std<T: FromRawFd>(self) -> io::Result<T> {
+        let fd = self
+            .fd
+            .try_into_raw_fd()
+            .map_err(|_| io::Error::other("operations are still in flight on the socket"))?;
+        // Safety: the descriptor is ours, and was released by the `SharedFd` above.
+        Ok(unsafe { T::from_raw_fd(fd) })
     }
 
     pub(crate) fn from_shared_fd(fd: SharedFd) -> Socket {
@@ -198,13 +588,12 @@ impl Socket {
     ) -> io::Result<Socket> {
         let sys_listener = socket2::Socket::new(domain, socket_type, None)?;
 
-        sys_listener.set_reuse_port(true)?;
+        // SO_REUSEPORT is only meaningful for inet sockets; recent kernels reject it for AF_UNIX.
+        if domain != socket2::Domain::UNIX {
+            sys_listener.set_reuse_port(true)?;
+        }
         sys_listener.set_reuse_address(true)?;
 
-        // TODO: config for buffer sizes
-        // sys_listener.set_send_buffer_size(send_buf_size)?;
-        // sys_listener.set_recv_buffer_size(recv_buf_size)?;
-
         sys_listener.bind(&socket_addr)?;
 
         let fd = SharedFd::new(sys_listener.into_raw_fd());
@@ -237,6 +626,335 @@ impl Socket {
         let socket_ref = socket2::SockRef::from(self);
         socket_ref.set_nodelay(nodelay)
     }
+
+    pub(crate) fn nodelay(&self) -> io::Result<bool> {
+        socket2::SockRef::from(self).nodelay()
+    }
+
+    pub(crate) fn set_ttl(&self, ttl: u32) -> io::Result<()> {
+        socket2::SockRef::from(self).set_ttl(ttl)
+    }
+
+    pub(crate) fn ttl(&self) -> io::Result<u32> {
+        socket2::SockRef::from(self).ttl()
+    }
+
+    /// Sets the TOS byte of outgoing IPv4 packets, or the traffic class of IPv6 ones. A
+    /// dual-stack IPv6 socket gets both, for its IPv4 peers.
+    pub(crate) fn set_tos(&self, tos: u8) -> io::Result<()> {
+        let socket_ref = socket2::SockRef::from(self);
+        if socket_ref.domain()? != socket2::Domain::IPV6 {
+            return socket_ref.set_tos(tos.into());
+        }
+        let class = libc::c_int::from(tos);
+        syscall!(setsockopt(
+            self.as_raw_fd(),
+            libc::IPPROTO_IPV6,
+            libc::IPV6_TCLASS,
+            &class as *const _ as *const libc::c_void,
+            std::mem::size_of_val(&class) as libc::socklen_t,
+        ))?;
+        if !socket_ref.only_v6()? {
+            socket_ref.set_tos(tos.into())?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn tos(&self) -> io::Result<u8> {
+        let socket_ref = socket2::SockRef::from(self);
+        if socket_ref.domain()? != socket2::Domain::IPV6 {
+            return Ok(socket_ref.tos()? as u8);
+        }
+        let mut class: libc::c_int = 0;
+        let mut len = std::mem::size_of_val(&class) as libc::socklen_t;
+        syscall!(getsockopt(
+            self.as_raw_fd(),
+            libc::IPPROTO_IPV6,
+            libc::IPV6_TCLASS,
+            &mut class as *mut _ as *mut libc::c_void,
+            &mut len,
+        ))?;
+        Ok(class as u8)
+    }
+
+    pub(crate) fn set_recv_buffer_size(&self, size: u32) -> io::Result<()> {
+        socket2::SockRef::from(self).set_recv_buffer_size(size as usize)
+    }
+
+    pub(crate) fn recv_buffer_size(&self) -> io::Result<u32> {
+        Ok(socket2::SockRef::from(self).recv_buffer_size()? as u32)
+    }
+
+    pub(crate) fn set_send_buffer_size(&self, size: u32) -> io::Result<()> {
+        socket2::SockRef::from(self).set_send_buffer_size(size as usize)
+    }
+
+    pub(crate) fn send_buffer_size(&self) -> io::Result<u32> {
+        Ok(socket2::SockRef::from(self).send_buffer_size()? as u32)
+    }
+
+    pub(crate) fn set_cork(&self, cork: bool) -> io::Result<()> {
+        socket2::SockRef::from(self).set_cork(cork)
+    }
+
+    pub(crate) fn cork(&self) -> io::Result<bool> {
+        socket2::SockRef::from(self).cork()
+    }
+
+    pub(crate) fn set_incoming_cpu(&self, cpu: u32) -> io::Result<()> {
+        socket2::SockRef::from(self).set_cpu_affinity(cpu as usize)
+    }
+
+    pub(crate) fn incoming_cpu(&self) -> io::Result<Option<u32>> {
+        // Unset is -1.
+        let cpu = socket2::SockRef::from(self).cpu_affinity()? as libc::c_int;
+        Ok(if cpu < 0 { None } else { Some(cpu as u32) })
+    }
+
+    /// Attaches a classic BPF program to the socket's `SO_REUSEPORT` group which hands each
+    /// connection to the socket at index `cpu % listeners`, where `cpu` is the CPU that
+    /// processed its first packet.
+    pub(crate) fn attach_reuseport_cpu_steering(&self, listeners: u32) -> io::Result<()> {
+        if listeners == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "cannot steer connections to zero listeners",
+            ));
+        }
+        let insn = |code: u32, k: u32| libc::sock_filter {
+            code: code as u16,
+            jt: 0,
+            jf: 0,
+            k,
+        };
+        let mut filter = [
+            // A = the CPU handling the packet.
+            insn(
+                libc::BPF_LD | libc::BPF_W | libc::BPF_ABS,
+                (libc::SKF_AD_OFF + libc::SKF_AD_CPU) as u32,
+            ),
+            // A %= listeners.
+            insn(libc::BPF_ALU | libc::BPF_MOD | libc::BPF_K, listeners),
+            // The index of the socket in the group.
+            insn(libc::BPF_RET | libc::BPF_A, 0),
+        ];
+        let prog = libc::sock_fprog {
+            len: filter.len() as u16,
+            filter: filter.as_mut_ptr(),
+        };
+        syscall!(setsockopt(
+            self.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_ATTACH_REUSEPORT_CBPF,
+            &prog as *const _ as *const libc::c_void,
+            std::mem::size_of::<libc::sock_fprog>() as libc::socklen_t,
+        ))
+        .map_err(|e| match e.raw_os_error() {
+            Some(libc::EPERM) | Some(libc::EACCES) => io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "not permitted to attach a program to the SO_REUSEPORT group",
+            ),
+            _ => e,
+        })?;
+        Ok(())
+    }
+
+    pub(crate) fn take_error(&self) -> io::Result<Option<io::Error>> {
+        socket2::SockRef::from(self).take_error()
+    }
+
+    pub(crate) fn set_device(&self, interface: Option<&str>) -> io::Result<()> {
+        let interface = interface.filter(|name| !name.is_empty());
+        socket2::SockRef::from(self)
+            .bind_device(interface.map(str::as_bytes))
+            .map_err(|e| {
+                if e.raw_os_error() == Some(libc::EPERM) {
+                    io::Error::new(
+                        io::ErrorKind::PermissionDenied,
+                        "SO_BINDTODEVICE requires CAP_NET_RAW",
+                    )
+                } else {
+                    e
+                }
+            })
+    }
+
+    pub(crate) fn device(&self) -> io::Result<Option<String>> {
+        let name = socket2::SockRef::from(self).device()?;
+        name.map(|name| {
+            String::from_utf8(name)
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "device name is not UTF-8"))
+        })
+        .transpose()
+    }
+
+    pub(crate) fn set_gro(&self, on: bool) -> io::Result<()> {
+        let on = libc::c_int::from(on);
+        // Safety: the option value is a valid int for the duration of the call.
+        let res = unsafe {
+            libc::setsockopt(
+                self.as_raw_fd(),
+                libc::SOL_UDP,
+                crate::net::cmsg::UDP_GRO,
+                &on as *const _ as *const libc::c_void,
+                std::mem::size_of_val(&on) as libc::socklen_t,
+            )
+        };
+        if res == -1 {
+            let err = io::Error::last_os_error();
+            if err.raw_os_error() == Some(libc::ENOPROTOOPT) {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "the kernel does not support UDP_GRO",
+                ));
+            }
+            return Err(err);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn set_timestamping(&self, flags: libc::c_uint) -> io::Result<()> {
+        syscall!(setsockopt(
+            self.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_TIMESTAMPING,
+            &flags as *const _ as *const libc::c_void,
+            std::mem::size_of_val(&flags) as libc::socklen_t,
+        ))?;
+        Ok(())
+    }
+
+    /// Checks that the kernel supports `UDP_SEGMENT`, which it would otherwise only report as
+    /// `EINVAL` once a send completes.
+    pub(crate) fn check_gso(&self) -> io::Result<()> {
+        // 0 while unknown, then 1 if supported and 2 if not.
+        static GSO: AtomicU8 = AtomicU8::new(0);
+
+        let supported = match GSO.load(Ordering::Relaxed) {
+            0 => {
+                let mut size: libc::c_int = 0;
+                let mut len = std::mem::size_of_val(&size) as libc::socklen_t;
+                // Safety: the option value is a valid int for the duration of the call.
+                let res = unsafe {
+                    libc::getsockopt(
+                        self.as_raw_fd(),
+                        libc::SOL_UDP,
+                        crate::net::cmsg::UDP_SEGMENT,
+                        &mut size as *mut _ as *mut libc::c_void,
+                        &mut len,
+                    )
+                };
+                let supported = res == 0;
+                GSO.store(if supported { 1 } else { 2 }, Ordering::Relaxed);
+                supported
+            }
+            state => state == 1,
+        };
+        if !supported {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "the kernel does not support UDP_SEGMENT",
+            ));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn join_multicast_v4(&self, group: Ipv4Addr, interface: Ipv4Addr) -> io::Result<()> {
+        check_multicast(group.is_multicast())?;
+        // ip_mreqn, which names the interface by its address here.
+        socket2::SockRef::from(self).join_multicast_v4_n(
+            &group,
+            &socket2::InterfaceIndexOrAddress::Address(interface),
+        )
+    }
+
+    pub(crate) fn leave_multicast_v4(
+        &self,
+        group: Ipv4Addr,
+        interface: Ipv4Addr,
+    ) -> io::Result<()> {
+        check_multicast(group.is_multicast())?;
+        socket2::SockRef::from(self).leave_multicast_v4_n(
+            &group,
+            &socket2::InterfaceIndexOrAddress::Address(interface),
+        )
+    }
+
+    pub(crate) fn join_multicast_v6(&self, group: Ipv6Addr, interface: u32) -> io::Result<()> {
+        check_multicast(group.is_multicast())?;
+        self.check_v6()?;
+        socket2::SockRef::from(self).join_multicast_v6(&group, interface)
+    }
+
+    pub(crate) fn leave_multicast_v6(&self, group: Ipv6Addr, interface: u32) -> io::Result<()> {
+        check_multicast(group.is_multicast())?;
+        self.check_v6()?;
+        socket2::SockRef::from(self).leave_multicast_v6(&group, interface)
+    }
+
+    pub(crate) fn set_multicast_loop_v4(&self, on: bool) -> io::Result<()> {
+        socket2::SockRef::from(self).set_multicast_loop_v4(on)
+    }
+
+    pub(crate) fn set_multicast_loop_v6(&self, on: bool) -> io::Result<()> {
+        self.check_v6()?;
+        socket2::SockRef::from(self).set_multicast_loop_v6(on)
+    }
+
+    pub(crate) fn set_multicast_ttl_v4(&self, ttl: u32) -> io::Result<()> {
+        socket2::SockRef::from(self).set_multicast_ttl_v4(ttl)
+    }
+
+    // IPv6 options on an IPv4 socket fail with a puzzling ENOPROTOOPT. The reverse works, as
+    // IPv6 sockets accept IPv4 options for their IPv4-mapped traffic.
+    fn check_v6(&self) -> io::Result<()> {
+        if socket2::SockRef::from(self).domain()? != socket2::Domain::IPV6 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "IPv6 multicast options require an IPv6 socket",
+            ));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn set_keepalive(&self, keepalive: Option<KeepaliveConfig>) -> io::Result<()> {
+        let socket_ref = socket2::SockRef::from(self);
+        match keepalive {
+            Some(config) => socket_ref.set_tcp_keepalive(
+                &socket2::TcpKeepalive::new()
+                    .with_time(config.time)
+                    .with_interval(config.interval)
+                    .with_retries(config.retries),
+            ),
+            None => socket_ref.set_keepalive(false),
+        }
+    }
+
+    pub(crate) fn keepalive(&self) -> io::Result<Option<KeepaliveConfig>> {
+        let socket_ref = socket2::SockRef::from(self);
+        if !socket_ref.keepalive()? {
+            return Ok(None);
+        }
+        Ok(Some(KeepaliveConfig {
+            time: socket_ref.keepalive_time()?,
+            interval: socket_ref.keepalive_interval()?,
+            retries: socket_ref.keepalive_retries()?,
+        }))
+    }
+}
+
+// Set in `tcp_info::tcpi_options` once the server acknowledged data sent in the SYN; from
+// linux/tcp.h.
+const TCPI_OPT_SYN_DATA: u8 = 32;
+
+fn check_multicast(is_multicast: bool) -> io::Result<()> {
+    if !is_multicast {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "not a multicast group address",
+        ));
+    }
+    Ok(())
 }
 
 impl AsRawFd for Socket {
@@ -0,0 +1,130 @@
+use crate::io::SharedFd;
+use crate::runtime::driver::op::{Completable, CqeResult, Op};
+use crate::runtime::CONTEXT;
+use io_uring::{opcode, squeue, types};
+use std::io;
+
+// The size staging pipes are grown to, if the system allows it.
+const PIPE_SIZE: libc::c_int = 1 << 20;
+
+/// Move data between two file descriptors, one of which is a pipe.
+pub(crate) struct Splice {
+    /// Hold strong refs to the FDs, preventing them from being closed
+    /// while the operation is in-flight.
+    #[allow(dead_code)]
+    fd_in: SharedFd,
+    #[allow(dead_code)]
+    fd_out: SharedFd,
+}
+
+impl Splice {
+    fn new(fd_in: &SharedFd, fd_out: &SharedFd) -> Splice {
+        Splice {
+            fd_in: fd_in.clone(),
+            fd_out: fd_out.clone(),
+        }
+    }
+}
+
+// `off_in` of `None` reads from the current position, as pipes and sockets require.
+fn splice_sqe(fd_in: &SharedFd, off_in: Option<u64>, fd_out: &SharedFd, len: u32) -> squeue::Entry {
+    let off_in = off_in.map_or(-1, |off| off as i64);
+    opcode::Splice::new(
+        types::Fd(fd_in.raw_fd()),
+        off_in,
+        types::Fd(fd_out.raw_fd()),
+        -1,
+        len,
+    )
+    .build()
+}
+
+impl Op<Splice> {
+    /// Submit a splice of up to `len` bytes from `fd_in` to `fd_out`.
+    pub(crate) fn splice(
+        fd_in: &SharedFd,
+        off_in: Option<u64>,
+        fd_out: &SharedFd,
+        len: u32,
+    ) -> io::Result<Op<Splice>> {
+        CONTEXT.with(|x| {
+            x.handle()
+                .expect("Not in a runtime context")
+                .submit_op(Splice::new(fd_in, fd_out), |_| {
+                    splice_sqe(fd_in, off_in, fd_out, len)
+                })
+        })
+    }
+
+    /// Submit a splice of up to `len` bytes from `fd_in` at `off_in` into the pipe, linked to
+    /// a splice of as many bytes from the pipe to `fd_out`.
+    ///
+    /// The kernel cancels the second splice if the first moves fewer than `len` bytes, leaving
+    /// whatever the first moved in the pipe.
+    pub(crate) fn splice_through(
+        fd_in: &SharedFd,
+        off_in: u64,
+        pipe: &Pipe,
+        fd_out: &SharedFd,
+        len: u32,
+    ) -> io::Result<(Op<Splice>, Op<Splice>)> {
+        CONTEXT.with(|x| {
+            x.handle()
+                .expect("Not in a runtime context")
+                .submit_op_pair(
+                    (Splice::new(fd_in, &pipe.write), |_: &mut Splice| {
+                        splice_sqe(fd_in, Some(off_in), &pipe.write, len)
+                    }),
+                    (Splice::new(&pipe.read, fd_out), |_: &mut Splice| {
+                        splice_sqe(&pipe.read, None, fd_out, len)
+                    }),
+                )
+        })
+    }
+}
+
+impl Completable for Splice {
+    type Output = io::Result<usize>;
+
+    fn complete(self, cqe: CqeResult) -> Self::Output {
+        cqe.result.map(|n| n as usize)
+    }
+}
+
+/// A pipe for staging spliced data in the kernel.
+pub(crate) struct Pipe {
+    pub(crate) read: SharedFd,
+    pub(crate) write: SharedFd,
+    /// How many bytes the pipe holds.
+    pub(crate) size: usize,
+}
+
+impl Pipe {
+    pub(crate) fn new() -> io::Result<Pipe> {
+        let mut fds = [0; 2];
+        // Safety: `fds` has room for the two descriptors.
+        if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let read = SharedFd::new(fds[0]);
+        let write = SharedFd::new(fds[1]);
+
+        // A larger pipe means fewer round trips; the default limit for unprivileged
+        // processes allows growing it to 1 MiB.
+        // Safety: fcntl on a descriptor we own.
+        let mut size = unsafe { libc::fcntl(fds[1], libc::F_SETPIPE_SZ, PIPE_SIZE) };
+        if size < 0 {
+            // Safety: as above.
+            size = unsafe { libc::fcntl(fds[1], libc::F_GETPIPE_SZ) };
+            if size < 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+
+        Ok(Pipe {
+            read,
+            write,
+            size: size as usize,
+        })
+    }
+}
@@ -0,0 +1,119 @@
+use std::io;
+
+use io_uring::{opcode, squeue, types};
+
+use crate::{
+    io::SharedFd,
+    runtime::{
+        driver::op::{Completable, CqeResult, Op},
+        CONTEXT,
+    },
+};
+
+pub(crate) struct UringCmd {
+    #[allow(dead_code)]
+    fd: SharedFd,
+}
+
+pub(crate) struct UringCmd80 {
+    #[allow(dead_code)]
+    fd: SharedFd,
+}
+
+impl Op<UringCmd> {
+    pub(crate) fn uring_cmd16(
+        fd: &SharedFd,
+        cmd_op: u32,
+        cmd: [u8; 16],
+    ) -> io::Result<Op<UringCmd>> {
+        CONTEXT.with(|x| {
+            x.handle()
+                .expect("not in a runtime context")
+                .submit_op(UringCmd { fd: fd.clone() }, |uring_cmd| {
+                    uring_cmd16_sqe(uring_cmd.fd.raw_fd(), cmd_op, cmd)
+                })
+        })
+    }
+}
+
+impl Op<UringCmd80> {
+    pub(crate) fn uring_cmd80(
+        fd: &SharedFd,
+        cmd_op: u32,
+        cmd: [u8; 80],
+    ) -> io::Result<Op<UringCmd80>> {
+        CONTEXT.with(|x| {
+            x.handle()
+                .expect("not in a runtime context")
+                .submit_op_big(UringCmd80 { fd: fd.clone() }, |uring_cmd| {
+                    uring_cmd80_sqe(uring_cmd.fd.raw_fd(), cmd_op, cmd)
+                })
+        })
+    }
+}
+
+fn uring_cmd16_sqe(fd: i32, cmd_op: u32, cmd: [u8; 16]) -> squeue::Entry {
+    opcode::UringCmd16::new(types::Fd(fd), cmd_op)
+        .cmd(cmd)
+        .build()
+}
+
+fn uring_cmd80_sqe(fd: i32, cmd_op: u32, cmd: [u8; 80]) -> squeue::Entry128 {
+    opcode::UringCmd80::new(types::Fd(fd), cmd_op)
+        .cmd(cmd)
+        .build()
+}
+
+impl Completable for UringCmd {
+    type Output = io::Result<u32>;
+
+    fn complete(self, cqe: CqeResult) -> Self::Output {
+        cqe.result
+    }
+}
+
+impl Completable for UringCmd80 {
+    type Output = io::Result<(u32, [u64; 2])>;
+
+    fn complete(self, cqe: CqeResult) -> Self::Output {
+        Ok((cqe.result?, cqe.big_cqe))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{uring_cmd16_sqe, uring_cmd80_sqe};
+    use std::convert::TryInto;
+
+    #[test]
+    fn uring_cmd16_encoding() {
+        let cmd: [u8; 16] = std::array::from_fn(|i| i as u8 + 1);
+        let sqe = uring_cmd16_sqe(7, 0xdead_beef, cmd);
+
+        // Safety: a submission queue entry is a plain 64-byte C struct.
+        let raw: [u8; 64] = unsafe { std::mem::transmute(sqe) };
+        assert_eq!(raw[0], io_uring::opcode::UringCmd16::CODE);
+        assert_eq!(i32::from_ne_bytes(raw[4..8].try_into().unwrap()), 7);
+        assert_eq!(
+            u32::from_ne_bytes(raw[8..12].try_into().unwrap()),
+            0xdead_beef
+        );
+        assert_eq!(raw[48..64], cmd);
+    }
+
+    #[test]
+    fn uring_cmd80_encoding() {
+        let cmd: [u8; 80] = std::array::from_fn(|i| i as u8 + 1);
+        let sqe = uring_cmd80_sqe(7, 0xdead_beef, cmd);
+
+        // Safety: a 128-byte submission queue entry is a plain C struct.
+        let raw: [u8; 128] = unsafe { std::mem::transmute(sqe) };
+        assert_eq!(raw[0], io_uring::opcode::UringCmd80::CODE);
+        assert_eq!(i32::from_ne_bytes(raw[4..8].try_into().unwrap()), 7);
+        assert_eq!(
+            u32::from_ne_bytes(raw[8..12].try_into().unwrap()),
+            0xdead_beef
+        );
+        assert_eq!(raw[48..128], cmd[..]);
+    }
+}
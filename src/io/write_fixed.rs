@@ -1,5 +1,5 @@
 use crate::buf::fixed::pool::PoolInfo;
-use crate::buf::fixed::registry::RegistryInfo;
+use crate::buf::fixed::registry::{InFlight, RegistryInfo};
 use crate::buf::fixed::{pool, registry};
 use crate::buf::BoundedBuf;
 use crate::io::SharedFd;
@@ -18,6 +18,9 @@ pub(crate) struct WriteFixed<T> {
     fd: SharedFd,
 
     buf: T,
+
+    // Marks a registry buffer as held by the operation.
+    _in_flight: Option<InFlight>,
 }
 
 impl<T> Op<WriteFixed<T>>
@@ -35,6 +38,7 @@ where
             x.handle().expect("Not in a runtime context").submit_op(
                 WriteFixed {
                     fd: fd.clone(),
+                    _in_flight: InFlight::mark(buf.get_buf()),
                     buf,
                 },
                 |write_fixed| {
@@ -59,6 +63,10 @@ where
                                 write_fixed.buf.get_buf().user_data() as *const PoolInfo;
                             (*pool_info).index
                         }
+                    } else if let Some(buf_index) =
+                        registry::shared_index(write_fixed.buf.get_buf())
+                    {
+                        buf_index
                     } else {
                         panic!("Buffer must be created from FixedBuf");
                     };
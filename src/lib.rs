@@ -81,6 +81,8 @@ pub mod net;
 
 pub use buf::Buffer;
 pub use io::read_write::*;
+pub use io::recv::*;
+pub use io::send::*;
 pub use runtime::driver::op::{
     InFlightOneshot, Link, LinkedInFlightOneshot, OneshotOutputTransform, Submit,
     UnsubmittedOneshot,
@@ -164,12 +166,25 @@ pub fn uring_builder() -> io_uring::Builder {
     io_uring::IoUring::builder()
 }
 
+/// Creates and returns an io_uring::Builder for a ring of 128-byte submission
+/// queue entries and 32-byte completion queue entries, which
+/// [`Builder::uring_builder_big`] takes.
+///
+/// The ring is set up with `IORING_SETUP_SQE128` and `IORING_SETUP_CQE32`,
+/// which require Linux 5.19 or later.
+pub fn uring_builder_big(
+) -> io_uring::Builder<io_uring::squeue::Entry128, io_uring::cqueue::Entry32> {
+    io_uring::IoUring::builder()
+}
+
 /// Builder API that can create and start the `io_uring` runtime with non-default parameters,
 /// while abstracting away the underlying io_uring crate.
 // #[derive(Clone, Default)]
 pub struct Builder {
     entries: u32,
+    direct_files: u32,
     urb: io_uring::Builder,
+    urb_big: Option<io_uring::Builder<io_uring::squeue::Entry128, io_uring::cqueue::Entry32>>,
 }
 
 /// Constructs a [`Builder`] with default settings.
@@ -181,7 +196,9 @@ pub struct Builder {
 pub fn builder() -> Builder {
     Builder {
         entries: 256,
+        direct_files: 0,
         urb: io_uring::IoUring::builder(),
+        urb_big: None,
     }
 }
 
@@ -197,12 +214,38 @@ impl Builder {
         self
     }
 
+    /// Sets the size of the table of direct descriptors, which
+    /// [`TcpListener::accept_direct`](crate::net::TcpListener::accept_direct) accepts into.
+    ///
+    /// The default is 0, which leaves the table unregistered. Registering it requires Linux
+    /// 5.19 or later.
+    pub fn direct_files(&mut self, slots: u32) -> &mut Self {
+        self.direct_files = slots;
+        self
+    }
+
     /// Replaces the default [`io_uring::Builder`], which controls the settings for the
     /// inner `io_uring` API.
     ///
     /// Refer to the [`io_uring::Builder`] documentation for all the supported methods.
     pub fn uring_builder(&mut self, b: &io_uring::Builder) -> &mut Self {
         self.urb = b.clone();
+        self.urb_big = None;
+        self
+    }
+
+    /// Replaces the default [`io_uring::Builder`] with one for a ring of 128-byte submission
+    /// queue entries and 32-byte completion queue entries, as built by [`uring_builder_big`].
+    ///
+    /// Commands that carry more than 16 bytes, such as
+    /// [`File::uring_cmd80`](crate::fs::File::uring_cmd80), need the larger entries. Every
+    /// other operation works as on the default ring. This takes the place of any builder set
+    /// with [`uring_builder`](Builder::uring_builder), and the other way around.
+    pub fn uring_builder_big(
+        &mut self,
+        b: &io_uring::Builder<io_uring::squeue::Entry128, io_uring::cqueue::Entry32>,
+    ) -> &mut Self {
+        self.urb_big = Some(b.clone());
         self
     }
 
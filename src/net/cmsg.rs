@@ -0,0 +1,449 @@
+use std::iter::FromIterator;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::{fmt, mem, ops, slice};
+
+// Not exported by the libc crate; from linux/udp.h.
+pub(crate) const UDP_SEGMENT: libc::c_int = 103;
+pub(crate) const UDP_GRO: libc::c_int = 104;
+
+/// A control message (ancillary data) sent or received along with a datagram.
+///
+/// The variants cover the common IP-level messages. Anything else is represented as
+/// [`Other`](ControlMessage::Other), carrying the raw `cmsg_level`, `cmsg_type` and payload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ControlMessage {
+    /// `IP_PKTINFO`.
+    ///
+    /// When received, after enabling the `IP_PKTINFO` socket option, `addr` is the destination
+    /// address in the IP header, `spec_dst` the local address the datagram was routed to and
+    /// `if_index` the interface it arrived on. When sent, a non-zero `if_index` selects the
+    /// outgoing interface and `spec_dst` the source address; `addr` is ignored.
+    Ipv4PacketInfo {
+        /// Interface index.
+        if_index: u32,
+        /// Local address.
+        spec_dst: Ipv4Addr,
+        /// Header destination address.
+        addr: Ipv4Addr,
+    },
+
+    /// `IPV6_PKTINFO`.
+    ///
+    /// When received, after enabling the `IPV6_RECVPKTINFO` socket option, `addr` is the
+    /// destination address of the datagram and `if_index` the interface it arrived on. When
+    /// sent, they select the source address and the outgoing interface.
+    Ipv6PacketInfo {
+        /// Interface index.
+        if_index: u32,
+        /// Destination address when received, source address when sent.
+        addr: Ipv6Addr,
+    },
+
+    /// `IP_TOS`: the type of service byte of the IPv4 header. Received after enabling the
+    /// `IP_RECVTOS` socket option. When sent, marks the datagram in place of the socket's
+    /// [`set_tos`](crate::net::UdpSocket::set_tos).
+    Ipv4Tos(u8),
+
+    /// `IPV6_TCLASS`: the traffic class of the IPv6 header. Received after enabling the
+    /// `IPV6_RECVTCLASS` socket option. When sent, marks the datagram in place of the
+    /// socket's [`set_tos`](crate::net::UdpSocket::set_tos).
+    Ipv6TrafficClass(u8),
+
+    /// `UDP_SEGMENT`: when sent, splits the payload into datagrams of this size, all but the
+    /// last of which are full (generic segmentation offload, or GSO). See
+    /// [`UdpSocket::send_to_segmented`](crate::net::UdpSocket::send_to_segmented).
+    UdpSegment(u16),
+
+    /// `UDP_GRO`: the size of the datagrams the kernel coalesced into the received payload
+    /// (generic receive offload, or GRO), all but the last of which are full. Received after
+    /// enabling it with [`UdpSocket::set_gro`](crate::net::UdpSocket::set_gro).
+    UdpGro(u16),
+
+    /// `SCM_TIMESTAMPING`: the timestamps the kernel recorded as it received the datagram,
+    /// after enabling them with [`UdpSocket::set_timestamping`]. Each is `None` unless it was
+    /// both generated and reported.
+    ///
+    /// [`UdpSocket::set_timestamping`]: crate::net::UdpSocket::set_timestamping
+    Timestamping {
+        /// When the kernel received the datagram, by the system clock.
+        software: Option<SystemTime>,
+        /// When the network card received the datagram, by the card's own clock, which need
+        /// not be synchronized with the system clock.
+        hardware: Option<Duration>,
+    },
+
+    /// `SCM_TIMESTAMPNS`: when the kernel received the datagram, by the system clock.
+    /// Received after enabling the `SO_TIMESTAMPNS` socket option.
+    TimestampNs(SystemTime),
+
+    /// Any other control message.
+    Other {
+        /// The `cmsg_level` field, such as `SOL_SOCKET` or `IPPROTO_IP`.
+        level: i32,
+        /// The `cmsg_type` field.
+        ty: i32,
+        /// The payload, without the header and padding.
+        data: Vec<u8>,
+    },
+}
+
+/// The timestamps the kernel records for the datagrams a socket receives, passed to
+/// [`UdpSocket::set_timestamping`](crate::net::UdpSocket::set_timestamping).
+///
+/// A timestamp is only reported if it is both generated and reported, so software receive
+/// timestamps take [`RX_SOFTWARE`](TimestampingFlags::RX_SOFTWARE) `|`
+/// [`SOFTWARE`](TimestampingFlags::SOFTWARE). Combine flags with `|`.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub struct TimestampingFlags(libc::c_uint);
+
+impl TimestampingFlags {
+    /// No timestamps.
+    pub const NONE: TimestampingFlags = TimestampingFlags(0);
+
+    /// `SOF_TIMESTAMPING_RX_SOFTWARE`: stamp datagrams as the kernel receives them.
+    pub const RX_SOFTWARE: TimestampingFlags =
+        TimestampingFlags(libc::SOF_TIMESTAMPING_RX_SOFTWARE);
+
+    /// `SOF_TIMESTAMPING_RX_HARDWARE`: have the network card stamp datagrams. The card must
+    /// also be configured to, with the `SIOCSHWTSTAMP` ioctl.
+    pub const RX_HARDWARE: TimestampingFlags =
+        TimestampingFlags(libc::SOF_TIMESTAMPING_RX_HARDWARE);
+
+    /// `SOF_TIMESTAMPING_SOFTWARE`: report software timestamps.
+    pub const SOFTWARE: TimestampingFlags = TimestampingFlags(libc::SOF_TIMESTAMPING_SOFTWARE);
+
+    /// `SOF_TIMESTAMPING_RAW_HARDWARE`: report hardware timestamps, as the card's clock reads.
+    pub const RAW_HARDWARE: TimestampingFlags =
+        TimestampingFlags(libc::SOF_TIMESTAMPING_RAW_HARDWARE);
+
+    /// Returns `true` if all the flags in `other` are set.
+    pub fn contains(self, other: TimestampingFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub(crate) fn bits(self) -> libc::c_uint {
+        self.0
+    }
+}
+
+impl ops::BitOr for TimestampingFlags {
+    type Output = TimestampingFlags;
+
+    fn bitor(self, other: TimestampingFlags) -> TimestampingFlags {
+        TimestampingFlags(self.0 | other.0)
+    }
+}
+
+impl fmt::Debug for TimestampingFlags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TimestampingFlags")
+            .field(
+                "rx_software",
+                &self.contains(TimestampingFlags::RX_SOFTWARE),
+            )
+            .field(
+                "rx_hardware",
+                &self.contains(TimestampingFlags::RX_HARDWARE),
+            )
+            .field("software", &self.contains(TimestampingFlags::SOFTWARE))
+            .field(
+                "raw_hardware",
+                &self.contains(TimestampingFlags::RAW_HARDWARE),
+            )
+            .finish()
+    }
+}
+
+/// A list of control messages.
+///
+/// Build one with [`push`](CMsgs::push) to send with [`UdpSocket::send_msg`], or inspect the
+/// messages received by [`UdpSocket::recv_msg`].
+///
+/// [`UdpSocket::send_msg`]: crate::net::UdpSocket::send_msg
+/// [`UdpSocket::recv_msg`]: crate::net::UdpSocket::recv_msg
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CMsgs {
+    msgs: Vec<ControlMessage>,
+    truncated: bool,
+}
+
+impl CMsgs {
+    /// Creates an empty list.
+    pub fn new() -> CMsgs {
+        CMsgs::default()
+    }
+
+    /// Appends a message.
+    pub fn push(&mut self, msg: ControlMessage) -> &mut Self {
+        self.msgs.push(msg);
+        self
+    }
+
+    /// Returns an iterator over the messages.
+    pub fn iter(&self) -> slice::Iter<'_, ControlMessage> {
+        self.msgs.iter()
+    }
+
+    /// Returns the number of messages.
+    pub fn len(&self) -> usize {
+        self.msgs.len()
+    }
+
+    /// Returns `true` if there are no messages.
+    pub fn is_empty(&self) -> bool {
+        self.msgs.is_empty()
+    }
+
+    /// Returns `true` if the kernel had more control data than fit in the receive buffer
+    /// (`MSG_CTRUNC`). The messages that did fit are still listed.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    // Number of bytes of control buffer needed to encode these messages.
+    fn encoded_len(&self) -> usize {
+        self.msgs
+            .iter()
+            .map(|msg| space(encode_payload(msg).2.len()))
+            .sum()
+    }
+
+    /// Encodes the messages into a control buffer for sendmsg(2).
+    ///
+    /// The buffer is made of `u64`s so that every header is suitably aligned; the second value
+    /// is the number of bytes used.
+    pub(crate) fn encode(&self) -> (Vec<u64>, usize) {
+        let len = self.encoded_len();
+        let mut control = vec![0u64; len.div_ceil(8)];
+        if len == 0 {
+            return (control, 0);
+        }
+
+        // Safety: the msghdr only describes `control`, which is large enough for every message
+        // by construction, so the CMSG_* macros stay in bounds.
+        unsafe {
+            let mut msghdr: libc::msghdr = mem::zeroed();
+            msghdr.msg_control = control.as_mut_ptr().cast();
+            msghdr.msg_controllen = len as _;
+
+            let mut cmsg = libc::CMSG_FIRSTHDR(&msghdr);
+            for msg in &self.msgs {
+                let (level, ty, data) = encode_payload(msg);
+                (*cmsg).cmsg_level = level;
+                (*cmsg).cmsg_type = ty;
+                (*cmsg).cmsg_len = libc::CMSG_LEN(data.len() as _) as _;
+                std::ptr::copy_nonoverlapping(data.as_ptr(), libc::CMSG_DATA(cmsg), data.len());
+                cmsg = libc::CMSG_NXTHDR(&msghdr, cmsg);
+            }
+        }
+        (control, len)
+    }
+
+    /// Parses the control data filled in by recvmsg(2).
+    ///
+    /// # Safety
+    ///
+    /// `msghdr.msg_control` and `msghdr.msg_controllen` must describe a valid, initialized
+    /// control buffer.
+    pub(crate) unsafe fn decode(msghdr: &libc::msghdr) -> CMsgs {
+        let mut msgs = Vec::new();
+        if !msghdr.msg_control.is_null() {
+            let mut cmsg = libc::CMSG_FIRSTHDR(msghdr);
+            while !cmsg.is_null() {
+                let header = libc::CMSG_LEN(0) as usize;
+                let len = ((*cmsg).cmsg_len as usize).saturating_sub(header);
+                let data = slice::from_raw_parts(libc::CMSG_DATA(cmsg), len);
+                msgs.push(decode_payload((*cmsg).cmsg_level, (*cmsg).cmsg_type, data));
+                cmsg = libc::CMSG_NXTHDR(msghdr, cmsg);
+            }
+        }
+        CMsgs {
+            msgs,
+            truncated: msghdr.msg_flags & libc::MSG_CTRUNC != 0,
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a CMsgs {
+    type Item = &'a ControlMessage;
+    type IntoIter = slice::Iter<'a, ControlMessage>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl IntoIterator for CMsgs {
+    type Item = ControlMessage;
+    type IntoIter = std::vec::IntoIter<ControlMessage>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.msgs.into_iter()
+    }
+}
+
+impl FromIterator<ControlMessage> for CMsgs {
+    fn from_iter<I: IntoIterator<Item = ControlMessage>>(iter: I) -> CMsgs {
+        CMsgs {
+            msgs: iter.into_iter().collect(),
+            truncated: false,
+        }
+    }
+}
+
+fn space(len: usize) -> usize {
+    // Safety: CMSG_SPACE is a pure computation.
+    unsafe { libc::CMSG_SPACE(len as _) as usize }
+}
+
+fn encode_payload(msg: &ControlMessage) -> (i32, i32, Vec<u8>) {
+    match *msg {
+        ControlMessage::Ipv4PacketInfo {
+            if_index,
+            spec_dst,
+            addr,
+        } => {
+            let info = libc::in_pktinfo {
+                ipi_ifindex: if_index as _,
+                ipi_spec_dst: libc::in_addr {
+                    s_addr: u32::from_ne_bytes(spec_dst.octets()),
+                },
+                ipi_addr: libc::in_addr {
+                    s_addr: u32::from_ne_bytes(addr.octets()),
+                },
+            };
+            (libc::IPPROTO_IP, libc::IP_PKTINFO, bytes_of(&info))
+        }
+        ControlMessage::Ipv6PacketInfo { if_index, addr } => {
+            let info = libc::in6_pktinfo {
+                ipi6_addr: libc::in6_addr {
+                    s6_addr: addr.octets(),
+                },
+                ipi6_ifindex: if_index as _,
+            };
+            (libc::IPPROTO_IPV6, libc::IPV6_PKTINFO, bytes_of(&info))
+        }
+        ControlMessage::Ipv4Tos(tos) => (
+            libc::IPPROTO_IP,
+            libc::IP_TOS,
+            bytes_of(&libc::c_int::from(tos)),
+        ),
+        ControlMessage::Ipv6TrafficClass(class) => (
+            libc::IPPROTO_IPV6,
+            libc::IPV6_TCLASS,
+            bytes_of(&libc::c_int::from(class)),
+        ),
+        // The kernel takes the segment size as a u16, and reports it as an int.
+        ControlMessage::UdpSegment(size) => (libc::SOL_UDP, UDP_SEGMENT, bytes_of(&size)),
+        ControlMessage::UdpGro(size) => {
+            (libc::SOL_UDP, UDP_GRO, bytes_of(&libc::c_int::from(size)))
+        }
+        // Only ever received; encoded as the kernel delivers them.
+        ControlMessage::Timestamping { software, hardware } => {
+            let stamps = [
+                timespec_of(
+                    software.map(|time| time.duration_since(UNIX_EPOCH).unwrap_or_default()),
+                ),
+                timespec_of(None),
+                timespec_of(hardware),
+            ];
+            (libc::SOL_SOCKET, libc::SCM_TIMESTAMPING, bytes_of(&stamps))
+        }
+        ControlMessage::TimestampNs(time) => {
+            let stamp = timespec_of(time.duration_since(UNIX_EPOCH).ok());
+            (libc::SOL_SOCKET, libc::SCM_TIMESTAMPNS, bytes_of(&stamp))
+        }
+        ControlMessage::Other {
+            level,
+            ty,
+            ref data,
+        } => (level, ty, data.clone()),
+    }
+}
+
+fn decode_payload(level: i32, ty: i32, data: &[u8]) -> ControlMessage {
+    match (level, ty) {
+        (libc::IPPROTO_IP, libc::IP_PKTINFO)
+            if data.len() >= mem::size_of::<libc::in_pktinfo>() =>
+        {
+            // Safety: the length was checked; the read tolerates the payload's alignment.
+            let info: libc::in_pktinfo = unsafe { std::ptr::read_unaligned(data.as_ptr().cast()) };
+            ControlMessage::Ipv4PacketInfo {
+                if_index: info.ipi_ifindex as u32,
+                spec_dst: Ipv4Addr::from(info.ipi_spec_dst.s_addr.to_ne_bytes()),
+                addr: Ipv4Addr::from(info.ipi_addr.s_addr.to_ne_bytes()),
+            }
+        }
+        (libc::IPPROTO_IPV6, libc::IPV6_PKTINFO)
+            if data.len() >= mem::size_of::<libc::in6_pktinfo>() =>
+        {
+            // Safety: as above.
+            let info: libc::in6_pktinfo = unsafe { std::ptr::read_unaligned(data.as_ptr().cast()) };
+            ControlMessage::Ipv6PacketInfo {
+                if_index: info.ipi6_ifindex as u32,
+                addr: Ipv6Addr::from(info.ipi6_addr.s6_addr),
+            }
+        }
+        // IP_TOS is delivered as a single byte.
+        (libc::IPPROTO_IP, libc::IP_TOS) if !data.is_empty() => ControlMessage::Ipv4Tos(data[0]),
+        (libc::IPPROTO_IPV6, libc::IPV6_TCLASS) if data.len() >= mem::size_of::<libc::c_int>() => {
+            // Safety: as above.
+            let class: libc::c_int = unsafe { std::ptr::read_unaligned(data.as_ptr().cast()) };
+            ControlMessage::Ipv6TrafficClass(class as u8)
+        }
+        (libc::SOL_UDP, UDP_GRO) if data.len() >= mem::size_of::<libc::c_int>() => {
+            // Safety: as above.
+            let size: libc::c_int = unsafe { std::ptr::read_unaligned(data.as_ptr().cast()) };
+            ControlMessage::UdpGro(size as u16)
+        }
+        (libc::SOL_SOCKET, libc::SCM_TIMESTAMPING)
+            if data.len() >= mem::size_of::<[libc::timespec; 3]>() =>
+        {
+            // Safety: as above.
+            let stamps: [libc::timespec; 3] =
+                unsafe { std::ptr::read_unaligned(data.as_ptr().cast()) };
+            // The middle timestamp is a deprecated, always zero, transformed hardware one.
+            ControlMessage::Timestamping {
+                software: duration_of(&stamps[0]).map(|since| UNIX_EPOCH + since),
+                hardware: duration_of(&stamps[2]),
+            }
+        }
+        (libc::SOL_SOCKET, libc::SCM_TIMESTAMPNS)
+            if data.len() >= mem::size_of::<libc::timespec>() =>
+        {
+            // Safety: as above.
+            let stamp: libc::timespec = unsafe { std::ptr::read_unaligned(data.as_ptr().cast()) };
+            ControlMessage::TimestampNs(UNIX_EPOCH + duration_of(&stamp).unwrap_or_default())
+        }
+        _ => ControlMessage::Other {
+            level,
+            ty,
+            data: data.to_vec(),
+        },
+    }
+}
+
+fn bytes_of<T>(value: &T) -> Vec<u8> {
+    // Safety: only used with plain C structs and integers without padding.
+    unsafe { slice::from_raw_parts((value as *const T).cast::<u8>(), mem::size_of::<T>()).to_vec() }
+}
+
+// The kernel leaves the timestamps it did not take zeroed.
+fn duration_of(stamp: &libc::timespec) -> Option<Duration> {
+    if stamp.tv_sec == 0 && stamp.tv_nsec == 0 {
+        return None;
+    }
+    Some(Duration::new(stamp.tv_sec as u64, stamp.tv_nsec as u32))
+}
+
+fn timespec_of(duration: Option<Duration>) -> libc::timespec {
+    // Safety: a zeroed timespec is valid; this also clears any padding.
+    let mut stamp: libc::timespec = unsafe { mem::zeroed() };
+    if let Some(duration) = duration {
+        stamp.tv_sec = duration.as_secs() as _;
+        stamp.tv_nsec = duration.subsec_nanos() as _;
+    }
+    stamp
+}
@@ -0,0 +1,291 @@
+use crate::buf::{Buffer, IoBuf, IoBufMut};
+use crate::io::{Resume, SharedFd, Shutdown};
+use crate::runtime::driver::op::Op;
+use crate::runtime::CONTEXT;
+use std::future::{poll_fn, Future};
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+// The capacity of each of the buffers a `Compat` copies through.
+const BUF_SIZE: usize = 64 * 1024;
+
+/// A stream adapted to tokio's [`AsyncRead`] and [`AsyncWrite`] traits.
+///
+/// Created by the `into_compat` method of [`TcpStream`] and [`UnixStream`], for use with
+/// libraries written against tokio's traits, such as TLS or HTTP implementations. io_uring
+/// needs to own the buffers of in-flight operations, while the traits lend them only for the
+/// duration of a call, so the adapter copies through buffers of its own:
+///
+/// - Reads receive into an internal buffer, which `poll_read` then copies out of.
+/// - Writes are copied into a staging buffer and sent from there, one send at a time, while
+///   further writes are staged. `poll_flush` completes once everything staged has been sent.
+///   `poll_shutdown` flushes, then shuts down the write half of the connection.
+///
+/// # Cancellation
+///
+/// Operations in flight belong to the adapter rather than to the future polling them, so
+/// dropping a read or write future, say on a timeout, loses nothing: a receive left in
+/// flight delivers its data to the next read.
+///
+/// Dropping the adapter with bytes still staged or in flight does not lose them either.
+/// They are sent by a background task, and the connection is closed once that is done, or
+/// once sending fails. This relies on the runtime still running; bytes staged when the
+/// adapter is dropped outside of it are lost. Use [`into_inner`](Compat::into_inner) to
+/// flush and recover the stream instead.
+///
+/// [`TcpStream`]: crate::net::TcpStream
+/// [`UnixStream`]: crate::net::UnixStream
+pub struct Compat<S> {
+    // `None` only once taken by `into_inner`.
+    stream: Option<S>,
+    fd: SharedFd,
+
+    // Bytes received, of which those before `read_pos` have been read; `None` while a
+    // receive is in flight.
+    read_buf: Option<Buffer>,
+    read_pos: usize,
+    recv: Option<Op<Resume>>,
+
+    // Bytes written but not yet handed to the kernel.
+    staged: Buffer,
+    // The send in flight, and how much of its buffer earlier sends covered.
+    send: Option<Op<Resume>>,
+    sent: usize,
+    // The buffer of the last completed send, kept for staging into.
+    spare: Option<Buffer>,
+
+    shutdown: Option<Op<Shutdown>>,
+}
+
+fn new_buf() -> Buffer {
+    let mut buf = Buffer::from(vec![0; BUF_SIZE]);
+    // Safety: the buffer is zeroed, so shrinking its initialized length is sound, and
+    // growing it back is too.
+    unsafe { IoBufMut::set_init(&mut buf, 0) };
+    buf
+}
+
+impl<S> Compat<S> {
+    pub(crate) fn new(stream: S, fd: &SharedFd) -> Compat<S> {
+        Compat {
+            stream: Some(stream),
+            fd: fd.clone(),
+            read_buf: Some(new_buf()),
+            read_pos: 0,
+            recv: None,
+            staged: new_buf(),
+            send: None,
+            sent: 0,
+            spare: None,
+            shutdown: None,
+        }
+    }
+
+    /// Returns a reference to the underlying stream.
+    pub fn get_ref(&self) -> &S {
+        self.stream.as_ref().unwrap()
+    }
+
+    /// Sends any bytes still staged, and returns the underlying stream.
+    ///
+    /// Bytes already received into the adapter's read buffer but not yet read are lost, as
+    /// are those of a receive still in flight.
+    pub async fn into_inner(mut self) -> io::Result<S> {
+        poll_fn(|cx| self.poll_flush_staged(cx)).await?;
+        Ok(self.stream.take().unwrap())
+    }
+
+    // Polls the send in flight, resending whatever part of its buffer it left unsent. Ready
+    // once no send is in flight.
+    fn poll_send(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while let Some(op) = self.send.as_mut() {
+            let res = ready!(Pin::new(op).poll(cx));
+            self.send = None;
+            let (n, mut buf) = res.map_err(|e| e.0)?;
+            if n == 0 {
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::WriteZero,
+                    "failed to write whole buffer",
+                )));
+            }
+            self.sent += n;
+            if self.sent < IoBuf::bytes_init(&buf) {
+                self.send = Some(Op::write_from(&self.fd, buf, self.sent)?);
+            } else {
+                // Safety: the buffer stays zeroed or holds earlier data, as in `new_buf`.
+                unsafe { IoBufMut::set_init(&mut buf, 0) };
+                self.spare = Some(buf);
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    // Sends the staged bytes, if there are any. No send may be in flight.
+    fn start_send(&mut self) -> io::Result<()> {
+        debug_assert!(self.send.is_none());
+        if IoBuf::bytes_init(&self.staged) == 0 {
+            return Ok(());
+        }
+        let spare = self.spare.take().unwrap_or_else(new_buf);
+        let buf = std::mem::replace(&mut self.staged, spare);
+        self.sent = 0;
+        self.send = Some(Op::write_from(&self.fd, buf, 0)?);
+        Ok(())
+    }
+
+    fn poll_flush_staged(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        loop {
+            ready!(self.poll_send(cx))?;
+            if IoBuf::bytes_init(&self.staged) == 0 {
+                return Poll::Ready(Ok(()));
+            }
+            self.start_send()?;
+        }
+    }
+}
+
+impl<S: Unpin> AsyncRead for Compat<S> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        out: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if out.remaining() == 0 {
+            return Poll::Ready(Ok(()));
+        }
+
+        loop {
+            if let Some(buf) = &this.read_buf {
+                let available = &buf[0][this.read_pos..];
+                if !available.is_empty() {
+                    let n = available.len().min(out.remaining());
+                    out.put_slice(&available[..n]);
+                    this.read_pos += n;
+                    return Poll::Ready(Ok(()));
+                }
+            }
+
+            if this.recv.is_none() {
+                let buf = this.read_buf.take().unwrap();
+                this.read_pos = 0;
+                this.recv = Some(Op::read_from(&this.fd, buf, 0)?);
+            }
+
+            let res = ready!(Pin::new(this.recv.as_mut().unwrap()).poll(cx));
+            this.recv = None;
+            match res {
+                Ok((n, mut buf)) => {
+                    // Safety: the kernel wrote `n` bytes at the start of the buffer.
+                    unsafe { IoBufMut::set_init(&mut buf, n) };
+                    this.read_buf = Some(buf);
+                    if n == 0 {
+                        // End of stream.
+                        return Poll::Ready(Ok(()));
+                    }
+                }
+                Err(e) => {
+                    let mut buf = e.1;
+                    // Safety: nothing was received.
+                    unsafe { IoBufMut::set_init(&mut buf, 0) };
+                    this.read_buf = Some(buf);
+                    return Poll::Ready(Err(e.0));
+                }
+            }
+        }
+    }
+}
+
+impl<S: Unpin> AsyncWrite for Compat<S> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        data: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if data.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
+        // Make progress on the send in flight; once it is done, the staged bytes follow.
+        if let Poll::Ready(res) = this.poll_send(cx) {
+            res?;
+            this.start_send()?;
+        }
+
+        let staged = IoBuf::bytes_init(&this.staged);
+        let n = data.len().min(BUF_SIZE - staged);
+        if n == 0 {
+            // Staging is full, and the send in flight registered the waker.
+            return Poll::Pending;
+        }
+        // Safety: the bytes are written right below, and are zeroed or stale until then.
+        unsafe { IoBufMut::set_init(&mut this.staged, staged + n) };
+        this.staged[0][staged..staged + n].copy_from_slice(&data[..n]);
+
+        if this.send.is_none() {
+            this.start_send()?;
+        }
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.get_mut().poll_flush_staged(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_flush_staged(cx))?;
+
+        if this.shutdown.is_none() {
+            this.shutdown = Some(Op::shutdown(&this.fd, std::net::Shutdown::Write)?);
+        }
+        let res = ready!(Pin::new(this.shutdown.as_mut().unwrap()).poll(cx));
+        this.shutdown = None;
+        Poll::Ready(res)
+    }
+}
+
+impl<S> Drop for Compat<S> {
+    fn drop(&mut self) {
+        let send = self.send.take();
+        if send.is_none() && IoBuf::bytes_init(&self.staged) == 0 {
+            return;
+        }
+        if !CONTEXT.with(|x| x.is_set()) {
+            return;
+        }
+
+        // The background task holds the descriptor open until it is done.
+        let fd = self.fd.clone();
+        let mut sent = self.sent;
+        let staged = std::mem::replace(&mut self.staged, Buffer::from(Vec::<u8>::new()));
+        crate::spawn(async move {
+            if let Some(send) = send {
+                let Ok((n, buf)) = send.await else {
+                    return;
+                };
+                sent += n;
+                if send_from(&fd, buf, sent).await.is_err() {
+                    return;
+                }
+            }
+            let _ = send_from(&fd, staged, 0).await;
+        });
+    }
+}
+
+// Sends the initialized bytes of `buf` from `from` on.
+async fn send_from(fd: &SharedFd, mut buf: Buffer, mut from: usize) -> io::Result<()> {
+    while from < IoBuf::bytes_init(&buf) {
+        let (n, b) = Op::write_from(fd, buf, from)?.await.map_err(|e| e.0)?;
+        if n == 0 {
+            return Err(io::ErrorKind::WriteZero.into());
+        }
+        from += n;
+        buf = b;
+    }
+    Ok(())
+}
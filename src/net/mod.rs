@@ -7,16 +7,73 @@
 //!
 //! * [`TcpListener`] and [`TcpStream`] provide functionality for communication over TCP
 //! * [`UdpSocket`] provides functionality for communication over UDP
+//! * [`RawSocket`] provides raw IP and ICMP datagram sockets
+//! * [`UnixListener`], [`UnixStream`] and [`UnixDatagram`] provide functionality for
+//!   communication over Unix domain sockets
+//! * [`VsockListener`] and [`VsockStream`] provide communication between virtual machines
+//!   and their host over vsock
 
 //!
 //! [`TcpListener`]: TcpListener
 //! [`TcpStream`]: TcpStream
 //! [`UdpSocket`]: UdpSocket
+//! [`RawSocket`]: RawSocket
+//! [`UnixListener`]: UnixListener
+//! [`UnixStream`]: UnixStream
+//! [`UnixDatagram`]: UnixDatagram
+//! [`VsockListener`]: VsockListener
+//! [`VsockStream`]: VsockStream
 
+pub(crate) mod cmsg;
+mod compat;
+mod raw;
+mod split;
 mod tcp;
 mod udp;
 mod unix;
+mod vsock;
 
-pub use tcp::{TcpListener, TcpStream};
-pub use udp::UdpSocket;
-pub use unix::{UnixListener, UnixStream};
+pub use crate::io::{AcceptFlags, DatagramTruncated, DirectTableFull, Interest, Ready};
+pub use cmsg::{CMsgs, ControlMessage, TimestampingFlags};
+pub use compat::Compat;
+pub use raw::RawSocket;
+pub use split::{OwnedReadHalf, OwnedWriteHalf, ReuniteError};
+pub use tcp::{
+    ConnectError, DirectTcpStream, KeepaliveConfig, TcpListener, TcpListenerBuilder, TcpSocket,
+    TcpStream,
+};
+pub use udp::{UdpSocket, UdpSocketBuilder};
+pub use unix::{UCred, UnixDatagram, UnixListener, UnixListenerBuilder, UnixStream};
+pub use vsock::{VsockAddr, VsockListener, VsockStream};
+
+use std::net::SocketAddr;
+
+/// Converts an IPv4-mapped IPv6 address, `[::ffff:a.b.c.d]:port`, to the IPv4 address it
+/// stands for, and returns other addresses unchanged.
+///
+/// Sockets bound to an IPv6 address with `IPV6_V6ONLY` off also serve IPv4 peers, and the
+/// kernel reports those in the mapped form. The socket types pass addresses on as the
+/// kernel reports them, as the standard library does; this converts them for logging or
+/// comparing against IPv4 addresses.
+///
+/// # Examples
+///
+/// ```
+/// use std::net::SocketAddr;
+/// use tokio_uring::net::to_canonical;
+///
+/// let mapped: SocketAddr = "[::ffff:127.0.0.1]:8080".parse().unwrap();
+/// assert_eq!(to_canonical(mapped), "127.0.0.1:8080".parse().unwrap());
+///
+/// let v6: SocketAddr = "[::1]:8080".parse().unwrap();
+/// assert_eq!(to_canonical(v6), v6);
+/// ```
+pub fn to_canonical(addr: SocketAddr) -> SocketAddr {
+    match addr {
+        SocketAddr::V6(v6) => match v6.ip().to_ipv4_mapped() {
+            Some(ip) => SocketAddr::new(ip.into(), v6.port()),
+            None => addr,
+        },
+        SocketAddr::V4(_) => addr,
+    }
+}
@@ -0,0 +1,194 @@
+use crate::{
+    buf::Buffer,
+    io::{Interest, Ready, SharedFd, Socket},
+};
+use std::{
+    io,
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr},
+    os::unix::prelude::{AsRawFd, FromRawFd, RawFd},
+};
+
+/// A raw IP socket, or an ICMP datagram socket, for protocols above IP other than TCP and
+/// UDP, such as ICMP for ping and traceroute.
+///
+/// Sockets made by [`new_v4`](RawSocket::new_v4) and [`new_v6`](RawSocket::new_v6) are
+/// `SOCK_RAW`, which requires `CAP_NET_RAW`. They receive every packet of their protocol
+/// addressed to the host, and on IPv4 each received packet starts with its IP header.
+///
+/// Sockets made by [`ping_v4`](RawSocket::ping_v4) and [`ping_v6`](RawSocket::ping_v6) are
+/// ICMP datagram sockets, open to unprivileged users whose group is in the
+/// `net.ipv4.ping_group_range` sysctl. They only send echo requests, and only receive the
+/// replies to them; the kernel manages the identifier field, and received packets start with
+/// the ICMP header.
+///
+/// # Examples
+///
+/// ```no_run
+/// use tokio_uring::net::RawSocket;
+///
+/// tokio_uring::start(async {
+///     let socket = match RawSocket::ping_v4() {
+///         Ok(socket) => socket,
+///         // Fall back to a raw socket, or tell the user to adjust the sysctl.
+///         Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => return,
+///         Err(e) => panic!("{}", e),
+///     };
+///
+///     // An echo request: type 8, code 0, checksum, identifier and sequence number, which
+///     // the kernel fills in or fixes up for ICMP datagram sockets.
+///     let request = vec![8, 0, 0, 0, 0, 0, 0, 1];
+///     let dest = "127.0.0.1:0".parse().unwrap();
+///     socket.send_to(request.into(), dest).await.unwrap();
+///
+///     let ((n, from), reply) = socket.recv_from(vec![0; 64].into()).await.unwrap();
+///     println!("{} bytes of echo reply from {}", n, from.ip());
+/// });
+/// ```
+pub struct RawSocket {
+    inner: Socket,
+}
+
+impl RawSocket {
+    /// Creates a `SOCK_RAW` IPv4 socket for the IP protocol number `protocol`, such as
+    /// `libc::IPPROTO_ICMP`.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`PermissionDenied`](io::ErrorKind::PermissionDenied) without
+    /// `CAP_NET_RAW`.
+    pub fn new_v4(protocol: libc::c_int) -> io::Result<RawSocket> {
+        RawSocket::new((Ipv4Addr::UNSPECIFIED, 0).into(), libc::SOCK_RAW, protocol)
+    }
+
+    /// Creates a `SOCK_RAW` IPv6 socket for the protocol number `protocol`, such as
+    /// `libc::IPPROTO_ICMPV6`.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`PermissionDenied`](io::ErrorKind::PermissionDenied) without
+    /// `CAP_NET_RAW`.
+    pub fn new_v6(protocol: libc::c_int) -> io::Result<RawSocket> {
+        RawSocket::new((Ipv6Addr::UNSPECIFIED, 0).into(), libc::SOCK_RAW, protocol)
+    }
+
+    /// Creates an ICMP datagram socket for IPv4 echo requests.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`PermissionDenied`](io::ErrorKind::PermissionDenied) if the group of the
+    /// process is outside `net.ipv4.ping_group_range`.
+    pub fn ping_v4() -> io::Result<RawSocket> {
+        RawSocket::new(
+            (Ipv4Addr::UNSPECIFIED, 0).into(),
+            libc::SOCK_DGRAM,
+            libc::IPPROTO_ICMP,
+        )
+    }
+
+    /// Creates an ICMPv6 datagram socket for IPv6 echo requests.
+    ///
+    /// # Errors
+    ///
+    /// As for [`ping_v4`](RawSocket::ping_v4), which shares the sysctl.
+    pub fn ping_v6() -> io::Result<RawSocket> {
+        RawSocket::new(
+            (Ipv6Addr::UNSPECIFIED, 0).into(),
+            libc::SOCK_DGRAM,
+            libc::IPPROTO_ICMPV6,
+        )
+    }
+
+    fn new(
+        addr: SocketAddr,
+        socket_type: libc::c_int,
+        protocol: libc::c_int,
+    ) -> io::Result<RawSocket> {
+        let inner = Socket::new_with_protocol(addr, socket_type, protocol).map_err(|e| match e
+            .raw_os_error()
+        {
+            Some(libc::EPERM) | Some(libc::EACCES) if socket_type == libc::SOCK_RAW => {
+                io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    "raw sockets require CAP_NET_RAW",
+                )
+            }
+            Some(libc::EPERM) | Some(libc::EACCES) => io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "ICMP datagram sockets require a group in net.ipv4.ping_group_range",
+            ),
+            _ => e,
+        })?;
+        Ok(RawSocket { inner })
+    }
+
+    /// Binds the socket to a local address, restricting the packets it receives to those
+    /// sent to it. The port is ignored by raw sockets; for ICMP datagram sockets, a non-zero
+    /// port sets the echo identifier.
+    pub fn bind(&self, addr: SocketAddr) -> io::Result<()> {
+        socket2::SockRef::from(&self.inner).bind(&addr.into())
+    }
+
+    /// Returns the local address of the socket. For ICMP datagram sockets, the port is the
+    /// echo identifier the kernel assigned, once the socket sent or was bound.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        socket2::SockRef::from(&self.inner)
+            .local_addr()?
+            .as_socket()
+            .ok_or_else(|| io::Error::other("Could not get socket IP address"))
+    }
+
+    /// Sets `IP_HDRINCL` on an IPv4 `SOCK_RAW` socket: the buffers passed to
+    /// [`send_to`](RawSocket::send_to) then start with an IP header the caller built, which
+    /// the kernel sends as is, apart from filling in a zero source address, identification
+    /// and checksum.
+    pub fn set_header_included(&self, included: bool) -> io::Result<()> {
+        socket2::SockRef::from(&self.inner).set_header_included(included)
+    }
+
+    /// Sets the value of the IP_TTL option on this socket, which traceroute varies to find
+    /// the hops to a host.
+    pub fn set_ttl(&self, ttl: u32) -> io::Result<()> {
+        self.inner.set_ttl(ttl)
+    }
+
+    /// Sends the initialized bytes of `buf` as one packet to `addr`, whose port is ignored.
+    ///
+    /// On success, returns the number of bytes sent.
+    pub async fn send_to(&self, buf: Buffer, addr: SocketAddr) -> crate::Result<usize, Buffer> {
+        self.inner.send_to(buf, Some(addr)).await
+    }
+
+    /// Receives one packet into the capacity of `buf`.
+    ///
+    /// On success, returns the number of bytes received and the address of the sender.
+    ///
+    /// # Errors
+    ///
+    /// Packets larger than the buffer fail with a
+    /// [`DatagramTruncated`](crate::net::DatagramTruncated) error, as with
+    /// [`UdpSocket::recv_from`](crate::net::UdpSocket::recv_from).
+    pub async fn recv_from(&self, buf: Buffer) -> crate::Result<(usize, SocketAddr), Buffer> {
+        self.inner.recv_from(buf).await
+    }
+
+    /// Waits until any of the events in `interest` fire, and returns those that did.
+    ///
+    /// See [`TcpStream::ready`](crate::net::TcpStream::ready).
+    pub async fn ready(&self, interest: Interest) -> io::Result<Ready> {
+        self.inner.ready(interest).await
+    }
+}
+
+impl FromRawFd for RawSocket {
+    unsafe fn from_raw_fd(fd: RawFd) -> Self {
+        RawSocket {
+            inner: Socket::from_shared_fd(SharedFd::new(fd)),
+        }
+    }
+}
+
+impl AsRawFd for RawSocket {
+    fn as_raw_fd(&self) -> RawFd {
+        self.inner.as_raw_fd()
+    }
+}
@@ -0,0 +1,234 @@
+use crate::buf::Buffer;
+use crate::net::{TcpStream, UnixStream};
+use crate::{Unsubmitted, UnsubmittedRecv, UnsubmittedSend};
+use std::net::SocketAddr;
+use std::rc::Rc;
+use std::{fmt, io};
+
+/// The read half of a stream, created by the `into_split` method of [`TcpStream`] and
+/// [`UnixStream`].
+///
+/// Both halves share the socket, which stays open until both are dropped. Reads happen
+/// through this half only, so it can be moved into a task of its own while another task
+/// writes through the [`OwnedWriteHalf`].
+pub struct OwnedReadHalf<S> {
+    inner: Rc<S>,
+}
+
+/// The write half of a stream, created by the `into_split` method of [`TcpStream`] and
+/// [`UnixStream`].
+///
+/// Writes and shutting down happen through this half only. Dropping it leaves the
+/// connection open for the read half; call [`shutdown`](OwnedWriteHalf::shutdown) to let
+/// the peer know nothing more is coming.
+pub struct OwnedWriteHalf<S> {
+    inner: Rc<S>,
+}
+
+pub(crate) fn split<S>(stream: S) -> (OwnedReadHalf<S>, OwnedWriteHalf<S>) {
+    let inner = Rc::new(stream);
+    (
+        OwnedReadHalf {
+            inner: inner.clone(),
+        },
+        OwnedWriteHalf { inner },
+    )
+}
+
+fn reunite<S>(read: OwnedReadHalf<S>, write: OwnedWriteHalf<S>) -> Result<S, ReuniteError<S>> {
+    if !Rc::ptr_eq(&read.inner, &write.inner) {
+        return Err(ReuniteError(read, write));
+    }
+    drop(write);
+    // Operations borrow a half for as long as they run, so with both halves here, nothing
+    // else holds the stream.
+    Ok(Rc::try_unwrap(read.inner)
+        .ok()
+        .expect("a stream split into halves is only held by them"))
+}
+
+impl<S> OwnedReadHalf<S> {
+    /// Puts the stream back together from its halves.
+    ///
+    /// # Errors
+    ///
+    /// Fails, handing both halves back, if they were split from different streams.
+    pub fn reunite(self, other: OwnedWriteHalf<S>) -> Result<S, ReuniteError<S>> {
+        reunite(self, other)
+    }
+}
+
+impl<S> OwnedWriteHalf<S> {
+    /// Puts the stream back together from its halves.
+    ///
+    /// # Errors
+    ///
+    /// Fails, handing both halves back, if they were split from different streams.
+    pub fn reunite(self, other: OwnedReadHalf<S>) -> Result<S, ReuniteError<S>> {
+        reunite(other, self)
+    }
+}
+
+impl OwnedReadHalf<TcpStream> {
+    /// Read some data from the stream into the buffer. See [`TcpStream::read`].
+    pub async fn read(&self, buf: Buffer) -> crate::Result<usize, Buffer> {
+        self.inner.read(buf).await
+    }
+
+    /// Reads into the buffer until it is full. See [`TcpStream::read_exact`].
+    pub async fn read_exact(&self, buf: Buffer) -> crate::Result<(), Buffer> {
+        self.inner.read_exact(buf).await
+    }
+
+    /// Receives data with the given flags. See [`TcpStream::recv`].
+    pub fn recv(&self, buf: Buffer) -> UnsubmittedRecv {
+        self.inner.recv(buf)
+    }
+
+    /// Receives data without removing it from the socket. See [`TcpStream::peek`].
+    pub async fn peek(&self, buf: Buffer) -> crate::Result<usize, Buffer> {
+        self.inner.peek(buf).await
+    }
+
+    /// Waits for the stream to become readable. See [`TcpStream::readable`].
+    pub async fn readable(&self) -> io::Result<()> {
+        self.inner.readable().await
+    }
+
+    /// Returns the local address that the stream is bound to.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.inner.local_addr()
+    }
+
+    /// Returns the address of the remote peer the stream is connected to.
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.inner.peer_addr()
+    }
+}
+
+impl OwnedWriteHalf<TcpStream> {
+    /// Write some data to the stream from the buffer. See [`TcpStream::write`].
+    pub fn write(&self, buf: Buffer) -> Unsubmitted {
+        self.inner.write(buf)
+    }
+
+    /// Sends data with the given flags. See [`TcpStream::send`].
+    pub fn send(&self, buf: Buffer) -> UnsubmittedSend {
+        self.inner.send(buf)
+    }
+
+    /// Writes the whole buffer. See [`TcpStream::write_all`].
+    pub async fn write_all(&self, buf: Buffer) -> crate::Result<(), Buffer> {
+        self.inner.write_all(buf).await
+    }
+
+    /// Writes the segments of the buffer in one operation. See [`TcpStream::writev`].
+    pub async fn writev(&self, buf: Buffer) -> crate::Result<usize, Buffer> {
+        self.inner.writev(buf).await
+    }
+
+    /// Waits for the stream to become writable. See [`TcpStream::writable`].
+    pub async fn writable(&self) -> io::Result<()> {
+        self.inner.writable().await
+    }
+
+    /// Shuts down the write half of the connection, so that the peer reads the end of the
+    /// stream once it has read everything sent before.
+    pub fn shutdown(&self) -> io::Result<()> {
+        self.inner.shutdown(std::net::Shutdown::Write)
+    }
+
+    /// Returns the local address that the stream is bound to.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.inner.local_addr()
+    }
+
+    /// Returns the address of the remote peer the stream is connected to.
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.inner.peer_addr()
+    }
+}
+
+impl OwnedReadHalf<UnixStream> {
+    /// Read some data from the stream into the buffer. See [`UnixStream::read`].
+    pub async fn read(&self, buf: Buffer) -> crate::Result<usize, Buffer> {
+        self.inner.read(buf).await
+    }
+
+    /// Reads into the buffer until it is full. See [`UnixStream::read_exact`].
+    pub async fn read_exact(&self, buf: Buffer) -> crate::Result<(), Buffer> {
+        self.inner.read_exact(buf).await
+    }
+
+    /// Waits for the stream to become readable. See [`UnixStream::readable`].
+    pub async fn readable(&self) -> io::Result<()> {
+        self.inner.readable().await
+    }
+
+    /// Returns the local address that the stream is bound to.
+    pub fn local_addr(&self) -> io::Result<std::os::unix::net::SocketAddr> {
+        self.inner.local_addr()
+    }
+
+    /// Returns the address of the remote peer the stream is connected to.
+    pub fn peer_addr(&self) -> io::Result<std::os::unix::net::SocketAddr> {
+        self.inner.peer_addr()
+    }
+}
+
+impl OwnedWriteHalf<UnixStream> {
+    /// Write some data to the stream from the buffer. See [`UnixStream::write`].
+    pub fn write(&self, buf: Buffer) -> Unsubmitted {
+        self.inner.write(buf)
+    }
+
+    /// Writes the whole buffer. See [`UnixStream::write_all`].
+    pub async fn write_all(&self, buf: Buffer) -> crate::Result<(), Buffer> {
+        self.inner.write_all(buf).await
+    }
+
+    /// Writes the segments of the buffer in one operation. See [`UnixStream::writev`].
+    pub async fn writev(&self, buf: Buffer) -> crate::Result<usize, Buffer> {
+        self.inner.writev(buf).await
+    }
+
+    /// Waits for the stream to become writable. See [`UnixStream::writable`].
+    pub async fn writable(&self) -> io::Result<()> {
+        self.inner.writable().await
+    }
+
+    /// Shuts down the write half of the connection, so that the peer reads the end of the
+    /// stream once it has read everything sent before.
+    pub fn shutdown(&self) -> io::Result<()> {
+        self.inner.shutdown(std::net::Shutdown::Write)
+    }
+
+    /// Returns the local address that the stream is bound to.
+    pub fn local_addr(&self) -> io::Result<std::os::unix::net::SocketAddr> {
+        self.inner.local_addr()
+    }
+
+    /// Returns the address of the remote peer the stream is connected to.
+    pub fn peer_addr(&self) -> io::Result<std::os::unix::net::SocketAddr> {
+        self.inner.peer_addr()
+    }
+}
+
+/// The error from `reunite` when the halves were split from different streams.
+///
+/// Holds both halves, so neither is lost.
+pub struct ReuniteError<S>(pub OwnedReadHalf<S>, pub OwnedWriteHalf<S>);
+
+impl<S> fmt::Debug for ReuniteError<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReuniteError").finish_non_exhaustive()
+    }
+}
+
+impl<S> fmt::Display for ReuniteError<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("tried to reunite halves that are not from the same stream")
+    }
+}
+
+impl<S> std::error::Error for ReuniteError<S> {}
@@ -0,0 +1,96 @@
+use crate::buf::{read_only_error, Buffer, IoBuf, IoBufMut};
+use crate::io::DirectFd;
+use crate::runtime::driver::op::Op;
+use crate::WithBuffer;
+use std::io;
+use std::net::Shutdown;
+
+/// A TCP stream accepted into the runtime's table of direct descriptors.
+///
+/// Created by [`TcpListener::accept_direct`]. The connection has no file descriptor: its
+/// operations name the slot in the table instead, which saves the kernel installing and
+/// removing a descriptor per connection. Without a descriptor, the stream cannot be passed
+/// to other code, and offers only the operations below.
+///
+/// Dropping the stream, or calling [`close`](Self::close), frees its slot with a
+/// registered-files update. The slot becomes available to the next direct accept once no
+/// operation on the stream is still in flight.
+///
+/// [`TcpListener::accept_direct`]: crate::net::TcpListener::accept_direct
+pub struct DirectTcpStream {
+    fd: DirectFd,
+}
+
+impl DirectTcpStream {
+    pub(crate) fn new(fd: DirectFd) -> DirectTcpStream {
+        DirectTcpStream { fd }
+    }
+
+    /// Returns the slot the stream occupies in the direct descriptor table.
+    pub fn slot(&self) -> u32 {
+        self.fd.slot()
+    }
+
+    /// Read some data from the stream into the buffer.
+    ///
+    /// Returns the original buffer and quantity of data read; 0 once the peer has closed the
+    /// connection.
+    pub async fn read(&self, mut buf: Buffer) -> crate::Result<usize, Buffer> {
+        if buf.is_read_only() {
+            return Err(read_only_error()).with_buffer(buf);
+        }
+        // Safety: the read fills the buffer from its start.
+        unsafe { IoBufMut::set_init(&mut buf, 0) };
+        if buf.capacity() == 0 {
+            return Ok((0, buf));
+        }
+        Op::direct_read(&self.fd, buf).unwrap().await
+    }
+
+    /// Write some data to the stream from the buffer.
+    ///
+    /// Returns the original buffer and quantity of data written.
+    pub async fn write(&self, buf: Buffer) -> crate::Result<usize, Buffer> {
+        if buf.is_empty() {
+            return Ok((0, buf));
+        }
+        Op::direct_write(&self.fd, buf, 0).unwrap().await
+    }
+
+    /// Writes the whole initialized contents of the buffer to the stream.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`WriteZero`](io::ErrorKind::WriteZero) if the stream stops accepting
+    /// data before the buffer is written.
+    pub async fn write_all(&self, mut buf: Buffer) -> crate::Result<(), Buffer> {
+        let total = IoBuf::bytes_init(&buf);
+        let mut written = 0;
+        while written < total {
+            let (n, b) = Op::direct_write(&self.fd, buf, written).unwrap().await?;
+            if n == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::WriteZero,
+                    "failed to write whole buffer",
+                ))
+                .with_buffer(b);
+            }
+            written += n;
+            buf = b;
+        }
+        Ok(((), buf))
+    }
+
+    /// Shuts down the read, write, or both halves of this connection.
+    pub async fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        Op::direct_shutdown(&self.fd, how)?.await
+    }
+
+    /// Frees the stream's slot in the direct descriptor table, closing the connection.
+    ///
+    /// If operations on the stream are still in flight, the slot is freed once they complete
+    /// instead, and this returns `Ok(())`.
+    pub fn close(self) -> io::Result<()> {
+        self.fd.close()
+    }
+}
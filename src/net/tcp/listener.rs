@@ -1,5 +1,7 @@
-use super::TcpStream;
-use crate::io::{SharedFd, Socket};
+use super::{DirectTcpStream, TcpSocket, TcpStream};
+use crate::io::{AcceptFlags, AcceptGate, AcceptStream, Incoming, SharedFd, Socket};
+use futures_core::Stream;
+use futures_util::StreamExt;
 use std::{
     io,
     net::SocketAddr,
@@ -43,6 +45,7 @@ use std::{
 /// ```
 pub struct TcpListener {
     inner: Socket,
+    gate: AcceptGate,
 }
 
 impl TcpListener {
@@ -52,18 +55,48 @@ impl TcpListener {
     ///
     /// Binding with a port number of 0 will request that the OS assigns a port
     /// to this listener.
+    ///
+    /// The socket has `SO_REUSEADDR` and `SO_REUSEPORT` set and a backlog of 1024. Use
+    /// [`builder`](TcpListener::builder) to choose otherwise.
     pub fn bind(addr: SocketAddr) -> io::Result<Self> {
-        let socket = Socket::bind(addr, libc::SOCK_STREAM)?;
-        socket.listen(1024)?;
-        Ok(TcpListener { inner: socket })
+        TcpListener::builder().bind(addr)
+    }
+
+    /// Returns a builder to configure the listening socket before it is bound.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tokio_uring::net::TcpListener;
+    ///
+    /// let listener = TcpListener::builder()
+    ///     .reuseport(false)
+    ///     .backlog(128)
+    ///     .bind("127.0.0.1:0".parse().unwrap())
+    ///     .unwrap();
+    /// assert_ne!(listener.local_addr().unwrap().port(), 0);
+    /// ```
+    pub fn builder() -> TcpListenerBuilder {
+        TcpListenerBuilder {
+            reuseaddr: true,
+            reuseport: true,
+            only_v6: None,
+            backlog: 1024,
+            fastopen: None,
+            device: None,
+            send_buffer_size: None,
+            recv_buffer_size: None,
+            incoming_cpu: None,
+        }
     }
 
     /// Creates new `TcpListener` from a previously bound `std::net::TcpListener`.
     ///
     /// This function is intended to be used to wrap a TCP listener from the
-    /// standard library in the tokio-uring equivalent. The conversion assumes nothing
-    /// about the underlying socket; it is left up to the user to decide what socket
-    /// options are appropriate for their use case.
+    /// standard library in the tokio-uring equivalent. The socket is switched to blocking
+    /// mode, as io_uring waits for readiness itself; beyond that, the conversion assumes
+    /// nothing about the underlying socket, and it is left up to the user to decide what
+    /// socket options are appropriate for their use case.
     ///
     /// This can be used in conjunction with socket2's `Socket` interface to
     /// configure a socket before it's handed off, such as setting options like
@@ -86,11 +119,27 @@ impl TcpListener {
     /// ```
     pub fn from_std(socket: std::net::TcpListener) -> Self {
         let inner = Socket::from_std(socket);
-        Self { inner }
+        Self::from_socket(inner)
+    }
+
+    /// Converts the listener into a [`std::net::TcpListener`], transferring ownership of the socket.
+    ///
+    /// The returned socket is in blocking mode; call `set_nonblocking(true)` on it before
+    /// handing it to a readiness-based runtime such as tokio.
+    ///
+    /// # Errors
+    ///
+    /// Fails while operations on the socket are still in flight, such as one whose future was
+    /// dropped before it completed. The socket is then closed once they finish.
+    pub fn into_std(self) -> io::Result<std::net::TcpListener> {
+        self.inner.into_std()
     }
 
     pub(crate) fn from_socket(inner: Socket) -> Self {
-        Self { inner }
+        Self {
+            inner,
+            gate: AcceptGate::default(),
+        }
     }
 
     /// Returns the local address that this listener is bound to.
@@ -120,21 +169,280 @@ impl TcpListener {
         local_addr
     }
 
+    /// Returns the name of the interface the listener is restricted to with
+    /// [`TcpListenerBuilder::device`], if any.
+    pub fn device(&self) -> io::Result<Option<String>> {
+        self.inner.device()
+    }
+
+    /// Gets `SO_INCOMING_CPU`, as set by [`TcpListenerBuilder::incoming_cpu`], or `None`
+    /// if it was not.
+    pub fn incoming_cpu(&self) -> io::Result<Option<u32>> {
+        self.inner.incoming_cpu()
+    }
+
+    /// Steers the connections of the listener's `SO_REUSEPORT` group by CPU, attaching a
+    /// classic BPF program with `SO_ATTACH_REUSEPORT_CBPF`.
+    ///
+    /// Each connection goes to the listener at index `cpu % listeners` in the group, where
+    /// `cpu` is the CPU that processed its SYN. Listeners are indexed in the order they were
+    /// bound, so a server running a runtime pinned to each of `n` cores binds a listener from
+    /// each runtime, in the order of their cores, and attaches the program with `n` once
+    /// all are bound. Each runtime then accepts the connections whose packets its own core
+    /// processes. Closing a listener moves the last one in the group into its index.
+    ///
+    /// The program applies to the whole group, so attaching it through one listener is
+    /// enough; it stays attached while any listener in the group is open.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`PermissionDenied`](io::ErrorKind::PermissionDenied) if the kernel does
+    /// not permit the attachment, and with [`InvalidInput`](io::ErrorKind::InvalidInput)
+    /// for zero listeners.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use tokio_uring::net::TcpListener;
+    ///
+    /// let cores = 4;
+    /// let addr = "0.0.0.0:8080".parse().unwrap();
+    /// // In practice, each listener is bound from the runtime pinned to its core.
+    /// let listeners: Vec<_> = (0..cores)
+    ///     .map(|_| TcpListener::bind(addr).unwrap())
+    ///     .collect();
+    /// listeners[0].attach_cpu_steering(cores).unwrap();
+    /// ```
+    pub fn attach_cpu_steering(&self, listeners: u32) -> io::Result<()> {
+        self.inner.attach_reuseport_cpu_steering(listeners)
+    }
+
+    /// Stops accepting connections, without closing the listening socket.
+    ///
+    /// Accepts in flight are cancelled as soon as the tasks waiting on them next run. Until
+    /// [`resume`](TcpListener::resume) is called, [`accept`](TcpListener::accept) and its
+    /// variants wait, as do the streams of [`incoming`](TcpListener::incoming) and
+    /// [`accept_multi`](TcpListener::accept_multi). A connection accepted before the
+    /// cancellation took effect is still returned.
+    ///
+    /// Meanwhile the kernel completes handshakes into the listen backlog, sized by
+    /// [`TcpListenerBuilder::backlog`]. Once that fills up, new clients' SYNs go unanswered,
+    /// and they retry with backoff, which sheds load without refusing anyone outright.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use tokio_uring::net::TcpListener;
+    ///
+    /// tokio_uring::start(async {
+    ///     let listener = TcpListener::bind("127.0.0.1:8080".parse().unwrap()).unwrap();
+    ///     let active = std::rc::Rc::new(std::cell::Cell::new(0));
+    ///
+    ///     loop {
+    ///         let (stream, _) = listener.accept().await.unwrap();
+    ///         active.set(active.get() + 1);
+    ///         if active.get() == 1000 {
+    ///             // Connection handlers call `resume` as load drops.
+    ///             listener.pause();
+    ///         }
+    ///         # drop(stream);
+    ///     }
+    /// });
+    /// ```
+    pub fn pause(&self) {
+        self.gate.set_paused(true);
+    }
+
+    /// Resumes accepting connections after [`pause`](TcpListener::pause).
+    pub fn resume(&self) {
+        self.gate.set_paused(false);
+    }
+
+    /// Returns `true` while the listener is paused.
+    pub fn is_paused(&self) -> bool {
+        self.gate.is_paused()
+    }
+
     /// Accepts a new incoming connection from this listener.
     ///
     /// This function will yield once a new TCP connection is established. When
     /// established, the corresponding [`TcpStream`] and the remote peer's
     /// address will be returned.
     ///
+    /// On a listener bound to an IPv6 address that also accepts IPv4 connections, see
+    /// [`TcpListenerBuilder::only_v6`], the address of an IPv4 client is reported in its
+    /// IPv4-mapped form, `[::ffff:a.b.c.d]:port`. [`to_canonical`] converts it.
+    ///
     /// [`TcpStream`]: struct@crate::net::TcpStream
+    /// [`to_canonical`]: crate::net::to_canonical
     pub async fn accept(&self) -> io::Result<(TcpStream, SocketAddr)> {
-        let (socket, socket_addr) = self.inner.accept().await?;
+        self.accept_with_flags(AcceptFlags::default()).await
+    }
+
+    /// Accepts a new incoming connection, with `flags` set on its descriptor.
+    ///
+    /// [`accept`](TcpListener::accept) sets [`AcceptFlags::CLOEXEC`]; this lets a caller
+    /// leave it out, for a connection meant to be inherited by a child program, or add
+    /// [`AcceptFlags::NONBLOCK`], for one handed to code outside the runtime.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use tokio_uring::net::{AcceptFlags, TcpListener};
+    ///
+    /// tokio_uring::start(async {
+    ///     let listener = TcpListener::bind("127.0.0.1:8080".parse().unwrap()).unwrap();
+    ///     let (stream, _) = listener
+    ///         .accept_with_flags(AcceptFlags::CLOEXEC | AcceptFlags::NONBLOCK)
+    ///         .await
+    ///         .unwrap();
+    ///     let stream: std::net::TcpStream = stream.into_std().unwrap();
+    /// });
+    /// ```
+    pub async fn accept_with_flags(
+        &self,
+        flags: AcceptFlags,
+    ) -> io::Result<(TcpStream, SocketAddr)> {
+        let (socket, socket_addr) = self.inner.accept(flags, &self.gate).await?;
         let stream = TcpStream { inner: socket };
-        let socket_addr = socket_addr.ok_or_else(|| {
-            io::Error::new(io::ErrorKind::Other, "Could not get socket IP address")
-        })?;
+        let socket_addr = socket_addr
+            .as_socket()
+            .ok_or_else(|| io::Error::other("Could not get socket IP address"))?;
+        Ok((stream, socket_addr))
+    }
+
+    /// Accepts a new incoming connection into the runtime's table of direct descriptors.
+    ///
+    /// Like [`accept`], but the kernel places the connection in a free slot of the table
+    /// rather than allocating a file descriptor for it, and the returned [`DirectTcpStream`]
+    /// names that slot in its operations. This saves work per connection for servers with
+    /// high connection churn.
+    ///
+    /// The table is set up when the runtime starts, with its size set by
+    /// [`Builder::direct_files`]. Requires Linux 5.19 or later.
+    ///
+    /// # Errors
+    ///
+    /// Fails with a [`DirectTableFull`] error when every slot is taken, and with an error of
+    /// kind [`Unsupported`](io::ErrorKind::Unsupported) if the runtime has no table.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use tokio_uring::net::TcpListener;
+    ///
+    /// tokio_uring::builder().direct_files(4096).start(async {
+    ///     let listener = TcpListener::bind("127.0.0.1:8080".parse().unwrap()).unwrap();
+    ///
+    ///     loop {
+    ///         let (stream, _) = listener.accept_direct().await.unwrap();
+    ///         tokio_uring::spawn(async move {
+    ///             let (n, buf) = stream.read(vec![0; 4096].into()).await.unwrap();
+    ///             // ...
+    ///         });
+    ///     }
+    /// });
+    /// ```
+    ///
+    /// [`accept`]: Self::accept
+    /// [`DirectTcpStream`]: crate::net::DirectTcpStream
+    /// [`DirectTableFull`]: crate::net::DirectTableFull
+    /// [`Builder::direct_files`]: crate::Builder::direct_files
+    pub async fn accept_direct(&self) -> io::Result<(DirectTcpStream, SocketAddr)> {
+        let (fd, socket_addr) = self.inner.accept_direct(&self.gate).await?;
+        let stream = DirectTcpStream::new(fd);
+        let socket_addr = socket_addr
+            .as_socket()
+            .ok_or_else(|| io::Error::other("Could not get socket IP address"))?;
         Ok((stream, socket_addr))
     }
+
+    /// Returns a stream of incoming connections, for use with stream combinators.
+    ///
+    /// One accept is kept in flight while the stream is polled, as with calling [`accept`] in
+    /// a loop, so this works on any kernel; see [`accept_multi`] for a stream backed by a
+    /// single multishot accept instead. Dropping the stream cancels the pending accept, and
+    /// closes the connection if the accept completed in the meantime.
+    ///
+    /// While the listener is [paused](TcpListener::pause), the stream is pending.
+    ///
+    /// # Examples
+    ///
+    /// Serving until a shutdown signal:
+    ///
+    /// ```no_run
+    /// use futures_util::StreamExt;
+    /// use tokio_uring::net::TcpListener;
+    ///
+    /// tokio_uring::start(async {
+    ///     let listener = TcpListener::bind("127.0.0.1:8080".parse().unwrap()).unwrap();
+    ///     let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+    ///     # drop(stop);
+    ///
+    ///     listener
+    ///         .incoming()
+    ///         .take_until(stopped)
+    ///         .for_each_concurrent(None, |conn| async move {
+    ///             let (stream, _) = conn.unwrap();
+    ///             stream.write_all(b"hello".to_vec().into()).await.unwrap();
+    ///         })
+    ///         .await;
+    /// });
+    /// ```
+    ///
+    /// [`accept`]: TcpListener::accept
+    /// [`accept_multi`]: TcpListener::accept_multi
+    pub fn incoming(&self) -> impl Stream<Item = io::Result<(TcpStream, SocketAddr)>> {
+        <Incoming>::new(&self.inner.fd, &self.gate, AcceptFlags::default()).map(|res| {
+            let (socket, socket_addr) = res?;
+            let socket_addr = socket_addr
+                .as_socket()
+                .ok_or_else(|| io::Error::other("Could not get socket IP address"))?;
+            Ok((TcpStream { inner: socket }, socket_addr))
+        })
+    }
+
+    /// Returns a stream of incoming connections, backed by a single multishot accept.
+    ///
+    /// Unlike calling [`accept`] in a loop, which submits one operation per connection, the
+    /// kernel keeps accepting and posting connections until the stream is dropped. If the
+    /// kernel ends the multishot operation, it is re-armed on the next poll. On kernels without
+    /// multishot accept (before 5.19), the stream falls back to single-shot accepts.
+    ///
+    /// Dropping the stream cancels the operation and closes connections that were accepted
+    /// but not yet yielded. While the listener is [paused](TcpListener::pause), the stream is
+    /// pending.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use futures_util::StreamExt;
+    /// use tokio_uring::net::TcpListener;
+    ///
+    /// tokio_uring::start(async {
+    ///     let listener = TcpListener::bind("127.0.0.1:8080".parse().unwrap()).unwrap();
+    ///     let mut incoming = listener.accept_multi();
+    ///
+    ///     while let Some(conn) = incoming.next().await {
+    ///         let (stream, peer) = conn.unwrap();
+    ///         println!("accepted {}", peer);
+    ///         tokio_uring::spawn(async move { drop(stream) });
+    ///     }
+    /// });
+    /// ```
+    ///
+    /// [`accept`]: TcpListener::accept
+    pub fn accept_multi(&self) -> impl Stream<Item = io::Result<(TcpStream, SocketAddr)>> {
+        AcceptStream::new(&self.inner.fd, &self.gate).map(|socket| {
+            let socket = socket?;
+            // The multishot accept does not report peer addresses.
+            let socket_addr = socket2::SockRef::from(&socket)
+                .peer_addr()?
+                .as_socket()
+                .ok_or_else(|| io::Error::other("Could not get socket IP address"))?;
+            Ok((TcpStream { inner: socket }, socket_addr))
+        })
+    }
 }
 
 impl FromRawFd for TcpListener {
@@ -148,3 +456,133 @@ impl AsRawFd for TcpListener {
         self.inner.as_raw_fd()
     }
 }
+
+/// Configures a [`TcpListener`] before it is bound, created by [`TcpListener::builder`].
+///
+/// The options are applied to the socket between its creation and `bind(2)`. For options
+/// not covered here, configure a [`TcpSocket`] and call [`listen`](TcpSocket::listen).
+#[derive(Debug, Clone)]
+pub struct TcpListenerBuilder {
+    reuseaddr: bool,
+    reuseport: bool,
+    only_v6: Option<bool>,
+    backlog: u32,
+    fastopen: Option<u32>,
+    device: Option<String>,
+    send_buffer_size: Option<u32>,
+    recv_buffer_size: Option<u32>,
+    incoming_cpu: Option<u32>,
+}
+
+impl TcpListenerBuilder {
+    /// Sets `SO_REUSEADDR`, which allows binding while connections from a previous listener
+    /// on the same address linger in `TIME_WAIT`.
+    ///
+    /// The default is `true`.
+    pub fn reuseaddr(&mut self, reuseaddr: bool) -> &mut Self {
+        self.reuseaddr = reuseaddr;
+        self
+    }
+
+    /// Sets `SO_REUSEPORT`, which allows several listeners to bind the same address, with the
+    /// kernel spreading incoming connections between them.
+    ///
+    /// The default is `true`.
+    pub fn reuseport(&mut self, reuseport: bool) -> &mut Self {
+        self.reuseport = reuseport;
+        self
+    }
+
+    /// Sets `IPV6_V6ONLY`, which restricts a listener on an IPv6 address to IPv6 connections.
+    /// Ignored for IPv4 addresses.
+    ///
+    /// By default the system-wide setting applies, `net.ipv6.bindv6only`, which on Linux
+    /// usually accepts IPv4 connections too. Set it either way for the same behavior on any
+    /// system.
+    pub fn only_v6(&mut self, only_v6: bool) -> &mut Self {
+        self.only_v6 = Some(only_v6);
+        self
+    }
+
+    /// Sets the maximum number of pending connections, passed to `listen(2)`. The kernel caps
+    /// it at `net.core.somaxconn`.
+    ///
+    /// The default is 1024.
+    pub fn backlog(&mut self, backlog: u32) -> &mut Self {
+        self.backlog = backlog;
+        self
+    }
+
+    /// Enables TCP Fast Open, letting clients send data in their SYN, with up to `queue_len`
+    /// such connections completing their handshake at a time.
+    ///
+    /// See [`TcpSocket::set_fastopen`]. The default is to leave it disabled.
+    pub fn fastopen(&mut self, queue_len: u32) -> &mut Self {
+        self.fastopen = Some(queue_len);
+        self
+    }
+
+    /// Restricts the listener to connections arriving on the network interface named
+    /// `interface`, with `SO_BINDTODEVICE`.
+    ///
+    /// See [`TcpSocket::set_device`]. The default is to accept connections on any interface.
+    pub fn device(&mut self, interface: &str) -> &mut Self {
+        self.device = Some(interface.to_owned());
+        self
+    }
+
+    /// Sets the size of the send buffer, `SO_SNDBUF`, which accepted connections inherit.
+    ///
+    /// See [`TcpStream::set_send_buffer_size`]. The default is the kernel's.
+    pub fn send_buffer_size(&mut self, size: u32) -> &mut Self {
+        self.send_buffer_size = Some(size);
+        self
+    }
+
+    /// Sets the size of the receive buffer, `SO_RCVBUF`, which accepted connections inherit,
+    /// along with a window scale chosen to match it.
+    ///
+    /// See [`TcpStream::set_recv_buffer_size`]. The default is the kernel's.
+    pub fn recv_buffer_size(&mut self, size: u32) -> &mut Self {
+        self.recv_buffer_size = Some(size);
+        self
+    }
+
+    /// Sets `SO_INCOMING_CPU`, associating the listener with a CPU.
+    ///
+    /// Among listeners sharing an address without `SO_REUSEPORT`, the kernel prefers the
+    /// one associated with the CPU processing a SYN. Within a `SO_REUSEPORT` group, the
+    /// kernel picks by hash instead; use [`TcpListener::attach_cpu_steering`] to pick by CPU
+    /// there. The default is no association.
+    pub fn incoming_cpu(&mut self, cpu: u32) -> &mut Self {
+        self.incoming_cpu = Some(cpu);
+        self
+    }
+
+    /// Creates the socket, binds it to `addr` and starts listening.
+    pub fn bind(&self, addr: SocketAddr) -> io::Result<TcpListener> {
+        let socket = TcpSocket::new_for_addr(addr)?;
+        socket.set_reuseaddr(self.reuseaddr)?;
+        socket.set_reuseport(self.reuseport)?;
+        if let (Some(only_v6), SocketAddr::V6(_)) = (self.only_v6, addr) {
+            socket.set_only_v6(only_v6)?;
+        }
+        if let Some(interface) = &self.device {
+            socket.set_device(Some(interface))?;
+        }
+        if let Some(size) = self.send_buffer_size {
+            socket.set_send_buffer_size(size)?;
+        }
+        if let Some(size) = self.recv_buffer_size {
+            socket.set_recv_buffer_size(size)?;
+        }
+        if let Some(cpu) = self.incoming_cpu {
+            socket.set_incoming_cpu(cpu)?;
+        }
+        socket.bind(addr)?;
+        if let Some(queue_len) = self.fastopen {
+            socket.set_fastopen(queue_len)?;
+        }
+        socket.listen(self.backlog)
+    }
+}
@@ -1,5 +1,11 @@
+mod direct;
+pub use direct::DirectTcpStream;
+
 mod listener;
-pub use listener::TcpListener;
+pub use listener::{TcpListener, TcpListenerBuilder};
+
+mod socket;
+pub use socket::TcpSocket;
 
 mod stream;
-pub use stream::TcpStream;
+pub use stream::{ConnectError, KeepaliveConfig, TcpStream};
@@ -0,0 +1,246 @@
+use super::{KeepaliveConfig, TcpListener, TcpStream};
+use crate::buf::Buffer;
+use crate::io::Socket;
+use std::{
+    io,
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr},
+    os::unix::prelude::{AsRawFd, RawFd},
+};
+
+/// A TCP socket that has not yet been converted to a [`TcpStream`] or [`TcpListener`].
+///
+/// Some options only take effect when set before the socket connects or starts listening,
+/// such as the local address to bind to or the device to send through. `TcpSocket` holds a
+/// fresh socket for that purpose; [`connect`](TcpSocket::connect) and
+/// [`listen`](TcpSocket::listen) then consume it.
+///
+/// # Examples
+///
+/// Connecting from a chosen source address:
+///
+/// ```no_run
+/// use tokio_uring::net::TcpSocket;
+///
+/// tokio_uring::start(async {
+///     let socket = TcpSocket::new_v4().unwrap();
+///     socket.bind("127.0.0.1:4000".parse().unwrap()).unwrap();
+///
+///     let stream = socket.connect("127.0.0.1:8080".parse().unwrap()).await.unwrap();
+/// });
+/// ```
+pub struct TcpSocket {
+    inner: Socket,
+}
+
+impl TcpSocket {
+    /// Creates a new socket for IPv4 addresses.
+    pub fn new_v4() -> io::Result<TcpSocket> {
+        TcpSocket::new_for_addr((Ipv4Addr::UNSPECIFIED, 0).into())
+    }
+
+    /// Creates a new socket for IPv6 addresses.
+    pub fn new_v6() -> io::Result<TcpSocket> {
+        TcpSocket::new_for_addr((Ipv6Addr::UNSPECIFIED, 0).into())
+    }
+
+    /// Creates a new socket of the family of `addr`.
+    pub(crate) fn new_for_addr(addr: SocketAddr) -> io::Result<TcpSocket> {
+        let inner = Socket::new(addr, libc::SOCK_STREAM)?;
+        Ok(TcpSocket { inner })
+    }
+
+    /// Sets `SO_REUSEADDR` on the socket.
+    ///
+    /// For listeners, this allows binding while connections from a previous listener on the
+    /// same address linger in `TIME_WAIT`.
+    pub fn set_reuseaddr(&self, reuseaddr: bool) -> io::Result<()> {
+        self.sock_ref().set_reuse_address(reuseaddr)
+    }
+
+    /// Sets `SO_REUSEPORT` on the socket, allowing several sockets to bind the same address.
+    pub fn set_reuseport(&self, reuseport: bool) -> io::Result<()> {
+        self.sock_ref().set_reuse_port(reuseport)
+    }
+
+    /// Sets `IPV6_V6ONLY` on an IPv6 socket, restricting it to IPv6 traffic.
+    pub fn set_only_v6(&self, only_v6: bool) -> io::Result<()> {
+        self.sock_ref().set_only_v6(only_v6)
+    }
+
+    /// Sets `SO_BINDTODEVICE`, restricting the socket to the network interface named
+    /// `interface`, or lifts the restriction with `None`.
+    ///
+    /// This usually requires `CAP_NET_RAW`.
+    pub fn bind_device(&self, interface: Option<&[u8]>) -> io::Result<()> {
+        self.sock_ref().bind_device(interface)
+    }
+
+    /// Sets `SO_BINDTODEVICE`, restricting the socket to the network interface named
+    /// `interface`, or lifts the restriction with `None` or an empty name.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`PermissionDenied`](io::ErrorKind::PermissionDenied) without
+    /// `CAP_NET_RAW`.
+    pub fn set_device(&self, interface: Option<&str>) -> io::Result<()> {
+        self.inner.set_device(interface)
+    }
+
+    /// Returns the name of the interface the socket is restricted to with
+    /// [`set_device`](TcpSocket::set_device), if any.
+    pub fn device(&self) -> io::Result<Option<String>> {
+        self.inner.device()
+    }
+
+    /// Sets the value of the TCP_NODELAY option, which the resulting stream keeps.
+    ///
+    /// See [`TcpStream::set_nodelay`].
+    pub fn set_nodelay(&self, nodelay: bool) -> io::Result<()> {
+        self.inner.set_nodelay(nodelay)
+    }
+
+    /// Configures TCP keepalive, which the resulting stream keeps.
+    ///
+    /// See [`TcpStream::set_keepalive`].
+    pub fn set_keepalive(&self, keepalive: Option<KeepaliveConfig>) -> io::Result<()> {
+        self.inner.set_keepalive(keepalive)
+    }
+
+    /// Sets the size of the send buffer, `SO_SNDBUF`, which the resulting stream keeps.
+    ///
+    /// See [`TcpStream::set_send_buffer_size`].
+    pub fn set_send_buffer_size(&self, size: u32) -> io::Result<()> {
+        self.inner.set_send_buffer_size(size)
+    }
+
+    /// Gets the size of the send buffer, as the kernel doubled it.
+    ///
+    /// See [`TcpStream::send_buffer_size`].
+    pub fn send_buffer_size(&self) -> io::Result<u32> {
+        self.inner.send_buffer_size()
+    }
+
+    /// Sets the TOS byte, or IPv6 traffic class, of the socket's packets, so that the
+    /// handshake is marked too. See [`TcpStream::set_tos`].
+    pub fn set_tos(&self, tos: u8) -> io::Result<()> {
+        self.inner.set_tos(tos)
+    }
+
+    /// Gets the TOS byte, or IPv6 traffic class. See [`TcpStream::tos`].
+    pub fn tos(&self) -> io::Result<u8> {
+        self.inner.tos()
+    }
+
+    /// Sets the size of the receive buffer, `SO_RCVBUF`, which the resulting stream keeps.
+    ///
+    /// The TCP window scale is chosen from it during the handshake, so a large receive
+    /// buffer only takes full effect when set before [`connect`](TcpSocket::connect) or
+    /// [`listen`](TcpSocket::listen).
+    pub fn set_recv_buffer_size(&self, size: u32) -> io::Result<()> {
+        self.inner.set_recv_buffer_size(size)
+    }
+
+    /// Gets the size of the receive buffer, as the kernel doubled it.
+    ///
+    /// See [`TcpStream::recv_buffer_size`].
+    pub fn recv_buffer_size(&self) -> io::Result<u32> {
+        self.inner.recv_buffer_size()
+    }
+
+    /// Sets `SO_INCOMING_CPU`, the CPU the socket is associated with.
+    ///
+    /// See [`TcpListenerBuilder::incoming_cpu`](crate::net::TcpListenerBuilder::incoming_cpu).
+    pub fn set_incoming_cpu(&self, cpu: u32) -> io::Result<()> {
+        self.inner.set_incoming_cpu(cpu)
+    }
+
+    /// Enables TCP Fast Open on a socket about to [`listen`](TcpSocket::listen), so that
+    /// clients may send data in their SYN. `queue_len` bounds the number of such connections
+    /// still completing their handshake; beyond it, SYNs are handled the usual way.
+    ///
+    /// The server side must also be enabled in the `net.ipv4.tcp_fastopen` sysctl, or data
+    /// in SYNs is ignored and sent again by the client once connected.
+    pub fn set_fastopen(&self, queue_len: u32) -> io::Result<()> {
+        self.inner.set_fastopen(queue_len)
+    }
+
+    /// Binds the socket to the given local address.
+    ///
+    /// Before [`connect`](TcpSocket::connect), this chooses the source address and port of
+    /// the connection.
+    pub fn bind(&self, addr: SocketAddr) -> io::Result<()> {
+        self.sock_ref().bind(&addr.into())
+    }
+
+    /// Returns the local address the socket is bound to.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.sock_ref()
+            .local_addr()?
+            .as_socket()
+            .ok_or_else(|| io::Error::other("Could not get socket IP address"))
+    }
+
+    /// Connects the socket to `addr`, turning it into a [`TcpStream`].
+    pub async fn connect(self, addr: SocketAddr) -> io::Result<TcpStream> {
+        self.inner.connect(addr.into()).await?;
+        Ok(TcpStream::from_socket(self.inner))
+    }
+
+    /// Connects the socket to `addr` with TCP Fast Open, sending the initialized contents of
+    /// `buf` with the SYN, and turns it into a [`TcpStream`].
+    ///
+    /// Fast open saves a round trip on short request/response connections. The server must
+    /// enable it, and the kernel must hold a cookie from an earlier connection to it; the
+    /// first connection only fetches one. Whenever fast open does not happen, because of that,
+    /// because the `net.ipv4.tcp_fastopen` sysctl disables it for clients, or because the
+    /// server declines the data, this falls back to connecting then sending.
+    ///
+    /// Either way, `buf` is sent in full. On success, returns the stream and the number of
+    /// bytes the server accepted with the SYN, which is 0 after a fallback, along with `buf`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use tokio_uring::net::TcpSocket;
+    ///
+    /// tokio_uring::start(async {
+    ///     let socket = TcpSocket::new_v4().unwrap();
+    ///     let request = b"GET / HTTP/1.0\r\n\r\n".to_vec();
+    ///     let ((stream, in_syn), _) = socket
+    ///         .connect_with_data("127.0.0.1:8080".parse().unwrap(), request.into())
+    ///         .await
+    ///         .unwrap();
+    ///     if in_syn == 0 {
+    ///         println!("fast open did not happen");
+    ///     }
+    /// });
+    /// ```
+    pub async fn connect_with_data(
+        self,
+        addr: SocketAddr,
+        buf: Buffer,
+    ) -> crate::Result<(TcpStream, usize), Buffer> {
+        let (in_syn, buf) = self.inner.connect_with_data(addr, buf).await?;
+        Ok(((TcpStream::from_socket(self.inner), in_syn), buf))
+    }
+
+    /// Starts listening on the socket, turning it into a [`TcpListener`].
+    ///
+    /// `backlog` is the maximum number of pending connections; the kernel caps it at
+    /// `net.core.somaxconn`.
+    pub fn listen(self, backlog: u32) -> io::Result<TcpListener> {
+        self.inner
+            .listen(backlog.min(libc::c_int::MAX as u32) as libc::c_int)?;
+        Ok(TcpListener::from_socket(self.inner))
+    }
+
+    fn sock_ref(&self) -> socket2::SockRef<'_> {
+        socket2::SockRef::from(&self.inner)
+    }
+}
+
+impl AsRawFd for TcpSocket {
+    fn as_raw_fd(&self) -> RawFd {
+        self.inner.as_raw_fd()
+    }
+}
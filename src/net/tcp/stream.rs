@@ -1,15 +1,51 @@
 use std::{
+    fmt,
+    future::Future,
     io,
-    net::SocketAddr,
+    net::{IpAddr, SocketAddr, ToSocketAddrs},
     os::unix::prelude::{AsRawFd, FromRawFd, RawFd},
+    time::Duration,
 };
 
+use futures_core::Stream;
+
 use crate::{
-    buf::{BoundedBuf, Buffer},
-    io::{SharedFd, Socket},
-    Submit, Unsubmitted,
+    buf::{bufring::BufRing, BoundedBuf, BoundedBufMut, Buffer, IoBuf},
+    io::{Interest, Ready, RecvStream, SelectedRead, SharedFd, Socket},
+    net::{split, Compat, OwnedReadHalf, OwnedWriteHalf},
+    Submit, Unsubmitted, UnsubmittedRecv, UnsubmittedSend,
 };
 
+/// The error from [`TcpStream::connect_to`] when no address accepted a connection.
+///
+/// It is wrapped in the [`io::Error`] returned; get it back with
+/// [`get_ref`](io::Error::get_ref) and
+/// [`downcast_ref`](std::error::Error#method.downcast_ref).
+#[derive(Debug)]
+pub struct ConnectError {
+    attempts: Vec<(SocketAddr, io::Error)>,
+}
+
+impl ConnectError {
+    /// Returns each address tried, in order, with the error connecting to it.
+    pub fn attempts(&self) -> &[(SocketAddr, io::Error)] {
+        &self.attempts
+    }
+}
+
+impl fmt::Display for ConnectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("could not connect to any address")?;
+        for (i, (addr, e)) in self.attempts.iter().enumerate() {
+            let sep = if i == 0 { ": " } else { "; " };
+            write!(f, "{}{}: {}", sep, addr, e)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConnectError {}
+
 /// A TCP stream between a local and a remote socket.
 ///
 /// A TCP stream can either be created by connecting to an endpoint, via the
@@ -39,7 +75,7 @@ use crate::{
 /// [`accepting`]: crate::net::TcpListener::accept
 /// [`listener`]: crate::net::TcpListener
 pub struct TcpStream {
-    pub(super) inner: Socket,
+    pub(crate) inner: Socket,
 }
 
 impl TcpStream {
@@ -51,12 +87,130 @@ impl TcpStream {
         Ok(tcp_stream)
     }
 
+    /// Opens a TCP connection to a remote host at the given `SocketAddr`, giving up after
+    /// `timeout`.
+    ///
+    /// The timeout is linked to the connect operation in the ring, so the kernel cancels the
+    /// connect itself when it expires instead of leaving it in flight until its SYN retries
+    /// run out. An expired timeout is reported as [`io::ErrorKind::TimedOut`].
+    pub async fn connect_timeout(addr: SocketAddr, timeout: Duration) -> io::Result<TcpStream> {
+        let socket = Socket::new(addr, libc::SOCK_STREAM)?;
+        socket
+            .connect_timeout(socket2::SockAddr::from(addr), timeout)
+            .await?;
+        Ok(TcpStream { inner: socket })
+    }
+
+    /// Opens a TCP connection to the first of `addrs` that accepts one, trying them in turn.
+    ///
+    /// This is the loop behind connecting to a host name, which
+    /// [`connect_host`](Self::connect_host) runs on the addresses it resolves; call it directly
+    /// with addresses from elsewhere, in order of preference. Each attempt uses a fresh socket of the address's family, which
+    /// is closed before the next attempt starts. With a `timeout`, each attempt gives up after
+    /// that long, as with [`connect_timeout`](Self::connect_timeout).
+    ///
+    /// # Errors
+    ///
+    /// When every attempt fails, the error carries a [`ConnectError`] listing each address
+    /// and why connecting to it failed, and has the kind of the last failure. Without any
+    /// address, the error is of kind [`InvalidInput`](io::ErrorKind::InvalidInput).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::net::ToSocketAddrs;
+    /// use std::time::Duration;
+    /// use tokio_uring::net::TcpStream;
+    ///
+    /// tokio_uring::start(async {
+    ///     let addrs = "example.com:80".to_socket_addrs().unwrap();
+    ///     let stream = TcpStream::connect_to(addrs, Some(Duration::from_secs(2)))
+    ///         .await
+    ///         .unwrap();
+    /// });
+    /// ```
+    ///
+    /// [`ConnectError`]: crate::net::ConnectError
+    pub async fn connect_to(
+        addrs: impl IntoIterator<Item = SocketAddr>,
+        timeout: Option<Duration>,
+    ) -> io::Result<TcpStream> {
+        let mut attempts = Vec::new();
+        for addr in addrs {
+            let mut socket = match Socket::new(addr, libc::SOCK_STREAM) {
+                Ok(socket) => socket,
+                Err(e) => {
+                    attempts.push((addr, e));
+                    continue;
+                }
+            };
+            let res = match timeout {
+                Some(timeout) => socket.connect_timeout(addr.into(), timeout).await,
+                None => socket.connect(addr.into()).await,
+            };
+            match res {
+                Ok(()) => return Ok(TcpStream { inner: socket }),
+                Err(e) => {
+                    let _ = socket.fd.close().await;
+                    attempts.push((addr, e));
+                }
+            }
+        }
+
+        match attempts.last() {
+            Some((_, e)) => Err(io::Error::new(e.kind(), ConnectError { attempts })),
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "no addresses to connect to",
+            )),
+        }
+    }
+
+    /// Opens a TCP connection to `host`, a host name or a literal IP address, on `port`.
+    ///
+    /// Host names are resolved with `getaddrinfo(3)` on a blocking thread, so that a slow
+    /// resolver does not hold up the ring. The addresses are then tried in the order the
+    /// resolver returned them, as by [`connect_to`](Self::connect_to), which applies its
+    /// preferences, such as those of `/etc/gai.conf`. Literal addresses are connected to
+    /// directly, without a lookup.
+    ///
+    /// # Errors
+    ///
+    /// Fails with the resolver's error if the name does not resolve, and otherwise as
+    /// [`connect_to`](Self::connect_to) does.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use tokio_uring::net::TcpStream;
+    ///
+    /// tokio_uring::start(async {
+    ///     let stream = TcpStream::connect_host("example.com", 80).await.unwrap();
+    /// });
+    /// ```
+    pub async fn connect_host(host: &str, port: u16) -> io::Result<TcpStream> {
+        if let Ok(ip) = host.parse::<IpAddr>() {
+            return TcpStream::connect(SocketAddr::new(ip, port)).await;
+        }
+
+        let host = host.to_string();
+        let addrs = tokio::task::spawn_blocking(move || {
+            (host.as_str(), port)
+                .to_socket_addrs()
+                .map(|addrs| addrs.collect::<Vec<_>>())
+        })
+        .await
+        .map_err(io::Error::other)??;
+        TcpStream::connect_to(addrs, None).await
+    }
+
     /// Creates new `TcpStream` from a previously bound `std::net::TcpStream`.
     ///
     /// This function is intended to be used to wrap a TCP stream from the
-    /// standard library in the tokio-uring equivalent. The conversion assumes nothing
-    /// about the underlying socket; it is left up to the user to decide what socket
-    /// options are appropriate for their use case.
+    /// standard library in the tokio-uring equivalent. The socket is switched to blocking
+    /// mode, as io_uring waits for readiness itself; beyond that, the conversion assumes
+    /// nothing about the underlying socket, and it is left up to the user to decide what
+    /// socket options are appropriate for their use case.
     ///
     /// This can be used in conjunction with socket2's `Socket` interface to
     /// configure a socket before it's handed off, such as setting options like
@@ -66,13 +220,210 @@ impl TcpStream {
         Self { inner }
     }
 
+    /// Converts the stream into a [`std::net::TcpStream`], transferring ownership of the socket.
+    ///
+    /// The returned socket is in blocking mode; call `set_nonblocking(true)` on it before
+    /// handing it to a readiness-based runtime such as tokio.
+    ///
+    /// # Errors
+    ///
+    /// Fails while operations on the socket are still in flight, such as one whose future was
+    /// dropped before it completed. The socket is then closed once they finish.
+    pub fn into_std(self) -> io::Result<std::net::TcpStream> {
+        self.inner.into_std()
+    }
+
+    /// Adapts the stream to tokio's [`AsyncRead`] and [`AsyncWrite`] traits.
+    ///
+    /// See [`Compat`] for how data is buffered, and what happens on cancellation.
+    ///
+    /// [`AsyncRead`]: tokio::io::AsyncRead
+    /// [`AsyncWrite`]: tokio::io::AsyncWrite
+    pub fn into_compat(self) -> Compat<TcpStream> {
+        let fd = self.inner.fd.clone();
+        Compat::new(self, &fd)
+    }
+
+    /// Splits the stream into a read half and a write half, which can be used from
+    /// different tasks at once.
+    ///
+    /// The halves share the socket, which is closed once both are dropped; put them back
+    /// together with [`OwnedReadHalf::reunite`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use tokio_uring::net::TcpStream;
+    ///
+    /// tokio_uring::start(async {
+    ///     let stream = TcpStream::connect("127.0.0.1:8080".parse().unwrap()).await.unwrap();
+    ///     let (reader, writer) = stream.into_split();
+    ///
+    ///     let echo = tokio_uring::spawn(async move {
+    ///         let (n, buf) = reader.read(vec![0; 4096].into()).await.unwrap();
+    ///         (reader, n, buf)
+    ///     });
+    ///     writer.write_all(b"hello".to_vec().into()).await.unwrap();
+    ///
+    ///     let (reader, _n, _buf) = echo.await.unwrap();
+    ///     let stream = reader.reunite(writer).unwrap();
+    /// });
+    /// ```
+    pub fn into_split(self) -> (OwnedReadHalf<TcpStream>, OwnedWriteHalf<TcpStream>) {
+        split::split(self)
+    }
+
     pub(crate) fn from_socket(inner: Socket) -> Self {
         Self { inner }
     }
 
+    /// Returns the local address that this stream is bound to.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.with_std(|s| s.local_addr())
+    }
+
+    /// Returns the address of the remote peer this stream is connected to.
+    ///
+    /// As with [`TcpListener::accept`](crate::net::TcpListener::accept), an IPv4 peer of an IPv6 socket is reported in its
+    /// IPv4-mapped form, which [`to_canonical`](crate::net::to_canonical) converts.
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.with_std(|s| s.peer_addr())
+    }
+
+    fn with_std<R>(&self, f: impl FnOnce(&std::net::TcpStream) -> R) -> R {
+        // SAFETY: Our fd is the handle the kernel has given us for a TcpStream.
+        // Create a std::net::TcpStream long enough to call the method
+        // and then forget it so the socket is not closed here.
+        let s = unsafe { std::net::TcpStream::from_raw_fd(self.inner.as_raw_fd()) };
+        let res = f(&s);
+        std::mem::forget(s);
+        res
+    }
+
+    /// Receives data as it arrives, into buffers the kernel selects from `group`.
+    ///
+    /// Unlike [`read`](Self::read), no buffer is tied up while the connection is idle: a
+    /// single multishot recv stays in flight and takes a buffer from the group only once data
+    /// has arrived. Each item is a buffer holding the data received and its length; dropping
+    /// the buffer returns it to the group. The stream ends when the peer closes the
+    /// connection.
+    ///
+    /// When the group runs out of buffers, the stream waits for one to be returned and then
+    /// resumes; no data is lost. Dropping the stream cancels the operation and returns any
+    /// buffers filled but not yet yielded.
+    ///
+    /// Requires Linux 6.0 or later.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use futures_util::StreamExt;
+    /// use tokio_uring::buf::bufring::BufRing;
+    /// use tokio_uring::net::TcpStream;
+    ///
+    /// tokio_uring::start(async {
+    ///     let group = BufRing::register(64, 4096, 0).unwrap();
+    ///     let stream = TcpStream::connect("127.0.0.1:8080".parse().unwrap()).await.unwrap();
+    ///
+    ///     let mut incoming = stream.recv_multi(&group);
+    ///     while let Some(res) = incoming.next().await {
+    ///         let (buf, n) = res.unwrap();
+    ///         println!("received {:?}", &buf[0][..n]);
+    ///     }
+    /// });
+    /// ```
+    pub fn recv_multi(&self, group: &BufRing) -> impl Stream<Item = io::Result<(Buffer, usize)>> {
+        RecvStream::new(&self.inner.fd, group)
+    }
+
+    /// Reads some data from the stream into a buffer the kernel selects from `group`,
+    /// returning `None` once the peer has closed the connection.
+    ///
+    /// Like [`recv_multi`](Self::recv_multi), but for a single read: the kernel takes a buffer
+    /// only once data is ready, so a read waiting on an idle connection ties none up. The
+    /// returned buffer holds the data read, and goes back to the group once dropped.
+    ///
+    /// When the group has no buffer left, the read waits for one to be returned and tries
+    /// again. Dropping the future cancels the read, returning a buffer it may have filled.
+    ///
+    /// Requires Linux 5.19 or later.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use tokio_uring::buf::{bufring::BufRing, IoBuf};
+    /// use tokio_uring::net::TcpStream;
+    ///
+    /// tokio_uring::start(async {
+    ///     let group = BufRing::register(64, 4096, 0).unwrap();
+    ///     let stream = TcpStream::connect("127.0.0.1:8080".parse().unwrap()).await.unwrap();
+    ///
+    ///     while let Some(buf) = stream.read_select(&group).await.unwrap() {
+    ///         println!("read {} bytes", buf.bytes_init());
+    ///     }
+    /// });
+    /// ```
+    pub fn read_select(&self, group: &BufRing) -> impl Future<Output = io::Result<Option<Buffer>>> {
+        SelectedRead::new(&self.inner.fd, group)
+    }
+
+    /// Receives some data from the stream into the buffer, with `MSG_*` flags set on the
+    /// returned [`UnsubmittedRecv`] before it is submitted.
+    ///
+    /// # Examples
+    ///
+    /// Receiving a fixed-length frame header in one operation:
+    ///
+    /// ```no_run
+    /// use tokio_uring::net::TcpStream;
+    /// use tokio_uring::Submit;
+    ///
+    /// tokio_uring::start(async {
+    ///     let stream = TcpStream::connect("127.0.0.1:8080".parse().unwrap()).await.unwrap();
+    ///
+    ///     let buf = Vec::<u8>::with_capacity(16).into();
+    ///     let (n, header) = stream.recv(buf).waitall().submit().await.unwrap();
+    ///     assert!(n == 16 || n == 0 /* closed */);
+    /// });
+    /// ```
+    pub fn recv(&self, buf: Buffer) -> UnsubmittedRecv {
+        UnsubmittedRecv::new(&self.inner.fd, buf, false)
+    }
+
+    /// Receives data into the buffer without removing it from the socket, so that the next
+    /// read returns the same bytes.
+    ///
+    /// This is a receive with `MSG_PEEK`, as set by [`recv`](Self::recv) with
+    /// [`peek`](UnsubmittedRecv::peek). It waits for data like a read, and may return fewer
+    /// bytes than are on their way; peek again to see more. Returns 0 once the peer closed
+    /// the connection, or at once if the buffer has no capacity.
+    ///
+    /// # Examples
+    ///
+    /// Detecting a TLS handshake, whose first byte is 0x16:
+    ///
+    /// ```no_run
+    /// use tokio_uring::net::TcpListener;
+    ///
+    /// tokio_uring::start(async {
+    ///     let listener = TcpListener::bind("127.0.0.1:8443".parse().unwrap()).unwrap();
+    ///     let (stream, _) = listener.accept().await.unwrap();
+    ///
+    ///     let (n, buf) = stream.peek(vec![0; 1].into()).await.unwrap();
+    ///     let is_tls = n == 1 && buf[0][0] == 0x16;
+    /// });
+    /// ```
+    pub async fn peek(&self, buf: Buffer) -> crate::Result<usize, Buffer> {
+        if IoBuf::bytes_total(&buf) == 0 {
+            return Ok((0, buf));
+        }
+        self.recv(buf).peek().submit().await
+    }
+
     /// Read some data from the stream into the buffer.
     ///
-    /// Returns the original buffer and quantity of data read.
+    /// Returns the original buffer and quantity of data read. A buffer with no capacity is
+    /// handed back at once with 0, without a read reaching the kernel.
     pub async fn read(&self, buf: Buffer) -> crate::Result<usize, Buffer> {
         self.inner.read(buf).await
     }
@@ -90,17 +441,65 @@ impl TcpStream {
     /// In addition to errors that can be reported by `read`,
     /// this operation fails if the buffer is not registered in the
     /// current `tokio-uring` runtime.
-    pub async fn read_fixed(&self, buf: Buffer) -> crate::Result<usize, Buffer> {
+    pub async fn read_fixed<T>(&self, buf: T) -> crate::Result<usize, T>
+    where
+        T: BoundedBufMut<BufMut = Buffer>,
+    {
         self.inner.read_fixed(buf).await
     }
 
     /// Write some data to the stream from the buffer.
     ///
-    /// Returns the original buffer and quantity of data written.
+    /// Returns the original buffer and quantity of data written. An empty buffer is handed
+    /// back at once with 0, without a write reaching the kernel.
     pub fn write(&self, buf: Buffer) -> Unsubmitted {
         self.inner.write(buf)
     }
 
+    /// Sends the initialized bytes of the buffer, with `MSG_*` flags set on the returned
+    /// [`UnsubmittedSend`] before it is submitted.
+    ///
+    /// See [`UnsubmittedSend::more`] to coalesce a small write with the next one.
+    pub fn send(&self, buf: Buffer) -> UnsubmittedSend {
+        UnsubmittedSend::new(&self.inner.fd, buf)
+    }
+
+    /// Write some data to the stream from the buffer, without copying it into the kernel.
+    ///
+    /// Returns the original buffer and quantity of data written, like [`write`]. The kernel
+    /// sends straight from the buffer's pages, so it keeps referencing them after the data is
+    /// queued; this completes only once the kernel has signalled it is done with the buffer,
+    /// which can take until the peer acknowledges the data.
+    ///
+    /// Zero-copy pays off for large writes, as pinning pages costs more than copying a few
+    /// kilobytes. On kernels without `IORING_OP_SEND_ZC` (before 6.0), this is a plain write.
+    ///
+    /// # Errors
+    ///
+    /// The buffer must have a single segment; other buffers are returned with an
+    /// [`InvalidInput`](io::ErrorKind::InvalidInput) error. Use [`writev_zc`] for those.
+    ///
+    /// [`write`]: Self::write
+    /// [`writev_zc`]: Self::writev_zc
+    pub async fn write_zc(&self, buf: Buffer) -> crate::Result<usize, Buffer> {
+        self.inner.write_zc(buf).await
+    }
+
+    /// Write every segment of the buffer to the stream, without copying or concatenating them.
+    ///
+    /// The vectored counterpart of [`write_zc`], sending with a single `sendmsg`; the same
+    /// rules apply, so this completes only once the kernel no longer references the buffer.
+    /// On kernels without `IORING_OP_SENDMSG_ZC` (before 6.1), this is a plain vectored write.
+    ///
+    /// Returns the original buffer and the number of bytes written, which may fall short of
+    /// the buffer's initialized length. [`Buffer::position`] locates where the unsent data
+    /// starts.
+    ///
+    /// [`write_zc`]: Self::write_zc
+    pub async fn writev_zc(&self, buf: Buffer) -> crate::Result<usize, Buffer> {
+        self.inner.writev_zc(buf).await
+    }
+
     /// Writes data into the socket from a registered buffer.
     ///
     /// Like [`write`], but using a pre-mapped buffer
@@ -114,6 +513,10 @@ impl TcpStream {
     /// In addition to errors that can be reported by `write`,
     /// this operation fails if the buffer is not registered in the
     /// current `tokio-uring` runtime.
+    ///
+    /// Registered buffers are written with `IORING_OP_WRITE_FIXED`, which, unlike the sends
+    /// behind `write`, cannot suppress `SIGPIPE` when the peer closed the connection. Ignore
+    /// the signal, as Rust programs do by default, before writing to peers that may go away.
     pub async fn write_fixed<T>(&self, buf: T) -> crate::Result<usize, T>
     where
         T: BoundedBuf<Buf = Buffer>,
@@ -141,6 +544,34 @@ impl TcpStream {
         self.inner.write_fixed_all(buf).await
     }
 
+    /// Reads from the stream until the whole capacity of the buffer is filled.
+    ///
+    /// Short reads are continued where they left off, across the segments of the buffer,
+    /// until it is full.
+    ///
+    /// # Errors
+    ///
+    /// If the peer closes the connection first, this fails with
+    /// [`UnexpectedEof`](std::io::ErrorKind::UnexpectedEof). On this and any other error, the
+    /// returned buffer holds the bytes read before it.
+    pub async fn read_exact(&self, buf: Buffer) -> crate::Result<(), Buffer> {
+        self.inner.read_exact(buf).await
+    }
+
+    /// Writes every initialized byte of the buffer to the stream.
+    ///
+    /// Short writes are continued where they left off, across the segments of the buffer,
+    /// until everything has been sent.
+    ///
+    /// # Errors
+    ///
+    /// This function returns the first error a write returns, or
+    /// [`WriteZero`](std::io::ErrorKind::WriteZero) if a write makes no progress. The buffer
+    /// is returned in either case, but how much of it was sent is unknown.
+    pub async fn write_all(&self, buf: Buffer) -> crate::Result<(), Buffer> {
+        self.inner.write_all(buf).await
+    }
+
     /// Writes data from multiple buffers into this socket using the scatter/gather IO style.
     ///
     /// This function will attempt to write the entire contents of `bufs`, but
@@ -168,6 +599,51 @@ impl TcpStream {
         self.inner.write(buf).submit().await
     }
 
+    /// Waits until the stream is readable: data is waiting, or the peer closed its write half.
+    ///
+    /// Nothing is read. This is for code that does its own reads once the socket is ready,
+    /// such as a C library handed the descriptor, or a `recv` with `MSG_DONTWAIT`. Readiness
+    /// can be spurious, so a read may still find nothing.
+    ///
+    /// The wait is an io_uring poll (`IORING_OP_POLL_ADD`), cancelled if the future is
+    /// dropped before it completes.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::os::unix::io::AsRawFd;
+    /// use tokio_uring::net::TcpStream;
+    ///
+    /// tokio_uring::start(async {
+    ///     let stream = TcpStream::connect("127.0.0.1:8080".parse().unwrap()).await.unwrap();
+    ///     stream.readable().await.unwrap();
+    ///
+    ///     let mut buf = [0u8; 1024];
+    ///     let n = unsafe {
+    ///         libc::recv(stream.as_raw_fd(), buf.as_mut_ptr().cast(), buf.len(), libc::MSG_DONTWAIT)
+    ///     };
+    ///     println!("read {}", n);
+    /// });
+    /// ```
+    pub async fn readable(&self) -> io::Result<()> {
+        self.inner.ready(Interest::READABLE).await.map(|_| ())
+    }
+
+    /// Waits until the stream is writable, with room in its send buffer.
+    ///
+    /// See [`readable`](Self::readable).
+    pub async fn writable(&self) -> io::Result<()> {
+        self.inner.ready(Interest::WRITABLE).await.map(|_| ())
+    }
+
+    /// Waits until any of the events in `interest` fire, and returns those that did, along
+    /// with errors and hang-ups, which are reported whatever the interest.
+    ///
+    /// See [`readable`](Self::readable).
+    pub async fn ready(&self, interest: Interest) -> io::Result<Ready> {
+        self.inner.ready(interest).await
+    }
+
     /// Shuts down the read, write, or both halves of this connection.
     ///
     /// This function will cause all pending and future I/O on the specified portions to return
@@ -176,15 +652,215 @@ impl TcpStream {
         self.inner.shutdown(how)
     }
 
+    /// Returns and clears the error pending on the socket (`SO_ERROR`), if any.
+    ///
+    /// The kernel records asynchronous failures there, such as a refused connection or a
+    /// reset, and otherwise reports them on the next operation. Checking it after
+    /// [`writable`](Self::writable) completes is how a non-blocking connect learns whether it
+    /// succeeded.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use tokio_uring::net::TcpStream;
+    ///
+    /// tokio_uring::start(async {
+    ///     let socket = socket2::Socket::new(socket2::Domain::IPV4, socket2::Type::STREAM, None)?;
+    ///     socket.set_nonblocking(true)?;
+    ///     let addr: std::net::SocketAddr = "127.0.0.1:8080".parse().unwrap();
+    ///     match socket.connect(&addr.into()) {
+    ///         Err(e) if e.raw_os_error() == Some(libc::EINPROGRESS) => {}
+    ///         res => res?,
+    ///     }
+    ///
+    ///     let stream = TcpStream::from_std(socket.into());
+    ///     stream.writable().await?;
+    ///     if let Some(e) = stream.take_error()? {
+    ///         return Err(e);
+    ///     }
+    ///     Ok::<(), std::io::Error>(())
+    /// })
+    /// .unwrap();
+    /// ```
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        self.inner.take_error()
+    }
+
     /// Sets the value of the TCP_NODELAY option on this socket.
     ///
     /// If set, this option disables the Nagle algorithm. This means that segments are always sent
     /// as soon as possible, even if there is only a small amount of data. When not set, data is
     /// buffered until there is a sufficient amount to send out, thereby avoiding the frequent
     /// sending of small packets.
+    ///
+    /// Data held back on purpose, by [`set_cork`](TcpStream::set_cork) or
+    /// [`UnsubmittedSend::more`], is held back with this option set too.
     pub fn set_nodelay(&self, nodelay: bool) -> io::Result<()> {
         self.inner.set_nodelay(nodelay)
     }
+
+    /// Gets the value of the TCP_NODELAY option on this socket.
+    ///
+    /// For more information about this option, see [`set_nodelay`].
+    ///
+    /// [`set_nodelay`]: TcpStream::set_nodelay
+    pub fn nodelay(&self) -> io::Result<bool> {
+        self.inner.nodelay()
+    }
+
+    /// Sets the value of the TCP_CORK option on this socket.
+    ///
+    /// While corked, the kernel only sends full segments, holding back a partial one until
+    /// more data fills it or the socket is uncorked; after 200ms it is sent anyway. This
+    /// coalesces a series of small writes, such as a header and a body, without copying them
+    /// together first. Uncorking with `set_cork(false)` sends whatever is held back at once,
+    /// so it doubles as a flush.
+    ///
+    /// Corking takes precedence over [`set_nodelay`]; once uncorked, a stream with
+    /// `TCP_NODELAY` set sends without waiting again. For a single write, see
+    /// [`UnsubmittedSend::more`].
+    ///
+    /// [`set_nodelay`]: TcpStream::set_nodelay
+    pub fn set_cork(&self, cork: bool) -> io::Result<()> {
+        self.inner.set_cork(cork)
+    }
+
+    /// Gets the value of the TCP_CORK option on this socket.
+    pub fn cork(&self) -> io::Result<bool> {
+        self.inner.cork()
+    }
+
+    /// Sets the value of the IP_TTL option on this socket.
+    ///
+    /// This value sets the time-to-live field that is used in every packet sent from this
+    /// socket.
+    pub fn set_ttl(&self, ttl: u32) -> io::Result<()> {
+        self.inner.set_ttl(ttl)
+    }
+
+    /// Gets the value of the IP_TTL option on this socket.
+    ///
+    /// For more information about this option, see [`set_ttl`].
+    ///
+    /// [`set_ttl`]: TcpStream::set_ttl
+    pub fn ttl(&self) -> io::Result<u32> {
+        self.inner.ttl()
+    }
+
+    /// Sets the TOS byte of the IP header of outgoing packets, which carries the DSCP mark in
+    /// its upper six bits, so `0xb8` marks Expedited Forwarding.
+    ///
+    /// This is `IP_TOS` on IPv4 sockets and `IPV6_TCLASS` on IPv6 ones. An IPv6 socket that
+    /// also carries IPv4 traffic, see [`TcpListenerBuilder::only_v6`], gets both. The kernel
+    /// manages the two low ECN bits itself.
+    ///
+    /// [`TcpListenerBuilder::only_v6`]: crate::net::TcpListenerBuilder::only_v6
+    pub fn set_tos(&self, tos: u8) -> io::Result<()> {
+        self.inner.set_tos(tos)
+    }
+
+    /// Gets the TOS byte, or the traffic class of an IPv6 socket, as set by
+    /// [`set_tos`](TcpStream::set_tos).
+    pub fn tos(&self) -> io::Result<u8> {
+        self.inner.tos()
+    }
+
+    /// Sets the size of the send buffer, `SO_SNDBUF`.
+    ///
+    /// Shrinking it makes writes wait for the peer sooner; growing it keeps more data in
+    /// flight on links with a large bandwidth-delay product. Setting it turns off the
+    /// kernel's automatic sizing of the buffer, and the kernel caps it at
+    /// `net.core.wmem_max`.
+    pub fn set_send_buffer_size(&self, size: u32) -> io::Result<()> {
+        self.inner.set_send_buffer_size(size)
+    }
+
+    /// Gets the size of the send buffer.
+    ///
+    /// Linux doubles the size passed to [`set_send_buffer_size`] to make room for its own
+    /// bookkeeping, and reports the doubled value here, so it is not the value that was set.
+    ///
+    /// [`set_send_buffer_size`]: TcpStream::set_send_buffer_size
+    pub fn send_buffer_size(&self) -> io::Result<u32> {
+        self.inner.send_buffer_size()
+    }
+
+    /// Sets the size of the receive buffer, `SO_RCVBUF`, capped by the kernel at
+    /// `net.core.rmem_max`.
+    ///
+    /// The window scale of a connection is fixed during its handshake, so growing the buffer
+    /// past 64 KiB afterwards may not enlarge the window; set it on a [`TcpSocket`] or
+    /// [`TcpListenerBuilder`] instead.
+    ///
+    /// [`TcpSocket`]: crate::net::TcpSocket
+    /// [`TcpListenerBuilder`]: crate::net::TcpListenerBuilder
+    pub fn set_recv_buffer_size(&self, size: u32) -> io::Result<()> {
+        self.inner.set_recv_buffer_size(size)
+    }
+
+    /// Gets the size of the receive buffer, which, as for
+    /// [`send_buffer_size`](TcpStream::send_buffer_size), is double the size that was set.
+    pub fn recv_buffer_size(&self) -> io::Result<u32> {
+        self.inner.recv_buffer_size()
+    }
+
+    /// Gets `SO_INCOMING_CPU`: the CPU on which the kernel last processed packets for the
+    /// connection, or `None` if it has not yet.
+    ///
+    /// A server running a runtime per core can check it after accepting, to hand the
+    /// connection to the runtime on that core and keep its data in that core's caches.
+    pub fn incoming_cpu(&self) -> io::Result<Option<u32>> {
+        self.inner.incoming_cpu()
+    }
+
+    /// Sets `SO_INCOMING_CPU`. The kernel overwrites it as packets for the connection are
+    /// processed, so it mostly matters on listening sockets; see
+    /// [`TcpListenerBuilder::incoming_cpu`](crate::net::TcpListenerBuilder::incoming_cpu).
+    pub fn set_incoming_cpu(&self, cpu: u32) -> io::Result<()> {
+        self.inner.set_incoming_cpu(cpu)
+    }
+
+    /// Enables TCP keepalive with the given parameters, or disables it with `None`.
+    ///
+    /// With keepalive enabled, the kernel probes a connection that has been idle for
+    /// `config.time`, and drops it once `config.retries` probes sent `config.interval` apart go
+    /// unanswered. This detects peers that vanished without closing the connection.
+    pub fn set_keepalive(&self, keepalive: Option<KeepaliveConfig>) -> io::Result<()> {
+        self.inner.set_keepalive(keepalive)
+    }
+
+    /// Gets the keepalive parameters of this socket, or `None` if keepalive is disabled.
+    ///
+    /// For more information, see [`set_keepalive`].
+    ///
+    /// [`set_keepalive`]: TcpStream::set_keepalive
+    pub fn keepalive(&self) -> io::Result<Option<KeepaliveConfig>> {
+        self.inner.keepalive()
+    }
+}
+
+/// TCP keepalive parameters, for [`TcpStream::set_keepalive`].
+///
+/// The default matches the usual Linux defaults: probing after two hours idle, every 75
+/// seconds, giving up after 9 probes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeepaliveConfig {
+    /// How long the connection must be idle before the first probe (`TCP_KEEPIDLE`).
+    pub time: Duration,
+    /// Time between probes (`TCP_KEEPINTVL`).
+    pub interval: Duration,
+    /// Number of unanswered probes before the connection is dropped (`TCP_KEEPCNT`).
+    pub retries: u32,
+}
+
+impl Default for KeepaliveConfig {
+    fn default() -> KeepaliveConfig {
+        KeepaliveConfig {
+            time: Duration::from_secs(7200),
+            interval: Duration::from_secs(75),
+            retries: 9,
+        }
+    }
 }
 
 impl FromRawFd for TcpStream {
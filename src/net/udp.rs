@@ -1,15 +1,21 @@
+use super::{CMsgs, ControlMessage, TimestampingFlags};
 use crate::{
-    buf::{BoundedBuf, BoundedBufMut, Buffer},
-    io::{SharedFd, Socket},
-    Unsubmitted,
+    buf::bufring::BufRing,
+    buf::{BoundedBuf, BoundedBufMut, Buffer, IoBuf},
+    io::{recvmsg_header_len, Interest, Ready, RecvMsgStream, SharedFd, Socket},
+    Unsubmitted, UnsubmittedRecv, WithBuffer,
 };
+use futures_core::Stream;
 use socket2::SockAddr;
 use std::{
     io,
-    net::SocketAddr,
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr},
     os::unix::prelude::{AsRawFd, FromRawFd, RawFd},
 };
 
+// Control data received by `recv_msg`, enough for packet info, TOS and a timestamp.
+const DEFAULT_CONTROL_LEN: usize = 256;
+
 /// A UDP socket.
 ///
 /// UDP is "connectionless", unlike TCP. Meaning, regardless of what address you've bound to, a `UdpSocket`
@@ -105,6 +111,32 @@ impl UdpSocket {
         Ok(UdpSocket { inner: socket })
     }
 
+    /// Returns a builder to configure a socket before it is bound, with options such as
+    /// `IPV6_V6ONLY` that must be set before `bind(2)`.
+    ///
+    /// # Examples
+    ///
+    /// A dual-stack socket, receiving from IPv4 and IPv6 peers alike:
+    ///
+    /// ```
+    /// use tokio_uring::net::UdpSocket;
+    ///
+    /// tokio_uring::start(async {
+    ///     let socket = UdpSocket::builder()
+    ///         .only_v6(false)
+    ///         .bind("[::]:0".parse().unwrap())
+    ///         .unwrap();
+    ///     assert!(socket.local_addr().unwrap().is_ipv6());
+    /// });
+    /// ```
+    pub fn builder() -> UdpSocketBuilder {
+        UdpSocketBuilder {
+            reuseaddr: true,
+            reuseport: true,
+            only_v6: None,
+        }
+    }
+
     /// Returns the local address to which this UDP socket is bound.
     ///
     /// This can be useful, for example, when binding to port 0 to
@@ -133,12 +165,30 @@ impl UdpSocket {
         local_addr
     }
 
+    /// Returns the address of the remote peer this socket was connected to.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`NotConnected`](io::ErrorKind::NotConnected) if the socket is not
+    /// connected.
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        let fd = self.inner.as_raw_fd();
+        // SAFETY: Our fd is the handle the kernel has given us for a UdpSocket.
+        // Create a std::net::UdpSocket long enough to call its peer_addr method
+        // and then forget it so the socket is not closed here.
+        let s = unsafe { std::net::UdpSocket::from_raw_fd(fd) };
+        let peer_addr = s.peer_addr();
+        std::mem::forget(s);
+        peer_addr
+    }
+
     /// Creates new `UdpSocket` from a previously bound `std::net::UdpSocket`.
     ///
     /// This function is intended to be used to wrap a UDP socket from the
-    /// standard library in the tokio-uring equivalent. The conversion assumes nothing
-    /// about the underlying socket; it is left up to the user to decide what socket
-    /// options are appropriate for their use case.
+    /// standard library in the tokio-uring equivalent. The socket is switched to blocking
+    /// mode, as io_uring waits for readiness itself; beyond that, the conversion assumes
+    /// nothing about the underlying socket, and it is left up to the user to decide what
+    /// socket options are appropriate for their use case.
     ///
     /// This can be used in conjunction with socket2's `Socket` interface to
     /// configure a socket before it's handed off, such as setting options like
@@ -186,6 +236,19 @@ impl UdpSocket {
         Self { inner }
     }
 
+    /// Converts the socket into a [`std::net::UdpSocket`], transferring ownership of the socket.
+    ///
+    /// The returned socket is in blocking mode; call `set_nonblocking(true)` on it before
+    /// handing it to a readiness-based runtime such as tokio.
+    ///
+    /// # Errors
+    ///
+    /// Fails while operations on the socket are still in flight, such as one whose future was
+    /// dropped before it completed. The socket is then closed once they finish.
+    pub fn into_std(self) -> io::Result<std::net::UdpSocket> {
+        self.inner.into_std()
+    }
+
     pub(crate) fn from_socket(inner: Socket) -> Self {
         Self { inner }
     }
@@ -198,19 +261,31 @@ impl UdpSocket {
     /// Note: UDP is connectionless, so a successful `connect` call does not execute
     /// a handshake or validation of the remote peer of any kind.
     /// Any errors would not be detected until the first send.
+    ///
+    /// Once connected, the kernel only delivers datagrams from the peer, and reports ICMP
+    /// errors about it, such as `ECONNREFUSED` when nothing listens on its port, on a
+    /// following [`send`](Self::send) or [`recv`](Self::recv).
     pub async fn connect(&self, socket_addr: SocketAddr) -> io::Result<()> {
         self.inner.connect(SockAddr::from(socket_addr)).await
     }
 
-    /// Sends data on the connected socket
+    /// Sends data on the connected socket.
+    ///
+    /// This is a plain `IORING_OP_SEND`, without the message header [`send_to`] passes the
+    /// address in.
     ///
     /// On success, returns the number of bytes written.
+    ///
+    /// [`send_to`]: Self::send_to
     pub async fn send<T: BoundedBuf>(&self, buf: T) -> crate::Result<usize, T> {
         self.inner.send_to(buf, None).await
     }
 
     /// Sends data on the socket to the given address.
     ///
+    /// On a connected socket, Linux sends the datagram to `socket_addr` all the same, without
+    /// changing the peer; other errors come back from the kernel as they are.
+    ///
     /// On success, returns the number of bytes written.
     pub async fn send_to<T: BoundedBuf>(
         &self,
@@ -220,6 +295,37 @@ impl UdpSocket {
         self.inner.send_to(buf, Some(socket_addr)).await
     }
 
+    /// Sends a batch of datagrams, each buffer to the address paired with it.
+    ///
+    /// A sendmsg is submitted for every datagram at once, so a burst costs one submission
+    /// rather than one per datagram. The sends complete independently, possibly out of order.
+    ///
+    /// Returns the result of each send, with its buffer, in the order of `items`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use tokio_uring::net::UdpSocket;
+    ///
+    /// tokio_uring::start(async {
+    ///     let socket = UdpSocket::bind("127.0.0.1:0".parse().unwrap()).await.unwrap();
+    ///     let peer = "127.0.0.1:4433".parse().unwrap();
+    ///     let packets = (0..32u8).map(|i| (vec![i; 1200].into(), peer)).collect();
+    ///
+    ///     for res in socket.send_batch(packets).await {
+    ///         if let Err(e) = res {
+    ///             println!("send failed: {}", e.0);
+    ///         }
+    ///     }
+    /// });
+    /// ```
+    pub async fn send_batch(
+        &self,
+        items: Vec<(Buffer, SocketAddr)>,
+    ) -> Vec<crate::Result<usize, Buffer>> {
+        self.inner.send_batch(items).await
+    }
+
     /// Sends data on the socket. Will attempt to do so without intermediate copies.
     ///
     /// On success, returns the number of bytes written.
@@ -288,7 +394,18 @@ impl UdpSocket {
 
     /// Receives a single datagram message on the socket.
     ///
-    /// On success, returns the number of bytes read and the origin.
+    /// On success, returns the number of bytes read and the origin. On a dual-stack IPv6
+    /// socket, IPv4 origins come in their IPv4-mapped form; see
+    /// [`to_canonical`](crate::net::to_canonical).
+    ///
+    /// # Errors
+    ///
+    /// If the datagram is larger than the buffer, the excess is discarded and a
+    /// [`DatagramTruncated`] error is returned, holding the origin and the full length of the
+    /// datagram. The buffer still holds the first part of the datagram, which is marked as
+    /// initialized.
+    ///
+    /// [`DatagramTruncated`]: crate::net::DatagramTruncated
     pub async fn recv_from<T: BoundedBufMut>(
         &self,
         buf: T,
@@ -296,9 +413,313 @@ impl UdpSocket {
         self.inner.recv_from(buf).await
     }
 
+    /// Receives a batch of datagrams, one into each buffer of `bufs`.
+    ///
+    /// A receive is submitted for every buffer at once, so a burst of small datagrams costs
+    /// one submission rather than one per datagram. The batch completes once at least `min`
+    /// datagrams arrived, clamped to between 1 and the number of buffers; the receives still
+    /// waiting are then cancelled. Any that completed before the cancellation took effect are
+    /// returned too, so the batch may hold more than `min` datagrams, but none are dropped.
+    ///
+    /// On success, returns the datagrams with their sizes and origins, in the order of their
+    /// buffers, along with the buffers that received nothing.
+    ///
+    /// # Errors
+    ///
+    /// A failed receive, including one failing with [`DatagramTruncated`] for a datagram
+    /// larger than its buffer, ends the batch early. The batch still succeeds if it received anything,
+    /// like `recvmmsg(2)`, and otherwise fails with the error and all the buffers.
+    ///
+    /// Dropping the future cancels the receives, losing datagrams already received.
+    ///
+    /// [`DatagramTruncated`]: crate::net::DatagramTruncated
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use tokio_uring::net::UdpSocket;
+    ///
+    /// tokio_uring::start(async {
+    ///     let socket = UdpSocket::bind("127.0.0.1:5353".parse().unwrap()).await.unwrap();
+    ///     let mut bufs: Vec<_> = (0..32).map(|_| vec![0; 1500].into()).collect();
+    ///
+    ///     loop {
+    ///         let (datagrams, mut unused) = socket.recv_batch(bufs, 1).await.unwrap();
+    ///         for (n, buf, from) in datagrams {
+    ///             println!("{} bytes from {}", n, from);
+    ///             unused.push(buf);
+    ///         }
+    ///         bufs = unused;
+    ///     }
+    /// });
+    /// ```
+    pub async fn recv_batch(
+        &self,
+        bufs: Vec<Buffer>,
+        min: usize,
+    ) -> crate::Result<Vec<(usize, Buffer, SocketAddr)>, Vec<Buffer>> {
+        self.inner.recv_batch(bufs, min).await
+    }
+
+    /// Receives a single datagram along with its control messages.
+    ///
+    /// On success, returns the number of bytes read, the origin and the control messages. The
+    /// datagram is spread over the segments of `buf` in order.
+    ///
+    /// Most control messages are only delivered after enabling the matching socket option, for
+    /// example `IP_PKTINFO` to learn the local address a datagram was sent to. Up to
+    /// 256 bytes of control data are received, enough for several IP-level messages; use
+    /// [`recv_msg_with_control_len`](Self::recv_msg_with_control_len) for more. When control
+    /// data is dropped for lack of space, [`CMsgs::is_truncated`] returns `true`.
+    ///
+    /// # Errors
+    ///
+    /// A datagram larger than `buf` fails with `EMSGSIZE`; its leading bytes are still in
+    /// `buf`, and the rest is discarded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::os::unix::io::AsRawFd;
+    /// use tokio_uring::net::{ControlMessage, UdpSocket};
+    /// use tokio_uring::Buffer;
+    ///
+    /// tokio_uring::start(async {
+    ///     let server = UdpSocket::bind("0.0.0.0:0".parse().unwrap()).await.unwrap();
+    ///     let on: libc::c_int = 1;
+    ///     unsafe {
+    ///         libc::setsockopt(
+    ///             server.as_raw_fd(),
+    ///             libc::IPPROTO_IP,
+    ///             libc::IP_PKTINFO,
+    ///             &on as *const _ as *const libc::c_void,
+    ///             std::mem::size_of_val(&on) as libc::socklen_t,
+    ///         );
+    ///     }
+    ///
+    ///     let port = server.local_addr().unwrap().port();
+    ///     let client = UdpSocket::bind("127.0.0.1:0".parse().unwrap()).await.unwrap();
+    ///     client.send_to(b"query".to_vec(), ([127, 0, 0, 1], port).into()).await.unwrap();
+    ///
+    ///     let buf = Buffer::from(Vec::<u8>::with_capacity(512));
+    ///     let (n, from, cmsgs) = server.recv_msg(buf).await.unwrap().0;
+    ///     for cmsg in &cmsgs {
+    ///         if let ControlMessage::Ipv4PacketInfo { addr, .. } = cmsg {
+    ///             println!("{} bytes from {} sent to {}", n, from, addr);
+    ///         }
+    ///     }
+    /// });
+    /// ```
+    pub async fn recv_msg(&self, buf: Buffer) -> crate::Result<(usize, SocketAddr, CMsgs), Buffer> {
+        self.recv_msg_with_control_len(buf, DEFAULT_CONTROL_LEN)
+            .await
+    }
+
+    /// Like [`recv_msg`](Self::recv_msg), receiving up to `control_len` bytes of control data.
+    pub async fn recv_msg_with_control_len(
+        &self,
+        buf: Buffer,
+        control_len: usize,
+    ) -> crate::Result<(usize, SocketAddr, CMsgs), Buffer> {
+        let ((n, socket_addr, cmsgs), buf) = self.inner.recv_msg(buf, control_len).await?;
+        match socket_addr.as_socket() {
+            Some(socket_addr) => Ok(((n, socket_addr, cmsgs), buf)),
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "datagram received from a non-IP address",
+            ))
+            .with_buffer(buf),
+        }
+    }
+
+    /// Receives datagrams into buffers selected from `group`, as a stream of payloads along
+    /// with their source addresses and control messages.
+    ///
+    /// A single multishot operation stays armed for the lifetime of the stream, instead of one
+    /// receive per datagram. Each buffer of the group holds a header, the source address and
+    /// room for control data in front of the payload, so the buffers must be larger than
+    /// that, and the largest payload fits in the rest; the returned [`Buffer`] only covers the
+    /// payload. A datagram that does not fit is reported as an `EMSGSIZE` error, after which
+    /// the stream carries on.
+    ///
+    /// Dropping a `Buffer` returns it to the group. When the group runs out, the kernel
+    /// stops receiving, and the stream resumes once a buffer is returned; datagrams queue up
+    /// in the socket in the meantime, up to its receive buffer size.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use futures_util::StreamExt;
+    /// use tokio_uring::buf::bufring::BufRing;
+    /// use tokio_uring::net::UdpSocket;
+    ///
+    /// tokio_uring::start(async {
+    ///     let group = BufRing::register(256, 2048, 0).unwrap();
+    ///     let socket = UdpSocket::bind("127.0.0.1:5353".parse().unwrap()).await.unwrap();
+    ///
+    ///     let mut queries = socket.recv_msg_multi(&group).unwrap();
+    ///     while let Some(res) = queries.next().await {
+    ///         let (buf, from, _cmsgs) = res.unwrap();
+    ///         println!("{} bytes from {}", buf[0].len(), from);
+    ///     }
+    /// });
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`InvalidInput`](io::ErrorKind::InvalidInput) if the buffers of `group`
+    /// leave no room for a payload. Requires Linux 6.0 or later.
+    pub fn recv_msg_multi(
+        &self,
+        group: &BufRing,
+    ) -> io::Result<impl Stream<Item = io::Result<(Buffer, SocketAddr, CMsgs)>>> {
+        if group.buf_size() <= recvmsg_header_len(DEFAULT_CONTROL_LEN) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "buffers are too small for the header received with each datagram",
+            ));
+        }
+        Ok(RecvMsgStream::new(
+            &self.inner.fd,
+            group,
+            DEFAULT_CONTROL_LEN,
+        ))
+    }
+
+    /// Sends the initialized contents of `buf` as a single datagram, along with control
+    /// messages.
+    ///
+    /// `socket_addr` may be `None` on a connected socket. Control messages such as
+    /// [`ControlMessage::Ipv4PacketInfo`](super::ControlMessage::Ipv4PacketInfo) select the source address or interface of this
+    /// datagram only.
+    ///
+    /// On success, returns the number of bytes sent.
+    pub async fn send_msg(
+        &self,
+        buf: Buffer,
+        socket_addr: Option<SocketAddr>,
+        cmsgs: &CMsgs,
+    ) -> crate::Result<usize, Buffer> {
+        self.inner
+            .send_msg(buf, socket_addr.map(SockAddr::from), cmsgs)
+            .await
+    }
+
+    /// Sends the initialized contents of `buf` as a series of datagrams of `segment_size`
+    /// bytes each, the last of which may be shorter, in a single operation.
+    ///
+    /// The kernel splits the payload with generic segmentation offload (GSO, `UDP_SEGMENT`),
+    /// or the network card does, which costs far less than a send per datagram. The payload
+    /// may span at most 64 segments, and must fit in a single IP packet before splitting.
+    ///
+    /// On success, returns the number of bytes sent.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`InvalidInput`](io::ErrorKind::InvalidInput) if `segment_size` is 0 or the
+    /// payload spans more than 64 segments, and with [`Unsupported`](io::ErrorKind::Unsupported)
+    /// if the kernel lacks `UDP_SEGMENT`; in both cases nothing is submitted.
+    pub async fn send_to_segmented(
+        &self,
+        buf: Buffer,
+        socket_addr: SocketAddr,
+        segment_size: u16,
+    ) -> crate::Result<usize, Buffer> {
+        // UDP_MAX_SEGMENTS in the kernel.
+        const MAX_SEGMENTS: usize = 64;
+
+        let segment_size_usize = usize::from(segment_size);
+        if segment_size == 0 || IoBuf::bytes_init(&buf) > segment_size_usize * MAX_SEGMENTS {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the segment size must be non-zero and split the payload into at most 64 datagrams",
+            ))
+            .with_buffer(buf);
+        }
+        if let Err(e) = self.inner.check_gso() {
+            return Err(e).with_buffer(buf);
+        }
+
+        let mut cmsgs = CMsgs::new();
+        cmsgs.push(ControlMessage::UdpSegment(segment_size));
+        self.send_msg(buf, Some(socket_addr), &cmsgs).await
+    }
+
+    /// Sets whether the kernel may coalesce datagrams from the same flow into a single receive
+    /// (generic receive offload, or GRO, `UDP_GRO`).
+    ///
+    /// A coalesced payload comes with a [`ControlMessage::UdpGro`] message giving the size of
+    /// its datagrams; [`recv_from_segmented`](Self::recv_from_segmented) returns it directly.
+    /// Receive into buffers of 64 KiB, or payloads are truncated.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`Unsupported`](io::ErrorKind::Unsupported) if the kernel lacks `UDP_GRO`.
+    pub fn set_gro(&self, on: bool) -> io::Result<()> {
+        self.inner.set_gro(on)
+    }
+
+    /// Sets which timestamps the kernel records for received datagrams, `SO_TIMESTAMPING`.
+    ///
+    /// [`recv_msg`](Self::recv_msg) returns them in a [`ControlMessage::Timestamping`]
+    /// message. [`TimestampingFlags::NONE`] turns timestamping off again.
+    ///
+    /// The first socket to ask for software timestamps has the kernel start taking them
+    /// in the background, so datagrams received right after this call may come without.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use tokio_uring::buf::Buffer;
+    /// use tokio_uring::net::{ControlMessage, TimestampingFlags, UdpSocket};
+    ///
+    /// tokio_uring::start(async {
+    ///     let socket = UdpSocket::bind("127.0.0.1:8080".parse().unwrap()).await.unwrap();
+    ///     socket
+    ///         .set_timestamping(TimestampingFlags::RX_SOFTWARE | TimestampingFlags::SOFTWARE)
+    ///         .unwrap();
+    ///
+    ///     let buf = Buffer::from(Vec::<u8>::with_capacity(1500));
+    ///     let ((_, _, cmsgs), _) = socket.recv_msg(buf).await.unwrap();
+    ///     for cmsg in &cmsgs {
+    ///         if let ControlMessage::Timestamping { software: Some(at), .. } = cmsg {
+    ///             println!("received at {:?}", at);
+    ///         }
+    ///     }
+    /// });
+    /// ```
+    pub fn set_timestamping(&self, flags: TimestampingFlags) -> io::Result<()> {
+        self.inner.set_timestamping(flags.bits())
+    }
+
+    /// Receives a datagram, or with [GRO](Self::set_gro) enabled a payload of coalesced
+    /// datagrams, into `buf`.
+    ///
+    /// On success, returns the number of bytes received, the origin, and the size of the
+    /// datagrams the payload splits into: all but the last are full. Without coalescing, the
+    /// size is that of the whole payload.
+    pub async fn recv_from_segmented(
+        &self,
+        buf: Buffer,
+    ) -> crate::Result<(usize, SocketAddr, usize), Buffer> {
+        let ((n, socket_addr, cmsgs), buf) = self.recv_msg(buf).await?;
+        let segment_size = cmsgs
+            .iter()
+            .find_map(|cmsg| match *cmsg {
+                ControlMessage::UdpGro(size) => Some(usize::from(size)),
+                _ => None,
+            })
+            .unwrap_or(n);
+        Ok(((n, socket_addr, segment_size), buf))
+    }
+
     /// Receives a single datagram message on the socket, into multiple buffers
     ///
-    /// On success, returns the number of bytes read and the origin.
+    /// On success, returns the number of bytes read and the origin. A datagram larger than the
+    /// buffers combined fails with a [`DatagramTruncated`] error, as with
+    /// [`recv_from`](Self::recv_from).
+    ///
+    /// [`DatagramTruncated`]: crate::net::DatagramTruncated
     pub async fn recvmsg<T: BoundedBufMut>(
         &self,
         buf: Vec<T>,
@@ -306,11 +727,37 @@ impl UdpSocket {
         self.inner.recvmsg(buf).await
     }
 
+    /// Receives a single datagram into the buffer, with `MSG_*` flags set on the returned
+    /// [`UnsubmittedRecv`] before it is submitted.
+    ///
+    /// # Examples
+    ///
+    /// Sizing the next datagram before receiving it:
+    ///
+    /// ```no_run
+    /// use tokio_uring::net::UdpSocket;
+    /// use tokio_uring::Submit;
+    ///
+    /// tokio_uring::start(async {
+    ///     let socket = UdpSocket::bind("127.0.0.1:8080".parse().unwrap()).await.unwrap();
+    ///
+    ///     let buf = Vec::<u8>::new().into();
+    ///     let (size, _) = socket.recv(buf).peek().trunc().submit().await.unwrap();
+    ///
+    ///     let buf = Vec::<u8>::with_capacity(size).into();
+    ///     let (n, buf) = socket.recv(buf).submit().await.unwrap();
+    ///     assert_eq!(n, size);
+    /// });
+    /// ```
+    pub fn recv(&self, buf: Buffer) -> UnsubmittedRecv {
+        UnsubmittedRecv::new(&self.inner.fd, buf, true)
+    }
+
     /// Reads a packet of data from the socket into the buffer.
     ///
     /// Returns the original buffer and quantity of data read.
     pub async fn read(&self, buf: Buffer) -> crate::Result<usize, Buffer> {
-        self.inner.read(buf).await
+        self.inner.read_datagram(buf).await
     }
 
     /// Receives a single datagram message into a registered buffer.
@@ -327,14 +774,14 @@ impl UdpSocket {
     /// this operation fails if the buffer is not registered in the
     /// current `tokio-uring` runtime.
     pub async fn read_fixed(&self, buf: Buffer) -> crate::Result<usize, Buffer> {
-        self.inner.read_fixed(buf).await
+        self.inner.read_fixed_datagram(buf).await
     }
 
     /// Writes data into the socket from the specified buffer.
     ///
     /// Returns the original buffer and quantity of data written.
     pub fn write(&self, buf: Buffer) -> Unsubmitted {
-        self.inner.write(buf)
+        self.inner.write_datagram(buf)
     }
 
     /// Writes data into the socket from a registered buffer.
@@ -351,7 +798,157 @@ impl UdpSocket {
     /// this operation fails if the buffer is not registered in the
     /// current `tokio-uring` runtime.
     pub async fn write_fixed(&self, buf: Buffer) -> crate::Result<usize, Buffer> {
-        self.inner.write_fixed(buf).await
+        self.inner.write_fixed_datagram(buf).await
+    }
+
+    /// Sets the TOS byte of the IP header of outgoing datagrams, whose upper six bits are the
+    /// DSCP mark.
+    ///
+    /// As for [`TcpStream::set_tos`](crate::net::TcpStream::set_tos), this is `IP_TOS` or
+    /// `IPV6_TCLASS` depending on the family of the socket, and both on a dual-stack one. To
+    /// mark a single datagram differently, send it with [`send_msg`](UdpSocket::send_msg)
+    /// and a [`ControlMessage::Ipv4Tos`] or [`ControlMessage::Ipv6TrafficClass`].
+    ///
+    /// [`ControlMessage::Ipv4Tos`]: crate::net::ControlMessage::Ipv4Tos
+    /// [`ControlMessage::Ipv6TrafficClass`]: crate::net::ControlMessage::Ipv6TrafficClass
+    pub fn set_tos(&self, tos: u8) -> io::Result<()> {
+        self.inner.set_tos(tos)
+    }
+
+    /// Gets the TOS byte, or the traffic class of an IPv6 socket.
+    pub fn tos(&self) -> io::Result<u8> {
+        self.inner.tos()
+    }
+
+    /// Sets the size of the send buffer, `SO_SNDBUF`, capped by the kernel at
+    /// `net.core.wmem_max`.
+    pub fn set_send_buffer_size(&self, size: u32) -> io::Result<()> {
+        self.inner.set_send_buffer_size(size)
+    }
+
+    /// Gets the size of the send buffer, which Linux reports as double the size that was set.
+    pub fn send_buffer_size(&self) -> io::Result<u32> {
+        self.inner.send_buffer_size()
+    }
+
+    /// Sets the size of the receive buffer, `SO_RCVBUF`, capped by the kernel at
+    /// `net.core.rmem_max`.
+    ///
+    /// Datagrams arriving while the buffer is full are dropped, so bursty receivers may
+    /// need a larger one.
+    pub fn set_recv_buffer_size(&self, size: u32) -> io::Result<()> {
+        self.inner.set_recv_buffer_size(size)
+    }
+
+    /// Gets the size of the receive buffer, which Linux reports as double the size that was
+    /// set.
+    pub fn recv_buffer_size(&self) -> io::Result<u32> {
+        self.inner.recv_buffer_size()
+    }
+
+    /// Sets `SO_BINDTODEVICE`, restricting the socket to the network interface named
+    /// `interface`, or lifts the restriction with `None` or an empty name.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`PermissionDenied`](io::ErrorKind::PermissionDenied) without
+    /// `CAP_NET_RAW`.
+    pub fn set_device(&self, interface: Option<&str>) -> io::Result<()> {
+        self.inner.set_device(interface)
+    }
+
+    /// Returns the name of the interface the socket is restricted to with
+    /// [`set_device`](Self::set_device), if any.
+    pub fn device(&self) -> io::Result<Option<String>> {
+        self.inner.device()
+    }
+
+    /// Joins the IPv4 multicast group `group` on the interface with the address `interface`.
+    ///
+    /// With [`Ipv4Addr::UNSPECIFIED`] as the interface, the kernel picks one from the routing
+    /// table. The socket must be bound to the group's port, and to the unspecified address or
+    /// the group itself, to receive the group's datagrams.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::net::Ipv4Addr;
+    /// use tokio_uring::net::UdpSocket;
+    ///
+    /// tokio_uring::start(async {
+    ///     let socket = UdpSocket::bind("0.0.0.0:5353".parse().unwrap()).await.unwrap();
+    ///     socket
+    ///         .join_multicast_v4(Ipv4Addr::new(224, 0, 0, 251), Ipv4Addr::UNSPECIFIED)
+    ///         .unwrap();
+    /// });
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`InvalidInput`](io::ErrorKind::InvalidInput) if `group` is not a multicast
+    /// address.
+    pub fn join_multicast_v4(&self, group: Ipv4Addr, interface: Ipv4Addr) -> io::Result<()> {
+        self.inner.join_multicast_v4(group, interface)
+    }
+
+    /// Leaves the IPv4 multicast group `group` on the interface with the address `interface`,
+    /// as joined with [`join_multicast_v4`](Self::join_multicast_v4).
+    pub fn leave_multicast_v4(&self, group: Ipv4Addr, interface: Ipv4Addr) -> io::Result<()> {
+        self.inner.leave_multicast_v4(group, interface)
+    }
+
+    /// Joins the IPv6 multicast group `group` on the interface with the index `interface`, or
+    /// on one the kernel picks if it is 0.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`InvalidInput`](io::ErrorKind::InvalidInput) if `group` is not a multicast
+    /// address, or the socket is not an IPv6 one.
+    pub fn join_multicast_v6(&self, group: Ipv6Addr, interface: u32) -> io::Result<()> {
+        self.inner.join_multicast_v6(group, interface)
+    }
+
+    /// Leaves the IPv6 multicast group `group` on the interface with the index `interface`,
+    /// as joined with [`join_multicast_v6`](Self::join_multicast_v6).
+    pub fn leave_multicast_v6(&self, group: Ipv6Addr, interface: u32) -> io::Result<()> {
+        self.inner.leave_multicast_v6(group, interface)
+    }
+
+    /// Sets whether IPv4 multicast datagrams sent from this socket are looped back to
+    /// sockets on this host that joined the group. On by default.
+    pub fn set_multicast_loop_v4(&self, on: bool) -> io::Result<()> {
+        self.inner.set_multicast_loop_v4(on)
+    }
+
+    /// Sets whether IPv6 multicast datagrams sent from this socket are looped back to
+    /// sockets on this host that joined the group. On by default.
+    pub fn set_multicast_loop_v6(&self, on: bool) -> io::Result<()> {
+        self.inner.set_multicast_loop_v6(on)
+    }
+
+    /// Sets the time-to-live of IPv4 multicast datagrams sent from this socket, which limits
+    /// how many routers forward them. The default of 1 keeps them on the local network.
+    pub fn set_multicast_ttl_v4(&self, ttl: u32) -> io::Result<()> {
+        self.inner.set_multicast_ttl_v4(ttl)
+    }
+
+    /// Waits until a datagram is waiting to be received.
+    ///
+    /// Nothing is received; the wait is an io_uring poll, cancelled if the future is dropped.
+    /// See [`TcpStream::readable`](crate::net::TcpStream::readable).
+    pub async fn readable(&self) -> io::Result<()> {
+        self.inner.ready(Interest::READABLE).await.map(|_| ())
+    }
+
+    /// Waits until the socket has room in its send buffer for a datagram.
+    pub async fn writable(&self) -> io::Result<()> {
+        self.inner.ready(Interest::WRITABLE).await.map(|_| ())
+    }
+
+    /// Waits until any of the events in `interest` fire, and returns those that did, along
+    /// with pending errors, which are reported whatever the interest.
+    pub async fn ready(&self, interest: Interest) -> io::Result<Ready> {
+        self.inner.ready(interest).await
     }
 
     /// Shuts down the read, write, or both halves of this connection.
@@ -374,3 +971,54 @@ impl AsRawFd for UdpSocket {
         self.inner.as_raw_fd()
     }
 }
+
+/// Configures a [`UdpSocket`] before it is bound, created by [`UdpSocket::builder`].
+///
+/// The defaults match [`UdpSocket::bind`].
+#[derive(Debug, Clone)]
+pub struct UdpSocketBuilder {
+    reuseaddr: bool,
+    reuseport: bool,
+    only_v6: Option<bool>,
+}
+
+impl UdpSocketBuilder {
+    /// Sets `SO_REUSEADDR`.
+    ///
+    /// The default is `true`.
+    pub fn reuseaddr(&mut self, reuseaddr: bool) -> &mut Self {
+        self.reuseaddr = reuseaddr;
+        self
+    }
+
+    /// Sets `SO_REUSEPORT`, which allows several sockets to bind the same address, with the
+    /// kernel spreading incoming datagrams between them.
+    ///
+    /// The default is `true`.
+    pub fn reuseport(&mut self, reuseport: bool) -> &mut Self {
+        self.reuseport = reuseport;
+        self
+    }
+
+    /// Sets `IPV6_V6ONLY`, which restricts a socket on an IPv6 address to IPv6 peers.
+    /// Ignored for IPv4 addresses.
+    ///
+    /// See [`TcpListenerBuilder::only_v6`](crate::net::TcpListenerBuilder::only_v6).
+    pub fn only_v6(&mut self, only_v6: bool) -> &mut Self {
+        self.only_v6 = Some(only_v6);
+        self
+    }
+
+    /// Creates the socket and binds it to `addr`.
+    pub fn bind(&self, addr: SocketAddr) -> io::Result<UdpSocket> {
+        let inner = Socket::new(addr, libc::SOCK_DGRAM)?;
+        let socket = socket2::SockRef::from(&inner);
+        socket.set_reuse_address(self.reuseaddr)?;
+        socket.set_reuse_port(self.reuseport)?;
+        if let (Some(only_v6), SocketAddr::V6(_)) = (self.only_v6, addr) {
+            socket.set_only_v6(only_v6)?;
+        }
+        socket.bind(&addr.into())?;
+        Ok(UdpSocket { inner })
+    }
+}
@@ -0,0 +1,199 @@
+use crate::{
+    buf::Buffer,
+    io::{SharedFd, Socket},
+    net::CMsgs,
+    WithBuffer,
+};
+use socket2::SockAddr;
+use std::{
+    io,
+    os::unix::{
+        net::SocketAddr,
+        prelude::{AsRawFd, FromRawFd, RawFd},
+    },
+    path::Path,
+};
+
+/// A Unix datagram socket.
+///
+/// Each send is delivered as a single datagram, and each receive returns at most one.
+///
+/// # Examples
+///
+/// ```
+/// use tokio_uring::net::UnixDatagram;
+///
+/// tokio_uring::start(async {
+///     let (a, b) = UnixDatagram::pair().unwrap();
+///
+///     a.send(b"hello".to_vec().into()).await.unwrap();
+///     let (n, buf) = b.recv(vec![0; 32].into()).await.unwrap();
+///     assert_eq!(&buf[0][..n], b"hello");
+/// });
+/// ```
+///
+/// Exchanging datagrams between a bound socket and an unbound one:
+///
+/// ```
+/// use tokio_uring::net::UnixDatagram;
+///
+/// let dir = tempfile::tempdir().unwrap();
+/// let path = dir.path().join("server.sock");
+///
+/// tokio_uring::start(async {
+///     let server = UnixDatagram::bind(&path).unwrap();
+///     let client = UnixDatagram::unbound().unwrap();
+///
+///     client.send_to(b"ping".to_vec().into(), &path).await.unwrap();
+///     let ((n, from), buf) = server.recv_from(vec![0; 32].into()).await.unwrap();
+///     assert_eq!(&buf[0][..n], b"ping");
+///     // The client never bound, so it has no address to reply to.
+///     assert!(from.is_unnamed());
+/// });
+/// ```
+pub struct UnixDatagram {
+    pub(super) inner: Socket,
+}
+
+impl UnixDatagram {
+    /// Creates an unnamed pair of connected sockets, with `socketpair(2)`.
+    pub fn pair() -> io::Result<(UnixDatagram, UnixDatagram)> {
+        let (a, b) = Socket::pair(libc::SOCK_DGRAM)?;
+        Ok((UnixDatagram { inner: a }, UnixDatagram { inner: b }))
+    }
+
+    /// Creates a socket bound to the specified file path.
+    ///
+    /// The path must not exist yet; the socket file is left behind when the socket is
+    /// dropped.
+    pub fn bind<P: AsRef<Path>>(path: P) -> io::Result<UnixDatagram> {
+        let inner = Socket::bind_unix(path, libc::SOCK_DGRAM)?;
+        Ok(UnixDatagram { inner })
+    }
+
+    /// Creates a socket bound to `addr`, which can be in the abstract namespace.
+    pub fn bind_addr(addr: &SocketAddr) -> io::Result<UnixDatagram> {
+        let inner = Socket::bind_unix_addr(super::from_unix_addr(addr)?, libc::SOCK_DGRAM)?;
+        Ok(UnixDatagram { inner })
+    }
+
+    /// Creates a socket that is not bound to any address.
+    ///
+    /// It can send with [`send_to`](Self::send_to), or with [`send`](Self::send) after
+    /// [`connect`](Self::connect), but peers see it as unnamed and cannot reply.
+    pub fn unbound() -> io::Result<UnixDatagram> {
+        let inner = Socket::new_unix(libc::SOCK_DGRAM)?;
+        Ok(UnixDatagram { inner })
+    }
+
+    /// Connects the socket to the socket bound at `path`.
+    ///
+    /// Afterwards, [`send`](Self::send) delivers to that socket and only datagrams from it
+    /// are received.
+    pub async fn connect<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.inner.connect(SockAddr::unix(path)?).await
+    }
+
+    /// Connects the socket to `addr`, which can be in the abstract namespace.
+    pub async fn connect_addr(&self, addr: &SocketAddr) -> io::Result<()> {
+        self.inner.connect(super::from_unix_addr(addr)?).await
+    }
+
+    /// Returns the local address of the socket, which is unnamed unless it was bound.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.with_std(|s| s.local_addr())
+    }
+
+    /// Returns the address of the connected peer.
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.with_std(|s| s.peer_addr())
+    }
+
+    fn with_std<R>(&self, f: impl FnOnce(&std::os::unix::net::UnixDatagram) -> R) -> R {
+        // SAFETY: Our fd is the handle the kernel has given us for a UnixDatagram.
+        // Create a std::os::unix::net::UnixDatagram long enough to call the method
+        // and then forget it so the socket is not closed here.
+        let s = unsafe { std::os::unix::net::UnixDatagram::from_raw_fd(self.inner.as_raw_fd()) };
+        let res = f(&s);
+        std::mem::forget(s);
+        res
+    }
+
+    /// Sends the buffer as a single datagram to the connected peer.
+    ///
+    /// Returns the original buffer and the number of bytes sent.
+    pub async fn send(&self, buf: Buffer) -> crate::Result<usize, Buffer> {
+        self.inner.send_msg(buf, None, &CMsgs::new()).await
+    }
+
+    /// Sends the buffer as a single datagram to the socket bound at `path`.
+    ///
+    /// Returns the original buffer and the number of bytes sent.
+    pub async fn send_to<P: AsRef<Path>>(
+        &self,
+        buf: Buffer,
+        path: P,
+    ) -> crate::Result<usize, Buffer> {
+        let socket_addr = match SockAddr::unix(path) {
+            Ok(socket_addr) => socket_addr,
+            Err(e) => return Err(e).with_buffer(buf),
+        };
+        self.inner
+            .send_msg(buf, Some(socket_addr), &CMsgs::new())
+            .await
+    }
+
+    /// Sends the buffer as a single datagram to `addr`, which can be in the abstract
+    /// namespace.
+    ///
+    /// Returns the original buffer and the number of bytes sent.
+    pub async fn send_to_addr(
+        &self,
+        buf: Buffer,
+        addr: &SocketAddr,
+    ) -> crate::Result<usize, Buffer> {
+        let socket_addr = match super::from_unix_addr(addr) {
+            Ok(socket_addr) => socket_addr,
+            Err(e) => return Err(e).with_buffer(buf),
+        };
+        self.inner
+            .send_msg(buf, Some(socket_addr), &CMsgs::new())
+            .await
+    }
+
+    /// Receives a single datagram into the buffer.
+    ///
+    /// Returns the original buffer and the size of the datagram. A datagram larger than the
+    /// buffer fails with `EMSGSIZE`; its leading bytes are still in the buffer, and the rest
+    /// is discarded.
+    pub async fn recv(&self, buf: Buffer) -> crate::Result<usize, Buffer> {
+        let ((n, _), buf) = self.recv_from(buf).await?;
+        Ok((n, buf))
+    }
+
+    /// Receives a single datagram into the buffer, along with the address of its sender.
+    ///
+    /// The address is unnamed for unbound senders, and abstract for senders the kernel
+    /// autobound. Truncation is reported as for [`recv`](Self::recv).
+    pub async fn recv_from(&self, buf: Buffer) -> crate::Result<(usize, SocketAddr), Buffer> {
+        let ((n, socket_addr, _), buf) = self.inner.recv_msg(buf, 0).await?;
+        match super::to_unix_addr(&socket_addr) {
+            Ok(socket_addr) => Ok(((n, socket_addr), buf)),
+            Err(e) => Err(e).with_buffer(buf),
+        }
+    }
+}
+
+impl FromRawFd for UnixDatagram {
+    unsafe fn from_raw_fd(fd: RawFd) -> Self {
+        UnixDatagram {
+            inner: Socket::from_shared_fd(SharedFd::new(fd)),
+        }
+    }
+}
+
+impl AsRawFd for UnixDatagram {
+    fn as_raw_fd(&self) -> RawFd {
+        self.inner.as_raw_fd()
+    }
+}
@@ -1,6 +1,15 @@
 use super::UnixStream;
-use crate::io::Socket;
-use std::{io, path::Path};
+use crate::io::{AcceptFlags, AcceptGate, Incoming, Socket};
+use futures_core::Stream;
+use futures_util::StreamExt;
+use std::{
+    io,
+    os::unix::{
+        fs::{MetadataExt, PermissionsExt},
+        net::SocketAddr,
+    },
+    path::{Path, PathBuf},
+};
 
 /// A Unix socket server, listening for connections.
 ///
@@ -21,7 +30,7 @@ use std::{io, path::Path};
 ///     let (tx_ch, rx_ch) = tokio::sync::oneshot::channel();
 ///
 ///     tokio_uring::spawn(async move {
-///         let rx = listener.accept().await.unwrap();
+///         let (rx, _) = listener.accept().await.unwrap();
 ///         if let Err(_) = tx_ch.send(rx) {
 ///             panic!("The receiver dropped");
 ///         }
@@ -42,15 +51,108 @@ use std::{io, path::Path};
 /// ```
 pub struct UnixListener {
     inner: Socket,
+    // The socket file to remove on drop, with its device and inode, so that a file another
+    // listener has since bound at the path is left alone.
+    unlink: Option<(PathBuf, u64, u64)>,
 }
 
 impl UnixListener {
     /// Creates a new UnixListener, which will be bound to the specified file path.
-    /// The file path cannnot yet exist, and will be cleaned up upon dropping `UnixListener`
+    ///
+    /// The file path cannot yet exist, and is left behind when the listener is dropped. Use
+    /// [`builder`](UnixListener::builder) to take over a stale path, remove the file on drop,
+    /// or restrict who may connect.
     pub fn bind<P: AsRef<Path>>(path: P) -> io::Result<UnixListener> {
-        let socket = Socket::bind_unix(path, libc::SOCK_STREAM)?;
+        UnixListener::builder().bind(path)
+    }
+
+    /// Returns a builder to configure how the listener binds its path.
+    ///
+    /// # Examples
+    ///
+    /// A control socket only its owner can connect to, which replaces the file a crashed
+    /// predecessor left behind and is removed on shutdown:
+    ///
+    /// ```
+    /// use tokio_uring::net::UnixListener;
+    ///
+    /// let sock_file = "/tmp/tokio-uring-unix-builder-doc.sock";
+    /// let listener = UnixListener::builder()
+    ///     .mode(0o600)
+    ///     .unlink_existing(true)
+    ///     .cleanup_on_drop(true)
+    ///     .bind(sock_file)
+    ///     .unwrap();
+    ///
+    /// drop(listener);
+    /// assert!(!std::path::Path::new(sock_file).exists());
+    /// ```
+    pub fn builder() -> UnixListenerBuilder {
+        UnixListenerBuilder {
+            backlog: 1024,
+            mode: None,
+            unlink_existing: false,
+            cleanup_on_drop: false,
+        }
+    }
+
+    /// Creates a new UnixListener bound to `addr`, which can be in the abstract namespace.
+    ///
+    /// Abstract addresses exist only as long as the listener does; there is no file to clean
+    /// up.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::os::linux::net::SocketAddrExt;
+    /// use std::os::unix::net::SocketAddr;
+    /// use tokio_uring::net::UnixListener;
+    ///
+    /// let addr = SocketAddr::from_abstract_name(b"tokio-uring-doc-listener").unwrap();
+    /// let listener = UnixListener::bind_addr(&addr).unwrap();
+    ///
+    /// let local_addr = listener.local_addr().unwrap();
+    /// assert_eq!(local_addr.as_abstract_name(), Some(&b"tokio-uring-doc-listener"[..]));
+    /// ```
+    pub fn bind_addr(addr: &SocketAddr) -> io::Result<UnixListener> {
+        let socket = Socket::bind_unix_addr(super::from_unix_addr(addr)?, libc::SOCK_STREAM)?;
         socket.listen(1024)?;
-        Ok(UnixListener { inner: socket })
+        Ok(UnixListener {
+            inner: socket,
+            unlink: None,
+        })
+    }
+
+    /// Creates new `UnixListener` from a previously bound and listening
+    /// `std::os::unix::net::UnixListener`.
+    ///
+    /// The socket is switched to blocking mode, as io_uring waits for readiness itself; it is
+    /// otherwise used as is. Listeners inherited through socket activation can be taken over
+    /// this way, after wrapping the descriptor with
+    /// [`FromRawFd`](std::os::unix::io::FromRawFd).
+    pub fn from_std(socket: std::os::unix::net::UnixListener) -> UnixListener {
+        UnixListener {
+            inner: Socket::from_std(socket),
+            unlink: None,
+        }
+    }
+
+    /// Converts the listener into a [`std::os::unix::net::UnixListener`], transferring ownership of the socket.
+    ///
+    /// The returned socket is in blocking mode; call `set_nonblocking(true)` on it before
+    /// handing it to a readiness-based runtime such as tokio.
+    ///
+    /// # Errors
+    ///
+    /// Fails while operations on the socket are still in flight, such as one whose future was
+    /// dropped before it completed. The socket is then closed once they finish.
+    ///
+    /// The socket file is not removed, even if the listener was to remove it on drop.
+    pub fn into_std(mut self) -> io::Result<std::os::unix::net::UnixListener> {
+        self.unlink = None;
+        let inner = self.inner.clone();
+        drop(self);
+        inner.into_std()
     }
 
     /// Returns the local address that this listener is bound to.
@@ -69,7 +171,7 @@ impl UnixListener {
     ///
     /// std::fs::remove_file(&sock_file).unwrap();
     /// ```
-    pub fn local_addr(&self) -> io::Result<std::os::unix::net::SocketAddr> {
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
         use std::os::unix::io::{AsRawFd, FromRawFd};
 
         let fd = self.inner.as_raw_fd();
@@ -86,12 +188,150 @@ impl UnixListener {
     ///
     /// This function will yield once a new Unix domain socket connection
     /// is established. When established, the corresponding [`UnixStream`] and
-    /// will be returned.
+    /// the remote peer's address will be returned.
+    ///
+    /// Peers that did not bind their socket, which is the usual case for
+    /// clients, have an unnamed address.
     ///
     /// [`UnixStream`]: struct@crate::net::UnixStream
-    pub async fn accept(&self) -> io::Result<UnixStream> {
-        let (socket, _) = self.inner.accept().await?;
+    pub async fn accept(&self) -> io::Result<(UnixStream, SocketAddr)> {
+        self.accept_with_flags(AcceptFlags::default()).await
+    }
+
+    /// Accepts a new incoming connection, with `flags` set on its descriptor instead of
+    /// [`AcceptFlags::CLOEXEC`]. See
+    /// [`TcpListener::accept_with_flags`](crate::net::TcpListener::accept_with_flags).
+    pub async fn accept_with_flags(
+        &self,
+        flags: AcceptFlags,
+    ) -> io::Result<(UnixStream, SocketAddr)> {
+        let (socket, socket_addr) = self.inner.accept(flags, &AcceptGate::default()).await?;
         let stream = UnixStream { inner: socket };
-        Ok(stream)
+        Ok((stream, super::to_unix_addr(&socket_addr)?))
+    }
+
+    /// Returns a stream of incoming connections, for use with stream combinators.
+    ///
+    /// One accept is kept in flight while the stream is polled. Dropping the stream cancels
+    /// it, and closes the connection if the accept completed in the meantime. See
+    /// [`TcpListener::incoming`](crate::net::TcpListener::incoming).
+    pub fn incoming(&self) -> impl Stream<Item = io::Result<(UnixStream, SocketAddr)>> {
+        <Incoming>::new(
+            &self.inner.fd,
+            &AcceptGate::default(),
+            AcceptFlags::default(),
+        )
+        .map(|res| {
+            let (socket, socket_addr) = res?;
+            let stream = UnixStream { inner: socket };
+            Ok((stream, super::to_unix_addr(&socket_addr)?))
+        })
+    }
+}
+
+impl Drop for UnixListener {
+    fn drop(&mut self) {
+        if let Some((path, dev, ino)) = &self.unlink {
+            match std::fs::symlink_metadata(path) {
+                Ok(meta) if meta.dev() == *dev && meta.ino() == *ino => {
+                    let _ = std::fs::remove_file(path);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// A builder for a [`UnixListener`] bound to a path, created by [`UnixListener::builder`].
+pub struct UnixListenerBuilder {
+    backlog: u32,
+    mode: Option<u32>,
+    unlink_existing: bool,
+    cleanup_on_drop: bool,
+}
+
+impl UnixListenerBuilder {
+    /// Sets the maximum number of pending connections, passed to `listen(2)`.
+    ///
+    /// The default is 1024.
+    pub fn backlog(&mut self, backlog: u32) -> &mut Self {
+        self.backlog = backlog;
+        self
+    }
+
+    /// Sets the permissions of the socket file, such as `0o600` to let only its owner
+    /// connect, as connecting requires write permission on it.
+    ///
+    /// The file is changed after binding but before listening, so no connection is accepted
+    /// under the permissions the umask gave it. The default is to leave those.
+    pub fn mode(&mut self, mode: u32) -> &mut Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    /// Sets whether to replace a socket file already at the path, as a crashed process
+    /// leaves behind.
+    ///
+    /// The file is only removed if it is a socket that refuses connections. A path another
+    /// listener is still serving fails to bind with [`io::ErrorKind::AddrInUse`], as does
+    /// one taken by anything other than a socket. The default is `false`.
+    pub fn unlink_existing(&mut self, unlink_existing: bool) -> &mut Self {
+        self.unlink_existing = unlink_existing;
+        self
+    }
+
+    /// Sets whether dropping the listener removes the socket file it created.
+    ///
+    /// A file another listener has since bound at the path is left alone. The default is
+    /// `false`.
+    pub fn cleanup_on_drop(&mut self, cleanup_on_drop: bool) -> &mut Self {
+        self.cleanup_on_drop = cleanup_on_drop;
+        self
+    }
+
+    /// Creates the socket, binds it to `path` and starts listening.
+    pub fn bind<P: AsRef<Path>>(&self, path: P) -> io::Result<UnixListener> {
+        let path = path.as_ref();
+        let socket = match Socket::bind_unix(path, libc::SOCK_STREAM) {
+            Err(e) if e.kind() == io::ErrorKind::AddrInUse && self.unlink_existing => {
+                if !is_stale(path)? {
+                    return Err(e);
+                }
+                std::fs::remove_file(path)?;
+                Socket::bind_unix(path, libc::SOCK_STREAM)?
+            }
+            res => res?,
+        };
+
+        let meta = std::fs::symlink_metadata(path)?;
+        if let Some(mode) = self.mode {
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))?;
+        }
+        socket.listen(self.backlog.min(libc::c_int::MAX as u32) as libc::c_int)?;
+        Ok(UnixListener {
+            inner: socket,
+            unlink: self
+                .cleanup_on_drop
+                .then(|| (path.to_path_buf(), meta.dev(), meta.ino())),
+        })
+    }
+}
+
+// Returns whether `path` is a socket nothing listens on, by trying to connect to it. The
+// connect does not block, so a listener with a full backlog counts as live.
+fn is_stale(path: &Path) -> io::Result<bool> {
+    use std::os::unix::fs::FileTypeExt;
+
+    if !std::fs::symlink_metadata(path)?.file_type().is_socket() {
+        return Ok(false);
+    }
+    let probe = socket2::Socket::new(
+        socket2::Domain::UNIX,
+        socket2::Type::STREAM.nonblocking(),
+        None,
+    )?;
+    match probe.connect(&socket2::SockAddr::unix(path)?) {
+        Err(e) if e.kind() == io::ErrorKind::ConnectionRefused => Ok(true),
+        _ => Ok(false),
     }
 }
@@ -1,5 +1,67 @@
+mod datagram;
+pub use datagram::UnixDatagram;
+
 mod listener;
-pub use listener::UnixListener;
+pub use listener::{UnixListener, UnixListenerBuilder};
 
 mod stream;
 pub use stream::UnixStream;
+
+mod ucred;
+pub use ucred::UCred;
+
+use std::io;
+use std::os::linux::net::SocketAddrExt;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::net::SocketAddr;
+
+// Converts an address to bind or connect to. Abstract names are passed to socket2 behind a
+// leading NUL, from which it computes a length that excludes any terminator.
+pub(crate) fn from_unix_addr(addr: &SocketAddr) -> io::Result<socket2::SockAddr> {
+    if let Some(name) = addr.as_abstract_name() {
+        let mut path = Vec::with_capacity(name.len() + 1);
+        path.push(0);
+        path.extend_from_slice(name);
+        socket2::SockAddr::unix(std::ffi::OsStr::from_bytes(&path))
+    } else if let Some(path) = addr.as_pathname() {
+        socket2::SockAddr::unix(path)
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "cannot bind or connect to an unnamed address",
+        ))
+    }
+}
+
+// Converts an address filled in by the kernel, such as the peer address of an accept, into the
+// standard library's representation, which has no constructor from raw parts.
+pub(crate) fn to_unix_addr(addr: &socket2::SockAddr) -> io::Result<SocketAddr> {
+    // A datagram from an unbound sender comes with no address at all, not even a family.
+    if addr.len() == 0 {
+        return SocketAddr::from_pathname("");
+    }
+    if addr.family() != libc::AF_UNIX as libc::sa_family_t {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "not a Unix domain socket address",
+        ));
+    }
+
+    // Safety: the family was checked, and the kernel never reports more than a sockaddr_un.
+    let sun = unsafe { &*(addr.as_ptr() as *const libc::sockaddr_un) };
+    let offset = std::mem::size_of::<libc::sa_family_t>();
+    let len = (addr.len() as usize)
+        .saturating_sub(offset)
+        .min(sun.sun_path.len());
+    let path: Vec<u8> = sun.sun_path[..len].iter().map(|&c| c as u8).collect();
+
+    match path.split_first() {
+        // An empty pathname yields the unnamed address.
+        None => SocketAddr::from_pathname(""),
+        Some((0, name)) => SocketAddr::from_abstract_name(name),
+        Some(_) => {
+            let end = path.iter().position(|&c| c == 0).unwrap_or(path.len());
+            SocketAddr::from_pathname(std::ffi::OsStr::from_bytes(&path[..end]))
+        }
+    }
+}
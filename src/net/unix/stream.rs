@@ -1,12 +1,14 @@
 use crate::{
     buf::{BoundedBuf, BoundedBufMut, Buffer},
-    io::{SharedFd, Socket},
-    Submit, Unsubmitted,
+    io::{Interest, Ready, SharedFd, Socket},
+    net::{split, CMsgs, Compat, ControlMessage, OwnedReadHalf, OwnedWriteHalf, UCred},
+    Submit, Unsubmitted, WithBuffer,
 };
 use socket2::SockAddr;
 use std::{
-    io,
-    os::unix::prelude::{AsRawFd, FromRawFd, RawFd},
+    convert::TryInto,
+    io, mem,
+    os::unix::prelude::{AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd},
     path::Path,
 };
 
@@ -39,13 +41,22 @@ use std::{
 /// [`accepting`]: crate::net::UnixListener::accept
 /// [`listener`]: crate::net::UnixListener
 pub struct UnixStream {
-    pub(super) inner: Socket,
+    pub(crate) inner: Socket,
 }
 
 impl UnixStream {
     /// Opens a Unix connection to the specified file path. There must be a
     /// `UnixListener` or equivalent listening on the corresponding Unix domain socket
     /// to successfully connect and return a `UnixStream`.
+    ///
+    /// # Errors
+    ///
+    /// The kernel's error is returned as is, so the common failures can be told apart by
+    /// [`kind`](io::Error::kind):
+    ///
+    /// * [`NotFound`](io::ErrorKind::NotFound) if nothing exists at `path`.
+    /// * [`ConnectionRefused`](io::ErrorKind::ConnectionRefused) if `path` is a socket
+    ///   file nobody listens on anymore, typically left behind by a listener that exited.
     pub async fn connect<P: AsRef<Path>>(path: P) -> io::Result<UnixStream> {
         let socket = Socket::new_unix(libc::SOCK_STREAM)?;
         socket.connect(SockAddr::unix(path)?).await?;
@@ -53,12 +64,31 @@ impl UnixStream {
         Ok(unix_stream)
     }
 
+    /// Opens a Unix connection to `addr`, which can be in the abstract namespace.
+    ///
+    /// See [`UnixListener::bind_addr`](crate::net::UnixListener::bind_addr).
+    pub async fn connect_addr(addr: &std::os::unix::net::SocketAddr) -> io::Result<UnixStream> {
+        let socket = Socket::new_unix(libc::SOCK_STREAM)?;
+        socket.connect(super::from_unix_addr(addr)?).await?;
+        Ok(UnixStream { inner: socket })
+    }
+
+    /// Creates an unnamed pair of connected streams, with `socketpair(2)`.
+    ///
+    /// The two ends are independent: each can be moved to its own task, and closing one makes
+    /// reads on the other return 0.
+    pub fn pair() -> io::Result<(UnixStream, UnixStream)> {
+        let (a, b) = Socket::pair(libc::SOCK_STREAM)?;
+        Ok((UnixStream { inner: a }, UnixStream { inner: b }))
+    }
+
     /// Creates new `UnixStream` from a previously bound `std::os::unix::net::UnixStream`.
     ///
     /// This function is intended to be used to wrap a TCP stream from the
-    /// standard library in the tokio-uring equivalent. The conversion assumes nothing
-    /// about the underlying socket; it is left up to the user to decide what socket
-    /// options are appropriate for their use case.
+    /// standard library in the tokio-uring equivalent. The socket is switched to blocking
+    /// mode, as io_uring waits for readiness itself; beyond that, the conversion assumes
+    /// nothing about the underlying socket, and it is left up to the user to decide what
+    /// socket options are appropriate for their use case.
     ///
     /// This can be used in conjunction with socket2's `Socket` interface to
     /// configure a socket before it's handed off, such as setting options like
@@ -68,10 +98,173 @@ impl UnixStream {
         Self { inner }
     }
 
+    /// Converts the stream into a [`std::os::unix::net::UnixStream`], transferring ownership of the socket.
+    ///
+    /// The returned socket is in blocking mode; call `set_nonblocking(true)` on it before
+    /// handing it to a readiness-based runtime such as tokio.
+    ///
+    /// # Errors
+    ///
+    /// Fails while operations on the socket are still in flight, such as one whose future was
+    /// dropped before it completed. The socket is then closed once they finish.
+    pub fn into_std(self) -> io::Result<std::os::unix::net::UnixStream> {
+        self.inner.into_std()
+    }
+
+    /// Adapts the stream to tokio's [`AsyncRead`] and [`AsyncWrite`] traits.
+    ///
+    /// See [`Compat`] for how data is buffered, and what happens on cancellation.
+    ///
+    /// [`AsyncRead`]: tokio::io::AsyncRead
+    /// [`AsyncWrite`]: tokio::io::AsyncWrite
+    pub fn into_compat(self) -> Compat<UnixStream> {
+        let fd = self.inner.fd.clone();
+        Compat::new(self, &fd)
+    }
+
+    /// Splits the stream into a read half and a write half, which can be used from
+    /// different tasks at once.
+    ///
+    /// The halves share the socket, which is closed once both are dropped; put them back
+    /// together with [`OwnedReadHalf::reunite`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use tokio_uring::net::UnixStream;
+    ///
+    /// tokio_uring::start(async {
+    ///     let stream = UnixStream::connect("/tmp/echo.sock").await.unwrap();
+    ///     let (reader, writer) = stream.into_split();
+    ///
+    ///     let echo = tokio_uring::spawn(async move {
+    ///         let (n, buf) = reader.read(vec![0; 4096].into()).await.unwrap();
+    ///         (reader, n, buf)
+    ///     });
+    ///     writer.write_all(b"hello".to_vec().into()).await.unwrap();
+    ///
+    ///     let (reader, _n, _buf) = echo.await.unwrap();
+    ///     let stream = reader.reunite(writer).unwrap();
+    /// });
+    /// ```
+    pub fn into_split(self) -> (OwnedReadHalf<UnixStream>, OwnedWriteHalf<UnixStream>) {
+        split::split(self)
+    }
+
     pub(crate) fn from_socket(inner: Socket) -> Self {
         Self { inner }
     }
 
+    /// Returns the socket address of the local half of this connection.
+    ///
+    /// Unless the socket was bound before connecting, which is unusual for clients, the
+    /// address is unnamed.
+    pub fn local_addr(&self) -> io::Result<std::os::unix::net::SocketAddr> {
+        self.with_std(|s| s.local_addr())
+    }
+
+    /// Returns the socket address of the remote half of this connection.
+    pub fn peer_addr(&self) -> io::Result<std::os::unix::net::SocketAddr> {
+        self.with_std(|s| s.peer_addr())
+    }
+
+    /// Returns the credentials of the peer process, as recorded by the kernel when the
+    /// connection was made (`SO_PEERCRED`).
+    ///
+    /// They are available as soon as a stream is connected, accepted or created by
+    /// [`pair`](Self::pair), and let a server authorize clients by user or group id.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use tokio_uring::net::UnixListener;
+    ///
+    /// tokio_uring::start(async {
+    ///     let listener = UnixListener::bind("/run/control.sock").unwrap();
+    ///     let (stream, _) = listener.accept().await.unwrap();
+    ///     if stream.peer_cred().unwrap().uid() != 0 {
+    ///         return;
+    ///     }
+    ///     // Serve the privileged client.
+    /// });
+    /// ```
+    pub fn peer_cred(&self) -> io::Result<UCred> {
+        super::ucred::peer_cred(&self.inner)
+    }
+
+    fn with_std<R>(&self, f: impl FnOnce(&std::os::unix::net::UnixStream) -> R) -> R {
+        // SAFETY: Our fd is the handle the kernel has given us for a UnixStream.
+        // Create a std::os::unix::net::UnixStream long enough to call the method
+        // and then forget it so the socket is not closed here.
+        let s = unsafe { std::os::unix::net::UnixStream::from_raw_fd(self.inner.as_raw_fd()) };
+        let res = f(&s);
+        std::mem::forget(s);
+        res
+    }
+
+    /// Writes data from the buffer together with the file descriptors `fds`, using an
+    /// `SCM_RIGHTS` control message.
+    ///
+    /// The peer receives duplicates of the descriptors with
+    /// [`recv_with_fds`](Self::recv_with_fds), attached to the first byte of this write, so
+    /// the buffer should not be empty. The descriptors here stay open and owned by the caller.
+    ///
+    /// Returns the original buffer and the number of bytes written.
+    pub async fn send_fds(
+        &self,
+        buf: Buffer,
+        fds: &[BorrowedFd<'_>],
+    ) -> crate::Result<usize, Buffer> {
+        let data = fds
+            .iter()
+            .flat_map(|fd| fd.as_raw_fd().to_ne_bytes())
+            .collect();
+        let mut cmsgs = CMsgs::new();
+        if !fds.is_empty() {
+            cmsgs.push(ControlMessage::Other {
+                level: libc::SOL_SOCKET,
+                ty: libc::SCM_RIGHTS,
+                data,
+            });
+        }
+        self.inner.send_msg(buf, None, &cmsgs).await
+    }
+
+    /// Reads some data from the stream into the buffer, along with up to `max_fds` file
+    /// descriptors sent by the peer with [`send_fds`](Self::send_fds).
+    ///
+    /// A read stops at the boundary of a write that carried descriptors, so descriptors are
+    /// never split between reads. The received descriptors have close-on-exec set.
+    ///
+    /// # Errors
+    ///
+    /// If the peer sent more than `max_fds` descriptors, the kernel discards the excess and
+    /// this fails with `EMSGSIZE`; the data read is still in the returned buffer, and the
+    /// descriptors that did fit are closed.
+    pub async fn recv_with_fds(
+        &self,
+        buf: Buffer,
+        max_fds: usize,
+    ) -> crate::Result<(usize, Vec<OwnedFd>), Buffer> {
+        let control_len = if max_fds == 0 {
+            0
+        } else {
+            // Not CMSG_SPACE: the kernel fills whatever room there is, and the padding would
+            // let in one more descriptor than asked for.
+            // Safety: CMSG_LEN is a pure computation.
+            unsafe { libc::CMSG_LEN((max_fds * mem::size_of::<RawFd>()) as _) as usize }
+        };
+        let ((n, _, cmsgs), buf) = self.inner.recv_msg(buf, control_len).await?;
+
+        let truncated = cmsgs.is_truncated();
+        let fds = received_fds(cmsgs);
+        if truncated {
+            drop(fds);
+            return Err(io::Error::from_raw_os_error(libc::EMSGSIZE)).with_buffer(buf);
+        }
+        Ok(((n, fds), buf))
+    }
+
     /// Read some data from the stream into the buffer, returning the original buffer and
     /// quantity of data read.
     pub async fn read(&self, buf: Buffer) -> crate::Result<usize, Buffer> {
@@ -140,6 +333,34 @@ impl UnixStream {
         self.inner.write_fixed_all(buf).await
     }
 
+    /// Reads from the stream until the whole capacity of the buffer is filled.
+    ///
+    /// Short reads are continued where they left off, across the segments of the buffer,
+    /// until it is full.
+    ///
+    /// # Errors
+    ///
+    /// If the peer closes the connection first, this fails with
+    /// [`UnexpectedEof`](std::io::ErrorKind::UnexpectedEof). On this and any other error, the
+    /// returned buffer holds the bytes read before it.
+    pub async fn read_exact(&self, buf: Buffer) -> crate::Result<(), Buffer> {
+        self.inner.read_exact(buf).await
+    }
+
+    /// Writes every initialized byte of the buffer to the stream.
+    ///
+    /// Short writes are continued where they left off, across the segments of the buffer,
+    /// until everything has been sent.
+    ///
+    /// # Errors
+    ///
+    /// This function returns the first error a write returns, or
+    /// [`WriteZero`](std::io::ErrorKind::WriteZero) if a write makes no progress. The buffer
+    /// is returned in either case, but how much of it was sent is unknown.
+    pub async fn write_all(&self, buf: Buffer) -> crate::Result<(), Buffer> {
+        self.inner.write_all(buf).await
+    }
+
     /// Write data from buffers into this socket returning how many bytes were
     /// written.
     ///
@@ -168,6 +389,25 @@ impl UnixStream {
         self.inner.write(buf).submit().await
     }
 
+    /// Waits until the stream is readable: data is waiting, or the peer closed its write half.
+    ///
+    /// Nothing is read; the wait is an io_uring poll, cancelled if the future is dropped. See
+    /// [`TcpStream::readable`](crate::net::TcpStream::readable).
+    pub async fn readable(&self) -> io::Result<()> {
+        self.inner.ready(Interest::READABLE).await.map(|_| ())
+    }
+
+    /// Waits until the stream is writable, with room in its send buffer.
+    pub async fn writable(&self) -> io::Result<()> {
+        self.inner.ready(Interest::WRITABLE).await.map(|_| ())
+    }
+
+    /// Waits until any of the events in `interest` fire, and returns those that did, along
+    /// with errors and hang-ups.
+    pub async fn ready(&self, interest: Interest) -> io::Result<Ready> {
+        self.inner.ready(interest).await
+    }
+
     /// Shuts down the read, write, or both halves of this connection.
     ///
     /// This function will cause all pending and future I/O on the specified portions to return
@@ -175,6 +415,13 @@ impl UnixStream {
     pub fn shutdown(&self, how: std::net::Shutdown) -> io::Result<()> {
         self.inner.shutdown(how)
     }
+
+    /// Returns and clears the error pending on the socket (`SO_ERROR`), if any.
+    ///
+    /// See [`TcpStream::take_error`](crate::net::TcpStream::take_error).
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        self.inner.take_error()
+    }
 }
 
 impl FromRawFd for UnixStream {
@@ -188,3 +435,23 @@ impl AsRawFd for UnixStream {
         self.inner.as_raw_fd()
     }
 }
+
+// Takes ownership of the descriptors in the `SCM_RIGHTS` messages of `cmsgs`.
+fn received_fds(cmsgs: CMsgs) -> Vec<OwnedFd> {
+    let mut fds = Vec::new();
+    for msg in cmsgs {
+        if let ControlMessage::Other {
+            level: libc::SOL_SOCKET,
+            ty: libc::SCM_RIGHTS,
+            data,
+        } = msg
+        {
+            for fd in data.chunks_exact(mem::size_of::<RawFd>()) {
+                let fd = RawFd::from_ne_bytes(fd.try_into().unwrap());
+                // Safety: the kernel installed the descriptor in this process for us alone.
+                fds.push(unsafe { OwnedFd::from_raw_fd(fd) });
+            }
+        }
+    }
+    fds
+}
@@ -0,0 +1,68 @@
+use std::io;
+use std::os::unix::io::AsRawFd;
+
+/// Credentials of the process at the other end of a Unix stream, returned by
+/// [`UnixStream::peer_cred`](super::UnixStream::peer_cred).
+///
+/// The kernel records them when the connection is made, by `connect(2)` or `socketpair(2)`,
+/// so they describe the peer as it was then.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct UCred {
+    pid: libc::pid_t,
+    uid: libc::uid_t,
+    gid: libc::gid_t,
+}
+
+impl UCred {
+    /// Returns the process id of the peer.
+    pub fn pid(&self) -> libc::pid_t {
+        self.pid
+    }
+
+    /// Returns the effective user id of the peer.
+    pub fn uid(&self) -> libc::uid_t {
+        self.uid
+    }
+
+    /// Returns the effective group id of the peer.
+    pub fn gid(&self) -> libc::gid_t {
+        self.gid
+    }
+}
+
+// Reads SO_PEERCRED, which only connection-oriented sockets carry.
+pub(crate) fn peer_cred(fd: &impl AsRawFd) -> io::Result<UCred> {
+    let ty = socket2::SockRef::from(fd).r#type()?;
+    if ty != socket2::Type::STREAM && ty != socket2::Type::SEQPACKET {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "peer credentials are only recorded for connected stream sockets",
+        ));
+    }
+
+    // Safety: ucred is a plain C struct, for which zeroes are valid.
+    let mut cred: libc::ucred = unsafe { std::mem::zeroed() };
+    let mut len = std::mem::size_of_val(&cred) as libc::socklen_t;
+    // Safety: the kernel writes at most `len` bytes into `cred`.
+    let res = unsafe {
+        libc::getsockopt(
+            fd.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            &mut cred as *mut _ as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    if res == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    // A socket that never connected reports no process, and the overflow ids.
+    if cred.pid == 0 {
+        return Err(io::ErrorKind::NotConnected.into());
+    }
+    Ok(UCred {
+        pid: cred.pid,
+        uid: cred.uid,
+        gid: cred.gid,
+    })
+}
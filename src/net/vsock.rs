@@ -0,0 +1,245 @@
+use crate::buf::Buffer;
+use crate::io::{AcceptFlags, AcceptGate, Interest, SharedFd, Socket};
+use crate::Unsubmitted;
+use std::fmt;
+use std::io;
+use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
+
+/// The address of a vsock socket: a context ID naming the machine, host or guest VM, and a
+/// port on it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct VsockAddr {
+    cid: u32,
+    port: u32,
+}
+
+impl VsockAddr {
+    /// Binds to any context ID of the local machine (`VMADDR_CID_ANY`).
+    pub const CID_ANY: u32 = libc::VMADDR_CID_ANY;
+
+    /// The hypervisor (`VMADDR_CID_HYPERVISOR`).
+    pub const CID_HYPERVISOR: u32 = libc::VMADDR_CID_HYPERVISOR;
+
+    /// The local machine, through the loopback transport (`VMADDR_CID_LOCAL`).
+    pub const CID_LOCAL: u32 = libc::VMADDR_CID_LOCAL;
+
+    /// The host, as seen from a guest (`VMADDR_CID_HOST`).
+    pub const CID_HOST: u32 = libc::VMADDR_CID_HOST;
+
+    /// Lets the kernel pick a free port (`VMADDR_PORT_ANY`).
+    pub const PORT_ANY: u32 = libc::VMADDR_PORT_ANY;
+
+    /// Creates an address from a context ID and a port.
+    pub fn new(cid: u32, port: u32) -> VsockAddr {
+        VsockAddr { cid, port }
+    }
+
+    /// Returns the context ID.
+    pub fn cid(&self) -> u32 {
+        self.cid
+    }
+
+    /// Returns the port.
+    pub fn port(&self) -> u32 {
+        self.port
+    }
+
+    fn to_sockaddr(self) -> io::Result<socket2::SockAddr> {
+        socket2::SockAddr::vsock(self.cid, self.port)
+    }
+
+    fn from_sockaddr(addr: &socket2::SockAddr) -> io::Result<VsockAddr> {
+        addr.vsock_address()
+            .map(|(cid, port)| VsockAddr::new(cid, port))
+            .ok_or_else(|| io::Error::other("Could not get socket vsock address"))
+    }
+}
+
+impl fmt::Display for VsockAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.cid, self.port)
+    }
+}
+
+// Creates a vsock socket, telling a kernel without vsock support apart from other failures.
+fn new_socket() -> io::Result<Socket> {
+    Socket::new_vsock(libc::SOCK_STREAM).map_err(|e| match e.raw_os_error() {
+        Some(libc::EAFNOSUPPORT) => io::Error::new(
+            io::ErrorKind::Unsupported,
+            "AF_VSOCK is not supported; the vsock kernel module may not be loaded",
+        ),
+        _ => e,
+    })
+}
+
+/// A vsock server, listening for connections from the host or from guest VMs.
+///
+/// # Examples
+///
+/// ```no_run
+/// use tokio_uring::net::{VsockAddr, VsockListener};
+///
+/// tokio_uring::start(async {
+///     let listener = VsockListener::bind(VsockAddr::CID_ANY, 5000).unwrap();
+///
+///     loop {
+///         let (stream, peer) = listener.accept().await.unwrap();
+///         println!("connection from CID {}", peer.cid());
+///         tokio_uring::spawn(async move {
+///             let (n, buf) = stream.read(vec![0; 4096].into()).await.unwrap();
+///             // ...
+///         });
+///     }
+/// });
+/// ```
+pub struct VsockListener {
+    inner: Socket,
+}
+
+impl VsockListener {
+    /// Creates a listener bound to `port` on the context ID `cid`, usually
+    /// [`VsockAddr::CID_ANY`].
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`Unsupported`](io::ErrorKind::Unsupported) if the kernel lacks vsock
+    /// support, and with [`AddrNotAvailable`](io::ErrorKind::AddrNotAvailable) for a context
+    /// ID no transport serves, such as [`VsockAddr::CID_LOCAL`] without the loopback
+    /// transport.
+    pub fn bind(cid: u32, port: u32) -> io::Result<VsockListener> {
+        let socket = new_socket()?;
+        socket2::SockRef::from(&socket).bind(&VsockAddr::new(cid, port).to_sockaddr()?)?;
+        socket.listen(1024)?;
+        Ok(VsockListener { inner: socket })
+    }
+
+    /// Returns the local address that this listener is bound to.
+    pub fn local_addr(&self) -> io::Result<VsockAddr> {
+        VsockAddr::from_sockaddr(&socket2::SockRef::from(&self.inner).local_addr()?)
+    }
+
+    /// Accepts a new incoming connection, returning it along with the address of the peer.
+    pub async fn accept(&self) -> io::Result<(VsockStream, VsockAddr)> {
+        let (socket, addr) = self
+            .inner
+            .accept(AcceptFlags::default(), &AcceptGate::default())
+            .await?;
+        Ok((
+            VsockStream { inner: socket },
+            VsockAddr::from_sockaddr(&addr)?,
+        ))
+    }
+}
+
+impl FromRawFd for VsockListener {
+    unsafe fn from_raw_fd(fd: RawFd) -> Self {
+        VsockListener {
+            inner: Socket::from_shared_fd(SharedFd::new(fd)),
+        }
+    }
+}
+
+impl AsRawFd for VsockListener {
+    fn as_raw_fd(&self) -> RawFd {
+        self.inner.as_raw_fd()
+    }
+}
+
+/// A vsock stream between the local machine and the host or a guest VM.
+///
+/// Created by [connecting](VsockStream::connect) or by accepting a connection from a
+/// [`VsockListener`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use tokio_uring::net::{VsockAddr, VsockStream};
+///
+/// tokio_uring::start(async {
+///     // From a guest, connect to a service on the host.
+///     let stream = VsockStream::connect(VsockAddr::CID_HOST, 5000).await.unwrap();
+///     stream.write_all(b"hello".to_vec().into()).await.unwrap();
+/// });
+/// ```
+pub struct VsockStream {
+    inner: Socket,
+}
+
+impl VsockStream {
+    /// Opens a connection to `port` on the machine with context ID `cid`.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`Unsupported`](io::ErrorKind::Unsupported) if the kernel lacks vsock
+    /// support.
+    pub async fn connect(cid: u32, port: u32) -> io::Result<VsockStream> {
+        let socket = new_socket()?;
+        socket
+            .connect(VsockAddr::new(cid, port).to_sockaddr()?)
+            .await?;
+        Ok(VsockStream { inner: socket })
+    }
+
+    /// Returns the local address of the stream.
+    pub fn local_addr(&self) -> io::Result<VsockAddr> {
+        VsockAddr::from_sockaddr(&socket2::SockRef::from(&self.inner).local_addr()?)
+    }
+
+    /// Returns the address of the peer the stream is connected to.
+    pub fn peer_addr(&self) -> io::Result<VsockAddr> {
+        VsockAddr::from_sockaddr(&socket2::SockRef::from(&self.inner).peer_addr()?)
+    }
+
+    /// Read some data from the stream into the buffer, returning the original buffer and
+    /// quantity of data read.
+    pub async fn read(&self, buf: Buffer) -> crate::Result<usize, Buffer> {
+        self.inner.read(buf).await
+    }
+
+    /// Reads from the stream until the whole capacity of the buffer is filled. See
+    /// [`TcpStream::read_exact`](crate::net::TcpStream::read_exact).
+    pub async fn read_exact(&self, buf: Buffer) -> crate::Result<(), Buffer> {
+        self.inner.read_exact(buf).await
+    }
+
+    /// Write some data to the stream from the buffer, returning the original buffer and
+    /// quantity of data written.
+    pub fn write(&self, buf: Buffer) -> Unsubmitted {
+        self.inner.write(buf)
+    }
+
+    /// Writes every initialized byte of the buffer to the stream. See
+    /// [`TcpStream::write_all`](crate::net::TcpStream::write_all).
+    pub async fn write_all(&self, buf: Buffer) -> crate::Result<(), Buffer> {
+        self.inner.write_all(buf).await
+    }
+
+    /// Waits until the stream is readable: data is waiting, or the peer closed its write half.
+    pub async fn readable(&self) -> io::Result<()> {
+        self.inner.ready(Interest::READABLE).await.map(|_| ())
+    }
+
+    /// Waits until the stream is writable, with room in its send buffer.
+    pub async fn writable(&self) -> io::Result<()> {
+        self.inner.ready(Interest::WRITABLE).await.map(|_| ())
+    }
+
+    /// Shuts down the read, write, or both halves of this connection.
+    pub fn shutdown(&self, how: std::net::Shutdown) -> io::Result<()> {
+        self.inner.shutdown(how)
+    }
+}
+
+impl FromRawFd for VsockStream {
+    unsafe fn from_raw_fd(fd: RawFd) -> Self {
+        VsockStream {
+            inner: Socket::from_shared_fd(SharedFd::new(fd)),
+        }
+    }
+}
+
+impl AsRawFd for VsockStream {
+    fn as_raw_fd(&self) -> RawFd {
+        self.inner.as_raw_fd()
+    }
+}
@@ -18,37 +18,63 @@ use std::io;
 use std::ops::Deref;
 use std::os::unix::io::{AsRawFd, RawFd};
 use std::rc::{Rc, Weak};
+use std::sync::atomic::{AtomicBool, AtomicUsize};
+use std::sync::{Arc, Mutex};
 use std::task::{Context, Poll};
 
-use crate::runtime::driver::op::{Completable, MultiCQEFuture, Op, Updateable};
+use crate::buf::bufring::Ring;
+use crate::runtime::driver::op::{
+    Completable, CqeResult, MultiCQEFuture, MultiCQEStream, Op, Updateable,
+};
 use crate::runtime::driver::Driver;
 
 #[derive(Clone)]
 pub struct Handle {
     pub(super) inner: Rc<RefCell<Driver>>,
+
+    // Direct descriptor slots released while the driver was borrowed, such as by the data of
+    // an ignored op dropped as its completion is dispatched. They are freed once the borrow
+    // ends.
+    deferred_frees: Rc<RefCell<Vec<u32>>>,
 }
 
 #[derive(Clone)]
 pub(crate) struct WeakHandle {
     inner: Weak<RefCell<Driver>>,
+    deferred_frees: Rc<RefCell<Vec<u32>>>,
 }
 
 impl Handle {
     pub(crate) fn new(b: &crate::Builder) -> io::Result<Self> {
-        Ok(Self {
-            inner: Rc::new(RefCell::new(Driver::new(b)?)),
-        })
+        Ok(Driver::new(b)?.into())
     }
 
     pub(crate) fn dispatch_completions(&self) {
-        self.inner.borrow_mut().dispatch_completions()
+        self.inner.borrow_mut().dispatch_completions();
+        self.free_deferred_direct();
     }
 
     pub(crate) fn flush(&self) -> io::Result<usize> {
         self.inner.borrow_mut().uring.submit()
     }
 
-    pub(crate) fn register_buffers(&self, buffers: &[libc::iovec]) -> io::Result<()> {
+    /// SQEs pushed for ops so far; a test hook to tell which ops reach the ring.
+    #[allow(unused)]
+    pub(crate) fn sqes_pushed(&self) -> u64 {
+        self.inner.borrow().sqes_pushed()
+    }
+
+    /// Number of entries in the submission queue, the most that can be pushed at once.
+    pub(crate) fn sq_capacity(&self) -> usize {
+        self.inner.borrow().uring.params().sq_entries() as usize
+    }
+
+    /// Whether the kernel supports `opcode`, according to the ring's probe.
+    pub(crate) fn is_supported(&self, opcode: u8) -> bool {
+        self.inner.borrow_mut().is_supported(opcode)
+    }
+
+    pub(crate) fn register_buffers(&self, buffers: &[libc::iovec]) -> io::Result<Arc<AtomicBool>> {
         self.inner.borrow_mut().register_buffers(buffers)
     }
 
@@ -56,6 +82,56 @@ impl Handle {
         self.inner.borrow_mut().unregister_buffers()
     }
 
+    pub(crate) fn pin_fixed_buffers(&self, pinned: Arc<AtomicUsize>) {
+        self.inner.borrow_mut().pin_fixed_buffers(pinned)
+    }
+
+    pub(crate) fn register_buffers_sparse(&self, nr: u32) -> io::Result<Arc<AtomicBool>> {
+        self.inner.borrow_mut().register_buffers_sparse(nr)
+    }
+
+    pub(crate) fn register_buffers_update(
+        &self,
+        offset: u32,
+        buffers: &[libc::iovec],
+    ) -> io::Result<()> {
+        self.inner
+            .borrow_mut()
+            .register_buffers_update(offset, buffers)
+    }
+
+    pub(crate) fn register_buf_ring(&self, ring: Arc<Mutex<Ring>>) -> io::Result<()> {
+        self.inner.borrow_mut().register_buf_ring(ring)
+    }
+
+    pub(crate) fn direct_files(&self) -> u32 {
+        self.inner.borrow().direct_files()
+    }
+
+    /// Frees the direct descriptor in `slot`, or defers it until the driver is no longer
+    /// borrowed, as it is while completions are dispatched.
+    pub(crate) fn free_direct(&self, slot: u32) -> io::Result<()> {
+        match self.inner.try_borrow() {
+            Ok(driver) => driver.free_direct(slot),
+            Err(_) => {
+                self.deferred_frees.borrow_mut().push(slot);
+                Ok(())
+            }
+        }
+    }
+
+    fn free_deferred_direct(&self) {
+        let slots = std::mem::take(&mut *self.deferred_frees.borrow_mut());
+        if slots.is_empty() {
+            return;
+        }
+        let driver = self.inner.borrow();
+        for slot in slots {
+            // As when the descriptor is dropped, there is no one to report a failure to.
+            let _ = driver.free_direct(slot);
+        }
+    }
+
     pub fn register_files(&self, fds: &[RawFd]) -> io::Result<()> {
         self.inner.borrow_mut().register_files(fds)
     }
@@ -80,6 +156,40 @@ impl Handle {
         self.inner.borrow_mut().submit_op(data, f, self.into())
     }
 
+    pub(crate) fn submit_op_big<T, S, F>(&self, data: T, f: F) -> io::Result<Op<T, S>>
+    where
+        T: Completable,
+        F: FnOnce(&mut T) -> squeue::Entry128,
+    {
+        self.inner.borrow_mut().submit_op_big(data, f, self.into())
+    }
+
+    pub(crate) fn submit_op_linked<T, S, F>(&self, data: T, f: F) -> io::Result<Op<T, S>>
+    where
+        T: Completable,
+        F: FnOnce(&mut T) -> (squeue::Entry, squeue::Entry),
+    {
+        self.inner
+            .borrow_mut()
+            .submit_op_linked(data, f, self.into())
+    }
+
+    pub(crate) fn submit_op_pair<T, U, F, G>(
+        &self,
+        first: (T, F),
+        second: (U, G),
+    ) -> io::Result<(Op<T>, Op<U>)>
+    where
+        T: Completable,
+        U: Completable,
+        F: FnOnce(&mut T) -> squeue::Entry,
+        G: FnOnce(&mut U) -> squeue::Entry,
+    {
+        self.inner
+            .borrow_mut()
+            .submit_op_pair(first, second, self.into())
+    }
+
     pub(crate) fn poll_op<T>(&self, op: &mut Op<T>, cx: &mut Context<'_>) -> Poll<T::Output>
     where
         T: Unpin + 'static + Completable,
@@ -102,6 +212,22 @@ impl Handle {
         self.inner.borrow_mut().poll_multishot_op(op, cx)
     }
 
+    pub(crate) fn poll_multishot_stream<T>(
+        &self,
+        op: &mut Op<T, MultiCQEStream>,
+        cx: &mut Context<'_>,
+    ) -> Poll<CqeResult>
+    where
+        T: Unpin + 'static + Completable,
+    {
+        self.inner.borrow_mut().poll_multishot_stream(op, cx)
+    }
+
+    pub(crate) fn cancel_op(&self, index: usize) {
+        self.inner.borrow_mut().cancel_op(index);
+        self.free_deferred_direct();
+    }
+
     pub(crate) fn remove_op<T, CqeType>(&self, op: &mut Op<T, CqeType>) {
         self.inner.borrow_mut().remove_op(op)
     }
@@ -115,6 +241,7 @@ impl WeakHandle {
     pub(crate) fn upgrade(&self) -> Option<Handle> {
         Some(Handle {
             inner: self.inner.upgrade()?,
+            deferred_frees: self.deferred_frees.clone(),
         })
     }
 }
@@ -129,6 +256,7 @@ impl From<Driver> for Handle {
     fn from(driver: Driver) -> Self {
         Self {
             inner: Rc::new(RefCell::new(driver)),
+            deferred_frees: Rc::new(RefCell::new(Vec::new())),
         }
     }
 }
@@ -140,6 +268,7 @@ where
     fn from(handle: T) -> Self {
         Self {
             inner: Rc::downgrade(&handle.inner),
+            deferred_frees: handle.deferred_frees.clone(),
         }
     }
 }
@@ -1,10 +1,15 @@
-use crate::runtime::driver::op::{Completable, Lifecycle, MultiCQEFuture, Op, Updateable};
+use crate::runtime::driver::op::{
+    Completable, CqeResult, Lifecycle, MultiCQEFuture, MultiCQEStream, Op, Updateable,
+};
 
+use crate::buf::bufring::Ring;
 use io_uring::opcode::AsyncCancel;
-use io_uring::{cqueue, squeue, IoUring};
+use io_uring::{cqueue, squeue};
 use slab::Slab;
 
 use std::os::unix::io::{AsRawFd, RawFd};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 
 use std::task::{Context, Poll};
 use std::{io, mem};
@@ -14,12 +19,58 @@ pub(crate) use handle::*;
 mod handle;
 pub(crate) mod op;
 
+mod uring;
+use uring::Uring;
+
+// Not exported by the io-uring or libc crates; from linux/io_uring.h.
+const IORING_REGISTER_BUFFERS2: libc::c_uint = 15;
+const IORING_REGISTER_BUFFERS_UPDATE: libc::c_uint = 16;
+const IORING_RSRC_REGISTER_SPARSE: u32 = 1 << 0;
+
+#[repr(C)]
+struct RsrcRegister {
+    nr: u32,
+    flags: u32,
+    resv2: u64,
+    data: u64,
+    tags: u64,
+}
+
+#[repr(C)]
+struct RsrcUpdate2 {
+    offset: u32,
+    resv: u32,
+    data: u64,
+    tags: u64,
+    nr: u32,
+    resv2: u32,
+}
+
 pub(crate) struct Driver {
     /// In-flight operations
     ops: Ops,
 
     /// IoUring bindings
-    uring: IoUring,
+    uring: Uring,
+
+    /// Supported opcodes, probed on first use
+    probe: Option<io_uring::Probe>,
+
+    /// Provided buffer rings registered with the kernel, whose memory must outlive `uring`
+    buf_rings: Vec<Arc<Mutex<Ring>>>,
+
+    /// Size of the sparse table of direct descriptors, 0 if none was registered
+    direct_files: u32,
+
+    /// Set while the fixed buffers registered last are, cleared once they are unregistered
+    fixed_buffers: Option<Arc<AtomicBool>>,
+
+    /// Number of buffers checked out from the fixed buffers registered last, if those are
+    /// memory regions the caller owns, which must stay registered while any is
+    fixed_buffers_pinned: Option<Arc<AtomicUsize>>,
+
+    /// SQEs pushed for ops and their cancellations since the driver was created
+    sqes_pushed: u64,
 }
 
 struct Ops {
@@ -33,12 +84,23 @@ struct Ops {
 
 impl Driver {
     pub(crate) fn new(b: &crate::Builder) -> io::Result<Driver> {
-        let uring = b.urb.build(b.entries)?;
+        let uring = match &b.urb_big {
+            Some(urb) => Uring::Big(urb.build(b.entries)?),
+            None => Uring::Default(b.urb.build(b.entries)?),
+        };
+        if b.direct_files > 0 {
+            uring.submitter().register_files_sparse(b.direct_files)?;
+        }
 
         Ok(Driver {
             ops: Ops::new(),
             uring,
-            // fixed_buffers: None,
+            probe: None,
+            buf_rings: Vec::new(),
+            direct_files: b.direct_files,
+            fixed_buffers: None,
+            fixed_buffers_pinned: None,
+            sqes_pushed: 0,
         })
     }
 
@@ -52,11 +114,15 @@ impl Driver {
         self.ops.lifecycle.len()
     }
 
+    pub(crate) fn sqes_pushed(&self) -> u64 {
+        self.sqes_pushed
+    }
+
     pub(crate) fn submit(&mut self) -> io::Result<()> {
         loop {
             match self.uring.submit() {
                 Ok(_) => {
-                    self.uring.submission().sync();
+                    self.uring.sync_submission();
                     return Ok(());
                 }
                 Err(ref e) if e.raw_os_error() == Some(libc::EBUSY) => {
@@ -71,29 +137,143 @@ impl Driver {
     }
 
     pub(crate) fn dispatch_completions(&mut self) {
-        let mut cq = self.uring.completion();
-        cq.sync();
-
-        for cqe in cq {
+        let ops = &mut self.ops;
+        self.uring.for_each_completion(|cqe, big_cqe| {
             if cqe.user_data() == u64::MAX {
-                // Result of the cancellation action. There isn't anything we
-                // need to do here. We must wait for the CQE for the operation
-                // that was canceled.
-                continue;
+                // Result of the cancellation action, or of an SQE linked to an op.
+                // There isn't anything we need to do here. We must wait for the CQE
+                // for the operation that was canceled.
+                return;
             }
 
             let index = cqe.user_data() as _;
 
-            self.ops.complete(index, cqe);
+            ops.complete(index, cqe, big_cqe);
+        });
+    }
+
+    pub(crate) fn is_supported(&mut self, opcode: u8) -> bool {
+        let uring = &self.uring;
+        self.probe
+            .get_or_insert_with(|| {
+                let mut probe = io_uring::Probe::new();
+                // An unprobeable ring reports nothing as supported.
+                let _ = uring.submitter().register_probe(&mut probe);
+                probe
+            })
+            .is_supported(opcode)
+    }
+
+    /// Registers fixed buffers, returning a flag that stays set until they are unregistered.
+    pub(crate) fn register_buffers(
+        &mut self,
+        buffers: &[libc::iovec],
+    ) -> io::Result<Arc<AtomicBool>> {
+        unsafe { self.uring.submitter().register_buffers(buffers) }?;
+        Ok(self.track_fixed_buffers())
+    }
+
+    pub(crate) fn unregister_buffers(&mut self) -> io::Result<()> {
+        let pinned = self.fixed_buffers_pinned.as_ref();
+        if pinned.is_some_and(|pinned| pinned.load(Ordering::Acquire) > 0) {
+            return Err(io::Error::new(
+                io::ErrorKind::ResourceBusy,
+                "buffers of the registered raw regions are still checked out",
+            ));
+        }
+        self.uring.submitter().unregister_buffers()?;
+        if let Some(registered) = self.fixed_buffers.take() {
+            registered.store(false, Ordering::Release);
+        }
+        self.fixed_buffers_pinned = None;
+        Ok(())
+    }
+
+    /// Refuses to unregister the fixed buffers registered last while `pinned` counts buffers
+    /// checked out from them.
+    pub(crate) fn pin_fixed_buffers(&mut self, pinned: Arc<AtomicUsize>) {
+        self.fixed_buffers_pinned = Some(pinned);
+    }
+
+    /// Registers a table of `nr` empty buffer slots, to fill with `register_buffers_update`.
+    pub(crate) fn register_buffers_sparse(&mut self, nr: u32) -> io::Result<Arc<AtomicBool>> {
+        let arg = RsrcRegister {
+            nr,
+            flags: IORING_RSRC_REGISTER_SPARSE,
+            resv2: 0,
+            data: 0,
+            tags: 0,
+        };
+        self.register(IORING_REGISTER_BUFFERS2, &arg)?;
+        Ok(self.track_fixed_buffers())
+    }
+
+    fn track_fixed_buffers(&mut self) -> Arc<AtomicBool> {
+        let registered = Arc::new(AtomicBool::new(true));
+        self.fixed_buffers = Some(registered.clone());
+        self.fixed_buffers_pinned = None;
+        registered
+    }
+
+    /// Replaces the registered buffers from slot `offset` on. An iovec with a null base empties
+    /// its slot.
+    pub(crate) fn register_buffers_update(
+        &self,
+        offset: u32,
+        buffers: &[libc::iovec],
+    ) -> io::Result<()> {
+        let arg = RsrcUpdate2 {
+            offset,
+            resv: 0,
+            data: buffers.as_ptr() as u64,
+            tags: 0,
+            nr: buffers.len() as u32,
+            resv2: 0,
+        };
+        self.register(IORING_REGISTER_BUFFERS_UPDATE, &arg)
+    }
+
+    // io_uring_register(2) for the opcodes the io-uring crate has no wrapper for.
+    fn register<T>(&self, opcode: libc::c_uint, arg: &T) -> io::Result<()> {
+        // Safety: `arg` is the struct the opcode expects, alive for the duration of the call.
+        let res = unsafe {
+            libc::syscall(
+                libc::SYS_io_uring_register,
+                self.uring.as_raw_fd(),
+                opcode,
+                arg as *const T,
+                mem::size_of::<T>() as libc::c_uint,
+            )
+        };
+        if res < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    pub(crate) fn register_buf_ring(&mut self, ring: Arc<Mutex<Ring>>) -> io::Result<()> {
+        {
+            let ring = ring.lock().unwrap();
+            unsafe {
+                self.uring.submitter().register_buf_ring(
+                    ring.addr(),
+                    ring.entries(),
+                    ring.bgid(),
+                )?
+            };
         }
+        self.buf_rings.push(ring);
+        Ok(())
     }
 
-    pub(crate) fn register_buffers(&self, buffers: &[libc::iovec]) -> io::Result<()> {
-        unsafe { self.uring.submitter().register_buffers(buffers) }
+    pub(crate) fn direct_files(&self) -> u32 {
+        self.direct_files
     }
 
-    pub(crate) fn unregister_buffers(&self) -> io::Result<()> {
-        self.uring.submitter().unregister_buffers()
+    /// Frees the direct descriptor in `slot`, closing the file unless requests still hold it.
+    pub(crate) fn free_direct(&self, slot: u32) -> io::Result<()> {
+        self.uring.submitter().register_files_update(slot, &[-1])?;
+        Ok(())
     }
 
     pub(crate) fn register_files(&mut self, fds: &[RawFd]) -> io::Result<()> {
@@ -115,10 +295,11 @@ impl Driver {
         let sqe = sqe.user_data(index as _);
 
         // Push the new operation
-        while unsafe { self.uring.submission().push(&sqe).is_err() } {
+        while unsafe { self.uring.push(&sqe).is_err() } {
             // If the submission queue is full, flush it to the kernel
             self.submit().expect("Internal error, failed to submit ops");
         }
+        self.sqes_pushed += 1;
 
         index
     }
@@ -135,10 +316,11 @@ impl Driver {
             entries.push(sqe);
         }
 
-        while unsafe { self.uring.submission().push_multiple(&entries).is_err() } {
+        while unsafe { self.uring.push_multiple(&entries).is_err() } {
             // If the submission queue is full, flush it to the kernel
             self.submit().expect("Internal error, failed to submit ops");
         }
+        self.sqes_pushed += entries.len() as u64;
 
         indices
     }
@@ -162,15 +344,130 @@ impl Driver {
         let op = Op::new(handle, data, index);
 
         // Push the new operation
-        while unsafe { self.uring.submission().push(&sqe).is_err() } {
+        while unsafe { self.uring.push(&sqe).is_err() } {
+            // If the submission queue is full, flush it to the kernel
+            self.submit()?;
+        }
+        self.sqes_pushed += 1;
+
+        Ok(op)
+    }
+
+    /// Like `submit_op`, for an op whose SQE needs a ring of 128-byte entries. Fails with
+    /// `Unsupported` if the ring was not set up with them.
+    pub(crate) fn submit_op_big<T, S, F>(
+        &mut self,
+        mut data: T,
+        f: F,
+        handle: WeakHandle,
+    ) -> io::Result<Op<T, S>>
+    where
+        T: Completable,
+        F: FnOnce(&mut T) -> squeue::Entry128,
+    {
+        if !self.uring.is_big() {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "the op needs 128-byte SQEs; start the runtime with `Builder::uring_builder_big`",
+            ));
+        }
+        let index = self.ops.insert();
+
+        // Configure the SQE
+        let sqe = f(&mut data).user_data(index as _);
+
+        // Create the operation
+        let op = Op::new(handle, data, index);
+
+        // Push the new operation
+        while unsafe { self.uring.push_big(&sqe).is_err() } {
+            // If the submission queue is full, flush it to the kernel
+            self.submit()?;
+        }
+        self.sqes_pushed += 1;
+
+        Ok(op)
+    }
+
+    /// Like `submit_op`, but `f` also returns an SQE linked after the op's own, such as a
+    /// link timeout. The completion of the linked SQE is discarded.
+    pub(crate) fn submit_op_linked<T, S, F>(
+        &mut self,
+        mut data: T,
+        f: F,
+        handle: WeakHandle,
+    ) -> io::Result<Op<T, S>>
+    where
+        T: Completable,
+        F: FnOnce(&mut T) -> (squeue::Entry, squeue::Entry),
+    {
+        let index = self.ops.insert();
+
+        // Configure the SQEs; the pair must be pushed contiguously for the link to hold
+        let (sqe, linked) = f(&mut data);
+        let entries = [
+            sqe.flags(squeue::Flags::IO_LINK).user_data(index as _),
+            linked.user_data(u64::MAX),
+        ];
+
+        // Create the operation
+        let op = Op::new(handle, data, index);
+
+        while unsafe { self.uring.push_multiple(&entries).is_err() } {
             // If the submission queue is full, flush it to the kernel
             self.submit()?;
         }
+        self.sqes_pushed += entries.len() as u64;
 
         Ok(op)
     }
 
+    /// Submits two ops whose SQEs are linked, so the second starts only once the first has
+    /// completed in full. Unlike `submit_op_linked`, each op gets its own completion.
+    pub(crate) fn submit_op_pair<T, U, F, G>(
+        &mut self,
+        (mut first, f): (T, F),
+        (mut second, g): (U, G),
+        handle: WeakHandle,
+    ) -> io::Result<(Op<T>, Op<U>)>
+    where
+        T: Completable,
+        U: Completable,
+        F: FnOnce(&mut T) -> squeue::Entry,
+        G: FnOnce(&mut U) -> squeue::Entry,
+    {
+        let first_index = self.ops.insert();
+        let second_index = self.ops.insert();
+
+        // The pair must be pushed contiguously for the link to hold
+        let entries = [
+            f(&mut first)
+                .flags(squeue::Flags::IO_LINK)
+                .user_data(first_index as _),
+            g(&mut second).user_data(second_index as _),
+        ];
+
+        let ops = (
+            Op::new(handle.clone(), first, first_index),
+            Op::new(handle, second, second_index),
+        );
+
+        while unsafe { self.uring.push_multiple(&entries).is_err() } {
+            // If the submission queue is full, flush it to the kernel
+            self.submit()?;
+        }
+        self.sqes_pushed += entries.len() as u64;
+
+        Ok(ops)
+    }
+
     pub(crate) fn remove_op<T, CqeType>(&mut self, op: &mut Op<T, CqeType>) {
+        if op.is_finished() {
+            // The final completion was polled, which already removed the op. Its index may
+            // since have been reused.
+            return;
+        }
+
         // Get the Op Lifecycle state from the driver
         let (lifecycle, completions) = match self.ops.get_mut(op.index()) {
             Some(val) => val,
@@ -257,7 +554,7 @@ impl Driver {
                 Poll::Pending
             }
             Lifecycle::Ignored(..) => unreachable!(),
-            Lifecycle::Completed(cqe) => {
+            Lifecycle::Completed(cqe, _) => {
                 self.ops.remove(index);
                 Poll::Ready(cqe)
             }
@@ -290,9 +587,9 @@ impl Driver {
                 Poll::Pending
             }
             Lifecycle::Ignored(..) => unreachable!(),
-            Lifecycle::Completed(cqe) => {
+            Lifecycle::Completed(cqe, big_cqe) => {
                 self.ops.remove(op.index());
-                Poll::Ready(op.take_data().unwrap().complete(cqe.into()))
+                Poll::Ready(op.take_data().unwrap().complete((cqe, big_cqe).into()))
             }
             Lifecycle::CompletionList(..) => {
                 unreachable!("No `more` flag set for SingleCQE")
@@ -327,11 +624,11 @@ impl Driver {
                 Poll::Pending
             }
             Lifecycle::Ignored(..) => unreachable!(),
-            Lifecycle::Completed(cqe) => {
+            Lifecycle::Completed(cqe, big_cqe) => {
                 // This is possible. We may have previously polled a CompletionList,
                 // and the final CQE registered as Completed
                 self.ops.remove(op.index());
-                Poll::Ready(op.take_data().unwrap().complete(cqe.into()))
+                Poll::Ready(op.take_data().unwrap().complete((cqe, big_cqe).into()))
             }
             Lifecycle::CompletionList(indices) => {
                 let mut data = op.take_data().unwrap();
@@ -361,6 +658,68 @@ impl Driver {
             }
         }
     }
+
+    pub(crate) fn poll_multishot_stream<T>(
+        &mut self,
+        op: &mut Op<T, MultiCQEStream>,
+        cx: &mut Context<'_>,
+    ) -> Poll<CqeResult>
+    where
+        T: Unpin + 'static + Completable,
+    {
+        let (lifecycle, completions) = self
+            .ops
+            .get_mut(op.index())
+            .expect("invalid internal state");
+
+        match mem::replace(lifecycle, Lifecycle::Submitted) {
+            Lifecycle::Submitted => {
+                *lifecycle = Lifecycle::Waiting(cx.waker().clone());
+                Poll::Pending
+            }
+            Lifecycle::Waiting(waker) if !waker.will_wake(cx.waker()) => {
+                *lifecycle = Lifecycle::Waiting(cx.waker().clone());
+                Poll::Pending
+            }
+            Lifecycle::Waiting(waker) => {
+                *lifecycle = Lifecycle::Waiting(waker);
+                Poll::Pending
+            }
+            Lifecycle::Ignored(..) => unreachable!(),
+            Lifecycle::Completed(cqe, big_cqe) => {
+                self.ops.remove(op.index());
+                op.take_data();
+                Poll::Ready((cqe, big_cqe).into())
+            }
+            Lifecycle::CompletionList(indices) => {
+                // Hand out the completions one at a time, in the order they arrived
+                let mut list = indices.into_list(completions);
+                let cqe = list.pop().expect("empty completion list");
+                if !cqueue::more(cqe.flags) {
+                    drop(list);
+                    self.ops.remove(op.index());
+                    op.take_data();
+                } else if list.is_empty() {
+                    *lifecycle = Lifecycle::Submitted;
+                } else {
+                    *lifecycle = Lifecycle::CompletionList(list.into_indices());
+                }
+                Poll::Ready(cqe)
+            }
+        }
+    }
+
+    /// Submits a cancellation for the op at `index` and dispatches whatever completions the
+    /// kernel posts while processing it.
+    pub(crate) fn cancel_op(&mut self, index: usize) {
+        let cancel = AsyncCancel::new(index as u64).build().user_data(u64::MAX);
+        while unsafe { self.uring.push(&cancel).is_err() } {
+            self.submit().expect("Internal error, failed to submit ops");
+        }
+        self.sqes_pushed += 1;
+        self.submit().expect("Internal error, failed to submit ops");
+        self.dispatch_completions();
+    }
 }
 
 impl AsRawFd for Driver {
@@ -376,13 +735,13 @@ impl AsRawFd for Driver {
 /// It is possible for this to be run without previously dropping the runtime, but this should only
 /// be possible in the case of [`std::process::exit`].
 ///
-/// This depends on us knowing when ops are completed and done firing.
-/// When multishot ops are added (support exists but none are implemented), a way to know if such
-/// an op is finished MUST be added, otherwise our shutdown process is unsound.
+/// This depends on us knowing when ops are completed and done firing. A multishot op is only
+/// finished once a CQE without the `more` flag has arrived, so those are cancelled like any other
+/// op still in flight.
 impl Drop for Driver {
     fn drop(&mut self) {
         // get all ops in flight for cancellation
-        while !self.uring.submission().is_empty() {
+        while !self.uring.submission_is_empty() {
             self.submit().expect("Internal error when dropping driver");
         }
 
@@ -390,7 +749,7 @@ impl Drop for Driver {
         // After this pass, all LifeCycles will be marked either as Completed or Ignored, as appropriate
         for (_, cycle) in self.ops.lifecycle.iter_mut() {
             match std::mem::replace(cycle, Lifecycle::Ignored(Box::new(()))) {
-                lc @ Lifecycle::Completed(_) => {
+                lc @ Lifecycle::Completed(..) => {
                     // don't cancel completed items
                     *cycle = lc;
                 }
@@ -401,7 +760,7 @@ impl Drop for Driver {
                         // This op is complete. Replace with a null Completed entry
                         // safety: zeroed memory is entirely valid with this underlying
                         // representation
-                        *cycle = Lifecycle::Completed(unsafe { mem::zeroed() });
+                        *cycle = Lifecycle::Completed(unsafe { mem::zeroed() }, [0; 2]);
                     }
                 }
 
@@ -418,7 +777,6 @@ impl Drop for Driver {
                 unsafe {
                     while self
                         .uring
-                        .submission()
                         .push(&AsyncCancel::new(id as u64).build().user_data(u64::MAX))
                         .is_err()
                     {
@@ -490,9 +848,9 @@ impl Ops {
         self.lifecycle.remove(index);
     }
 
-    fn complete(&mut self, index: usize, cqe: cqueue::Entry) {
+    fn complete(&mut self, index: usize, cqe: cqueue::Entry, big_cqe: [u64; 2]) {
         let completions = &mut self.completions;
-        if self.lifecycle[index].complete(completions, cqe) {
+        if self.lifecycle[index].complete(completions, cqe, big_cqe) {
             self.lifecycle.remove(index);
         }
     }
@@ -503,7 +861,7 @@ impl Drop for Ops {
         assert!(self
             .lifecycle
             .iter()
-            .all(|(_, cycle)| matches!(cycle, Lifecycle::Completed(_))))
+            .all(|(_, cycle)| matches!(cycle, Lifecycle::Completed(..))))
     }
 }
 
@@ -647,12 +1005,11 @@ mod test {
         assert_eq!(1, num_operations());
 
         CONTEXT.with(|cx| {
-            cx.handle()
-                .unwrap()
-                .inner
-                .borrow_mut()
-                .ops
-                .complete(index, unsafe { mem::zeroed() })
+            cx.handle().unwrap().inner.borrow_mut().ops.complete(
+                index,
+                unsafe { mem::zeroed() },
+                [0; 2],
+            )
         });
 
         assert_eq!(1, Rc::strong_count(&data));
@@ -688,7 +1045,11 @@ mod test {
         CONTEXT.with(|cx| {
             let driver = cx.handle().unwrap();
 
-            driver.inner.borrow_mut().ops.complete(op.index(), cqe);
+            driver
+                .inner
+                .borrow_mut()
+                .ops
+                .complete(op.index(), cqe, [0; 2]);
         });
     }
 
@@ -46,7 +46,7 @@ impl<D1, T1: OneshotOutputTransform<StoredData = D1>, N> Link<UnsubmittedOneshot
             data: self.data.set_flags(Flags::IO_LINK),
             next: Link {
                 data: self.next,
-                next: other,
+                next: other.link_target(),
             },
         }
     }
@@ -60,7 +60,7 @@ impl<D1, T1: OneshotOutputTransform<StoredData = D1>, N> Link<UnsubmittedOneshot
             data: self.data.set_flags(Flags::IO_HARDLINK),
             next: Link {
                 data: self.next,
-                next: other,
+                next: other.link_target(),
             },
         }
     }
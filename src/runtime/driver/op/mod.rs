@@ -5,7 +5,7 @@ use std::pin::Pin;
 use std::task::{Context, Poll, Waker};
 
 use io_uring::squeue::Flags;
-use io_uring::{cqueue, squeue};
+use io_uring::{cqueue, opcode, squeue};
 
 mod link;
 mod slab_list;
@@ -29,6 +29,17 @@ pub struct UnsubmittedOneshot<D: 'static, T: OneshotOutputTransform<StoredData =
     post_op: T,
     #[allow(missing_docs)]
     pub sqe: squeue::Entry,
+    // Set for an operation with no bytes to transfer, which completes with a result of 0 on
+    // submission, without its SQE being pushed.
+    empty: bool,
+    // Set for an operation that cannot be started, which fails with this errno on
+    // submission. In a chain, `sqe` stands in for it, and fails too.
+    error: Option<i32>,
+    // The flags set on `sqe`, and whether an operation is linked to this one. An empty
+    // operation in a chain is pushed as a no-op, so the chain does not take in whatever
+    // SQE comes next.
+    flags: Flags,
+    link_target: bool,
 }
 
 impl<D, T: OneshotOutputTransform<StoredData = D>> UnsubmittedOneshot<D, T> {
@@ -38,15 +49,35 @@ impl<D, T: OneshotOutputTransform<StoredData = D>> UnsubmittedOneshot<D, T> {
             stable_data,
             post_op,
             sqe,
+            empty: false,
+            error: None,
+            flags: Flags::empty(),
+            link_target: false,
         }
     }
 
+    /// Marks the operation as transferring no bytes, so that submitting it completes it at
+    /// once with a result of 0, leaving the ring untouched. `sqe` is still built, for callers
+    /// that push it themselves.
+    pub(crate) fn set_empty(mut self, empty: bool) -> Self {
+        self.empty = empty;
+        self
+    }
+
+    /// Marks the operation as failing with `errno` on submission, without reaching the
+    /// ring. Within a chain, `sqe` is pushed in its place and must fail as well, so the
+    /// chain breaks as it would on the operation's own failure.
+    pub(crate) fn set_error(mut self, errno: i32) -> Self {
+        self.error = Some(errno);
+        self
+    }
+
     /// Link two UnsubmittedOneshots.
     pub fn link<D2, T2: OneshotOutputTransform<StoredData = D2>>(
         self,
         other: UnsubmittedOneshot<D2, T2>,
     ) -> Link<UnsubmittedOneshot<D, T>, UnsubmittedOneshot<D2, T2>> {
-        Link::new(self.set_flags(Flags::IO_LINK), other)
+        Link::new(self.set_flags(Flags::IO_LINK), other.link_target())
     }
 
     /// Hard-link two UnsubmittedOneshots.
@@ -54,12 +85,19 @@ impl<D, T: OneshotOutputTransform<StoredData = D>> UnsubmittedOneshot<D, T> {
         self,
         other: UnsubmittedOneshot<D2, T2>,
     ) -> Link<UnsubmittedOneshot<D, T>, UnsubmittedOneshot<D2, T2>> {
-        Link::new(self.set_flags(Flags::IO_HARDLINK), other)
+        Link::new(self.set_flags(Flags::IO_HARDLINK), other.link_target())
     }
 
     /// Set the SQE's flags.
     pub fn set_flags(mut self, flags: Flags) -> Self {
         self.sqe = self.sqe.flags(flags);
+        self.flags |= flags;
+        self
+    }
+
+    // Marks the operation as linked to the one submitted before it.
+    pub(crate) fn link_target(mut self) -> Self {
+        self.link_target = true;
         self
     }
 
@@ -70,7 +108,8 @@ impl<D, T: OneshotOutputTransform<StoredData = D>> UnsubmittedOneshot<D, T> {
             .expect("Could not submit op; not in runtime context");
 
         let inner = InFlightOneshotInner {
-            index,
+            index: Some(index),
+            error: self.error,
             driver: (&handle).into(),
             stable_data: self.stable_data,
             post_op: self.post_op,
@@ -89,10 +128,21 @@ impl<D, T: OneshotOutputTransform<StoredData = D>> Submit for UnsubmittedOneshot
             .with(|x| x.handle())
             .expect("Could not submit op; not in runtime context");
 
-        let index = handle.submit_op_2(self.sqe);
+        let in_chain =
+            self.link_target || self.flags.intersects(Flags::IO_LINK | Flags::IO_HARDLINK);
+        let index = match (self.empty || self.error.is_some(), in_chain) {
+            (false, _) => Some(handle.submit_op_2(self.sqe)),
+            (true, false) => None,
+            // Completes with a result of 0 too, keeping its place in the chain.
+            (true, true) if self.error.is_none() => {
+                Some(handle.submit_op_2(opcode::Nop::new().build().flags(self.flags)))
+            }
+            (true, true) => Some(handle.submit_op_2(self.sqe)),
+        };
 
         let inner = InFlightOneshotInner {
             index,
+            error: self.error,
             driver: (&handle).into(),
             stable_data: self.stable_data,
             post_op: self.post_op,
@@ -109,7 +159,9 @@ pub struct InFlightOneshot<D: 'static, T: OneshotOutputTransform<StoredData = D>
 
 struct InFlightOneshotInner<D, T: OneshotOutputTransform<StoredData = D>> {
     driver: driver::WeakHandle,
-    index: usize,
+    // `None` for an empty or failed operation outside a chain, which was never pushed.
+    index: Option<usize>,
+    error: Option<i32>,
     stable_data: D,
     post_op: T,
 }
@@ -125,14 +177,23 @@ impl<D: Unpin, T: OneshotOutputTransform<StoredData = D> + Unpin> Future for InF
             .as_mut()
             .expect("Cannot poll already-completed operation");
 
-        let index = inner.index;
+        let cqe = match inner.index {
+            Some(index) => {
+                let upgraded = inner
+                    .driver
+                    .upgrade()
+                    .expect("Failed to poll op: driver no longer exists");
 
-        let upgraded = inner
-            .driver
-            .upgrade()
-            .expect("Failed to poll op: driver no longer exists");
-
-        let cqe = ready!(upgraded.poll_op_2(index, cx));
+                ready!(upgraded.poll_op_2(index, cx))
+            }
+            None => synthetic_cqe(0),
+        };
+        // A failed operation reports its own error over that of its stand-in, unless the
+        // chain was broken before reaching it.
+        let cqe = match inner.error {
+            Some(errno) if cqe.result() != -libc::ECANCELED => synthetic_cqe(-errno),
+            _ => cqe,
+        };
 
         let inner = this.inner.take().unwrap();
 
@@ -147,13 +208,28 @@ impl<D: Unpin, T: OneshotOutputTransform<StoredData = D> + Unpin> Future for InF
 impl<D: 'static, T: OneshotOutputTransform<StoredData = D>> Drop for InFlightOneshot<D, T> {
     fn drop(&mut self) {
         if let Some(inner) = self.inner.take() {
-            if let Some(driver) = inner.driver.upgrade() {
-                driver.remove_op_2(inner.index, inner.stable_data)
+            if let (Some(index), Some(driver)) = (inner.index, inner.driver.upgrade()) {
+                driver.remove_op_2(index, inner.stable_data)
             }
         }
     }
 }
 
+// A CQE with the given result and no flags, for an operation completed without the kernel.
+fn synthetic_cqe(result: i32) -> cqueue::Entry {
+    // Safety: a zeroed CQE is valid. `cqueue::Entry` wraps `io_uring_cqe` with `repr(C)`,
+    // where `res` follows the 8 bytes of `user_data`.
+    unsafe {
+        let mut cqe: cqueue::Entry = std::mem::zeroed();
+        let res = (&mut cqe as *mut cqueue::Entry)
+            .cast::<u8>()
+            .add(8)
+            .cast::<i32>();
+        res.write(result);
+        cqe
+    }
+}
+
 /// Submit an operation or operations to the driver.
 pub trait Submit {
     /// The output of the submission with an in-flight operation or linked in-flight operations.
@@ -193,6 +269,10 @@ pub(crate) struct SingleCQE;
 /// which combined resolve to a single Future value
 pub(crate) struct MultiCQEFuture;
 
+/// A Marker for Operations which process multiple completion events,
+/// each of which is handed to the submitter as it is polled
+pub(crate) struct MultiCQEStream;
+
 pub(crate) trait Completable {
     type Output;
     /// `complete` will be called for cqe's do not have the `more` flag set
@@ -217,8 +297,9 @@ pub(crate) enum Lifecycle {
     #[allow(dead_code)]
     Ignored(Box<dyn std::any::Any>),
 
-    /// The operation has completed with a single cqe result
-    Completed(cqueue::Entry),
+    /// The operation has completed with a single cqe result, and the extra 16 bytes of a
+    /// 32-byte cqe
+    Completed(cqueue::Entry, [u64; 2]),
 
     /// One or more completion results have been recieved
     /// This holds the indices uniquely identifying the list within the slab
@@ -229,6 +310,8 @@ pub(crate) enum Lifecycle {
 pub(crate) struct CqeResult {
     pub(crate) result: io::Result<u32>,
     pub(crate) flags: u32,
+    /// The extra 16 bytes of a 32-byte cqe, zeros for a 16-byte one
+    pub(crate) big_cqe: [u64; 2],
 }
 
 impl From<cqueue::Entry> for CqeResult {
@@ -240,7 +323,20 @@ impl From<cqueue::Entry> for CqeResult {
         } else {
             Err(io::Error::from_raw_os_error(-res))
         };
-        CqeResult { result, flags }
+        CqeResult {
+            result,
+            flags,
+            big_cqe: [0; 2],
+        }
+    }
+}
+
+impl From<(cqueue::Entry, [u64; 2])> for CqeResult {
+    fn from((cqe, big_cqe): (cqueue::Entry, [u64; 2])) -> Self {
+        CqeResult {
+            big_cqe,
+            ..cqe.into()
+        }
     }
 }
 
@@ -266,6 +362,38 @@ impl<T, CqeType> Op<T, CqeType> {
     pub(super) fn insert_data(&mut self, data: T) {
         self.data = Some(data);
     }
+
+    /// Returns true once the final completion has been consumed and the op removed from the
+    /// driver.
+    pub(super) fn is_finished(&self) -> bool {
+        self.data.is_none()
+    }
+
+    /// Asks the kernel to cancel the operation.
+    ///
+    /// Completions the kernel has already posted are dispatched before this returns, so for
+    /// multishot operations they can still be polled and released.
+    pub(crate) fn cancel(&self) {
+        if let Some(driver) = self.driver.upgrade() {
+            driver.cancel_op(self.index);
+        }
+    }
+}
+
+impl<T> Op<T, MultiCQEStream>
+where
+    T: Unpin + 'static + Completable,
+{
+    /// Polls for the next completion of a multishot operation.
+    ///
+    /// The returned CQE is the last one for this operation if it does not have the `more` flag
+    /// set; the op must not be polled again after that.
+    pub(crate) fn poll_next_cqe(&mut self, cx: &mut Context<'_>) -> Poll<CqeResult> {
+        self.driver
+            .upgrade()
+            .expect("Not in runtime context")
+            .poll_multishot_stream(self, cx)
+    }
 }
 
 impl<T> Future for Op<T, SingleCQE>
@@ -314,6 +442,7 @@ impl Lifecycle {
         &mut self,
         completions: &mut Slab<Completion>,
         cqe: cqueue::Entry,
+        big_cqe: [u64; 2],
     ) -> bool {
         use std::mem;
 
@@ -321,10 +450,10 @@ impl Lifecycle {
             x @ Lifecycle::Submitted | x @ Lifecycle::Waiting(..) => {
                 if io_uring::cqueue::more(cqe.flags()) {
                     let mut list = SlabListIndices::new().into_list(completions);
-                    list.push(cqe.into());
+                    list.push((cqe, big_cqe).into());
                     *self = Lifecycle::CompletionList(list.into_indices());
                 } else {
-                    *self = Lifecycle::Completed(cqe);
+                    *self = Lifecycle::Completed(cqe, big_cqe);
                 }
                 if let Lifecycle::Waiting(waker) = x {
                     // waker is woken to notify cqe has arrived
@@ -358,7 +487,7 @@ impl Lifecycle {
                 // A completion list may contain CQE's with and without `more` flag set.
                 // Only the final one may have `more` unset, although we don't check.
                 let mut list = indices.into_list(completions);
-                list.push(cqe.into());
+                list.push((cqe, big_cqe).into());
                 *self = Lifecycle::CompletionList(list.into_indices());
                 false
             }
@@ -0,0 +1,115 @@
+use io_uring::squeue::PushError;
+use io_uring::{cqueue, squeue, IoUring, Parameters, Submitter};
+
+use std::io;
+use std::os::unix::io::{AsRawFd, RawFd};
+
+// The ring of the driver, of 64-byte SQEs and 16-byte CQEs, or of 128-byte SQEs and 32-byte
+// CQEs if set up for commands that need the larger entries. Ops build 64-byte SQEs, which are
+// widened to go into a ring of the larger ones.
+pub(crate) enum Uring {
+    Default(IoUring),
+    Big(IoUring<squeue::Entry128, cqueue::Entry32>),
+}
+
+impl Uring {
+    pub(crate) fn is_big(&self) -> bool {
+        matches!(self, Uring::Big(_))
+    }
+
+    pub(crate) fn submitter(&self) -> Submitter<'_> {
+        match self {
+            Uring::Default(uring) => uring.submitter(),
+            Uring::Big(uring) => uring.submitter(),
+        }
+    }
+
+    pub(crate) fn params(&self) -> &Parameters {
+        match self {
+            Uring::Default(uring) => uring.params(),
+            Uring::Big(uring) => uring.params(),
+        }
+    }
+
+    pub(crate) fn submit(&self) -> io::Result<usize> {
+        self.submitter().submit()
+    }
+
+    pub(crate) fn submit_and_wait(&self, want: usize) -> io::Result<usize> {
+        self.submitter().submit_and_wait(want)
+    }
+
+    pub(crate) fn sync_submission(&mut self) {
+        match self {
+            Uring::Default(uring) => uring.submission().sync(),
+            Uring::Big(uring) => uring.submission().sync(),
+        }
+    }
+
+    pub(crate) fn submission_is_empty(&mut self) -> bool {
+        match self {
+            Uring::Default(uring) => uring.submission().is_empty(),
+            Uring::Big(uring) => uring.submission().is_empty(),
+        }
+    }
+
+    // Safety: as for `SubmissionQueue::push`.
+    pub(crate) unsafe fn push(&mut self, sqe: &squeue::Entry) -> Result<(), PushError> {
+        match self {
+            Uring::Default(uring) => uring.submission().push(sqe),
+            Uring::Big(uring) => uring.submission().push(&sqe.clone().into()),
+        }
+    }
+
+    // Safety: as for `SubmissionQueue::push_multiple`.
+    pub(crate) unsafe fn push_multiple(&mut self, sqes: &[squeue::Entry]) -> Result<(), PushError> {
+        match self {
+            Uring::Default(uring) => uring.submission().push_multiple(sqes),
+            Uring::Big(uring) => {
+                let sqes = sqes.iter().cloned().map(Into::into).collect::<Vec<_>>();
+                uring.submission().push_multiple(&sqes)
+            }
+        }
+    }
+
+    // Pushes an SQE of the larger size, which only a ring of those takes.
+    //
+    // Safety: as for `SubmissionQueue::push`.
+    pub(crate) unsafe fn push_big(&mut self, sqe: &squeue::Entry128) -> Result<(), PushError> {
+        match self {
+            Uring::Default(_) => unreachable!("128-byte SQE pushed to a ring of 64-byte ones"),
+            Uring::Big(uring) => uring.submission().push(sqe),
+        }
+    }
+
+    // Calls `f` with each CQE posted, along with the extra 16 bytes of a 32-byte one, zeros
+    // for a 16-byte one.
+    pub(crate) fn for_each_completion(&mut self, mut f: impl FnMut(cqueue::Entry, [u64; 2])) {
+        match self {
+            Uring::Default(uring) => {
+                let mut cq = uring.completion();
+                cq.sync();
+                for cqe in cq {
+                    f(cqe, [0; 2]);
+                }
+            }
+            Uring::Big(uring) => {
+                let mut cq = uring.completion();
+                cq.sync();
+                for cqe in cq {
+                    let big_cqe = *cqe.big_cqe();
+                    f(cqe.into(), big_cqe);
+                }
+            }
+        }
+    }
+}
+
+impl AsRawFd for Uring {
+    fn as_raw_fd(&self) -> RawFd {
+        match self {
+            Uring::Default(uring) => uring.as_raw_fd(),
+            Uring::Big(uring) => uring.as_raw_fd(),
+        }
+    }
+}
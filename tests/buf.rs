@@ -211,3 +211,423 @@ fn can_deref_slice_into_uninit_buf() {
     buf.copy_from_slice(&[43]);
     assert_eq!(&buf[..], &[43]);
 }
+
+#[test]
+fn buffer_position() {
+    use tokio_uring::Buffer;
+
+    let buf = Buffer::from(vec![b"abc".to_vec(), Vec::new(), b"de".to_vec()]);
+    assert_eq!(buf.position(0), Some((0, 0)));
+    assert_eq!(buf.position(2), Some((0, 2)));
+    assert_eq!(buf.position(3), Some((2, 0)));
+    assert_eq!(buf.position(4), Some((2, 1)));
+    assert_eq!(buf.position(5), None);
+}
+
+#[test]
+fn buffer_introspection_single_segment() {
+    use tokio_uring::Buffer;
+
+    let mut v = Vec::with_capacity(16);
+    v.extend_from_slice(b"hello");
+    let mut buf = Buffer::from(v);
+    assert_eq!(buf.segment_count(), 1);
+    assert_eq!((buf.len(), buf.capacity()), (5, 16));
+    assert!(!buf.is_empty());
+    assert_eq!(buf.iter_segments().collect::<Vec<_>>(), [b"hello"]);
+
+    buf.iter_segments_mut()
+        .for_each(|seg| seg.make_ascii_uppercase());
+    assert_eq!(&buf[0], b"HELLO");
+    assert_eq!(
+        format!("{:?}", buf),
+        "Buffer { len: 5, capacity: 16, segments: [5/16], read_only: false, view: false }"
+    );
+}
+
+#[test]
+fn buffer_introspection_multi_segment() {
+    use tokio_uring::Buffer;
+
+    let mut buf = Buffer::from(vec![
+        b"ab".to_vec(),
+        Vec::with_capacity(4),
+        vec![0; 1 << 20],
+    ]);
+    assert_eq!(buf.segment_count(), 3);
+    assert_eq!(buf.len(), 2 + (1 << 20));
+    assert_eq!(buf.capacity(), 2 + 4 + (1 << 20));
+    let lens: Vec<usize> = buf.iter_segments().map(<[u8]>::len).collect();
+    assert_eq!(lens, [2, 0, 1 << 20]);
+
+    for seg in buf.iter_segments_mut() {
+        seg.fill(b'x');
+    }
+    assert_eq!(&buf[0], b"xx");
+    assert!(buf[2].iter().all(|&b| b == b'x'));
+
+    // The contents are left out, however large.
+    let debug = format!("{:?}", buf);
+    assert!(debug.contains("segments: [2/2, 0/4, 1048576/1048576]"));
+    assert!(debug.len() < 200);
+}
+
+#[test]
+fn buffer_introspection_empty() {
+    use tokio_uring::Buffer;
+
+    let buf = Buffer::from(Vec::<Vec<u8>>::new());
+    assert_eq!(buf.segment_count(), 0);
+    assert_eq!((buf.len(), buf.capacity()), (0, 0));
+    assert!(buf.is_empty());
+    assert_eq!(buf.iter_segments().count(), 0);
+    assert!(format!("{:?}", buf).contains("segments: []"));
+
+    // An empty segment still counts as one.
+    let buf = Buffer::from(Vec::<u8>::new());
+    assert_eq!(buf.segment_count(), 1);
+    assert!(buf.is_empty());
+}
+
+#[test]
+#[should_panic(expected = "segment index 2 out of range for a buffer of 2 segments")]
+fn buffer_index_out_of_range() {
+    use tokio_uring::Buffer;
+
+    let buf = Buffer::from(vec![b"a".to_vec(), b"b".to_vec()]);
+    let _ = &buf[2];
+}
+
+#[test]
+fn buffer_reused_across_reads() {
+    use std::io::Write;
+    use tokio_uring::fs::File;
+    use tokio_uring::{Buffer, Submit};
+
+    let mut tempfile = tempfile::NamedTempFile::new().unwrap();
+    tempfile.write_all(b"0123456789abcdefghij").unwrap();
+
+    tokio_uring::start(async {
+        let file = File::open(tempfile.path()).await.unwrap();
+        let mut buf = Buffer::from(Vec::<u8>::with_capacity(8));
+
+        // Each read starts from an empty buffer, and a short one leaves nothing of the last.
+        for (offset, expected) in [(0, &b"01234567"[..]), (12, b"cdefghij"), (16, b"ghij")] {
+            buf.clear();
+            assert!(buf.is_empty());
+            assert_eq!(buf.capacity(), 8);
+            let (n, read) = file.read_at(buf, offset).submit().await.unwrap();
+            assert_eq!(n, expected.len());
+            assert_eq!(&read[0], expected);
+            buf = read;
+        }
+
+        // Keeping a prefix, and appending to it.
+        buf.truncate(2);
+        buf.extend_from_slice(b"!!").unwrap();
+        assert_eq!(&buf[0], b"gh!!");
+    });
+}
+
+#[test]
+fn buffer_truncate_and_set_init_across_segments() {
+    use tokio_uring::Buffer;
+
+    let mut buf = Buffer::from(vec![b"abc".to_vec(), b"de".to_vec(), b"fgh".to_vec()]);
+    buf.truncate(4);
+    let lens: Vec<usize> = buf.iter_segments().map(<[u8]>::len).collect();
+    assert_eq!(lens, [3, 1, 0]);
+    buf.truncate(10);
+    assert_eq!(buf.len(), 4);
+
+    // The bytes truncated away are still in memory, for set_init to count again.
+    unsafe { buf.set_init(7) };
+    let text: Vec<u8> = buf.iter_segments().flatten().copied().collect();
+    assert_eq!(text, b"abcdefg");
+    buf.clear();
+    assert!(buf.iter_segments().all(<[u8]>::is_empty));
+    assert_eq!(buf.capacity(), 8);
+}
+
+#[test]
+#[should_panic(expected = "9 initialized bytes out of range for a buffer of 8")]
+fn buffer_set_init_past_capacity() {
+    use tokio_uring::Buffer;
+
+    let mut buf = Buffer::from(Vec::<u8>::with_capacity(8));
+    unsafe { buf.set_init(9) };
+}
+
+#[test]
+fn buffer_string_round_trip() {
+    use std::io::Read;
+    use tokio_uring::fs::File;
+    use tokio_uring::{Buffer, Submit};
+
+    let tempfile = tempfile::NamedTempFile::new().unwrap();
+
+    tokio_uring::start(async {
+        let file = File::create(tempfile.path()).await.unwrap();
+        let line = String::from("{\"temp\": \"21°C\"}\n");
+        let (ptr, len) = (line.as_ptr(), line.len());
+
+        let buf = Buffer::from(line);
+        assert!(buf.is_read_only());
+        let (n, buf) = file.write_at(buf, 0).submit().await.unwrap();
+        assert_eq!(n, len);
+        let line = buf.try_into_string().unwrap();
+        assert_eq!(line, "{\"temp\": \"21°C\"}\n");
+        assert_eq!(line.as_ptr(), ptr);
+    });
+
+    let mut written = String::new();
+    tempfile.as_file().read_to_string(&mut written).unwrap();
+    assert_eq!(written, "{\"temp\": \"21°C\"}\n");
+
+    // Shortened on a character boundary, the string keeps its allocation.
+    let line = String::from("21°C");
+    let ptr = line.as_ptr();
+    let mut buf = Buffer::from(line);
+    buf.truncate(4);
+    let line = buf.try_into_string().unwrap();
+    assert_eq!((line.as_str(), line.as_ptr()), ("21°", ptr));
+
+    // Cut within a character, it is no longer UTF-8.
+    let mut buf = Buffer::from(line);
+    buf.truncate(3);
+    let buf = buf.try_into_string().unwrap_err();
+    assert_eq!(&buf[0], b"21\xc2");
+
+    // Other buffers are checked and copied.
+    let buf = Buffer::from(vec![b"21".to_vec(), "°C".as_bytes().to_vec()]);
+    assert_eq!(buf.try_into_string().unwrap(), "21°C");
+    let buf = Buffer::from(vec![0xff]);
+    assert_eq!(&buf.try_into_string().unwrap_err()[0], [0xff]);
+}
+
+#[test]
+fn buffer_string_refuses_reads() {
+    use tokio_uring::fs::File;
+    use tokio_uring::{Buffer, Submit};
+
+    let tempfile = tempfile::NamedTempFile::new().unwrap();
+    tokio_uring::start(async {
+        let file = File::open(tempfile.path()).await.unwrap();
+        let err = file
+            .read_at(Buffer::from(String::from("kept")), 0)
+            .submit()
+            .await
+            .unwrap_err();
+        assert_eq!(err.0.kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(&err.1[0][..], b"kept");
+    });
+}
+
+#[test]
+fn buffer_alignment() {
+    use tokio_uring::buf::AlignedBuf;
+    use tokio_uring::Buffer;
+
+    // A `Vec` is as aligned as its address happens to be.
+    let vec = vec![0u8; 64];
+    let addr = vec.as_ptr() as usize;
+    let buf = Buffer::from(vec);
+    assert!(buf.alignment().is_power_of_two());
+    assert_eq!(addr % buf.alignment(), 0);
+    assert_ne!(addr % (buf.alignment() * 2), 0);
+
+    // An `AlignedBuf` declares its alignment, which an empty one has exactly.
+    assert_eq!(Buffer::from(AlignedBuf::new(0, 4096)).alignment(), 4096);
+    let buf = Buffer::from(AlignedBuf::new(8192, 4096));
+    assert_eq!(buf.alignment() % 4096, 0);
+    let buf = buf.view(1024..);
+    assert_eq!(buf.alignment(), 1024);
+    assert_eq!(buf.into_inner().alignment() % 4096, 0);
+
+    // Segments are only as aligned as the least aligned one.
+    let segments = vec![vec![0u8; 8192], vec![0u8; 16]];
+    let least = segments
+        .iter()
+        .map(|seg| 1 << (seg.as_ptr() as usize).trailing_zeros())
+        .min()
+        .unwrap();
+    let buf = Buffer::from(segments);
+    assert_eq!(buf.alignment(), least);
+    assert_eq!(buf.view(1..).alignment(), 1);
+    assert_eq!(
+        Buffer::from(Vec::<Vec<u8>>::new()).alignment(),
+        1 << (usize::BITS - 1)
+    );
+}
+
+#[test]
+fn buffer_static_header_chained_with_owned_body() {
+    use tokio_uring::net::UnixStream;
+    use tokio_uring::{Buffer, Submit};
+
+    static HEADER: &[u8] = b"HTTP/1.1 200 OK\r\ncontent-length: 11\r\n\r\n";
+
+    tokio_uring::start(async {
+        let (a, b) = UnixStream::pair().unwrap();
+
+        let header = Buffer::from_static(HEADER);
+        assert!(header.is_read_only());
+        let buf = header.chain(Buffer::from(b"{\"ok\":true}".to_vec()));
+        assert_eq!(buf.segment_count(), 2);
+        assert!(buf.is_read_only());
+
+        // One vectored write sends both.
+        let (n, buf) = a.write(buf).submit().await.unwrap();
+        assert_eq!(n, HEADER.len() + 11);
+        assert_eq!(buf[0].as_ptr(), HEADER.as_ptr());
+        drop(a);
+        let (n, out) = b.read(Vec::<u8>::with_capacity(128).into()).await.unwrap();
+        assert_eq!(
+            &out[0][..n],
+            b"HTTP/1.1 200 OK\r\ncontent-length: 11\r\n\r\n{\"ok\":true}"
+        );
+
+        // A chain of owned buffers is read into, each keeping what landed in it.
+        let (a, b) = UnixStream::pair().unwrap();
+        let buf = Buffer::from(Vec::<u8>::with_capacity(4)).chain(Buffer::from(vec![
+            Vec::with_capacity(2),
+            Vec::with_capacity(8),
+        ]));
+        assert_eq!(buf.segment_count(), 3);
+        a.write_all(b"abcdefgh".to_vec().into()).await.unwrap();
+        let (n, buf) = b.read(buf).await.unwrap();
+        assert_eq!(n, 8);
+        assert_eq!(
+            (&buf[0], &buf[1], &buf[2]),
+            (&b"abcd"[..], &b"ef"[..], &b"gh"[..])
+        );
+    });
+}
+
+#[test]
+fn buffer_static_refuses_reads() {
+    use tokio_uring::net::{UdpSocket, UnixStream};
+    use tokio_uring::Buffer;
+
+    tokio_uring::start(async {
+        let (_a, b) = UnixStream::pair().unwrap();
+        let err = b.read(Buffer::from_static(b"constant")).await.unwrap_err();
+        assert_eq!(err.0.kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(&err.1[0][..], b"constant");
+
+        let socket = UdpSocket::bind("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        let err = socket
+            .recv_from(Buffer::from_static(b"constant"))
+            .await
+            .unwrap_err();
+        assert_eq!(err.0.kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(&err.1[0][..], b"constant");
+    });
+}
+
+#[cfg(feature = "bytes")]
+#[test]
+fn buffer_frame_straddles_segments() {
+    use bytes::{Buf, BufMut};
+    use tokio_uring::net::UnixStream;
+    use tokio_uring::{Buffer, Submit};
+
+    let mut buf = Buffer::from(vec![
+        Vec::with_capacity(6),
+        Vec::with_capacity(0),
+        Vec::with_capacity(8),
+    ]);
+    let mut writer = buf.writer();
+    assert_eq!(writer.remaining_mut(), 14);
+    writer.put_u32(5);
+    writer.put_slice(b"hello");
+    assert_eq!(writer.remaining_mut(), 5);
+    assert_eq!(&buf[0], b"\0\0\0\x05he");
+    assert_eq!(&buf[2], b"llo");
+
+    // The frame's payload is split across two chunks.
+    let mut reader = buf.reader();
+    assert_eq!(reader.remaining(), 9);
+    assert_eq!(reader.get_u32(), 5);
+    assert_eq!(reader.chunk(), b"he");
+    assert_eq!(reader.copy_to_bytes(5), &b"hello"[..]);
+    assert_eq!(reader.remaining(), 0);
+    assert!(reader.chunk().is_empty());
+
+    // A write sends exactly the bytes written.
+    tokio_uring::start(async {
+        let (a, b) = UnixStream::pair().unwrap();
+        a.write(buf).submit().await.unwrap();
+        drop(a);
+        let (n, buf) = b.read(Vec::<u8>::with_capacity(32).into()).await.unwrap();
+        assert_eq!(&buf[0][..n], b"\0\0\0\x05hello");
+    });
+}
+
+#[test]
+fn buf_pool_recycles_across_reads() {
+    use std::collections::VecDeque;
+    use std::io::Write;
+    use tokio_uring::buf::pool::BufPool;
+    use tokio_uring::fs::File;
+    use tokio_uring::Submit;
+
+    let mut tempfile = tempfile::NamedTempFile::new().unwrap();
+    tempfile.write_all(b"hello pooled world").unwrap();
+
+    tokio_uring::start(async {
+        let file = File::open(tempfile.path()).await.unwrap();
+        let pool = BufPool::new(64, 4);
+        // Keep the last few buffers read into alive, so that all four are in use at times.
+        let mut held = VecDeque::new();
+
+        for i in 0..1000 {
+            let buf = pool.acquire().await;
+            assert!(buf.is_empty());
+            assert_eq!(buf.capacity(), 64);
+            let (n, buf) = file.read_at(buf, i % 6).submit().await.unwrap();
+            assert_eq!(&buf[0][..n], &b"hello pooled world"[(i % 6) as usize..]);
+
+            held.push_back(buf);
+            if held.len() == 4 {
+                held.pop_front();
+            }
+            assert!(pool.allocations() <= 4);
+        }
+        assert_eq!(pool.allocations(), 4);
+        assert_eq!(pool.high_water_mark(), 4);
+        assert_eq!(pool.in_use_count(), 3);
+
+        drop(held);
+        assert_eq!(pool.free_count(), 4);
+        assert_eq!(pool.in_use_count(), 0);
+    });
+}
+
+#[test]
+fn buf_pool_acquire_waits_for_a_dropped_buffer() {
+    use tokio_uring::buf::pool::BufPool;
+
+    tokio_uring::start(async {
+        let pool = BufPool::new(16, 1);
+        let mut buf = pool.try_acquire().unwrap();
+        buf.extend_from_slice(b"stale").unwrap();
+        assert!(pool.try_acquire().is_none());
+
+        let waiter = tokio_uring::spawn({
+            let pool = pool.clone();
+            async move { pool.acquire().await }
+        });
+        tokio::task::yield_now().await;
+        assert!(!waiter.is_finished());
+
+        // The dropped buffer goes straight to the waiting task, emptied.
+        drop(buf);
+        assert!(pool.try_acquire().is_none());
+        let buf = waiter.await.unwrap();
+        assert!(buf.is_empty());
+        assert_eq!(pool.allocations(), 1);
+    });
+}
@@ -1,12 +1,14 @@
-use tokio_uring::buf::fixed::{pool, registry};
-use tokio_uring::buf::{BoundedBuf, BoundedBufMut};
+use tokio_uring::buf::fixed::{self, pool, registry};
+use tokio_uring::buf::{BoundedBuf, BoundedBufMut, PageBacking, Slice};
 use tokio_uring::fs::File;
+use tokio_uring::net::{TcpListener, TcpStream, UnixStream};
 use tokio_uring::Buffer;
 
 use std::fs::File as StdFile;
 use std::io::prelude::*;
 use std::iter;
 use std::mem;
+use std::time::{Duration, Instant};
 use tempfile::NamedTempFile;
 
 const HELLO: &[u8] = b"hello world...";
@@ -94,6 +96,178 @@ fn unregister_invalidates_checked_out_buffers() {
     });
 }
 
+#[test]
+fn reregister_revokes_stale_registry() {
+    tokio_uring::start(async {
+        let mut tempfile = tempfile();
+        tempfile.write_all(HELLO).unwrap();
+        let file = File::open(tempfile.path()).await.unwrap();
+
+        let old =
+            registry::register(iter::repeat_with(|| Vec::<u8>::with_capacity(16).into()).take(2))
+                .unwrap();
+        let stale = old.check_out(0).unwrap();
+        registry::unregister().unwrap();
+
+        let new =
+            registry::register(iter::repeat_with(|| Vec::<u8>::with_capacity(64).into()).take(4))
+                .unwrap();
+        for i in 0..4 {
+            let (n, buf) = file
+                .read_fixed_at(new.check_out(i).unwrap(), 0)
+                .await
+                .unwrap();
+            assert_eq!(n, HELLO.len());
+            assert_eq!(&buf[0][..], HELLO);
+        }
+
+        // The old handle hands out nothing, and its buffers are refused
+        // before reaching the kernel, where index 0 is now a new buffer.
+        assert_eq!(
+            old.try_check_out(1).unwrap_err(),
+            registry::RegistrationRevoked
+        );
+        assert!(old.check_out(1).is_none());
+        let revoked = |e: &std::io::Error| {
+            e.get_ref()
+                .is_some_and(|e| e.is::<registry::RegistrationRevoked>())
+        };
+        let err = file.read_fixed_at(stale, 0).await.unwrap_err();
+        assert!(revoked(&err.0));
+        let err = old
+            .update(1, Vec::<u8>::with_capacity(16).into())
+            .unwrap_err();
+        assert!(revoked(&err.0));
+
+        registry::unregister().unwrap();
+    });
+}
+
+#[test]
+fn reregister_revokes_stale_pool() {
+    tokio_uring::start(async {
+        let mut tempfile = tempfile();
+        tempfile.write_all(HELLO).unwrap();
+        let file = File::open(tempfile.path()).await.unwrap();
+
+        let old = pool::register(iter::repeat_with(|| Vec::<u8>::with_capacity(16).into()).take(2))
+            .unwrap();
+        let stale = old.try_next(16).unwrap();
+        pool::unregister().unwrap();
+
+        let new = pool::register(iter::repeat_with(|| Vec::<u8>::with_capacity(64).into()).take(2))
+            .unwrap();
+        let (n, buf) = file
+            .read_fixed_at(new.try_next(64).unwrap(), 0)
+            .await
+            .unwrap();
+        assert_eq!(n, HELLO.len());
+        assert_eq!(&buf[0][..], HELLO);
+
+        // The stale buffer is refused before reaching the kernel, where its
+        // index now names a buffer of the new pool.
+        let err = file.read_fixed_at(stale, 0).await.unwrap_err();
+        assert!(err
+            .0
+            .get_ref()
+            .is_some_and(|e| e.is::<registry::RegistrationRevoked>()));
+
+        pool::unregister().unwrap();
+    });
+}
+
+#[test]
+fn unregister_graceful_waits_for_checked_out_buffers() {
+    tokio_uring::start(async {
+        let buffers =
+            registry::register(iter::repeat_with(|| Vec::<u8>::with_capacity(1024).into()).take(2))
+                .unwrap();
+
+        let held = buffers.check_out(0).unwrap();
+        let task = tokio_uring::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            drop(held);
+        });
+
+        let start = Instant::now();
+        let stragglers = registry::unregister_graceful(&buffers, None).await.unwrap();
+        assert_eq!(stragglers, 0);
+        assert!(start.elapsed() >= Duration::from_millis(50));
+        task.await.unwrap();
+
+        // Drained and unregistered, the registry hands out nothing more, and
+        // another collection can be registered.
+        assert_eq!(buffers.free_count(), 2);
+        assert!(buffers.check_out(1).is_none());
+        registry::register(iter::once(Vec::<u8>::with_capacity(1024).into())).unwrap();
+        registry::unregister().unwrap();
+    });
+}
+
+#[test]
+fn unregister_graceful_times_out_with_stragglers() {
+    tokio_uring::start(async {
+        let buffers =
+            registry::register(iter::repeat_with(|| Vec::<u8>::with_capacity(1024).into()).take(3))
+                .unwrap();
+        let _held = (buffers.check_out(0).unwrap(), buffers.check_out(2).unwrap());
+
+        let stragglers = registry::unregister_graceful(&buffers, Some(Duration::from_millis(20)))
+            .await
+            .unwrap();
+        assert_eq!(stragglers, 2);
+        let err = buffers
+            .update(1, Vec::<u8>::with_capacity(16).into())
+            .unwrap_err();
+        assert_eq!(err.0.kind(), std::io::ErrorKind::InvalidInput);
+    });
+}
+
+#[test]
+fn unregister_graceful_fails_waiters() {
+    tokio_uring::start(async {
+        let buffers =
+            registry::register(iter::repeat_with(|| Vec::<u8>::with_capacity(1024).into()).take(2))
+                .unwrap();
+        let held = (buffers.check_out(0).unwrap(), buffers.check_out(1).unwrap());
+
+        let waiters = [
+            tokio_uring::spawn({
+                let buffers = buffers.clone();
+                async move { buffers.check_out_async(0).await.map(drop) }
+            }),
+            tokio_uring::spawn({
+                let buffers = buffers.clone();
+                async move { buffers.check_out_any_async().await.map(drop) }
+            }),
+        ];
+        tokio::task::yield_now().await;
+        assert_eq!(buffers.stats().waiters, 2);
+
+        // Draining wakes the waiters, which give up rather than wait for
+        // buffers that will not be handed out.
+        let graceful = tokio_uring::spawn({
+            let buffers = buffers.clone();
+            async move { registry::unregister_graceful(&buffers, None).await }
+        });
+        for waiter in waiters {
+            let res = tokio::time::timeout(Duration::from_secs(5), waiter)
+                .await
+                .expect("the waiter was not woken")
+                .unwrap();
+            assert_eq!(res, Err(registry::RegistrationRevoked));
+        }
+        assert_eq!(buffers.stats().waiters, 0);
+        assert_eq!(
+            buffers.check_out_async(1).await.unwrap_err(),
+            registry::RegistrationRevoked
+        );
+
+        mem::drop(held);
+        assert_eq!(graceful.await.unwrap().unwrap(), 0);
+    });
+}
+
 #[test]
 fn slicing() {
     tokio_uring::start(async {
@@ -139,6 +313,83 @@ fn slicing() {
     })
 }
 
+#[test]
+fn huge_page_buffer_read_fixed() {
+    const HUGE_PAGE: usize = 2 << 20;
+
+    // Without free hugetlb pages, the buffer falls back to transparent huge
+    // pages, or regular ones.
+    let hugetlb_free = std::fs::read_to_string("/proc/meminfo")
+        .unwrap()
+        .lines()
+        .find_map(|line| line.strip_prefix("HugePages_Free:"))
+        .is_some_and(|n| n.trim() != "0");
+
+    tokio_uring::start(async {
+        let mut tempfile = tempfile();
+        tempfile.write_all(HELLO).unwrap();
+        let file = File::open(tempfile.path()).await.unwrap();
+
+        let buf = Buffer::with_capacity_hugetlb(HUGE_PAGE).unwrap();
+        assert_eq!(buf.bytes_total(), HUGE_PAGE);
+        let backing = buf.page_backing();
+        if hugetlb_free {
+            assert_eq!(backing, PageBacking::HugeTlb);
+        } else {
+            assert_ne!(backing, PageBacking::HugeTlb);
+        }
+
+        let buffers = registry::register(iter::once(buf)).unwrap();
+        assert_eq!(buffers.snapshot()[0].page_backing(), backing);
+        let (n, buf) = file
+            .read_fixed_at(buffers.check_out(0).unwrap(), 0)
+            .await
+            .unwrap();
+        assert_eq!(n, HELLO.len());
+        assert_eq!(&buf[0][..], HELLO);
+    });
+}
+
+#[test]
+fn aligned_bufs_read_fixed_direct() {
+    use std::os::unix::fs::OpenOptionsExt;
+    use tokio_uring::buf::AlignedBuf;
+    use tokio_uring::fs::OpenOptions;
+
+    const ALIGN: usize = 4096;
+
+    tokio_uring::start(async {
+        let mut tempfile = tempfile();
+        let data: Vec<u8> = (0..2 * ALIGN).map(|i| i as u8).collect();
+        tempfile.write_all(&data).unwrap();
+
+        let bufs: Vec<_> = (0..2).map(|_| AlignedBuf::new(ALIGN, ALIGN)).collect();
+        for buf in &bufs {
+            assert_eq!(buf.as_ptr() as usize % ALIGN, 0);
+            assert_eq!(buf.capacity(), ALIGN);
+        }
+        let registry = registry::register(bufs.into_iter().map(Buffer::from)).unwrap();
+
+        let file = OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_DIRECT)
+            .open(tempfile.path())
+            .await
+            .unwrap();
+        let (n, buf) = file
+            .read_fixed_at(registry.check_out(1).unwrap(), ALIGN as u64)
+            .await
+            .unwrap();
+        assert_eq!(n, ALIGN);
+        assert_eq!(&buf[0][..], &data[ALIGN..]);
+        drop(buf);
+
+        // The read length stays with the registered buffer.
+        let buf = registry.check_out(1).unwrap();
+        assert_eq!(buf.bytes_init(), ALIGN);
+    });
+}
+
 #[test]
 fn pool_next_as_concurrency_limit() {
     tokio_uring::start(async move {
@@ -187,6 +438,794 @@ fn pool_next_as_concurrency_limit() {
     })
 }
 
+#[test]
+fn sparse_registry_fills_and_replaces_slots() {
+    use std::io::ErrorKind;
+
+    tokio_uring::start(async {
+        let mut tempfile = tempfile();
+        tempfile.write_all(HELLO).unwrap();
+        let file = File::open(tempfile.path()).await.unwrap();
+
+        let buffers = registry::register_sparse(8).unwrap();
+        assert!(buffers.check_out(0).is_none());
+
+        buffers
+            .update(0, Vec::<u8>::with_capacity(5).into())
+            .unwrap();
+        buffers
+            .update(3, Vec::<u8>::with_capacity(HELLO.len()).into())
+            .unwrap();
+        assert!(buffers.check_out(1).is_none());
+
+        let (n, buf) = file
+            .read_fixed_at(buffers.check_out(0).unwrap(), 0)
+            .await
+            .unwrap();
+        assert_eq!(buf[0][..n], HELLO[..5]);
+        let (n, buf3) = file
+            .read_fixed_at(buffers.check_out(3).unwrap(), 0)
+            .await
+            .unwrap();
+        assert_eq!(buf3[0][..n], HELLO[..]);
+
+        // A slot whose buffer is checked out keeps it.
+        let err = buffers
+            .replace(3, Vec::<u8>::with_capacity(2).into())
+            .unwrap_err();
+        assert_eq!(err.0.kind(), ErrorKind::ResourceBusy);
+        mem::drop(buf3);
+
+        mem::drop(buf);
+        let old = buffers
+            .replace(0, Vec::<u8>::with_capacity(HELLO.len() + 10).into())
+            .unwrap()
+            .unwrap();
+        assert_eq!(old.bytes_total(), 5);
+        let buf = buffers.check_out(0).unwrap();
+        assert_eq!(buf.bytes_total(), HELLO.len() + 10);
+        let (n, buf) = file.read_fixed_at(buf, 0).await.unwrap();
+        assert_eq!(buf[0][..n], HELLO[..]);
+        mem::drop(buf);
+
+        registry::unregister().unwrap();
+    })
+}
+
+#[test]
+fn registry_check_out_async_serves_waiters_in_order() {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use tokio::task::yield_now;
+
+    tokio_uring::start(async {
+        let buffers =
+            registry::register(iter::once(Vec::<u8>::with_capacity(16)).map(Buffer::from)).unwrap();
+        let held = buffers.check_out(0).unwrap();
+
+        let order = Rc::new(RefCell::new(Vec::new()));
+        let mut tasks = Vec::new();
+        for id in 0..2 {
+            let buffers = buffers.clone();
+            let order = order.clone();
+            tasks.push(tokio_uring::spawn(async move {
+                let mut buf = buffers.check_out_async(0).await.unwrap();
+                order.borrow_mut().push(id);
+                buf.put_slice(&[id]);
+                // Hold on to it while the other task is waiting.
+                yield_now().await;
+            }));
+            yield_now().await;
+        }
+
+        // Queued waiters keep a synchronous check-out from jumping ahead.
+        mem::drop(held);
+        assert!(buffers.check_out(0).is_none());
+        for task in tasks {
+            task.await.unwrap();
+        }
+        assert_eq!(*order.borrow(), [0, 1]);
+
+        let buf = buffers.check_out(0).unwrap();
+        assert_eq!(buf[0][..], [1]);
+    })
+}
+
+#[test]
+fn registry_check_out_any() {
+    use registry::BufStatus::{CheckedOut, Free};
+
+    tokio_uring::start(async {
+        let buffers = registry::register(
+            (0..3)
+                .map(|_| Vec::<u8>::with_capacity(16))
+                .map(Buffer::from),
+        )
+        .unwrap();
+
+        // A buffer checked out by index is skipped.
+        let by_index = buffers.check_out(1).unwrap();
+        let mut bufs: Vec<_> = iter::from_fn(|| buffers.check_out_any()).collect();
+        assert_eq!(bufs.len(), 2);
+        assert!(buffers.check_out_any().is_none());
+        assert_eq!(statuses(&buffers), [CheckedOut; 3]);
+
+        // A buffer dropped is the one checked out next, and not by index.
+        let ptr = bufs[1][0].as_ptr();
+        bufs.pop();
+        assert_eq!(statuses(&buffers), [CheckedOut, CheckedOut, Free]);
+        let buf = buffers.check_out_any().unwrap();
+        assert_eq!(buf[0].as_ptr(), ptr);
+        assert!(buffers.check_out(2).is_none());
+
+        // The async variant waits for the next check-in.
+        let waiting = {
+            let buffers = buffers.clone();
+            tokio_uring::spawn(async move { buffers.check_out_any_async().await })
+        };
+        tokio::task::yield_now().await;
+        mem::drop(by_index);
+        let buf = waiting.await.unwrap().unwrap();
+        assert_eq!(statuses(&buffers), [CheckedOut; 3]);
+        mem::drop(buf);
+        assert_eq!(statuses(&buffers), [CheckedOut, Free, CheckedOut]);
+    })
+}
+
+#[test]
+fn registry_shared_check_out_for_concurrent_writes() {
+    use registry::BufStatus::{Free, Shared};
+
+    tokio_uring::start(async {
+        let buffers = registry::register(iter::once(Buffer::from(HELLO.to_vec()))).unwrap();
+        let shared = buffers.check_out_shared(0).unwrap();
+        assert_eq!(&shared[..], HELLO);
+        assert_eq!(statuses(&buffers), [Shared]);
+
+        // Shared handles exclude an exclusive check-out, but not each other.
+        assert!(buffers.check_out(0).is_none());
+        assert!(buffers.check_out_any().is_none());
+        assert!(buffers.check_out_shared(0).is_some());
+
+        let mut readers = Vec::new();
+        let mut writes = Vec::new();
+        for _ in 0..8 {
+            let (a, b) = UnixStream::pair().unwrap();
+            let buf = Buffer::from(shared.clone());
+            assert!(buf.is_read_only());
+            writes.push(tokio_uring::spawn(async move { a.write_fixed(buf).await }));
+            readers.push(b);
+        }
+        mem::drop(shared);
+        assert!(buffers.check_out(0).is_none());
+
+        for write in writes {
+            let (n, _) = write.await.unwrap().unwrap();
+            assert_eq!(n, HELLO.len());
+        }
+        for b in readers {
+            let (n, buf) = b.read(Vec::<u8>::with_capacity(32).into()).await.unwrap();
+            assert_eq!(&buf[0][..n], HELLO);
+        }
+
+        // With the last handle gone, the buffer can be checked out exclusively.
+        assert_eq!(statuses(&buffers), [Free]);
+        let buf = buffers.check_out(0).unwrap();
+        assert_eq!(&buf[0], HELLO);
+        assert!(buffers.check_out_shared(0).is_none());
+    })
+}
+
+#[test]
+fn registry_try_check_out_for_times_out() {
+    tokio_uring::start(async {
+        let buffers =
+            registry::register(iter::once(Vec::<u8>::with_capacity(16)).map(Buffer::from)).unwrap();
+        let held = buffers.check_out(0).unwrap();
+        assert!(buffers
+            .try_check_out_for(0, Duration::from_millis(20))
+            .await
+            .is_none());
+
+        // The waiter that timed out is gone from the queue.
+        mem::drop(held);
+        let buf = buffers
+            .try_check_out_for(0, Duration::from_millis(20))
+            .await
+            .unwrap();
+        assert_eq!(buf.bytes_total(), 16);
+    })
+}
+
+#[test]
+fn registry_snapshot_tracks_buffers() {
+    use registry::BufStatus::{CheckedOut, Free, InFlight};
+
+    tokio_uring::start(async {
+        let (a, b) = UnixStream::pair().unwrap();
+        let buffers = registry::register(
+            [10, 20, 30, 40]
+                .iter()
+                .map(|&n| Vec::<u8>::with_capacity(n).into()),
+        )
+        .unwrap();
+        assert_eq!(buffers.len(), 4);
+        assert_eq!(buffers.total_bytes(), 100);
+        assert_eq!(statuses(&buffers), [Free; 4]);
+
+        let held = buffers.check_out(0).unwrap();
+        let read_buf = buffers.check_out(1).unwrap();
+        // Nothing to read yet, so the read stays in flight.
+        let read = tokio_uring::spawn(async move { b.read_fixed(read_buf).await.unwrap() });
+        tokio::task::yield_now().await;
+        assert_eq!(statuses(&buffers), [CheckedOut, InFlight, Free, Free]);
+        assert_eq!((buffers.free_count(), buffers.in_use_count()), (2, 2));
+
+        a.write_all(b"ping".to_vec().into()).await.unwrap();
+        let (n, read_buf) = read.await.unwrap();
+        assert_eq!(n, 4);
+        assert_eq!(statuses(&buffers), [CheckedOut, CheckedOut, Free, Free]);
+
+        drop((held, read_buf));
+        assert_eq!(statuses(&buffers), [Free; 4]);
+        assert_eq!((buffers.free_count(), buffers.in_use_count()), (4, 0));
+    })
+}
+
+#[test]
+fn pool_counts_buffers_in_use() {
+    tokio_uring::start(async {
+        let buffers = pool::register(
+            [10, 20, 20]
+                .iter()
+                .map(|&n| Vec::<u8>::with_capacity(n).into()),
+        )
+        .unwrap();
+        assert_eq!((buffers.len(), buffers.total_bytes()), (3, 50));
+
+        let a = buffers.try_next(15).unwrap();
+        let b = buffers.try_next(5).unwrap();
+        assert_eq!((buffers.free_count(), buffers.in_use_count()), (1, 2));
+
+        drop((a, b));
+        assert_eq!((buffers.free_count(), buffers.in_use_count()), (3, 0));
+    })
+}
+
+#[test]
+fn pool_try_next_picks_smallest_fitting_class() {
+    tokio_uring::start(async {
+        let buffers = pool::register(
+            [10, 20, 20, 40]
+                .iter()
+                .map(|&n| Vec::<u8>::with_capacity(n).into()),
+        )
+        .unwrap();
+
+        let a = buffers.try_next(15).unwrap();
+        let b = buffers.try_next(15).unwrap();
+        assert_eq!((a.bytes_total(), b.bytes_total()), (20, 20));
+        // The 20 byte class ran out, so the next size up serves the request.
+        let c = buffers.try_next(15).unwrap();
+        assert_eq!(c.bytes_total(), 40);
+        assert!(buffers.try_next(15).is_none());
+        assert_eq!(buffers.try_next(5).unwrap().bytes_total(), 10);
+        assert!(buffers.try_next(41).is_none());
+    })
+}
+
+#[test]
+fn pool_next_waits_for_check_in() {
+    use tokio::task::yield_now;
+
+    tokio_uring::start(async {
+        let buffers = pool::register(
+            [16, 16, 64]
+                .iter()
+                .map(|&n| Vec::<u8>::with_capacity(n).into()),
+        )
+        .unwrap();
+        let small = [buffers.try_next(16).unwrap(), buffers.try_next(16).unwrap()];
+        let large = buffers.try_next(64).unwrap();
+
+        // The small request queues up first.
+        let small_waiter = tokio_uring::spawn({
+            let buffers = buffers.clone();
+            async move { buffers.next(8).await }
+        });
+        yield_now().await;
+        let large_waiter = tokio_uring::spawn({
+            let buffers = buffers.clone();
+            async move { buffers.next(64).await }
+        });
+        yield_now().await;
+        assert!(!small_waiter.is_finished() && !large_waiter.is_finished());
+
+        // The large buffer goes to the request that needs it, even though the
+        // small one has waited longer.
+        mem::drop(large);
+        let large = large_waiter.await.unwrap();
+        assert_eq!(large.bytes_total(), 64);
+        yield_now().await;
+        assert!(!small_waiter.is_finished());
+
+        let [first, _second] = small;
+        mem::drop(first);
+        assert_eq!(small_waiter.await.unwrap().bytes_total(), 16);
+    })
+}
+
+#[test]
+fn pool_next_dropped_waiter_passes_buffer_on() {
+    use std::future::Future;
+    use std::task::Context;
+
+    tokio_uring::start(async {
+        let buffers =
+            pool::register(iter::once(Vec::<u8>::with_capacity(32)).map(Buffer::from)).unwrap();
+        let buf = buffers.try_next(32).unwrap();
+
+        let mut first = Box::pin(buffers.next(32));
+        let waker = futures_util::task::noop_waker();
+        assert!(first
+            .as_mut()
+            .poll(&mut Context::from_waker(&waker))
+            .is_pending());
+        let second = tokio_uring::spawn({
+            let buffers = buffers.clone();
+            async move { buffers.next(32).await.bytes_total() }
+        });
+        tokio::task::yield_now().await;
+
+        // The buffer is handed over to the first waiter, which gives up on it.
+        mem::drop(buf);
+        mem::drop(first);
+        assert_eq!(second.await.unwrap(), 32);
+        assert!(buffers.try_next(32).is_some());
+    })
+}
+
+#[test]
+fn pool_stats_track_contention() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tokio::task::yield_now;
+
+    tokio_uring::start(async {
+        let started = Arc::new(AtomicUsize::new(0));
+        let ended = Arc::new(AtomicUsize::new(0));
+        let buffers = pool::register((0..2).map(|_| Vec::<u8>::with_capacity(16).into()))
+            .unwrap()
+            .on_wait_start({
+                let started = started.clone();
+                move || {
+                    started.fetch_add(1, Ordering::Relaxed);
+                }
+            })
+            .on_wait_end({
+                let ended = ended.clone();
+                move |_| {
+                    ended.fetch_add(1, Ordering::Relaxed);
+                }
+            });
+        let held = [buffers.try_next(16).unwrap(), buffers.try_next(16).unwrap()];
+
+        // More tasks than buffers queue up.
+        let tasks = (0..6)
+            .map(|_| {
+                let buffers = buffers.clone();
+                tokio_uring::spawn(async move {
+                    let buf = buffers.next(16).await;
+                    yield_now().await;
+                    mem::drop(buf);
+                })
+            })
+            .collect::<Vec<_>>();
+        yield_now().await;
+        let stats = buffers.stats();
+        assert_eq!(stats.waiters, 6);
+        assert_eq!(stats.total_waits, 6);
+        assert_eq!(stats.in_use, 2);
+        assert_eq!(started.load(Ordering::Relaxed), 6);
+        assert_eq!(ended.load(Ordering::Relaxed), 0);
+
+        // Each buffer checked in serves a waiter, until none is left.
+        mem::drop(held);
+        assert_eq!(buffers.stats().waiters, 4);
+        for task in tasks {
+            task.await.unwrap();
+        }
+        let stats = buffers.stats();
+        assert_eq!(stats.waiters, 0);
+        assert_eq!(stats.total_waits, 6);
+        assert_eq!(stats.in_use, 0);
+        assert_eq!(stats.peak_in_use, 2);
+        assert!(stats.total_wait_time > Duration::ZERO);
+        assert_eq!(ended.load(Ordering::Relaxed), 6);
+    })
+}
+
+#[test]
+fn registry_stats_track_contention() {
+    tokio_uring::start(async {
+        let buffers =
+            registry::register((0..2).map(|_| Vec::<u8>::with_capacity(16).into())).unwrap();
+        let held = [buffers.check_out(0).unwrap(), buffers.check_out(1).unwrap()];
+        assert_eq!(buffers.stats().peak_in_use, 2);
+
+        let tasks = (0..5)
+            .map(|i| {
+                let buffers = buffers.clone();
+                tokio_uring::spawn(async move {
+                    let buf = match i % 2 {
+                        0 => buffers.check_out_async(0).await.unwrap(),
+                        _ => buffers.check_out_any_async().await.unwrap(),
+                    };
+                    tokio::task::yield_now().await;
+                    mem::drop(buf);
+                })
+            })
+            .collect::<Vec<_>>();
+        tokio::task::yield_now().await;
+        assert_eq!(buffers.stats().waiters, 5);
+
+        // A waiter that gives up stops counting as one.
+        let mut gave_up = Box::pin(buffers.check_out_async(1));
+        let waker = futures_util::task::noop_waker();
+        let mut cx = std::task::Context::from_waker(&waker);
+        assert!(std::future::Future::poll(gave_up.as_mut(), &mut cx).is_pending());
+        assert_eq!(buffers.stats().waiters, 6);
+        mem::drop(gave_up);
+        assert_eq!(buffers.stats().waiters, 5);
+
+        mem::drop(held);
+        for task in tasks {
+            task.await.unwrap();
+        }
+        let stats = buffers.stats();
+        assert_eq!(stats.waiters, 0);
+        assert_eq!(stats.total_waits, 6);
+        assert_eq!(stats.in_use, 0);
+        assert_eq!(stats.peak_in_use, 2);
+    })
+}
+
+#[test]
+fn tcp_stream_fixed_buffers() {
+    tokio_uring::start(async {
+        let listener = TcpListener::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let data: Vec<u8> = (0..STREAM_LEN).map(|i| (i % 251) as u8).collect();
+        let buffers = registry::register(
+            vec![
+                data.clone().into(),
+                Vec::<u8>::with_capacity(STREAM_LEN).into(),
+            ]
+            .into_iter(),
+        )
+        .unwrap();
+
+        let write_buf = buffers.check_out(0).unwrap();
+        let sender = tokio_uring::spawn(async move {
+            let stream = TcpStream::connect(addr).await.unwrap();
+            stream.write_fixed_all(write_buf).await.unwrap();
+        });
+        let (stream, _) = listener.accept().await.unwrap();
+
+        let received = read_fixed_to_end(buffers.check_out(1).unwrap(), |slice| {
+            stream.read_fixed(slice)
+        })
+        .await;
+        sender.await.unwrap();
+        assert_eq!(received, data);
+    })
+}
+
+#[test]
+fn unix_stream_fixed_buffers() {
+    tokio_uring::start(async {
+        let (a, b) = UnixStream::pair().unwrap();
+
+        let data: Vec<u8> = (0..STREAM_LEN).map(|i| (i % 251) as u8).collect();
+        let buffers = registry::register(
+            vec![
+                data.clone().into(),
+                Vec::<u8>::with_capacity(STREAM_LEN).into(),
+            ]
+            .into_iter(),
+        )
+        .unwrap();
+
+        let write_buf = buffers.check_out(0).unwrap();
+        let sender = tokio_uring::spawn(async move {
+            // Write the first half through a slice, then the second half.
+            let (_, slice) = a
+                .write_fixed_all(write_buf.slice(..STREAM_LEN / 2))
+                .await
+                .unwrap();
+            let (_, slice) = a
+                .write_fixed_all(slice.into_inner().slice(STREAM_LEN / 2..))
+                .await
+                .unwrap();
+            slice.into_inner()
+        });
+
+        let received =
+            read_fixed_to_end(buffers.check_out(1).unwrap(), |slice| b.read_fixed(slice)).await;
+        assert_eq!(received, data);
+        sender.await.unwrap();
+    })
+}
+
+const STREAM_LEN: usize = 256 * 1024;
+
+// Reads into successive slices of a registered buffer until it is full.
+async fn read_fixed_to_end<F, Fut>(mut buf: Buffer, mut read: F) -> Vec<u8>
+where
+    F: FnMut(Slice<Buffer>) -> Fut,
+    Fut: std::future::Future<Output = tokio_uring::Result<usize, Slice<Buffer>>>,
+{
+    let mut filled = 0;
+    while filled < buf.bytes_total() {
+        let (n, slice) = read(buf.slice(filled..)).await.unwrap();
+        assert_ne!(n, 0, "unexpected end of stream");
+        filled += n;
+        buf = slice.into_inner();
+    }
+    assert_eq!(buf.bytes_init(), filled);
+    buf[0].to_vec()
+}
+
+#[test]
+fn zero_on_check_in() {
+    tokio_uring::start(async {
+        let mut tempfile = tempfile();
+        tempfile.write_all(HELLO).unwrap();
+        let file = File::open(tempfile.path()).await.unwrap();
+
+        let buffers = registry::register(iter::once(Vec::<u8>::with_capacity(30).into()))
+            .unwrap()
+            .zero_on_check_in(true);
+
+        // A read fills the buffer, which is zeroed as it is dropped.
+        let buf = buffers.check_out(0).unwrap();
+        let (n, buf) = file.read_fixed_at(buf, 0).await.unwrap();
+        assert_eq!(&buf[0][..n], HELLO);
+        drop(buf);
+        let buf = buffers.check_out(0).unwrap();
+        assert_eq!(buf.len(), HELLO.len());
+        assert!(buf[0].iter().all(|&b| b == 0));
+
+        // Unless it is checked in explicitly without zeroing.
+        let (_, buf) = file.read_fixed_at(buf, 0).await.unwrap();
+        fixed::check_in_without_zero(buf);
+        let buf = buffers.check_out(0).unwrap();
+        assert_eq!(&buf[0], HELLO);
+        drop(buf);
+
+        // The same goes for a pool.
+        registry::unregister().unwrap();
+        let pool = pool::register(iter::once(Vec::<u8>::with_capacity(30).into()))
+            .unwrap()
+            .zero_on_check_in(true);
+        let buf = pool.try_next(30).unwrap();
+        let (_, buf) = file.read_fixed_at(buf, 0).await.unwrap();
+        drop(buf);
+        let buf = pool.try_next(30).unwrap();
+        assert!(buf[0].iter().all(|&b| b == 0));
+        let (_, buf) = file.read_fixed_at(buf, 0).await.unwrap();
+        fixed::check_in_without_zero(buf);
+        assert_eq!(&pool.try_next(30).unwrap()[0], HELLO);
+    });
+}
+
+#[test]
+fn register_raw_regions() {
+    tokio_uring::start(async {
+        let tempfile = tempfile();
+        let file = StdFile::options()
+            .read(true)
+            .write(true)
+            .open(tempfile.path())
+            .unwrap();
+        let file = File::from_std(file);
+
+        let arena: &'static mut [u8] = Box::leak(vec![0; 60].into_boxed_slice());
+        arena[..HELLO.len()].copy_from_slice(HELLO);
+        let regions = arena
+            .chunks_exact_mut(30)
+            .map(|c| (c.as_mut_ptr(), c.len()));
+        let buffers = unsafe { registry::register_raw(regions) }.unwrap();
+
+        // The regions are buffers whose whole length is initialized.
+        let buf = buffers.check_out(0).unwrap();
+        assert_eq!(buf.len(), 30);
+        let (n, buf) = file
+            .write_fixed_at(buf.slice(..HELLO.len()), 0)
+            .await
+            .unwrap();
+        assert_eq!(n, HELLO.len());
+        let buf = buf.into_inner();
+
+        let other = buffers.check_out(1).unwrap();
+        let (n, other) = file.read_fixed_at(other, 0).await.unwrap();
+        assert_eq!(&other[0][..n], HELLO);
+
+        // Unregistering fails while any region is checked out.
+        drop(buf);
+        let err = registry::unregister().unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::ResourceBusy);
+        drop(other);
+        registry::unregister().unwrap();
+    });
+}
+
+#[test]
+fn fixed_buffers_refuse_to_coalesce() {
+    tokio_uring::start(async {
+        let buffers = registry::register(iter::once(Buffer::from(HELLO.to_vec()))).unwrap();
+        let buf = buffers.check_out(0).unwrap();
+        let err = buf.coalesce().unwrap_err();
+        assert_eq!(err.0.kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(statuses(&buffers), [registry::BufStatus::CheckedOut]);
+        assert_eq!(&err.1[0], HELLO);
+    });
+}
+
+#[test]
+fn prefault_keeps_buffers_as_they_were() {
+    const LEN: usize = 64 * 1024;
+
+    let contents: Vec<u8> = (0..LEN).map(|i| (i % 251) as u8).collect();
+    let mut src = tempfile();
+    src.write_all(&contents).unwrap();
+
+    tokio_uring::start(async {
+        let buffers = registry::register(
+            vec![
+                Vec::<u8>::with_capacity(LEN).into(),
+                HELLO.to_vec().into(),
+                Vec::<u8>::with_capacity(LEN).into(),
+            ]
+            .into_iter(),
+        )
+        .unwrap();
+        let held = buffers.check_out(2).unwrap();
+        buffers.prefault().unwrap();
+        assert_eq!(
+            statuses(&buffers),
+            [
+                registry::BufStatus::Free,
+                registry::BufStatus::Free,
+                registry::BufStatus::CheckedOut
+            ]
+        );
+
+        // Reads still see an empty buffer, and initialized bytes are kept.
+        let buf = buffers.check_out(0).unwrap();
+        assert_eq!(buf.bytes_init(), 0);
+        assert_eq!(&buffers.check_out(1).unwrap()[0], HELLO);
+        let file = File::open(src.path()).await.unwrap();
+        let (n, buf) = file.read_fixed_at(buf, 0).await.unwrap();
+        assert_eq!(n, LEN);
+        assert_eq!(&buf[0][..], &contents[..]);
+        mem::drop(held);
+    });
+}
+
+#[test]
+fn pool_prefault_on_register() {
+    const LEN: usize = 64 * 1024;
+
+    let contents: Vec<u8> = (0..LEN).map(|i| (i % 251) as u8).collect();
+    let mut src = tempfile();
+    src.write_all(&contents).unwrap();
+
+    tokio_uring::start(async {
+        let buffers = pool::register(iter::once(Vec::<u8>::with_capacity(LEN).into()))
+            .unwrap()
+            .prefault()
+            .unwrap();
+        let buf = buffers.try_next(LEN).unwrap();
+        assert_eq!(buf.bytes_init(), 0);
+
+        let file = File::open(src.path()).await.unwrap();
+        let (n, buf) = file.read_fixed_at(buf, 0).await.unwrap();
+        assert_eq!(n, LEN);
+        assert_eq!(&buf[0][..], &contents[..]);
+    });
+}
+
+#[cfg(feature = "dmabuf")]
+#[test]
+fn dmabuf_import_rejects_other_fds() {
+    use std::os::unix::io::OwnedFd;
+    use tokio_uring::buf::DmaBuf;
+
+    let fd = OwnedFd::from(tempfile().into_file());
+    let err = DmaBuf::import(fd, 4096).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[cfg(feature = "dmabuf")]
+#[test]
+fn dmabuf_udmabuf_read_fixed() {
+    use std::os::unix::io::{AsRawFd, FromRawFd, OwnedFd};
+    use tokio_uring::buf::DmaBuf;
+
+    #[repr(C)]
+    struct UdmabufCreate {
+        memfd: u32,
+        flags: u32,
+        offset: u64,
+        size: u64,
+    }
+    const UDMABUF_CREATE: libc::c_ulong = 0x4018_7542;
+    const UDMABUF_FLAGS_CLOEXEC: u32 = 1;
+    const LEN: usize = 64 * 1024;
+
+    // Without the udmabuf driver there is no dma-buf to import.
+    let Ok(dev) = StdFile::options()
+        .read(true)
+        .write(true)
+        .open("/dev/udmabuf")
+    else {
+        return;
+    };
+    // The driver exports the pages of a memfd that cannot shrink.
+    let memfd =
+        unsafe { libc::memfd_create(b"udmabuf\0".as_ptr().cast(), libc::MFD_ALLOW_SEALING) };
+    assert!(memfd >= 0);
+    let memfd = unsafe { OwnedFd::from_raw_fd(memfd) };
+    StdFile::from(memfd.try_clone().unwrap())
+        .set_len(LEN as u64)
+        .unwrap();
+    assert_eq!(
+        unsafe { libc::fcntl(memfd.as_raw_fd(), libc::F_ADD_SEALS, libc::F_SEAL_SHRINK) },
+        0
+    );
+    let create = UdmabufCreate {
+        memfd: memfd.as_raw_fd() as u32,
+        flags: UDMABUF_FLAGS_CLOEXEC,
+        offset: 0,
+        size: LEN as u64,
+    };
+    let fd = unsafe { libc::ioctl(dev.as_raw_fd(), UDMABUF_CREATE, &create) };
+    assert!(fd >= 0, "{}", std::io::Error::last_os_error());
+    let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+    assert_eq!(
+        DmaBuf::import(fd.try_clone().unwrap(), LEN + 1)
+            .unwrap_err()
+            .kind(),
+        std::io::ErrorKind::InvalidInput
+    );
+    let dmabuf = DmaBuf::import(fd, LEN).unwrap();
+    assert_eq!(dmabuf.len(), LEN);
+
+    let contents: Vec<u8> = (0..LEN).map(|i| (i % 251) as u8).collect();
+    let mut src = tempfile();
+    src.write_all(&contents).unwrap();
+
+    tokio_uring::start(async {
+        let buffers = registry::register(iter::once(Buffer::from(dmabuf))).unwrap();
+        let file = File::open(src.path()).await.unwrap();
+        let buf = buffers.check_out(0).unwrap();
+        let (n, buf) = file.read_fixed_at(buf, 0).await.unwrap();
+        assert_eq!(n, LEN);
+        assert_eq!(&buf[0][..], &contents[..]);
+    });
+}
+
+fn statuses(buffers: &registry::FixedBufRegistry) -> Vec<registry::BufStatus> {
+    buffers
+        .snapshot()
+        .iter()
+        .map(|slot| slot.status())
+        .collect()
+}
+
 fn tempfile() -> NamedTempFile {
     NamedTempFile::new().unwrap()
 }
@@ -0,0 +1,105 @@
+use std::future::Future;
+use std::io::{BufRead, Write};
+
+use tempfile::NamedTempFile;
+use tokio_uring::fs::{BufReader, File};
+
+fn generated_file() -> NamedTempFile {
+    let mut tempfile = NamedTempFile::new().unwrap();
+    let mut contents = String::new();
+    for i in 0..100_000 {
+        match i % 1000 {
+            // Lines longer than the reader's buffer.
+            0 => contents.push_str(&format!("{}{}\n", i, "x".repeat(10_000))),
+            1 => contents.push_str(&format!("crlf {}\r\n", i)),
+            2 => contents.push('\n'),
+            _ => contents.push_str(&format!("line number {}\n", i)),
+        }
+    }
+    contents.push_str("no trailing newline");
+    tempfile.write_all(contents.as_bytes()).unwrap();
+    tempfile
+}
+
+#[test]
+fn lines_match_std() {
+    let tempfile = generated_file();
+    let expected: Vec<String> =
+        std::io::BufReader::new(std::fs::File::open(tempfile.path()).unwrap())
+            .lines()
+            .map(Result::unwrap)
+            .collect();
+
+    for capacity in [4096, 64 * 1024] {
+        let got = tokio_uring::start(async {
+            let file = File::open(tempfile.path()).await.unwrap();
+            let mut lines = BufReader::with_capacity(capacity, file).lines();
+            let mut got = Vec::new();
+            while let Some(line) = lines.next_line().await.unwrap() {
+                got.push(line);
+            }
+            got
+        });
+        assert_eq!(got.len(), expected.len());
+        assert!(got == expected);
+    }
+}
+
+#[test]
+fn read_line_keeps_terminators() {
+    let mut tempfile = NamedTempFile::new().unwrap();
+    tempfile.write_all(b"one\r\ntwo\nthree").unwrap();
+
+    tokio_uring::start(async {
+        let file = File::open(tempfile.path()).await.unwrap();
+        let mut reader = BufReader::with_capacity(2, file);
+
+        let mut line = String::new();
+        assert_eq!(reader.read_line(&mut line).await.unwrap(), 5);
+        assert_eq!(reader.read_line(&mut line).await.unwrap(), 4);
+        assert_eq!(reader.read_line(&mut line).await.unwrap(), 5);
+        assert_eq!(reader.read_line(&mut line).await.unwrap(), 0);
+        assert_eq!(line, "one\r\ntwo\nthree");
+        assert_eq!(reader.position(), 14);
+    });
+}
+
+#[test]
+fn file_grows_during_iteration() {
+    let mut tempfile = NamedTempFile::new().unwrap();
+    tempfile.write_all(b"first\n").unwrap();
+
+    tokio_uring::start(async {
+        let file = File::open(tempfile.path()).await.unwrap();
+        let mut lines = BufReader::new(file).lines();
+
+        assert_eq!(lines.next_line().await.unwrap().unwrap(), "first");
+        assert!(lines.next_line().await.unwrap().is_none());
+
+        tempfile.write_all(b"second\n").unwrap();
+        assert_eq!(lines.next_line().await.unwrap().unwrap(), "second");
+        assert!(lines.next_line().await.unwrap().is_none());
+    });
+}
+
+#[test]
+fn cancelled_fill_buf_keeps_data() {
+    let mut tempfile = NamedTempFile::new().unwrap();
+    tempfile.write_all(b"hello world").unwrap();
+
+    tokio_uring::start(async {
+        let file = File::open(tempfile.path()).await.unwrap();
+        let mut reader = BufReader::new(file);
+
+        // Poll once so the read is submitted, then drop the future.
+        {
+            let fut = reader.fill_buf();
+            tokio::pin!(fut);
+            let _ = std::future::poll_fn(|cx| std::task::Poll::Ready(fut.as_mut().poll(cx))).await;
+        }
+
+        assert_eq!(reader.fill_buf().await.unwrap(), b"hello world");
+        reader.consume(6);
+        assert_eq!(reader.fill_buf().await.unwrap(), b"world");
+    });
+}
@@ -0,0 +1,62 @@
+use tokio_uring::buf::AlignedBuf;
+use tokio_uring::fs::Device;
+use tokio_uring::Buffer;
+
+// Scratch block device for these tests, e.g. a loop device. Its contents are overwritten.
+const DEVICE_ENV: &str = "TOKIO_URING_TEST_BLOCK_DEVICE";
+
+const ALIGN: usize = 4096;
+
+#[test]
+fn device_rejects_regular_file() {
+    tokio_uring::start(async {
+        let tempfile = tempfile::NamedTempFile::new().unwrap();
+        let err = Device::open(tempfile.path()).await.err().unwrap();
+        assert_eq!(err.raw_os_error(), Some(libc::ENOTBLK));
+    });
+}
+
+#[test]
+fn device_read_write() {
+    let path = match std::env::var(DEVICE_ENV) {
+        Ok(path) => path,
+        Err(_) => return,
+    };
+
+    tokio_uring::start(async {
+        let dev = Device::open_rw(&path).await.unwrap();
+        let size = dev.size_bytes();
+        let bs = dev.block_size() as usize;
+        assert!(size > 0);
+        assert!(bs.is_power_of_two());
+        assert!(dev.physical_block_size() as usize >= bs);
+
+        let mut buf = Buffer::from(AlignedBuf::zeroed(ALIGN, ALIGN));
+        buf[0].fill(0xa5);
+        let (n, _) = dev.write_at(buf, 0).await.unwrap();
+        assert_eq!(n, ALIGN);
+
+        let (n, buf) = dev
+            .read_at(Buffer::from(AlignedBuf::new(ALIGN, ALIGN)), 0)
+            .await
+            .unwrap();
+        assert_eq!(n, ALIGN);
+        assert!(buf[0].iter().all(|&b| b == 0xa5));
+
+        // Misaligned offset.
+        let err = dev
+            .read_at(Buffer::from(AlignedBuf::new(ALIGN, ALIGN)), 1)
+            .await
+            .unwrap_err();
+        assert_eq!(err.0.kind(), std::io::ErrorKind::InvalidInput);
+
+        // Starting at the end of the device.
+        let err = dev
+            .read_at(Buffer::from(AlignedBuf::new(ALIGN, ALIGN)), size)
+            .await
+            .unwrap_err();
+        assert_eq!(err.0.kind(), std::io::ErrorKind::UnexpectedEof);
+
+        dev.close().await.unwrap();
+    });
+}
@@ -0,0 +1,131 @@
+use std::os::unix::fs::OpenOptionsExt;
+
+use tokio_uring::buf::fixed::registry;
+use tokio_uring::buf::AlignedBuf;
+use tokio_uring::fs::{DirectWriter, OpenOptions};
+use tokio_uring::Buffer;
+
+const ALIGN: usize = 4096;
+
+// Deterministic pseudo-random records of varying size.
+fn records(total: usize) -> Vec<Vec<u8>> {
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+
+    let mut out = Vec::new();
+    let mut size = 0;
+    while size < total {
+        let len = (next() % 10_000) as usize + 1;
+        let record: Vec<u8> = (0..len).map(|_| next() as u8).collect();
+        size += len;
+        out.push(record);
+    }
+    out
+}
+
+fn direct_write(nbufs: usize, truncate: bool, dio_alignment: bool) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("direct");
+    let records = records(3 * 1024 * 1024);
+    let expected = records.concat();
+    assert_ne!(expected.len() % ALIGN, 0, "the tail must be ragged");
+
+    let opened = tokio_uring::start(async {
+        let file = match OpenOptions::new()
+            .write(true)
+            .create(true)
+            .custom_flags(libc::O_DIRECT)
+            .open(&path)
+            .await
+        {
+            Ok(file) => file,
+            // The filesystem backing the temp dir may not support direct I/O.
+            Err(e) if e.raw_os_error() == Some(libc::EINVAL) => return false,
+            Err(e) => panic!("{}", e),
+        };
+
+        let registry = registry::register(
+            (0..nbufs).map(|_| Buffer::from(AlignedBuf::new(16 * ALIGN, ALIGN))),
+        )
+        .unwrap();
+        let bufs = (0..nbufs).map(|i| registry.check_out(i).unwrap()).collect();
+
+        let mut writer = if dio_alignment {
+            DirectWriter::with_dio_alignment(file, bufs).await.unwrap()
+        } else {
+            DirectWriter::new(file, bufs, ALIGN).unwrap()
+        };
+        writer.truncate_on_close(truncate);
+        for (i, record) in records.iter().enumerate() {
+            if i % 2 == 0 {
+                writer.write(record).await.unwrap();
+            } else {
+                writer
+                    .write_buffer(&Buffer::from(record.clone()))
+                    .await
+                    .unwrap();
+            }
+        }
+        assert_eq!(writer.len(), expected.len() as u64);
+        writer.close().await.unwrap();
+
+        registry::unregister().unwrap();
+        true
+    });
+    if !opened {
+        return;
+    }
+
+    let written = std::fs::read(&path).unwrap();
+    if truncate {
+        assert_eq!(written.len(), expected.len());
+    } else {
+        assert_eq!(written.len() % ALIGN, 0);
+        assert!(written[expected.len()..].iter().all(|&b| b == 0));
+    }
+    assert!(written[..expected.len()] == expected[..]);
+}
+
+#[test]
+fn direct_writer_double_buffered() {
+    direct_write(2, true, false);
+}
+
+#[test]
+fn direct_writer_single_buffer() {
+    direct_write(1, true, false);
+}
+
+#[test]
+fn direct_writer_keeps_padding() {
+    direct_write(2, false, false);
+}
+
+#[test]
+fn direct_writer_with_dio_alignment() {
+    direct_write(2, true, true);
+}
+
+#[test]
+fn direct_writer_rejects_unaligned_buffers() {
+    tokio_uring::start(async {
+        let dir = tempfile::tempdir().unwrap();
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .open(dir.path().join("f"))
+            .await
+            .unwrap();
+
+        // A plain vector is not a fixed buffer.
+        let err = DirectWriter::new(file, vec![Buffer::from(vec![0u8; ALIGN])], ALIGN)
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    });
+}
@@ -28,3 +28,44 @@ fn basic_remove_dir() {
         assert!(std::fs::metadata(temp_dir.path()).is_err());
     });
 }
+
+#[test]
+fn create_dir_all_nested() {
+    tokio_uring::start(async {
+        let base_dir = tempdir().unwrap();
+        let nested = base_dir.path().join("a/b/c/d");
+
+        assert_ok!(fs::create_dir_all(&nested).await);
+        assert!(nested.is_dir());
+
+        // Repeating the call on an existing tree is not an error.
+        assert_ok!(fs::create_dir_all(&nested).await);
+        assert!(nested.is_dir());
+    });
+}
+
+#[test]
+fn create_dir_all_trailing_slash() {
+    tokio_uring::start(async {
+        let base_dir = tempdir().unwrap();
+        let nested = base_dir.path().join("a/b/");
+
+        assert_ok!(fs::create_dir_all(&nested).await);
+        assert!(base_dir.path().join("a/b").is_dir());
+    });
+}
+
+#[test]
+fn create_dir_all_file_in_the_way() {
+    tokio_uring::start(async {
+        let base_dir = tempdir().unwrap();
+        let file = base_dir.path().join("a");
+        std::fs::write(&file, b"").unwrap();
+
+        let err = fs::create_dir_all(&file).await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists);
+
+        let err = fs::create_dir_all(file.join("b/c")).await.unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(libc::ENOTDIR));
+    });
+}
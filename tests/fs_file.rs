@@ -1,6 +1,6 @@
 use std::{
     io::prelude::*,
-    os::unix::io::{AsRawFd, FromRawFd, RawFd},
+    os::unix::io::{AsRawFd, RawFd},
 };
 
 use tempfile::NamedTempFile;
@@ -71,6 +71,39 @@ fn vectored_read() {
     });
 }
 
+#[test]
+fn vectored_read_coalesce() {
+    tokio_uring::start(async {
+        let mut tempfile = tempfile();
+        tempfile.write_all(HELLO).unwrap();
+        let file = File::open(tempfile.path()).await.unwrap();
+
+        // No room to spare in the first segment: the bytes are copied out.
+        let bufs = Buffer::new(vec![
+            Vec::<u8>::with_capacity(5),
+            Vec::<u8>::with_capacity(9),
+        ]);
+        let (_, bufs) = file.read_at(bufs, 0).submit().await.unwrap();
+        assert_eq!(bufs.to_contiguous_vec(), HELLO);
+        let buf = bufs.coalesce().unwrap();
+        assert_eq!(buf.segment_count(), 1);
+        assert_eq!(&buf[0], HELLO);
+
+        // With room to spare, the first segment takes in the others.
+        let bufs = Buffer::new(vec![
+            Vec::<u8>::with_capacity(16),
+            Vec::<u8>::with_capacity(9),
+        ]);
+        let first = bufs[0].as_ptr();
+        let (n, bufs) = file.read_at(bufs, 0).submit().await.unwrap();
+        assert_eq!(n, HELLO.len());
+        let buf = bufs.coalesce().unwrap();
+        assert_eq!(&buf[0], HELLO);
+        assert_eq!(buf[0].as_ptr(), first);
+        assert_eq!(buf.capacity(), 16);
+    });
+}
+
 #[test]
 fn vectored_write() {
     tokio_uring::start(async {
@@ -88,6 +121,255 @@ fn vectored_write() {
     });
 }
 
+#[test]
+fn view_writes_range_across_segments() {
+    tokio_uring::start(async {
+        let tempfile = tempfile();
+        let file = File::create(tempfile.path()).await.unwrap();
+        let bufs = Buffer::new(vec![b"abcd".to_vec(), b"efgh".to_vec(), b"ijkl".to_vec()]);
+
+        // Starts mid first segment and ends mid last one.
+        let (n, view) = file.write_at(bufs.view(3..10), 0).submit().await.unwrap();
+        assert_eq!(n, 7);
+        assert_eq!(std::fs::read(tempfile.path()).unwrap(), b"defghij");
+
+        // Within a single segment, and empty.
+        let bufs = view.into_inner();
+        let (n, view) = file.write_at(bufs.view(5..7), 7).submit().await.unwrap();
+        assert_eq!(n, 2);
+        let (n, view) = file
+            .write_at(view.into_inner().view(4..4), 9)
+            .submit()
+            .await
+            .unwrap();
+        assert_eq!(n, 0);
+        assert!(view.is_empty());
+        assert_eq!(std::fs::read(tempfile.path()).unwrap(), b"defghijfg");
+
+        let bufs = view.into_inner();
+        assert_eq!(bufs.segment_count(), 3);
+        assert_eq!(&bufs[1][..], b"efgh");
+    });
+}
+
+#[test]
+fn copy_and_extend_across_segments() {
+    tokio_uring::start(async {
+        let tempfile = tempfile();
+        let file = File::create(tempfile.path()).await.unwrap();
+        let mut buf = Buffer::new(vec![Vec::<u8>::with_capacity(8), Vec::with_capacity(8)]);
+        assert_eq!(buf.bytes_total(), 16);
+
+        // Crosses from the end of the first segment into the second.
+        buf.extend_from_slice(b".....").unwrap();
+        buf.extend_from_slice(b"abcdef").unwrap();
+        assert_eq!(&buf[0][..], b".....abc");
+        assert_eq!(&buf[1][..], b"def");
+
+        // Overwrites initialized bytes, extending past them.
+        buf.copy_from_slice(7, b"XYZW").unwrap();
+        assert_eq!(&buf[0][..], b".....abX");
+        assert_eq!(&buf[1][..], b"YZW");
+
+        // Neither grows the buffer, nor leaves a gap of uninitialized bytes.
+        assert!(buf.extend_from_slice(&[0; 6]).is_err());
+        assert!(buf.copy_from_slice(12, b"!").is_err());
+        buf.extend_from_slice(b"-----").unwrap();
+        assert_eq!(buf.bytes_init(), 16);
+
+        let (n, _) = file.write_at(buf, 0).submit().await.unwrap();
+        assert_eq!(n, 16);
+        assert_eq!(std::fs::read(tempfile.path()).unwrap(), b".....abXYZW-----");
+    });
+}
+
+#[test]
+fn view_reads_into_range() {
+    tokio_uring::start(async {
+        let mut tempfile = tempfile();
+        tempfile.write_all(HELLO).unwrap();
+        let file = File::open(tempfile.path()).await.unwrap();
+        let bufs = Buffer::new(vec![
+            Vec::<u8>::with_capacity(4),
+            Vec::<u8>::with_capacity(4),
+            Vec::<u8>::with_capacity(4),
+        ]);
+
+        let (n, view) = file.read_at(bufs.view(..6), 0).submit().await.unwrap();
+        assert_eq!(n, 6);
+        assert_eq!(view.segment_count(), 2);
+        let bufs = view.into_inner();
+        assert_eq!(&bufs[0][..], b"hell");
+        assert_eq!(&bufs[1][..], b"o ");
+        assert!(bufs[2].is_empty());
+
+        // A narrower view of a view, continuing the second segment's data.
+        let view = bufs.view(4..).view(2..3);
+        let (n, view) = file.read_at(view, 6).submit().await.unwrap();
+        assert_eq!(n, 1);
+        let bufs = view.into_inner();
+        assert_eq!(&bufs[1][..], b"o w");
+
+        // Bytes read past a gap in a segment do not count as initialized.
+        let (n, view) = file.read_at(bufs.view(10..12), 0).submit().await.unwrap();
+        assert_eq!(n, 2);
+        let bufs = view.into_inner();
+        assert!(bufs[2].is_empty());
+    });
+}
+
+#[test]
+fn try_into_recovers_vec_after_read() {
+    tokio_uring::start(async {
+        let mut tempfile = tempfile();
+        tempfile.write_all(HELLO).unwrap();
+        let file = File::open(tempfile.path()).await.unwrap();
+
+        let vec = Vec::<u8>::with_capacity(32);
+        let ptr = vec.as_ptr();
+        let (n, buf) = file.read_at(vec.into(), 0).submit().await.unwrap();
+        assert_eq!(n, HELLO.len());
+
+        // The wrong type hands the buffer back.
+        let buf = buf.try_into::<Vec<Vec<u8>>>().unwrap_err();
+        let vec = buf.try_into::<Vec<u8>>().unwrap();
+        assert_eq!(vec.as_ptr(), ptr);
+        assert_eq!((vec.len(), vec.capacity()), (HELLO.len(), 32));
+        assert_eq!(vec, HELLO);
+
+        let vecs = vec![Vec::<u8>::with_capacity(4), Vec::<u8>::with_capacity(16)];
+        let (n, buf) = file.read_at(vecs.into(), 0).submit().await.unwrap();
+        assert_eq!(n, HELLO.len());
+        let vecs = buf.try_into::<Vec<Vec<u8>>>().unwrap();
+        assert_eq!(vecs, [&HELLO[..4], &HELLO[4..]]);
+        assert_eq!(vecs[1].capacity(), 16);
+
+        // A registered buffer stays with its registry.
+        let buffers =
+            registry::register(std::iter::once(Vec::<u8>::with_capacity(8).into())).unwrap();
+        let fixed = buffers.check_out(0).unwrap();
+        assert!(fixed.try_into::<Vec<u8>>().is_err());
+        assert!(buffers.check_out(0).is_some());
+    });
+}
+
+#[test]
+fn read_into_boxed_slice() {
+    tokio_uring::start(async {
+        let mut tempfile = tempfile();
+        tempfile.write_all(HELLO).unwrap();
+        let file = File::open(tempfile.path()).await.unwrap();
+        let boxed: Box<[u8]> = vec![b'-'; HELLO.len() + 2].into();
+        let ptr = boxed.as_ptr();
+
+        let (n, buf) = file.read_at(boxed.into(), 0).submit().await.unwrap();
+        assert_eq!(n, HELLO.len());
+        assert_eq!(&buf[0][..], HELLO);
+
+        // The slice comes back whole, bytes past the read included.
+        let boxed = buf.try_into::<Box<[u8]>>().unwrap();
+        assert_eq!(boxed.as_ptr(), ptr);
+        assert_eq!(&boxed[..n], HELLO);
+        assert_eq!(&boxed[n..], b"--");
+    });
+}
+
+#[cfg(feature = "bytes")]
+#[test]
+fn write_bytes_keeps_storage() {
+    tokio_uring::start(async {
+        let tempfile = tempfile();
+        let file = File::create(tempfile.path()).await.unwrap();
+        let bytes = bytes::Bytes::from(HELLO.to_vec());
+        let ptr = bytes.as_ptr();
+
+        let (n, buf) = file
+            .write_at(bytes.clone().into(), 0)
+            .submit()
+            .await
+            .unwrap();
+        assert_eq!(n, HELLO.len());
+        let bytes = buf.try_into::<bytes::Bytes>().unwrap();
+        assert_eq!(bytes.as_ptr(), ptr);
+        assert_eq!(std::fs::read(tempfile.path()).unwrap(), HELLO);
+    });
+}
+
+#[cfg(feature = "bytes")]
+#[test]
+fn read_into_bytes_fails() {
+    tokio_uring::start(async {
+        let tempfile = tempfile();
+        let file = File::open(tempfile.path()).await.unwrap();
+        let bytes = bytes::Bytes::from(HELLO.to_vec());
+        let err = file.read_at(bytes.into(), 0).submit().await.unwrap_err();
+        assert_eq!(err.0.kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(&err.1[0][..], HELLO);
+    });
+}
+
+#[cfg(feature = "bytes")]
+#[test]
+fn read_bytes_mut_into_spare_capacity() {
+    tokio_uring::start(async {
+        let mut tempfile = tempfile();
+        tempfile.write_all(HELLO).unwrap();
+        let file = File::open(tempfile.path()).await.unwrap();
+
+        // The tail shares the head's allocation, and reads into its own region of it.
+        let mut head = bytes::BytesMut::with_capacity(64);
+        head.extend_from_slice(b"> ");
+        let tail = head.split_off(2);
+        let ptr = tail.as_ptr();
+
+        let (n, buf) = file.read_at(tail.into(), 0).submit().await.unwrap();
+        assert_eq!(n, HELLO.len());
+        let tail = buf.try_into::<bytes::BytesMut>().unwrap();
+        assert_eq!(tail.as_ptr(), ptr);
+        assert_eq!(&tail[..], HELLO);
+
+        // Still contiguous, the halves join back without a copy.
+        head.unsplit(tail);
+        assert_eq!(&head[..2], b"> ");
+        assert_eq!(&head[2..], HELLO);
+    });
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn write_mmap_views_concurrently() {
+    use tokio_uring::buf::MmapBuf;
+
+    tokio_uring::start(async {
+        let page = 4096;
+        let contents: Vec<u8> = (0..3 * page).map(|i| (i / page) as u8 + b'a').collect();
+        let mut src = tempfile();
+        src.write_all(&contents).unwrap();
+        let map = MmapBuf::map(src.as_file(), 0, contents.len()).unwrap();
+        assert_eq!(&map[..], &contents[..]);
+
+        // Each page goes to its own offset of the copy, all from the one mapping.
+        let dst = tempfile();
+        let file = File::create(dst.path()).await.unwrap();
+        let writes = (0..3).map(|i| {
+            let view = Buffer::from(map.clone()).view(i * page..(i + 1) * page);
+            file.write_at(view, (i * page) as u64).submit()
+        });
+        for res in futures_util::future::join_all(writes).await {
+            let (n, view) = res.unwrap();
+            assert_eq!(n, page);
+            assert!(view.into_inner().is_read_only());
+        }
+        assert_eq!(std::fs::read(dst.path()).unwrap(), contents);
+
+        // A read would write through the mapping, so it cannot be registered either.
+        match registry::register(std::iter::once(Buffer::from(map))) {
+            Err(e) => assert_eq!(e.kind(), std::io::ErrorKind::InvalidInput),
+            Ok(_) => panic!("registered a read-only buffer"),
+        }
+    });
+}
+
 #[test]
 fn cancel_read() {
     tokio_uring::start(async {
@@ -122,7 +404,7 @@ fn explicit_close() {
 fn drop_open() {
     tokio_uring::start(async {
         let tempfile = tempfile();
-        let _ = File::create(tempfile.path());
+        drop(File::create(tempfile.path()));
 
         // Do something else
         let file = File::create(tempfile.path()).await.unwrap();
@@ -319,6 +601,520 @@ fn write_linked() {
     });
 }
 
+#[test]
+fn write_at_sync() {
+    tokio_uring::start(async {
+        let tempfile = tempfile();
+        let file = File::create(tempfile.path()).await.unwrap();
+
+        for datasync in [false, true] {
+            let (res, sync) = file
+                .write_at_sync(Buffer::new(HELLO.to_vec()), 0, datasync)
+                .await;
+            let (n, buf) = res.unwrap();
+            sync.unwrap();
+
+            assert_eq!(n, HELLO.len());
+            assert_eq!(&buf[0][..], HELLO);
+        }
+
+        let file = std::fs::read(tempfile.path()).unwrap();
+        assert_eq!(file, HELLO);
+    });
+}
+
+#[test]
+fn write_at_sync_read_only() {
+    tokio_uring::start(async {
+        let tempfile = tempfile();
+        let file = File::open(tempfile.path()).await.unwrap();
+
+        let (res, sync) = file
+            .write_at_sync(Buffer::new(HELLO.to_vec()), 0, false)
+            .await;
+
+        // The write's own error is preserved; the linked sync is cancelled.
+        let err = res.unwrap_err();
+        assert_eq!(err.0.raw_os_error(), Some(libc::EBADF));
+        assert_eq!(&err.1[0][..], HELLO);
+        assert_eq!(sync.unwrap_err().raw_os_error(), Some(libc::ECANCELED));
+    });
+}
+
+#[test]
+fn write_atomic_replaces_whole_file() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config");
+    let old = vec![b'a'; 64 * 1024];
+    let new = vec![b'b'; 128 * 1024];
+    std::fs::write(&path, &old).unwrap();
+
+    let done = Arc::new(AtomicBool::new(false));
+    let reader = {
+        let (path, old, new, done) = (path.clone(), old.clone(), new.clone(), done.clone());
+        std::thread::spawn(move || {
+            while !done.load(Ordering::Relaxed) {
+                let contents = std::fs::read(&path).unwrap();
+                assert!(
+                    contents == old || contents == new,
+                    "observed a partial file"
+                );
+            }
+        })
+    };
+
+    tokio_uring::start(async {
+        for _ in 0..20 {
+            tokio_uring::fs::write_atomic(&path, &new).await.unwrap();
+            tokio_uring::fs::write_atomic(&path, &old).await.unwrap();
+        }
+        tokio_uring::fs::write_atomic(&path, &new).await.unwrap();
+    });
+
+    done.store(true, Ordering::Relaxed);
+    reader.join().unwrap();
+
+    assert_eq!(std::fs::read(&path).unwrap(), new);
+    // Only the target remains; no temp files are left behind.
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+}
+
+#[test]
+fn write_atomic_preserves_mode() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("secret");
+    std::fs::write(&path, b"old").unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o640)).unwrap();
+
+    tokio_uring::start(async {
+        tokio_uring::fs::write_atomic(&path, b"new").await.unwrap();
+    });
+
+    let metadata = std::fs::metadata(&path).unwrap();
+    assert_eq!(metadata.permissions().mode() & 0o7777, 0o640);
+    assert_eq!(std::fs::read(&path).unwrap(), b"new");
+}
+
+#[test]
+fn write_atomic_cleans_up_on_failure() {
+    let dir = tempfile::tempdir().unwrap();
+    // Renaming a file over a non-empty directory fails after the temp file is written.
+    let path = dir.path().join("occupied");
+    std::fs::create_dir(&path).unwrap();
+    std::fs::write(path.join("child"), b"").unwrap();
+
+    tokio_uring::start(async {
+        assert!(tokio_uring::fs::write_atomic(&path, b"data").await.is_err());
+    });
+
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+}
+
+#[test]
+fn chunks_stream() {
+    use futures_util::StreamExt;
+
+    let contents: Vec<u8> = (0..100_000u32).map(|i| i as u8).collect();
+    let mut tempfile = tempfile();
+    tempfile.write_all(&contents).unwrap();
+
+    tokio_uring::start(async {
+        let file = File::open(tempfile.path()).await.unwrap();
+
+        let mut chunks = file.chunks(0, 4096);
+        let mut collected = Vec::new();
+        let mut sizes = Vec::new();
+        while let Some(chunk) = chunks.next().await {
+            let chunk = chunk.unwrap();
+            collected.extend_from_slice(&chunk[0]);
+            sizes.push(chunk[0].len());
+            chunks.recycle(chunk);
+        }
+        assert_eq!(collected, contents);
+        // 100_000 is not a multiple of 4096: the last chunk is short.
+        assert_eq!(*sizes.last().unwrap(), 100_000 % 4096);
+        assert!(chunks.next().await.is_none());
+
+        // Starting from an offset, and dropping the stream with a read in flight.
+        let mut chunks = file.chunks(99_000, 512);
+        let first = chunks.next().await.unwrap().unwrap();
+        assert_eq!(&first[0], &contents[99_000..99_512]);
+        drop(chunks);
+    });
+}
+
+#[test]
+fn chunks_reuse_dropped_chunks() {
+    use futures_util::StreamExt;
+
+    let contents = vec![7u8; 16 * 4096];
+    let mut tempfile = tempfile();
+    tempfile.write_all(&contents).unwrap();
+
+    tokio_uring::start(async {
+        let file = File::open(tempfile.path()).await.unwrap();
+
+        // Without recycling, the memory of each dropped chunk goes to a later read, rather
+        // than to whatever the consumer allocates next.
+        let mut chunks = file.chunks(0, 4096);
+        let mut addrs = Vec::new();
+        let mut held = Vec::new();
+        while let Some(chunk) = chunks.next().await {
+            addrs.push(chunk.unwrap()[0].as_ptr() as usize);
+            held.push(Vec::<u8>::with_capacity(4096));
+        }
+        addrs.sort_unstable();
+        addrs.dedup();
+        assert_eq!(addrs.len(), 2);
+    });
+}
+
+#[test]
+fn uring_cmd_unsupported_file() {
+    tokio_uring::start(async {
+        let tempfile = tempfile();
+        let file = File::open(tempfile.path()).await.unwrap();
+
+        // Regular files do not implement uring_cmd; the error comes back from the kernel.
+        let err = unsafe { file.uring_cmd(0, [0; 16]) }.await.unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(libc::EOPNOTSUPP));
+
+        // The default ring has no room for an 80-byte command.
+        let err = unsafe { file.uring_cmd80(0, [0; 80]) }.await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::Unsupported);
+    });
+}
+
+#[test]
+fn uring_cmd80_big_ring() {
+    tokio_uring::builder()
+        .uring_builder_big(&tokio_uring::uring_builder_big())
+        .start(async {
+            let mut tempfile = tempfile();
+            tempfile.write_all(HELLO).unwrap();
+            let file = File::open(tempfile.path()).await.unwrap();
+
+            // Other ops work as usual on a ring of the larger entries.
+            read_hello(&file).await;
+
+            // The command is submitted, and refused by the kernel for a regular file.
+            let err = unsafe { file.uring_cmd80(0, [0; 80]) }.await.unwrap_err();
+            assert_eq!(err.raw_os_error(), Some(libc::EOPNOTSUPP));
+        });
+}
+
+#[test]
+fn open_resolve_beneath() {
+    use std::os::unix::fs::OpenOptionsExt;
+    use tokio_uring::fs::{OpenOptions, ResolveFlags};
+
+    let outside = tempfile::tempdir().unwrap();
+    let root = tempfile::tempdir().unwrap();
+    std::fs::write(outside.path().join("secret"), HELLO).unwrap();
+    std::fs::write(root.path().join("public"), HELLO).unwrap();
+    std::os::unix::fs::symlink(outside.path().join("secret"), root.path().join("escape")).unwrap();
+
+    tokio_uring::start(async {
+        let dir = OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_DIRECTORY)
+            .open(root.path())
+            .await
+            .unwrap();
+
+        // Without restrictions the symlink is followed out of the directory.
+        let file = OpenOptions::new()
+            .read(true)
+            .open_at(&dir, "escape")
+            .await
+            .unwrap();
+        read_hello(&file).await;
+
+        let mut beneath = OpenOptions::new();
+        beneath.read(true).resolve(ResolveFlags::BENEATH);
+
+        let file = beneath.open_at(&dir, "public").await.unwrap();
+        read_hello(&file).await;
+
+        let err = beneath.open_at(&dir, "escape").await.unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(libc::EXDEV));
+        let err = beneath.open_at(&dir, "../x").await.unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(libc::EXDEV));
+
+        let err = OpenOptions::new()
+            .read(true)
+            .resolve(ResolveFlags::NO_SYMLINKS | ResolveFlags::NO_XDEV)
+            .open_at(&dir, "escape")
+            .await
+            .unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(libc::ELOOP));
+    });
+}
+
+fn copy_and_compare(src_dir: &std::path::Path, dst_dir: &std::path::Path) {
+    use std::os::unix::fs::PermissionsExt;
+
+    // Three and a bit megabytes of non-repeating data.
+    let data: Vec<u8> = (0..3 * 1024 * 1024 + 12345u32)
+        .map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8)
+        .collect();
+    let src = src_dir.join("src");
+    let dst = dst_dir.join("dst");
+    std::fs::write(&src, &data).unwrap();
+    std::fs::set_permissions(&src, std::fs::Permissions::from_mode(0o640)).unwrap();
+    // An existing, longer destination is truncated.
+    std::fs::write(&dst, vec![7u8; 4 * 1024 * 1024]).unwrap();
+
+    let n = tokio_uring::start(tokio_uring::fs::copy(&src, &dst)).unwrap();
+    assert_eq!(n, data.len() as u64);
+    assert!(std::fs::read(&dst).unwrap() == data);
+    let mode = std::fs::metadata(&dst).unwrap().permissions().mode();
+    assert_eq!(mode & 0o7777, 0o640);
+}
+
+#[test]
+fn copy_same_filesystem() {
+    let dir = tempfile::tempdir().unwrap();
+    copy_and_compare(dir.path(), dir.path());
+}
+
+#[test]
+fn copy_across_filesystems() {
+    let shm = std::path::Path::new("/dev/shm");
+    if !shm.is_dir() {
+        return;
+    }
+    let disk = tempfile::tempdir().unwrap();
+    let tmpfs = tempfile::tempdir_in(shm).unwrap();
+    copy_and_compare(disk.path(), tmpfs.path());
+    copy_and_compare(tmpfs.path(), disk.path());
+}
+
+#[test]
+fn copy_empty_and_directory() {
+    let dir = tempfile::tempdir().unwrap();
+    let src = dir.path().join("empty");
+    std::fs::write(&src, b"").unwrap();
+
+    tokio_uring::start(async {
+        let n = tokio_uring::fs::copy(&src, dir.path().join("dst"))
+            .await
+            .unwrap();
+        assert_eq!(n, 0);
+        assert_eq!(std::fs::read(dir.path().join("dst")).unwrap().len(), 0);
+
+        let err = tokio_uring::fs::copy(dir.path(), dir.path().join("dst2"))
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    });
+}
+
+fn supports_reflink(path: &std::path::Path) -> bool {
+    use std::os::unix::ffi::OsStrExt;
+
+    const BTRFS_SUPER_MAGIC: i64 = 0x9123_683e;
+    const XFS_SUPER_MAGIC: i64 = 0x5846_5342;
+
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes()).unwrap();
+    let mut buf: libc::statfs = unsafe { std::mem::zeroed() };
+    assert_eq!(unsafe { libc::statfs(path.as_ptr(), &mut buf) }, 0);
+    // XFS only reflinks when formatted with reflink=1, which is the default since xfsprogs 5.1.
+    matches!(buf.f_type as i64, BTRFS_SUPER_MAGIC | XFS_SUPER_MAGIC)
+}
+
+#[test]
+fn clone_range_from() {
+    let dir = tempfile::tempdir().unwrap();
+    let data: Vec<u8> = (0..256 * 1024u32).map(|i| (i % 251) as u8).collect();
+    std::fs::write(dir.path().join("src"), &data).unwrap();
+    let reflink = supports_reflink(dir.path());
+
+    tokio_uring::start(async {
+        let src = File::open(dir.path().join("src")).await.unwrap();
+        let dst = File::create(dir.path().join("dst")).await.unwrap();
+        let block = src.metadata().await.unwrap().blksize();
+
+        let err = dst.clone_range_from(&src, 1, 0, block).await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+
+        match dst.clone_from(&src).await {
+            Ok(()) => assert!(std::fs::read(dir.path().join("dst")).unwrap() == data),
+            Err(e) => {
+                assert!(!reflink, "{}", e);
+                assert_eq!(e.kind(), std::io::ErrorKind::Unsupported);
+                // The fallback produces the same result.
+                tokio_uring::fs::copy(dir.path().join("src"), dir.path().join("dst"))
+                    .await
+                    .unwrap();
+                assert!(std::fs::read(dir.path().join("dst")).unwrap() == data);
+            }
+        }
+
+        let res = dst.clone_range_from(&src, block, 0, block).await;
+        if reflink {
+            res.unwrap();
+            let cloned = std::fs::read(dir.path().join("dst")).unwrap();
+            assert!(cloned[..block as usize] == data[block as usize..2 * block as usize]);
+        } else {
+            assert_eq!(res.unwrap_err().kind(), std::io::ErrorKind::Unsupported);
+        }
+    });
+}
+
+#[test]
+fn write_queue_preserves_order() {
+    use tokio_uring::fs::WriteQueue;
+
+    tokio_uring::start(async {
+        let tempfile = tempfile();
+        let file = File::create(tempfile.path()).await.unwrap();
+        let queue = WriteQueue::new(file, 0);
+
+        let mut expected = Vec::new();
+        let mut pending = Vec::new();
+        for i in 0..1000 {
+            let record = format!("record {} {}\n", i, "x".repeat(i % 37)).into_bytes();
+            expected.extend_from_slice(&record);
+            pending.push(queue.push(Buffer::from(record)));
+        }
+        assert_eq!(queue.offset(), expected.len() as u64);
+
+        queue.flush().await.unwrap();
+        for write in pending {
+            write.await.unwrap();
+        }
+        assert_eq!(std::fs::read(tempfile.path()).unwrap(), expected);
+
+        let tail = queue.push(Buffer::from(b"tail".to_vec()));
+        queue.sync().await.unwrap();
+        tail.await.unwrap();
+        expected.extend_from_slice(b"tail");
+
+        let file = queue.into_inner().await.unwrap();
+        file.close().await.unwrap();
+        assert_eq!(std::fs::read(tempfile.path()).unwrap(), expected);
+    });
+}
+
+#[test]
+fn write_queue_flush_driven_by_another_task() {
+    use std::rc::Rc;
+    use tokio_uring::fs::WriteQueue;
+
+    tokio_uring::start(async {
+        let tempfile = tempfile();
+        let file = File::create(tempfile.path()).await.unwrap();
+        let queue = Rc::new(WriteQueue::new(file, 0));
+
+        let mut expected = Vec::new();
+        let mut pending = Vec::new();
+        for i in 0..200 {
+            let record = format!("record {}\n", i).into_bytes();
+            expected.extend_from_slice(&record);
+            pending.push(queue.push(Buffer::from(record)));
+        }
+
+        // The flush is polled first, then this task takes over driving the writes.
+        let flush = tokio_uring::spawn({
+            let queue = queue.clone();
+            async move { queue.flush().await }
+        });
+        tokio::task::yield_now().await;
+        for write in pending {
+            write.await.unwrap();
+        }
+
+        tokio::time::timeout(std::time::Duration::from_secs(5), flush)
+            .await
+            .expect("flush was never woken")
+            .unwrap()
+            .unwrap();
+        assert_eq!(std::fs::read(tempfile.path()).unwrap(), expected);
+    });
+}
+
+#[test]
+fn write_queue_error_is_sticky() {
+    use tokio_uring::fs::WriteQueue;
+
+    tokio_uring::start(async {
+        let tempfile = tempfile();
+        // Opened read-only, so every write fails with EBADF.
+        let file = File::open(tempfile.path()).await.unwrap();
+        let queue = WriteQueue::new(file, 0);
+
+        let first = queue.push(Buffer::from(b"a".to_vec()));
+        let second = queue.push(Buffer::from(b"b".to_vec()));
+        let err = first.await.unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(libc::EBADF));
+        assert_eq!(second.await.unwrap_err().raw_os_error(), Some(libc::EBADF));
+        assert!(queue.flush().await.is_err());
+        assert!(queue.push(Buffer::from(b"c".to_vec())).await.is_err());
+    });
+}
+
+#[test]
+fn send_to_socket() {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::Hasher;
+    use tokio_uring::net::TcpStream;
+
+    fn digest(bytes: &[u8]) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        hasher.write(bytes);
+        hasher.finish()
+    }
+
+    const LEN: usize = 8 << 20;
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    let data: Vec<u8> = (0..LEN)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        })
+        .collect();
+    let mut tempfile = tempfile();
+    tempfile.write_all(&data).unwrap();
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let receiver = std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut received = Vec::new();
+        stream.read_to_end(&mut received).unwrap();
+        (received.len(), digest(&received))
+    });
+
+    tokio_uring::start(async {
+        let file = File::open(tempfile.path()).await.unwrap();
+        let stream = TcpStream::connect(addr).await.unwrap();
+
+        let n = file.send_to_socket(&stream, 0, LEN as u64).await.unwrap();
+        assert_eq!(n, LEN as u64);
+        // A range running past the end of the file stops there.
+        let n = file
+            .send_to_socket(&stream, LEN as u64 - 100, 1000)
+            .await
+            .unwrap();
+        assert_eq!(n, 100);
+    });
+
+    let mut expected = data.clone();
+    expected.extend_from_slice(&data[LEN - 100..]);
+    assert_eq!(
+        receiver.join().unwrap(),
+        (expected.len(), digest(&expected))
+    );
+}
+
 fn tempfile() -> NamedTempFile {
     NamedTempFile::new().unwrap()
 }
@@ -339,13 +1135,13 @@ async fn poll_once(future: impl std::future::Future) {
 }
 
 fn assert_invalid_fd(fd: RawFd) {
-    use std::fs::File;
-
-    let mut f = unsafe { File::from_raw_fd(fd) };
-    let mut buf = vec![];
-
-    match f.read_to_end(&mut buf) {
-        Err(ref e) if e.raw_os_error() == Some(libc::EBADF) => {}
-        res => panic!("assert_invalid_fd finds for fd {:?}, res = {:?}", fd, res),
+    // Probe with fcntl rather than wrapping the fd in a std `File`: dropping an
+    // owned fd that is already closed aborts the process under std's IO safety checks.
+    let res = unsafe { libc::fcntl(fd, libc::F_GETFD) };
+    let err = std::io::Error::last_os_error();
+
+    match (res, err.raw_os_error()) {
+        (-1, Some(libc::EBADF)) => {}
+        _ => panic!("assert_invalid_fd finds for fd {:?}, res = {:?}", fd, res),
     }
 }
@@ -0,0 +1,155 @@
+use std::os::unix::fs::symlink;
+
+use tempfile::tempdir;
+use tokio_uring::fs::{self, File};
+
+#[test]
+fn metadata_regular_file() {
+    tokio_uring::start(async {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("file");
+        std::fs::write(&path, b"hello world").unwrap();
+
+        let metadata = fs::metadata(&path).await.unwrap();
+        assert!(metadata.is_file());
+        assert!(!metadata.is_symlink());
+        assert_eq!(metadata.len(), 11);
+
+        let file = File::open(&path).await.unwrap();
+        let from_file = file.metadata().await.unwrap();
+        assert_eq!(from_file.ino(), metadata.ino());
+        file.close().await.unwrap();
+
+        let std_meta = std::fs::metadata(&path).unwrap();
+        assert_eq!(metadata.modified().unwrap(), std_meta.modified().unwrap());
+        assert!(fs::metadata(dir.path()).await.unwrap().is_dir());
+    });
+}
+
+#[test]
+fn metadata_symlink() {
+    tokio_uring::start(async {
+        let dir = tempdir().unwrap();
+        let target = dir.path().join("target");
+        let link = dir.path().join("link");
+        std::fs::write(&target, b"abc").unwrap();
+        symlink(&target, &link).unwrap();
+
+        let followed = fs::metadata(&link).await.unwrap();
+        assert!(followed.is_file());
+        assert_eq!(followed.len(), 3);
+
+        let not_followed = fs::symlink_metadata(&link).await.unwrap();
+        assert!(not_followed.is_symlink());
+        assert!(not_followed.file_type().is_symlink());
+    });
+}
+
+#[test]
+fn metadata_dangling_symlink() {
+    tokio_uring::start(async {
+        let dir = tempdir().unwrap();
+        let link = dir.path().join("dangling");
+        symlink(dir.path().join("missing"), &link).unwrap();
+
+        let err = fs::metadata(&link).await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+
+        let metadata = fs::symlink_metadata(&link).await.unwrap();
+        assert!(metadata.is_symlink());
+    });
+}
+
+#[test]
+fn read_link_round_trip() {
+    tokio_uring::start(async {
+        let dir = tempdir().unwrap();
+
+        let short = dir.path().join("short");
+        symlink("releases/v1", &short).unwrap();
+        assert_eq!(
+            fs::read_link(&short).await.unwrap(),
+            std::path::Path::new("releases/v1")
+        );
+
+        // Targets at and past the initial buffer size exercise the grow-and-retry path.
+        for len in [255, 256, 257, 1000] {
+            let target = "x".repeat(len);
+            let link = dir.path().join(format!("long-{}", len));
+            symlink(&target, &link).unwrap();
+            assert_eq!(
+                fs::read_link(&link).await.unwrap(),
+                std::path::Path::new(&target)
+            );
+        }
+    });
+}
+
+#[test]
+fn read_link_not_a_symlink() {
+    tokio_uring::start(async {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("file");
+        std::fs::write(&path, b"").unwrap();
+
+        let err = fs::read_link(&path).await.unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(libc::EINVAL));
+    });
+}
+
+#[test]
+fn try_exists() {
+    tokio_uring::start(async {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("file");
+        let dangling = dir.path().join("dangling");
+        std::fs::write(&file, b"").unwrap();
+        symlink(dir.path().join("missing"), &dangling).unwrap();
+
+        assert!(fs::try_exists(&file).await.unwrap());
+        assert!(!fs::try_exists(dir.path().join("missing")).await.unwrap());
+
+        assert!(!fs::try_exists(&dangling).await.unwrap());
+        assert!(fs::symlink_try_exists(&dangling).await.unwrap());
+    });
+}
+
+#[test]
+fn try_exists_permission_denied() {
+    use std::os::unix::fs::PermissionsExt;
+
+    // Root bypasses directory permissions, so the error cannot be provoked.
+    if unsafe { libc::geteuid() } == 0 {
+        return;
+    }
+
+    tokio_uring::start(async {
+        let dir = tempdir().unwrap();
+        let locked = dir.path().join("locked");
+        std::fs::create_dir(&locked).unwrap();
+        std::fs::write(locked.join("file"), b"").unwrap();
+        std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o000)).unwrap();
+
+        let err = fs::try_exists(locked.join("file")).await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::PermissionDenied);
+
+        std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o700)).unwrap();
+    });
+}
+
+#[test]
+fn dio_alignment() {
+    tokio_uring::start(async {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("file");
+        std::fs::write(&path, b"").unwrap();
+
+        let file = File::open(&path).await.unwrap();
+        // Older kernels and some filesystems do not report the alignment.
+        if let Some(align) = file.dio_alignment().await.unwrap() {
+            assert!(align.mem_align.is_power_of_two());
+            assert!(align.offset_align.is_power_of_two());
+        }
+        file.close().await.unwrap();
+    });
+}
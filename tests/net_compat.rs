@@ -0,0 +1,108 @@
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio_uring::net::{TcpListener, TcpStream, UnixStream};
+
+// Returns both ends of a fresh localhost connection.
+async fn connection() -> (TcpStream, TcpStream) {
+    let listener = TcpListener::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = listener.local_addr().unwrap();
+    let client = tokio_uring::spawn(async move { TcpStream::connect(addr).await.unwrap() });
+    let (server, _) = listener.accept().await.unwrap();
+    (client.await.unwrap(), server)
+}
+
+fn payload(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i % 251) as u8).collect()
+}
+
+#[test]
+fn copy_bidirectional() {
+    tokio_uring::start(async {
+        // client <-> proxy_in, proxy_out <-> server
+        let (client, proxy_in) = connection().await;
+        let (proxy_out, server) = connection().await;
+
+        let proxy = tokio_uring::spawn(async move {
+            let mut a = proxy_in.into_compat();
+            let mut b = proxy_out.into_compat();
+            tokio::io::copy_bidirectional(&mut a, &mut b).await.unwrap()
+        });
+
+        let request = payload(3 << 20);
+        let response = payload(1 << 20);
+
+        let expected = request.clone();
+        let reply = response.clone();
+        let server = tokio_uring::spawn(async move {
+            let mut server = server.into_compat();
+            let mut received = Vec::new();
+            server.read_to_end(&mut received).await.unwrap();
+            assert!(received == expected);
+            server.write_all(&reply).await.unwrap();
+            server.shutdown().await.unwrap();
+        });
+
+        let mut client = client.into_compat();
+        client.write_all(&request).await.unwrap();
+        client.shutdown().await.unwrap();
+        let mut received = Vec::new();
+        client.read_to_end(&mut received).await.unwrap();
+        assert!(received == response);
+
+        server.await.unwrap();
+        let (up, down) = proxy.await.unwrap();
+        assert_eq!(up, request.len() as u64);
+        assert_eq!(down, response.len() as u64);
+    });
+}
+
+#[test]
+fn read_cancelled_loses_nothing() {
+    tokio_uring::start(async {
+        let (a, b) = UnixStream::pair().unwrap();
+        let mut a = a.into_compat();
+
+        // The receive stays in flight after the read future is dropped.
+        let mut buf = [0; 16];
+        let res = tokio::time::timeout(Duration::from_millis(50), a.read(&mut buf)).await;
+        assert!(res.is_err());
+
+        b.write_all(b"hello".to_vec().into()).await.unwrap();
+        let n = a.read(&mut buf).await.unwrap();
+        assert_eq!(&buf[..n], b"hello");
+    });
+}
+
+#[test]
+fn drop_sends_staged_bytes() {
+    tokio_uring::start(async {
+        let (client, server) = connection().await;
+        let data = payload(1 << 20);
+
+        let mut client = client.into_compat();
+        // Larger than the staging buffer, and left unflushed.
+        client.write_all(&data).await.unwrap();
+        drop(client);
+
+        let mut server = server.into_compat();
+        let mut received = Vec::new();
+        server.read_to_end(&mut received).await.unwrap();
+        assert!(received == data);
+    });
+}
+
+#[test]
+fn into_inner_flushes() {
+    tokio_uring::start(async {
+        let (client, server) = connection().await;
+
+        let mut client = client.into_compat();
+        client.write_all(b"staged").await.unwrap();
+        let client = client.into_inner().await.unwrap();
+
+        let (n, buf) = server.read(vec![0; 16].into()).await.unwrap();
+        assert_eq!(&buf[0][..n], b"staged");
+        drop(client);
+    });
+}
@@ -0,0 +1,85 @@
+use std::io::ErrorKind;
+use std::net::SocketAddr;
+
+use tokio_uring::net::RawSocket;
+
+// The internet checksum of an ICMP message.
+fn checksum(data: &[u8]) -> u16 {
+    let mut sum: u32 = data
+        .chunks(2)
+        .map(|c| u32::from(u16::from_be_bytes([c[0], *c.get(1).unwrap_or(&0)])))
+        .sum();
+    while sum > 0xffff {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+fn echo_request(id: u16, seq: u16) -> Vec<u8> {
+    let mut packet = vec![8, 0, 0, 0];
+    packet.extend_from_slice(&id.to_be_bytes());
+    packet.extend_from_slice(&seq.to_be_bytes());
+    packet.extend_from_slice(b"tokio-uring ping");
+    let sum = checksum(&packet);
+    packet[2..4].copy_from_slice(&sum.to_be_bytes());
+    packet
+}
+
+#[test]
+fn raw_icmp_echo() {
+    tokio_uring::start(async {
+        let socket = match RawSocket::new_v4(libc::IPPROTO_ICMP) {
+            Ok(socket) => socket,
+            Err(e) if e.kind() == ErrorKind::PermissionDenied => return,
+            Err(e) => panic!("{}", e),
+        };
+        let id = std::process::id() as u16;
+        let dest: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let (n, _) = socket
+            .send_to(echo_request(id, 1).into(), dest)
+            .await
+            .unwrap();
+        assert_eq!(n, 24);
+
+        // The socket sees every ICMP packet, including the request going out on loopback.
+        loop {
+            let ((n, from), buf) = socket.recv_from(vec![0; 256].into()).await.unwrap();
+            let packet = &buf[0][..n];
+            let icmp = &packet[usize::from(packet[0] & 0x0f) * 4..];
+            if icmp[0] == 0 && icmp[4..6] == id.to_be_bytes() {
+                assert_eq!(from.ip(), dest.ip());
+                assert_eq!(&icmp[6..8], &1u16.to_be_bytes());
+                assert_eq!(&icmp[8..], b"tokio-uring ping");
+                break;
+            }
+        }
+    });
+}
+
+#[test]
+fn ping_socket_echo() {
+    tokio_uring::start(async {
+        let socket = match RawSocket::ping_v4() {
+            Ok(socket) => socket,
+            Err(e) => {
+                // Unless the sysctl allows it, the refusal says what is missing.
+                assert_eq!(e.kind(), ErrorKind::PermissionDenied);
+                assert!(e.to_string().contains("ping_group_range"));
+                return;
+            }
+        };
+        let dest: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        socket
+            .send_to(echo_request(0, 7).into(), dest)
+            .await
+            .unwrap();
+
+        // The kernel replaced the identifier with the one it assigned the socket.
+        let id = socket.local_addr().unwrap().port();
+        let ((n, _), buf) = socket.recv_from(vec![0; 256].into()).await.unwrap();
+        let icmp = &buf[0][..n];
+        assert_eq!(icmp[0], 0);
+        assert_eq!(icmp[4..6], id.to_be_bytes());
+        assert_eq!(&icmp[8..], b"tokio-uring ping");
+    });
+}
@@ -0,0 +1,74 @@
+// Kept in a binary of its own, as it restores the default disposition of SIGPIPE, which
+// kills the process, where the test harness otherwise ignores the signal.
+
+use std::io::ErrorKind;
+
+use tokio_uring::net::{TcpListener, UnixStream};
+use tokio_uring::Submit;
+
+fn default_sigpipe() {
+    // Safety: resetting a signal disposition has no memory safety implications.
+    let prev = unsafe { libc::signal(libc::SIGPIPE, libc::SIG_DFL) };
+    assert_ne!(prev, libc::SIG_ERR);
+}
+
+#[test]
+fn write_to_closed_peer_fails_with_broken_pipe() {
+    default_sigpipe();
+
+    tokio_uring::start(async {
+        let listener = TcpListener::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let client = std::net::TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (stream, _) = listener.accept().await.unwrap();
+        drop(client);
+
+        // The first write is answered with a reset, which makes the second fail.
+        let mut res = Ok(());
+        for _ in 0..2 {
+            res = stream
+                .write_all(vec![0; 1024].into())
+                .await
+                .map(|_| ())
+                .map_err(|e| e.0);
+        }
+        let err = res.unwrap_err();
+        assert!(
+            matches!(
+                err.kind(),
+                ErrorKind::BrokenPipe | ErrorKind::ConnectionReset
+            ),
+            "{:?}",
+            err
+        );
+
+        let err = stream
+            .send(vec![0; 16].into())
+            .submit()
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(err.0.kind(), ErrorKind::BrokenPipe);
+        let err = stream
+            .write(vec![0; 16].into())
+            .submit()
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(err.0.kind(), ErrorKind::BrokenPipe);
+    });
+}
+
+#[test]
+fn unix_write_to_closed_peer_fails_with_broken_pipe() {
+    default_sigpipe();
+
+    tokio_uring::start(async {
+        let (a, b) = UnixStream::pair().unwrap();
+        drop(b);
+
+        let err = a.write(b"x".to_vec().into()).submit().await.err().unwrap();
+        assert_eq!(err.0.kind(), ErrorKind::BrokenPipe);
+        let err = a.write_all(vec![0; 3].into()).await.err().unwrap();
+        assert_eq!(err.0.kind(), ErrorKind::BrokenPipe);
+    });
+}
@@ -0,0 +1,1479 @@
+use tokio_uring::net::TcpListener;
+use tokio_uring::Submit;
+
+#[test]
+fn accept_returns_peer_addr() {
+    tokio_uring::start(async {
+        let listener = TcpListener::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let client = std::net::TcpStream::connect(addr).unwrap();
+        let (_stream, peer) = listener.accept().await.unwrap();
+        assert_eq!(peer, client.local_addr().unwrap());
+    });
+}
+
+#[test]
+fn accept_multi_many() {
+    use futures_util::StreamExt;
+
+    tokio_uring::start(async {
+        let listener = TcpListener::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let clients: Vec<_> = (0..4)
+            .map(|_| {
+                std::thread::spawn(move || {
+                    (0..25)
+                        .map(|_| {
+                            let client = std::net::TcpStream::connect(addr).unwrap();
+                            (client.local_addr().unwrap(), client)
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        let mut incoming = listener.accept_multi();
+        let mut peers = Vec::new();
+        while peers.len() < 100 {
+            let (_stream, peer) = incoming.next().await.unwrap().unwrap();
+            peers.push(peer);
+        }
+
+        let mut expected: Vec<_> = clients
+            .into_iter()
+            .flat_map(|t| t.join().unwrap())
+            .map(|(addr, _)| addr)
+            .collect();
+        expected.sort();
+        peers.sort();
+        assert_eq!(peers, expected);
+    });
+}
+
+#[test]
+fn accept_multi_drop_closes_pending() {
+    use futures_util::StreamExt;
+    use std::io::Read;
+
+    tokio_uring::start(async {
+        let listener = TcpListener::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let mut incoming = listener.accept_multi();
+        let first = std::net::TcpStream::connect(addr).unwrap();
+        let (_stream, _) = incoming.next().await.unwrap().unwrap();
+
+        // Accepted by the kernel while nobody polls the stream.
+        let mut second = std::net::TcpStream::connect(addr).unwrap();
+        tokio_uring::fs::File::open("/dev/null")
+            .await
+            .unwrap()
+            .close()
+            .await
+            .unwrap();
+        drop(incoming);
+
+        let mut buf = [0; 1];
+        second
+            .set_read_timeout(Some(std::time::Duration::from_secs(5)))
+            .unwrap();
+        assert_eq!(second.read(&mut buf).unwrap(), 0);
+        drop(first);
+
+        // The listener still works with a plain accept.
+        let third = std::net::TcpStream::connect(addr).unwrap();
+        let (_stream, peer) = listener.accept().await.unwrap();
+        assert_eq!(peer, third.local_addr().unwrap());
+    });
+}
+
+// Connections to `port` still waiting for a SYN-ACK, as listed in /proc/net/tcp.
+fn syn_sent_to(port: u16) -> usize {
+    std::fs::read_to_string("/proc/net/tcp")
+        .unwrap()
+        .lines()
+        .skip(1)
+        .filter(|line| {
+            let fields: Vec<_> = line.split_whitespace().collect();
+            fields[2].ends_with(&format!(":{:04X}", port)) && fields[3] == "02"
+        })
+        .count()
+}
+
+// Once its single accept queue slot is taken, a listener with a backlog of 0 drops further
+// SYNs, which makes for a local blackhole. Connects to the returned address hang for as long
+// as the returned sockets are kept.
+fn blackhole() -> (std::net::SocketAddr, (socket2::Socket, std::net::TcpStream)) {
+    let listener =
+        socket2::Socket::new(socket2::Domain::IPV4, socket2::Type::STREAM, None).unwrap();
+    listener
+        .bind(
+            &"127.0.0.1:0"
+                .parse::<std::net::SocketAddr>()
+                .unwrap()
+                .into(),
+        )
+        .unwrap();
+    listener.listen(0).unwrap();
+    let addr = listener.local_addr().unwrap().as_socket().unwrap();
+    let queued = std::net::TcpStream::connect(addr).unwrap();
+    (addr, (listener, queued))
+}
+
+#[test]
+fn connect_timeout_expires() {
+    use std::time::{Duration, Instant};
+    use tokio_uring::net::TcpStream;
+
+    let (addr, _blackhole) = blackhole();
+
+    tokio_uring::start(async {
+        let start = Instant::now();
+        let err = TcpStream::connect_timeout(addr, Duration::from_millis(200))
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(200), "{:?}", elapsed);
+        assert!(elapsed < Duration::from_secs(1), "{:?}", elapsed);
+
+        // The socket was closed, not left retrying.
+        assert_eq!(syn_sent_to(addr.port()), 0);
+
+        // A reachable peer connects well within the timeout.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let stream =
+            TcpStream::connect_timeout(listener.local_addr().unwrap(), Duration::from_secs(5))
+                .await
+                .unwrap();
+        drop(stream);
+    });
+}
+
+#[test]
+fn write_zc_large() {
+    use std::io::Read;
+    use tokio_uring::net::TcpStream;
+    use tokio_uring::Buffer;
+
+    const LEN: usize = 4 << 20;
+    let data: Vec<u8> = (0..LEN).map(|i| (i % 251) as u8).collect();
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let reader = std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut received = Vec::new();
+        stream.read_to_end(&mut received).unwrap();
+        received
+    });
+
+    tokio_uring::start(async {
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut sent = 0;
+        while sent < LEN {
+            let buf = Buffer::from(data[sent..].to_vec());
+            let (n, buf) = stream.write_zc(buf).await.unwrap();
+            assert_eq!(&buf[0][..], &data[sent..]);
+            sent += n;
+        }
+
+        // A multi-segment buffer is refused and handed back.
+        let buf = Buffer::from(vec![b"a".to_vec(), b"b".to_vec()]);
+        let err = stream.write_zc(buf).await.unwrap_err();
+        assert_eq!(err.0.kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(err.1.segment_count(), 2);
+    });
+
+    assert_eq!(reader.join().unwrap(), data);
+}
+
+#[test]
+fn writev_zc_segments() {
+    use std::io::Read;
+    use tokio_uring::net::TcpStream;
+    use tokio_uring::Buffer;
+
+    let header = b"HTTP/1.1 200 OK\r\n\r\n".to_vec();
+    let body: Vec<u8> = (0..1 << 20).map(|i| (i % 251) as u8).collect();
+    let trailer = b"\r\n".to_vec();
+    let expected = [&header[..], &body[..], &trailer[..]].concat();
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let reader = std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut received = Vec::new();
+        stream.read_to_end(&mut received).unwrap();
+        received
+    });
+
+    tokio_uring::start(async {
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut buf = Buffer::from(vec![header, body, trailer]);
+        // Resubmit whatever a partial send left over.
+        loop {
+            let (n, sent) = stream.writev_zc(buf).await.unwrap();
+            let (segment, offset) = match sent.position(n) {
+                Some(position) => position,
+                None => break,
+            };
+            let rest: Vec<Vec<u8>> = (segment..sent.segment_count())
+                .map(|i| sent[i][if i == segment { offset } else { 0 }..].to_vec())
+                .collect();
+            buf = Buffer::from(rest);
+        }
+    });
+
+    assert_eq!(reader.join().unwrap(), expected);
+}
+
+#[test]
+fn stream_socket_options() {
+    use std::time::Duration;
+    use tokio_uring::net::{KeepaliveConfig, TcpStream};
+
+    tokio_uring::start(async {
+        let listener = TcpListener::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = listener.local_addr().unwrap();
+        let client = TcpStream::connect(addr).await.unwrap();
+        let (server, _) = listener.accept().await.unwrap();
+
+        client.set_nodelay(true).unwrap();
+        assert!(client.nodelay().unwrap());
+        client.set_nodelay(false).unwrap();
+        assert!(!client.nodelay().unwrap());
+
+        client.set_ttl(17).unwrap();
+        assert_eq!(client.ttl().unwrap(), 17);
+
+        assert_eq!(server.keepalive().unwrap(), None);
+        let config = KeepaliveConfig {
+            time: Duration::from_secs(30),
+            interval: Duration::from_secs(5),
+            retries: 3,
+        };
+        server.set_keepalive(Some(config)).unwrap();
+        assert_eq!(server.keepalive().unwrap(), Some(config));
+
+        // Options can still be changed after data has flowed.
+        client
+            .write(b"ping".to_vec().into())
+            .submit()
+            .await
+            .unwrap();
+        server.read(vec![0; 4].into()).await.unwrap();
+        server.set_keepalive(None).unwrap();
+        assert_eq!(server.keepalive().unwrap(), None);
+        client.set_nodelay(true).unwrap();
+        assert!(client.nodelay().unwrap());
+    });
+}
+
+#[test]
+fn builder_reuseport() {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    tokio_uring::start(async {
+        let first = TcpListener::builder()
+            .backlog(64)
+            .bind("127.0.0.1:0".parse().unwrap())
+            .unwrap();
+        let addr = first.local_addr().unwrap();
+        assert_ne!(addr.port(), 0);
+        let second = TcpListener::builder().backlog(64).bind(addr).unwrap();
+        assert_eq!(second.local_addr().unwrap(), addr);
+
+        // Without SO_REUSEPORT on both, the port is taken.
+        let err = TcpListener::builder()
+            .reuseport(false)
+            .bind(addr)
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::AddrInUse);
+
+        // The kernel hashes connections across the listeners.
+        const CLIENTS: usize = 64;
+        let counts: Vec<Rc<Cell<usize>>> = vec![Rc::default(), Rc::default()];
+        let done = Rc::new(tokio::sync::Notify::new());
+        for (listener, count) in vec![first, second].into_iter().zip(&counts) {
+            let (count, counts, done) = (count.clone(), counts.clone(), done.clone());
+            tokio_uring::spawn(async move {
+                loop {
+                    let (_stream, _) = listener.accept().await.unwrap();
+                    count.set(count.get() + 1);
+                    if counts.iter().map(|c| c.get()).sum::<usize>() == CLIENTS {
+                        done.notify_one();
+                    }
+                }
+            });
+        }
+
+        let _clients: Vec<_> = (0..CLIENTS)
+            .map(|_| std::net::TcpStream::connect(addr).unwrap())
+            .collect();
+        done.notified().await;
+        assert!(counts[0].get() > 0, "first listener accepted nothing");
+        assert!(counts[1].get() > 0, "second listener accepted nothing");
+    });
+}
+
+#[test]
+fn socket_bind_then_connect() {
+    use std::net::{IpAddr, Ipv4Addr};
+    use tokio_uring::net::TcpSocket;
+
+    tokio_uring::start(async {
+        let listener = TcpSocket::new_v4().unwrap();
+        listener.bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let listener = listener.listen(16).unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let socket = TcpSocket::new_v4().unwrap();
+        socket.set_reuseaddr(true).unwrap();
+        socket.bind("127.0.0.2:0".parse().unwrap()).unwrap();
+        let source = socket.local_addr().unwrap();
+        assert_eq!(source.ip(), IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2)));
+        assert_ne!(source.port(), 0);
+
+        let _stream = socket.connect(addr).await.unwrap();
+        let (_peer_stream, peer) = listener.accept().await.unwrap();
+        assert_eq!(peer, source);
+    });
+}
+
+#[test]
+fn stream_addrs() {
+    use tokio_uring::net::TcpStream;
+
+    async fn check(bind: &str) {
+        let listener = TcpListener::bind(bind.parse().unwrap()).unwrap();
+        let addr = listener.local_addr().unwrap();
+        assert_ne!(addr.port(), 0);
+
+        let client = TcpStream::connect(addr).await.unwrap();
+        let (server, peer) = listener.accept().await.unwrap();
+
+        assert_eq!(client.peer_addr().unwrap(), addr);
+        assert_eq!(server.local_addr().unwrap(), addr);
+        assert_eq!(client.local_addr().unwrap(), peer);
+        assert_eq!(server.peer_addr().unwrap(), peer);
+    }
+
+    tokio_uring::start(async {
+        check("127.0.0.1:0").await;
+        if std::net::TcpListener::bind("[::1]:0").is_ok() {
+            check("[::1]:0").await;
+        }
+    });
+}
+
+#[test]
+fn recv_multi_bursts() {
+    use futures_util::StreamExt;
+    use std::io::Write;
+    use tokio_uring::buf::bufring::BufRing;
+
+    tokio_uring::start(async {
+        let group = BufRing::register(8, 64, 1).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let expected: Vec<u8> = (0..5000u32).map(|i| (i % 251) as u8).collect();
+        let sent = expected.clone();
+        let writer = std::thread::spawn(move || {
+            let mut client = std::net::TcpStream::connect(addr).unwrap();
+            for burst in sent.chunks(1000) {
+                client.write_all(burst).unwrap();
+                std::thread::sleep(std::time::Duration::from_millis(10));
+            }
+        });
+
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut received = Vec::new();
+        let mut incoming = stream.recv_multi(&group);
+        while let Some(res) = incoming.next().await {
+            let (buf, n) = res.unwrap();
+            assert!(n > 0 && n <= 64);
+            assert_eq!(buf[0].len(), n);
+            received.extend_from_slice(&buf[0]);
+        }
+        writer.join().unwrap();
+        assert_eq!(received, expected);
+    });
+}
+
+#[test]
+fn recv_multi_waits_for_buffers() {
+    use futures_util::StreamExt;
+    use std::io::Write;
+    use tokio_uring::buf::bufring::BufRing;
+
+    tokio_uring::start(async {
+        let group = BufRing::register(2, 8, 2).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let expected: Vec<u8> = (0..64).collect();
+        let mut client = std::net::TcpStream::connect(addr).unwrap();
+        client.write_all(&expected).unwrap();
+        drop(client);
+
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut incoming = stream.recv_multi(&group);
+
+        // Hold on to every buffer of the group, so the kernel runs out.
+        let (first, _) = incoming.next().await.unwrap().unwrap();
+        let (second, _) = incoming.next().await.unwrap().unwrap();
+        let mut received = Vec::new();
+        received.extend_from_slice(&first[0]);
+        received.extend_from_slice(&second[0]);
+        tokio_uring::spawn(async move {
+            tokio::task::yield_now().await;
+            drop((first, second));
+        });
+
+        while let Some(res) = incoming.next().await {
+            let (buf, _) = res.unwrap();
+            received.extend_from_slice(&buf[0]);
+        }
+        assert_eq!(received, expected);
+    });
+}
+
+#[test]
+fn read_select_recycles_buffers() {
+    use std::io::Write;
+    use tokio_uring::buf::bufring::BufRing;
+
+    tokio_uring::start(async {
+        let group = BufRing::register(2, 8, 3).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let expected: Vec<u8> = (0..64).collect();
+        let mut client = std::net::TcpStream::connect(addr).unwrap();
+        client.write_all(&expected).unwrap();
+        drop(client);
+        let (stream, _) = listener.accept().await.unwrap();
+
+        // Hold on to every buffer of the group, so the next read runs out and waits.
+        let first = stream.read_select(&group).await.unwrap().unwrap();
+        let second = stream.read_select(&group).await.unwrap().unwrap();
+        let mut received = Vec::new();
+        received.extend_from_slice(&first[0]);
+        received.extend_from_slice(&second[0]);
+        tokio_uring::spawn(async move {
+            tokio::task::yield_now().await;
+            drop((first, second));
+        });
+
+        // Many more reads than buffers, each buffer going back as it is dropped.
+        while let Some(buf) = stream.read_select(&group).await.unwrap() {
+            received.extend_from_slice(&buf[0]);
+        }
+        assert_eq!(received, expected);
+
+        // A read dropped while waiting for data is cancelled without keeping a buffer.
+        let mut client = std::net::TcpStream::connect(addr).unwrap();
+        let (stream, _) = listener.accept().await.unwrap();
+        let read = stream.read_select(&group);
+        assert!(
+            tokio::time::timeout(std::time::Duration::from_millis(10), read)
+                .await
+                .is_err()
+        );
+
+        // Both buffers are back: two reads can hold them at once.
+        client.write_all(&expected[..16]).unwrap();
+        let first = stream.read_select(&group).await.unwrap().unwrap();
+        let second = stream.read_select(&group).await.unwrap().unwrap();
+        assert_eq!([&first[0][..], &second[0][..]].concat(), &expected[..16]);
+    });
+}
+
+#[test]
+fn recv_peek_then_read() {
+    use std::io::Write;
+
+    tokio_uring::start(async {
+        let listener = TcpListener::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let mut client = std::net::TcpStream::connect(addr).unwrap();
+        client.write_all(b"GET / HTTP/1.1").unwrap();
+        let (stream, _) = listener.accept().await.unwrap();
+
+        let buf = Vec::<u8>::with_capacity(4).into();
+        let (n, buf) = stream.recv(buf).waitall().peek().submit().await.unwrap();
+        assert_eq!(&buf[0][..n], b"GET ");
+
+        let (n, buf) = stream.recv(buf).waitall().submit().await.unwrap();
+        assert_eq!(&buf[0][..n], b"GET ");
+        let (n, buf) = stream.read(buf).await.unwrap();
+        assert_eq!(&buf[0][..n], b"/ HT");
+    });
+}
+
+#[test]
+fn recv_waitall_assembles_sends() {
+    use std::io::Write;
+
+    tokio_uring::start(async {
+        let listener = TcpListener::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let writer = std::thread::spawn(move || {
+            let mut client = std::net::TcpStream::connect(addr).unwrap();
+            client.set_nodelay(true).unwrap();
+            for part in [&b"three"[..], b"-part-", b"frame"].iter() {
+                client.write_all(part).unwrap();
+                std::thread::sleep(std::time::Duration::from_millis(20));
+            }
+            client
+        });
+        let (stream, _) = listener.accept().await.unwrap();
+
+        let buf = Vec::<u8>::with_capacity(16).into();
+        let (n, buf) = stream.recv(buf).waitall().submit().await.unwrap();
+        assert_eq!(n, 16);
+        assert_eq!(&buf[0][..], b"three-part-frame");
+        writer.join().unwrap();
+    });
+}
+
+#[test]
+fn read_exact_assembles_chunks() {
+    use std::io::Write;
+    use tokio_uring::Buffer;
+
+    tokio_uring::start(async {
+        let listener = TcpListener::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let expected: Vec<u8> = (0..1 << 20).map(|i: u32| (i % 253) as u8).collect();
+        let sent = expected.clone();
+        let writer = std::thread::spawn(move || {
+            let mut client = std::net::TcpStream::connect(addr).unwrap();
+            for (i, chunk) in sent.chunks(4096).enumerate() {
+                client.write_all(chunk).unwrap();
+                if i % 32 == 0 {
+                    std::thread::sleep(std::time::Duration::from_millis(2));
+                }
+            }
+        });
+        let (stream, _) = listener.accept().await.unwrap();
+
+        // Segments of uneven size, so reads end partway through them.
+        let buf = Buffer::from(vec![
+            Vec::with_capacity(300_001),
+            Vec::with_capacity((1 << 20) - 300_001),
+        ]);
+        let ((), buf) = stream.read_exact(buf).await.unwrap();
+        writer.join().unwrap();
+        assert_eq!(buf[0].len() + buf[1].len(), 1 << 20);
+        assert_eq!([&buf[0], &buf[1]].concat(), expected);
+    });
+}
+
+#[test]
+fn read_exact_early_eof() {
+    use std::io::Write;
+
+    tokio_uring::start(async {
+        let listener = TcpListener::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let mut client = std::net::TcpStream::connect(addr).unwrap();
+        client.write_all(b"only ten!!").unwrap();
+        drop(client);
+        let (stream, _) = listener.accept().await.unwrap();
+
+        let err = stream
+            .read_exact(Vec::<u8>::with_capacity(16).into())
+            .await
+            .unwrap_err();
+        assert_eq!(err.0.kind(), std::io::ErrorKind::UnexpectedEof);
+        assert_eq!(&err.1[0][..], b"only ten!!");
+    });
+}
+
+#[test]
+fn write_all_multi_segment() {
+    use std::io::Read;
+    use tokio_uring::Buffer;
+
+    tokio_uring::start(async {
+        let listener = TcpListener::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let reader = std::thread::spawn(move || {
+            let mut client = std::net::TcpStream::connect(addr).unwrap();
+            let mut received = Vec::new();
+            client.read_to_end(&mut received).unwrap();
+            received
+        });
+        let (stream, _) = listener.accept().await.unwrap();
+        // A small send buffer makes the kernel accept the data in several parts.
+        socket2::SockRef::from(&stream)
+            .set_send_buffer_size(16 * 1024)
+            .unwrap();
+
+        let segments: Vec<Vec<u8>> = (0..4u8)
+            .map(|i| vec![i; (1 << 20) + 17 * i as usize])
+            .collect();
+        let expected = segments.concat();
+        stream.write_all(Buffer::from(segments)).await.unwrap();
+        drop(stream);
+
+        assert_eq!(reader.join().unwrap(), expected);
+    });
+}
+
+#[test]
+fn std_conversions() {
+    use futures_util::FutureExt;
+    use std::io::{Read, Write};
+    use tokio_uring::net::TcpStream;
+
+    fn is_nonblocking(fd: std::os::unix::io::RawFd) -> bool {
+        unsafe { libc::fcntl(fd, libc::F_GETFL) & libc::O_NONBLOCK != 0 }
+    }
+
+    tokio_uring::start(async {
+        use std::os::unix::io::AsRawFd;
+
+        // As handed over by tokio, say.
+        let std_listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        std_listener.set_nonblocking(true).unwrap();
+        let addr = std_listener.local_addr().unwrap();
+        let listener = TcpListener::from_std(std_listener);
+        assert!(!is_nonblocking(listener.as_raw_fd()));
+
+        let client = std::net::TcpStream::connect(addr).unwrap();
+        client.set_nonblocking(true).unwrap();
+        let client = TcpStream::from_std(client);
+        let (server, _) = listener.accept().await.unwrap();
+
+        // The read is waiting in the kernel before the data is written.
+        let read = tokio_uring::spawn(async move {
+            let (n, buf) = client.read(vec![0; 16].into()).await.unwrap();
+            assert_eq!(&buf[0][..n], b"ping");
+            client
+        });
+        tokio::task::yield_now().await;
+        server.write_all(b"ping".to_vec().into()).await.unwrap();
+        let client = read.await.unwrap();
+
+        let mut client = client.into_std().unwrap();
+        let mut server = server.into_std().unwrap();
+        assert!(!is_nonblocking(client.as_raw_fd()));
+        client.write_all(b"pong").unwrap();
+        let mut buf = [0; 4];
+        server.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"pong");
+
+        // The socket stays open for a read dropped while in flight, which would otherwise
+        // take data meant for the std stream.
+        let server = TcpStream::from_std(server);
+        assert!(server.read(vec![0; 16].into()).now_or_never().is_none());
+        let err = server.into_std().unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::Other);
+
+        assert!(listener.into_std().is_ok());
+    });
+}
+
+#[test]
+fn accept_direct_reuses_slots() {
+    use std::collections::BTreeSet;
+    use std::io::{Read, Write};
+    use tokio_uring::net::DirectTableFull;
+
+    const SLOTS: u32 = 8;
+
+    tokio_uring::builder().direct_files(SLOTS).start(async {
+        let listener = TcpListener::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let mut first_slots = BTreeSet::new();
+        for round in 0..2 {
+            let mut conns = Vec::new();
+            for i in 0..SLOTS {
+                let mut client = std::net::TcpStream::connect(addr).unwrap();
+                let (stream, peer) = listener.accept_direct().await.unwrap();
+                assert_eq!(peer, client.local_addr().unwrap());
+
+                let msg = format!("round {} conn {}", round, i).into_bytes();
+                client.write_all(&msg).unwrap();
+                let (n, buf) = stream.read(vec![0; 64].into()).await.unwrap();
+                assert_eq!(&buf[0][..n], &msg[..]);
+                stream.write_all(buf).await.unwrap();
+                let mut echo = vec![0; n];
+                client.read_exact(&mut echo).unwrap();
+                assert_eq!(echo, msg);
+
+                conns.push((client, stream));
+            }
+
+            let slots: BTreeSet<u32> = conns.iter().map(|(_, s)| s.slot()).collect();
+            assert_eq!(slots.len(), SLOTS as usize);
+            if round == 0 {
+                first_slots = slots;
+
+                let _client = std::net::TcpStream::connect(addr).unwrap();
+                let err = listener.accept_direct().await.err().unwrap();
+                assert!(err.get_ref().unwrap().is::<DirectTableFull>(), "{}", err);
+            } else {
+                assert_eq!(slots, first_slots);
+            }
+
+            for (mut client, stream) in conns {
+                stream.close().unwrap();
+                // Freeing the slot closed the connection.
+                assert_eq!(client.read(&mut [0; 1]).unwrap(), 0);
+            }
+        }
+    });
+}
+
+#[test]
+fn accept_direct_freed_by_cancelled_read() {
+    use futures_util::FutureExt;
+    use std::io::{Read, Write};
+    use std::time::Duration;
+
+    tokio_uring::builder().direct_files(1).start(async {
+        let listener = TcpListener::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut client = std::net::TcpStream::connect(addr).unwrap();
+        let (stream, _) = listener.accept_direct().await.unwrap();
+
+        // The read is dropped in flight, leaving the last reference to the slot with the
+        // ignored op, which releases it as its completion is dispatched.
+        assert!(stream.read(vec![0; 16].into()).now_or_never().is_none());
+        drop(stream);
+        client.write_all(b"ping").unwrap();
+        tokio::time::timeout(Duration::from_secs(5), tokio_uring::no_op())
+            .await
+            .expect("completions stopped being dispatched")
+            .unwrap();
+
+        // Freeing the slot closed the connection, and made room for another.
+        client
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        assert_eq!(client.read(&mut [0; 1]).unwrap(), 0);
+        let _client = std::net::TcpStream::connect(addr).unwrap();
+        let (stream, _) = listener.accept_direct().await.unwrap();
+        stream.close().unwrap();
+    });
+}
+
+#[test]
+fn accept_direct_requires_table() {
+    tokio_uring::start(async {
+        let listener = TcpListener::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let err = listener.accept_direct().await.err().unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::Unsupported);
+    });
+}
+
+#[test]
+fn connect_to_falls_back() {
+    use std::time::{Duration, Instant};
+    use tokio_uring::net::{ConnectError, TcpStream};
+
+    let (dead, _blackhole) = blackhole();
+    // A port nothing listens on any more refuses connections.
+    let refused = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let live = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let timeout = Duration::from_millis(200);
+
+    tokio_uring::start(async {
+        let start = Instant::now();
+        let stream = TcpStream::connect_to(
+            vec![dead, refused, live.local_addr().unwrap()],
+            Some(timeout),
+        )
+        .await
+        .unwrap();
+        let elapsed = start.elapsed();
+        assert!(elapsed >= timeout, "{:?}", elapsed);
+        assert!(elapsed < 2 * timeout, "{:?}", elapsed);
+        assert_eq!(stream.peer_addr().unwrap(), live.local_addr().unwrap());
+
+        // The failed attempt's socket was closed, not left retrying.
+        assert_eq!(syn_sent_to(dead.port()), 0);
+
+        // Every failure is reported, with the kind of the last one. The IPv6 attempt needs a
+        // socket of its own family, and fails whether or not the host has IPv6.
+        let v6: std::net::SocketAddr = format!("[::1]:{}", refused.port()).parse().unwrap();
+        let err = TcpStream::connect_to(vec![v6, dead, refused], Some(timeout))
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::ConnectionRefused);
+        let attempts = err
+            .get_ref()
+            .unwrap()
+            .downcast_ref::<ConnectError>()
+            .unwrap()
+            .attempts();
+        let addrs: Vec<_> = attempts.iter().map(|(addr, _)| *addr).collect();
+        assert_eq!(addrs, vec![v6, dead, refused]);
+        assert_eq!(attempts[1].1.kind(), std::io::ErrorKind::TimedOut);
+        assert!(err.to_string().contains(&dead.to_string()), "{}", err);
+
+        let err = TcpStream::connect_to(vec![], None).await.err().unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    });
+}
+
+#[test]
+fn connect_with_data_delivers_payload() {
+    use tokio_uring::net::TcpSocket;
+    use tokio_uring::Buffer;
+
+    // Fast open only happens with both the client (1) and server (2) bits of the sysctl set;
+    // otherwise the payload still has to arrive, after the handshake.
+    let sysctl = std::fs::read_to_string("/proc/sys/net/ipv4/tcp_fastopen")
+        .ok()
+        .and_then(|s| s.trim().parse::<u32>().ok())
+        .unwrap_or(0);
+    let enabled = sysctl & 3 == 3;
+
+    tokio_uring::start(async {
+        let listener = TcpListener::builder()
+            .fastopen(16)
+            .bind("127.0.0.1:0".parse().unwrap())
+            .unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        // The first connection fetches the cookie that lets the later ones send in the SYN.
+        // The last payload is too large for the SYN, so the rest follows the handshake.
+        for (i, &len) in [100, 100, 256 * 1024].iter().enumerate() {
+            let payload: Vec<u8> = (0..len).map(|j| (j % 253) as u8).collect();
+            let socket = TcpSocket::new_v4().unwrap();
+            let ((stream, in_syn), buf) = socket
+                .connect_with_data(addr, payload.clone().into())
+                .await
+                .unwrap();
+            assert_eq!(&buf[0][..], &payload[..]);
+            assert!(in_syn <= len);
+            if i == 0 || !enabled {
+                assert_eq!(in_syn, 0);
+            } else {
+                assert!(in_syn > 0);
+            }
+
+            let (accepted, _) = listener.accept().await.unwrap();
+            let buf = Buffer::from(vec![0u8; len]);
+            let ((), buf) = accepted.read_exact(buf).await.unwrap();
+            assert_eq!(&buf[0][..], &payload[..]);
+
+            // The stream works as usual afterwards.
+            accepted.write_all(b"reply".to_vec().into()).await.unwrap();
+            let ((), buf) = stream.read_exact(vec![0u8; 5].into()).await.unwrap();
+            assert_eq!(&buf[0][..], b"reply");
+        }
+    });
+}
+
+#[test]
+fn connect_with_data_reports_refusal() {
+    use tokio_uring::net::TcpSocket;
+
+    tokio_uring::start(async {
+        // Nothing listens on the port once the listener is dropped.
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let socket = TcpSocket::new_v4().unwrap();
+        let err = socket
+            .connect_with_data(addr, b"hello".to_vec().into())
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(err.0.kind(), std::io::ErrorKind::ConnectionRefused);
+        assert_eq!(&err.1[0][..], b"hello");
+    });
+}
+
+#[test]
+fn bind_to_loopback_device() {
+    use tokio_uring::net::TcpSocket;
+
+    tokio_uring::start(async {
+        let socket = TcpSocket::new_v4().unwrap();
+        if let Err(e) = socket.set_device(Some("lo")) {
+            // Without CAP_NET_RAW, the failure must say so.
+            assert_eq!(e.kind(), std::io::ErrorKind::PermissionDenied);
+            return;
+        }
+        assert_eq!(socket.device().unwrap().as_deref(), Some("lo"));
+
+        let listener = TcpListener::builder()
+            .device("lo")
+            .bind("127.0.0.1:0".parse().unwrap())
+            .unwrap();
+        assert_eq!(listener.device().unwrap().as_deref(), Some("lo"));
+        let addr = listener.local_addr().unwrap();
+
+        let stream = socket.connect(addr).await.unwrap();
+        let (accepted, _) = listener.accept().await.unwrap();
+        stream.write_all(b"ping".to_vec().into()).await.unwrap();
+        let ((), buf) = accepted.read_exact(vec![0; 4].into()).await.unwrap();
+        assert_eq!(&buf[0][..], b"ping");
+
+        // An empty name unbinds.
+        let socket = TcpSocket::new_v4().unwrap();
+        socket.set_device(Some("lo")).unwrap();
+        socket.set_device(Some("")).unwrap();
+        assert_eq!(socket.device().unwrap(), None);
+    });
+}
+
+#[test]
+fn readable_waits_for_data() {
+    use std::io::Write;
+    use std::time::Duration;
+    use tokio_uring::net::Interest;
+
+    tokio_uring::start(async {
+        let listener = TcpListener::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let mut peer = std::net::TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (stream, _) = listener.accept().await.unwrap();
+
+        // A fresh connection has an empty send buffer, but nothing to read.
+        stream.writable().await.unwrap();
+        let ready = stream
+            .ready(Interest::READABLE | Interest::WRITABLE)
+            .await
+            .unwrap();
+        assert!(ready.is_writable());
+        assert!(!ready.is_readable());
+
+        // The timed out wait is cancelled, and a later one still works.
+        let res = tokio::time::timeout(Duration::from_millis(100), stream.readable()).await;
+        assert!(res.is_err());
+
+        peer.write_all(b"hello").unwrap();
+        stream.readable().await.unwrap();
+        let (n, buf) = stream.read(vec![0; 16].into()).await.unwrap();
+        assert_eq!(&buf[0][..n], b"hello");
+
+        drop(peer);
+        let ready = stream.ready(Interest::READABLE).await.unwrap();
+        assert!(ready.is_readable());
+        assert!(ready.is_read_closed());
+    });
+}
+
+#[test]
+fn incoming_take_three() {
+    use futures_util::StreamExt;
+    use std::time::Duration;
+
+    tokio_uring::start(async {
+        let listener = TcpListener::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let clients = std::thread::spawn(move || {
+            (0..3)
+                .map(|_| std::net::TcpStream::connect(addr).unwrap())
+                .collect::<Vec<_>>()
+        });
+        let accepted: Vec<_> = listener
+            .incoming()
+            .take(3)
+            .map(|conn| conn.unwrap())
+            .collect()
+            .await;
+        let clients = clients.join().unwrap();
+        assert_eq!(accepted.len(), 3);
+        for (_, peer) in &accepted {
+            assert!(clients.iter().any(|c| c.local_addr().unwrap() == *peer));
+        }
+
+        // Dropping a stream with an accept pending leaves the next connection to others.
+        let mut incoming = listener.incoming();
+        let pending = tokio::time::timeout(Duration::from_millis(50), incoming.next()).await;
+        assert!(pending.is_err());
+        drop(incoming);
+        let client = std::net::TcpStream::connect(addr).unwrap();
+        let (_, peer) = listener.accept().await.unwrap();
+        assert_eq!(peer, client.local_addr().unwrap());
+
+        drop(listener);
+        let err = std::net::TcpStream::connect(addr).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::ConnectionRefused);
+    });
+}
+
+#[test]
+fn peek_leaves_data_queued() {
+    use std::io::Write;
+
+    tokio_uring::start(async {
+        let listener = TcpListener::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let mut client = std::net::TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (stream, _) = listener.accept().await.unwrap();
+
+        let request = b"GET / HTTP/1.1\r\n\r\n";
+        client.write_all(request).unwrap();
+
+        let (n, prefix) = stream.peek(vec![0; 5].into()).await.unwrap();
+        assert_eq!(&prefix[0][..n], b"GET /");
+        // Nothing to see in an empty buffer, and nothing consumed either.
+        let (n, _) = stream.peek(Vec::<u8>::new().into()).await.unwrap();
+        assert_eq!(n, 0);
+
+        let ((), buf) = stream
+            .read_exact(vec![0; request.len()].into())
+            .await
+            .unwrap();
+        let mut seen = prefix[0].to_vec();
+        seen.extend_from_slice(&buf[0]);
+        assert_eq!(&seen[..5], &seen[5..10]);
+        assert_eq!(&buf[0][..], &request[..]);
+
+        drop(client);
+        let (n, _) = stream.peek(vec![0; 5].into()).await.unwrap();
+        assert_eq!(n, 0);
+    });
+}
+
+#[test]
+fn take_error_reports_refused_connect() {
+    use socket2::{Domain, Socket, Type};
+    use tokio_uring::net::TcpStream;
+
+    tokio_uring::start(async {
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+
+        // Connect without waiting, leaving the refusal for SO_ERROR.
+        let socket = Socket::new(Domain::IPV4, Type::STREAM, None).unwrap();
+        socket.set_nonblocking(true).unwrap();
+        match socket.connect(&addr.into()) {
+            Err(e) if e.raw_os_error() == Some(libc::EINPROGRESS) => {}
+            res => panic!("connect did not go asynchronous: {:?}", res),
+        }
+        let stream = TcpStream::from_std(socket.into());
+
+        stream.writable().await.unwrap();
+        let err = stream.take_error().unwrap().unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::ConnectionRefused);
+        // Taking it clears it.
+        assert!(stream.take_error().unwrap().is_none());
+    });
+}
+
+#[test]
+fn dual_stack_listener_reports_mapped_peers() {
+    use std::net::{Ipv4Addr, SocketAddr};
+    use tokio_uring::net::to_canonical;
+
+    tokio_uring::start(async {
+        let listener = TcpListener::builder()
+            .only_v6(false)
+            .bind("[::]:0".parse().unwrap())
+            .unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let client = std::net::TcpStream::connect((Ipv4Addr::LOCALHOST, port)).unwrap();
+        let (stream, peer) = listener.accept().await.unwrap();
+        let client_addr = client.local_addr().unwrap();
+        let mapped = SocketAddr::new(
+            Ipv4Addr::LOCALHOST.to_ipv6_mapped().into(),
+            client_addr.port(),
+        );
+        assert_eq!(peer, mapped);
+        assert_eq!(stream.peer_addr().unwrap(), mapped);
+        assert_eq!(to_canonical(peer), client_addr);
+
+        // Restricted to IPv6, the same kind of listener refuses IPv4 clients.
+        let listener = TcpListener::builder()
+            .only_v6(true)
+            .bind("[::]:0".parse().unwrap())
+            .unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let err = std::net::TcpStream::connect((Ipv4Addr::LOCALHOST, port))
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::ConnectionRefused);
+    });
+}
+
+#[test]
+fn accept_flags_set_on_descriptors() {
+    use std::os::unix::io::AsRawFd;
+    use tokio_uring::net::{AcceptFlags, TcpStream};
+
+    fn fd_flags(fd: &impl AsRawFd) -> (bool, bool) {
+        let fd = fd.as_raw_fd();
+        let fd_flags = unsafe { libc::fcntl(fd, libc::F_GETFD) };
+        let fl_flags = unsafe { libc::fcntl(fd, libc::F_GETFL) };
+        assert!(fd_flags >= 0 && fl_flags >= 0);
+        (
+            fd_flags & libc::FD_CLOEXEC != 0,
+            fl_flags & libc::O_NONBLOCK != 0,
+        )
+    }
+
+    tokio_uring::start(async {
+        let listener = TcpListener::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = listener.local_addr().unwrap();
+        assert_eq!(fd_flags(&listener), (true, false));
+
+        let client = TcpStream::connect(addr).await.unwrap();
+        assert_eq!(fd_flags(&client), (true, false));
+        let (stream, _) = listener.accept().await.unwrap();
+        assert_eq!(fd_flags(&stream), (true, false));
+
+        let _client = std::net::TcpStream::connect(addr).unwrap();
+        let (stream, _) = listener.accept_with_flags(AcceptFlags::NONE).await.unwrap();
+        assert_eq!(fd_flags(&stream), (false, false));
+
+        let _client = std::net::TcpStream::connect(addr).unwrap();
+        let (stream, _) = listener
+            .accept_with_flags(AcceptFlags::CLOEXEC | AcceptFlags::NONBLOCK)
+            .await
+            .unwrap();
+        assert_eq!(fd_flags(&stream), (true, true));
+    });
+}
+
+// The most a buffer size set with `SO_SNDBUF` or `SO_RCVBUF` is allowed to reach.
+fn buffer_max(name: &str) -> u32 {
+    let path = format!("/proc/sys/net/core/{}", name);
+    std::fs::read_to_string(path)
+        .unwrap()
+        .trim()
+        .parse()
+        .unwrap()
+}
+
+#[test]
+fn tos_round_trips() {
+    use tokio_uring::net::TcpSocket;
+
+    // Expedited Forwarding, DSCP 46.
+    const EF: u8 = 0xb8;
+    tokio_uring::start(async {
+        let listener = TcpListener::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let socket = TcpSocket::new_v4().unwrap();
+        socket.set_tos(EF).unwrap();
+        assert_eq!(socket.tos().unwrap(), EF);
+
+        let client = socket
+            .connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(client.tos().unwrap(), EF);
+        client.set_tos(0).unwrap();
+        assert_eq!(client.tos().unwrap(), 0);
+
+        let socket = TcpSocket::new_v6().unwrap();
+        socket.set_tos(EF).unwrap();
+        assert_eq!(socket.tos().unwrap(), EF);
+    });
+}
+
+#[test]
+fn socket_buffer_sizes() {
+    use tokio_uring::net::TcpSocket;
+
+    const SIZE: u32 = 1024 * 1024;
+    tokio_uring::start(async {
+        let listener = TcpListener::builder()
+            .recv_buffer_size(SIZE)
+            .send_buffer_size(SIZE)
+            .bind("127.0.0.1:0".parse().unwrap())
+            .unwrap();
+
+        let socket = TcpSocket::new_v4().unwrap();
+        socket.set_recv_buffer_size(SIZE).unwrap();
+        socket.set_send_buffer_size(SIZE).unwrap();
+        // The kernel reports double the size, unless capped below it.
+        assert!(socket.recv_buffer_size().unwrap() >= SIZE.min(buffer_max("rmem_max")));
+        assert!(socket.send_buffer_size().unwrap() >= SIZE.min(buffer_max("wmem_max")));
+
+        let client = socket
+            .connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (server, _) = listener.accept().await.unwrap();
+        for stream in [&client, &server].iter() {
+            assert!(stream.recv_buffer_size().unwrap() >= SIZE.min(buffer_max("rmem_max")));
+            assert!(stream.send_buffer_size().unwrap() >= SIZE.min(buffer_max("wmem_max")));
+        }
+
+        // Shrinking works on a connected stream too.
+        client.set_send_buffer_size(4096).unwrap();
+        assert!(client.send_buffer_size().unwrap() < SIZE);
+    });
+}
+
+#[test]
+fn send_more_and_cork_deliver_everything() {
+    tokio_uring::start(async {
+        let listener = TcpListener::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let client = tokio_uring::net::TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (server, _) = listener.accept().await.unwrap();
+        client.set_nodelay(true).unwrap();
+
+        let (n, _) = client
+            .send(b"header:".to_vec().into())
+            .more()
+            .submit()
+            .await
+            .unwrap();
+        assert_eq!(n, 7);
+        let (n, _) = client.send(b"body".to_vec().into()).submit().await.unwrap();
+        assert_eq!(n, 4);
+        let ((), buf) = server.read_exact(vec![0; 11].into()).await.unwrap();
+        assert_eq!(&buf[0][..], b"header:body");
+
+        client.set_cork(true).unwrap();
+        assert!(client.cork().unwrap());
+        for part in [&b"a"[..], b"bc", b"def"].iter() {
+            client.write_all(part.to_vec().into()).await.unwrap();
+        }
+        client.set_cork(false).unwrap();
+        assert!(!client.cork().unwrap());
+        let ((), buf) = server.read_exact(vec![0; 6].into()).await.unwrap();
+        assert_eq!(&buf[0][..], b"abcdef");
+    });
+}
+
+#[test]
+fn split_halves_run_concurrently() {
+    tokio_uring::start(async {
+        let listener = TcpListener::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let client = tokio_uring::net::TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (server, _) = listener.accept().await.unwrap();
+
+        // The server echoes until the client shuts down its write half.
+        let echo = tokio_uring::spawn(async move {
+            let (reader, writer) = server.into_split();
+            let mut buf = vec![0; 64].into();
+            loop {
+                let (n, b) = reader.read(buf).await.unwrap();
+                if n == 0 {
+                    break;
+                }
+                writer.write_all(b[0][..n].to_vec().into()).await.unwrap();
+                buf = b;
+            }
+        });
+
+        let (reader, writer) = client.into_split();
+        let local = writer.local_addr().unwrap();
+        let read = tokio_uring::spawn(async move {
+            let ((), buf) = reader.read_exact(vec![0; 300].into()).await.unwrap();
+            (reader, buf)
+        });
+        let write = tokio_uring::spawn(async move {
+            for i in 0..100u8 {
+                writer.write_all(vec![i; 3].into()).await.unwrap();
+            }
+            writer
+        });
+        let writer = write.await.unwrap();
+        let (reader, buf) = read.await.unwrap();
+        let expected: Vec<u8> = (0..100u8).flat_map(|i| vec![i; 3]).collect();
+        assert_eq!(&buf[0][..], &expected[..]);
+
+        writer.shutdown().unwrap();
+        echo.await.unwrap();
+
+        let stream = writer.reunite(reader).unwrap();
+        assert_eq!(stream.local_addr().unwrap(), local);
+        let (n, _) = stream.read(vec![0; 8].into()).await.unwrap();
+        assert_eq!(n, 0);
+    });
+}
+
+#[test]
+fn split_half_keeps_socket_open() {
+    tokio_uring::start(async {
+        let listener = TcpListener::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let a = tokio_uring::net::TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let b = tokio_uring::net::TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (server, _) = listener.accept().await.unwrap();
+
+        let (a_read, a_write) = a.into_split();
+        let (b_read, b_write) = b.into_split();
+        let err = a_read.reunite(b_write).err().unwrap();
+        let tokio_uring::net::ReuniteError(a_read, b_write) = err;
+        drop(b_read.reunite(b_write).unwrap());
+
+        // The write half alone still sends.
+        drop(a_read);
+        a_write
+            .write_all(b"still open".to_vec().into())
+            .await
+            .unwrap();
+        let ((), buf) = server.read_exact(vec![0; 10].into()).await.unwrap();
+        assert_eq!(&buf[0][..], b"still open");
+    });
+}
+
+#[test]
+fn pause_holds_back_accepts() {
+    use futures_util::StreamExt;
+    use std::rc::Rc;
+    use std::time::Duration;
+
+    tokio_uring::start(async {
+        let listener = Rc::new(TcpListener::bind("127.0.0.1:0".parse().unwrap()).unwrap());
+        let addr = listener.local_addr().unwrap();
+
+        // An accept already in flight is cancelled by the pause, and waits out the pause.
+        let accept = tokio_uring::spawn({
+            let listener = listener.clone();
+            async move { listener.accept().await.unwrap().1 }
+        });
+        tokio::task::yield_now().await;
+        listener.pause();
+        assert!(listener.is_paused());
+        // Let the accepting task cancel its accept.
+        tokio::task::yield_now().await;
+        let client = std::net::TcpStream::connect(addr).unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!accept.is_finished());
+
+        listener.resume();
+        assert_eq!(accept.await.unwrap(), client.local_addr().unwrap());
+
+        // The streams are pending while paused, then yield what queued up in the backlog.
+        let mut incoming = listener.incoming();
+        let mut multi = listener.accept_multi();
+        listener.pause();
+        let clients: Vec<_> = (0..2)
+            .map(|_| std::net::TcpStream::connect(addr).unwrap())
+            .collect();
+        let pending = tokio::time::timeout(Duration::from_millis(50), incoming.next()).await;
+        assert!(pending.is_err());
+        let pending = tokio::time::timeout(Duration::from_millis(50), multi.next()).await;
+        assert!(pending.is_err());
+
+        listener.resume();
+        let (_, first) = incoming.next().await.unwrap().unwrap();
+        let (_, second) = multi.next().await.unwrap().unwrap();
+        assert_eq!(first, clients[0].local_addr().unwrap());
+        assert_eq!(second, clients[1].local_addr().unwrap());
+    });
+}
+
+#[test]
+fn connect_host_resolves_localhost() {
+    tokio_uring::start(async {
+        // Listen on both loopback addresses, so whichever family the resolver puts first
+        // has a listener.
+        let (v4, v6) = loop {
+            let v4 = TcpListener::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+            let port = v4.local_addr().unwrap().port();
+            let v6 = TcpListener::builder()
+                .reuseport(false)
+                .bind(std::net::SocketAddr::new("::1".parse().unwrap(), port));
+            if let Ok(v6) = v6 {
+                break (v4, v6);
+            }
+        };
+        let port = v4.local_addr().unwrap().port();
+
+        let stream = tokio_uring::net::TcpStream::connect_host("localhost", port)
+            .await
+            .unwrap();
+        assert_eq!(stream.peer_addr().unwrap().port(), port);
+        assert!(stream.peer_addr().unwrap().ip().is_loopback());
+
+        // Literal addresses connect to that address.
+        let stream = tokio_uring::net::TcpStream::connect_host("::1", port)
+            .await
+            .unwrap();
+        assert_eq!(stream.peer_addr().unwrap(), v6.local_addr().unwrap());
+        let stream = tokio_uring::net::TcpStream::connect_host("127.0.0.1", port)
+            .await
+            .unwrap();
+        assert_eq!(stream.peer_addr().unwrap(), v4.local_addr().unwrap());
+    });
+}
+
+#[test]
+fn incoming_cpu_round_trips() {
+    tokio_uring::start(async {
+        let listener = TcpListener::builder()
+            .incoming_cpu(0)
+            .bind("127.0.0.1:0".parse().unwrap())
+            .unwrap();
+        assert_eq!(listener.incoming_cpu().unwrap(), Some(0));
+        let plain = TcpListener::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        assert_eq!(plain.incoming_cpu().unwrap(), None);
+
+        let client = tokio_uring::net::TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        client.set_incoming_cpu(0).unwrap();
+        assert!(client.incoming_cpu().unwrap().is_some());
+    });
+}
+
+#[test]
+fn cpu_steering_picks_listener_by_cpu() {
+    use std::time::Duration;
+
+    tokio_uring::start(async {
+        let first = TcpListener::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = first.local_addr().unwrap();
+        let second = TcpListener::bind(addr).unwrap();
+        let listeners = [first, second];
+        listeners[0].attach_cpu_steering(2).unwrap();
+        let err = listeners[0].attach_cpu_steering(0).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+
+        for _ in 0..4 {
+            // Connect from a thread pinned to the CPU it runs on, which handles the SYN.
+            let (client, cpu) = std::thread::spawn(move || unsafe {
+                let cpu = libc::sched_getcpu();
+                let mut set: libc::cpu_set_t = std::mem::zeroed();
+                libc::CPU_SET(cpu as usize, &mut set);
+                libc::sched_setaffinity(0, std::mem::size_of_val(&set), &set);
+                (std::net::TcpStream::connect(addr).unwrap(), cpu as usize)
+            })
+            .join()
+            .unwrap();
+
+            let steered = &listeners[cpu % 2];
+            let other = &listeners[(cpu + 1) % 2];
+            let (_, peer) = tokio::time::timeout(Duration::from_secs(5), steered.accept())
+                .await
+                .expect("connection went to the other listener")
+                .unwrap();
+            assert_eq!(peer, client.local_addr().unwrap());
+            let res = tokio::time::timeout(Duration::from_millis(20), other.accept()).await;
+            assert!(res.is_err());
+        }
+    });
+}
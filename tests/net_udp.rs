@@ -0,0 +1,837 @@
+use std::net::SocketAddr;
+
+use tokio_uring::net::{DatagramTruncated, UdpSocket};
+use tokio_uring::Buffer;
+
+async fn exchange(local: &str) {
+    let addr: SocketAddr = local.parse().unwrap();
+    let a = UdpSocket::bind(addr).await.unwrap();
+    let b = UdpSocket::bind(addr).await.unwrap();
+    let a_addr = a.local_addr().unwrap();
+    let b_addr = b.local_addr().unwrap();
+
+    a.send_to(b"ping".to_vec(), b_addr).await.unwrap();
+    let buf = Buffer::from(Vec::<u8>::with_capacity(64));
+    let ((n, from), buf) = b.recv_from(buf).await.unwrap();
+    assert_eq!(&buf[0][..n], b"ping");
+    assert_eq!(from, a_addr);
+
+    b.send_to(b"pong".to_vec(), from).await.unwrap();
+    let ((n, from), buf) = a.recv_from(buf).await.unwrap();
+    assert_eq!(&buf[0][..n], b"pong");
+    assert_eq!(from, b_addr);
+}
+
+#[test]
+fn send_to_recv_from_v4() {
+    tokio_uring::start(exchange("127.0.0.1:0"));
+}
+
+#[test]
+fn send_to_recv_from_v6() {
+    // Skip on hosts without IPv6 loopback.
+    if std::net::UdpSocket::bind("[::1]:0").is_err() {
+        return;
+    }
+    tokio_uring::start(exchange("[::1]:0"));
+}
+
+#[test]
+fn recv_from_reports_truncation() {
+    tokio_uring::start(async {
+        let a = UdpSocket::bind("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        let b = UdpSocket::bind("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        let a_addr = a.local_addr().unwrap();
+        let b_addr = b.local_addr().unwrap();
+
+        a.send_to(b"0123456789".to_vec(), b_addr).await.unwrap();
+        let buf = Buffer::from(Vec::<u8>::with_capacity(4));
+        let err = b.recv_from(buf).await.unwrap_err();
+        let truncated = err
+            .0
+            .get_ref()
+            .and_then(|e| e.downcast_ref::<DatagramTruncated>())
+            .unwrap();
+        assert_eq!(truncated.addr(), a_addr);
+        assert_eq!(truncated.datagram_len(), 10);
+        assert_eq!(&err.1[0][..], b"0123");
+
+        // The rest of the datagram is gone; the next one is received normally.
+        a.send_to(b"ok".to_vec(), b_addr).await.unwrap();
+        let ((n, _), buf) = b.recv_from(err.1).await.unwrap();
+        assert_eq!(&buf[0][..n], b"ok");
+    });
+}
+
+#[test]
+fn recv_trunc_reports_datagram_size() {
+    use tokio_uring::Submit;
+
+    tokio_uring::start(async {
+        let a = UdpSocket::bind("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        let b = UdpSocket::bind("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        let b_addr = b.local_addr().unwrap();
+
+        a.send_to(b"0123456789".to_vec(), b_addr).await.unwrap();
+
+        // Peeking with an empty buffer sizes the datagram without consuming it.
+        let buf = Buffer::from(Vec::<u8>::new());
+        let (size, _) = b.recv(buf).peek().trunc().submit().await.unwrap();
+        assert_eq!(size, 10);
+
+        // With a short buffer, the count is still the full size; the buffer holds the start.
+        let buf = Buffer::from(Vec::<u8>::with_capacity(4));
+        let (n, buf) = b.recv(buf).trunc().submit().await.unwrap();
+        assert_eq!(n, 10);
+        assert_eq!(&buf[0][..], b"0123");
+
+        // Without the flag, the same clipping is an error.
+        a.send_to(b"0123456789".to_vec(), b_addr).await.unwrap();
+        let err = b.recv(buf).submit().await.unwrap_err();
+        assert_eq!(err.0.raw_os_error(), Some(libc::EMSGSIZE));
+        assert_eq!(&err.1[0][..], b"0123");
+    });
+}
+
+fn enable(socket: &UdpSocket, level: libc::c_int, name: libc::c_int) {
+    use std::os::unix::io::AsRawFd;
+
+    let on: libc::c_int = 1;
+    let res = unsafe {
+        libc::setsockopt(
+            socket.as_raw_fd(),
+            level,
+            name,
+            &on as *const _ as *const libc::c_void,
+            std::mem::size_of_val(&on) as libc::socklen_t,
+        )
+    };
+    assert_eq!(res, 0, "{}", std::io::Error::last_os_error());
+}
+
+#[test]
+fn recv_msg_packet_info() {
+    use std::net::Ipv4Addr;
+    use tokio_uring::net::{CMsgs, ControlMessage};
+
+    tokio_uring::start(async {
+        let server = UdpSocket::bind("0.0.0.0:0".parse().unwrap()).await.unwrap();
+        enable(&server, libc::IPPROTO_IP, libc::IP_PKTINFO);
+        enable(&server, libc::IPPROTO_IP, libc::IP_RECVTOS);
+        let port = server.local_addr().unwrap().port();
+
+        let client = UdpSocket::bind("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        let mut cmsgs = CMsgs::new();
+        cmsgs.push(ControlMessage::Ipv4Tos(0x10));
+        client
+            .send_msg(
+                Buffer::from(b"query".to_vec()),
+                Some((Ipv4Addr::LOCALHOST, port).into()),
+                &cmsgs,
+            )
+            .await
+            .unwrap();
+
+        let buf = Buffer::from(Vec::<u8>::with_capacity(512));
+        let ((n, from, cmsgs), buf) = server.recv_msg(buf).await.unwrap();
+        assert_eq!(&buf[0][..n], b"query");
+        assert_eq!(from, client.local_addr().unwrap());
+        assert!(!cmsgs.is_truncated());
+
+        let dst = cmsgs.iter().find_map(|cmsg| match *cmsg {
+            ControlMessage::Ipv4PacketInfo { addr, .. } => Some(addr),
+            _ => None,
+        });
+        assert_eq!(dst, Some(Ipv4Addr::LOCALHOST));
+        assert!(cmsgs
+            .iter()
+            .any(|cmsg| *cmsg == ControlMessage::Ipv4Tos(0x10)));
+
+        // Reply from the address the query was sent to.
+        let mut reply = CMsgs::new();
+        reply.push(ControlMessage::Ipv4PacketInfo {
+            if_index: 0,
+            spec_dst: Ipv4Addr::LOCALHOST,
+            addr: Ipv4Addr::UNSPECIFIED,
+        });
+        server
+            .send_msg(Buffer::from(b"answer".to_vec()), Some(from), &reply)
+            .await
+            .unwrap();
+        let ((n, from), buf) = client
+            .recv_from(Buffer::from(Vec::<u8>::with_capacity(64)))
+            .await
+            .unwrap();
+        assert_eq!(&buf[0][..n], b"answer");
+        assert_eq!(from, (Ipv4Addr::LOCALHOST, port).into());
+    });
+}
+
+#[test]
+fn recv_msg_control_truncated() {
+    tokio_uring::start(async {
+        let server = UdpSocket::bind("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        enable(&server, libc::IPPROTO_IP, libc::IP_PKTINFO);
+        let client = UdpSocket::bind("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        client
+            .send_to(b"x".to_vec(), server.local_addr().unwrap())
+            .await
+            .unwrap();
+
+        let buf = Buffer::from(Vec::<u8>::with_capacity(16));
+        let ((n, _, cmsgs), _) = server.recv_msg_with_control_len(buf, 4).await.unwrap();
+        assert_eq!(n, 1);
+        assert!(cmsgs.is_truncated());
+        assert!(cmsgs.is_empty());
+    });
+}
+
+#[test]
+fn peer_addr_when_connected() {
+    tokio_uring::start(async {
+        let a = UdpSocket::bind("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        let b = UdpSocket::bind("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+
+        let err = a.peer_addr().unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::NotConnected);
+
+        a.connect(b.local_addr().unwrap()).await.unwrap();
+        assert_eq!(a.peer_addr().unwrap(), b.local_addr().unwrap());
+    });
+}
+
+#[test]
+fn multicast_v4_on_loopback() {
+    use std::net::Ipv4Addr;
+    use std::os::unix::io::AsRawFd;
+
+    let group = Ipv4Addr::new(239, 255, 0, 1);
+    tokio_uring::start(async {
+        let receiver = UdpSocket::bind("0.0.0.0:0".parse().unwrap()).await.unwrap();
+        receiver
+            .join_multicast_v4(group, Ipv4Addr::LOCALHOST)
+            .unwrap();
+        let port = receiver.local_addr().unwrap().port();
+
+        let sender = UdpSocket::bind("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        sender.set_multicast_loop_v4(true).unwrap();
+        sender.set_multicast_ttl_v4(1).unwrap();
+        // Send through loopback rather than the interface of the default route.
+        let interface = libc::in_addr {
+            s_addr: u32::from(Ipv4Addr::LOCALHOST).to_be(),
+        };
+        let res = unsafe {
+            libc::setsockopt(
+                sender.as_raw_fd(),
+                libc::IPPROTO_IP,
+                libc::IP_MULTICAST_IF,
+                &interface as *const _ as *const libc::c_void,
+                std::mem::size_of_val(&interface) as libc::socklen_t,
+            )
+        };
+        assert_eq!(res, 0, "{}", std::io::Error::last_os_error());
+
+        sender
+            .send_to(b"hello group".to_vec(), SocketAddr::from((group, port)))
+            .await
+            .unwrap();
+        let buf = Buffer::from(Vec::<u8>::with_capacity(64));
+        let ((n, from), buf) = receiver.recv_from(buf).await.unwrap();
+        assert_eq!(&buf[0][..n], b"hello group");
+        assert_eq!(from, sender.local_addr().unwrap());
+
+        receiver
+            .leave_multicast_v4(group, Ipv4Addr::LOCALHOST)
+            .unwrap();
+        // Not a member any more.
+        let err = receiver
+            .leave_multicast_v4(group, Ipv4Addr::LOCALHOST)
+            .unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(libc::EADDRNOTAVAIL));
+    });
+}
+
+#[test]
+fn multicast_rejects_bad_arguments() {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    tokio_uring::start(async {
+        let socket = UdpSocket::bind("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+
+        let err = socket
+            .join_multicast_v4(Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::LOCALHOST)
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+
+        let group = Ipv6Addr::new(0xff02, 0, 0, 0, 0, 0, 0, 0x1234);
+        let err = socket.join_multicast_v6(group, 0).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        let err = socket.set_multicast_loop_v6(true).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    });
+}
+
+const GSO_LEN: usize = 65000;
+const GSO_SEGMENT: usize = 1200;
+
+fn gso_payload() -> Vec<u8> {
+    (0..GSO_LEN).map(|i| (i % 251) as u8).collect()
+}
+
+// Sends the payload in segments, or returns `false` if the kernel lacks GSO.
+async fn send_segmented(sender: &UdpSocket, to: SocketAddr) -> bool {
+    let buf = Buffer::from(gso_payload());
+    match sender.send_to_segmented(buf, to, GSO_SEGMENT as u16).await {
+        Ok((n, _)) => {
+            assert_eq!(n, GSO_LEN);
+            true
+        }
+        Err(e) if e.0.kind() == std::io::ErrorKind::Unsupported => false,
+        Err(e) => panic!("{}", e.0),
+    }
+}
+
+#[test]
+fn send_to_segmented_splits_datagrams() {
+    tokio_uring::start(async {
+        let receiver = UdpSocket::bind("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        let sender = UdpSocket::bind("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        if !send_segmented(&sender, receiver.local_addr().unwrap()).await {
+            return;
+        }
+
+        let mut received = Vec::new();
+        let mut datagrams = 0;
+        let mut buf = Buffer::from(Vec::<u8>::with_capacity(64 * 1024));
+        while received.len() < GSO_LEN {
+            let ((n, _, segment_size), b) = receiver.recv_from_segmented(buf).await.unwrap();
+            assert_eq!(segment_size, n);
+            assert!(n <= GSO_SEGMENT);
+            received.extend_from_slice(&b[0][..n]);
+            datagrams += 1;
+            buf = b;
+        }
+        assert_eq!(datagrams, GSO_LEN.div_ceil(GSO_SEGMENT));
+        assert_eq!(received, gso_payload());
+    });
+}
+
+#[test]
+fn gro_reports_segment_size() {
+    tokio_uring::start(async {
+        let receiver = UdpSocket::bind("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        match receiver.set_gro(true) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::Unsupported => return,
+            Err(e) => panic!("{}", e),
+        }
+        let sender = UdpSocket::bind("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        if !send_segmented(&sender, receiver.local_addr().unwrap()).await {
+            return;
+        }
+
+        // Whether the segments arrive coalesced or not, splitting each payload by the
+        // reported size gives back the datagrams that were sent.
+        let mut received = Vec::new();
+        let mut datagrams = 0;
+        let mut buf = Buffer::from(Vec::<u8>::with_capacity(64 * 1024));
+        while received.len() < GSO_LEN {
+            let ((n, _, segment_size), b) = receiver.recv_from_segmented(buf).await.unwrap();
+            for segment in b[0][..n].chunks(segment_size) {
+                assert!(segment.len() <= GSO_SEGMENT);
+                received.extend_from_slice(segment);
+                datagrams += 1;
+            }
+            buf = b;
+        }
+        assert_eq!(datagrams, GSO_LEN.div_ceil(GSO_SEGMENT));
+        assert_eq!(received, gso_payload());
+    });
+}
+
+#[test]
+fn send_to_segmented_rejects_bad_sizes() {
+    tokio_uring::start(async {
+        let socket = UdpSocket::bind("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        let to = socket.local_addr().unwrap();
+
+        let err = socket
+            .send_to_segmented(Buffer::from(vec![0; 100]), to, 0)
+            .await
+            .unwrap_err();
+        assert_eq!(err.0.kind(), std::io::ErrorKind::InvalidInput);
+
+        let err = socket
+            .send_to_segmented(Buffer::from(vec![0; 65 * 100]), to, 100)
+            .await
+            .unwrap_err();
+        assert_eq!(err.0.kind(), std::io::ErrorKind::InvalidInput);
+    });
+}
+
+#[test]
+fn recv_msg_multi_from_many_senders() {
+    use futures_util::StreamExt;
+    use std::sync::mpsc;
+    use tokio_uring::buf::bufring::BufRing;
+
+    const SENDERS: usize = 4;
+    const ROUNDS: usize = 10;
+    const PER_ROUND: usize = 25;
+    const TOTAL: usize = SENDERS * ROUNDS * PER_ROUND;
+
+    tokio_uring::start(async {
+        let group = BufRing::register(64, 1024, 3).unwrap();
+        let receiver = UdpSocket::bind("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        let to = receiver.local_addr().unwrap();
+
+        let senders: Vec<_> = (0..SENDERS)
+            .map(|_| std::net::UdpSocket::bind("127.0.0.1:0").unwrap())
+            .collect();
+        let addrs: Vec<SocketAddr> = senders.iter().map(|s| s.local_addr().unwrap()).collect();
+
+        // Rounds are paced by the receiver, so the socket's receive buffer never overflows.
+        let (round_done, next_round) = mpsc::channel::<()>();
+        let blaster = std::thread::spawn(move || {
+            for round in 0..ROUNDS {
+                for i in 0..PER_ROUND {
+                    for (sender, socket) in senders.iter().enumerate() {
+                        let seq = (round * PER_ROUND + i) as u32;
+                        let mut datagram = vec![sender as u8];
+                        datagram.extend_from_slice(&seq.to_be_bytes());
+                        datagram.resize(5 + seq as usize % 100, 0xaa);
+                        socket.send_to(&datagram, to).unwrap();
+                    }
+                }
+                next_round.recv().unwrap();
+            }
+        });
+
+        let mut next_seq = [0u32; SENDERS];
+        let mut received = 0;
+        let mut datagrams = receiver.recv_msg_multi(&group).unwrap();
+        while received < TOTAL {
+            let (buf, from, cmsgs) = datagrams.next().await.unwrap().unwrap();
+            let payload = &buf[0];
+            let sender = payload[0] as usize;
+            let seq = u32::from_be_bytes([payload[1], payload[2], payload[3], payload[4]]);
+            assert_eq!(from, addrs[sender]);
+            assert!(cmsgs.is_empty());
+            assert_eq!(payload.len(), 5 + seq as usize % 100);
+            assert!(payload[5..].iter().all(|&b| b == 0xaa));
+            // Loopback keeps each sender's datagrams in order.
+            assert_eq!(seq, next_seq[sender]);
+            next_seq[sender] += 1;
+
+            received += 1;
+            if received % (SENDERS * PER_ROUND) == 0 {
+                round_done.send(()).unwrap();
+            }
+        }
+        blaster.join().unwrap();
+    });
+}
+
+#[test]
+fn recv_msg_multi_rejects_small_buffers() {
+    use tokio_uring::buf::bufring::BufRing;
+
+    tokio_uring::start(async {
+        let group = BufRing::register(4, 64, 4).unwrap();
+        let socket = UdpSocket::bind("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        let err = socket.recv_msg_multi(&group).err().unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    });
+}
+
+#[test]
+fn bind_to_loopback_device() {
+    tokio_uring::start(async {
+        let a = UdpSocket::bind("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        if let Err(e) = a.set_device(Some("lo")) {
+            assert_eq!(e.kind(), std::io::ErrorKind::PermissionDenied);
+            return;
+        }
+        assert_eq!(a.device().unwrap().as_deref(), Some("lo"));
+
+        let b = UdpSocket::bind("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        a.send_to(b"ping".to_vec(), b.local_addr().unwrap())
+            .await
+            .unwrap();
+        let buf = Buffer::from(Vec::<u8>::with_capacity(16));
+        let ((n, from), buf) = b.recv_from(buf).await.unwrap();
+        assert_eq!(&buf[0][..n], b"ping");
+        assert_eq!(from, a.local_addr().unwrap());
+
+        a.set_device(None).unwrap();
+        assert_eq!(a.device().unwrap(), None);
+    });
+}
+
+#[test]
+fn readable_waits_for_datagram() {
+    use std::time::Duration;
+
+    tokio_uring::start(async {
+        let socket = UdpSocket::bind("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        socket.writable().await.unwrap();
+
+        let res = tokio::time::timeout(Duration::from_millis(100), socket.readable()).await;
+        assert!(res.is_err());
+
+        let peer = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        peer.send_to(b"ping", socket.local_addr().unwrap()).unwrap();
+        socket.readable().await.unwrap();
+        let buf = Buffer::from(Vec::<u8>::with_capacity(16));
+        let ((n, _), buf) = socket.recv_from(buf).await.unwrap();
+        assert_eq!(&buf[0][..n], b"ping");
+    });
+}
+
+#[test]
+fn connected_send_recv() {
+    use tokio_uring::Submit;
+
+    tokio_uring::start(async {
+        let a = UdpSocket::bind("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        let b = UdpSocket::bind("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        let stranger = UdpSocket::bind("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        a.connect(b.local_addr().unwrap()).await.unwrap();
+        b.connect(a.local_addr().unwrap()).await.unwrap();
+        assert_eq!(a.peer_addr().unwrap(), b.local_addr().unwrap());
+
+        // b only hears from a.
+        stranger
+            .send_to(b"noise".to_vec(), b.local_addr().unwrap())
+            .await
+            .unwrap();
+        a.send(b"ping".to_vec()).await.unwrap();
+        let (n, buf) = b.recv(vec![0; 16].into()).submit().await.unwrap();
+        assert_eq!(&buf[0][..n], b"ping");
+
+        b.send(b"pong".to_vec()).await.unwrap();
+        let (n, buf) = a.recv(vec![0; 16].into()).submit().await.unwrap();
+        assert_eq!(&buf[0][..n], b"pong");
+    });
+}
+
+#[test]
+fn connected_recv_reports_refusal() {
+    use tokio_uring::Submit;
+
+    tokio_uring::start(async {
+        // Nothing listens on the port once the socket is dropped.
+        let addr = std::net::UdpSocket::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let socket = UdpSocket::bind("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        socket.connect(addr).await.unwrap();
+
+        socket.send(b"ping".to_vec()).await.unwrap();
+        let err = socket
+            .recv(vec![0; 16].into())
+            .submit()
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(err.0.kind(), std::io::ErrorKind::ConnectionRefused);
+    });
+}
+
+#[test]
+fn dual_stack_socket_reports_mapped_origins() {
+    use std::net::Ipv4Addr;
+    use tokio_uring::net::to_canonical;
+
+    tokio_uring::start(async {
+        let socket = UdpSocket::builder()
+            .only_v6(false)
+            .bind("[::]:0".parse().unwrap())
+            .unwrap();
+        let port = socket.local_addr().unwrap().port();
+
+        let sender = std::net::UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        sender
+            .send_to(b"ping", (Ipv4Addr::LOCALHOST, port))
+            .unwrap();
+        let ((n, from), buf) = socket.recv_from(vec![0; 16]).await.unwrap();
+        assert_eq!(&buf[..n], b"ping");
+        assert_eq!(
+            from.ip(),
+            std::net::IpAddr::from(Ipv4Addr::LOCALHOST.to_ipv6_mapped())
+        );
+        assert_eq!(to_canonical(from), sender.local_addr().unwrap());
+    });
+}
+
+#[test]
+fn recv_batch_receives_bursts() {
+    use std::collections::HashSet;
+
+    tokio_uring::start(async {
+        let socket = UdpSocket::bind("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        let addr = socket.local_addr().unwrap();
+        let senders: Vec<_> = (0..2)
+            .map(|_| std::net::UdpSocket::bind("127.0.0.1:0").unwrap())
+            .collect();
+
+        for i in 0..64u8 {
+            let sender = &senders[usize::from(i % 2)];
+            sender.send_to(&[i], addr).unwrap();
+        }
+
+        let mut seen = HashSet::new();
+        for _ in 0..2 {
+            let bufs = (0..32).map(|_| vec![0; 16].into()).collect();
+            let (datagrams, unused) = socket.recv_batch(bufs, 32).await.unwrap();
+            assert_eq!(datagrams.len(), 32);
+            assert!(unused.is_empty());
+            for (n, buf, from) in datagrams {
+                assert_eq!(n, 1);
+                let i = buf[0][0];
+                assert_eq!(from, senders[usize::from(i % 2)].local_addr().unwrap());
+                assert!(seen.insert(i));
+            }
+        }
+        assert_eq!(seen.len(), 64);
+
+        // A partial batch cancels the receives left waiting and hands their buffers back.
+        for i in 0..3u8 {
+            senders[0].send_to(&[i], addr).unwrap();
+        }
+        let bufs = (0..8).map(|_| vec![0; 16].into()).collect();
+        let (datagrams, unused) = socket.recv_batch(bufs, 3).await.unwrap();
+        assert_eq!(datagrams.len(), 3);
+        assert_eq!(unused.len(), 5);
+
+        // None of the cancelled receives took the next datagram.
+        senders[1].send_to(b"after", addr).unwrap();
+        let ((n, from), buf) = socket.recv_from(vec![0; 16]).await.unwrap();
+        assert_eq!(&buf[..n], b"after");
+        assert_eq!(from, senders[1].local_addr().unwrap());
+    });
+}
+
+#[test]
+fn send_batch_delivers_burst() {
+    tokio_uring::start(async {
+        let socket = UdpSocket::bind("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        let receivers: Vec<_> = (0..2)
+            .map(|_| std::net::UdpSocket::bind("127.0.0.1:0").unwrap())
+            .collect();
+
+        let items = (0..32u8)
+            .map(|i| {
+                let to = receivers[usize::from(i % 2)].local_addr().unwrap();
+                (vec![i; usize::from(i) + 1].into(), to)
+            })
+            .collect();
+        let results = socket.send_batch(items).await;
+        assert_eq!(results.len(), 32);
+        for (i, res) in results.into_iter().enumerate() {
+            let (n, buf) = res.unwrap();
+            assert_eq!(n, i + 1);
+            assert_eq!(&buf[0][..], &vec![i as u8; i + 1][..]);
+        }
+
+        let mut seen = [false; 32];
+        for receiver in &receivers {
+            for _ in 0..16 {
+                let mut buf = [0; 64];
+                let (n, from) = receiver.recv_from(&mut buf).unwrap();
+                assert_eq!(from, socket.local_addr().unwrap());
+                let i = usize::from(buf[0]);
+                assert_eq!(n, i + 1);
+                assert_eq!(
+                    receiver.local_addr().unwrap(),
+                    receivers[i % 2].local_addr().unwrap()
+                );
+                assert!(buf[..n].iter().all(|&b| usize::from(b) == i));
+                seen[i] = true;
+            }
+        }
+        assert!(seen.iter().all(|&s| s));
+    });
+}
+
+#[test]
+fn socket_buffer_sizes() {
+    const SIZE: u32 = 1024 * 1024;
+    let max = |name: &str| -> u32 {
+        let path = format!("/proc/sys/net/core/{}", name);
+        std::fs::read_to_string(path)
+            .unwrap()
+            .trim()
+            .parse()
+            .unwrap()
+    };
+
+    tokio_uring::start(async {
+        let socket = UdpSocket::bind("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        socket.set_recv_buffer_size(SIZE).unwrap();
+        socket.set_send_buffer_size(SIZE).unwrap();
+        assert!(socket.recv_buffer_size().unwrap() >= SIZE.min(max("rmem_max")));
+        assert!(socket.send_buffer_size().unwrap() >= SIZE.min(max("wmem_max")));
+    });
+}
+
+#[test]
+fn tos_marks_datagrams() {
+    use std::net::Ipv4Addr;
+    use tokio_uring::net::{CMsgs, ControlMessage};
+
+    const EF: u8 = 0xb8;
+    tokio_uring::start(async {
+        let server = UdpSocket::bind("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        enable(&server, libc::IPPROTO_IP, libc::IP_RECVTOS);
+        let addr = server.local_addr().unwrap();
+
+        let client = UdpSocket::bind("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        client.set_tos(EF).unwrap();
+        assert_eq!(client.tos().unwrap(), EF);
+
+        // The socket's mark, then a per-datagram override.
+        client
+            .send_msg(Buffer::from(b"ef".to_vec()), Some(addr), &CMsgs::new())
+            .await
+            .unwrap();
+        let mut cmsgs = CMsgs::new();
+        cmsgs.push(ControlMessage::Ipv4Tos(0x28));
+        client
+            .send_msg(
+                Buffer::from(b"af11".to_vec()),
+                Some((Ipv4Addr::LOCALHOST, addr.port()).into()),
+                &cmsgs,
+            )
+            .await
+            .unwrap();
+
+        for &expected in [EF, 0x28].iter() {
+            let buf = Buffer::from(Vec::<u8>::with_capacity(64));
+            let ((_, _, cmsgs), _) = server.recv_msg(buf).await.unwrap();
+            assert!(cmsgs
+                .iter()
+                .any(|cmsg| *cmsg == ControlMessage::Ipv4Tos(expected)));
+        }
+
+        let v6 = UdpSocket::bind("[::1]:0".parse().unwrap()).await.unwrap();
+        v6.set_tos(EF).unwrap();
+        assert_eq!(v6.tos().unwrap(), EF);
+    });
+}
+
+#[test]
+fn recv_msg_software_timestamps() {
+    use std::time::{Duration, SystemTime};
+    use tokio_uring::net::{ControlMessage, TimestampingFlags};
+
+    tokio_uring::start(async {
+        let server = UdpSocket::bind("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        server
+            .set_timestamping(TimestampingFlags::RX_SOFTWARE | TimestampingFlags::SOFTWARE)
+            .unwrap();
+        let addr = server.local_addr().unwrap();
+        let client = UdpSocket::bind("127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        let software = |cmsgs: &tokio_uring::net::CMsgs| {
+            cmsgs.iter().find_map(|cmsg| match *cmsg {
+                ControlMessage::Timestamping { software, .. } => software,
+                _ => None,
+            })
+        };
+
+        // The kernel turns timestamping on in the background, so the first
+        // datagrams may come unstamped on a busy machine.
+        let mut warm = false;
+        for _ in 0..100 {
+            client.send_to(b"warm-up".to_vec(), addr).await.unwrap();
+            let buf = Buffer::from(Vec::<u8>::with_capacity(64));
+            let ((_, _, cmsgs), _) = server.recv_msg(buf).await.unwrap();
+            if software(&cmsgs).is_some() {
+                warm = true;
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(warm, "timestamping never took effect");
+
+        let mut stamps = Vec::new();
+        for &payload in [&b"first"[..], &b"second"[..]].iter() {
+            client.send_to(payload.to_vec(), addr).await.unwrap();
+            let buf = Buffer::from(Vec::<u8>::with_capacity(64));
+            let ((n, _, cmsgs), buf) = server.recv_msg(buf).await.unwrap();
+            assert_eq!(&buf[0][..n], payload);
+
+            let stamp = software(&cmsgs).expect("no software timestamp");
+            let now = SystemTime::now();
+            let age = now.duration_since(stamp).unwrap_or_else(|e| e.duration());
+            assert!(age < Duration::from_secs(1), "{:?} vs {:?}", stamp, now);
+            stamps.push(stamp);
+        }
+        assert!(stamps[0] <= stamps[1]);
+    });
+}
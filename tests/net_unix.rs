@@ -0,0 +1,651 @@
+use std::ffi::OsStr;
+use std::os::linux::net::SocketAddrExt;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::io::AsRawFd;
+
+use tokio_uring::net::UnixListener;
+
+#[test]
+fn accept_unnamed_peer() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("listener.sock");
+
+    tokio_uring::start(async {
+        let listener = UnixListener::bind(&path).unwrap();
+
+        let _client = std::os::unix::net::UnixStream::connect(&path).unwrap();
+        let (_stream, peer) = listener.accept().await.unwrap();
+        assert!(peer.is_unnamed());
+    });
+}
+
+#[test]
+fn accept_bound_peers() {
+    use socket2::{Domain, SockAddr, Socket, Type};
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("listener.sock");
+    let client_path = dir.path().join("client.sock");
+    let name = format!("\0tokio-uring-accept-{}", std::process::id());
+
+    tokio_uring::start(async {
+        let listener = UnixListener::bind(&path).unwrap();
+
+        // std cannot bind a connecting socket, so use socket2 for the clients.
+        let client = Socket::new(Domain::UNIX, Type::STREAM, None).unwrap();
+        client.bind(&SockAddr::unix(&client_path).unwrap()).unwrap();
+        client.connect(&SockAddr::unix(&path).unwrap()).unwrap();
+        let (_stream, peer) = listener.accept().await.unwrap();
+        assert_eq!(peer.as_pathname(), Some(client_path.as_path()));
+
+        let client = Socket::new(Domain::UNIX, Type::STREAM, None).unwrap();
+        client
+            .bind(&SockAddr::unix(OsStr::from_bytes(name.as_bytes())).unwrap())
+            .unwrap();
+        client.connect(&SockAddr::unix(&path).unwrap()).unwrap();
+        let (_stream, peer) = listener.accept().await.unwrap();
+        assert!(peer.as_pathname().is_none());
+        assert_eq!(peer.as_abstract_name(), Some(&name.as_bytes()[1..]));
+    });
+}
+
+#[test]
+fn stream_addrs() {
+    use tokio_uring::net::UnixStream;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("listener.sock");
+
+    tokio_uring::start(async {
+        let listener = UnixListener::bind(&path).unwrap();
+        assert_eq!(
+            listener.local_addr().unwrap().as_pathname(),
+            Some(path.as_path())
+        );
+
+        let client = UnixStream::connect(&path).await.unwrap();
+        let (server, _) = listener.accept().await.unwrap();
+
+        assert_eq!(
+            client.peer_addr().unwrap().as_pathname(),
+            Some(path.as_path())
+        );
+        assert!(client.local_addr().unwrap().is_unnamed());
+        assert_eq!(
+            server.local_addr().unwrap().as_pathname(),
+            Some(path.as_path())
+        );
+        assert!(server.peer_addr().unwrap().is_unnamed());
+    });
+}
+
+#[test]
+fn connect_exchange() {
+    use tokio_uring::net::UnixStream;
+    use tokio_uring::Submit;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("listener.sock");
+
+    tokio_uring::start(async {
+        let listener = UnixListener::bind(&path).unwrap();
+        let client = UnixStream::connect(&path).await.unwrap();
+        let (server, _) = listener.accept().await.unwrap();
+
+        client
+            .write(b"ping".to_vec().into())
+            .submit()
+            .await
+            .unwrap();
+        let (n, buf) = server.read(vec![0; 16].into()).await.unwrap();
+        assert_eq!(&buf[0][..n], b"ping");
+
+        server
+            .write(b"pong".to_vec().into())
+            .submit()
+            .await
+            .unwrap();
+        let (n, buf) = client.read(vec![0; 16].into()).await.unwrap();
+        assert_eq!(&buf[0][..n], b"pong");
+    });
+}
+
+#[test]
+fn connect_errors() {
+    use std::io::ErrorKind;
+    use tokio_uring::net::UnixStream;
+
+    let dir = tempfile::tempdir().unwrap();
+    let missing = dir.path().join("missing.sock");
+    let stale = dir.path().join("stale.sock");
+    // Dropping the listener leaves its socket file behind.
+    drop(std::os::unix::net::UnixListener::bind(&stale).unwrap());
+
+    tokio_uring::start(async {
+        let err = UnixStream::connect(&missing).await.err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+
+        let err = UnixStream::connect(&stale).await.err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::ConnectionRefused);
+    });
+}
+
+#[test]
+fn stream_pair() {
+    use tokio_uring::net::UnixStream;
+    use tokio_uring::Submit;
+
+    tokio_uring::start(async {
+        let (a, b) = UnixStream::pair().unwrap();
+
+        let echo = tokio_uring::spawn(async move {
+            let (n, buf) = b.read(vec![0; 16].into()).await.unwrap();
+            assert_eq!(&buf[0][..n], b"ping");
+            b.write(b"pong".to_vec().into()).submit().await.unwrap();
+            // Closing this end shows up as end-of-file on the other.
+        });
+
+        a.write(b"ping".to_vec().into()).submit().await.unwrap();
+        let (n, buf) = a.read(vec![0; 16].into()).await.unwrap();
+        assert_eq!(&buf[0][..n], b"pong");
+
+        echo.await.unwrap();
+        let (n, _) = a.read(buf).await.unwrap();
+        assert_eq!(n, 0);
+    });
+}
+
+#[test]
+fn datagram_pair() {
+    use tokio_uring::net::UnixDatagram;
+
+    tokio_uring::start(async {
+        let (a, b) = UnixDatagram::pair().unwrap();
+
+        a.send(b"one".to_vec().into()).await.unwrap();
+        a.send(b"two".to_vec().into()).await.unwrap();
+        let (n, buf) = b.recv(vec![0; 16].into()).await.unwrap();
+        assert_eq!(&buf[0][..n], b"one");
+        let (n, buf) = b.recv(buf).await.unwrap();
+        assert_eq!(&buf[0][..n], b"two");
+
+        b.send(b"three".to_vec().into()).await.unwrap();
+        let (n, buf) = a.recv(buf).await.unwrap();
+        assert_eq!(&buf[0][..n], b"three");
+    });
+}
+
+#[test]
+fn datagram_bound_unbound_exchange() {
+    use tokio_uring::net::UnixDatagram;
+
+    let dir = tempfile::tempdir().unwrap();
+    let server_path = dir.path().join("server.sock");
+    let client_path = dir.path().join("client.sock");
+
+    tokio_uring::start(async {
+        let server = UnixDatagram::bind(&server_path).unwrap();
+        assert_eq!(
+            server.local_addr().unwrap().as_pathname(),
+            Some(server_path.as_path())
+        );
+
+        // An unbound sender is unnamed, and its datagrams keep their boundaries.
+        let unbound = UnixDatagram::unbound().unwrap();
+        unbound
+            .send_to(b"first".to_vec().into(), &server_path)
+            .await
+            .unwrap();
+        unbound
+            .send_to(b"second".to_vec().into(), &server_path)
+            .await
+            .unwrap();
+        let ((n, from), buf) = server.recv_from(vec![0; 64].into()).await.unwrap();
+        assert_eq!(&buf[0][..n], b"first");
+        assert!(from.is_unnamed());
+        let ((n, from), buf) = server.recv_from(buf).await.unwrap();
+        assert_eq!(&buf[0][..n], b"second");
+        assert!(from.is_unnamed());
+
+        // A bound sender can be replied to at the address it is reported under.
+        let client = UnixDatagram::bind(&client_path).unwrap();
+        client
+            .send_to(b"ping".to_vec().into(), &server_path)
+            .await
+            .unwrap();
+        let ((n, from), buf) = server.recv_from(buf).await.unwrap();
+        assert_eq!(&buf[0][..n], b"ping");
+        let reply_to = from.as_pathname().unwrap();
+        assert_eq!(reply_to, client_path.as_path());
+
+        server
+            .send_to(b"pong".to_vec().into(), reply_to)
+            .await
+            .unwrap();
+        let (n, buf) = client.recv(buf).await.unwrap();
+        assert_eq!(&buf[0][..n], b"pong");
+
+        // Once connected, send goes to the peer.
+        unbound.connect(&client_path).await.unwrap();
+        assert_eq!(
+            unbound.peer_addr().unwrap().as_pathname(),
+            Some(client_path.as_path())
+        );
+        unbound.send(b"connected".to_vec().into()).await.unwrap();
+        let (n, buf) = client.recv(buf).await.unwrap();
+        assert_eq!(&buf[0][..n], b"connected");
+    });
+}
+
+#[test]
+fn datagram_truncated() {
+    use tokio_uring::net::UnixDatagram;
+
+    tokio_uring::start(async {
+        let (a, b) = UnixDatagram::pair().unwrap();
+
+        a.send(b"too long".to_vec().into()).await.unwrap();
+        a.send(b"fits".to_vec().into()).await.unwrap();
+
+        let err = b
+            .recv(Vec::<u8>::with_capacity(4).into())
+            .await
+            .unwrap_err();
+        assert_eq!(err.0.raw_os_error(), Some(libc::EMSGSIZE));
+        assert_eq!(&err.1[0][..], b"too ");
+
+        // The rest of the truncated datagram is gone.
+        let (n, buf) = b.recv(vec![0; 16].into()).await.unwrap();
+        assert_eq!(&buf[0][..n], b"fits");
+    });
+}
+
+#[test]
+fn stream_pass_fds() {
+    use std::io::{Read, Seek, Write};
+    use std::os::fd::AsFd;
+    use tokio_uring::net::UnixStream;
+
+    let mut file = tempfile::tempfile().unwrap();
+    file.write_all(b"passed along").unwrap();
+
+    tokio_uring::start(async {
+        let (a, b) = UnixStream::pair().unwrap();
+
+        a.send_fds(b"fd".to_vec().into(), &[file.as_fd()])
+            .await
+            .unwrap();
+        let ((n, fds), _) = b.recv_with_fds(vec![0; 16].into(), 4).await.unwrap();
+        assert_eq!(n, 2);
+        assert_eq!(fds.len(), 1);
+
+        let flags = unsafe { libc::fcntl(fds[0].as_raw_fd(), libc::F_GETFD) };
+        assert_ne!(flags & libc::FD_CLOEXEC, 0);
+
+        // The received descriptor shares the file, and its offset, with the original.
+        let mut received = std::fs::File::from(fds.into_iter().next().unwrap());
+        received.rewind().unwrap();
+        let mut contents = String::new();
+        received.read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "passed along");
+    });
+}
+
+#[test]
+fn stream_pass_fds_truncated() {
+    use std::os::fd::AsFd;
+    use tokio_uring::net::UnixStream;
+
+    let file = tempfile::tempfile().unwrap();
+
+    tokio_uring::start(async {
+        let (a, b) = UnixStream::pair().unwrap();
+
+        a.send_fds(b"two".to_vec().into(), &[file.as_fd(), file.as_fd()])
+            .await
+            .unwrap();
+        let err = b.recv_with_fds(vec![0; 16].into(), 1).await.unwrap_err();
+        assert_eq!(err.0.raw_os_error(), Some(libc::EMSGSIZE));
+        assert_eq!(&err.1[0][..], b"two");
+    });
+}
+
+#[test]
+fn abstract_addresses() {
+    use std::os::unix::net::SocketAddr;
+    use tokio_uring::net::{UnixDatagram, UnixStream};
+    use tokio_uring::Submit;
+
+    let name = format!("tokio-uring-abstract-{}", std::process::id());
+    let addr = SocketAddr::from_abstract_name(name.as_bytes()).unwrap();
+
+    tokio_uring::start(async {
+        let listener = UnixListener::bind_addr(&addr).unwrap();
+        let local_addr = listener.local_addr().unwrap();
+        assert!(local_addr.as_pathname().is_none());
+        assert_eq!(local_addr.as_abstract_name(), Some(name.as_bytes()));
+
+        let client = UnixStream::connect_addr(&addr).await.unwrap();
+        let (server, _) = listener.accept().await.unwrap();
+        assert_eq!(
+            client.peer_addr().unwrap().as_abstract_name(),
+            Some(name.as_bytes())
+        );
+        client.write(b"hi".to_vec().into()).submit().await.unwrap();
+        let (n, buf) = server.read(vec![0; 8].into()).await.unwrap();
+        assert_eq!(&buf[0][..n], b"hi");
+
+        // The name is taken until the listener goes away.
+        assert_eq!(
+            UnixListener::bind_addr(&addr).err().unwrap().kind(),
+            std::io::ErrorKind::AddrInUse
+        );
+
+        let dgram_name = format!("{}-dgram", name);
+        let dgram_addr = SocketAddr::from_abstract_name(dgram_name.as_bytes()).unwrap();
+        let receiver = UnixDatagram::bind_addr(&dgram_addr).unwrap();
+        let sender = UnixDatagram::unbound().unwrap();
+        sender
+            .send_to_addr(b"abstract".to_vec().into(), &dgram_addr)
+            .await
+            .unwrap();
+        let (n, buf) = receiver.recv(buf).await.unwrap();
+        assert_eq!(&buf[0][..n], b"abstract");
+        sender.connect_addr(&dgram_addr).await.unwrap();
+        assert_eq!(
+            sender.peer_addr().unwrap().as_abstract_name(),
+            Some(dgram_name.as_bytes())
+        );
+
+        // Nothing was created on disk under either name.
+        assert!(!std::path::Path::new(&name).exists());
+        assert!(!std::path::Path::new(&dgram_name).exists());
+    });
+}
+
+#[test]
+fn unnamed_address_rejected() {
+    use tokio_uring::net::UnixStream;
+
+    let (a, _b) = std::os::unix::net::UnixStream::pair().unwrap();
+    let unnamed = a.local_addr().unwrap();
+
+    tokio_uring::start(async {
+        let err = UnixListener::bind_addr(&unnamed).err().unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        let err = UnixStream::connect_addr(&unnamed).await.err().unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    });
+}
+
+#[test]
+fn stream_read_exact_write_all() {
+    use tokio_uring::net::UnixStream;
+
+    tokio_uring::start(async {
+        let (a, b) = UnixStream::pair().unwrap();
+
+        let payload: Vec<u8> = (0..1 << 20).map(|i: u32| (i % 251) as u8).collect();
+        let expected = payload.clone();
+        let writer = tokio_uring::spawn(async move {
+            a.write_all(payload.into()).await.unwrap();
+        });
+
+        let ((), buf) = b
+            .read_exact(Vec::<u8>::with_capacity(1 << 20).into())
+            .await
+            .unwrap();
+        writer.await.unwrap();
+        assert_eq!(&buf[0][..], &expected[..]);
+    });
+}
+
+#[test]
+fn listener_std_conversions() {
+    use tokio_uring::net::UnixStream;
+
+    tokio_uring::start(async {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("std.sock");
+        let std_listener = std::os::unix::net::UnixListener::bind(&path).unwrap();
+        std_listener.set_nonblocking(true).unwrap();
+        let listener = UnixListener::from_std(std_listener);
+
+        let client = UnixStream::connect(&path).await.unwrap();
+        let (server, _) = listener.accept().await.unwrap();
+        client.write_all(b"hello".to_vec().into()).await.unwrap();
+        let (n, buf) = server.read(vec![0; 16].into()).await.unwrap();
+        assert_eq!(&buf[0][..n], b"hello");
+
+        let std_listener = listener.into_std().unwrap();
+        let _client = std::os::unix::net::UnixStream::connect(&path).unwrap();
+        let (_, addr) = std_listener.accept().unwrap();
+        assert!(addr.is_unnamed());
+    });
+}
+
+#[test]
+fn peer_cred_identifies_process() {
+    use std::os::unix::io::{FromRawFd, IntoRawFd};
+    use tokio_uring::net::UnixStream;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("listener.sock");
+    let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+    let pid = std::process::id() as libc::pid_t;
+
+    tokio_uring::start(async {
+        let listener = UnixListener::bind(&path).unwrap();
+        let client = UnixStream::connect(&path).await.unwrap();
+        let (accepted, _) = listener.accept().await.unwrap();
+        for stream in [&client, &accepted].iter() {
+            let cred = stream.peer_cred().unwrap();
+            assert_eq!((cred.pid(), cred.uid(), cred.gid()), (pid, uid, gid));
+        }
+
+        let (a, b) = UnixStream::pair().unwrap();
+        assert_eq!(a.peer_cred().unwrap(), b.peer_cred().unwrap());
+        assert_eq!(a.peer_cred().unwrap().pid(), pid);
+
+        // Datagram sockets carry no credentials to read, even when forced into a stream.
+        let (datagram, _peer) = std::os::unix::net::UnixDatagram::pair().unwrap();
+        let forced = unsafe { std::os::unix::net::UnixStream::from_raw_fd(datagram.into_raw_fd()) };
+        let err = UnixStream::from_std(forced).peer_cred().unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    });
+}
+
+#[test]
+fn incoming_take_three() {
+    use futures_util::StreamExt;
+    use tokio_uring::net::UnixStream;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("listener.sock");
+
+    tokio_uring::start(async {
+        let listener = UnixListener::bind(&path).unwrap();
+        let mut clients = Vec::new();
+        for _ in 0..3 {
+            clients.push(UnixStream::connect(&path).await.unwrap());
+        }
+        let accepted: Vec<_> = listener
+            .incoming()
+            .take(3)
+            .map(|conn| conn.unwrap())
+            .collect()
+            .await;
+        assert_eq!(accepted.len(), 3);
+
+        drop(listener);
+        let err = std::os::unix::net::UnixStream::connect(&path).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::ConnectionRefused);
+    });
+}
+
+#[test]
+fn stream_split_and_reunite() {
+    tokio_uring::start(async {
+        let (a, b) = tokio_uring::net::UnixStream::pair().unwrap();
+        let (reader, writer) = a.into_split();
+
+        let read = tokio_uring::spawn(async move {
+            let ((), buf) = reader.read_exact(vec![0; 5].into()).await.unwrap();
+            (reader, buf)
+        });
+        let echo = tokio_uring::spawn(async move {
+            let ((), buf) = b.read_exact(vec![0; 5].into()).await.unwrap();
+            b.write_all(buf).await.unwrap();
+            b
+        });
+        writer.write_all(b"hello".to_vec().into()).await.unwrap();
+
+        let (reader, buf) = read.await.unwrap();
+        assert_eq!(&buf[0][..], b"hello");
+        let b = echo.await.unwrap();
+
+        let a = reader.reunite(writer).unwrap();
+        a.write_all(b"!".to_vec().into()).await.unwrap();
+        let ((), buf) = b.read_exact(vec![0; 1].into()).await.unwrap();
+        assert_eq!(&buf[0][..], b"!");
+    });
+}
+
+#[test]
+fn builder_takes_over_stale_path() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("stale.sock");
+
+    // A listener that went away without removing its file.
+    drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+    let err = UnixListener::bind(&path).err().unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::AddrInUse);
+
+    tokio_uring::start(async {
+        let listener = UnixListener::builder()
+            .unlink_existing(true)
+            .bind(&path)
+            .unwrap();
+        let _client = std::os::unix::net::UnixStream::connect(&path).unwrap();
+        listener.accept().await.unwrap();
+    });
+}
+
+#[test]
+fn builder_refuses_live_path() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("live.sock");
+    let live = std::os::unix::net::UnixListener::bind(&path).unwrap();
+
+    let err = UnixListener::builder()
+        .unlink_existing(true)
+        .bind(&path)
+        .err()
+        .unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::AddrInUse);
+
+    // The probe's connection aside, the live listener still serves its path.
+    drop(live.accept().unwrap());
+    let _client = std::os::unix::net::UnixStream::connect(&path).unwrap();
+    live.accept().unwrap();
+
+    // Nor is a file that is not a socket replaced.
+    let file = dir.path().join("file");
+    std::fs::write(&file, b"data").unwrap();
+    let err = UnixListener::builder()
+        .unlink_existing(true)
+        .bind(&file)
+        .err()
+        .unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::AddrInUse);
+    assert_eq!(std::fs::read(&file).unwrap(), b"data");
+}
+
+#[test]
+fn builder_mode_and_cleanup() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("control.sock");
+
+    let listener = UnixListener::builder()
+        .mode(0o600)
+        .cleanup_on_drop(true)
+        .bind(&path)
+        .unwrap();
+    let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+    drop(listener);
+    assert!(!path.exists());
+
+    // Without cleanup the file stays, and neither is it removed by a listener whose file
+    // was replaced in the meantime.
+    drop(UnixListener::bind(&path).unwrap());
+    assert!(path.exists());
+    let listener = UnixListener::builder()
+        .unlink_existing(true)
+        .cleanup_on_drop(true)
+        .bind(&path)
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let successor = UnixListener::bind(&path).unwrap();
+    drop(listener);
+    assert!(path.exists());
+    drop(successor);
+
+    // Converting to a std listener keeps the file in place.
+    std::fs::remove_file(&path).unwrap();
+    let listener = UnixListener::builder()
+        .cleanup_on_drop(true)
+        .bind(&path)
+        .unwrap();
+    let std_listener = listener.into_std().unwrap();
+    assert!(path.exists());
+    drop(std_listener);
+}
+
+#[test]
+fn shared_buf_concurrent_writes() {
+    use std::sync::Arc;
+    use tokio_uring::buf::SharedBuf;
+    use tokio_uring::net::UnixStream;
+    use tokio_uring::Submit;
+
+    tokio_uring::start(async {
+        let page = SharedBuf::from(b"cached page".to_vec());
+        let pairs: Vec<_> = (0..4).map(|_| UnixStream::pair().unwrap()).collect();
+
+        let writes: Vec<_> = pairs
+            .iter()
+            .map(|(a, _)| a.write(page.clone().into()).submit())
+            .collect();
+        // Each write in flight holds a clone.
+        assert_eq!(Arc::strong_count(&page.0), 5);
+
+        for (write, (_, b)) in writes.into_iter().zip(&pairs) {
+            let (n, buf) = write.await.unwrap();
+            assert_eq!(n, page.len());
+            assert_eq!(buf.try_into::<SharedBuf>().unwrap(), page);
+
+            let (n, buf) = b.read(vec![0; 16].into()).await.unwrap();
+            assert_eq!(&buf[0][..n], b"cached page");
+        }
+        assert_eq!(Arc::strong_count(&page.0), 1);
+    });
+}
+
+#[test]
+fn shared_buf_read_fails() {
+    use tokio_uring::buf::SharedBuf;
+    use tokio_uring::net::UnixStream;
+
+    tokio_uring::start(async {
+        let (a, _b) = UnixStream::pair().unwrap();
+        let err = a
+            .read(SharedBuf::from(vec![0; 16]).into())
+            .await
+            .unwrap_err();
+        assert_eq!(err.0.kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(err.1.len(), 16);
+    });
+}
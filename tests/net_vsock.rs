@@ -0,0 +1,65 @@
+use std::io::ErrorKind;
+
+use tokio_uring::net::{VsockAddr, VsockListener, VsockStream};
+
+#[test]
+fn addr_accessors() {
+    let addr = VsockAddr::new(VsockAddr::CID_HOST, 5000);
+    assert_eq!(addr.cid(), 2);
+    assert_eq!(addr.port(), 5000);
+    assert_eq!(addr.to_string(), "2:5000");
+    assert_eq!(VsockAddr::CID_ANY, u32::MAX);
+    assert_eq!(VsockAddr::CID_LOCAL, 1);
+}
+
+#[test]
+fn listener_reports_bound_port() {
+    let listener = match VsockListener::bind(VsockAddr::CID_ANY, VsockAddr::PORT_ANY) {
+        Ok(listener) => listener,
+        // Without vsock support in the kernel, the error says so.
+        Err(e) => {
+            assert_eq!(e.kind(), ErrorKind::Unsupported);
+            return;
+        }
+    };
+    let addr = listener.local_addr().unwrap();
+    assert_eq!(addr.cid(), VsockAddr::CID_ANY);
+    assert_ne!(addr.port(), VsockAddr::PORT_ANY);
+}
+
+#[test]
+fn loopback_exchange() {
+    tokio_uring::start(async {
+        // The loopback transport is a module of its own, often missing.
+        let listener = match VsockListener::bind(VsockAddr::CID_LOCAL, VsockAddr::PORT_ANY) {
+            Ok(listener) => listener,
+            Err(e) => {
+                assert!(
+                    matches!(
+                        e.kind(),
+                        ErrorKind::Unsupported | ErrorKind::AddrNotAvailable
+                    ),
+                    "{}",
+                    e
+                );
+                return;
+            }
+        };
+        let port = listener.local_addr().unwrap().port();
+
+        let client = VsockStream::connect(VsockAddr::CID_LOCAL, port)
+            .await
+            .unwrap();
+        let (server, peer) = listener.accept().await.unwrap();
+        assert_eq!(peer, client.local_addr().unwrap());
+        assert_eq!(server.peer_addr().unwrap(), client.local_addr().unwrap());
+        assert_eq!(
+            client.peer_addr().unwrap(),
+            VsockAddr::new(VsockAddr::CID_LOCAL, port)
+        );
+
+        client.write_all(b"ping".to_vec().into()).await.unwrap();
+        let ((), buf) = server.read_exact(vec![0; 4].into()).await.unwrap();
+        assert_eq!(&buf[0][..], b"ping");
+    });
+}